@@ -0,0 +1,30 @@
+certificate-error-cryptographic = Ein kryptografischer Fehler ist aufgetreten. Bitte prüfen Sie Ihre Sicherheitskonfiguration.
+certificate-error-key-generation-failed = Schlüsselerzeugung fehlgeschlagen: { $detail }
+certificate-error-signing-failed = Signieren fehlgeschlagen: { $detail }
+certificate-error-signature-verification-failed = Die Prüfung der Zertifikatssignatur ist fehlgeschlagen. Das Zertifikat wurde möglicherweise manipuliert.
+certificate-error-invalid-format = Ungültiges Zertifikatsformat: { $detail }
+certificate-error-pdf-generation-failed = Das PDF-Zertifikat konnte nicht erstellt werden. Bitte versuchen Sie es erneut oder verwenden Sie das JSON-Format.
+certificate-error-pdf-template = Fehler in der PDF-Vorlage: { $detail }
+certificate-error-font-loading-failed = Schriftart konnte nicht geladen werden: { $detail }
+certificate-error-json-serialization-failed = JSON-Serialisierung fehlgeschlagen: { $detail }
+certificate-error-json-deserialization-failed = JSON-Deserialisierung fehlgeschlagen: { $detail }
+certificate-error-file-operation-failed = Dateivorgang fehlgeschlagen: { $detail }
+certificate-error-file-not-found = Die benötigte Datei „{ $file }“ wurde nicht gefunden. Bitte prüfen Sie den Dateipfad.
+certificate-error-permission-denied = Zugriff auf „{ $path }“ verweigert. Bitte prüfen Sie die Dateiberechtigungen.
+certificate-error-invalid-file-format = Ungültiges Dateiformat: { $detail }
+certificate-error-template-not-found = Die Zertifikatsvorlage „{ $template }“ wurde nicht gefunden. Bitte prüfen Sie die Vorlagenkonfiguration.
+certificate-error-template-parsing-failed = Vorlage konnte nicht gelesen werden: { $detail }
+certificate-error-template-rendering-failed = Vorlage konnte nicht dargestellt werden: { $detail }
+certificate-error-qr-code-generation-failed = QR-Code-Erzeugung fehlgeschlagen: { $detail }
+certificate-error-qr-code-data-too-large = Daten für den QR-Code zu groß: { $detail }
+certificate-error-validation-failed = Zertifikatsprüfung fehlgeschlagen: { $detail }
+certificate-error-invalid-data = Ungültige Zertifikatsdaten: { $reason }. Bitte prüfen Sie die Eingabedaten.
+certificate-error-missing-required-field = Pflichtfeld fehlt: { $detail }
+certificate-error-invalid-timestamp = Ungültiger Zeitstempel: { $detail }
+certificate-error-network = Netzwerkfehler: { $detail }
+certificate-error-verification-service-unavailable = Der Dienst zur Zertifikatsprüfung ist derzeit nicht erreichbar. Bitte versuchen Sie es später erneut.
+certificate-error-not-found-in-database = Zertifikat nicht in der Prüfdatenbank gefunden
+certificate-error-invalid-configuration = Ungültige Konfiguration: { $detail }
+certificate-error-missing-configuration = Fehlende Konfiguration: { $detail }
+certificate-error-internal = Interner Fehler: { $detail }
+certificate-error-not-supported = Vorgang nicht unterstützt: { $detail }
@@ -0,0 +1,33 @@
+# CertificateError messages. English is the canonical text returned by
+# `CertificateError::user_message()` and written to logs.
+
+certificate-error-cryptographic = A cryptographic error occurred. Please check your security configuration.
+certificate-error-key-generation-failed = Key generation failed: { $detail }
+certificate-error-signing-failed = Signing failed: { $detail }
+certificate-error-signature-verification-failed = Certificate signature verification failed. The certificate may have been tampered with.
+certificate-error-invalid-format = Invalid certificate format: { $detail }
+certificate-error-pdf-generation-failed = Failed to generate PDF certificate. Please try again or use JSON format.
+certificate-error-pdf-template = PDF template error: { $detail }
+certificate-error-font-loading-failed = Font loading failed: { $detail }
+certificate-error-json-serialization-failed = JSON serialization failed: { $detail }
+certificate-error-json-deserialization-failed = JSON deserialization failed: { $detail }
+certificate-error-file-operation-failed = File operation failed: { $detail }
+certificate-error-file-not-found = Required file '{ $file }' was not found. Please check the file path.
+certificate-error-permission-denied = Permission denied accessing '{ $path }'. Please check file permissions.
+certificate-error-invalid-file-format = Invalid file format: { $detail }
+certificate-error-template-not-found = Certificate template '{ $template }' was not found. Please check template configuration.
+certificate-error-template-parsing-failed = Template parsing failed: { $detail }
+certificate-error-template-rendering-failed = Template rendering failed: { $detail }
+certificate-error-qr-code-generation-failed = QR code generation failed: { $detail }
+certificate-error-qr-code-data-too-large = QR code data too large: { $detail }
+certificate-error-validation-failed = Certificate validation failed: { $detail }
+certificate-error-invalid-data = Invalid certificate data: { $reason }. Please check the input data.
+certificate-error-missing-required-field = Missing required field: { $detail }
+certificate-error-invalid-timestamp = Invalid timestamp: { $detail }
+certificate-error-network = Network error: { $detail }
+certificate-error-verification-service-unavailable = Certificate verification service is currently unavailable. Please try again later.
+certificate-error-not-found-in-database = Certificate not found in verification database
+certificate-error-invalid-configuration = Invalid configuration: { $detail }
+certificate-error-missing-configuration = Missing configuration: { $detail }
+certificate-error-internal = Internal error: { $detail }
+certificate-error-not-supported = Operation not supported: { $detail }
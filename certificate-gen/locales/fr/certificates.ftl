@@ -0,0 +1,30 @@
+certificate-error-cryptographic = Une erreur cryptographique s'est produite. Vérifiez votre configuration de sécurité.
+certificate-error-key-generation-failed = La génération de clé a échoué : { $detail }
+certificate-error-signing-failed = La signature a échoué : { $detail }
+certificate-error-signature-verification-failed = La vérification de la signature du certificat a échoué. Le certificat a peut-être été falsifié.
+certificate-error-invalid-format = Format de certificat invalide : { $detail }
+certificate-error-pdf-generation-failed = Impossible de générer le certificat PDF. Réessayez ou utilisez le format JSON.
+certificate-error-pdf-template = Erreur de modèle PDF : { $detail }
+certificate-error-font-loading-failed = Impossible de charger la police : { $detail }
+certificate-error-json-serialization-failed = La sérialisation JSON a échoué : { $detail }
+certificate-error-json-deserialization-failed = La désérialisation JSON a échoué : { $detail }
+certificate-error-file-operation-failed = L'opération sur le fichier a échoué : { $detail }
+certificate-error-file-not-found = Le fichier requis « { $file } » est introuvable. Vérifiez le chemin du fichier.
+certificate-error-permission-denied = Accès refusé à « { $path } ». Vérifiez les autorisations du fichier.
+certificate-error-invalid-file-format = Format de fichier invalide : { $detail }
+certificate-error-template-not-found = Le modèle de certificat « { $template } » est introuvable. Vérifiez la configuration des modèles.
+certificate-error-template-parsing-failed = L'analyse du modèle a échoué : { $detail }
+certificate-error-template-rendering-failed = Le rendu du modèle a échoué : { $detail }
+certificate-error-qr-code-generation-failed = La génération du code QR a échoué : { $detail }
+certificate-error-qr-code-data-too-large = Données trop volumineuses pour le code QR : { $detail }
+certificate-error-validation-failed = La validation du certificat a échoué : { $detail }
+certificate-error-invalid-data = Données de certificat invalides : { $reason }. Vérifiez les données saisies.
+certificate-error-missing-required-field = Champ obligatoire manquant : { $detail }
+certificate-error-invalid-timestamp = Horodatage invalide : { $detail }
+certificate-error-network = Erreur réseau : { $detail }
+certificate-error-verification-service-unavailable = Le service de vérification des certificats est actuellement indisponible. Réessayez plus tard.
+certificate-error-not-found-in-database = Certificat introuvable dans la base de vérification
+certificate-error-invalid-configuration = Configuration invalide : { $detail }
+certificate-error-missing-configuration = Configuration manquante : { $detail }
+certificate-error-internal = Erreur interne : { $detail }
+certificate-error-not-supported = Opération non prise en charge : { $detail }
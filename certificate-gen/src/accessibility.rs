@@ -0,0 +1,316 @@
+//! Logical structure of PDF certificates for tagged, accessible output
+//!
+//! Government customers require delivered documents to meet Section 508 and
+//! EN 301 549, which for PDF means PDF/UA: a structure tree that gives the
+//! reading order, a document language and title, header cells on tables and
+//! alternative text on every image. This module builds that structure from a
+//! signed certificate; the PDF generator marks the content it draws with the
+//! nodes in the same order, so what is read aloud matches what is printed.
+
+use serde::{Deserialize, Serialize};
+
+use crate::certificate::{CertificateData, SignedCertificate};
+use crate::error::{CertificateError, Result};
+
+/// Title of certificates without a custom one
+pub const DEFAULT_TITLE: &str = "Certificate of Data Sanitization";
+
+/// Accessibility settings for PDF certificates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfAccessibility {
+    /// BCP 47 language tag of the document, e.g. `en-US` or `de-DE`
+    pub language: String,
+    /// Document title shown by readers instead of the file name
+    pub title: Option<String>,
+    /// Alternative text of the organization logo; derived from the
+    /// organization name when unset
+    pub logo_alt_text: Option<String>,
+    /// Alternative text of the verification QR code; describes the link when unset
+    pub qr_alt_text: Option<String>,
+}
+
+/// Standard structure types (ISO 32000-1, 14.8.4) used in certificates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureRole {
+    Document,
+    H1,
+    H2,
+    P,
+    Table,
+    TR,
+    TH,
+    TD,
+    Figure,
+}
+
+/// Node of the structure tree, in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureNode {
+    pub role: StructureRole,
+    /// Text content of leaf nodes
+    pub text: Option<String>,
+    /// Alternative text; required on figures
+    pub alt_text: Option<String>,
+    pub children: Vec<StructureNode>,
+}
+
+impl Default for PdfAccessibility {
+    fn default() -> Self {
+        Self {
+            language: "en-US".to_string(),
+            title: None,
+            logo_alt_text: None,
+            qr_alt_text: None,
+        }
+    }
+}
+
+impl PdfAccessibility {
+    /// Check the language tag: a 2-3 letter primary subtag followed by
+    /// alphanumeric subtags of up to 8 characters
+    pub fn validate(&self) -> Result<()> {
+        let mut subtags = self.language.split('-');
+        let primary = subtags.next().unwrap_or_default();
+        let valid = (2..=3).contains(&primary.len())
+            && primary.chars().all(|c| c.is_ascii_alphabetic())
+            && subtags.all(|tag| (1..=8).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+        if !valid {
+            return Err(CertificateError::InvalidConfiguration(format!(
+                "PDF language must be a BCP 47 tag such as en-US, got '{}'", self.language
+            )));
+        }
+        Ok(())
+    }
+    
+    pub fn document_title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| DEFAULT_TITLE.to_string())
+    }
+    
+    pub fn logo_alt_text(&self, data: &CertificateData) -> String {
+        self.logo_alt_text.clone().unwrap_or_else(|| match &data.organization {
+            Some(organization) => format!("Logo of {}", organization.name),
+            None => "Organization logo".to_string(),
+        })
+    }
+    
+    pub fn qr_alt_text(&self, data: &CertificateData) -> String {
+        self.qr_alt_text.clone().unwrap_or_else(|| {
+            format!(
+                "QR code linking to the online verification page of certificate {}",
+                data.certificate_id
+            )
+        })
+    }
+}
+
+impl StructureNode {
+    pub fn new(role: StructureRole, children: Vec<StructureNode>) -> Self {
+        Self { role, text: None, alt_text: None, children }
+    }
+    
+    pub fn text(role: StructureRole, text: impl Into<String>) -> Self {
+        Self { role, text: Some(text.into()), alt_text: None, children: Vec::new() }
+    }
+    
+    pub fn figure(alt_text: impl Into<String>) -> Self {
+        Self { role: StructureRole::Figure, text: None, alt_text: Some(alt_text.into()), children: Vec::new() }
+    }
+    
+    /// Two-column table whose first column holds row headers
+    pub fn key_value_table(rows: Vec<(&str, String)>) -> Self {
+        let rows = rows
+            .into_iter()
+            .map(|(key, value)| {
+                Self::new(StructureRole::TR, vec![
+                    Self::text(StructureRole::TH, key),
+                    Self::text(StructureRole::TD, value),
+                ])
+            })
+            .collect();
+        Self::new(StructureRole::Table, rows)
+    }
+    
+    /// Text and alternative text in reading order, as a screen reader announces it
+    pub fn reading_order(&self) -> Vec<String> {
+        let mut items = Vec::new();
+        self.collect(&mut items);
+        items
+    }
+    
+    /// Figures without alternative text, which PDF/UA forbids
+    pub fn figures_missing_alt_text(&self) -> usize {
+        let own = usize::from(self.role == StructureRole::Figure && self.alt_text.as_deref().is_none_or(str::is_empty));
+        own + self.children.iter().map(Self::figures_missing_alt_text).sum::<usize>()
+    }
+    
+    fn collect(&self, items: &mut Vec<String>) {
+        items.extend(self.text.iter().chain(self.alt_text.iter()).cloned());
+        for child in &self.children {
+            child.collect(items);
+        }
+    }
+}
+
+/// Structure tree of a certificate page, in the order content is drawn
+pub fn structure_tree(signed: &SignedCertificate, accessibility: &PdfAccessibility, include_qr_code: bool) -> StructureNode {
+    use StructureRole::*;
+    
+    let data = &signed.certificate.data;
+    let wipe = &data.wipe_info;
+    let mut nodes = Vec::new();
+    
+    if data.organization.as_ref().is_some_and(|organization| organization.logo_path.is_some()) {
+        nodes.push(StructureNode::figure(accessibility.logo_alt_text(data)));
+    }
+    nodes.push(StructureNode::text(H1, accessibility.document_title()));
+    nodes.push(StructureNode::text(P, format!("Certificate ID {}", data.certificate_id)));
+    
+    nodes.push(StructureNode::text(H2, "Device"));
+    let mut device_rows = vec![
+        ("Model", data.device_info.model.clone()),
+        ("Serial number", data.device_info.serial.clone()),
+        ("Capacity", format!("{} bytes", data.device_info.size)),
+        ("Device path", data.device_info.path.clone()),
+    ];
+    if let Some(layer) = data.device_info.virtualization {
+        device_rows.push(("Storage layer", format!("{}; covers only the blocks it exposes", layer)));
+    }
+    nodes.push(StructureNode::key_value_table(device_rows));
+    
+    nodes.push(StructureNode::text(H2, "Sanitization"));
+    let mut rows = vec![
+        ("Method", wipe.algorithm.to_string()),
+        ("Started", wipe.started_at.to_rfc3339()),
+        ("Passes completed", wipe.passes_completed.to_string()),
+    ];
+    if let Some(completed_at) = wipe.completed_at {
+        rows.push(("Completed", completed_at.to_rfc3339()));
+    }
+    if let Some(passed) = wipe.verification_passed {
+        rows.push(("Verification", if passed { "Passed" } else { "Failed" }.to_string()));
+    }
+    if let Some(host) = &data.host {
+        rows.push(("Performed on", host.to_string()));
+    }
+    nodes.push(StructureNode::key_value_table(rows));
+    
+    if let Some(compliance) = &data.compliance_info {
+        nodes.push(StructureNode::text(H2, "Compliance"));
+        for standard in &compliance.standards_met {
+            nodes.push(StructureNode::text(P, format!("{}: {}", standard.name, standard.description)));
+        }
+    }
+    
+    nodes.push(StructureNode::text(H2, "Signature"));
+    nodes.push(StructureNode::key_value_table(vec![
+        ("Signing key", signed.signature_info.key_id.clone()),
+        ("Signed at", signed.signed_at.to_rfc3339()),
+    ]));
+    if include_qr_code {
+        nodes.push(StructureNode::figure(accessibility.qr_alt_text(data)));
+    }
+    
+    StructureNode::new(Document, nodes)
+}
+
+impl StructureRole {
+    /// Structure type name written to the PDF
+    pub fn pdf_name(&self) -> &'static str {
+        match self {
+            StructureRole::Document => "Document",
+            StructureRole::H1 => "H1",
+            StructureRole::H2 => "H2",
+            StructureRole::P => "P",
+            StructureRole::Table => "Table",
+            StructureRole::TR => "TR",
+            StructureRole::TH => "TH",
+            StructureRole::TD => "TD",
+            StructureRole::Figure => "Figure",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::certificate::{DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate() -> SignedCertificate {
+        let data = CertificateData {
+            certificate_id: Uuid::nil(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "TEST123".to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: "0011223344556677".to_string(),
+            timestamp: Utc::now(),
+            certificate_hash: String::new(),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    #[test]
+    fn test_structure_tree_reading_order() {
+        let tree = structure_tree(&signed_certificate(), &PdfAccessibility::default(), true);
+        assert_eq!(tree.role, StructureRole::Document);
+        assert_eq!(tree.figures_missing_alt_text(), 0);
+        
+        let order = tree.reading_order();
+        let position = |text: &str| order.iter().position(|item| item.starts_with(text)).unwrap();
+        assert_eq!(position(DEFAULT_TITLE), 0);
+        assert!(position("Device") < position("Serial number"));
+        assert!(position("TEST123") < position("Sanitization"));
+        assert!(order.last().unwrap().starts_with("QR code linking"));
+        
+        let table = &tree.children[3];
+        assert_eq!(table.children[0].children[0].role, StructureRole::TH);
+    }
+    
+    #[test]
+    fn test_language_validation_and_alt_text() {
+        for language in ["en", "en-US", "de-DE", "zh-Hant-TW"] {
+            let accessibility = PdfAccessibility { language: language.to_string(), ..PdfAccessibility::default() };
+            assert!(accessibility.validate().is_ok(), "{}", language);
+        }
+        for language in ["", "english", "en_US", "en-"] {
+            let accessibility = PdfAccessibility { language: language.to_string(), ..PdfAccessibility::default() };
+            assert!(accessibility.validate().is_err(), "{}", language);
+        }
+        
+        let custom = PdfAccessibility { qr_alt_text: Some("Scan to verify".to_string()), ..PdfAccessibility::default() };
+        assert_eq!(custom.qr_alt_text(&signed_certificate().certificate.data), "Scan to verify");
+        assert_eq!(custom.logo_alt_text(&signed_certificate().certificate.data), "Organization logo");
+    }
+}
@@ -0,0 +1,293 @@
+//! Reports of failed and cancelled wipes
+//!
+//! A certificate states that a drive was sanitized, so none is issued when a
+//! wipe fails or is cancelled. The drive still has to be accounted for: it is
+//! usually sent to physical destruction, and the customer needs paperwork
+//! showing what was attempted on it first. A sanitization attempt report
+//! records the method, how many passes and bytes were written before the
+//! wipe stopped and why it stopped. Its document type and the notice at its
+//! top say it is not a certificate, and it is refused for completed wipes, so
+//! it cannot be mistaken for one. The report is signed with the certificate
+//! key over its canonical JSON, which makes it as tamper-evident as a
+//! certificate without making it one.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use safe_erase_core::{ErrorContext, WipeResult, WipeStatus};
+
+use crate::crypto::{CertificateSigner, CertificateVerifier};
+use crate::error::{CertificateError, Result};
+use crate::CertificateOptions;
+
+/// `document_type` of attempt reports
+pub const ATTEMPT_REPORT_TYPE: &str = "sanitization_attempt_report";
+
+/// Notice at the top of every attempt report
+pub const NOT_A_CERTIFICATE: &str =
+    "NOT A CERTIFICATE OF SANITIZATION. The wipe described below did not complete; \
+     the device must be treated as still holding its data.";
+
+/// What was attempted on a device whose wipe did not complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptReport {
+    /// Always [`ATTEMPT_REPORT_TYPE`]
+    pub document_type: String,
+    /// Always [`NOT_A_CERTIFICATE`]
+    pub notice: String,
+    pub report_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    pub operation_id: Uuid,
+    pub device_path: String,
+    pub device_serial: String,
+    pub device_model: String,
+    pub algorithm: safe_erase_core::WipeAlgorithm,
+    /// `Failed`, `Cancelled` or `DeviceRemoved`
+    pub outcome: WipeStatus,
+    pub started_at: DateTime<Utc>,
+    /// When the wipe stopped
+    pub ended_at: Option<DateTime<Utc>>,
+    pub passes_completed: usize,
+    /// Passes the method calls for
+    pub passes_planned: usize,
+    pub bytes_wiped: u64,
+    pub failure_reason: String,
+    /// Why and by whom a cancelled wipe was stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<safe_erase_core::Cancellation>,
+    /// Pass and block the wipe failed at, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_context: Option<ErrorContext>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_order: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<safe_erase_core::HostInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<safe_erase_core::OperatorContext>,
+    pub organization: Option<crate::OrganizationInfo>,
+    pub metadata: HashMap<String, String>,
+}
+
+/// Attempt report with the issuer's signature over its canonical JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAttemptReport {
+    pub report: AttemptReport,
+    pub key_id: String,
+    /// Base64 SHA-256 signature
+    pub signature: String,
+}
+
+impl AttemptReport {
+    /// Report on a wipe that failed or was cancelled; completed wipes get a certificate instead
+    pub fn from_result(wipe_result: &WipeResult, options: &CertificateOptions) -> Result<Self> {
+        if !matches!(wipe_result.status, WipeStatus::Failed | WipeStatus::Cancelled | WipeStatus::DeviceRemoved) {
+            return Err(CertificateError::InvalidCertificateData(format!(
+                "Attempt reports are only issued for failed or cancelled wipes, not {}", wipe_result.status
+            )));
+        }
+        
+        let mut failure_reason = wipe_result.error_message.clone().unwrap_or_else(|| match wipe_result.status {
+            WipeStatus::Cancelled => "Cancelled before completion".to_string(),
+            _ => "No reason recorded".to_string(),
+        });
+        let mut serial = wipe_result.device_serial.clone();
+        let mut error_context = wipe_result.error_context.clone();
+        let mut metadata = options.metadata.clone();
+        if let Some(redactor) = &options.redactor {
+            failure_reason = redactor.redact_in(&failure_reason, &serial);
+            if let Some(context) = &mut error_context {
+                context.device_serial = context.device_serial.as_deref().map(|value| redactor.redact(value));
+            }
+            for key in crate::REDACTED_METADATA {
+                if let Some(value) = metadata.get_mut(key) {
+                    *value = redactor.redact(value);
+                }
+            }
+            metadata.insert("identifiers_redacted".to_string(), "true".to_string());
+            serial = redactor.redact(&serial);
+        }
+        
+        Ok(Self {
+            document_type: ATTEMPT_REPORT_TYPE.to_string(),
+            notice: NOT_A_CERTIFICATE.to_string(),
+            report_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            operation_id: wipe_result.operation_id,
+            device_path: wipe_result.device_path.clone(),
+            device_serial: serial,
+            device_model: wipe_result.device_model.clone(),
+            algorithm: wipe_result.algorithm.clone(),
+            outcome: wipe_result.status,
+            started_at: wipe_result.started_at,
+            ended_at: wipe_result.completed_at,
+            passes_completed: wipe_result.passes_completed,
+            passes_planned: wipe_result.algorithm.info().passes,
+            bytes_wiped: wipe_result.bytes_wiped,
+            failure_reason,
+            cancellation: wipe_result.cancellation.clone(),
+            error_context,
+            work_order: wipe_result.work_order.clone(),
+            host: wipe_result.host.clone(),
+            operator: wipe_result.operator.clone(),
+            organization: options.organization.clone(),
+            metadata,
+        })
+    }
+    
+    /// Sign the report's canonical JSON
+    pub fn sign(self, signer: &CertificateSigner) -> Result<SignedAttemptReport> {
+        Ok(SignedAttemptReport {
+            signature: signer.sign_document(&self)?,
+            key_id: signer.key_id().to_string(),
+            report: self,
+        })
+    }
+    
+    /// Printable form of the report
+    pub fn text(&self) -> String {
+        let timestamp = |at: &DateTime<Utc>| safe_erase_core::time_format::format_timestamp(at);
+        let mut text = String::new();
+        let _ = writeln!(text, "SANITIZATION ATTEMPT REPORT");
+        let _ = writeln!(text, "{}", NOT_A_CERTIFICATE);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Report ID:        {}", self.report_id);
+        let _ = writeln!(text, "Generated:        {}", timestamp(&self.generated_at));
+        if let Some(organization) = &self.organization {
+            let _ = writeln!(text, "Organization:     {}", organization.name);
+        }
+        let _ = writeln!(text, "Operation ID:     {}", self.operation_id);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Device:           {} ({})", self.device_model, self.device_path);
+        let _ = writeln!(text, "Serial number:    {}", self.device_serial);
+        let _ = writeln!(text, "Method:           {}", self.algorithm);
+        let _ = writeln!(text, "Outcome:          {}", self.outcome);
+        let _ = writeln!(text, "Started:          {}", timestamp(&self.started_at));
+        let _ = writeln!(text, "Stopped:          {}", self.ended_at.as_ref().map(timestamp).unwrap_or_else(|| "-".to_string()));
+        let _ = writeln!(text, "Passes completed: {} of {}", self.passes_completed, self.passes_planned);
+        let _ = writeln!(text, "Bytes written:    {}", self.bytes_wiped);
+        let _ = writeln!(text, "Reason:           {}", self.failure_reason);
+        if let Some(cancellation) = &self.cancellation {
+            let _ = writeln!(text, "Cancel requested: {}", timestamp(&cancellation.requested_at));
+        }
+        if let Some(context) = &self.error_context {
+            if let Some(pass) = context.pass_index {
+                let _ = writeln!(text, "Failed in pass:   {}", pass + 1);
+            }
+            if let Some(lba) = context.lba {
+                let _ = writeln!(text, "Failed at LBA:    {}", lba);
+            }
+        }
+        if let Some(work_order) = &self.work_order {
+            let _ = writeln!(text, "Work order:       {}", work_order);
+        }
+        if let Some(operator) = &self.operator {
+            let _ = writeln!(text, "Operator:         {}", operator);
+        }
+        let mut metadata: Vec<_> = self.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            let _ = writeln!(text, "{}: {}", key, value);
+        }
+        text
+    }
+}
+
+impl SignedAttemptReport {
+    /// Check the signature against the verifier's trusted keys
+    pub fn verify(&self, verifier: &CertificateVerifier) -> Result<bool> {
+        if self.report.document_type != ATTEMPT_REPORT_TYPE {
+            return Ok(false);
+        }
+        verifier.verify_document(&self.key_id, &self.report, &self.signature)
+    }
+    
+    /// Text form followed by the signature, for printing
+    pub fn text(&self) -> String {
+        format!("{}\nSigned by key {}\nSignature: {}\n", self.report.text(), self.key_id, self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::PublicKey;
+    
+    fn result(status: &str, error_message: Option<&str>) -> WipeResult {
+        serde_json::from_value(serde_json::json!({
+            "operation_id": Uuid::new_v4(),
+            "device_path": "/dev/sdb",
+            "device_serial": "WD-1234567890",
+            "device_model": "Test Drive",
+            "algorithm": "DoD522022M",
+            "options": safe_erase_core::WipeOptions::default(),
+            "status": status,
+            "started_at": "2024-05-01T12:00:00Z",
+            "completed_at": "2024-05-01T12:40:00Z",
+            "bytes_wiped": 1024,
+            "passes_completed": 1,
+            "verification_requested": false,
+            "verification_passed": null,
+            "hpa_detected": false,
+            "hpa_cleared": false,
+            "dco_detected": false,
+            "dco_cleared": false,
+            "error_message": error_message,
+            "error_context": {"pass_index": 1, "lba": 52000, "device_serial": "WD-1234567890"},
+            "performance_stats": {
+                "average_speed": 1.0, "peak_speed": 1.0, "total_time": "PT40M", "wipe_time": "PT40M"
+            }
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_report_from_failed_result() {
+        let options = CertificateOptions {
+            redactor: Some(safe_erase_core::Redactor::new("salt")),
+            ..CertificateOptions::default()
+        };
+        let report = AttemptReport::from_result(
+            &result("Failed", Some("Write error on WD-1234567890 at LBA 52000")),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(report.document_type, ATTEMPT_REPORT_TYPE);
+        assert_eq!(report.outcome, WipeStatus::Failed);
+        assert_eq!(report.passes_planned, 3);
+        assert!(!report.device_serial.contains("WD-1234567890"));
+        assert!(!report.failure_reason.contains("WD-1234567890"));
+        assert!(!report.error_context.as_ref().unwrap().device_serial.as_ref().unwrap().contains("WD-1234567890"));
+        
+        let text = report.text();
+        assert!(text.contains("NOT A CERTIFICATE"));
+        assert!(text.contains("Passes completed: 1 of 3"));
+        assert!(text.contains("Failed in pass:   2"));
+        
+        let cancelled = AttemptReport::from_result(&result("Cancelled", None), &CertificateOptions::default()).unwrap();
+        assert_eq!(cancelled.failure_reason, "Cancelled before completion");
+        assert!(AttemptReport::from_result(&result("Completed", None), &CertificateOptions::default()).is_err());
+    }
+    
+    #[test]
+    fn test_signed_report_verifies() {
+        let signer = CertificateSigner::new().unwrap();
+        let mut verifier = CertificateVerifier::new().unwrap();
+        let pem = signer.get_key_info().unwrap().public_key_pem;
+        verifier.add_trusted_key(signer.key_id().to_string(), PublicKey::from_pem(&pem).unwrap());
+        
+        let report = AttemptReport::from_result(&result("Failed", Some("Device removed")), &CertificateOptions::default()).unwrap();
+        let signed = report.sign(&signer).unwrap();
+        assert!(signed.verify(&verifier).unwrap());
+        
+        // Survives a trip through JSON
+        let stored: SignedAttemptReport = serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+        assert!(stored.verify(&verifier).unwrap());
+        
+        let mut altered = signed.clone();
+        altered.report.passes_completed = 3;
+        assert!(!altered.verify(&verifier).unwrap());
+    }
+}
@@ -0,0 +1,83 @@
+//! Canonical JSON encoding of signed certificate content
+//!
+//! Signatures and certificate hashes cover the canonical form of the
+//! certificate: compact JSON with object keys sorted by their UTF-8 bytes and
+//! numbers and strings written as `serde_json` writes them. Unlike serializing
+//! the Rust structs directly, the canonical form does not depend on field
+//! declaration order or `HashMap` iteration order, so any verifier that can
+//! parse JSON (including the WebAssembly verifier, which compiles this file on
+//! its own) reproduces the signed bytes exactly.
+//!
+//! This module must only depend on `serde` and `serde_json`.
+
+use serde_json::Value;
+
+/// Canonical encoding of a JSON value
+pub fn to_canonical_string(value: &Value) -> String {
+    let mut output = String::new();
+    write_value(value, &mut output);
+    output
+}
+
+/// Canonical encoding of any serializable value
+pub fn canonicalize<T: serde::Serialize>(value: &T) -> serde_json::Result<String> {
+    Ok(to_canonical_string(&serde_json::to_value(value)?))
+}
+
+fn write_value(value: &Value, output: &mut String) {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(flag) => output.push_str(if *flag { "true" } else { "false" }),
+        Value::Number(number) => output.push_str(&number.to_string()),
+        Value::String(string) => write_string(string, output),
+        Value::Array(items) => {
+            output.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_value(item, output);
+            }
+            output.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            output.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_string(key, output);
+                output.push(':');
+                write_value(item, output);
+            }
+            output.push('}');
+        }
+    }
+}
+
+fn write_string(string: &str, output: &mut String) {
+    // Serializing a string cannot fail
+    output.push_str(&serde_json::to_string(string).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_keys_are_sorted_recursively() {
+        let value = json!({"b": 1, "a": {"z": [true, null], "m": "x\"y"}});
+        assert_eq!(to_canonical_string(&value), r#"{"a":{"m":"x\"y","z":[true,null]},"b":1}"#);
+    }
+    
+    #[test]
+    fn test_reparsed_document_is_stable() {
+        let value = json!({"rate": 0.95, "size": 18446744073709551615u64, "name": "disk \u{e9}"});
+        let canonical = to_canonical_string(&value);
+        let reparsed: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(to_canonical_string(&reparsed), canonical);
+    }
+}
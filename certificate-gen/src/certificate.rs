@@ -36,6 +36,18 @@ pub struct CertificateData {
     pub technical_details: Option<HashMap<String, serde_json::Value>>,
     pub organization: Option<crate::OrganizationInfo>,
     pub metadata: HashMap<String, String>,
+    /// Work order or ticket number the wipe was performed under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_order: Option<String>,
+    /// Link to the ticket in the service desk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket_url: Option<String>,
+    /// Host and software build that performed the wipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<safe_erase_core::HostInfo>,
+    /// Authenticated operator who started the wipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<safe_erase_core::OperatorContext>,
 }
 
 /// Device information in certificate
@@ -45,6 +57,11 @@ pub struct DeviceInfo {
     pub serial: String,
     pub model: String,
     pub size: u64,
+    /// Hypervisor or thin-provisioning layer the wipe went through; the
+    /// sanitization covers only the blocks this layer exposed. Omitted for
+    /// physical drives so their signed encoding is unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<safe_erase_core::VirtualizationLayer>,
 }
 
 /// Wipe operation information
@@ -53,9 +70,14 @@ pub struct WipeInfo {
     pub algorithm: safe_erase_core::WipeAlgorithm,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "safe_erase_core::time_format::option_duration")]
     pub duration: Option<std::time::Duration>,
     pub passes_completed: usize,
     pub verification_passed: Option<bool>,
+    /// Digest of the device contents after the final pass; omitted when not
+    /// computed so certificates without one keep their signed encoding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_digest: Option<safe_erase_core::DeviceDigest>,
 }
 
 /// Verification information
@@ -67,6 +89,9 @@ pub struct VerificationInfo {
     pub samples_passed: usize,
     pub success_rate: f64,
     pub overall_result: safe_erase_core::VerificationStatus,
+    /// Share of the device (0-100) the samples covered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage_percent: Option<f64>,
 }
 
 /// Compliance and standards information
@@ -127,6 +152,16 @@ impl WipeCertificate {
         &self.data.device_info.serial
     }
     
+    /// Work order the wipe was performed under
+    pub fn work_order(&self) -> Option<&str> {
+        self.data.work_order.as_deref()
+    }
+    
+    /// Link to the ticket the wipe was performed under
+    pub fn ticket_url(&self) -> Option<&str> {
+        self.data.ticket_url.as_deref()
+    }
+    
     /// Get wipe algorithm used
     pub fn wipe_algorithm(&self) -> &safe_erase_core::WipeAlgorithm {
         &self.data.wipe_info.algorithm
@@ -166,6 +201,14 @@ impl WipeCertificate {
             ));
         }
         
+        if let Some(ticket_url) = &self.data.ticket_url {
+            if !(ticket_url.starts_with("https://") || ticket_url.starts_with("http://")) {
+                return Err(crate::error::CertificateError::InvalidCertificateData(format!(
+                    "Ticket URL must use http or https: {}", ticket_url
+                )));
+            }
+        }
+        
         // Check timestamps
         if let Some(completed_at) = self.data.wipe_info.completed_at {
             if completed_at < self.data.wipe_info.started_at {
@@ -200,7 +243,7 @@ impl WipeCertificate {
             certificate_id: self.data.certificate_id,
             device_model: self.data.device_info.model.clone(),
             device_serial: self.data.device_info.serial.clone(),
-            algorithm: self.data.wipe_info.algorithm,
+            algorithm: self.data.wipe_info.algorithm.clone(),
             completed_at: self.data.wipe_info.completed_at,
             verification_passed: self.data.wipe_info.verification_passed,
             security_level: self.data.compliance_info
@@ -259,6 +302,36 @@ impl ComplianceInfo {
         let mut standards_met = Vec::new();
         let mut compliance_notes = Vec::new();
         
+        // A named procedure is described as its author defined it; its claims are not assessed
+        if let safe_erase_core::WipeAlgorithm::Named(custom) = algorithm {
+            standards_met = custom
+                .compliance_claims
+                .iter()
+                .map(|claim| ComplianceStandard {
+                    name: claim.clone(),
+                    version: None,
+                    description: format!("Claimed by the definition of {}", custom.name),
+                    requirements_met: Vec::new(),
+                    compliance_level: ComplianceLevel::NotApplicable,
+                })
+                .collect();
+            compliance_notes.push(format!("Procedure: {} - {}", custom.name, custom.description));
+            compliance_notes.extend(
+                custom
+                    .patterns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pattern)| format!("Pass {}: {}", i + 1, pattern.description())),
+            );
+            compliance_notes.push("Compliance claims are declared by the procedure, not verified by SafeErase".to_string());
+            return Self {
+                standards_met,
+                security_level: SecurityLevel::Custom,
+                certification_body: None,
+                compliance_notes,
+            };
+        }
+        
         // Map algorithm to compliance standards
         for standard_name in &algorithm_info.compliance_standards {
             let standard = match standard_name.as_str() {
@@ -397,6 +470,7 @@ mod tests {
                 serial: "TEST123456".to_string(),
                 model: "Test SSD".to_string(),
                 size: 1000000000,
+                virtualization: None,
             },
             wipe_info: WipeInfo {
                 algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
@@ -405,12 +479,17 @@ mod tests {
                 duration: Some(Duration::from_secs(3600)),
                 passes_completed: 1,
                 verification_passed: Some(true),
+                device_digest: None,
             },
             verification_info: None,
             compliance_info: None,
             technical_details: None,
             organization: None,
             metadata: HashMap::new(),
+            work_order: Some("WO-1042".to_string()),
+            ticket_url: None,
+            host: None,
+            operator: None,
         }
     }
     
@@ -429,6 +508,11 @@ mod tests {
         let certificate = WipeCertificate::new(data);
         
         assert!(certificate.validate().is_ok());
+        assert_eq!(certificate.work_order(), Some("WO-1042"));
+        
+        let mut data = create_test_certificate_data();
+        data.ticket_url = Some("javascript:alert(1)".to_string());
+        assert!(WipeCertificate::new(data).validate().is_err());
     }
     
     #[test]
@@ -441,6 +525,23 @@ mod tests {
         assert!(compliance.is_fully_compliant());
     }
     
+    #[test]
+    fn test_named_algorithm_claims_are_not_assessed() {
+        let algorithm = safe_erase_core::WipeAlgorithm::Named(safe_erase_core::CustomAlgorithm {
+            name: "ACME 2-pass".to_string(),
+            description: "Zeros, then random data".to_string(),
+            patterns: vec![safe_erase_core::WipePattern::Zeros, safe_erase_core::WipePattern::Random],
+            compliance_claims: vec!["NIST 800-88".to_string()],
+        });
+        let compliance = ComplianceInfo::from_algorithm(&algorithm);
+        
+        assert_eq!(compliance.security_level, SecurityLevel::Custom);
+        assert_eq!(compliance.standards_met[0].compliance_level, ComplianceLevel::NotApplicable);
+        assert!(!compliance.is_fully_compliant());
+        assert!(compliance.compliance_notes[0].contains("ACME 2-pass"));
+        assert_eq!(compliance.compliance_notes[2], "Pass 2: Fill with cryptographically secure random data");
+    }
+    
     #[test]
     fn test_certificate_summary() {
         let data = create_test_certificate_data();
@@ -0,0 +1,261 @@
+//! System clock sanity checks before signing
+//!
+//! A certificate's `generated_at` and signature timestamp are only as good
+//! as the clock of the station that issued it; a machine with a dead CMOS
+//! battery happily signs certificates dated years in the past. Before
+//! signing, the local clock can be compared against NTP servers (SNTPv4,
+//! RFC 4330) and the certificate either refused or flagged in its signed
+//! metadata when the offset exceeds the allowed skew or no server answers.
+
+use std::net::UdpSocket;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CertificateError, Result};
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+/// Metadata key recording the measured clock offset in seconds
+pub const METADATA_CLOCK_OFFSET: &str = "clock_offset_secs";
+
+/// Metadata key naming the server the clock was compared with
+pub const METADATA_CLOCK_SOURCE: &str = "clock_source";
+
+/// Metadata key set to `skewed` or `unverified` when the clock check failed
+pub const METADATA_CLOCK_WARNING: &str = "clock_warning";
+
+/// What to do when the clock cannot be trusted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockPolicy {
+    /// Do not check the clock
+    #[default]
+    Off,
+    /// Issue the certificate with a warning in its metadata
+    Flag,
+    /// Refuse to issue the certificate
+    Refuse,
+}
+
+/// Clock check settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockCheck {
+    pub policy: ClockPolicy,
+    /// Servers tried in order, as `host` or `host:port`
+    pub ntp_servers: Vec<String>,
+    /// Largest accepted difference between local and NTP time
+    pub max_skew_secs: u64,
+    /// Time to wait for each server
+    pub timeout_ms: u64,
+}
+
+/// Result of comparing the local clock with an NTP server
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClockStatus {
+    /// The offset is within the allowed skew
+    Synchronized { server: String, offset_secs: f64 },
+    /// The clock is off by more than the allowed skew
+    Skewed { server: String, offset_secs: f64 },
+    /// No server answered
+    Unverified { reason: String },
+}
+
+impl Default for ClockCheck {
+    fn default() -> Self {
+        Self {
+            policy: ClockPolicy::Off,
+            ntp_servers: vec!["pool.ntp.org".to_string()],
+            max_skew_secs: 300,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+impl ClockCheck {
+    /// Query the servers; `None` when checking is off
+    pub fn run(&self) -> Option<ClockStatus> {
+        if self.policy == ClockPolicy::Off {
+            return None;
+        }
+        let mut errors = Vec::new();
+        for server in &self.ntp_servers {
+            match query_offset(server, Duration::from_millis(self.timeout_ms)) {
+                Ok(offset_secs) => return Some(self.classify(server, offset_secs)),
+                Err(e) => errors.push(format!("{}: {}", server, e)),
+            }
+        }
+        Some(ClockStatus::Unverified {
+            reason: if errors.is_empty() { "no NTP servers configured".to_string() } else { errors.join("; ") },
+        })
+    }
+    
+    /// [`ClockCheck::run`] on the blocking thread pool, for async callers;
+    /// each server may hold the lookup and query for up to the timeout
+    pub async fn run_async(&self) -> Option<ClockStatus> {
+        if self.policy == ClockPolicy::Off {
+            return None;
+        }
+        let check = self.clone();
+        tokio::task::spawn_blocking(move || check.run()).await.unwrap_or_else(|e| {
+            Some(ClockStatus::Unverified { reason: format!("clock check did not finish: {}", e) })
+        })
+    }
+    
+    /// Apply the policy to a check result, returning the metadata to record
+    /// in the certificate, or an error when the certificate must be refused
+    pub fn enforce(&self, status: &ClockStatus) -> Result<Vec<(&'static str, String)>> {
+        let refuse = self.policy == ClockPolicy::Refuse;
+        match status {
+            ClockStatus::Synchronized { server, offset_secs } => Ok(vec![
+                (METADATA_CLOCK_OFFSET, format!("{:.3}", offset_secs)),
+                (METADATA_CLOCK_SOURCE, server.clone()),
+            ]),
+            ClockStatus::Skewed { server, offset_secs } if refuse => Err(CertificateError::InvalidTimestamp(format!(
+                "System clock differs from {} by {:.0} s (allowed {} s)", server, offset_secs, self.max_skew_secs
+            ))),
+            ClockStatus::Skewed { server, offset_secs } => Ok(vec![
+                (METADATA_CLOCK_OFFSET, format!("{:.3}", offset_secs)),
+                (METADATA_CLOCK_SOURCE, server.clone()),
+                (METADATA_CLOCK_WARNING, "skewed".to_string()),
+            ]),
+            ClockStatus::Unverified { reason } if refuse => Err(CertificateError::InvalidTimestamp(format!(
+                "System clock could not be verified: {}", reason
+            ))),
+            ClockStatus::Unverified { .. } => Ok(vec![(METADATA_CLOCK_WARNING, "unverified".to_string())]),
+        }
+    }
+    
+    fn classify(&self, server: &str, offset_secs: f64) -> ClockStatus {
+        let server = server.to_string();
+        if offset_secs.abs() > self.max_skew_secs as f64 {
+            ClockStatus::Skewed { server, offset_secs }
+        } else {
+            ClockStatus::Synchronized { server, offset_secs }
+        }
+    }
+}
+
+/// Offset of the NTP server's clock from the local clock in seconds;
+/// positive when the local clock is behind
+fn query_offset(server: &str, timeout: Duration) -> std::io::Result<f64> {
+    let address = if server.contains(':') { server.to_string() } else { format!("{}:123", server) };
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.connect(address)?;
+    
+    // LI 0, version 4, mode 3 (client)
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    let sent_at = ntp_now();
+    socket.send(&request)?;
+    let mut response = [0u8; 48];
+    let received = socket.recv(&mut response)?;
+    let received_at = ntp_now();
+    
+    offset_from_response(&response[..received], sent_at, received_at)
+}
+
+/// Clock offset from an SNTP response, per RFC 4330 section 5
+fn offset_from_response(response: &[u8], sent_at: f64, received_at: f64) -> std::io::Result<f64> {
+    let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, reason.to_string());
+    if response.len() < 48 {
+        return Err(invalid("short NTP response"));
+    }
+    if response[0] & 0x07 != 4 || response[1] == 0 || response[0] >> 6 == 3 {
+        return Err(invalid("server is not synchronized"));
+    }
+    let server_received = ntp_timestamp(&response[32..40]);
+    let server_sent = ntp_timestamp(&response[40..48]);
+    Ok(((server_received - sent_at) + (server_sent - received_at)) / 2.0)
+}
+
+fn ntp_timestamp(bytes: &[u8]) -> f64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as f64 / 4_294_967_296.0;
+    seconds + fraction
+}
+
+fn ntp_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+        + NTP_UNIX_OFFSET
+}
+
+impl std::str::FromStr for ClockPolicy {
+    type Err = CertificateError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(ClockPolicy::Off),
+            "flag" => Ok(ClockPolicy::Flag),
+            "refuse" => Ok(ClockPolicy::Refuse),
+            other => Err(CertificateError::InvalidConfiguration(format!("Unknown clock policy: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn response(server_time: f64) -> [u8; 48] {
+        let mut response = [0u8; 48];
+        response[0] = 0x24;
+        response[1] = 2;
+        for offset in [32, 40] {
+            response[offset..offset + 4].copy_from_slice(&(server_time.trunc() as u32).to_be_bytes());
+        }
+        response
+    }
+    
+    #[test]
+    fn test_offset_from_response() {
+        let now = ntp_now().trunc();
+        let offset = offset_from_response(&response(now + 3600.0), now, now).unwrap();
+        assert!((offset - 3600.0).abs() < 1e-6);
+        
+        let mut unsynchronized = response(now);
+        unsynchronized[1] = 0;
+        assert!(offset_from_response(&unsynchronized, now, now).is_err());
+        assert!(offset_from_response(&[0u8; 12], now, now).is_err());
+    }
+    
+    #[test]
+    fn test_policy_enforcement() {
+        let flag = ClockCheck { policy: ClockPolicy::Flag, ntp_servers: Vec::new(), ..ClockCheck::default() };
+        assert!(ClockCheck::default().run().is_none());
+        let status = flag.run().unwrap();
+        assert!(matches!(status, ClockStatus::Unverified { .. }));
+        assert_eq!(flag.enforce(&status).unwrap(), vec![(METADATA_CLOCK_WARNING, "unverified".to_string())]);
+        
+        let refuse = ClockCheck { policy: ClockPolicy::Refuse, ..flag.clone() };
+        assert!(refuse.enforce(&status).is_err());
+        let skewed = refuse.classify("ntp.example", -86_400.0);
+        assert!(matches!(skewed, ClockStatus::Skewed { .. }));
+        assert!(refuse.enforce(&skewed).is_err());
+        assert!(refuse.enforce(&refuse.classify("ntp.example", 0.25)).is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_async_check_leaves_runtime_free() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let check = ClockCheck {
+            policy: ClockPolicy::Flag,
+            ntp_servers: vec![silent.local_addr().unwrap().to_string()],
+            timeout_ms: 500,
+            ..ClockCheck::default()
+        };
+        
+        // The runtime keeps running other tasks while the server does not answer
+        let started = std::time::Instant::now();
+        let (status, ticked_after) = tokio::join!(check.run_async(), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            started.elapsed()
+        });
+        assert!(matches!(status, Some(ClockStatus::Unverified { .. })));
+        assert!(ticked_after < Duration::from_millis(400));
+    }
+}
@@ -2,26 +2,30 @@
 
 use std::path::Path;
 use serde::{Deserialize, Serialize};
-use openssl::{
-    pkey::{PKey, Private, Public},
-    rsa::Rsa,
-    sign::{Signer, Verifier},
-    hash::MessageDigest,
-    base64,
-};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use sha2::{Sha256, Digest};
 use chrono::{DateTime, Utc};
-use uuid::Uuid;
 
+use crate::canonical;
 use crate::certificate::{WipeCertificate, SignedCertificate};
 use crate::error::{CertificateError, Result};
+use crate::keys::{HashAlgorithm, KeyType, PrivateKey, PublicKey};
+use crate::trust_store::TrustedKey;
+
+/// Signature format written by [`CertificateSigner`]. Version 1 signed the
+/// canonical certificate alone; version 2 signs the canonical
+/// [`SignatureInfo`] without its `signature` field, so the timestamp, key ID
+/// and algorithm are covered too and the certificate through its hash.
+pub const SIGNATURE_VERSION: u32 = 2;
 
 /// Certificate signer for creating cryptographic signatures
 #[derive(Debug)]
 pub struct CertificateSigner {
-    private_key: PKey<Private>,
-    public_key: PKey<Public>,
+    private_key: PrivateKey,
+    public_key: PublicKey,
     key_id: String,
+    algorithm: SignatureAlgorithm,
 }
 
 /// Signature information attached to certificates
@@ -54,16 +58,60 @@ pub struct KeyPairInfo {
     pub fingerprint: String,
 }
 
+impl SignatureAlgorithm {
+    /// Algorithm matching a key's type and size
+    pub fn for_key(key: &PublicKey) -> Result<Self> {
+        match key.key_type() {
+            KeyType::Rsa { bits: 2048 } => Ok(SignatureAlgorithm::RSA2048SHA256),
+            KeyType::Rsa { bits: 4096 } => Ok(SignatureAlgorithm::RSA4096SHA256),
+            KeyType::EcP256 => Ok(SignatureAlgorithm::ECDSAP256SHA256),
+            KeyType::EcP384 => Ok(SignatureAlgorithm::ECDSAP384SHA384),
+            key_type => Err(CertificateError::NotSupported(format!("Signing keys of type {:?}", key_type))),
+        }
+    }
+    
+    /// Hash function used with this algorithm
+    pub fn digest(&self) -> HashAlgorithm {
+        match self {
+            SignatureAlgorithm::ECDSAP384SHA384 => HashAlgorithm::Sha384,
+            _ => HashAlgorithm::Sha256,
+        }
+    }
+    
+    pub fn is_rsa(&self) -> bool {
+        matches!(self, SignatureAlgorithm::RSA2048SHA256 | SignatureAlgorithm::RSA4096SHA256)
+    }
+}
+
+impl SignatureInfo {
+    /// Bytes covered by a version 2 signature: the canonical signature
+    /// information without the signature itself
+    pub fn signed_attributes(&self) -> Result<String> {
+        let mut attributes = serde_json::to_value(self)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+        if let Some(fields) = attributes.as_object_mut() {
+            fields.remove("signature");
+        }
+        Ok(canonical::to_canonical_string(&attributes))
+    }
+}
+
 impl CertificateSigner {
     /// Create a new certificate signer with generated keys
     pub fn new() -> Result<Self> {
-        let (private_key, public_key) = Self::generate_key_pair(SignatureAlgorithm::RSA2048SHA256)?;
+        Self::with_algorithm(SignatureAlgorithm::RSA2048SHA256)
+    }
+    
+    /// Create a certificate signer with a generated key for `algorithm`
+    pub fn with_algorithm(algorithm: SignatureAlgorithm) -> Result<Self> {
+        let (private_key, public_key) = Self::generate_key_pair(algorithm)?;
         let key_id = Self::generate_key_id(&public_key)?;
         
         Ok(Self {
             private_key,
             public_key,
             key_id,
+            algorithm,
         })
     }
     
@@ -75,56 +123,30 @@ impl CertificateSigner {
         let public_key_pem = std::fs::read_to_string(public_key_path)
             .map_err(|e| CertificateError::FileOperationFailed(e.to_string()))?;
         
-        let private_key = PKey::private_key_from_pem(private_key_pem.as_bytes())
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
-        
-        let public_key = PKey::public_key_from_pem(public_key_pem.as_bytes())
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+        let private_key = PrivateKey::from_pem(&private_key_pem)?;
+        let public_key = PublicKey::from_pem(&public_key_pem)?;
         
         let key_id = Self::generate_key_id(&public_key)?;
+        let algorithm = SignatureAlgorithm::for_key(&public_key)?;
         
         Ok(Self {
             private_key,
             public_key,
             key_id,
+            algorithm,
         })
     }
     
     /// Generate a new key pair
-    pub fn generate_key_pair(algorithm: SignatureAlgorithm) -> Result<(PKey<Private>, PKey<Public>)> {
-        match algorithm {
-            SignatureAlgorithm::RSA2048SHA256 => {
-                let rsa = Rsa::generate(2048)
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                let private_key = PKey::from_rsa(rsa.clone())
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                let public_key = PKey::from_rsa(rsa)
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                Ok((private_key, public_key))
-            }
-            SignatureAlgorithm::RSA4096SHA256 => {
-                let rsa = Rsa::generate(4096)
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                let private_key = PKey::from_rsa(rsa.clone())
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                let public_key = PKey::from_rsa(rsa)
-                    .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))?;
-                
-                Ok((private_key, public_key))
-            }
-            _ => Err(CertificateError::NotSupported(format!("Algorithm {:?} not yet implemented", algorithm))),
-        }
+    pub fn generate_key_pair(algorithm: SignatureAlgorithm) -> Result<(PrivateKey, PublicKey)> {
+        let private_key = PrivateKey::generate(algorithm)?;
+        let public_key = private_key.public_key()?;
+        Ok((private_key, public_key))
     }
     
     /// Generate a unique key ID from the public key
-    fn generate_key_id(public_key: &PKey<Public>) -> Result<String> {
-        let public_key_der = public_key.public_key_to_der()
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+    pub(crate) fn generate_key_id(public_key: &PublicKey) -> Result<String> {
+        let public_key_der = public_key.to_der()?;
         
         let mut hasher = Sha256::new();
         hasher.update(&public_key_der);
@@ -139,7 +161,7 @@ impl CertificateSigner {
         certificate.validate()?;
         
         // Serialize certificate for signing
-        let certificate_json = serde_json::to_string(certificate)
+        let certificate_json = canonical::canonicalize(certificate)
             .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
         
         // Calculate certificate hash
@@ -147,56 +169,67 @@ impl CertificateSigner {
         hasher.update(certificate_json.as_bytes());
         let certificate_hash = hex::encode(hasher.finalize());
         
-        // Create signature
-        let signature = self.create_signature(&certificate_json)?;
-        
-        let signature_info = SignatureInfo {
-            signature,
-            algorithm: SignatureAlgorithm::RSA2048SHA256, // Default for now
+        let mut signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: self.algorithm,
             key_id: self.key_id.clone(),
             timestamp: Utc::now(),
             certificate_hash,
-            signature_version: 1,
+            signature_version: SIGNATURE_VERSION,
         };
         
+        // Sign the metadata, which binds the certificate through its hash
+        signature_info.signature = self.create_signature(&signature_info.signed_attributes()?)?;
+        
         Ok(SignedCertificate::new(certificate.clone(), signature_info))
     }
     
+    /// ID of the signing key, as recorded in [`SignatureInfo::key_id`]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+    
+    /// Algorithm of the signing key, as recorded in [`SignatureInfo::algorithm`]
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+    
+    /// Raw SHA-256 signature over arbitrary bytes
+    pub fn sign_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.private_key.sign(HashAlgorithm::Sha256, data)
+    }
+    
+    /// Base64 SHA-256 signature over the canonical JSON of a document other
+    /// than a certificate, such as an attempt report
+    pub fn sign_document<T: Serialize>(&self, document: &T) -> Result<String> {
+        let canonical = canonical::canonicalize(document)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+        Ok(BASE64.encode(self.sign_bytes(canonical.as_bytes())?))
+    }
+    
     /// Create a cryptographic signature
     fn create_signature(&self, data: &str) -> Result<String> {
-        let mut signer = Signer::new(MessageDigest::sha256(), &self.private_key)
-            .map_err(|e| CertificateError::SigningFailed(e.to_string()))?;
-        
-        signer.update(data.as_bytes())
-            .map_err(|e| CertificateError::SigningFailed(e.to_string()))?;
-        
-        let signature = signer.sign_to_vec()
-            .map_err(|e| CertificateError::SigningFailed(e.to_string()))?;
-        
-        Ok(base64::encode_block(&signature))
+        let signature = self.private_key.sign(self.algorithm.digest(), data.as_bytes())?;
+        Ok(BASE64.encode(signature))
     }
     
     /// Get public key information
     pub fn get_key_info(&self) -> Result<KeyPairInfo> {
-        let public_key_pem = self.public_key.public_key_to_pem()
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
-        
+        let public_key_pem = self.public_key.to_pem()?;
         let fingerprint = self.calculate_fingerprint()?;
         
         Ok(KeyPairInfo {
             key_id: self.key_id.clone(),
-            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            algorithm: self.algorithm,
             created_at: Utc::now(),
-            public_key_pem: String::from_utf8(public_key_pem)
-                .map_err(|e| CertificateError::CryptographicError(e.to_string()))?,
+            public_key_pem,
             fingerprint,
         })
     }
     
     /// Calculate public key fingerprint
     fn calculate_fingerprint(&self) -> Result<String> {
-        let public_key_der = self.public_key.public_key_to_der()
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+        let public_key_der = self.public_key.to_der()?;
         
         let mut hasher = Sha256::new();
         hasher.update(&public_key_der);
@@ -217,11 +250,8 @@ impl CertificateSigner {
     
     /// Export keys to files
     pub fn export_keys<P: AsRef<Path>>(&self, private_key_path: P, public_key_path: P) -> Result<()> {
-        let private_key_pem = self.private_key.private_key_to_pem_pkcs8()
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
-        
-        let public_key_pem = self.public_key.public_key_to_pem()
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+        let private_key_pem = self.private_key.to_pkcs8_pem()?;
+        let public_key_pem = self.public_key.to_pem()?;
         
         std::fs::write(private_key_path, private_key_pem)
             .map_err(|e| CertificateError::FileOperationFailed(e.to_string()))?;
@@ -236,7 +266,9 @@ impl CertificateSigner {
 /// Certificate verifier for validating signatures
 #[derive(Debug)]
 pub struct CertificateVerifier {
-    trusted_keys: std::collections::HashMap<String, PKey<Public>>,
+    trusted_keys: std::collections::HashMap<String, PublicKey>,
+    /// Label and validity window of each trusted key
+    pub(crate) key_entries: std::collections::HashMap<String, TrustedKey>,
 }
 
 impl CertificateVerifier {
@@ -244,12 +276,27 @@ impl CertificateVerifier {
     pub fn new() -> Result<Self> {
         Ok(Self {
             trusted_keys: std::collections::HashMap::new(),
+            key_entries: std::collections::HashMap::new(),
         })
     }
     
     /// Add a trusted public key
-    pub fn add_trusted_key(&mut self, key_id: String, public_key: PKey<Public>) {
-        self.trusted_keys.insert(key_id, public_key);
+    pub fn add_trusted_key(&mut self, key_id: String, public_key: PublicKey) {
+        let pem = public_key.to_pem().unwrap_or_default();
+        let entry = TrustedKey::new(key_id.clone(), key_id, pem);
+        self.insert_key(entry, public_key);
+    }
+    
+    /// Trust a key, replacing any entry with the same ID
+    pub(crate) fn insert_key(&mut self, entry: TrustedKey, public_key: PublicKey) {
+        self.trusted_keys.insert(entry.key_id.clone(), public_key);
+        self.key_entries.insert(entry.key_id.clone(), entry);
+    }
+    
+    /// Stop trusting a key
+    pub(crate) fn remove_key_entry(&mut self, key_id: &str) -> bool {
+        self.key_entries.remove(key_id);
+        self.trusted_keys.remove(key_id).is_some()
     }
     
     /// Load trusted keys from a directory
@@ -266,7 +313,9 @@ impl CertificateVerifier {
             if path.extension().and_then(|s| s.to_str()) == Some("pem") {
                 match self.load_public_key_file(&path) {
                     Ok((key_id, public_key)) => {
-                        self.trusted_keys.insert(key_id, public_key);
+                        let label = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| key_id.clone());
+                        let pem = public_key.to_pem().unwrap_or_default();
+                        self.insert_key(TrustedKey::new(key_id, label, pem), public_key);
                         loaded_count += 1;
                     }
                     Err(e) => {
@@ -279,13 +328,46 @@ impl CertificateVerifier {
         Ok(loaded_count)
     }
     
+    /// Load a trust bundle: a file of concatenated PEM public keys, a saved
+    /// trust store or JWKS document, or a directory of `.pem` files
+    pub fn load_trust_bundle<P: AsRef<Path>>(&mut self, bundle: P) -> Result<usize> {
+        let bundle = bundle.as_ref();
+        if bundle.is_dir() {
+            return self.load_trusted_keys(bundle);
+        }
+        
+        let contents = std::fs::read_to_string(bundle)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", bundle.display(), e)))?;
+        let loaded = if contents.trim_start().starts_with('{') {
+            self.import_json(&contents)?
+        } else {
+            let label = bundle.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            self.import_pem(&label, &contents, None, None)?
+        };
+        if loaded.is_empty() {
+            return Err(CertificateError::InvalidFileFormat(format!(
+                "{} contains no public keys", bundle.display()
+            )));
+        }
+        Ok(loaded.len())
+    }
+    
+    /// Whether a key with this ID is trusted
+    pub fn is_trusted(&self, key_id: &str) -> bool {
+        self.trusted_keys.contains_key(key_id)
+    }
+    
+    /// Whether a key with this ID is trusted and its validity window covers `at`
+    pub fn is_trusted_at(&self, key_id: &str, at: DateTime<Utc>) -> bool {
+        self.is_trusted(key_id) && self.key_entries.get(key_id).is_none_or(|entry| entry.is_valid_at(at))
+    }
+    
     /// Load a public key from file
-    fn load_public_key_file<P: AsRef<Path>>(&self, path: P) -> Result<(String, PKey<Public>)> {
+    fn load_public_key_file<P: AsRef<Path>>(&self, path: P) -> Result<(String, PublicKey)> {
         let pem_data = std::fs::read_to_string(path)
             .map_err(|e| CertificateError::FileOperationFailed(e.to_string()))?;
         
-        let public_key = PKey::public_key_from_pem(pem_data.as_bytes())
-            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+        let public_key = PublicKey::from_pem(&pem_data)?;
         
         let key_id = CertificateSigner::generate_key_id(&public_key)?;
         
@@ -294,45 +376,84 @@ impl CertificateVerifier {
     
     /// Verify a signed certificate
     pub async fn verify_certificate(&self, signed_certificate: &SignedCertificate) -> Result<bool> {
+        self.verify_encodings(signed_certificate, Vec::new())
+    }
+    
+    /// Verify against the given encodings of the certificate content and the
+    /// encodings of its current serialization
+    fn verify_encodings(&self, signed_certificate: &SignedCertificate, mut encodings: Vec<String>) -> Result<bool> {
         // Validate the certificate structure
         signed_certificate.validate()?;
         
         // Get the public key for verification
-        let public_key = self.trusted_keys.get(&signed_certificate.signature_info.key_id)
-            .ok_or_else(|| CertificateError::SignatureVerificationFailed)?;
+        let key_id = &signed_certificate.signature_info.key_id;
+        let public_key = self.trusted_keys.get(key_id)
+            .ok_or(CertificateError::SignatureVerificationFailed)?;
+        if !self.is_trusted_at(key_id, signed_certificate.signature_info.timestamp) {
+            return Err(CertificateError::CertificateValidationFailed(format!(
+                "Key {} was not valid when the certificate was signed", key_id
+            )));
+        }
         
-        // Serialize the certificate for verification
-        let certificate_json = serde_json::to_string(&signed_certificate.certificate)
+        // Serialize the certificate for verification. Certificates issued before
+        // canonical encoding was introduced were signed over the struct serialization.
+        let canonical_json = canonical::canonicalize(&signed_certificate.certificate)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+        let legacy_json = serde_json::to_string(&signed_certificate.certificate)
             .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
         
         // Verify the certificate hash
-        let mut hasher = Sha256::new();
-        hasher.update(certificate_json.as_bytes());
-        let calculated_hash = hex::encode(hasher.finalize());
-        
-        if calculated_hash != signed_certificate.signature_info.certificate_hash {
+        let expected_hash = &signed_certificate.signature_info.certificate_hash;
+        encodings.extend([canonical_json, legacy_json]);
+        let Some(certificate_json) = encodings
+            .into_iter()
+            .find(|json| &hex::encode(Sha256::digest(json.as_bytes())) == expected_hash)
+        else {
             return Ok(false);
-        }
+        };
         
         // Verify the signature
-        self.verify_signature(&certificate_json, &signed_certificate.signature_info.signature, public_key)
+        let signature_info = &signed_certificate.signature_info;
+        match signature_info.signature_version {
+            1 => self.verify_signature(&certificate_json, &signature_info.signature, public_key, HashAlgorithm::Sha256),
+            2 => {
+                // The recorded algorithm is signed, but must also fit the key
+                if SignatureAlgorithm::for_key(public_key)? != signature_info.algorithm {
+                    return Err(CertificateError::SignatureVerificationFailed);
+                }
+                self.verify_signature(
+                    &signature_info.signed_attributes()?,
+                    &signature_info.signature,
+                    public_key,
+                    signature_info.algorithm.digest(),
+                )
+            }
+            version => Err(CertificateError::NotSupported(format!("Signature version {}", version))),
+        }
+    }
+    
+    /// Check a raw RSA-SHA256 signature made by the trusted key `key_id`
+    pub fn verify_bytes(&self, key_id: &str, data: &[u8], signature: &[u8]) -> Result<bool> {
+        let public_key = self.trusted_keys.get(key_id)
+            .ok_or(CertificateError::SignatureVerificationFailed)?;
+        public_key.verify(HashAlgorithm::Sha256, data, signature)
+    }
+    
+    /// Check a signature made by [`CertificateSigner::sign_document`]
+    pub fn verify_document<T: Serialize>(&self, key_id: &str, document: &T, signature: &str) -> Result<bool> {
+        let canonical = canonical::canonicalize(document)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+        let signature = BASE64.decode(signature)
+            .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+        self.verify_bytes(key_id, canonical.as_bytes(), &signature)
     }
     
     /// Verify a cryptographic signature
-    fn verify_signature(&self, data: &str, signature: &str, public_key: &PKey<Public>) -> Result<bool> {
-        let signature_bytes = base64::decode_block(signature)
+    fn verify_signature(&self, data: &str, signature: &str, public_key: &PublicKey, digest: HashAlgorithm) -> Result<bool> {
+        let signature_bytes = BASE64.decode(signature)
             .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
         
-        let mut verifier = Verifier::new(MessageDigest::sha256(), public_key)
-            .map_err(|e| CertificateError::SignatureVerificationFailed)?;
-        
-        verifier.update(data.as_bytes())
-            .map_err(|e| CertificateError::SignatureVerificationFailed)?;
-        
-        let is_valid = verifier.verify(&signature_bytes)
-            .map_err(|e| CertificateError::SignatureVerificationFailed)?;
-        
-        Ok(is_valid)
+        public_key.verify(digest, data.as_bytes(), &signature_bytes)
     }
     
     /// Verify a certificate from file
@@ -340,10 +461,16 @@ impl CertificateVerifier {
         let certificate_json = std::fs::read_to_string(certificate_path)
             .map_err(|e| CertificateError::FileOperationFailed(e.to_string()))?;
         
-        let signed_certificate: SignedCertificate = serde_json::from_str(&certificate_json)
+        let document: serde_json::Value = serde_json::from_str(&certificate_json)
+            .map_err(|e| CertificateError::JsonDeserializationFailed(e.to_string()))?;
+        let signed_certificate: SignedCertificate = serde_json::from_value(document.clone())
             .map_err(|e| CertificateError::JsonDeserializationFailed(e.to_string()))?;
         
-        self.verify_certificate(&signed_certificate).await
+        // Older certificates store durations as `{"secs", "nanos"}`, which no
+        // longer survives a round trip through the structs; check the content
+        // exactly as stored as well
+        let stored = document.get("certificate").map(canonical::to_canonical_string);
+        self.verify_encodings(&signed_certificate, stored.into_iter().collect())
     }
 }
 
@@ -373,6 +500,7 @@ mod tests {
                 serial: "TEST123".to_string(),
                 model: "Test Drive".to_string(),
                 size: 1000000000,
+                virtualization: None,
             },
             wipe_info: WipeInfo {
                 algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
@@ -381,12 +509,17 @@ mod tests {
                 duration: Some(std::time::Duration::from_secs(3600)),
                 passes_completed: 1,
                 verification_passed: Some(true),
+                device_digest: None,
             },
             verification_info: None,
             compliance_info: None,
             technical_details: None,
             organization: None,
             metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
         };
         
         WipeCertificate::new(data)
@@ -407,6 +540,55 @@ mod tests {
         // Verify the certificate
         let is_valid = verifier.verify_certificate(&signed_certificate).await.unwrap();
         assert!(is_valid);
+        assert_eq!(signed_certificate.signature_info.signature_version, SIGNATURE_VERSION);
+        assert_eq!(signed_certificate.signature_info.algorithm, SignatureAlgorithm::RSA2048SHA256);
+    }
+    
+    #[tokio::test]
+    async fn test_v2_covers_metadata_and_v1_still_verifies() {
+        let signer = CertificateSigner::with_algorithm(SignatureAlgorithm::ECDSAP256SHA256).unwrap();
+        let certificate = create_test_certificate();
+        let signed_certificate = signer.sign_certificate(&certificate).await.unwrap();
+        assert_eq!(signed_certificate.signature_info.algorithm, SignatureAlgorithm::ECDSAP256SHA256);
+        
+        let mut verifier = CertificateVerifier::new().unwrap();
+        verifier.add_trusted_key(signer.key_id.clone(), signer.public_key.clone());
+        assert!(verifier.verify_certificate(&signed_certificate).await.unwrap());
+        
+        // Back-dating the signature is detected
+        let mut backdated = signed_certificate.clone();
+        backdated.signature_info.timestamp -= chrono::Duration::days(30);
+        assert!(!verifier.verify_certificate(&backdated).await.unwrap());
+        
+        // Version 1: the signature covers the canonical certificate only
+        let rsa_signer = CertificateSigner::new().unwrap();
+        let canonical_json = canonical::canonicalize(&certificate).unwrap();
+        let mut legacy = rsa_signer.sign_certificate(&certificate).await.unwrap();
+        legacy.signature_info.signature_version = 1;
+        legacy.signature_info.signature = BASE64.encode(rsa_signer.sign_bytes(canonical_json.as_bytes()).unwrap());
+        verifier.add_trusted_key(rsa_signer.key_id.clone(), rsa_signer.public_key.clone());
+        assert!(verifier.verify_certificate(&legacy).await.unwrap());
+        
+        legacy.signature_info.signature_version = 3;
+        assert!(verifier.verify_certificate(&legacy).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_signature_survives_json_roundtrip() {
+        let signer = CertificateSigner::new().unwrap();
+        let mut certificate = create_test_certificate();
+        for i in 0..8 {
+            certificate.data.metadata.insert(format!("key{}", i), i.to_string());
+        }
+        let signed_certificate = signer.sign_certificate(&certificate).await.unwrap();
+        
+        // HashMap order after parsing differs from the order at signing time
+        let json = serde_json::to_string(&signed_certificate).unwrap();
+        let reparsed: SignedCertificate = serde_json::from_str(&json).unwrap();
+        
+        let mut verifier = CertificateVerifier::new().unwrap();
+        verifier.add_trusted_key(signer.key_id.clone(), signer.public_key.clone());
+        assert!(verifier.verify_certificate(&reparsed).await.unwrap());
     }
     
     #[test]
@@ -0,0 +1,321 @@
+//! Records of physical destruction
+//!
+//! Not every drive leaves by being wiped: failed drives, drives whose wipe
+//! did not complete and media an organization's policy never lets out whole
+//! are shredded, degaussed or otherwise destroyed, usually by a vendor and in
+//! front of witnesses. A destruction record documents that disposition with
+//! the same signing key and canonical JSON as certificates, so all the media
+//! paperwork of an organization comes out of one pipeline and is checked the
+//! same way. The record carries its own document type and names the NIST SP
+//! 800-88 category of its method, which is Purge for degaussing and Destroy
+//! for the others.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::crypto::{CertificateSigner, CertificateVerifier};
+use crate::error::{CertificateError, Result};
+use crate::CertificateOptions;
+
+/// `document_type` of destruction records
+pub const DESTRUCTION_RECORD_TYPE: &str = "physical_destruction_record";
+
+/// How the media was destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestructionMethod {
+    Shredding,
+    Degaussing,
+    Disintegration,
+    Pulverization,
+    Incineration,
+    /// Crushing or punching through the platters or flash packages
+    Crushing,
+}
+
+/// Company that carried out the destruction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestructionVendor {
+    pub name: String,
+    /// Number of the vendor's own certificate of destruction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_number: Option<String>,
+}
+
+/// Person who watched the destruction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Witness {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+}
+
+/// What the operator reports about a destruction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestructionRequest {
+    pub device_serial: String,
+    #[serde(default)]
+    pub device_model: Option<String>,
+    #[serde(default)]
+    pub asset_tag: Option<String>,
+    pub method: DestructionMethod,
+    pub vendor: DestructionVendor,
+    pub witnesses: Vec<Witness>,
+    pub destroyed_at: DateTime<Utc>,
+    /// Site or facility the media was destroyed at
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Largest particle left by shredding or disintegration, in millimetres
+    #[serde(default)]
+    pub particle_size_mm: Option<f64>,
+    /// Attempt report of the wipe that failed on this media, if one did
+    #[serde(default)]
+    pub attempt_report_id: Option<Uuid>,
+}
+
+/// Signed account of one destruction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestructionRecord {
+    /// Always [`DESTRUCTION_RECORD_TYPE`]
+    pub document_type: String,
+    pub record_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    /// NIST SP 800-88 category of the method
+    pub nist_category: String,
+    #[serde(flatten)]
+    pub destruction: DestructionRequest,
+    /// Who recorded the destruction
+    pub recorded_by: String,
+    pub organization: Option<crate::OrganizationInfo>,
+    pub metadata: HashMap<String, String>,
+}
+
+/// Destruction record with the issuer's signature over its canonical JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDestructionRecord {
+    pub record: DestructionRecord,
+    pub key_id: String,
+    /// Base64 SHA-256 signature
+    pub signature: String,
+}
+
+impl DestructionMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DestructionMethod::Shredding => "shredding",
+            DestructionMethod::Degaussing => "degaussing",
+            DestructionMethod::Disintegration => "disintegration",
+            DestructionMethod::Pulverization => "pulverization",
+            DestructionMethod::Incineration => "incineration",
+            DestructionMethod::Crushing => "crushing",
+        }
+    }
+    
+    /// NIST SP 800-88 category; a degaussed drive is intact but unreadable
+    pub fn nist_category(&self) -> &'static str {
+        match self {
+            DestructionMethod::Degaussing => "Purge",
+            _ => "Destroy",
+        }
+    }
+}
+
+impl std::fmt::Display for DestructionMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl DestructionRequest {
+    /// Check that the request identifies the media, the vendor and a witness
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Err(CertificateError::InvalidCertificateData(reason.to_string()));
+        if self.device_serial.trim().is_empty() {
+            return invalid("A destruction record needs the device serial number");
+        }
+        if self.vendor.name.trim().is_empty() {
+            return invalid("A destruction record needs the destruction vendor");
+        }
+        if self.witnesses.is_empty() || self.witnesses.iter().any(|witness| witness.name.trim().is_empty()) {
+            return invalid("A destruction record needs at least one named witness");
+        }
+        if self.destroyed_at > Utc::now() {
+            return invalid("The destruction date is in the future");
+        }
+        if self.particle_size_mm.is_some_and(|size| size.is_nan() || size <= 0.0) {
+            return invalid("The particle size must be positive");
+        }
+        Ok(())
+    }
+}
+
+impl DestructionRecord {
+    /// Record a destruction reported by `recorded_by`
+    pub fn new(mut destruction: DestructionRequest, recorded_by: &str, options: &CertificateOptions) -> Result<Self> {
+        destruction.validate()?;
+        
+        let mut metadata = options.metadata.clone();
+        if let Some(redactor) = &options.redactor {
+            destruction.device_serial = redactor.redact(&destruction.device_serial);
+            destruction.asset_tag = destruction.asset_tag.as_deref().map(|tag| redactor.redact(tag));
+            for key in crate::REDACTED_METADATA {
+                if let Some(value) = metadata.get_mut(key) {
+                    *value = redactor.redact(value);
+                }
+            }
+            metadata.insert("identifiers_redacted".to_string(), "true".to_string());
+        }
+        
+        Ok(Self {
+            document_type: DESTRUCTION_RECORD_TYPE.to_string(),
+            record_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            nist_category: destruction.method.nist_category().to_string(),
+            destruction,
+            recorded_by: recorded_by.to_string(),
+            organization: options.organization.clone(),
+            metadata,
+        })
+    }
+    
+    /// Sign the record's canonical JSON
+    pub fn sign(self, signer: &CertificateSigner) -> Result<SignedDestructionRecord> {
+        Ok(SignedDestructionRecord {
+            signature: signer.sign_document(&self)?,
+            key_id: signer.key_id().to_string(),
+            record: self,
+        })
+    }
+    
+    /// Printable form of the record
+    pub fn text(&self) -> String {
+        let timestamp = |at: &DateTime<Utc>| safe_erase_core::time_format::format_timestamp(at);
+        let destruction = &self.destruction;
+        let mut text = String::new();
+        let _ = writeln!(text, "RECORD OF PHYSICAL DESTRUCTION");
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Record ID:        {}", self.record_id);
+        let _ = writeln!(text, "Generated:        {}", timestamp(&self.generated_at));
+        if let Some(organization) = &self.organization {
+            let _ = writeln!(text, "Organization:     {}", organization.name);
+        }
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Serial number:    {}", destruction.device_serial);
+        if let Some(model) = &destruction.device_model {
+            let _ = writeln!(text, "Model:            {}", model);
+        }
+        if let Some(asset_tag) = &destruction.asset_tag {
+            let _ = writeln!(text, "Asset tag:        {}", asset_tag);
+        }
+        let _ = writeln!(text, "Method:           {} (NIST SP 800-88 {})", destruction.method, self.nist_category);
+        if let Some(size) = destruction.particle_size_mm {
+            let _ = writeln!(text, "Particle size:    {} mm", size);
+        }
+        let _ = writeln!(text, "Destroyed:        {}", timestamp(&destruction.destroyed_at));
+        if let Some(location) = &destruction.location {
+            let _ = writeln!(text, "Location:         {}", location);
+        }
+        let _ = writeln!(text, "Vendor:           {}", destruction.vendor.name);
+        if let Some(number) = &destruction.vendor.certificate_number {
+            let _ = writeln!(text, "Vendor reference: {}", number);
+        }
+        for witness in &destruction.witnesses {
+            let witness = match &witness.organization {
+                Some(organization) => format!("{}, {}", witness.name, organization),
+                None => witness.name.clone(),
+            };
+            let _ = writeln!(text, "Witness:          {}", witness);
+        }
+        if let Some(report_id) = destruction.attempt_report_id {
+            let _ = writeln!(text, "Attempt report:   {}", report_id);
+        }
+        let _ = writeln!(text, "Recorded by:      {}", self.recorded_by);
+        let mut metadata: Vec<_> = self.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            let _ = writeln!(text, "{}: {}", key, value);
+        }
+        text
+    }
+}
+
+impl SignedDestructionRecord {
+    /// Check the signature against the verifier's trusted keys
+    pub fn verify(&self, verifier: &CertificateVerifier) -> Result<bool> {
+        if self.record.document_type != DESTRUCTION_RECORD_TYPE {
+            return Ok(false);
+        }
+        verifier.verify_document(&self.key_id, &self.record, &self.signature)
+    }
+    
+    /// Text form followed by the signature, for printing
+    pub fn text(&self) -> String {
+        format!("{}\nSigned by key {}\nSignature: {}\n", self.record.text(), self.key_id, self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::PublicKey;
+    
+    fn request() -> DestructionRequest {
+        serde_json::from_value(serde_json::json!({
+            "device_serial": "WD-1234567890",
+            "device_model": "Test Drive",
+            "method": "shredding",
+            "vendor": {"name": "Shred Co", "certificate_number": "SC-2024-0042"},
+            "witnesses": [{"name": "Bob", "organization": "Shred Co"}, {"name": "Alice"}],
+            "destroyed_at": "2024-05-01T12:00:00Z",
+            "particle_size_mm": 2.0
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_request_validation() {
+        assert!(request().validate().is_ok());
+        assert_eq!(DestructionMethod::Degaussing.nist_category(), "Purge");
+        assert_eq!(DestructionMethod::Shredding.nist_category(), "Destroy");
+        
+        let without_witness = DestructionRequest { witnesses: Vec::new(), ..request() };
+        assert!(without_witness.validate().is_err());
+        let without_vendor = DestructionRequest {
+            vendor: DestructionVendor { name: " ".to_string(), certificate_number: None },
+            ..request()
+        };
+        assert!(without_vendor.validate().is_err());
+        let future = DestructionRequest { destroyed_at: Utc::now() + chrono::Duration::days(1), ..request() };
+        assert!(future.validate().is_err());
+    }
+    
+    #[test]
+    fn test_signed_record_verifies() {
+        let signer = CertificateSigner::new().unwrap();
+        let mut verifier = CertificateVerifier::new().unwrap();
+        let pem = signer.get_key_info().unwrap().public_key_pem;
+        verifier.add_trusted_key(signer.key_id().to_string(), PublicKey::from_pem(&pem).unwrap());
+        
+        let record = DestructionRecord::new(request(), "alice", &CertificateOptions::default()).unwrap();
+        assert_eq!(record.nist_category, "Destroy");
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["document_type"], DESTRUCTION_RECORD_TYPE);
+        assert_eq!(json["vendor"]["name"], "Shred Co");
+        
+        let text = record.text();
+        assert!(text.contains("Method:           shredding (NIST SP 800-88 Destroy)"));
+        assert!(text.contains("Witness:          Bob, Shred Co"));
+        
+        let signed = record.sign(&signer).unwrap();
+        assert!(signed.verify(&verifier).unwrap());
+        let stored: SignedDestructionRecord = serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+        assert!(stored.verify(&verifier).unwrap());
+        
+        let mut altered = signed.clone();
+        altered.record.destruction.witnesses.pop();
+        assert!(!altered.verify(&verifier).unwrap());
+    }
+}
@@ -0,0 +1,235 @@
+//! PDF encryption with the standard security handler, revision 6
+//!
+//! Protected certificates use AES-256 (PDF 2.0, ISO 32000-2 §7.6.4): every
+//! string and stream is encrypted with a random 256-bit file key, and the
+//! encryption dictionary holds that key wrapped under the user and owner
+//! passwords. Passwords are used as UTF-8, truncated to 127 bytes; they are
+//! not normalized with SASLprep, so non-ASCII passwords must be entered in
+//! the same form.
+
+use aes::cipher::block_padding::{NoPadding, Pkcs7};
+use aes::cipher::{BlockEncryptMut, KeyIvInit};
+use aes::{Aes128, Aes256};
+use pdf_writer::{Finish, Name, Pdf, Ref, Str};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::error::{CertificateError, Result};
+use crate::PdfProtection;
+
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+
+/// Longest password prefix revision 6 uses
+const MAX_PASSWORD_LEN: usize = 127;
+
+/// Permissions that are never withheld: reserved bits set, content
+/// extraction for accessibility tools allowed
+const BASE_PERMISSIONS: u32 = 0xFFFF_F0C0 | 0x200;
+/// Print (bit 3) and print at full quality (bit 12)
+const PRINT_PERMISSIONS: u32 = 0x4 | 0x800;
+
+/// File key and encryption dictionary of one protected document
+pub(crate) struct PdfEncryption {
+    file_key: [u8; 32],
+    owner: [u8; 48],
+    user: [u8; 48],
+    owner_key: [u8; 32],
+    user_key: [u8; 32],
+    perms: [u8; 16],
+    permissions: u32,
+    rng: SystemRandom,
+}
+
+impl PdfEncryption {
+    /// Draw a file key and wrap it under the passwords of `protection`
+    pub(crate) fn new(protection: &PdfProtection) -> Result<Self> {
+        protection.validate()?;
+        let rng = SystemRandom::new();
+        let file_key: [u8; 32] = random(&rng)?;
+        let permissions = BASE_PERMISSIONS | if protection.allow_printing { PRINT_PERMISSIONS } else { 0 };
+        
+        let user_password = password(protection.user_password.as_deref().unwrap_or_default());
+        let (user, user_key) = wrap_key(&rng, &file_key, user_password, &[])?;
+        let (owner, owner_key) = wrap_key(&rng, &file_key, password(&protection.owner_password), &user)?;
+        
+        // Permissions, metadata encrypted, the marker and random padding, sealed with the file key
+        let mut perms = [0u8; 16];
+        perms[..4].copy_from_slice(&permissions.to_le_bytes());
+        perms[4..8].fill(0xFF);
+        perms[8..12].copy_from_slice(b"Tadb");
+        perms[12..].copy_from_slice(&random::<4>(&rng)?);
+        let perms = aes_256_block(&file_key, &perms);
+        
+        Ok(Self { file_key, owner, user, owner_key, user_key, perms, permissions, rng })
+    }
+    
+    /// Encrypt a string or stream: a random IV followed by the padded ciphertext
+    pub(crate) fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let iv: [u8; 16] = random(&self.rng)?;
+        let ciphertext = Aes256CbcEnc::new(&self.file_key.into(), &iv.into()).encrypt_padded_vec_mut::<Pkcs7>(data);
+        Ok([iv.as_slice(), &ciphertext].concat())
+    }
+    
+    /// Write the encryption dictionary; its strings are stored as they are
+    pub(crate) fn write_dictionary(&self, pdf: &mut Pdf, id: Ref) {
+        let mut dict = pdf.indirect(id).dict();
+        dict.pair(Name(b"Filter"), Name(b"Standard"));
+        dict.pair(Name(b"V"), 5);
+        dict.pair(Name(b"R"), 6);
+        dict.pair(Name(b"Length"), 256);
+        let mut filters = dict.insert(Name(b"CF")).dict();
+        filters
+            .insert(Name(b"StdCF"))
+            .dict()
+            .pair(Name(b"CFM"), Name(b"AESV3"))
+            .pair(Name(b"AuthEvent"), Name(b"DocOpen"))
+            .pair(Name(b"Length"), 32);
+        filters.finish();
+        dict.pair(Name(b"StmF"), Name(b"StdCF"));
+        dict.pair(Name(b"StrF"), Name(b"StdCF"));
+        dict.pair(Name(b"O"), Str(&self.owner));
+        dict.pair(Name(b"U"), Str(&self.user));
+        dict.pair(Name(b"OE"), Str(&self.owner_key));
+        dict.pair(Name(b"UE"), Str(&self.user_key));
+        dict.pair(Name(b"P"), self.permissions as i32);
+        dict.pair(Name(b"Perms"), Str(&self.perms));
+    }
+    
+    /// Reference the encryption dictionary from the trailer, which
+    /// `pdf-writer` finishes without one
+    pub(crate) fn link_trailer(bytes: Vec<u8>, id: Ref) -> Result<Vec<u8>> {
+        let trailer = bytes
+            .windows(b"trailer\n".len())
+            .rposition(|window| window == b"trailer\n")
+            .ok_or_else(|| CertificateError::PdfGenerationFailed("PDF has no trailer".to_string()))?;
+        let Some(dict) = bytes[trailer..].windows(2).position(|window| window == b"<<") else {
+            return Err(CertificateError::PdfGenerationFailed("PDF trailer is not a dictionary".to_string()));
+        };
+        let at = trailer + dict + 2;
+        let entry = format!("\n  /Encrypt {} 0 R", id.get());
+        Ok([&bytes[..at], entry.as_bytes(), &bytes[at..]].concat())
+    }
+}
+
+/// Validation and key salts with the password hash, and the file key
+/// encrypted under the hash with the key salt (algorithms 8 and 9)
+fn wrap_key(rng: &SystemRandom, file_key: &[u8; 32], password: &[u8], user: &[u8]) -> Result<([u8; 48], [u8; 32])> {
+    let validation_salt: [u8; 8] = random(rng)?;
+    let key_salt: [u8; 8] = random(rng)?;
+    let mut entry = [0u8; 48];
+    entry[..32].copy_from_slice(&hash(password, &validation_salt, user));
+    entry[32..40].copy_from_slice(&validation_salt);
+    entry[40..].copy_from_slice(&key_salt);
+    
+    let intermediate = hash(password, &key_salt, user);
+    let mut wrapped = [0u8; 32];
+    Aes256CbcEnc::new(&intermediate.into(), &[0u8; 16].into())
+        .encrypt_padded_b2b_mut::<NoPadding>(file_key, &mut wrapped)
+        .map_err(|e| CertificateError::CryptographicError(e.to_string()))?;
+    Ok((entry, wrapped))
+}
+
+/// Password hash of revision 6 (algorithm 2.B)
+fn hash(password: &[u8], salt: &[u8], user: &[u8]) -> [u8; 32] {
+    let mut key: Vec<u8> = Sha256::new().chain_update(password).chain_update(salt).chain_update(user).finalize().to_vec();
+    let mut round = 0usize;
+    loop {
+        let block = [password, &key, user].concat().repeat(64);
+        let aes_key: [u8; 16] = key[..16].try_into().expect("hash is at least 32 bytes");
+        let iv: [u8; 16] = key[16..32].try_into().expect("hash is at least 32 bytes");
+        let encrypted = Aes128CbcEnc::new(&aes_key.into(), &iv.into()).encrypt_padded_vec_mut::<NoPadding>(&block);
+        key = match encrypted[..16].iter().map(|&byte| u32::from(byte)).sum::<u32>() % 3 {
+            0 => Sha256::digest(&encrypted).to_vec(),
+            1 => Sha384::digest(&encrypted).to_vec(),
+            _ => Sha512::digest(&encrypted).to_vec(),
+        };
+        round += 1;
+        let last = usize::from(*encrypted.last().expect("block is never empty"));
+        if round >= 64 && last + 32 <= round {
+            break;
+        }
+    }
+    key[..32].try_into().expect("hash is at least 32 bytes")
+}
+
+fn aes_256_block(key: &[u8; 32], block: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    Aes256CbcEnc::new(key.into(), &[0u8; 16].into())
+        .encrypt_padded_b2b_mut::<NoPadding>(block, &mut out)
+        .expect("one whole block");
+    out
+}
+
+fn password(password: &str) -> &[u8] {
+    let bytes = password.as_bytes();
+    &bytes[..bytes.len().min(MAX_PASSWORD_LEN)]
+}
+
+fn random<const N: usize>(rng: &SystemRandom) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    rng.fill(&mut bytes)
+        .map_err(|_| CertificateError::CryptographicError("No randomness for the PDF file key".to_string()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockDecryptMut;
+    
+    type Aes256CbcDec = cbc::Decryptor<Aes256>;
+    
+    fn protection() -> PdfProtection {
+        PdfProtection {
+            owner_password: "owner".to_string(),
+            user_password: Some("reader".to_string()),
+            allow_printing: false,
+        }
+    }
+    
+    fn unwrap_key(hash: [u8; 32], wrapped: &[u8; 32]) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        Aes256CbcDec::new(&hash.into(), &[0u8; 16].into())
+            .decrypt_padded_b2b_mut::<NoPadding>(wrapped, &mut key)
+            .unwrap();
+        key
+    }
+    
+    #[test]
+    fn test_passwords_recover_file_key() {
+        let encryption = PdfEncryption::new(&protection()).unwrap();
+        let user = &encryption.user;
+        let owner = &encryption.owner;
+        
+        assert_eq!(hash(b"reader", &user[32..40], &[]), user[..32]);
+        assert_ne!(hash(b"owner", &user[32..40], &[]), user[..32]);
+        assert_eq!(hash(b"owner", &owner[32..40], user), owner[..32]);
+        assert_eq!(unwrap_key(hash(b"reader", &user[40..], &[]), &encryption.user_key), encryption.file_key);
+        assert_eq!(unwrap_key(hash(b"owner", &owner[40..], user), &encryption.owner_key), encryption.file_key);
+        
+        // Printing was withheld
+        assert_eq!(encryption.permissions & PRINT_PERMISSIONS, 0);
+        let mut perms = [0u8; 16];
+        Aes256CbcDec::new(&encryption.file_key.into(), &[0u8; 16].into())
+            .decrypt_padded_b2b_mut::<NoPadding>(&encryption.perms, &mut perms)
+            .unwrap();
+        assert_eq!(&perms[..4], &encryption.permissions.to_le_bytes());
+        assert_eq!(&perms[8..12], b"Tadb");
+    }
+    
+    #[test]
+    fn test_strings_decrypt_with_file_key() {
+        let encryption = PdfEncryption::new(&protection()).unwrap();
+        let sealed = encryption.encrypt(b"TEST123").unwrap();
+        assert_eq!(sealed.len(), 32);
+        assert_ne!(encryption.encrypt(b"TEST123").unwrap(), sealed);
+        
+        let (iv, ciphertext) = sealed.split_at(16);
+        let iv: [u8; 16] = iv.try_into().unwrap();
+        let plain = Aes256CbcDec::new(&encryption.file_key.into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+            .unwrap();
+        assert_eq!(plain, b"TEST123");
+    }
+}
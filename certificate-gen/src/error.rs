@@ -2,6 +2,10 @@
 
 use thiserror::Error;
 
+use safe_erase_core::error::ErrorCode;
+use safe_erase_core::i18n::{LocalizedError, MessageCatalog};
+use safe_erase_core::retry::Recoverable;
+
 /// Result type alias for certificate operations
 pub type Result<T> = std::result::Result<T, CertificateError>;
 
@@ -110,6 +114,42 @@ pub enum CertificateError {
 }
 
 impl CertificateError {
+    /// Stable code identifying this kind of error (2000-2999)
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            CertificateError::CryptographicError(_) => ErrorCode::new(2001, "CERT_CRYPTOGRAPHIC_ERROR"),
+            CertificateError::KeyGenerationFailed(_) => ErrorCode::new(2002, "CERT_KEY_GENERATION_FAILED"),
+            CertificateError::SigningFailed(_) => ErrorCode::new(2003, "CERT_SIGNING_FAILED"),
+            CertificateError::SignatureVerificationFailed => ErrorCode::new(2004, "CERT_SIGNATURE_VERIFICATION_FAILED"),
+            CertificateError::InvalidCertificateFormat(_) => ErrorCode::new(2005, "CERT_INVALID_FORMAT"),
+            CertificateError::PdfGenerationFailed(_) => ErrorCode::new(2101, "CERT_PDF_GENERATION_FAILED"),
+            CertificateError::PdfTemplateError(_) => ErrorCode::new(2102, "CERT_PDF_TEMPLATE_ERROR"),
+            CertificateError::FontLoadingFailed(_) => ErrorCode::new(2103, "CERT_FONT_LOADING_FAILED"),
+            CertificateError::JsonSerializationFailed(_) => ErrorCode::new(2201, "CERT_JSON_SERIALIZATION_FAILED"),
+            CertificateError::JsonDeserializationFailed(_) => ErrorCode::new(2202, "CERT_JSON_DESERIALIZATION_FAILED"),
+            CertificateError::FileOperationFailed(_) => ErrorCode::new(2301, "CERT_FILE_OPERATION_FAILED"),
+            CertificateError::FileNotFound(_) => ErrorCode::new(2302, "CERT_FILE_NOT_FOUND"),
+            CertificateError::PermissionDenied(_) => ErrorCode::new(2303, "CERT_PERMISSION_DENIED"),
+            CertificateError::InvalidFileFormat(_) => ErrorCode::new(2304, "CERT_INVALID_FILE_FORMAT"),
+            CertificateError::TemplateNotFound(_) => ErrorCode::new(2401, "CERT_TEMPLATE_NOT_FOUND"),
+            CertificateError::TemplateParsingFailed(_) => ErrorCode::new(2402, "CERT_TEMPLATE_PARSING_FAILED"),
+            CertificateError::TemplateRenderingFailed(_) => ErrorCode::new(2403, "CERT_TEMPLATE_RENDERING_FAILED"),
+            CertificateError::QrCodeGenerationFailed(_) => ErrorCode::new(2501, "CERT_QR_CODE_GENERATION_FAILED"),
+            CertificateError::QrCodeDataTooLarge(_) => ErrorCode::new(2502, "CERT_QR_CODE_DATA_TOO_LARGE"),
+            CertificateError::CertificateValidationFailed(_) => ErrorCode::new(2601, "CERT_VALIDATION_FAILED"),
+            CertificateError::InvalidCertificateData(_) => ErrorCode::new(2602, "CERT_INVALID_DATA"),
+            CertificateError::MissingRequiredField(_) => ErrorCode::new(2603, "CERT_MISSING_REQUIRED_FIELD"),
+            CertificateError::InvalidTimestamp(_) => ErrorCode::new(2604, "CERT_INVALID_TIMESTAMP"),
+            CertificateError::NetworkError(_) => ErrorCode::new(2701, "CERT_NETWORK_ERROR"),
+            CertificateError::VerificationServiceUnavailable => ErrorCode::new(2702, "CERT_VERIFICATION_SERVICE_UNAVAILABLE"),
+            CertificateError::CertificateNotFoundInDatabase => ErrorCode::new(2703, "CERT_NOT_FOUND_IN_DATABASE"),
+            CertificateError::InvalidConfiguration(_) => ErrorCode::new(2801, "CERT_INVALID_CONFIGURATION"),
+            CertificateError::MissingConfiguration(_) => ErrorCode::new(2802, "CERT_MISSING_CONFIGURATION"),
+            CertificateError::Internal(_) => ErrorCode::new(2901, "CERT_INTERNAL"),
+            CertificateError::NotSupported(_) => ErrorCode::new(2902, "CERT_NOT_SUPPORTED"),
+        }
+    }
+    
     /// Check if the error is recoverable
     pub fn is_recoverable(&self) -> bool {
         match self {
@@ -218,6 +258,96 @@ impl CertificateError {
     }
 }
 
+impl Recoverable for CertificateError {
+    fn is_recoverable(&self) -> bool {
+        CertificateError::is_recoverable(self)
+    }
+}
+
+impl LocalizedError for CertificateError {
+    fn message_id(&self) -> &'static str {
+        match self {
+            CertificateError::CryptographicError(_) => "certificate-error-cryptographic",
+            CertificateError::KeyGenerationFailed(_) => "certificate-error-key-generation-failed",
+            CertificateError::SigningFailed(_) => "certificate-error-signing-failed",
+            CertificateError::SignatureVerificationFailed => "certificate-error-signature-verification-failed",
+            CertificateError::InvalidCertificateFormat(_) => "certificate-error-invalid-format",
+            CertificateError::PdfGenerationFailed(_) => "certificate-error-pdf-generation-failed",
+            CertificateError::PdfTemplateError(_) => "certificate-error-pdf-template",
+            CertificateError::FontLoadingFailed(_) => "certificate-error-font-loading-failed",
+            CertificateError::JsonSerializationFailed(_) => "certificate-error-json-serialization-failed",
+            CertificateError::JsonDeserializationFailed(_) => "certificate-error-json-deserialization-failed",
+            CertificateError::FileOperationFailed(_) => "certificate-error-file-operation-failed",
+            CertificateError::FileNotFound(_) => "certificate-error-file-not-found",
+            CertificateError::PermissionDenied(_) => "certificate-error-permission-denied",
+            CertificateError::InvalidFileFormat(_) => "certificate-error-invalid-file-format",
+            CertificateError::TemplateNotFound(_) => "certificate-error-template-not-found",
+            CertificateError::TemplateParsingFailed(_) => "certificate-error-template-parsing-failed",
+            CertificateError::TemplateRenderingFailed(_) => "certificate-error-template-rendering-failed",
+            CertificateError::QrCodeGenerationFailed(_) => "certificate-error-qr-code-generation-failed",
+            CertificateError::QrCodeDataTooLarge(_) => "certificate-error-qr-code-data-too-large",
+            CertificateError::CertificateValidationFailed(_) => "certificate-error-validation-failed",
+            CertificateError::InvalidCertificateData(_) => "certificate-error-invalid-data",
+            CertificateError::MissingRequiredField(_) => "certificate-error-missing-required-field",
+            CertificateError::InvalidTimestamp(_) => "certificate-error-invalid-timestamp",
+            CertificateError::NetworkError(_) => "certificate-error-network",
+            CertificateError::VerificationServiceUnavailable => "certificate-error-verification-service-unavailable",
+            CertificateError::CertificateNotFoundInDatabase => "certificate-error-not-found-in-database",
+            CertificateError::InvalidConfiguration(_) => "certificate-error-invalid-configuration",
+            CertificateError::MissingConfiguration(_) => "certificate-error-missing-configuration",
+            CertificateError::Internal(_) => "certificate-error-internal",
+            CertificateError::NotSupported(_) => "certificate-error-not-supported",
+        }
+    }
+    
+    fn message_args(&self) -> Vec<(&'static str, String)> {
+        match self {
+            CertificateError::FileNotFound(file) => vec![("file", file.clone())],
+            CertificateError::PermissionDenied(path) => vec![("path", path.clone())],
+            CertificateError::TemplateNotFound(template) => vec![("template", template.clone())],
+            CertificateError::InvalidCertificateData(reason) => vec![("reason", reason.clone())],
+            CertificateError::SignatureVerificationFailed
+            | CertificateError::VerificationServiceUnavailable
+            | CertificateError::CertificateNotFoundInDatabase => Vec::new(),
+            CertificateError::CryptographicError(detail)
+            | CertificateError::KeyGenerationFailed(detail)
+            | CertificateError::SigningFailed(detail)
+            | CertificateError::InvalidCertificateFormat(detail)
+            | CertificateError::PdfGenerationFailed(detail)
+            | CertificateError::PdfTemplateError(detail)
+            | CertificateError::FontLoadingFailed(detail)
+            | CertificateError::JsonSerializationFailed(detail)
+            | CertificateError::JsonDeserializationFailed(detail)
+            | CertificateError::FileOperationFailed(detail)
+            | CertificateError::InvalidFileFormat(detail)
+            | CertificateError::TemplateParsingFailed(detail)
+            | CertificateError::TemplateRenderingFailed(detail)
+            | CertificateError::QrCodeGenerationFailed(detail)
+            | CertificateError::QrCodeDataTooLarge(detail)
+            | CertificateError::CertificateValidationFailed(detail)
+            | CertificateError::MissingRequiredField(detail)
+            | CertificateError::InvalidTimestamp(detail)
+            | CertificateError::NetworkError(detail)
+            | CertificateError::InvalidConfiguration(detail)
+            | CertificateError::MissingConfiguration(detail)
+            | CertificateError::Internal(detail)
+            | CertificateError::NotSupported(detail) => vec![("detail", detail.clone())],
+        }
+    }
+    
+    fn canonical_message(&self) -> String {
+        self.user_message()
+    }
+}
+
+/// Add the certificate error translations to a message catalog
+pub fn add_messages(catalog: &mut MessageCatalog) -> safe_erase_core::Result<()> {
+    catalog.add_resource("en-US", include_str!("../locales/en-US/certificates.ftl"))?;
+    catalog.add_resource("de", include_str!("../locales/de/certificates.ftl"))?;
+    catalog.add_resource("fr", include_str!("../locales/fr/certificates.ftl"))?;
+    Ok(())
+}
+
 /// Error severity levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorSeverity {
@@ -293,6 +423,7 @@ impl From<serde_json::Error> for CertificateError {
     }
 }
 
+#[cfg(feature = "openssl")]
 impl From<openssl::error::ErrorStack> for CertificateError {
     fn from(err: openssl::error::ErrorStack) -> Self {
         CertificateError::CryptographicError(err.to_string())
@@ -332,6 +463,30 @@ mod tests {
         assert!(message.contains("not found"));
     }
     
+    #[test]
+    fn test_localized_messages() {
+        let mut catalog = MessageCatalog::builtin().unwrap();
+        add_messages(&mut catalog).unwrap();
+        
+        let error = CertificateError::TemplateNotFound("audit".to_string());
+        assert_eq!(error.localized_message(&catalog, "en-US"), error.user_message());
+        assert!(error.localized_message(&catalog, "fr-CA").contains("« audit »"));
+        assert_eq!(
+            CertificateError::SignatureVerificationFailed.localized_message(&catalog, "en-GB"),
+            CertificateError::SignatureVerificationFailed.user_message()
+        );
+    }
+    
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(CertificateError::SignatureVerificationFailed.code().number, 2004);
+        assert_eq!(CertificateError::FileNotFound("x".to_string()).code().name, "CERT_FILE_NOT_FOUND");
+        assert_ne!(
+            CertificateError::NetworkError("x".to_string()).code(),
+            CertificateError::InvalidConfiguration("x".to_string()).code()
+        );
+    }
+    
     #[test]
     fn test_error_conversion() {
         let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
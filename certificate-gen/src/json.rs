@@ -0,0 +1,93 @@
+//! JSON certificate output
+//!
+//! The JSON file is the signed certificate itself: it is what
+//! [`CertificateVerifier::verify_certificate_file`](crate::CertificateVerifier::verify_certificate_file)
+//! reads back and checks.
+
+use std::path::Path;
+
+use crate::certificate::SignedCertificate;
+use crate::error::{CertificateError, Result};
+
+/// Writes signed certificates as JSON
+#[derive(Debug, Default)]
+pub struct JsonGenerator;
+
+impl JsonGenerator {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+    
+    /// Signed certificate as pretty-printed JSON
+    pub fn render(&self, certificate: &SignedCertificate) -> Result<String> {
+        serde_json::to_string_pretty(certificate)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))
+    }
+    
+    /// Write the signed certificate to `path`
+    pub async fn generate_certificate(&self, certificate: &SignedCertificate, path: &Path) -> Result<()> {
+        let json = self.render(certificate)?;
+        std::fs::write(path, json)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::CertificateSigner;
+    use crate::CertificateVerifier;
+    
+    fn certificate() -> WipeCertificate {
+        WipeCertificate::new(CertificateData {
+            certificate_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "TEST123".to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        })
+    }
+    
+    #[tokio::test]
+    async fn test_written_certificate_verifies() {
+        let signer = CertificateSigner::new().unwrap();
+        let signed = signer.sign_certificate(&certificate()).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("certificate.json");
+        
+        JsonGenerator::new().unwrap().generate_certificate(&signed, &path).await.unwrap();
+        
+        let keys = dir.path().join("keys");
+        std::fs::create_dir(&keys).unwrap();
+        signer.export_keys(dir.path().join("private.pem"), keys.join("signer.pem")).unwrap();
+        let mut verifier = CertificateVerifier::new().unwrap();
+        verifier.load_trusted_keys(&keys).unwrap();
+        assert!(verifier.verify_certificate_file(&path).await.unwrap());
+    }
+}
@@ -0,0 +1,299 @@
+//! Verification keys published at a JWKS endpoint
+//!
+//! Organizations with many auditors publish their signing keys once as a
+//! JSON Web Key Set over HTTPS instead of copying PEM files to every
+//! verifier. A [`JwksSource`] fetches the set into a [`CertificateVerifier`]
+//! and refreshes it on an interval, dropping keys the issuer has withdrawn.
+//! The endpoint can be pinned to a private CA and the accepted keys to a
+//! list of key IDs, so a compromised web server cannot introduce new signing
+//! keys. The last good response is cached on disk for offline starts.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{CertificateSigner, CertificateVerifier};
+use crate::error::{CertificateError, Result};
+use crate::trust_store;
+
+/// Where and how to fetch a JWKS
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwksConfig {
+    /// HTTPS URL of the key set
+    pub url: String,
+    /// Seconds between refreshes
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    /// Only accept keys with these key IDs; empty accepts every key in the set
+    #[serde(default)]
+    pub pinned_key_ids: Vec<String>,
+    /// PEM CA certificate that must have issued the server certificate,
+    /// replacing the system roots
+    #[serde(default)]
+    pub ca_certificate: Option<PathBuf>,
+    /// File holding the last good response
+    #[serde(default)]
+    pub cache_path: Option<PathBuf>,
+    /// Oldest cached response accepted when the endpoint is unreachable
+    #[serde(default = "default_max_cache_age_secs")]
+    pub max_cache_age_secs: u64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Keeps a verifier's keys in sync with a JWKS endpoint
+#[derive(Debug)]
+pub struct JwksSource {
+    config: JwksConfig,
+    client: reqwest::Client,
+    etag: Option<String>,
+    refreshed_at: Option<DateTime<Utc>>,
+    /// Key IDs this source added to the verifier
+    key_ids: Vec<String>,
+}
+
+impl JwksConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+            pinned_key_ids: Vec::new(),
+            ca_certificate: None,
+            cache_path: None,
+            max_cache_age_secs: default_max_cache_age_secs(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+    
+    pub fn validate(&self) -> Result<()> {
+        if !self.url.starts_with("https://") {
+            return Err(CertificateError::InvalidConfiguration(format!(
+                "JWKS URL must use https: {}", self.url
+            )));
+        }
+        if self.refresh_interval_secs == 0 || self.timeout_secs == 0 {
+            return Err(CertificateError::InvalidConfiguration(
+                "JWKS refresh interval and timeout must be positive".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl JwksSource {
+    pub fn new(config: JwksConfig) -> Result<Self> {
+        config.validate()?;
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .https_only(true);
+        if let Some(ca_path) = &config.ca_certificate {
+            let pem = std::fs::read(ca_path)
+                .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", ca_path.display(), e)))?;
+            let ca = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| CertificateError::InvalidConfiguration(format!("{}: {}", ca_path.display(), e)))?;
+            builder = builder.tls_built_in_root_certs(false).add_root_certificate(ca);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| CertificateError::InvalidConfiguration(format!("JWKS client: {}", e)))?;
+        
+        Ok(Self {
+            config,
+            client,
+            etag: None,
+            refreshed_at: None,
+            key_ids: Vec::new(),
+        })
+    }
+    
+    pub fn config(&self) -> &JwksConfig {
+        &self.config
+    }
+    
+    /// When the keys were last confirmed current
+    pub fn refreshed_at(&self) -> Option<DateTime<Utc>> {
+        self.refreshed_at
+    }
+    
+    /// Whether the refresh interval has elapsed
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.refreshed_at.is_none_or(|at| {
+            now - at >= chrono::Duration::seconds(self.config.refresh_interval_secs as i64)
+        })
+    }
+    
+    /// Refresh when due; returns whether a refresh was attempted
+    pub async fn refresh_if_due(&mut self, verifier: &mut CertificateVerifier) -> Result<bool> {
+        if !self.is_due(Utc::now()) {
+            return Ok(false);
+        }
+        self.refresh(verifier).await?;
+        Ok(true)
+    }
+    
+    /// Fetch the key set and update the verifier; returns the number of keys
+    /// from this source. If the first fetch fails, a sufficiently recent
+    /// cached response is used instead.
+    pub async fn refresh(&mut self, verifier: &mut CertificateVerifier) -> Result<usize> {
+        match self.fetch().await {
+            Ok(Some((body, etag))) => {
+                let count = self.apply(verifier, &body)?;
+                self.etag = etag;
+                self.write_cache(&body);
+                self.refreshed_at = Some(Utc::now());
+                Ok(count)
+            }
+            Ok(None) => {
+                self.refreshed_at = Some(Utc::now());
+                Ok(self.key_ids.len())
+            }
+            Err(e) if self.refreshed_at.is_none() => match self.read_cache() {
+                Some(body) => self.apply(verifier, &body),
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Response body and ETag, or `None` when the server reports the set unchanged
+    async fn fetch(&self) -> Result<Option<(String, Option<String>)>> {
+        let mut request = self.client.get(&self.config.url).header(reqwest::header::ACCEPT, "application/json");
+        if let Some(etag) = &self.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| CertificateError::FileOperationFailed(format!("JWKS {}: {}", self.config.url, e)))?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(CertificateError::FileOperationFailed(format!(
+                "JWKS {} returned {}", self.config.url, response.status()
+            )));
+        }
+        
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        response
+            .text()
+            .await
+            .map(|body| Some((body, etag)))
+            .map_err(|e| CertificateError::FileOperationFailed(format!("JWKS {}: {}", self.config.url, e)))
+    }
+    
+    /// Replace this source's keys in the verifier with those in `body`
+    fn apply(&mut self, verifier: &mut CertificateVerifier, body: &str) -> Result<usize> {
+        let mut accepted = Vec::new();
+        for (label, public_key) in trust_store::parse_jwks(body)? {
+            let key_id = CertificateSigner::generate_key_id(&public_key)?;
+            if self.config.pinned_key_ids.is_empty() || self.config.pinned_key_ids.contains(&key_id) {
+                accepted.push((label, public_key));
+            }
+        }
+        if accepted.is_empty() {
+            return Err(CertificateError::InvalidFileFormat(format!(
+                "JWKS {} contains no acceptable RSA keys", self.config.url
+            )));
+        }
+        
+        for key_id in self.key_ids.drain(..) {
+            verifier.remove_key(&key_id);
+        }
+        for (label, public_key) in accepted {
+            let key_id = verifier.add_public_key(&label, public_key, None, None)?;
+            self.key_ids.push(key_id);
+        }
+        Ok(self.key_ids.len())
+    }
+    
+    /// Best effort; a missing cache only matters on the next offline start
+    fn write_cache(&self, body: &str) {
+        if let Some(path) = &self.config.cache_path {
+            let _ = std::fs::write(path, body);
+        }
+    }
+    
+    fn read_cache(&self) -> Option<String> {
+        let path = self.config.cache_path.as_ref()?;
+        let age = std::fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+        if age > Duration::from_secs(self.config.max_cache_age_secs) {
+            return None;
+        }
+        std::fs::read_to_string(path).ok()
+    }
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    3600
+}
+
+fn default_max_cache_age_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use crate::keys::PublicKey;
+    
+    fn jwks(signers: &[&CertificateSigner]) -> String {
+        let keys: Vec<serde_json::Value> = signers
+            .iter()
+            .map(|signer| {
+                let pem = signer.get_key_info().unwrap().public_key_pem;
+                let (n, e) = PublicKey::from_pem(&pem).unwrap().rsa_components().unwrap();
+                serde_json::json!({"kty": "RSA", "kid": signer.key_id(), "n": URL_SAFE_NO_PAD.encode(n), "e": URL_SAFE_NO_PAD.encode(e)})
+            })
+            .collect();
+        serde_json::json!({ "keys": keys }).to_string()
+    }
+    
+    #[test]
+    fn test_config_validation() {
+        assert!(JwksConfig::new("https://keys.example.com/jwks.json").validate().is_ok());
+        assert!(JwksConfig::new("http://keys.example.com/jwks.json").validate().is_err());
+        
+        let config: JwksConfig = serde_json::from_str(r#"{"url": "https://keys.example.com/jwks.json"}"#).unwrap();
+        assert_eq!(config.refresh_interval_secs, 3600);
+        let source = JwksSource::new(config).unwrap();
+        assert!(source.is_due(Utc::now()));
+    }
+    
+    #[test]
+    fn test_rotation_and_pinning() {
+        let (old, new, rogue) = (
+            CertificateSigner::new().unwrap(),
+            CertificateSigner::new().unwrap(),
+            CertificateSigner::new().unwrap(),
+        );
+        let mut config = JwksConfig::new("https://keys.example.com/jwks.json");
+        config.pinned_key_ids = vec![old.key_id().to_string(), new.key_id().to_string()];
+        let mut source = JwksSource::new(config).unwrap();
+        let mut verifier = CertificateVerifier::new().unwrap();
+        verifier.add_key("local", &rogue.get_key_info().unwrap().public_key_pem, None, None).unwrap();
+        
+        assert_eq!(source.apply(&mut verifier, &jwks(&[&old, &rogue])).unwrap(), 1);
+        assert!(verifier.is_trusted(old.key_id()));
+        
+        // The issuer rotated: the withdrawn key goes, keys from elsewhere stay
+        assert_eq!(source.apply(&mut verifier, &jwks(&[&new])).unwrap(), 1);
+        assert!(!verifier.is_trusted(old.key_id()));
+        assert!(verifier.is_trusted(new.key_id()));
+        assert!(verifier.is_trusted(rogue.key_id()));
+        
+        assert!(source.apply(&mut verifier, &jwks(&[&rogue])).is_err());
+        assert!(verifier.is_trusted(new.key_id()));
+    }
+}
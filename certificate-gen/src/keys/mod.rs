@@ -0,0 +1,80 @@
+//! Signing keys and the cryptographic backend behind them
+//!
+//! Keys are used through [`PrivateKey`] and [`PublicKey`], implemented either
+//! on OpenSSL (the default `openssl` feature) or on the pure-Rust RustCrypto
+//! crates (`rustcrypto`), which need no system library and so allow fully
+//! static musl builds. The output of both is the same: RSA signatures use
+//! PKCS#1 v1.5 padding, ECDSA signatures are DER-encoded, private keys are
+//! written as PKCS#8 and public keys as SubjectPublicKeyInfo, so key IDs do
+//! not change and certificates signed with one backend verify with the other.
+
+#[cfg(not(any(feature = "openssl", feature = "rustcrypto")))]
+compile_error!("a cryptographic backend is required: enable the `openssl` or `rustcrypto` feature");
+
+#[cfg(not(feature = "rustcrypto"))]
+mod openssl;
+#[cfg(not(feature = "rustcrypto"))]
+pub use self::openssl::{PrivateKey, PublicKey};
+
+#[cfg(feature = "rustcrypto")]
+mod rustcrypto;
+#[cfg(feature = "rustcrypto")]
+pub use self::rustcrypto::{PrivateKey, PublicKey};
+
+/// Name of the backend compiled in; `rustcrypto` wins when both features are enabled
+pub const BACKEND: &str = if cfg!(feature = "rustcrypto") { "rustcrypto" } else { "openssl" };
+
+/// Hash function a signature is computed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+/// Type and size of a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyType {
+    Rsa { bits: usize },
+    EcP256,
+    EcP384,
+    /// Any other key, by the backend's name for it
+    Other(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SignatureAlgorithm;
+    
+    #[test]
+    fn test_pem_roundtrip_and_signatures() {
+        for algorithm in [SignatureAlgorithm::RSA2048SHA256, SignatureAlgorithm::ECDSAP384SHA384] {
+            let private_key = PrivateKey::generate(algorithm).unwrap();
+            let private_key = PrivateKey::from_pem(&private_key.to_pkcs8_pem().unwrap()).unwrap();
+            let public_key = private_key.public_key().unwrap();
+            let reloaded = PublicKey::from_pem(&public_key.to_pem().unwrap()).unwrap();
+            assert_eq!(reloaded.to_der().unwrap(), public_key.to_der().unwrap());
+            assert_eq!(SignatureAlgorithm::for_key(&reloaded).unwrap(), algorithm);
+            
+            let hash = algorithm.digest();
+            let signature = private_key.sign(hash, b"data").unwrap();
+            assert!(reloaded.verify(hash, b"data", &signature).unwrap());
+            assert!(!reloaded.verify(hash, b"other", &signature).unwrap());
+        }
+    }
+    
+    #[test]
+    fn test_rsa_signature_is_deterministic_pkcs1() {
+        let private_key = PrivateKey::generate(SignatureAlgorithm::RSA2048SHA256).unwrap();
+        let public_key = private_key.public_key().unwrap();
+        let signature = private_key.sign(HashAlgorithm::Sha256, b"data").unwrap();
+        assert_eq!(signature.len(), 256);
+        assert_eq!(signature, private_key.sign(HashAlgorithm::Sha256, b"data").unwrap());
+        
+        let (n, e) = public_key.rsa_components().unwrap();
+        assert_eq!(e, [1, 0, 1]);
+        let rebuilt = PublicKey::from_rsa_components(&n, &e).unwrap();
+        assert_eq!(rebuilt.to_der().unwrap(), public_key.to_der().unwrap());
+        assert_eq!(rebuilt.key_type(), KeyType::Rsa { bits: 2048 });
+    }
+}
@@ -0,0 +1,132 @@
+//! Keys backed by OpenSSL
+
+use ::openssl::bn::BigNum;
+use ::openssl::ec::{EcGroup, EcKey};
+use ::openssl::hash::MessageDigest;
+use ::openssl::nid::Nid;
+use ::openssl::pkey::{Id, PKey, Private, Public};
+use ::openssl::rsa::Rsa;
+use ::openssl::sign::{Signer, Verifier};
+
+use super::{HashAlgorithm, KeyType};
+use crate::crypto::SignatureAlgorithm;
+use crate::error::{CertificateError, Result};
+
+#[derive(Debug, Clone)]
+pub struct PrivateKey(PKey<Private>);
+
+#[derive(Debug, Clone)]
+pub struct PublicKey(PKey<Public>);
+
+fn digest(hash: HashAlgorithm) -> MessageDigest {
+    match hash {
+        HashAlgorithm::Sha256 => MessageDigest::sha256(),
+        HashAlgorithm::Sha384 => MessageDigest::sha384(),
+    }
+}
+
+fn crypto_error(e: impl std::fmt::Display) -> CertificateError {
+    CertificateError::CryptographicError(e.to_string())
+}
+
+impl PrivateKey {
+    /// Generate a key for `algorithm`
+    pub fn generate(algorithm: SignatureAlgorithm) -> Result<Self> {
+        let failed = |e: ::openssl::error::ErrorStack| CertificateError::KeyGenerationFailed(e.to_string());
+        let key = match algorithm {
+            SignatureAlgorithm::RSA2048SHA256 => PKey::from_rsa(Rsa::generate(2048).map_err(failed)?),
+            SignatureAlgorithm::RSA4096SHA256 => PKey::from_rsa(Rsa::generate(4096).map_err(failed)?),
+            SignatureAlgorithm::ECDSAP256SHA256 | SignatureAlgorithm::ECDSAP384SHA384 => {
+                let curve = if algorithm == SignatureAlgorithm::ECDSAP256SHA256 {
+                    Nid::X9_62_PRIME256V1
+                } else {
+                    Nid::SECP384R1
+                };
+                let group = EcGroup::from_curve_name(curve).map_err(failed)?;
+                PKey::from_ec_key(EcKey::generate(&group).map_err(failed)?)
+            }
+        };
+        key.map(Self).map_err(failed)
+    }
+    
+    /// Parse a PKCS#8 or traditional PEM private key
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        PKey::private_key_from_pem(pem.as_bytes()).map(Self).map_err(crypto_error)
+    }
+    
+    pub fn to_pkcs8_pem(&self) -> Result<String> {
+        let pem = self.0.private_key_to_pem_pkcs8().map_err(crypto_error)?;
+        String::from_utf8(pem).map_err(crypto_error)
+    }
+    
+    pub fn public_key(&self) -> Result<PublicKey> {
+        let der = self.0.public_key_to_der().map_err(crypto_error)?;
+        PKey::public_key_from_der(&der).map(PublicKey).map_err(crypto_error)
+    }
+    
+    pub fn bits(&self) -> usize {
+        self.0.bits() as usize
+    }
+    
+    /// PKCS#1 v1.5 (RSA) or DER-encoded ECDSA signature over `data`
+    pub fn sign(&self, hash: HashAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+        let failed = |e: ::openssl::error::ErrorStack| CertificateError::SigningFailed(e.to_string());
+        let mut signer = Signer::new(digest(hash), &self.0).map_err(failed)?;
+        signer.update(data).map_err(failed)?;
+        signer.sign_to_vec().map_err(failed)
+    }
+}
+
+impl PublicKey {
+    /// Parse a SubjectPublicKeyInfo PEM public key
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        PKey::public_key_from_pem(pem.as_bytes()).map(Self).map_err(crypto_error)
+    }
+    
+    /// RSA key from its big-endian modulus and exponent
+    pub fn from_rsa_components(n: &[u8], e: &[u8]) -> Result<Self> {
+        let n = BigNum::from_slice(n).map_err(crypto_error)?;
+        let e = BigNum::from_slice(e).map_err(crypto_error)?;
+        let rsa = Rsa::from_public_components(n, e).map_err(crypto_error)?;
+        PKey::from_rsa(rsa).map(Self).map_err(crypto_error)
+    }
+    
+    pub fn to_pem(&self) -> Result<String> {
+        let pem = self.0.public_key_to_pem().map_err(crypto_error)?;
+        String::from_utf8(pem).map_err(crypto_error)
+    }
+    
+    /// DER-encoded SubjectPublicKeyInfo
+    pub fn to_der(&self) -> Result<Vec<u8>> {
+        self.0.public_key_to_der().map_err(crypto_error)
+    }
+    
+    pub fn bits(&self) -> usize {
+        self.0.bits() as usize
+    }
+    
+    pub fn key_type(&self) -> KeyType {
+        let curve = self.0.ec_key().ok().and_then(|ec| ec.group().curve_name());
+        match self.0.id() {
+            Id::RSA => KeyType::Rsa { bits: self.bits() },
+            Id::EC if curve == Some(Nid::X9_62_PRIME256V1) => KeyType::EcP256,
+            Id::EC if curve == Some(Nid::SECP384R1) => KeyType::EcP384,
+            id => KeyType::Other(format!("{:?} with {} bits", id, self.bits())),
+        }
+    }
+    
+    /// Big-endian modulus and exponent of an RSA key
+    pub fn rsa_components(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let rsa = self.0.rsa().ok()?;
+        Some((rsa.n().to_vec(), rsa.e().to_vec()))
+    }
+    
+    pub fn verify(&self, hash: HashAlgorithm, data: &[u8], signature: &[u8]) -> Result<bool> {
+        let mut verifier = Verifier::new(digest(hash), &self.0)
+            .map_err(|_| CertificateError::SignatureVerificationFailed)?;
+        verifier.update(data)
+            .map_err(|_| CertificateError::SignatureVerificationFailed)?;
+        verifier.verify(signature)
+            .map_err(|_| CertificateError::SignatureVerificationFailed)
+    }
+}
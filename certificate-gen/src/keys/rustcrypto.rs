@@ -0,0 +1,206 @@
+//! Keys backed by the pure-Rust RustCrypto crates
+
+use rand::rngs::OsRng;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use p256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use sha2::{Digest, Sha256, Sha384};
+
+use super::{HashAlgorithm, KeyType};
+use crate::crypto::SignatureAlgorithm;
+use crate::error::{CertificateError, Result};
+
+#[derive(Clone)]
+pub enum PrivateKey {
+    Rsa(RsaPrivateKey),
+    P256(p256::ecdsa::SigningKey),
+    P384(p384::ecdsa::SigningKey),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    P256(p256::ecdsa::VerifyingKey),
+    P384(p384::ecdsa::VerifyingKey),
+}
+
+fn crypto_error(e: impl std::fmt::Display) -> CertificateError {
+    CertificateError::CryptographicError(e.to_string())
+}
+
+/// Hash of `data`, with the PKCS#1 v1.5 scheme naming it for RSA
+fn prehash(hash: HashAlgorithm, data: &[u8]) -> (Vec<u8>, Pkcs1v15Sign) {
+    match hash {
+        HashAlgorithm::Sha256 => (Sha256::digest(data).to_vec(), Pkcs1v15Sign::new::<Sha256>()),
+        HashAlgorithm::Sha384 => (Sha384::digest(data).to_vec(), Pkcs1v15Sign::new::<Sha384>()),
+    }
+}
+
+impl PrivateKey {
+    /// Generate a key for `algorithm`
+    pub fn generate(algorithm: SignatureAlgorithm) -> Result<Self> {
+        let rsa = |bits| {
+            RsaPrivateKey::new(&mut OsRng, bits)
+                .map(PrivateKey::Rsa)
+                .map_err(|e| CertificateError::KeyGenerationFailed(e.to_string()))
+        };
+        match algorithm {
+            SignatureAlgorithm::RSA2048SHA256 => rsa(2048),
+            SignatureAlgorithm::RSA4096SHA256 => rsa(4096),
+            SignatureAlgorithm::ECDSAP256SHA256 => Ok(Self::P256(p256::ecdsa::SigningKey::random(&mut OsRng))),
+            SignatureAlgorithm::ECDSAP384SHA384 => Ok(Self::P384(p384::ecdsa::SigningKey::random(&mut OsRng))),
+        }
+    }
+    
+    /// Parse a PKCS#8 private key, or a PKCS#1 RSA or SEC1 EC key as OpenSSL used to write them
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem)) {
+            return Ok(Self::Rsa(key));
+        }
+        if let Ok(key) = p256::SecretKey::from_pkcs8_pem(pem).or_else(|_| p256::SecretKey::from_sec1_pem(pem)) {
+            return Ok(Self::P256(key.into()));
+        }
+        p384::SecretKey::from_pkcs8_pem(pem)
+            .or_else(|_| p384::SecretKey::from_sec1_pem(pem))
+            .map(|key| Self::P384(key.into()))
+            .map_err(|_| CertificateError::CryptographicError(
+                "Not an RSA, P-256 or P-384 private key in PKCS#8, PKCS#1 or SEC1 PEM".to_string()
+            ))
+    }
+    
+    pub fn to_pkcs8_pem(&self) -> Result<String> {
+        let pem = match self {
+            PrivateKey::Rsa(key) => key.to_pkcs8_pem(LineEnding::LF),
+            PrivateKey::P256(key) => p256::SecretKey::from(key).to_pkcs8_pem(LineEnding::LF),
+            PrivateKey::P384(key) => p384::SecretKey::from(key).to_pkcs8_pem(LineEnding::LF),
+        };
+        pem.map(|pem| pem.to_string()).map_err(crypto_error)
+    }
+    
+    pub fn public_key(&self) -> Result<PublicKey> {
+        Ok(match self {
+            PrivateKey::Rsa(key) => PublicKey::Rsa(key.to_public_key()),
+            PrivateKey::P256(key) => PublicKey::P256(*key.verifying_key()),
+            PrivateKey::P384(key) => PublicKey::P384(*key.verifying_key()),
+        })
+    }
+    
+    pub fn bits(&self) -> usize {
+        match self {
+            PrivateKey::Rsa(key) => key.size() * 8,
+            PrivateKey::P256(_) => 256,
+            PrivateKey::P384(_) => 384,
+        }
+    }
+    
+    /// PKCS#1 v1.5 (RSA) or DER-encoded ECDSA signature over `data`
+    pub fn sign(&self, hash: HashAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+        let failed = |e: &dyn std::fmt::Display| CertificateError::SigningFailed(e.to_string());
+        let (digest, scheme) = prehash(hash, data);
+        match self {
+            PrivateKey::Rsa(key) => key.sign(scheme, &digest).map_err(|e| failed(&e)),
+            PrivateKey::P256(key) => {
+                let signature: p256::ecdsa::Signature = key.sign_prehash(&digest).map_err(|e| failed(&e))?;
+                Ok(signature.to_der().as_bytes().to_vec())
+            }
+            PrivateKey::P384(key) => {
+                let signature: p384::ecdsa::Signature = key.sign_prehash(&digest).map_err(|e| failed(&e))?;
+                Ok(signature.to_der().as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        write!(f, "PrivateKey({} bits)", self.bits())
+    }
+}
+
+impl PublicKey {
+    /// Parse a SubjectPublicKeyInfo PEM public key
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        if let Ok(key) = RsaPublicKey::from_public_key_pem(pem) {
+            return Ok(Self::Rsa(key));
+        }
+        if let Ok(key) = p256::ecdsa::VerifyingKey::from_public_key_pem(pem) {
+            return Ok(Self::P256(key));
+        }
+        p384::ecdsa::VerifyingKey::from_public_key_pem(pem)
+            .map(Self::P384)
+            .map_err(|_| CertificateError::CryptographicError(
+                "Not an RSA, P-256 or P-384 public key in SubjectPublicKeyInfo PEM".to_string()
+            ))
+    }
+    
+    /// RSA key from its big-endian modulus and exponent
+    pub fn from_rsa_components(n: &[u8], e: &[u8]) -> Result<Self> {
+        RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))
+            .map(Self::Rsa)
+            .map_err(crypto_error)
+    }
+    
+    pub fn to_pem(&self) -> Result<String> {
+        let pem = match self {
+            PublicKey::Rsa(key) => key.to_public_key_pem(LineEnding::LF),
+            PublicKey::P256(key) => key.to_public_key_pem(LineEnding::LF),
+            PublicKey::P384(key) => key.to_public_key_pem(LineEnding::LF),
+        };
+        pem.map_err(crypto_error)
+    }
+    
+    /// DER-encoded SubjectPublicKeyInfo
+    pub fn to_der(&self) -> Result<Vec<u8>> {
+        let der = match self {
+            PublicKey::Rsa(key) => key.to_public_key_der(),
+            PublicKey::P256(key) => key.to_public_key_der(),
+            PublicKey::P384(key) => key.to_public_key_der(),
+        };
+        der.map(|der| der.as_bytes().to_vec()).map_err(crypto_error)
+    }
+    
+    pub fn bits(&self) -> usize {
+        match self {
+            PublicKey::Rsa(key) => key.size() * 8,
+            PublicKey::P256(_) => 256,
+            PublicKey::P384(_) => 384,
+        }
+    }
+    
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            PublicKey::Rsa(_) => KeyType::Rsa { bits: self.bits() },
+            PublicKey::P256(_) => KeyType::EcP256,
+            PublicKey::P384(_) => KeyType::EcP384,
+        }
+    }
+    
+    /// Big-endian modulus and exponent of an RSA key
+    pub fn rsa_components(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        match self {
+            PublicKey::Rsa(key) => Some((key.n().to_bytes_be(), key.e().to_bytes_be())),
+            _ => None,
+        }
+    }
+    
+    /// Check a signature; malformed ECDSA signatures are an error as with OpenSSL
+    pub fn verify(&self, hash: HashAlgorithm, data: &[u8], signature: &[u8]) -> Result<bool> {
+        let (digest, scheme) = prehash(hash, data);
+        match self {
+            PublicKey::Rsa(key) => Ok(key.verify(scheme, &digest, signature).is_ok()),
+            PublicKey::P256(key) => {
+                let signature = p256::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| CertificateError::SignatureVerificationFailed)?;
+                Ok(key.verify_prehash(&digest, &signature).is_ok())
+            }
+            PublicKey::P384(key) => {
+                let signature = p384::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| CertificateError::SignatureVerificationFailed)?;
+                Ok(key.verify_prehash(&digest, &signature).is_ok())
+            }
+        }
+    }
+}
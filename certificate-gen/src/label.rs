@@ -0,0 +1,707 @@
+//! Printable drive labels
+//!
+//! Wiped drives leave the station by the boxful, and a certificate is only
+//! useful if whoever holds a drive can find it. A label sized for continuous
+//! tape label printers (62 mm by default) carries the device serial, the
+//! certificate ID and the wipe date in plain text, plus either a Code 128
+//! barcode of the certificate ID for handheld scanners or a QR code of the
+//! verification URL for phones. Labels are laid out once as marks in
+//! millimetres and written as a single-page PDF or as a PNG at the printer's
+//! resolution, or sent straight to a label printer as ZPL or EPL commands.
+
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::certificate::SignedCertificate;
+use crate::error::{CertificateError, Result};
+
+const MM_PER_INCH: f32 = 25.4;
+
+/// PDF points per millimetre
+const PT_PER_MM: f32 = 72.0 / MM_PER_INCH;
+
+/// Cap height of Helvetica as a fraction of the font size
+const HELVETICA_CAP_HEIGHT: f32 = 0.718;
+
+const MARGIN_MM: f32 = 2.0;
+
+/// Narrowest Code 128 bar most handheld scanners still resolve
+const MIN_MODULE_MM: f32 = 0.125;
+
+/// Quiet zone required on each side of a Code 128 barcode, in modules
+const CODE128_QUIET_ZONE: usize = 10;
+
+/// Quiet zone around a QR code, in modules
+const QR_QUIET_ZONE: usize = 4;
+
+/// Bar and space widths of the Code 128 symbols, indexed by symbol value
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: usize = 106;
+
+/// Output file format of a label
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelFormat {
+    #[default]
+    Pdf,
+    Png,
+}
+
+/// Command language of thermal label printers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrinterLanguage {
+    /// Zebra Programming Language
+    Zpl,
+    /// Eltron Programming Language, spoken by older Zebra and many budget printers
+    Epl,
+}
+
+/// Machine-readable symbol printed on a label
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelSymbol {
+    /// Code 128 barcode of the certificate ID
+    #[default]
+    Code128,
+    /// QR code of the verification URL
+    Qr,
+}
+
+/// Label size and output settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelOptions {
+    pub format: LabelFormat,
+    pub symbol: LabelSymbol,
+    pub width_mm: f32,
+    pub height_mm: f32,
+    /// Printer resolution used for PNG output
+    pub dpi: u32,
+}
+
+/// Text and symbol data printed on a label
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelContent {
+    pub serial: String,
+    pub certificate_id: String,
+    /// Wipe completion date, `YYYY-MM-DD`
+    pub date: String,
+    pub symbol: LabelSymbol,
+    /// Data encoded in the barcode or QR code
+    pub symbol_data: String,
+}
+
+/// Something drawn on a label; coordinates are millimetres from the top-left corner
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mark {
+    /// Filled black rectangle
+    Rect { x: f32, y: f32, width: f32, height: f32 },
+    /// Line of text whose capitals are `size` millimetres high
+    Text { x: f32, y: f32, size: f32, text: String },
+}
+
+impl Default for LabelOptions {
+    fn default() -> Self {
+        Self {
+            format: LabelFormat::Pdf,
+            symbol: LabelSymbol::Code128,
+            width_mm: 62.0,
+            height_mm: 29.0,
+            dpi: 300,
+        }
+    }
+}
+
+impl LabelOptions {
+    pub fn validate(&self) -> Result<()> {
+        if !(20.0..=200.0).contains(&self.width_mm) || !(15.0..=200.0).contains(&self.height_mm) {
+            return Err(CertificateError::InvalidConfiguration(format!(
+                "Label size {}x{} mm is outside 20-200 x 15-200 mm", self.width_mm, self.height_mm
+            )));
+        }
+        if !(150..=1200).contains(&self.dpi) {
+            return Err(CertificateError::InvalidConfiguration(format!(
+                "Label resolution must be between 150 and 1200 dpi, got {}", self.dpi
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl LabelFormat {
+    /// Extension of label files, distinct from the certificate's own
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LabelFormat::Pdf => "label.pdf",
+            LabelFormat::Png => "label.png",
+        }
+    }
+    
+    /// MIME type of label documents, e.g. for IPP's `document-format`
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            LabelFormat::Pdf => "application/pdf",
+            LabelFormat::Png => "image/png",
+        }
+    }
+}
+
+impl LabelContent {
+    pub fn new(
+        serial: &str,
+        certificate_id: Uuid,
+        completed_at: DateTime<Utc>,
+        verification_url: &str,
+        symbol: LabelSymbol,
+    ) -> Self {
+        Self {
+            serial: serial.to_string(),
+            certificate_id: certificate_id.to_string().to_uppercase(),
+            date: completed_at.format("%Y-%m-%d").to_string(),
+            symbol,
+            symbol_data: match symbol {
+                // The hyphenless form keeps the barcode short; verifiers accept both
+                LabelSymbol::Code128 => certificate_id.simple().to_string().to_uppercase(),
+                LabelSymbol::Qr => verification_url.to_string(),
+            },
+        }
+    }
+    
+    pub fn from_certificate(signed: &SignedCertificate, verification_url: &str, symbol: LabelSymbol) -> Self {
+        let data = &signed.certificate.data;
+        let completed_at = data.wipe_info.completed_at.unwrap_or(data.generated_at);
+        Self::new(&data.device_info.serial, data.certificate_id, completed_at, verification_url, symbol)
+    }
+    
+    /// Lay the label out as marks
+    pub fn layout(&self, options: &LabelOptions) -> Result<Vec<Mark>> {
+        options.validate()?;
+        let (width, height) = (options.width_mm, options.height_mm);
+        let mut marks = Vec::new();
+        
+        match self.symbol {
+            LabelSymbol::Code128 => {
+                let text_width = width - 2.0 * MARGIN_MM;
+                let mut y = MARGIN_MM;
+                for (text, size) in [
+                    (format!("S/N {}", self.serial), 3.0),
+                    (format!("CERT {}", self.certificate_id), 1.8),
+                    (format!("WIPED {}", self.date), 2.2),
+                ] {
+                    y += text_line(&mut marks, MARGIN_MM, y, size, text, text_width) + 1.2;
+                }
+                
+                let modules = code128(&self.symbol_data)?;
+                let module = text_width / (modules.len() + 2 * CODE128_QUIET_ZONE) as f32;
+                let bar_height = height - MARGIN_MM - y;
+                if module < MIN_MODULE_MM || bar_height < 5.0 {
+                    return Err(CertificateError::InvalidConfiguration(format!(
+                        "A {}x{} mm label is too small for a Code 128 barcode; use a QR code", width, height
+                    )));
+                }
+                let left = MARGIN_MM + CODE128_QUIET_ZONE as f32 * module;
+                for (start, run) in dark_runs(&modules) {
+                    marks.push(Mark::Rect {
+                        x: left + start as f32 * module,
+                        y,
+                        width: run as f32 * module,
+                        height: bar_height,
+                    });
+                }
+            }
+            LabelSymbol::Qr => {
+                let code = qrcode::QrCode::new(self.symbol_data.as_bytes())
+                    .map_err(|e| CertificateError::InvalidCertificateData(format!("Label QR code: {}", e)))?;
+                let side = height - 2.0 * MARGIN_MM;
+                let module = side / (code.width() + 2 * QR_QUIET_ZONE) as f32;
+                let origin = MARGIN_MM + QR_QUIET_ZONE as f32 * module;
+                let colors = code.to_colors();
+                for (row, cells) in colors.chunks(code.width()).enumerate() {
+                    let dark: Vec<bool> = cells.iter().map(|color| *color == qrcode::Color::Dark).collect();
+                    for (start, run) in dark_runs(&dark) {
+                        marks.push(Mark::Rect {
+                            x: origin + start as f32 * module,
+                            y: origin + row as f32 * module,
+                            width: run as f32 * module,
+                            height: module,
+                        });
+                    }
+                }
+                
+                // Beside the square code the ID is split over two lines
+                let x = MARGIN_MM + side + 1.0;
+                let text_width = width - x - MARGIN_MM;
+                let (id_head, id_tail) = self.certificate_id.split_at(self.certificate_id.len().min(18));
+                let mut y = MARGIN_MM;
+                for (text, size) in [
+                    (format!("S/N {}", self.serial), 3.0),
+                    (id_head.to_string(), 1.8),
+                    (id_tail.to_string(), 1.8),
+                    (format!("WIPED {}", self.date), 2.2),
+                ] {
+                    y += text_line(&mut marks, x, y, size, text, text_width) + 1.2;
+                }
+            }
+        }
+        Ok(marks)
+    }
+}
+
+/// Push a line of text, shrunk to fit `available` millimetres; returns its height
+fn text_line(marks: &mut Vec<Mark>, x: f32, y: f32, size: f32, text: String, available: f32) -> f32 {
+    // Glyphs of the PNG font advance 6/7 of their height, wider than Helvetica
+    let natural = text.chars().count() as f32 * size * 6.0 / 7.0;
+    let size = if natural > available { size * available / natural } else { size };
+    marks.push(Mark::Text { x, y, size, text });
+    size
+}
+
+/// Start and length of each run of dark modules
+fn dark_runs(modules: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (index, dark) in modules.iter().chain(std::iter::once(&false)).enumerate() {
+        match (dark, start) {
+            (true, None) => start = Some(index),
+            (false, Some(begin)) => {
+                runs.push((begin, index - begin));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+/// Code 128 modules of `data` in code set B, `true` for bars, with start,
+/// check and stop symbols but without quiet zones
+pub fn code128(data: &str) -> Result<Vec<bool>> {
+    if data.is_empty() {
+        return Err(CertificateError::InvalidCertificateData("Code 128 data is empty".to_string()));
+    }
+    let mut values = vec![CODE128_START_B];
+    for c in data.chars() {
+        match c {
+            ' '..='~' => values.push(c as usize - 32),
+            other => {
+                return Err(CertificateError::InvalidCertificateData(format!(
+                    "Code 128 set B cannot encode '{}'", other
+                )))
+            }
+        }
+    }
+    values.push(code128_checksum(&values));
+    values.push(CODE128_STOP);
+    
+    let mut modules = Vec::new();
+    for value in values {
+        for (index, width) in CODE128_PATTERNS[value].bytes().enumerate() {
+            modules.extend(std::iter::repeat_n(index % 2 == 0, usize::from(width - b'0')));
+        }
+    }
+    Ok(modules)
+}
+
+/// Modulo 103 check value; the start symbol counts once, data symbols by position
+fn code128_checksum(values: &[usize]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .map(|(position, value)| value * position.max(1))
+        .sum::<usize>()
+        % 103
+}
+
+/// Render the label and write it to `path`
+pub fn write_label(content: &LabelContent, options: &LabelOptions, path: &Path) -> Result<()> {
+    std::fs::write(path, render_document(content, options)?)
+        .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))
+}
+
+/// The label as a PDF or PNG file, as chosen by `options.format`
+pub fn render_document(content: &LabelContent, options: &LabelOptions) -> Result<Vec<u8>> {
+    let marks = content.layout(options)?;
+    match options.format {
+        LabelFormat::Pdf => Ok(render_pdf(&marks, options)),
+        LabelFormat::Png => {
+            let mut png = std::io::Cursor::new(Vec::new());
+            render_png(&marks, options)
+                .write_to(&mut png, image::ImageOutputFormat::Png)
+                .map_err(|e| CertificateError::FileOperationFailed(format!("Label PNG: {}", e)))?;
+            Ok(png.into_inner())
+        }
+    }
+}
+
+/// Commands printing the label on a thermal printer at `options.dpi`; bars
+/// are drawn as filled boxes from the same layout as the files, text in the
+/// printer's own font
+pub fn printer_commands(content: &LabelContent, options: &LabelOptions, language: PrinterLanguage) -> Result<Vec<u8>> {
+    let marks = content.layout(options)?;
+    let dots = |mm: f32| (mm * options.dpi as f32 / MM_PER_INCH).round() as u32;
+    // Neither language has a portable escape for every character, so like
+    // the PDF output anything outside printable ASCII prints as '?'
+    let printable = |text: &str, reserved: &[char]| -> String {
+        text.chars().map(|c| if (' '..='~').contains(&c) && !reserved.contains(&c) { c } else { '?' }).collect()
+    };
+    
+    let mut commands = String::new();
+    match language {
+        PrinterLanguage::Zpl => {
+            commands.push_str(&format!("^XA^PW{}^LL{}^LH0,0\n", dots(options.width_mm), dots(options.height_mm)));
+            for mark in &marks {
+                match mark {
+                    Mark::Rect { x, y, width, height } => {
+                        let (left, top) = (dots(*x), dots(*y));
+                        let (w, h) = ((dots(x + width) - left).max(1), (dots(y + height) - top).max(1));
+                        // A box whose border is as thick as its narrower side is solid
+                        commands.push_str(&format!("^FO{},{}^GB{},{},{}^FS\n", left, top, w, h, w.min(h)));
+                    }
+                    Mark::Text { x, y, size, text } => {
+                        let height = dots(size / HELVETICA_CAP_HEIGHT).max(10);
+                        commands.push_str(&format!(
+                            "^FO{},{}^A0N,{},{}^FD{}^FS\n", dots(*x), dots(*y), height, height * 6 / 7, printable(text, &['^', '~'])
+                        ));
+                    }
+                }
+            }
+            commands.push_str("^XZ\n");
+        }
+        PrinterLanguage::Epl => {
+            commands.push_str(&format!("\nN\nq{}\nQ{},24\n", dots(options.width_mm), dots(options.height_mm)));
+            for mark in &marks {
+                match mark {
+                    Mark::Rect { x, y, width, height } => {
+                        let (left, top) = (dots(*x), dots(*y));
+                        let (w, h) = ((dots(x + width) - left).max(1), (dots(y + height) - top).max(1));
+                        commands.push_str(&format!("LO{},{},{},{}\n", left, top, w, h));
+                    }
+                    Mark::Text { x, y, size, text } => {
+                        let (font, multiplier) = epl_font(dots(*size));
+                        commands.push_str(&format!(
+                            "A{},{},0,{},{},{},N,\"{}\"\n", dots(*x), dots(*y), font, multiplier, multiplier, printable(text, &['"', '\\'])
+                        ));
+                    }
+                }
+            }
+            commands.push_str("P1\n");
+        }
+    }
+    Ok(commands.into_bytes())
+}
+
+/// EPL font and magnification whose capitals come closest to `cap_height`
+/// dots without exceeding it; fonts 1 to 4 have 12 to 24 dot cells
+fn epl_font(cap_height: u32) -> (u8, u32) {
+    let fonts = [(1u8, 12u32), (2, 16), (3, 20), (4, 24)];
+    let mut best = (1, 1);
+    let mut best_height = 0;
+    for multiplier in 1..=4 {
+        for (font, cell) in fonts {
+            // Capitals take about 7/10 of the cell
+            let height = cell * multiplier * 7 / 10;
+            if height <= cap_height && height > best_height {
+                best = (font, multiplier);
+                best_height = height;
+            }
+        }
+    }
+    best
+}
+
+/// Single-page PDF at the label's exact size, text in Helvetica Bold
+fn render_pdf(marks: &[Mark], options: &LabelOptions) -> Vec<u8> {
+    use pdf_writer::{Content, Finish, Name, Pdf, Ref, Str};
+    
+    let (catalog_id, tree_id, page_id, font_id, content_id) =
+        (Ref::new(1), Ref::new(2), Ref::new(3), Ref::new(4), Ref::new(5));
+    let font_name = Name(b"F1");
+    let page_height = options.height_mm * PT_PER_MM;
+    
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id).kids([page_id]).count(1);
+    let mut page = pdf.page(page_id);
+    page.media_box(pdf_writer::Rect::new(0.0, 0.0, options.width_mm * PT_PER_MM, page_height));
+    page.parent(tree_id);
+    page.contents(content_id);
+    page.resources().fonts().pair(font_name, font_id);
+    page.finish();
+    pdf.type1_font(font_id).base_font(Name(b"Helvetica-Bold"));
+    
+    let mut content = Content::new();
+    content.set_fill_gray(0.0);
+    for mark in marks {
+        match mark {
+            Mark::Rect { x, y, width, height } => {
+                content
+                    .rect(x * PT_PER_MM, page_height - (y + height) * PT_PER_MM, width * PT_PER_MM, height * PT_PER_MM)
+                    .fill_nonzero();
+            }
+            Mark::Text { x, y, size, text } => {
+                // The standard fonts use WinAnsiEncoding; anything else prints as '?'
+                let text: String = text.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect();
+                content
+                    .begin_text()
+                    .set_font(font_name, size * PT_PER_MM / HELVETICA_CAP_HEIGHT)
+                    .next_line(x * PT_PER_MM, page_height - (y + size) * PT_PER_MM)
+                    .show(Str(text.as_bytes()))
+                    .end_text();
+            }
+        }
+    }
+    pdf.stream(content_id, &content.finish());
+    pdf.finish()
+}
+
+/// Bitmap at the printer resolution, text in a built-in 5x7 font
+fn render_png(marks: &[Mark], options: &LabelOptions) -> image::GrayImage {
+    let px = |mm: f32| (mm * options.dpi as f32 / MM_PER_INCH).round() as u32;
+    let mut image = image::GrayImage::from_pixel(px(options.width_mm), px(options.height_mm), image::Luma([255]));
+    
+    for mark in marks {
+        match mark {
+            // Edges are rounded separately so adjacent modules neither overlap nor leave gaps
+            Mark::Rect { x, y, width, height } => fill(&mut image, px(*x), px(*y), px(x + width), px(y + height)),
+            Mark::Text { x, y, size, text } => {
+                let scale = (px(*size) / 7).max(1);
+                let (mut left, top) = (px(*x), px(*y));
+                for c in text.chars() {
+                    for (row, bits) in glyph(c).iter().enumerate() {
+                        for column in 0..5u32 {
+                            if bits >> (4 - column) & 1 == 1 {
+                                let (cell_x, cell_y) = (left + column * scale, top + row as u32 * scale);
+                                fill(&mut image, cell_x, cell_y, cell_x + scale, cell_y + scale);
+                            }
+                        }
+                    }
+                    left += 6 * scale;
+                }
+            }
+        }
+    }
+    image
+}
+
+fn fill(image: &mut image::GrayImage, x0: u32, y0: u32, x1: u32, y1: u32) {
+    for y in y0..y1.min(image.height()) {
+        for x in x0..x1.min(image.width()) {
+            image.put_pixel(x, y, image::Luma([0]));
+        }
+    }
+}
+
+/// Rows of a 5x7 glyph, most significant of the low five bits on the left;
+/// lowercase prints as uppercase and unknown characters as '?'
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        ' ' => [0x00; 7],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+impl std::str::FromStr for LabelFormat {
+    type Err = CertificateError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pdf" => Ok(LabelFormat::Pdf),
+            "png" => Ok(LabelFormat::Png),
+            other => Err(CertificateError::InvalidConfiguration(format!("Unknown label format: {}", other))),
+        }
+    }
+}
+
+impl std::str::FromStr for LabelSymbol {
+    type Err = CertificateError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "code128" => Ok(LabelSymbol::Code128),
+            "qr" => Ok(LabelSymbol::Qr),
+            other => Err(CertificateError::InvalidConfiguration(format!("Unknown label symbol: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate() -> SignedCertificate {
+        let data = CertificateData {
+            certificate_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "WD-WCC4N1234567".to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: String::new(),
+            timestamp: Utc::now(),
+            certificate_hash: String::new(),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    #[test]
+    fn test_code128_encoding() {
+        // Reference value for "Wikipedia" in code set B
+        let values: Vec<usize> = std::iter::once(CODE128_START_B)
+            .chain("Wikipedia".chars().map(|c| c as usize - 32))
+            .collect();
+        assert_eq!(code128_checksum(&values), 88);
+        
+        assert!(CODE128_PATTERNS[..106].iter().all(|pattern| pattern.bytes().map(|w| w - b'0').sum::<u8>() == 11));
+        let modules = code128("Wikipedia").unwrap();
+        assert_eq!(modules.len(), 11 * (9 + 3) + 2);
+        assert!(modules[0] && *modules.last().unwrap());
+        
+        assert!(code128("").is_err());
+        assert!(code128("Größe").is_err());
+    }
+    
+    #[test]
+    fn test_label_layout_and_output() {
+        let signed = signed_certificate();
+        let content = LabelContent::from_certificate(&signed, "https://verify.example/c/1", LabelSymbol::Code128);
+        assert_eq!(content.symbol_data.len(), 32);
+        
+        let options = LabelOptions::default();
+        let marks = content.layout(&options).unwrap();
+        assert!(marks.iter().any(|mark| matches!(mark, Mark::Text { text, .. } if text == "S/N WD-WCC4N1234567")));
+        for mark in &marks {
+            if let Mark::Rect { x, y, width, height } = mark {
+                assert!(*x >= MARGIN_MM && x + width <= options.width_mm - MARGIN_MM);
+                assert!(y + height <= options.height_mm);
+            }
+        }
+        
+        let qr = LabelContent::from_certificate(&signed, "https://verify.example/c/1", LabelSymbol::Qr);
+        assert!(qr.layout(&options).unwrap().len() > marks.len());
+        let tiny = LabelOptions { width_mm: 25.0, height_mm: 15.0, ..LabelOptions::default() };
+        assert!(content.layout(&tiny).is_err());
+        
+        let dir = tempfile::tempdir().unwrap();
+        for format in [LabelFormat::Pdf, LabelFormat::Png] {
+            let path = dir.path().join(format.extension());
+            write_label(&content, &LabelOptions { format, ..LabelOptions::default() }, &path).unwrap();
+            assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        }
+        let png = image::open(dir.path().join("label.png")).unwrap();
+        assert_eq!((png.width(), png.height()), (732, 343));
+    }
+    
+    #[test]
+    fn test_printer_commands() {
+        let content = LabelContent::new(
+            "S^N~1",
+            Uuid::nil(),
+            Utc::now(),
+            "https://verify.example/c/1",
+            LabelSymbol::Code128,
+        );
+        let options = LabelOptions { dpi: 203, ..LabelOptions::default() };
+        let bars = content.layout(&options).unwrap().iter().filter(|mark| matches!(mark, Mark::Rect { .. })).count();
+        
+        let zpl = String::from_utf8(printer_commands(&content, &options, PrinterLanguage::Zpl).unwrap()).unwrap();
+        assert!(zpl.starts_with("^XA^PW496^LL232"));
+        assert!(zpl.trim_end().ends_with("^XZ"));
+        assert_eq!(zpl.matches("^GB").count(), bars);
+        assert!(zpl.contains("^FDS/N S?N?1^FS"));
+        
+        let epl = String::from_utf8(printer_commands(&content, &options, PrinterLanguage::Epl).unwrap()).unwrap();
+        assert!(epl.contains("\nN\nq496\nQ232,24\n"));
+        assert_eq!(epl.lines().filter(|line| line.starts_with("LO")).count(), bars);
+        assert!(epl.contains(",N,\"CERT 00000000-0000-0000-0000-000000000000\"\n"));
+        assert!(epl.ends_with("P1\n"));
+        assert_eq!(epl_font(0), (1, 1));
+        assert_eq!(epl_font(17), (4, 1));
+    }
+}
@@ -0,0 +1,315 @@
+//! Page layout of PDF certificates and job reports
+//!
+//! A single page holds the summary of one wipe, but multi-pass methods and
+//! job reports covering a whole cart of drives carry far more rows than fit.
+//! Content is laid out as a summary page followed by detail pages: one table
+//! of passes (pattern, duration, speed, verification) per certificate and,
+//! for job reports, a page per device. Tables longer than a page continue on
+//! the next one with the header repeated, and every page knows its number so
+//! the generator can print "Page 2 of 5".
+
+use crate::certificate::SignedCertificate;
+use crate::error::{CertificateError, Result};
+
+/// Key in `technical_details` holding the per-pass breakdown
+pub const PASSES_DETAIL: &str = "passes";
+
+/// Header of the per-pass table
+pub const PASS_COLUMNS: [&str; 5] = ["Pass", "Pattern", "Duration", "Speed", "Verification"];
+
+/// Header of the device overview in job reports
+pub const DEVICE_COLUMNS: [&str; 5] = ["Serial number", "Model", "Method", "Completed", "Verification"];
+
+/// Table rows that fit on one page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLayout {
+    /// Rows on a page holding only a table
+    pub rows_per_page: usize,
+}
+
+/// What a page shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    Summary,
+    Passes,
+    Device,
+}
+
+/// One laid-out page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub kind: PageKind,
+    pub title: String,
+    /// Column headers, repeated on continuation pages
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Page number, starting at 1
+    pub number: usize,
+    pub total: usize,
+}
+
+impl Default for PageLayout {
+    fn default() -> Self {
+        Self { rows_per_page: 32 }
+    }
+}
+
+impl PageLayout {
+    pub fn validate(&self) -> Result<()> {
+        if self.rows_per_page < 4 {
+            return Err(CertificateError::InvalidConfiguration(format!(
+                "At least 4 rows must fit on a page, got {}", self.rows_per_page
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Pages of a single certificate: the summary, then its passes
+    pub fn certificate_pages(&self, signed: &SignedCertificate) -> Result<Vec<Page>> {
+        self.validate()?;
+        let mut pages = self.paginate(PageKind::Summary, "Summary", &["Field", "Value"], summary_rows(signed));
+        let passes = pass_rows(signed);
+        if !passes.is_empty() {
+            pages.extend(self.paginate(PageKind::Passes, "Passes", &PASS_COLUMNS, passes));
+        }
+        Ok(number(pages))
+    }
+    
+    /// Pages of a job report: an overview of all devices, then each device's
+    /// summary and passes on pages of its own
+    pub fn job_report_pages(&self, certificates: &[SignedCertificate]) -> Result<Vec<Page>> {
+        self.validate()?;
+        let overview = certificates.iter().map(device_row).collect();
+        let mut pages = self.paginate(PageKind::Summary, "Devices", &DEVICE_COLUMNS, overview);
+        for signed in certificates {
+            let data = &signed.certificate.data;
+            let title = format!("{} {}", data.device_info.model, data.device_info.serial);
+            let mut rows = summary_rows(signed);
+            let passes = pass_rows(signed);
+            if !passes.is_empty() {
+                // Pass rows follow the summary under their own header row
+                rows.push(PASS_COLUMNS.iter().map(|column| column.to_string()).collect());
+                rows.extend(passes);
+            }
+            pages.extend(self.paginate(PageKind::Device, &title, &["Field", "Value"], rows));
+        }
+        Ok(number(pages))
+    }
+    
+    /// Split rows over as many pages as needed; a table without rows still gets a page
+    fn paginate(&self, kind: PageKind, title: &str, header: &[&str], rows: Vec<Vec<String>>) -> Vec<Page> {
+        let header: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+        let chunks: Vec<Vec<Vec<String>>> = if rows.is_empty() {
+            vec![Vec::new()]
+        } else {
+            rows.chunks(self.rows_per_page).map(<[Vec<String>]>::to_vec).collect()
+        };
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, rows)| Page {
+                kind,
+                title: if index == 0 { title.to_string() } else { format!("{} (continued)", title) },
+                header: header.clone(),
+                rows,
+                number: 0,
+                total: 0,
+            })
+            .collect()
+    }
+}
+
+fn number(mut pages: Vec<Page>) -> Vec<Page> {
+    let total = pages.len();
+    for (index, page) in pages.iter_mut().enumerate() {
+        page.number = index + 1;
+        page.total = total;
+    }
+    pages
+}
+
+fn summary_rows(signed: &SignedCertificate) -> Vec<Vec<String>> {
+    let data = &signed.certificate.data;
+    let wipe = &data.wipe_info;
+    let mut rows = vec![
+        ("Certificate ID", data.certificate_id.to_string()),
+        ("Model", data.device_info.model.clone()),
+        ("Serial number", data.device_info.serial.clone()),
+        ("Capacity", format!("{} bytes", data.device_info.size)),
+        ("Method", wipe.algorithm.to_string()),
+        ("Started", safe_erase_core::time_format::format_timestamp(&wipe.started_at)),
+        ("Passes completed", wipe.passes_completed.to_string()),
+    ];
+    if let Some(layer) = data.device_info.virtualization {
+        rows.push(("Storage layer", format!("{}; covers only the blocks it exposes", layer)));
+    }
+    if let Some(completed_at) = &wipe.completed_at {
+        rows.push(("Completed", safe_erase_core::time_format::format_timestamp(completed_at)));
+    }
+    if let Some(duration) = wipe.duration {
+        rows.push(("Duration", duration_text(duration)));
+    }
+    rows.push(("Verification", verification_text(wipe.verification_passed)));
+    if let Some(work_order) = &data.work_order {
+        rows.push(("Work order", work_order.clone()));
+    }
+    if let Some(host) = &data.host {
+        rows.push(("Performed on", host.to_string()));
+    }
+    if let Some(operator) = &data.operator {
+        rows.push(("Operator", operator.to_string()));
+    }
+    rows.into_iter().map(|(field, value)| vec![field.to_string(), value]).collect()
+}
+
+fn pass_rows(signed: &SignedCertificate) -> Vec<Vec<String>> {
+    let passes: Vec<safe_erase_core::PassRecord> = signed
+        .certificate
+        .data
+        .technical_details
+        .as_ref()
+        .and_then(|details| details.get(PASSES_DETAIL))
+        .and_then(|passes| serde_json::from_value(passes.clone()).ok())
+        .unwrap_or_default();
+    passes
+        .into_iter()
+        .map(|pass| {
+            vec![
+                pass.pass.to_string(),
+                pass.pattern,
+                duration_text(pass.duration),
+                format!("{:.1} MB/s", pass.average_speed / 1_000_000.0),
+                verification_text(pass.verified),
+            ]
+        })
+        .collect()
+}
+
+fn device_row(signed: &SignedCertificate) -> Vec<String> {
+    let data = &signed.certificate.data;
+    vec![
+        data.device_info.serial.clone(),
+        data.device_info.model.clone(),
+        data.wipe_info.algorithm.to_string(),
+        data.wipe_info
+            .completed_at
+            .as_ref()
+            .map(safe_erase_core::time_format::format_timestamp)
+            .unwrap_or_default(),
+        verification_text(data.wipe_info.verification_passed),
+    ]
+}
+
+/// Duration as printed, e.g. `1h 02m 03s`
+fn duration_text(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, seconds) => format!("{}s", seconds),
+        (0, minutes, seconds) => format!("{}m {:02}s", minutes, seconds),
+        (hours, minutes, seconds) => format!("{}h {:02}m {:02}s", hours, minutes, seconds),
+    }
+}
+
+fn verification_text(passed: Option<bool>) -> String {
+    match passed {
+        Some(true) => "Passed",
+        Some(false) => "Failed",
+        None => "Not verified",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate(serial: &str, passes: usize) -> SignedCertificate {
+        let passes: Vec<safe_erase_core::PassRecord> = (1..=passes)
+            .map(|pass| safe_erase_core::PassRecord {
+                pass,
+                pattern: "Random data".to_string(),
+                bytes_written: 1_000_000_000,
+                duration: Duration::from_secs(10),
+                average_speed: 100_000_000.0,
+                verified: None,
+            })
+            .collect();
+        let data = CertificateData {
+            certificate_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: serial.to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::Gutmann,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: passes.len(),
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: Some(HashMap::from([
+                (PASSES_DETAIL.to_string(), serde_json::to_value(&passes).unwrap()),
+            ])),
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: String::new(),
+            timestamp: Utc::now(),
+            certificate_hash: String::new(),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    #[test]
+    fn test_passes_paginate_with_numbering() {
+        let layout = PageLayout { rows_per_page: 10 };
+        let pages = layout.certificate_pages(&signed_certificate("S1", 35)).unwrap();
+        
+        let kinds: Vec<PageKind> = pages.iter().map(|page| page.kind).collect();
+        assert_eq!(kinds, [PageKind::Summary, PageKind::Passes, PageKind::Passes, PageKind::Passes, PageKind::Passes]);
+        assert_eq!(pages[2].title, "Passes (continued)");
+        assert_eq!(pages[2].header, PASS_COLUMNS);
+        assert_eq!(pages[4].rows.len(), 5);
+        assert_eq!(pages[1].rows[0], ["1", "Random data", "10s", "100.0 MB/s", "Not verified"]);
+        assert!(pages.iter().all(|page| page.total == 5));
+        assert_eq!(pages[4].number, 5);
+        
+        assert!(PageLayout { rows_per_page: 2 }.certificate_pages(&signed_certificate("S1", 1)).is_err());
+    }
+    
+    #[test]
+    fn test_job_report_has_page_per_device() {
+        let certificates: Vec<SignedCertificate> =
+            (0..3).map(|index| signed_certificate(&format!("S{}", index), 3)).collect();
+        let pages = PageLayout::default().job_report_pages(&certificates).unwrap();
+        
+        assert_eq!(pages.len(), 4);
+        assert_eq!(pages[0].rows.len(), 3);
+        assert_eq!(pages[0].header, DEVICE_COLUMNS);
+        assert!(pages[1..].iter().all(|page| page.kind == PageKind::Device));
+        assert_eq!(pages[3].title, "Test Drive S2");
+        assert!(pages[3].rows.iter().any(|row| row[0] == "Pass"));
+    }
+}
@@ -2,14 +2,28 @@
 //! 
 //! This crate provides tamper-proof certificate generation for SafeErase wipe operations,
 //! supporting both PDF and JSON formats with cryptographic verification using
-//! OpenSSL and JSON Web Signatures.
+//! OpenSSL or RustCrypto (see [`keys`]) and JSON Web Signatures.
 
+pub mod accessibility;
+pub mod attempt;
+pub mod canonical;
 pub mod certificate;
+pub mod clock;
 pub mod pdf;
 pub mod json;
 pub mod crypto;
-pub mod templates;
-pub mod verification;
+pub mod destruction;
+mod encryption;
+#[cfg(feature = "jwks")]
+pub mod jwks;
+pub mod keys;
+pub mod label;
+pub mod layout;
+pub mod naming;
+pub mod offline;
+pub mod report;
+pub mod rollup;
+pub mod trust_store;
 pub mod error;
 
 use std::path::Path;
@@ -17,11 +31,19 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+pub use accessibility::PdfAccessibility;
+pub use attempt::{AttemptReport, SignedAttemptReport};
 pub use certificate::{WipeCertificate, CertificateData, ComplianceInfo};
 pub use pdf::PdfGenerator;
 pub use json::JsonGenerator;
-pub use crypto::{CertificateSigner, SignatureInfo};
-pub use verification::CertificateVerifier;
+pub use clock::{ClockCheck, ClockPolicy};
+pub use crypto::{CertificateSigner, CertificateVerifier, SignatureInfo};
+pub use destruction::{DestructionRecord, DestructionRequest, SignedDestructionRecord};
+pub use label::{LabelFormat, LabelOptions, LabelSymbol, PrinterLanguage};
+pub use naming::{CollisionPolicy, FileNaming};
+pub use report::{FileOutcome, VerificationReport};
+pub use rollup::Rollup;
+pub use trust_store::{TrustWarning, TrustedKey};
 pub use error::{CertificateError, Result};
 
 /// Main certificate generation engine
@@ -44,12 +66,48 @@ pub struct CertificateOptions {
     pub include_compliance_info: bool,
     /// Custom certificate template
     pub template_name: Option<String>,
+    /// Output file naming template and collision handling
+    #[serde(default)]
+    pub file_naming: FileNaming,
     /// Organization information
     pub organization: Option<OrganizationInfo>,
     /// Additional metadata
     pub metadata: std::collections::HashMap<String, String>,
+    /// Mask the device serial and asset tag for certificates shared outside
+    /// the organization; never serialized since it holds the salt
+    #[serde(skip)]
+    pub redactor: Option<safe_erase_core::Redactor>,
+    /// Encrypt generated PDFs; passwords are accepted but never serialized back
+    #[serde(default, skip_serializing)]
+    pub pdf_protection: Option<PdfProtection>,
+    /// Compare the system clock with NTP before signing
+    #[serde(default)]
+    pub clock_check: ClockCheck,
+    /// Language, title and alternative texts of tagged PDF output
+    #[serde(default)]
+    pub accessibility: PdfAccessibility,
+    /// Also print a drive label linking the physical drive to the certificate
+    #[serde(default)]
+    pub label: Option<LabelOptions>,
 }
 
+/// Password protection for PDF certificates, applied with AES-256 (PDF 2.0,
+/// security handler revision 6)
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdfProtection {
+    /// Grants full access, including editing and removing the protection
+    pub owner_password: String,
+    /// Needed to open the document; anyone can open it when unset
+    #[serde(default)]
+    pub user_password: Option<String>,
+    /// Allow printing without the owner password
+    #[serde(default = "default_allow_printing")]
+    pub allow_printing: bool,
+}
+
+/// Metadata entries masked when redaction is enabled
+const REDACTED_METADATA: [&str; 2] = ["asset_tag", "serial"];
+
 /// Organization information for certificates
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrganizationInfo {
@@ -76,12 +134,25 @@ pub struct CertificateResult {
     pub certificate_id: Uuid,
     pub pdf_path: Option<String>,
     pub json_path: Option<String>,
+    pub label_path: Option<String>,
     pub signature_info: SignatureInfo,
     pub generated_at: DateTime<Utc>,
     pub verification_url: Option<String>,
     pub qr_code_data: Option<String>,
 }
 
+/// Result of generating a signed document other than a certificate: an
+/// attempt report or a destruction record
+#[derive(Debug, Clone)]
+pub struct DocumentResult {
+    pub document_id: Uuid,
+    /// Signed document
+    pub json_path: String,
+    /// Printable copy
+    pub text_path: String,
+    pub generated_at: DateTime<Utc>,
+}
+
 impl CertificateEngine {
     /// Create a new certificate engine
     pub fn new() -> Result<Self> {
@@ -113,15 +184,34 @@ impl CertificateEngine {
         })
     }
     
+    /// Create a certificate engine from the configured certificate defaults,
+    /// using the configured signing key pair when one is set
+    pub fn from_config(config: &safe_erase_core::config::CertificateDefaults) -> Result<Self> {
+        match (&config.signing_key, &config.public_key) {
+            (Some(private_key), Some(public_key)) => Self::with_signing_key(private_key, public_key),
+            (None, None) => Self::new(),
+            _ => Err(CertificateError::MissingConfiguration(
+                "certificates.signing_key and certificates.public_key must be set together".to_string()
+            )),
+        }
+    }
+    
     /// Generate a wipe certificate
     pub async fn generate_certificate(
         &self,
         wipe_result: &safe_erase_core::WipeResult,
         verification_result: Option<&safe_erase_core::VerificationResult>,
         format: CertificateFormat,
-        options: CertificateOptions,
+        mut options: CertificateOptions,
         output_dir: &Path,
     ) -> Result<CertificateResult> {
+        // Check the clock before anything is dated; the outcome is signed with the certificate
+        if let Some(status) = options.clock_check.run_async().await {
+            for (key, value) in options.clock_check.enforce(&status)? {
+                options.metadata.insert(key.to_string(), value);
+            }
+        }
+        
         // Create certificate data
         let certificate_data = self.create_certificate_data(
             wipe_result,
@@ -144,14 +234,19 @@ impl CertificateEngine {
                 pdf_path = Some(self.generate_pdf(&signed_certificate, &options, output_dir).await?);
             }
             CertificateFormat::JSON => {
-                json_path = Some(self.generate_json(&signed_certificate, output_dir).await?);
+                json_path = Some(self.generate_json(&signed_certificate, &options, output_dir).await?);
             }
             CertificateFormat::Both => {
                 pdf_path = Some(self.generate_pdf(&signed_certificate, &options, output_dir).await?);
-                json_path = Some(self.generate_json(&signed_certificate, output_dir).await?);
+                json_path = Some(self.generate_json(&signed_certificate, &options, output_dir).await?);
             }
         }
         
+        let label_path = match &options.label {
+            Some(label) => Some(self.generate_label(&signed_certificate, label, &options, output_dir)?),
+            None => None,
+        };
+        
         // Generate QR code data if requested
         let qr_code_data = if options.include_qr_code {
             Some(self.generate_qr_code_data(&signed_certificate)?)
@@ -166,6 +261,7 @@ impl CertificateEngine {
             certificate_id: signed_certificate.certificate_id(),
             pdf_path,
             json_path,
+            label_path,
             signature_info: signed_certificate.signature_info().clone(),
             generated_at: Utc::now(),
             verification_url,
@@ -173,6 +269,35 @@ impl CertificateEngine {
         })
     }
     
+    /// Generate a signed sanitization attempt report for a failed or cancelled
+    /// wipe, as JSON and as printable text
+    pub async fn generate_attempt_report(
+        &self,
+        wipe_result: &safe_erase_core::WipeResult,
+        options: &CertificateOptions,
+        output_dir: &Path,
+    ) -> Result<DocumentResult> {
+        let signed = AttemptReport::from_result(wipe_result, options)?.sign(&self.signer)?;
+        let name = format!("sanitization-attempt-{}", signed.report.report_id);
+        write_document(&signed, &signed.text(), &name, output_dir)?;
+        Ok(DocumentResult::new(signed.report.report_id, &name, output_dir, signed.report.generated_at))
+    }
+    
+    /// Generate a signed record of a physical destruction reported by
+    /// `recorded_by`, as JSON and as printable text
+    pub async fn generate_destruction_record(
+        &self,
+        destruction: DestructionRequest,
+        recorded_by: &str,
+        options: &CertificateOptions,
+        output_dir: &Path,
+    ) -> Result<DocumentResult> {
+        let signed = DestructionRecord::new(destruction, recorded_by, options)?.sign(&self.signer)?;
+        let name = format!("destruction-record-{}", signed.record.record_id);
+        write_document(&signed, &signed.text(), &name, output_dir)?;
+        Ok(DocumentResult::new(signed.record.record_id, &name, output_dir, signed.record.generated_at))
+    }
+    
     /// Verify a certificate
     pub async fn verify_certificate<P: AsRef<Path>>(&self, certificate_path: P) -> Result<bool> {
         self.verifier.verify_certificate_file(certificate_path).await
@@ -202,22 +327,54 @@ impl CertificateEngine {
             None
         };
         
+        // Record what the media held before the wipe; only counts and format names
+        let mut metadata = options.metadata.clone();
+        if let Some(scan) = &wipe_result.sensitive_data_scan {
+            metadata.insert("pre_wipe_risk_level".to_string(), scan.risk_level.to_string());
+            metadata.insert("pre_wipe_findings".to_string(), scan.summary());
+        }
+        // A region wipe is one certificate naming every region; the rest of the disk was kept
+        if !wipe_result.regions.is_empty() {
+            let regions: Vec<String> = wipe_result
+                .regions
+                .iter()
+                .map(|result| match &result.region.label {
+                    Some(label) => format!("{} (LBA {}-{})", label, result.region.start_lba, result.region.end_lba),
+                    None => format!("LBA {}-{}", result.region.start_lba, result.region.end_lba),
+                })
+                .collect();
+            metadata.insert("wiped_regions".to_string(), regions.join(", "));
+        }
+        
+        let mut serial = wipe_result.device_serial.clone();
+        if let Some(redactor) = &options.redactor {
+            serial = redactor.redact(&serial);
+            for key in REDACTED_METADATA {
+                if let Some(value) = metadata.get_mut(key) {
+                    *value = redactor.redact(value);
+                }
+            }
+            metadata.insert("identifiers_redacted".to_string(), "true".to_string());
+        }
+        
         Ok(CertificateData {
             certificate_id,
             generated_at,
             device_info: certificate::DeviceInfo {
                 path: wipe_result.device_path.clone(),
-                serial: wipe_result.device_serial.clone(),
+                serial,
                 model: wipe_result.device_model.clone(),
                 size: wipe_result.bytes_wiped,
+                virtualization: wipe_result.virtualization,
             },
             wipe_info: certificate::WipeInfo {
-                algorithm: wipe_result.algorithm,
+                algorithm: wipe_result.algorithm.clone(),
                 started_at: wipe_result.started_at,
                 completed_at: wipe_result.completed_at,
                 duration: wipe_result.duration,
                 passes_completed: wipe_result.passes_completed,
                 verification_passed: wipe_result.verification_passed,
+                device_digest: wipe_result.device_digest.clone(),
             },
             verification_info: verification_result.map(|vr| certificate::VerificationInfo {
                 verification_id: vr.verification_id,
@@ -226,11 +383,16 @@ impl CertificateEngine {
                 samples_passed: vr.samples_passed,
                 success_rate: vr.success_rate,
                 overall_result: vr.overall_result,
+                coverage_percent: Some(vr.coverage_percent),
             }),
             compliance_info,
             technical_details,
             organization: options.organization.clone(),
-            metadata: options.metadata.clone(),
+            metadata,
+            work_order: wipe_result.work_order.clone(),
+            ticket_url: wipe_result.ticket_url.clone(),
+            host: wipe_result.host.clone(),
+            operator: wipe_result.operator.clone(),
         })
     }
     
@@ -248,8 +410,47 @@ impl CertificateEngine {
         // Add HPA/DCO information
         details.insert("hpa_detected".to_string(), serde_json::Value::Bool(wipe_result.hpa_detected));
         details.insert("hpa_cleared".to_string(), serde_json::Value::Bool(wipe_result.hpa_cleared));
+        details.insert("hpa_restored".to_string(), serde_json::Value::Bool(wipe_result.hpa_restored));
         details.insert("dco_detected".to_string(), serde_json::Value::Bool(wipe_result.dco_detected));
         details.insert("dco_cleared".to_string(), serde_json::Value::Bool(wipe_result.dco_cleared));
+        if let Some(hidden) = &wipe_result.hidden_area {
+            details.insert("hidden_area".to_string(), serde_json::json!({
+                "hpa_sectors": hidden.hpa_sectors,
+                "dco_sectors": hidden.dco_sectors,
+                "sector_size": hidden.sector_size,
+                "hidden_bytes": hidden.hidden_bytes(),
+                "recovered_bytes": wipe_result.recovered_bytes,
+            }));
+        }
+        
+        if let Some(degradation) = &wipe_result.smart_degradation {
+            details.insert("smart_degradation".to_string(), serde_json::to_value(degradation).unwrap());
+        }
+        
+        if let Some(sealed) = &wipe_result.block_seeds {
+            details.insert("block_seeds_key_id".to_string(), serde_json::Value::String(sealed.key_id.clone()));
+        }
+        
+        if let Some(wear) = &wipe_result.wear {
+            details.insert("wear".to_string(), serde_json::to_value(wear).unwrap());
+        }
+        
+        if !wipe_result.regions.is_empty() {
+            details.insert("regions".to_string(), serde_json::to_value(&wipe_result.regions).unwrap());
+        }
+        
+        if !wipe_result.skipped_ranges.is_empty() {
+            details.insert("skipped_ranges".to_string(), serde_json::to_value(&wipe_result.skipped_ranges).unwrap());
+        }
+        
+        if let Some(scan) = &wipe_result.sensitive_data_scan {
+            details.insert("sensitive_data_scan".to_string(), serde_json::to_value(scan).unwrap());
+        }
+        
+        // Per-pass breakdown for the PDF detail pages
+        if !wipe_result.passes.is_empty() {
+            details.insert(layout::PASSES_DETAIL.to_string(), serde_json::to_value(&wipe_result.passes).unwrap());
+        }
         
         // Add verification details if available
         if let Some(verification) = verification_result {
@@ -260,15 +461,18 @@ impl CertificateEngine {
         details
     }
     
-    /// Generate PDF certificate
+    /// Generate a tagged PDF certificate, encrypted when `options.pdf_protection` is set
     async fn generate_pdf(
         &self,
         certificate: &certificate::SignedCertificate,
         options: &CertificateOptions,
         output_dir: &Path,
     ) -> Result<String> {
-        let filename = format!("wipe_certificate_{}.pdf", certificate.certificate_id());
-        let output_path = output_dir.join(&filename);
+        if let Some(protection) = &options.pdf_protection {
+            protection.validate()?;
+        }
+        options.accessibility.validate()?;
+        let output_path = options.file_naming.resolve(&certificate.certificate().data, "pdf", output_dir)?;
         
         self.pdf_generator.generate_certificate(certificate, options, &output_path).await?;
         
@@ -279,35 +483,79 @@ impl CertificateEngine {
     async fn generate_json(
         &self,
         certificate: &certificate::SignedCertificate,
+        options: &CertificateOptions,
         output_dir: &Path,
     ) -> Result<String> {
-        let filename = format!("wipe_certificate_{}.json", certificate.certificate_id());
-        let output_path = output_dir.join(&filename);
+        let output_path = options.file_naming.resolve(&certificate.certificate().data, "json", output_dir)?;
         
         self.json_generator.generate_certificate(certificate, &output_path).await?;
         
         Ok(output_path.to_string_lossy().to_string())
     }
     
-    /// Generate QR code data for certificate verification
+    /// Write a drive label next to the certificate
+    fn generate_label(
+        &self,
+        certificate: &certificate::SignedCertificate,
+        label: &LabelOptions,
+        options: &CertificateOptions,
+        output_dir: &Path,
+    ) -> Result<String> {
+        let verification_url = self.generate_verification_url(certificate).unwrap_or_default();
+        let content = label::LabelContent::from_certificate(certificate, &verification_url, label.symbol);
+        let output_path = options.file_naming.resolve(&certificate.certificate().data, label.format.extension(), output_dir)?;
+        
+        label::write_label(&content, label, &output_path)?;
+        
+        Ok(output_path.to_string_lossy().to_string())
+    }
+    
+    /// Generate QR code data for certificate verification: the verification
+    /// URL with a signed summary in its fragment, checkable offline
     fn generate_qr_code_data(&self, certificate: &certificate::SignedCertificate) -> Result<String> {
-        // Create verification data
-        let verification_data = serde_json::json!({
-            "certificate_id": certificate.certificate_id(),
-            "signature": certificate.signature_info().signature,
-            "verification_url": self.generate_verification_url(certificate)
-        });
-        
-        Ok(verification_data.to_string())
+        let token = offline::encode(certificate, &self.signer)?;
+        let verification_url = self.generate_verification_url(certificate).unwrap_or_default();
+        Ok(offline::qr_content(&verification_url, &token))
     }
     
     /// Generate verification URL for the certificate
     fn generate_verification_url(&self, certificate: &certificate::SignedCertificate) -> Option<String> {
+        Some(Self::verification_url(certificate.certificate_id()))
+    }
+    
+    /// Where a certificate can be checked online
+    pub fn verification_url(certificate_id: Uuid) -> String {
         // This would typically point to a web service for certificate verification
-        Some(format!("https://verify.safeerase.com/certificate/{}", certificate.certificate_id()))
+        format!("https://verify.safeerase.com/certificate/{}", certificate_id)
+    }
+}
+
+impl DocumentResult {
+    fn new(document_id: Uuid, name: &str, output_dir: &Path, generated_at: DateTime<Utc>) -> Self {
+        let path = |extension: &str| output_dir.join(format!("{}.{}", name, extension)).to_string_lossy().to_string();
+        Self {
+            document_id,
+            json_path: path("json"),
+            text_path: path("txt"),
+            generated_at,
+        }
     }
 }
 
+/// Write a signed document as `<name>.json` and its printable form as `<name>.txt`
+fn write_document<T: Serialize>(signed: &T, text: &str, name: &str, output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", output_dir.display(), e)))?;
+    let json = serde_json::to_string_pretty(signed)
+        .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+    for (extension, contents) in [("json", json.as_str()), ("txt", text)] {
+        let path = output_dir.join(format!("{}.{}", name, extension));
+        std::fs::write(&path, contents)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))?;
+    }
+    Ok(())
+}
+
 impl Default for CertificateOptions {
     fn default() -> Self {
         Self {
@@ -315,8 +563,95 @@ impl Default for CertificateOptions {
             include_technical_details: true,
             include_compliance_info: true,
             template_name: None,
+            file_naming: FileNaming::default(),
             organization: None,
             metadata: std::collections::HashMap::new(),
+            redactor: None,
+            pdf_protection: None,
+            clock_check: ClockCheck::default(),
+            accessibility: PdfAccessibility::default(),
+            label: None,
+        }
+    }
+}
+
+impl PdfProtection {
+    /// Check the passwords before any certificate is generated
+    pub fn validate(&self) -> Result<()> {
+        if self.owner_password.is_empty() {
+            return Err(CertificateError::InvalidConfiguration(
+                "PDF owner password must not be empty".to_string()
+            ));
+        }
+        if self.user_password.as_deref() == Some(self.owner_password.as_str()) {
+            return Err(CertificateError::InvalidConfiguration(
+                "PDF user and owner passwords must differ".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Passwords must never end up in logs
+impl std::fmt::Debug for PdfProtection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PdfProtection")
+            .field("user_password", &self.user_password.as_ref().map(|_| "***"))
+            .field("allow_printing", &self.allow_printing)
+            .finish_non_exhaustive()
+    }
+}
+
+fn default_allow_printing() -> bool {
+    true
+}
+
+impl CertificateOptions {
+    /// Certificate options from the configured certificate defaults
+    pub fn from_config(config: &safe_erase_core::config::CertificateDefaults) -> Self {
+        Self {
+            include_qr_code: config.include_qr_code,
+            include_technical_details: config.include_technical_details,
+            include_compliance_info: config.include_compliance_info,
+            template_name: config.template_name.clone(),
+            file_naming: FileNaming {
+                template: config.file_name_template.clone(),
+                on_collision: config.on_collision.parse().unwrap_or_default(),
+            },
+            pdf_protection: config.pdf_owner_password.clone().map(|owner_password| PdfProtection {
+                owner_password,
+                user_password: config.pdf_user_password.clone(),
+                allow_printing: true,
+            }),
+            clock_check: ClockCheck {
+                policy: config.clock_policy.parse().unwrap_or_default(),
+                ntp_servers: config.ntp_servers.clone(),
+                max_skew_secs: config.max_clock_skew_secs,
+                ..ClockCheck::default()
+            },
+            accessibility: PdfAccessibility {
+                language: config.pdf_language.clone(),
+                ..PdfAccessibility::default()
+            },
+            label: config.label_format.as_deref().map(|format| LabelOptions {
+                format: format.parse().unwrap_or_default(),
+                symbol: config.label_symbol.parse().unwrap_or_default(),
+                ..LabelOptions::default()
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+impl std::str::FromStr for CertificateFormat {
+    type Err = CertificateError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pdf" => Ok(CertificateFormat::PDF),
+            "json" => Ok(CertificateFormat::JSON),
+            "both" => Ok(CertificateFormat::Both),
+            other => Err(CertificateError::InvalidConfiguration(format!("Unknown certificate format: {}", other))),
         }
     }
 }
@@ -344,6 +679,27 @@ mod tests {
         assert!(options.template_name.is_none());
     }
     
+    #[test]
+    fn test_pdf_protection() {
+        let protection: PdfProtection = serde_json::from_str(r#"{"owner_password": "owner"}"#).unwrap();
+        assert!(protection.allow_printing);
+        assert!(protection.validate().is_ok());
+        
+        let same = PdfProtection { user_password: Some("owner".to_string()), ..protection.clone() };
+        assert!(same.validate().is_err());
+        assert!(!format!("{:?}", same).contains("owner"));
+        
+        let options = CertificateOptions { pdf_protection: Some(protection), ..CertificateOptions::default() };
+        assert!(!serde_json::to_string(&options).unwrap().contains("owner"));
+    }
+    
+    #[test]
+    fn test_certificate_format_from_str() {
+        assert_eq!("PDF".parse::<CertificateFormat>().unwrap(), CertificateFormat::PDF);
+        assert_eq!("both".parse::<CertificateFormat>().unwrap(), CertificateFormat::Both);
+        assert!("html".parse::<CertificateFormat>().is_err());
+    }
+    
     #[test]
     fn test_certificate_format_display() {
         assert_eq!(CertificateFormat::PDF.to_string(), "PDF");
@@ -0,0 +1,285 @@
+//! Certificate file naming
+//!
+//! Output files are named from a template such as
+//! `{date}/{asset_tag}_{serial}` so certificates land directly in the
+//! directory structure a customer mandates. Placeholder values are sanitized
+//! into single path components; `/` in the template itself creates
+//! subdirectories below the output directory. The extension is appended
+//! from the output format.
+
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::certificate::CertificateData;
+use crate::error::{CertificateError, Result};
+
+/// Template that reproduces the historical `wipe_certificate_<uuid>` names
+pub const DEFAULT_TEMPLATE: &str = "wipe_certificate_{certificate_id}";
+
+/// Placeholders accepted in a naming template
+pub const PLACEHOLDERS: [&str; 9] = [
+    "certificate_id",
+    "serial",
+    "model",
+    "asset_tag",
+    "work_order",
+    "algorithm",
+    "date",
+    "time",
+    "format",
+];
+
+/// What to do when the rendered file name already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Append `-2`, `-3`, ... before the extension
+    #[default]
+    Suffix,
+    /// Replace the existing file
+    Overwrite,
+    /// Fail certificate generation
+    Error,
+}
+
+/// File naming settings for generated certificates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileNaming {
+    pub template: String,
+    pub on_collision: CollisionPolicy,
+}
+
+impl Default for FileNaming {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_TEMPLATE.to_string(),
+            on_collision: CollisionPolicy::default(),
+        }
+    }
+}
+
+impl FileNaming {
+    /// Check the template for unknown placeholders and paths escaping the output directory
+    pub fn validate(&self) -> Result<()> {
+        let mut rest = self.template.as_str();
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| template_error(&self.template, "unclosed '{'"))?;
+            let name = &rest[open + 1..open + close];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(template_error(&self.template, &format!("unknown placeholder {{{}}}", name)));
+            }
+            rest = &rest[open + close + 1..];
+        }
+        
+        let path = Path::new(&self.template);
+        let escapes = path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)));
+        if self.template.trim().is_empty() || escapes || self.template.ends_with('/') {
+            return Err(template_error(
+                &self.template,
+                "must be a relative file name without '..' components",
+            ));
+        }
+        Ok(())
+    }
+    
+    /// File name (relative to the output directory) for a certificate in `format`, e.g. `pdf`
+    pub fn render(&self, data: &CertificateData, format: &str) -> Result<PathBuf> {
+        self.validate()?;
+        let mut name = self.template.clone();
+        for placeholder in PLACEHOLDERS {
+            let token = format!("{{{}}}", placeholder);
+            if name.contains(&token) {
+                name = name.replace(&token, &sanitize(&placeholder_value(data, placeholder, format)));
+            }
+        }
+        Ok(PathBuf::from(format!("{}.{}", name, format)))
+    }
+    
+    /// Render the name below `output_dir`, create missing subdirectories and
+    /// apply the collision policy. With [`CollisionPolicy::Suffix`] and
+    /// [`CollisionPolicy::Error`] the returned path is reserved by creating an
+    /// empty file, so concurrent jobs cannot pick the same name.
+    pub fn resolve(&self, data: &CertificateData, format: &str, output_dir: &Path) -> Result<PathBuf> {
+        let path = output_dir.join(self.render(data, format)?);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                CertificateError::FileOperationFailed(format!("Cannot create {}: {}", parent.display(), e))
+            })?;
+        }
+        
+        match self.on_collision {
+            CollisionPolicy::Overwrite => Ok(path),
+            CollisionPolicy::Error => reserve(&path)?.ok_or_else(|| {
+                CertificateError::FileOperationFailed(format!("Certificate file already exists: {}", path.display()))
+            }),
+            CollisionPolicy::Suffix => {
+                let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                for attempt in 1u32.. {
+                    let candidate = if attempt == 1 {
+                        path.clone()
+                    } else {
+                        path.with_file_name(format!("{}-{}.{}", stem, attempt, format))
+                    };
+                    if let Some(reserved) = reserve(&candidate)? {
+                        return Ok(reserved);
+                    }
+                }
+                unreachable!("suffix attempts are unbounded")
+            }
+        }
+    }
+}
+
+/// Create the file if it does not exist yet; `None` when it does
+fn reserve(path: &Path) -> Result<Option<PathBuf>> {
+    match std::fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(_) => Ok(Some(path.to_path_buf())),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(CertificateError::FileOperationFailed(format!(
+            "Cannot create {}: {}", path.display(), e
+        ))),
+    }
+}
+
+fn placeholder_value(data: &CertificateData, placeholder: &str, format: &str) -> String {
+    match placeholder {
+        "certificate_id" => data.certificate_id.to_string(),
+        "serial" => data.device_info.serial.clone(),
+        "model" => data.device_info.model.clone(),
+        "asset_tag" => data.metadata.get("asset_tag").cloned().unwrap_or_default(),
+        "work_order" => data.work_order.clone().unwrap_or_default(),
+        "algorithm" => data.wipe_info.algorithm.to_string(),
+        "date" => data.generated_at.format("%Y-%m-%d").to_string(),
+        "time" => data.generated_at.format("%H%M%S").to_string(),
+        "format" => format.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Reduce a value to one safe path component; empty values become `unknown`
+fn sanitize(value: &str) -> String {
+    let sanitized: String = value
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+    if sanitized.is_empty() {
+        "unknown".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+fn template_error(template: &str, reason: &str) -> CertificateError {
+    CertificateError::TemplateParsingFailed(format!("File name template '{}': {}", template, reason))
+}
+
+impl std::str::FromStr for CollisionPolicy {
+    type Err = CertificateError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "suffix" => Ok(CollisionPolicy::Suffix),
+            "overwrite" => Ok(CollisionPolicy::Overwrite),
+            "error" => Ok(CollisionPolicy::Error),
+            other => Err(CertificateError::InvalidConfiguration(format!("Unknown collision policy: {}", other))),
+        }
+    }
+}
+
+impl std::fmt::Display for CollisionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollisionPolicy::Suffix => write!(f, "suffix"),
+            CollisionPolicy::Overwrite => write!(f, "overwrite"),
+            CollisionPolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+    use crate::certificate::{DeviceInfo, WipeInfo};
+    
+    fn data() -> CertificateData {
+        CertificateData {
+            certificate_id: Uuid::nil(),
+            generated_at: chrono::Utc.with_ymd_and_hms(2024, 5, 1, 13, 4, 5).unwrap(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "WD/1234 X".to_string(),
+                model: "Test SSD".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::ZeroFill,
+                started_at: chrono::Utc::now(),
+                completed_at: None,
+                duration: None,
+                passes_completed: 1,
+                verification_passed: None,
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::from([("asset_tag".to_string(), "../TAG-7".to_string())]),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        }
+    }
+    
+    #[test]
+    fn test_render_placeholders() {
+        let naming = FileNaming::default();
+        assert_eq!(
+            naming.render(&data(), "pdf").unwrap(),
+            PathBuf::from(format!("wipe_certificate_{}.pdf", Uuid::nil()))
+        );
+        
+        let naming = FileNaming {
+            template: "{date}/{asset_tag}_{serial}_{work_order}_{time}".to_string(),
+            ..FileNaming::default()
+        };
+        assert_eq!(
+            naming.render(&data(), "json").unwrap(),
+            PathBuf::from("2024-05-01/_TAG-7_WD_1234_X_unknown_130405.json")
+        );
+        
+        for template in ["{owner}", "/srv/{serial}", "../{serial}", "{serial"] {
+            let naming = FileNaming { template: template.to_string(), ..FileNaming::default() };
+            assert!(naming.validate().is_err(), "{}", template);
+        }
+    }
+    
+    #[test]
+    fn test_collision_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = FileNaming { template: "{date}/{model}".to_string(), ..FileNaming::default() };
+        
+        let first = naming.resolve(&data(), "pdf", dir.path()).unwrap();
+        let second = naming.resolve(&data(), "pdf", dir.path()).unwrap();
+        assert_eq!(first, dir.path().join("2024-05-01/Test_SSD.pdf"));
+        assert_eq!(second, dir.path().join("2024-05-01/Test_SSD-2.pdf"));
+        
+        let strict = FileNaming { on_collision: CollisionPolicy::Error, ..naming.clone() };
+        assert!(strict.resolve(&data(), "pdf", dir.path()).is_err());
+        let overwrite = FileNaming { on_collision: CollisionPolicy::Overwrite, ..naming };
+        assert_eq!(overwrite.resolve(&data(), "pdf", dir.path()).unwrap(), first);
+    }
+}
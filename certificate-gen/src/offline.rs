@@ -0,0 +1,233 @@
+//! Self-verifying QR payload for printed certificates
+//!
+//! The QR code carries a compact JWS (RFC 7515, `RS256`) whose claims
+//! summarise the certificate and include the SHA-256 hash of its canonical
+//! form. Anyone holding the issuer's public key can check the signature from
+//! a scan alone, without reaching the verification service; the hash then
+//! ties the printed page to the full JSON certificate when that is at hand.
+//! The token is placed in the fragment of the verification URL, so phones
+//! still open the online page while the fragment never leaves the device.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::canonical;
+use crate::certificate::SignedCertificate;
+use crate::crypto::{CertificateSigner, CertificateVerifier};
+use crate::error::{CertificateError, Result};
+
+/// JWS `typ` header of QR payloads
+pub const PAYLOAD_TYPE: &str = "safeerase-qr+jws";
+
+/// Claims carried in the QR code; short names keep the code scannable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfflineClaims {
+    #[serde(rename = "cid")]
+    pub certificate_id: Uuid,
+    /// SHA-256 of the canonical certificate, as in `SignatureInfo::certificate_hash`
+    #[serde(rename = "sha")]
+    pub certificate_hash: String,
+    #[serde(rename = "srl")]
+    pub serial: String,
+    #[serde(rename = "mdl")]
+    pub model: String,
+    #[serde(rename = "sz")]
+    pub size: u64,
+    #[serde(rename = "alg")]
+    pub algorithm: String,
+    /// Completion time in seconds since the Unix epoch
+    #[serde(rename = "cmp", default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<i64>,
+    #[serde(rename = "vfy", default, skip_serializing_if = "Option::is_none")]
+    pub verification_passed: Option<bool>,
+    /// Signing time in seconds since the Unix epoch
+    #[serde(rename = "iat")]
+    pub issued_at: i64,
+}
+
+impl OfflineClaims {
+    /// Claims describing a signed certificate
+    pub fn from_certificate(signed: &SignedCertificate) -> Self {
+        let data = &signed.certificate.data;
+        Self {
+            certificate_id: data.certificate_id,
+            certificate_hash: signed.signature_info.certificate_hash.clone(),
+            serial: data.device_info.serial.clone(),
+            model: data.device_info.model.clone(),
+            size: data.device_info.size,
+            algorithm: data.wipe_info.algorithm.to_string(),
+            completed_at: data.wipe_info.completed_at.map(|t| t.timestamp()),
+            verification_passed: data.wipe_info.verification_passed,
+            issued_at: signed.signed_at.timestamp(),
+        }
+    }
+    
+    /// Whether these claims were issued for the given full certificate
+    pub fn matches(&self, signed: &SignedCertificate) -> bool {
+        self.certificate_id == signed.certificate_id()
+            && self.certificate_hash == signed.signature_info.certificate_hash
+    }
+    
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at.and_then(|secs| DateTime::from_timestamp(secs, 0))
+    }
+}
+
+/// Signed compact token for a certificate; requires an RSA signing key
+pub fn encode(signed: &SignedCertificate, signer: &CertificateSigner) -> Result<String> {
+    if !signer.algorithm().is_rsa() {
+        return Err(CertificateError::NotSupported(format!(
+            "QR payloads signed with {}", signer.algorithm()
+        )));
+    }
+    let header = json!({ "alg": "RS256", "typ": PAYLOAD_TYPE, "kid": signer.key_id() });
+    let claims = canonical::canonicalize(&OfflineClaims::from_certificate(signed))
+        .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))?;
+    let signing_input = format!(
+        "{}.{}",
+        base64url_encode(canonical::to_canonical_string(&header).as_bytes()),
+        base64url_encode(claims.as_bytes())
+    );
+    let signature = signer.sign_bytes(signing_input.as_bytes())?;
+    Ok(format!("{}.{}", signing_input, base64url_encode(&signature)))
+}
+
+/// Check a token against the verifier's trusted keys and return its key ID and claims
+pub fn decode(token: &str, verifier: &CertificateVerifier) -> Result<(String, OfflineClaims)> {
+    let invalid = |reason: &str| CertificateError::InvalidCertificateFormat(format!("QR payload: {}", reason));
+    
+    let token = token_from_qr(token);
+    let mut parts = token.split('.');
+    let (Some(header), Some(claims), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid("expected three dot-separated parts"));
+    };
+    
+    let header: serde_json::Value = serde_json::from_slice(&base64url_decode(header)?)
+        .map_err(|e| invalid(&e.to_string()))?;
+    if header["alg"] != "RS256" || header["typ"] != PAYLOAD_TYPE {
+        return Err(invalid("unsupported algorithm or type"));
+    }
+    let key_id = header["kid"].as_str().ok_or_else(|| invalid("missing key ID"))?.to_string();
+    
+    let signing_input = &token[..token.len() - signature.len() - 1];
+    if !verifier.verify_bytes(&key_id, signing_input.as_bytes(), &base64url_decode(signature)?)? {
+        return Err(CertificateError::SignatureVerificationFailed);
+    }
+    
+    let claims = serde_json::from_slice(&base64url_decode(claims)?)
+        .map_err(|e| CertificateError::JsonDeserializationFailed(e.to_string()))?;
+    Ok((key_id, claims))
+}
+
+/// Text encoded in the QR code: the verification URL with the token as fragment
+pub fn qr_content(verification_url: &str, token: &str) -> String {
+    format!("{}#{}", verification_url, token)
+}
+
+/// Token part of scanned QR content; bare tokens are returned unchanged
+pub fn token_from_qr(content: &str) -> &str {
+    content.rsplit_once('#').map_or(content, |(_, token)| token).trim()
+}
+
+fn base64url_encode(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+fn base64url_decode(data: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(data.trim_end_matches('='))
+        .map_err(|e| CertificateError::InvalidCertificateFormat(format!("QR payload: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::keys::PublicKey;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate() -> SignedCertificate {
+        let data = CertificateData {
+            certificate_id: Uuid::new_v4(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "TEST123".to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: String::new(),
+            timestamp: Utc::now(),
+            certificate_hash: "ab".repeat(32),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    fn verifier_for(signer: &CertificateSigner) -> CertificateVerifier {
+        let pem = signer.get_key_info().unwrap().public_key_pem;
+        let mut verifier = CertificateVerifier::new().unwrap();
+        verifier.add_trusted_key(signer.key_id().to_string(), PublicKey::from_pem(&pem).unwrap());
+        verifier
+    }
+    
+    #[test]
+    fn test_offline_roundtrip() {
+        let signer = CertificateSigner::new().unwrap();
+        let signed = signed_certificate();
+        let token = encode(&signed, &signer).unwrap();
+        let content = qr_content("https://verify.safeerase.com/certificate/x", &token);
+        
+        let (key_id, claims) = decode(&content, &verifier_for(&signer)).unwrap();
+        assert_eq!(key_id, signer.key_id());
+        assert!(claims.matches(&signed));
+        assert_eq!(claims.serial, "TEST123");
+        assert_eq!(claims.verification_passed, Some(true));
+        // An RSA-2048 token must stay well within a QR code's capacity
+        assert!(content.len() < 1200, "{}", content.len());
+    }
+    
+    #[test]
+    fn test_tampered_or_untrusted_tokens_rejected() {
+        let signer = CertificateSigner::new().unwrap();
+        let token = encode(&signed_certificate(), &signer).unwrap();
+        
+        let parts: Vec<&str> = token.split('.').collect();
+        let mut claims: serde_json::Value =
+            serde_json::from_slice(&base64url_decode(parts[1]).unwrap()).unwrap();
+        claims["srl"] = json!("OTHER");
+        let forged = format!("{}.{}.{}", parts[0], base64url_encode(claims.to_string().as_bytes()), parts[2]);
+        assert!(decode(&forged, &verifier_for(&signer)).is_err());
+        
+        let stranger = CertificateSigner::new().unwrap();
+        assert!(decode(&token, &verifier_for(&stranger)).is_err());
+    }
+}
@@ -0,0 +1,696 @@
+//! PDF certificate output
+//!
+//! Certificates are drawn with the standard Helvetica fonts, so no font files
+//! are embedded and the output is the same on every platform. The page shows
+//! the certificate's [structure](crate::accessibility::structure_tree) in
+//! reading order: title, device and sanitization tables, compliance and
+//! signature. The pages after it are the detail pages of the certificate's
+//! [layout](crate::layout::PageLayout), such as the table of passes, and
+//! every page carries a "Page 2 of 3" footer. The document is tagged:
+//! everything drawn apart from the footers is marked content belonging to an
+//! element of that structure, so screen readers announce the pages in the
+//! same order. With [`PdfProtection`](crate::PdfProtection) set,
+//! every string and stream is encrypted with AES-256.
+
+use std::path::Path;
+
+use pdf_writer::types::{ArtifactType, TabOrder, TableHeaderScope};
+use pdf_writer::writers::StructTreeRoot;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use uuid::Uuid;
+
+use crate::accessibility::{self, StructureNode, StructureRole};
+use crate::certificate::SignedCertificate;
+use crate::encryption::PdfEncryption;
+use crate::error::{CertificateError, Result};
+use crate::layout::{Page, PageKind, PageLayout, PASS_COLUMNS};
+use crate::CertificateOptions;
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+/// Baseline of the page number footer
+const FOOTER_Y: f32 = 28.0;
+/// Width of the row header column of key-value tables
+const KEY_COLUMN: f32 = 140.0;
+
+const JOB_REPORT_TITLE: &str = "Sanitization Job Report";
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// Draws signed certificates as PDF documents
+#[derive(Debug, Default)]
+pub struct PdfGenerator;
+
+/// Text cursor moving down the pages, starting a new one when it reaches
+/// the bottom margin
+struct Cursor {
+    /// Content of the pages already filled
+    pages: Vec<Content>,
+    content: Content,
+    y: f32,
+    /// Identifier of the next marked-content sequence on the page
+    next_mcid: i32,
+}
+
+/// Structure element as drawn, with the marked content it owns
+struct Tagged {
+    role: StructureRole,
+    alt_text: Option<String>,
+    /// Set on table header cells
+    scope: Option<TableHeaderScope>,
+    kids: Vec<TaggedKid>,
+}
+
+enum TaggedKid {
+    Element(Tagged),
+    /// Marked-content identifier on the page with the given index
+    Content { page: usize, mcid: i32 },
+}
+
+/// Kid of a structure element already written to the file
+enum WrittenKid {
+    Element(Ref),
+    Content { page: usize, mcid: i32 },
+}
+
+/// Writes structure elements, numbering them after the pages
+struct StructureWriter<'a> {
+    encryption: Option<&'a PdfEncryption>,
+    page_ids: &'a [Ref],
+    next_id: Ref,
+    /// Element owning each marked-content sequence, per page and indexed by identifier
+    parent_trees: Vec<Vec<Option<Ref>>>,
+}
+
+impl PdfGenerator {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+    
+    /// The certificate as PDF bytes
+    pub fn render(&self, certificate: &SignedCertificate, options: &CertificateOptions) -> Result<Vec<u8>> {
+        let tree = accessibility::structure_tree(certificate, &options.accessibility, options.include_qr_code);
+        let mut cursor = Cursor::new();
+        let mut document = cursor.draw(&tree);
+        // The structure tree already shows the summary; the layout adds the detail pages
+        for page in PageLayout::default().certificate_pages(certificate)? {
+            if page.kind != PageKind::Summary {
+                document.kids.extend(cursor.detail_page(&page));
+            }
+        }
+        let title = options.accessibility.document_title();
+        Self::assemble(document, cursor.finish(), options, &title, certificate.certificate_id())
+    }
+    
+    /// A job report as PDF bytes: an overview of the devices, then each
+    /// device's summary and passes on pages of its own
+    pub fn render_job_report(&self, certificates: &[SignedCertificate], options: &CertificateOptions) -> Result<Vec<u8>> {
+        let mut cursor = Cursor::new();
+        let mut document = Tagged::new(StructureRole::Document);
+        let mut heading = Tagged::new(StructureRole::H1);
+        heading.kids.push(cursor.heading(StructureRole::H1, JOB_REPORT_TITLE, 18.0));
+        document.kids.push(TaggedKid::Element(heading));
+        for page in PageLayout::default().job_report_pages(certificates)? {
+            document.kids.extend(cursor.detail_page(&page));
+        }
+        Self::assemble(document, cursor.finish(), options, JOB_REPORT_TITLE, Uuid::new_v4())
+    }
+    
+    /// Write the drawn pages and their structure as a PDF file
+    fn assemble(document: Tagged, contents: Vec<Content>, options: &CertificateOptions, title: &str, file_id: Uuid) -> Result<Vec<u8>> {
+        let encryption = options.pdf_protection.as_ref().map(PdfEncryption::new).transpose()?;
+        let encryption = encryption.as_ref();
+        
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let encrypt_id = Ref::new(6);
+        let struct_tree_id = Ref::new(7);
+        let mut next_id = struct_tree_id.next();
+        let page_ids: Vec<Ref> = contents.iter().map(|_| next_id.bump()).collect();
+        
+        let mut pdf = Pdf::new();
+        let language = sealed(encryption, text_string(&options.accessibility.language))?;
+        let mut catalog = pdf.catalog(catalog_id);
+        catalog.pages(page_tree_id);
+        catalog.pair(Name(b"StructTreeRoot"), struct_tree_id);
+        catalog.mark_info().marked(true);
+        catalog.pair(Name(b"Lang"), Str(&language));
+        catalog.viewer_preferences().pair(Name(b"DisplayDocTitle"), true);
+        catalog.finish();
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+        let total = contents.len();
+        for (index, mut content) in contents.into_iter().enumerate() {
+            footer(&mut content, index + 1, total);
+            let content_id = next_id.bump();
+            let mut page = pdf.page(page_ids[index]);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            page.struct_parents(index as i32);
+            page.tab_order(TabOrder::StructureOrder);
+            page.resources().fonts().pair(REGULAR, regular_id).pair(BOLD, bold_id);
+            page.finish();
+            pdf.stream(content_id, &sealed(encryption, content.finish())?);
+        }
+        pdf.type1_font(regular_id).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        
+        let mut structure = StructureWriter {
+            encryption,
+            page_ids: &page_ids,
+            next_id,
+            parent_trees: vec![Vec::new(); page_ids.len()],
+        };
+        let (document_id, _) = structure.write(&mut pdf, &document, struct_tree_id)?;
+        let mut root = pdf.indirect(struct_tree_id).start::<StructTreeRoot>();
+        root.child(document_id);
+        let mut entries = root.insert(Name(b"ParentTree")).dict();
+        let mut numbers = entries.insert(Name(b"Nums")).array();
+        for (index, parents) in structure.parent_trees.into_iter().enumerate() {
+            numbers.item(index as i32);
+            numbers.push().array().items(parents.into_iter().flatten());
+        }
+        numbers.finish();
+        entries.finish();
+        root.parent_tree_next_key(page_ids.len() as i32);
+        root.finish();
+        
+        let title = sealed(encryption, text_string(title))?;
+        let producer = sealed(encryption, text_string(concat!("SafeErase ", env!("CARGO_PKG_VERSION"))))?;
+        pdf.document_info(info_id)
+            .pair(Name(b"Title"), Str(&title))
+            .pair(Name(b"Producer"), Str(&producer));
+        
+        let Some(encryption) = encryption else {
+            return Ok(pdf.finish());
+        };
+        // AES-256 is a PDF 2.0 feature; its handler needs no file ID, but readers expect one
+        pdf.set_version(2, 0);
+        let file_id = file_id.as_bytes().to_vec();
+        pdf.set_file_id((file_id.clone(), file_id));
+        encryption.write_dictionary(&mut pdf, encrypt_id);
+        PdfEncryption::link_trailer(pdf.finish(), encrypt_id)
+    }
+    
+    /// Write the certificate to `path`
+    pub async fn generate_certificate(
+        &self,
+        certificate: &SignedCertificate,
+        options: &CertificateOptions,
+        path: &Path,
+    ) -> Result<()> {
+        let bytes = self.render(certificate, options)?;
+        std::fs::write(path, bytes)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))
+    }
+    
+    /// Write the job report of `certificates` to `path`
+    pub async fn generate_job_report(
+        &self,
+        certificates: &[SignedCertificate],
+        options: &CertificateOptions,
+        path: &Path,
+    ) -> Result<()> {
+        let bytes = self.render_job_report(certificates, options)?;
+        std::fs::write(path, bytes)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))
+    }
+}
+
+impl Cursor {
+    fn new() -> Self {
+        Self { pages: Vec::new(), content: Content::new(), y: PAGE_HEIGHT - MARGIN, next_mcid: 0 }
+    }
+    
+    /// Content of every page drawn
+    fn finish(mut self) -> Vec<Content> {
+        self.pages.push(self.content);
+        self.pages
+    }
+    
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::replace(&mut self.content, Content::new()));
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.next_mcid = 0;
+    }
+    
+    /// Start a new page unless `height` points still fit above the bottom margin
+    fn ensure_room(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+    
+    /// A detail page of the layout on a page of its own: the title, then the
+    /// table with its column headers
+    fn detail_page(&mut self, page: &Page) -> Vec<TaggedKid> {
+        // Only the job report title shares its page with the first table
+        if self.next_mcid > 1 {
+            self.new_page();
+        }
+        let mut heading = Tagged::new(StructureRole::H2);
+        heading.kids.push(self.heading(StructureRole::H2, &page.title, 13.0));
+        let mut table = Tagged::new(StructureRole::Table);
+        table.kids.push(TaggedKid::Element(self.grid_row(StructureRole::TH, &page.header)));
+        for row in &page.rows {
+            // Device pages head the pass rows with their own header row
+            let role = if row.iter().eq(PASS_COLUMNS.iter()) { StructureRole::TH } else { StructureRole::TD };
+            table.kids.push(TaggedKid::Element(self.grid_row(role, row)));
+        }
+        vec![TaggedKid::Element(heading), TaggedKid::Element(table)]
+    }
+    
+    fn draw(&mut self, node: &StructureNode) -> Tagged {
+        let mut tagged = Tagged::new(node.role);
+        match node.role {
+            StructureRole::H1 => tagged.kids.push(self.heading(node.role, node.text.as_deref().unwrap_or_default(), 18.0)),
+            StructureRole::H2 => tagged.kids.push(self.heading(node.role, node.text.as_deref().unwrap_or_default(), 13.0)),
+            StructureRole::Table => {
+                for row in &node.children {
+                    tagged.kids.push(TaggedKid::Element(self.row(row)));
+                }
+                self.y -= 6.0;
+            }
+            // Figures are described by their alternative text until images are drawn
+            StructureRole::Figure => {
+                tagged.alt_text = node.alt_text.clone();
+                if let Some(alt_text) = &node.alt_text {
+                    self.ensure_room(text_height(alt_text, PAGE_WIDTH - 2.0 * MARGIN, 9.0));
+                    tagged.kids.push(self.marked(node.role, |cursor| {
+                        cursor.text(REGULAR, 9.0, MARGIN, PAGE_WIDTH - 2.0 * MARGIN, alt_text);
+                    }));
+                }
+            }
+            _ => {
+                if let Some(text) = &node.text {
+                    self.ensure_room(text_height(text, PAGE_WIDTH - 2.0 * MARGIN, 10.0));
+                    tagged.kids.push(self.marked(node.role, |cursor| {
+                        cursor.text(REGULAR, 10.0, MARGIN, PAGE_WIDTH - 2.0 * MARGIN, text);
+                    }));
+                }
+                for child in &node.children {
+                    tagged.kids.push(TaggedKid::Element(self.draw(child)));
+                }
+            }
+        }
+        tagged
+    }
+    
+    /// Heading, kept on the same page as the first line below it
+    fn heading(&mut self, role: StructureRole, text: &str, size: f32) -> TaggedKid {
+        self.ensure_room(size * 0.9 + text_height(text, PAGE_WIDTH - 2.0 * MARGIN, size) + 13.0);
+        self.y -= size * 0.6;
+        let kid = self.marked(role, |cursor| cursor.text(BOLD, size, MARGIN, PAGE_WIDTH - 2.0 * MARGIN, text));
+        self.y -= size * 0.3;
+        kid
+    }
+    
+    /// Table row: the header cell in bold on the left, the data cell beside it
+    fn row(&mut self, row: &StructureNode) -> Tagged {
+        let height = row
+            .children
+            .iter()
+            .map(|cell| {
+                let width = if cell.role == StructureRole::TH { KEY_COLUMN } else { PAGE_WIDTH - 2.0 * MARGIN - KEY_COLUMN };
+                text_height(cell.text.as_deref().unwrap_or_default(), width, 10.0)
+            })
+            .fold(0.0, f32::max);
+        self.ensure_room(height);
+        let mut tagged = Tagged::new(row.role);
+        let top = self.y;
+        let mut bottom = top;
+        let mut x = MARGIN;
+        for cell in &row.children {
+            let mut tagged_cell = Tagged::new(cell.role);
+            let (font, width) = if cell.role == StructureRole::TH {
+                tagged_cell.scope = Some(TableHeaderScope::Row);
+                (BOLD, KEY_COLUMN)
+            } else {
+                (REGULAR, PAGE_WIDTH - MARGIN - x)
+            };
+            self.y = top;
+            let text = cell.text.as_deref().unwrap_or_default();
+            tagged_cell.kids.push(self.marked(cell.role, |cursor| cursor.text(font, 10.0, x, width, text)));
+            tagged.kids.push(TaggedKid::Element(tagged_cell));
+            bottom = bottom.min(self.y);
+            x += width;
+        }
+        self.y = bottom;
+        tagged
+    }
+    
+    /// Row of a table with column headers: the columns share the page width
+    fn grid_row(&mut self, role: StructureRole, cells: &[String]) -> Tagged {
+        let width = (PAGE_WIDTH - 2.0 * MARGIN) / cells.len().max(1) as f32;
+        let (font, scope) = if role == StructureRole::TH { (BOLD, Some(TableHeaderScope::Column)) } else { (REGULAR, None) };
+        self.ensure_room(cells.iter().map(|cell| text_height(cell, width, 9.0)).fold(0.0, f32::max));
+        let mut tagged = Tagged::new(StructureRole::TR);
+        let top = self.y;
+        let mut bottom = top;
+        for (column, text) in cells.iter().enumerate() {
+            let x = MARGIN + column as f32 * width;
+            let mut tagged_cell = Tagged::new(role);
+            tagged_cell.scope = scope;
+            self.y = top;
+            tagged_cell.kids.push(self.marked(role, |cursor| cursor.text(font, 9.0, x, width - 4.0, text)));
+            tagged.kids.push(TaggedKid::Element(tagged_cell));
+            bottom = bottom.min(self.y);
+        }
+        self.y = bottom - 2.0;
+        tagged
+    }
+    
+    /// Draw content as the next marked-content sequence, tagged with `role`
+    fn marked(&mut self, role: StructureRole, draw: impl FnOnce(&mut Self)) -> TaggedKid {
+        let mcid = self.next_mcid;
+        self.next_mcid += 1;
+        self.content
+            .begin_marked_content_with_properties(Name(role.pdf_name().as_bytes()))
+            .properties()
+            .identify(mcid);
+        draw(self);
+        self.content.end_marked_content();
+        TaggedKid::Content { page: self.pages.len(), mcid }
+    }
+    
+    /// Draw wrapped text starting at the cursor and move the cursor below it
+    fn text(&mut self, font: Name, size: f32, x: f32, width: f32, text: &str) {
+        let leading = size * 1.3;
+        for line in wrap(text, width, size) {
+            self.y -= leading;
+            self.content
+                .begin_text()
+                .set_font(font, size)
+                .next_line(x, self.y)
+                .show(Str(&win_ansi(&line)))
+                .end_text();
+        }
+    }
+}
+
+impl Tagged {
+    fn new(role: StructureRole) -> Self {
+        Self { role, alt_text: None, scope: None, kids: Vec::new() }
+    }
+}
+
+impl StructureWriter<'_> {
+    /// Write `tagged` under `parent`, its descendants first; returns its
+    /// object and the index of the page it starts on
+    fn write(&mut self, pdf: &mut Pdf, tagged: &Tagged, parent: Ref) -> Result<(Ref, usize)> {
+        let id = self.next_id.bump();
+        let mut page = None;
+        let mut kids = Vec::with_capacity(tagged.kids.len());
+        for kid in &tagged.kids {
+            match kid {
+                TaggedKid::Element(child) => {
+                    let (child_id, child_page) = self.write(pdf, child, id)?;
+                    page.get_or_insert(child_page);
+                    kids.push(WrittenKid::Element(child_id));
+                }
+                &TaggedKid::Content { page: content_page, mcid } => {
+                    let parents = &mut self.parent_trees[content_page];
+                    let index = mcid as usize;
+                    if parents.len() <= index {
+                        parents.resize(index + 1, None);
+                    }
+                    parents[index] = Some(id);
+                    page.get_or_insert(content_page);
+                    kids.push(WrittenKid::Content { page: content_page, mcid });
+                }
+            }
+        }
+        let page = page.unwrap_or_default();
+        let alt_text = tagged.alt_text.as_deref().map(|alt_text| sealed(self.encryption, text_string(alt_text))).transpose()?;
+        
+        let mut element = pdf.struct_element(id);
+        element.custom_kind(Name(tagged.role.pdf_name().as_bytes()));
+        element.parent(parent);
+        element.page(self.page_ids[page]);
+        if let Some(alt_text) = &alt_text {
+            element.pair(Name(b"Alt"), Str(alt_text));
+        }
+        if let Some(scope) = tagged.scope {
+            element.attributes().push().table().scope(scope);
+        }
+        let mut children = element.children();
+        for kid in kids {
+            match kid {
+                WrittenKid::Element(child_id) => {
+                    children.struct_element(child_id);
+                }
+                // Content on another page than the element's names its page
+                WrittenKid::Content { page: content_page, mcid } if content_page != page => {
+                    children.marked_content_ref().page(self.page_ids[content_page]).marked_content_id(mcid);
+                }
+                WrittenKid::Content { mcid, .. } => {
+                    children.marked_content_id(mcid);
+                }
+            }
+        }
+        Ok((id, page))
+    }
+}
+
+/// Draw the page number at the foot of a page, marked as a pagination
+/// artifact so screen readers skip it
+fn footer(content: &mut Content, number: usize, total: usize) {
+    content
+        .begin_marked_content_with_properties(Name(b"Artifact"))
+        .properties()
+        .artifact()
+        .kind(ArtifactType::Pagination);
+    content
+        .begin_text()
+        .set_font(REGULAR, 8.0)
+        .next_line(MARGIN, FOOTER_Y)
+        .show(Str(format!("Page {} of {}", number, total).as_bytes()))
+        .end_text();
+    content.end_marked_content();
+}
+
+/// A string or stream as written to the file, encrypted when the document is protected
+fn sealed(encryption: Option<&PdfEncryption>, data: Vec<u8>) -> Result<Vec<u8>> {
+    match encryption {
+        Some(encryption) => encryption.encrypt(&data),
+        None => Ok(data),
+    }
+}
+
+/// Encode a text string outside content streams: ASCII as is, anything else as UTF-16BE
+fn text_string(text: &str) -> Vec<u8> {
+    if text.is_ascii() {
+        return text.as_bytes().to_vec();
+    }
+    [0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect()
+}
+
+/// Height of `text` drawn wrapped at `width`
+fn text_height(text: &str, width: f32, size: f32) -> f32 {
+    wrap(text, width, size).len() as f32 * size * 1.3
+}
+
+/// Break text into lines of at most `width` points, estimating Helvetica's
+/// average glyph width as half the font size
+fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+    let max_chars = ((width / (size * 0.5)) as usize).max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        // Break words longer than a line, such as hashes and paths
+        while word.chars().count() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let split = word.char_indices().nth(max_chars).map_or(word.len(), |(index, _)| index);
+            lines.push(word[..split].to_string());
+            word = &word[split..];
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Encode text for the standard fonts; characters outside Latin-1 become `?`
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars().map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?')).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate() -> SignedCertificate {
+        let data = CertificateData {
+            certificate_id: Uuid::nil(),
+            generated_at: Utc::now(),
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: "TEST123".to_string(),
+                model: "Test Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: Utc::now(),
+                completed_at: Some(Utc::now()),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: HashMap::new(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: "0011223344556677".to_string(),
+            timestamp: Utc::now(),
+            certificate_hash: String::new(),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle.as_bytes())
+    }
+    
+    #[test]
+    fn test_certificate_page_shows_device_and_method() {
+        let bytes = PdfGenerator::new().unwrap().render(&signed_certificate(), &CertificateOptions::default()).unwrap();
+        assert!(bytes.starts_with(b"%PDF-"));
+        assert!(contains(&bytes, "/Helvetica-Bold"));
+        assert!(contains(&bytes, "(TEST123)"));
+        assert!(contains(&bytes, "(Serial number)"));
+        assert!(contains(&bytes, "(Certificate of Data Sanitization)"));
+    }
+    
+    #[test]
+    fn test_page_is_tagged_in_reading_order() {
+        let bytes = PdfGenerator::new().unwrap().render(&signed_certificate(), &CertificateOptions::default()).unwrap();
+        assert!(contains(&bytes, "/StructTreeRoot 7 0 R"));
+        assert!(contains(&bytes, "/MarkInfo <<\n    /Marked true"));
+        assert!(contains(&bytes, "/Lang (en-US)"));
+        assert!(contains(&bytes, "/DisplayDocTitle true"));
+        assert!(contains(&bytes, "/StructParents 0"));
+        assert!(contains(&bytes, "/S /Document"));
+        assert!(contains(&bytes, "/Scope /Row"));
+        assert!(contains(&bytes, "/Alt (QR code linking"));
+        
+        // Every marked-content sequence is owned by an element in the parent tree
+        let marked = bytes.windows(b"/MCID ".len()).filter(|window| window == b"/MCID ").count();
+        let text = String::from_utf8_lossy(&bytes);
+        let parents = text.split("/Nums [0 [").nth(1).unwrap().split(']').next().unwrap();
+        assert!(marked > 10);
+        assert_eq!(parents.matches(" 0 R").count(), marked);
+        let title = text.find("/H1 <<\n  /MCID 0\n>> BDC").unwrap();
+        assert!(title < text.find("(Certificate of Data Sanitization) Tj").unwrap());
+        assert!(contains(&bytes, "/Artifact <<\n  /Type /Pagination\n>> BDC"));
+        assert!(contains(&bytes, "(Page 1 of 1) Tj"));
+    }
+    
+    #[test]
+    fn test_passes_continue_on_detail_pages() {
+        let passes: Vec<safe_erase_core::PassRecord> = (1..=40)
+            .map(|pass| safe_erase_core::PassRecord {
+                pass,
+                pattern: format!("Pattern {}", pass),
+                bytes_written: 1_000_000_000,
+                duration: std::time::Duration::from_secs(10),
+                average_speed: 100_000_000.0,
+                verified: None,
+            })
+            .collect();
+        let mut certificate = signed_certificate();
+        certificate.certificate.data.technical_details = Some(HashMap::from([
+            (crate::layout::PASSES_DETAIL.to_string(), serde_json::to_value(&passes).unwrap()),
+        ]));
+        let bytes = PdfGenerator::new().unwrap().render(&certificate, &CertificateOptions::default()).unwrap();
+        
+        // The summary, then 32 passes and the remaining 8 on a continuation page
+        assert!(contains(&bytes, "/Count 3"));
+        assert!(contains(&bytes, "(Passes (continued)) Tj"));
+        assert!(contains(&bytes, "(Pattern 40) Tj"));
+        assert!(contains(&bytes, "(Page 3 of 3) Tj"));
+        assert!(contains(&bytes, "/Scope /Column"));
+        assert!(contains(&bytes, "/StructParents 2"));
+        assert!(contains(&bytes, "/ParentTreeNextKey 3"));
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Nums [0 ["));
+        assert!(text.contains("] 1 [") && text.contains("] 2 ["));
+    }
+    
+    #[test]
+    fn test_job_report_has_page_per_device() {
+        let certificates = [signed_certificate(), signed_certificate()];
+        let bytes = PdfGenerator::new().unwrap().render_job_report(&certificates, &CertificateOptions::default()).unwrap();
+        
+        // The device overview shares the first page with the title
+        assert!(contains(&bytes, "/Count 3"));
+        assert!(contains(&bytes, "/Title (Sanitization Job Report)"));
+        assert!(contains(&bytes, "(Serial number) Tj"));
+        assert!(contains(&bytes, "(Test Drive TEST123) Tj"));
+        assert!(contains(&bytes, "(Page 3 of 3) Tj"));
+    }
+    
+    #[test]
+    fn test_protected_certificate_is_encrypted() {
+        let options = CertificateOptions {
+            pdf_protection: Some(crate::PdfProtection {
+                owner_password: "owner".to_string(),
+                user_password: None,
+                allow_printing: true,
+            }),
+            ..CertificateOptions::default()
+        };
+        let bytes = PdfGenerator::new().unwrap().render(&signed_certificate(), &options).unwrap();
+        assert!(bytes.starts_with(b"%PDF-2.0"));
+        assert!(contains(&bytes, "/Filter /Standard"));
+        assert!(contains(&bytes, "/CFM /AESV3"));
+        assert!(contains(&bytes, "/Encrypt 6 0 R"));
+        assert!(contains(&bytes, "/ID ["));
+        assert!(!contains(&bytes, "TEST123"));
+        assert!(!contains(&bytes, "Certificate of Data Sanitization"));
+    }
+    
+    #[test]
+    fn test_wrap_breaks_long_text() {
+        assert_eq!(wrap("short", 100.0, 10.0), vec!["short"]);
+        let lines = wrap(&"x".repeat(50), 100.0, 10.0);
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 20));
+        assert_eq!(wrap("one two three", 40.0, 10.0), vec!["one two", "three"]);
+        assert_eq!(win_ansi("Größe €"), b"Gr\xf6\xdfe ?".to_vec());
+    }
+}
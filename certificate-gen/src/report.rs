@@ -0,0 +1,219 @@
+//! Batch verification of certificate files
+//!
+//! Ingest pipelines verify whole directories of certificates and need to
+//! branch on why a file was rejected. Each file is classified as valid,
+//! carrying an invalid signature, signed by an unknown key, or not a
+//! well-formed certificate, and the report maps the most serious outcome to
+//! a process exit code.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::certificate::SignedCertificate;
+use crate::crypto::CertificateVerifier;
+use crate::error::CertificateError;
+
+/// Exit code when the command line or trust bundle is unusable
+pub const EXIT_USAGE: i32 = 2;
+
+/// Verification outcome of one certificate file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOutcome {
+    /// Signature is valid and made by a trusted key
+    Valid,
+    /// Signed by a key that is not in the trust bundle
+    UnknownKey,
+    /// The file could not be read
+    Unreadable,
+    /// Not a well-formed signed certificate
+    SchemaError,
+    /// The content was altered or the signature does not match
+    InvalidSignature,
+}
+
+/// Result for one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub outcome: FileOutcome,
+    pub certificate_id: Option<Uuid>,
+    pub device_serial: Option<String>,
+    pub key_id: Option<String>,
+    /// Why the file was rejected
+    pub message: Option<String>,
+}
+
+/// Results for a set of files
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub files: Vec<FileReport>,
+}
+
+impl FileOutcome {
+    /// Process exit code for this outcome
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FileOutcome::Valid => 0,
+            FileOutcome::InvalidSignature => 1,
+            FileOutcome::UnknownKey => 3,
+            FileOutcome::SchemaError => 4,
+            FileOutcome::Unreadable => 5,
+        }
+    }
+}
+
+impl VerificationReport {
+    /// Number of files with the given outcome
+    pub fn count(&self, outcome: FileOutcome) -> usize {
+        self.files.iter().filter(|file| file.outcome == outcome).count()
+    }
+    
+    /// Exit code of the most serious outcome; invalid signatures outrank
+    /// everything else since they indicate tampering
+    pub fn exit_code(&self) -> i32 {
+        self.files
+            .iter()
+            .map(|file| file.outcome)
+            .max()
+            .map_or(0, FileOutcome::exit_code)
+    }
+}
+
+/// Verify each file against the verifier's trusted keys
+pub async fn verify_files<P: AsRef<Path>>(verifier: &CertificateVerifier, paths: &[P]) -> VerificationReport {
+    let mut report = VerificationReport::default();
+    for path in paths {
+        report.files.push(verify_file(verifier, path.as_ref()).await);
+    }
+    report
+}
+
+async fn verify_file(verifier: &CertificateVerifier, path: &Path) -> FileReport {
+    let mut report = FileReport {
+        path: path.to_path_buf(),
+        outcome: FileOutcome::Valid,
+        certificate_id: None,
+        device_serial: None,
+        key_id: None,
+        message: None,
+    };
+    let mut reject = |outcome: FileOutcome, message: String| {
+        report.outcome = outcome;
+        report.message = Some(message);
+    };
+    
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            reject(FileOutcome::Unreadable, e.to_string());
+            return report;
+        }
+    };
+    let signed: SignedCertificate = match serde_json::from_str(&contents) {
+        Ok(signed) => signed,
+        Err(e) => {
+            reject(FileOutcome::SchemaError, format!("Not a signed certificate: {}", e));
+            return report;
+        }
+    };
+    let key_id = signed.signature_info.key_id.clone();
+    let (certificate_id, serial) = (signed.certificate_id(), signed.certificate.data.device_info.serial.clone());
+    let known_key = verifier.is_trusted_at(&key_id, signed.signature_info.timestamp);
+    
+    let result = verifier.verify_certificate_file(path).await;
+    match result {
+        _ if !known_key => reject(
+            FileOutcome::UnknownKey,
+            format!("Signed with untrusted key {} or outside its validity window", key_id),
+        ),
+        Ok(true) => {}
+        Ok(false) | Err(CertificateError::SignatureVerificationFailed) => {
+            reject(FileOutcome::InvalidSignature, "Signature does not match the certificate content".to_string())
+        }
+        Err(e) => reject(FileOutcome::SchemaError, e.to_string()),
+    }
+    report.certificate_id = Some(certificate_id);
+    report.device_serial = Some(serial);
+    report.key_id = Some(key_id);
+    report
+}
+
+impl std::fmt::Display for FileOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileOutcome::Valid => write!(f, "valid"),
+            FileOutcome::UnknownKey => write!(f, "unknown key"),
+            FileOutcome::Unreadable => write!(f, "unreadable"),
+            FileOutcome::SchemaError => write!(f, "schema error"),
+            FileOutcome::InvalidSignature => write!(f, "invalid signature"),
+        }
+    }
+}
+
+impl std::fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for file in &self.files {
+            write!(f, "{}: {}", file.path.display(), file.outcome)?;
+            if let Some(id) = file.certificate_id {
+                write!(f, " (certificate {})", id)?;
+            }
+            if let Some(message) = &file.message {
+                write!(f, " - {}", message)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} checked, {} valid, {} rejected",
+            self.files.len(),
+            self.count(FileOutcome::Valid),
+            self.files.len() - self.count(FileOutcome::Valid)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn file(outcome: FileOutcome) -> FileReport {
+        FileReport {
+            path: PathBuf::from("cert.json"),
+            outcome,
+            certificate_id: None,
+            device_serial: None,
+            key_id: None,
+            message: None,
+        }
+    }
+    
+    #[test]
+    fn test_exit_code_reflects_worst_outcome() {
+        let mut report = VerificationReport::default();
+        assert_eq!(report.exit_code(), 0);
+        
+        report.files.push(file(FileOutcome::Valid));
+        report.files.push(file(FileOutcome::UnknownKey));
+        assert_eq!(report.exit_code(), 3);
+        
+        report.files.push(file(FileOutcome::InvalidSignature));
+        report.files.push(file(FileOutcome::SchemaError));
+        assert_eq!(report.exit_code(), 1);
+        assert!(report.to_string().ends_with("4 checked, 1 valid, 3 rejected"));
+    }
+    
+    #[tokio::test]
+    async fn test_schema_and_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let garbage = dir.path().join("garbage.json");
+        std::fs::write(&garbage, "{\"hello\": 1}").unwrap();
+        
+        let verifier = CertificateVerifier::new().unwrap();
+        let report = verify_files(&verifier, &[garbage, dir.path().join("missing.json")]).await;
+        assert_eq!(report.files[0].outcome, FileOutcome::SchemaError);
+        assert_eq!(report.files[1].outcome, FileOutcome::Unreadable);
+        assert_eq!(report.exit_code(), 4);
+    }
+}
@@ -0,0 +1,228 @@
+//! CSV roll-up of issued certificates
+//!
+//! Monthly compliance reports list every drive sanitized in the period.
+//! Rather than have each customer script against the JSON certificates, the
+//! roll-up walks a certificate directory (including the subdirectories file
+//! name templates create), keeps the certificates completed in a date range
+//! and writes one CSV row per certificate. Files that are not signed
+//! certificates, such as job reports, are skipped and reported.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+
+use crate::certificate::SignedCertificate;
+use crate::error::{CertificateError, Result};
+
+/// Column names of the roll-up CSV
+pub const ROLLUP_COLUMNS: [&str; 8] = [
+    "serial", "model", "size_bytes", "algorithm", "result", "certificate_id", "date", "operator",
+];
+
+/// Metadata keys naming the operator in certificates without an operator context, in order of preference
+const OPERATOR_METADATA: [&str; 2] = ["operator", "submitted_by"];
+
+/// Certificates found below a directory
+#[derive(Debug, Default)]
+pub struct Rollup {
+    /// Certificates in the date range, oldest first
+    pub certificates: Vec<SignedCertificate>,
+    /// JSON files that could not be read as certificates, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl Rollup {
+    /// Collect the certificates below `dir` completed in `[since, until)`;
+    /// copies of the same certificate are listed once
+    pub fn collect(dir: &Path, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Result<Self> {
+        let mut files = Vec::new();
+        json_files(dir, &mut files)
+            .map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", dir.display(), e)))?;
+        files.sort();
+        
+        let mut rollup = Rollup::default();
+        let mut seen = HashSet::new();
+        for path in files {
+            let signed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| serde_json::from_str::<SignedCertificate>(&contents).map_err(|e| e.to_string()));
+            match signed {
+                Ok(signed) => {
+                    let date = completion_date(&signed);
+                    let in_range = since.is_none_or(|since| date >= since) && until.is_none_or(|until| date < until);
+                    if in_range && seen.insert(signed.certificate_id()) {
+                        rollup.certificates.push(signed);
+                    }
+                }
+                Err(reason) => rollup.skipped.push((path, reason)),
+            }
+        }
+        rollup
+            .certificates
+            .sort_by(|a, b| completion_date(a).cmp(&completion_date(b)).then_with(|| a.certificate_id().cmp(&b.certificate_id())));
+        Ok(rollup)
+    }
+    
+    /// Write the CSV; returns the number of rows written
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        write_csv(writer, &self.certificates)
+    }
+}
+
+/// Write one row per certificate under a header row; returns the number of rows written
+pub fn write_csv<W: Write>(writer: &mut W, certificates: &[SignedCertificate]) -> io::Result<usize> {
+    writeln!(writer, "{}", ROLLUP_COLUMNS.join(","))?;
+    for signed in certificates {
+        let data = &signed.certificate.data;
+        let fields = [
+            data.device_info.serial.clone(),
+            data.device_info.model.clone(),
+            data.device_info.size.to_string(),
+            data.wipe_info.algorithm.to_string(),
+            result(signed).to_string(),
+            data.certificate_id.to_string(),
+            safe_erase_core::time_format::format_timestamp(&completion_date(signed)),
+            operator(signed),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| safe_erase_core::export::csv_escape(field)).collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    Ok(certificates.len())
+}
+
+/// Operator recorded by the engine, or for older certificates the one the frontend put in the metadata
+fn operator(signed: &SignedCertificate) -> String {
+    let data = &signed.certificate.data;
+    data.operator
+        .as_ref()
+        .map(|operator| operator.identity.clone())
+        .or_else(|| OPERATOR_METADATA.iter().find_map(|key| data.metadata.get(*key)).cloned())
+        .unwrap_or_default()
+}
+
+/// When the wipe finished, or when the certificate was issued for wipes without an end time
+fn completion_date(signed: &SignedCertificate) -> DateTime<Utc> {
+    let data = &signed.certificate.data;
+    data.wipe_info.completed_at.unwrap_or(data.generated_at)
+}
+
+fn result(signed: &SignedCertificate) -> &'static str {
+    let wipe = &signed.certificate.data.wipe_info;
+    match (wipe.completed_at, wipe.verification_passed) {
+        (_, Some(false)) => "verification failed",
+        (Some(_), Some(true)) => "passed",
+        (Some(_), None) => "completed",
+        (None, _) => "incomplete",
+    }
+}
+
+fn json_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            json_files(&path, files)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+    use crate::certificate::{CertificateData, DeviceInfo, WipeCertificate, WipeInfo};
+    use crate::crypto::{SignatureAlgorithm, SignatureInfo};
+    
+    fn signed_certificate(serial: &str, completed_at: DateTime<Utc>, operator: Option<&str>) -> SignedCertificate {
+        let data = CertificateData {
+            certificate_id: Uuid::new_v4(),
+            generated_at: completed_at,
+            device_info: DeviceInfo {
+                path: "/dev/sdb".to_string(),
+                serial: serial.to_string(),
+                model: "Test, Drive".to_string(),
+                size: 1_000_000_000,
+                virtualization: None,
+            },
+            wipe_info: WipeInfo {
+                algorithm: safe_erase_core::WipeAlgorithm::NIST80088,
+                started_at: completed_at,
+                completed_at: Some(completed_at),
+                duration: None,
+                passes_completed: 1,
+                verification_passed: Some(true),
+                device_digest: None,
+            },
+            verification_info: None,
+            compliance_info: None,
+            technical_details: None,
+            organization: None,
+            metadata: operator
+                .map(|operator| HashMap::from([("submitted_by".to_string(), operator.to_string())]))
+                .unwrap_or_default(),
+            work_order: None,
+            ticket_url: None,
+            host: None,
+            operator: None,
+        };
+        let signature_info = SignatureInfo {
+            signature: String::new(),
+            algorithm: SignatureAlgorithm::RSA2048SHA256,
+            key_id: String::new(),
+            timestamp: completed_at,
+            certificate_hash: String::new(),
+            signature_version: crate::crypto::SIGNATURE_VERSION,
+        };
+        SignedCertificate::new(WipeCertificate::new(data), signature_info)
+    }
+    
+    #[test]
+    fn test_csv_rows() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 5, 10, 0, 0).unwrap();
+        let signed = signed_certificate("S1", date, Some("alice"));
+        let mut csv = Vec::new();
+        assert_eq!(write_csv(&mut csv, std::slice::from_ref(&signed)).unwrap(), 1);
+        
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "serial,model,size_bytes,algorithm,result,certificate_id,date,operator");
+        assert!(lines[1].starts_with("S1,\"Test, Drive\",1000000000,"));
+        assert!(lines[1].ends_with(&format!(",passed,{},2024-03-05T10:00:00Z,alice", signed.certificate_id())));
+        
+        // The operator the engine recorded wins over the frontend's metadata
+        let mut recorded = signed.clone();
+        recorded.certificate.data.operator = Some(safe_erase_core::OperatorContext::new(
+            "alice@example.com",
+            safe_erase_core::AuthMethod::SingleSignOn,
+        ));
+        assert_eq!(operator(&recorded), "alice@example.com");
+    }
+    
+    #[test]
+    fn test_collect_filters_range_and_skips_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let march = Utc.with_ymd_and_hms(2024, 3, 5, 10, 0, 0).unwrap();
+        let april = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+        let in_march = signed_certificate("S1", march, None);
+        std::fs::create_dir(dir.path().join("2024-03-05")).unwrap();
+        for path in ["2024-03-05/S1.json", "S1-copy.json"] {
+            std::fs::write(dir.path().join(path), serde_json::to_string(&in_march).unwrap()).unwrap();
+        }
+        std::fs::write(dir.path().join("S2.json"), serde_json::to_string(&signed_certificate("S2", april, None)).unwrap()).unwrap();
+        std::fs::write(dir.path().join("job_report.json"), "{\"job_id\": 1}").unwrap();
+        std::fs::write(dir.path().join("S1.pdf"), "%PDF").unwrap();
+        
+        let rollup = Rollup::collect(dir.path(), Some(march - chrono::Duration::days(4)), Some(april)).unwrap();
+        assert_eq!(rollup.certificates.len(), 1);
+        assert_eq!(rollup.certificates[0].certificate_id(), in_march.certificate_id());
+        assert_eq!(rollup.skipped.len(), 1);
+        assert!(rollup.skipped[0].0.ends_with("job_report.json"));
+        
+        assert_eq!(Rollup::collect(dir.path(), None, None).unwrap().certificates.len(), 2);
+    }
+}
@@ -0,0 +1,340 @@
+//! Persistent trust store for verification keys
+//!
+//! Verifiers used to receive their keys as loose `.pem` files with no record
+//! of whose key it is or how long it should be trusted. The trust store keeps
+//! each key with a label and an optional validity window, persists them as a
+//! JSON document and flags keys that have expired or are about to, so
+//! operators rotate keys before certificates start failing verification.
+//! Certificates signed outside a key's window are rejected.
+
+use std::path::Path;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::crypto::{CertificateSigner, CertificateVerifier};
+use crate::error::{CertificateError, Result};
+use crate::keys::PublicKey;
+
+/// Format version written to saved trust stores
+pub const TRUST_STORE_VERSION: u32 = 1;
+
+/// A trusted verification key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub key_id: String,
+    /// Human-readable owner, e.g. `Bench 3 (2024)`
+    pub label: String,
+    pub public_key_pem: String,
+    pub added_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+/// Problem with a trusted key's validity window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustWarning {
+    Expired { key_id: String, label: String, not_after: DateTime<Utc> },
+    ExpiringSoon { key_id: String, label: String, not_after: DateTime<Utc> },
+    NotYetValid { key_id: String, label: String, not_before: DateTime<Utc> },
+}
+
+/// On-disk layout of a saved trust store
+#[derive(Debug, Serialize, Deserialize)]
+struct TrustStoreFile {
+    version: u32,
+    keys: Vec<TrustedKey>,
+}
+
+impl TrustedKey {
+    /// Key without a validity window
+    pub fn new(key_id: String, label: String, public_key_pem: String) -> Self {
+        Self {
+            key_id,
+            label,
+            public_key_pem,
+            added_at: Utc::now(),
+            not_before: None,
+            not_after: None,
+        }
+    }
+    
+    /// Whether the validity window covers `at`
+    pub fn is_valid_at(&self, at: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|start| at >= start) && self.not_after.is_none_or(|end| at <= end)
+    }
+}
+
+impl CertificateVerifier {
+    /// Trust a PEM public key and return its key ID
+    pub fn add_key(
+        &mut self,
+        label: &str,
+        public_key_pem: &str,
+        not_before: Option<DateTime<Utc>>,
+        not_after: Option<DateTime<Utc>>,
+    ) -> Result<String> {
+        if let (Some(start), Some(end)) = (not_before, not_after) {
+            if end <= start {
+                return Err(CertificateError::InvalidConfiguration(format!(
+                    "Key '{}' expires before it becomes valid", label
+                )));
+            }
+        }
+        let public_key = PublicKey::from_pem(public_key_pem)?;
+        self.add_public_key(label, public_key, not_before, not_after)
+    }
+    
+    /// Stop trusting a key; `false` if it was not trusted
+    pub fn remove_key(&mut self, key_id: &str) -> bool {
+        self.remove_key_entry(key_id)
+    }
+    
+    /// Trusted keys ordered by label
+    pub fn list_keys(&self) -> Vec<&TrustedKey> {
+        let mut keys: Vec<&TrustedKey> = self.key_entries.values().collect();
+        keys.sort_by(|a, b| a.label.cmp(&b.label).then_with(|| a.key_id.cmp(&b.key_id)));
+        keys
+    }
+    
+    /// Trust every public key in a PEM bundle; later keys get a numbered label
+    pub fn import_pem(
+        &mut self,
+        label: &str,
+        bundle: &str,
+        not_before: Option<DateTime<Utc>>,
+        not_after: Option<DateTime<Utc>>,
+    ) -> Result<Vec<String>> {
+        let mut imported = Vec::new();
+        for (index, block) in bundle.split("-----BEGIN ").skip(1).enumerate() {
+            let label = if index == 0 { label.to_string() } else { format!("{} #{}", label, index + 1) };
+            imported.push(self.add_key(&label, &format!("-----BEGIN {}", block), not_before, not_after)?);
+        }
+        Ok(imported)
+    }
+    
+    /// Trust the RSA keys of a JWKS document, labelled by their `kid`; keys
+    /// of other types are skipped
+    pub fn import_jwks(&mut self, jwks: &str) -> Result<Vec<String>> {
+        parse_jwks(jwks)?
+            .into_iter()
+            .map(|(label, public_key)| self.add_public_key(&label, public_key, None, None))
+            .collect()
+    }
+    
+    /// Import a saved trust store or a JWKS document
+    pub(crate) fn import_json(&mut self, contents: &str) -> Result<Vec<String>> {
+        let is_jwks = serde_json::from_str::<Value>(contents)
+            .map(|document| document["keys"][0].get("kty").is_some())
+            .unwrap_or(false);
+        if is_jwks {
+            return self.import_jwks(contents);
+        }
+        
+        let store: TrustStoreFile = serde_json::from_str(contents)
+            .map_err(|e| CertificateError::JsonDeserializationFailed(format!("Trust store: {}", e)))?;
+        if store.version > TRUST_STORE_VERSION {
+            return Err(CertificateError::NotSupported(format!(
+                "Trust store version {} is newer than this build supports", store.version
+            )));
+        }
+        let mut imported = Vec::new();
+        for entry in store.keys {
+            let public_key = PublicKey::from_pem(&entry.public_key_pem)
+                .map_err(|e| CertificateError::CryptographicError(format!("{}: {}", entry.label, e)))?;
+            if CertificateSigner::generate_key_id(&public_key)? != entry.key_id {
+                return Err(CertificateError::InvalidFileFormat(format!(
+                    "Trust store entry '{}' does not match its key ID", entry.label
+                )));
+            }
+            imported.push(entry.key_id.clone());
+            self.insert_key(entry, public_key);
+        }
+        Ok(imported)
+    }
+    
+    /// Trust store as a JSON document
+    pub fn export_trust_store(&self) -> Result<String> {
+        let store = TrustStoreFile {
+            version: TRUST_STORE_VERSION,
+            keys: self.list_keys().into_iter().cloned().collect(),
+        };
+        serde_json::to_string_pretty(&store)
+            .map_err(|e| CertificateError::JsonSerializationFailed(e.to_string()))
+    }
+    
+    /// Verifier trusting the keys of a saved store; a missing file gives an empty store
+    pub fn open_trust_store<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut verifier = Self::new()?;
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                verifier.import_json(&contents)?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)));
+            }
+        }
+        Ok(verifier)
+    }
+    
+    /// Write the trust store, replacing the file atomically
+    pub fn save_trust_store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let temp_path = path.with_extension("json.tmp");
+        let write = std::fs::write(&temp_path, self.export_trust_store()?)
+            .and_then(|_| std::fs::rename(&temp_path, path));
+        write.map_err(|e| CertificateError::FileOperationFailed(format!("{}: {}", path.display(), e)))
+    }
+    
+    /// Keys that are expired, not yet valid, or expire within `within` of `now`
+    pub fn trust_warnings(&self, now: DateTime<Utc>, within: Duration) -> Vec<TrustWarning> {
+        let mut warnings = Vec::new();
+        for key in self.list_keys() {
+            let (key_id, label) = (key.key_id.clone(), key.label.clone());
+            match (key.not_before, key.not_after) {
+                (_, Some(not_after)) if not_after < now => {
+                    warnings.push(TrustWarning::Expired { key_id, label, not_after })
+                }
+                (Some(not_before), _) if not_before > now => {
+                    warnings.push(TrustWarning::NotYetValid { key_id, label, not_before })
+                }
+                (_, Some(not_after)) if not_after < now + within => {
+                    warnings.push(TrustWarning::ExpiringSoon { key_id, label, not_after })
+                }
+                _ => {}
+            }
+        }
+        warnings
+    }
+    
+    pub(crate) fn add_public_key(
+        &mut self,
+        label: &str,
+        public_key: PublicKey,
+        not_before: Option<DateTime<Utc>>,
+        not_after: Option<DateTime<Utc>>,
+    ) -> Result<String> {
+        let key_id = CertificateSigner::generate_key_id(&public_key)?;
+        let pem = public_key.to_pem()?;
+        let entry = TrustedKey {
+            not_before,
+            not_after,
+            ..TrustedKey::new(key_id.clone(), label.to_string(), pem)
+        };
+        self.insert_key(entry, public_key);
+        Ok(key_id)
+    }
+}
+
+/// RSA keys of a JWKS document with their labels: the `kid`, or the key ID
+/// when a JWK has none
+pub(crate) fn parse_jwks(jwks: &str) -> Result<Vec<(String, PublicKey)>> {
+    let document: Value = serde_json::from_str(jwks)
+        .map_err(|e| CertificateError::JsonDeserializationFailed(e.to_string()))?;
+    let keys = document["keys"].as_array().ok_or_else(|| {
+        CertificateError::InvalidFileFormat("JWKS document has no \"keys\" array".to_string())
+    })?;
+    
+    let mut parsed = Vec::new();
+    for jwk in keys.iter().filter(|jwk| jwk["kty"] == "RSA") {
+        let public_key = jwk_to_public_key(jwk)?;
+        let label = match jwk["kid"].as_str() {
+            Some(kid) => kid.to_string(),
+            None => CertificateSigner::generate_key_id(&public_key)?,
+        };
+        parsed.push((label, public_key));
+    }
+    Ok(parsed)
+}
+
+/// RSA public key from a JWK's base64url `n` and `e` members
+pub(crate) fn jwk_to_public_key(jwk: &Value) -> Result<PublicKey> {
+    let component = |name: &str| -> Result<Vec<u8>> {
+        let encoded = jwk[name].as_str().ok_or_else(|| {
+            CertificateError::InvalidFileFormat(format!("JWK is missing \"{}\"", name))
+        })?;
+        URL_SAFE_NO_PAD
+            .decode(encoded.trim_end_matches('='))
+            .map_err(|e| CertificateError::InvalidFileFormat(format!("JWK \"{}\": {}", name, e)))
+    };
+    PublicKey::from_rsa_components(&component("n")?, &component("e")?)
+}
+
+impl std::fmt::Display for TrustWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrustWarning::Expired { key_id, label, not_after } => {
+                write!(f, "key {} ({}) expired on {}", key_id, label, not_after.format("%Y-%m-%d"))
+            }
+            TrustWarning::ExpiringSoon { key_id, label, not_after } => {
+                write!(f, "key {} ({}) expires on {}", key_id, label, not_after.format("%Y-%m-%d"))
+            }
+            TrustWarning::NotYetValid { key_id, label, not_before } => {
+                write!(f, "key {} ({}) is not valid before {}", key_id, label, not_before.format("%Y-%m-%d"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn public_pem(signer: &CertificateSigner) -> String {
+        signer.get_key_info().unwrap().public_key_pem
+    }
+    
+    #[test]
+    fn test_store_roundtrip_and_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trust.json");
+        let (bench, retired) = (CertificateSigner::new().unwrap(), CertificateSigner::new().unwrap());
+        let now = Utc::now();
+        
+        let mut verifier = CertificateVerifier::open_trust_store(&path).unwrap();
+        assert!(verifier.list_keys().is_empty());
+        let bench_id = verifier.add_key("Bench 3", &public_pem(&bench), None, Some(now + Duration::days(10))).unwrap();
+        let retired_id = verifier.add_key("Retired", &public_pem(&retired), None, Some(now - Duration::days(1))).unwrap();
+        assert_eq!(bench_id, bench.key_id());
+        verifier.save_trust_store(&path).unwrap();
+        
+        let mut reopened = CertificateVerifier::open_trust_store(&path).unwrap();
+        let labels: Vec<&str> = reopened.list_keys().iter().map(|key| key.label.as_str()).collect();
+        assert_eq!(labels, ["Bench 3", "Retired"]);
+        assert!(reopened.is_trusted_at(&bench_id, now));
+        assert!(!reopened.is_trusted_at(&retired_id, now));
+        
+        let warnings = reopened.trust_warnings(now, Duration::days(30));
+        assert!(matches!(warnings[0], TrustWarning::ExpiringSoon { .. }));
+        assert!(matches!(warnings[1], TrustWarning::Expired { .. }));
+        assert!(reopened.trust_warnings(now, Duration::days(1)).len() == 1);
+        
+        assert!(reopened.remove_key(&retired_id));
+        assert!(!reopened.remove_key(&retired_id));
+        assert!(!reopened.is_trusted(&retired_id));
+    }
+    
+    #[test]
+    fn test_import_pem_bundle_and_jwks() {
+        let (a, b) = (CertificateSigner::new().unwrap(), CertificateSigner::new().unwrap());
+        let mut verifier = CertificateVerifier::new().unwrap();
+        let bundle = format!("{}\n{}", public_pem(&a), public_pem(&b));
+        assert_eq!(verifier.import_pem("Vendor", &bundle, None, None).unwrap().len(), 2);
+        assert_eq!(verifier.list_keys()[1].label, "Vendor #2");
+        
+        let (n, e) = PublicKey::from_pem(&public_pem(&a)).unwrap().rsa_components().unwrap();
+        let jwks = serde_json::json!({"keys": [
+            {"kty": "EC", "kid": "ignored", "crv": "P-256"},
+            {"kty": "RSA", "kid": "signing-2024", "n": URL_SAFE_NO_PAD.encode(n), "e": URL_SAFE_NO_PAD.encode(e)}
+        ]});
+        let mut from_jwks = CertificateVerifier::new().unwrap();
+        assert_eq!(from_jwks.import_json(&jwks.to_string()).unwrap(), vec![a.key_id().to_string()]);
+        assert_eq!(from_jwks.list_keys()[0].label, "signing-2024");
+    }
+}
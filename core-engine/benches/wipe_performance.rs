@@ -0,0 +1,25 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use safe_erase_core::bench::{self, BenchConfig, IoBackend};
+use safe_erase_core::WipeAlgorithm;
+
+const SIZE: u64 = 16 * 1024 * 1024;
+
+fn wipe_performance(c: &mut Criterion) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let mut group = c.benchmark_group("file_target");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(SIZE));
+    
+    for algorithm in [WipeAlgorithm::ZeroFill, WipeAlgorithm::Random, WipeAlgorithm::DoD522022M] {
+        for backend in [IoBackend::Buffered, IoBackend::Synchronous] {
+            let mut config = BenchConfig::file(dir.path().join("target.img"), SIZE, vec![algorithm.clone()]);
+            config.backends = vec![backend];
+            let id = format!("{}/{:?}", algorithm.info().name, backend);
+            group.bench_function(id, |b| b.iter(|| bench::run(&config).expect("benchmark run")));
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, wipe_performance);
+criterion_main!(benches);
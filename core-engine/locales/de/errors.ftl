@@ -0,0 +1,27 @@
+error-device-not-found = Das Gerät „{ $device }“ wurde nicht gefunden. Bitte prüfen Sie, ob es angeschlossen ist.
+error-device-access-denied = Der Zugriff auf das Gerät „{ $device }“ wurde verweigert. Bitte prüfen Sie die Berechtigungen.
+error-device-busy = Das Gerät „{ $device }“ wird gerade verwendet. Bitte schließen Sie alle Anwendungen, die darauf zugreifen.
+error-device-io = E/A-Fehler am Gerät: { $detail }
+error-unsupported-device = Der Gerätetyp „{ $device }“ wird für sicheres Löschen nicht unterstützt.
+error-device-removed = Das Gerät „{ $device }“ wurde während des Vorgangs entfernt. Schließen Sie es wieder an und beginnen Sie erneut.
+error-wipe-failed = Der Löschvorgang ist fehlgeschlagen: { $reason }
+error-wipe-cancelled = Der Löschvorgang wurde abgebrochen
+error-verification-failed = Die Überprüfung des Löschvorgangs ist fehlgeschlagen. Die Daten wurden möglicherweise nicht vollständig gelöscht.
+error-unsupported-algorithm = Nicht unterstütztes Löschverfahren: { $detail }
+error-operation-stalled = Seit { $seconds } s keine Daten geschrieben bei LBA { $start_lba }-{ $end_lba }
+error-insufficient-privileges = Für den Zugriff auf Speichergeräte sind Administrator- bzw. Root-Rechte erforderlich.
+error-system-command-failed = Systembefehl fehlgeschlagen: { $detail }
+error-unsupported-platform = Plattform nicht unterstützt: { $detail }
+error-certificate = Zertifikatserstellung fehlgeschlagen: { $detail }
+error-cryptographic = Kryptografischer Vorgang fehlgeschlagen: { $detail }
+error-signature-verification-failed = Die Prüfung der digitalen Signatur ist fehlgeschlagen
+error-invalid-configuration = Ungültige Konfiguration: { $detail }
+error-invalid-parameter = Ungültiger Parameter: { $detail }
+error-policy-violation = Dieser Vorgang ist durch die Löschrichtlinie Ihrer Organisation nicht erlaubt: { $reason }
+error-timeout = Zeitüberschreitung: { $detail }
+error-file-system = Dateisystemfehler: { $detail }
+error-permission-denied = Zugriff verweigert: { $detail }
+error-network = Netzwerkfehler: { $detail }
+error-communication-timeout = Zeitüberschreitung bei der Kommunikation
+error-internal = Interner Fehler: { $detail }
+error-unknown = Unbekannter Fehler: { $detail }
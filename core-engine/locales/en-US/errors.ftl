@@ -0,0 +1,30 @@
+# SafeEraseError messages. English is the canonical text returned by
+# `SafeEraseError::user_message()` and written to logs.
+
+error-device-not-found = The device '{ $device }' could not be found. Please check if it's connected.
+error-device-access-denied = Access to device '{ $device }' was denied. Please check permissions.
+error-device-busy = Device '{ $device }' is currently busy. Please close any applications using it.
+error-device-io = Device I/O error: { $detail }
+error-unsupported-device = Device type '{ $device }' is not supported for secure wiping.
+error-device-removed = Device '{ $device }' was removed during the operation. Reconnect it and start again.
+error-wipe-failed = The wipe operation failed: { $reason }
+error-wipe-cancelled = Wipe operation was cancelled
+error-verification-failed = Wipe verification failed. The data may not have been completely erased.
+error-unsupported-algorithm = Unsupported wipe algorithm: { $detail }
+error-operation-stalled = No data written for { $seconds } s at LBA { $start_lba }-{ $end_lba }
+error-insufficient-privileges = Administrator or root privileges are required to access storage devices.
+error-system-command-failed = System command failed: { $detail }
+error-unsupported-platform = Platform not supported: { $detail }
+error-certificate = Certificate generation failed: { $detail }
+error-cryptographic = Cryptographic operation failed: { $detail }
+error-signature-verification-failed = Digital signature verification failed
+error-invalid-configuration = Invalid configuration: { $detail }
+error-invalid-parameter = Invalid parameter: { $detail }
+error-policy-violation = This operation is not permitted by your organization's wipe policy: { $reason }
+error-timeout = Operation timeout: { $detail }
+error-file-system = File system error: { $detail }
+error-permission-denied = Permission denied: { $detail }
+error-network = Network error: { $detail }
+error-communication-timeout = Communication timeout
+error-internal = Internal error: { $detail }
+error-unknown = Unknown error: { $detail }
@@ -0,0 +1,27 @@
+error-device-not-found = Le périphérique « { $device } » est introuvable. Vérifiez qu'il est bien connecté.
+error-device-access-denied = L'accès au périphérique « { $device } » a été refusé. Vérifiez les autorisations.
+error-device-busy = Le périphérique « { $device } » est occupé. Fermez les applications qui l'utilisent.
+error-device-io = Erreur d'E/S sur le périphérique : { $detail }
+error-unsupported-device = Le type de périphérique « { $device } » n'est pas pris en charge pour l'effacement sécurisé.
+error-device-removed = Le périphérique « { $device } » a été retiré pendant l'opération. Reconnectez-le et recommencez.
+error-wipe-failed = L'effacement a échoué : { $reason }
+error-wipe-cancelled = L'effacement a été annulé
+error-verification-failed = La vérification de l'effacement a échoué. Les données n'ont peut-être pas été entièrement effacées.
+error-unsupported-algorithm = Algorithme d'effacement non pris en charge : { $detail }
+error-operation-stalled = Aucune donnée écrite depuis { $seconds } s aux LBA { $start_lba }-{ $end_lba }
+error-insufficient-privileges = Des droits administrateur ou root sont nécessaires pour accéder aux périphériques de stockage.
+error-system-command-failed = La commande système a échoué : { $detail }
+error-unsupported-platform = Plateforme non prise en charge : { $detail }
+error-certificate = La génération du certificat a échoué : { $detail }
+error-cryptographic = L'opération cryptographique a échoué : { $detail }
+error-signature-verification-failed = La vérification de la signature numérique a échoué
+error-invalid-configuration = Configuration invalide : { $detail }
+error-invalid-parameter = Paramètre invalide : { $detail }
+error-policy-violation = Cette opération n'est pas autorisée par la politique d'effacement de votre organisation : { $reason }
+error-timeout = Délai d'attente dépassé : { $detail }
+error-file-system = Erreur du système de fichiers : { $detail }
+error-permission-denied = Permission refusée : { $detail }
+error-network = Erreur réseau : { $detail }
+error-communication-timeout = Délai de communication dépassé
+error-internal = Erreur interne : { $detail }
+error-unknown = Erreur inconnue : { $detail }
@@ -1,12 +1,18 @@
 //! Secure wiping algorithms for SafeErase
 
 use serde::{Deserialize, Serialize};
-use rand::{Rng, SeedableRng};
-use rand::rngs::ChaCha20Rng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use sha2::{Sha256, Digest};
 
+/// Random passes of the Gutmann method (four before and four after the
+/// patterns aimed at MFM/RLL encodings). Gutmann's epilogue to the paper
+/// notes that on PRML/EPRML drives a few passes of random data are all that
+/// can help, which is what `GutmannLite` runs.
+const GUTMANN_RANDOM_PASSES: usize = 8;
+
 /// Supported wiping algorithms
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WipeAlgorithm {
     /// NIST 800-88 - Single pass with cryptographic erase for SSDs
     NIST80088,
@@ -14,6 +20,8 @@ pub enum WipeAlgorithm {
     DoD522022M,
     /// Gutmann - 35-pass algorithm for maximum security
     Gutmann,
+    /// Gutmann Lite - Gutmann's 8 random passes without the encoding-specific ones
+    GutmannLite,
     /// Random - Cryptographically secure random data
     Random,
     /// Zero Fill - Single pass with zeros
@@ -24,8 +32,24 @@ pub enum WipeAlgorithm {
     ATASecureErase,
     /// NVMe Format - NVMe secure format
     NVMeFormat,
+    /// PSID Revert - factory reset of a locked Opal drive, discarding its media key
+    PSIDRevert,
     /// Custom pattern
     Custom(Vec<WipePattern>),
+    /// Custom procedure with a name, description and declared compliance claims
+    Named(CustomAlgorithm),
+}
+
+/// A site-defined overwrite procedure, described well enough for a certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomAlgorithm {
+    /// Short name, e.g. `ACME 2-pass`
+    pub name: String,
+    pub description: String,
+    pub patterns: Vec<WipePattern>,
+    /// Standards the procedure's author states it meets; SafeErase does not assess them
+    #[serde(default)]
+    pub compliance_claims: Vec<String>,
 }
 
 /// Individual wipe pattern for a single pass
@@ -47,6 +71,16 @@ pub enum WipePattern {
     Pattern(Vec<u8>),
 }
 
+/// Device content left behind by a deterministic pattern
+///
+/// Patterns are generated afresh for every write block, so the content
+/// repeats with the block size (and `Complement` alternates between blocks).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedContent {
+    block: Vec<u8>,
+    alternate_blocks: bool,
+}
+
 /// Wipe algorithm metadata
 #[derive(Debug, Clone)]
 pub struct AlgorithmInfo {
@@ -59,7 +93,7 @@ pub struct AlgorithmInfo {
 }
 
 /// Security level classification
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum SecurityLevel {
     Basic,
     Standard,
@@ -95,6 +129,14 @@ impl WipeAlgorithm {
                 compliance_standards: vec!["Academic Research".to_string()],
                 estimated_time_factor: 35.0,
             },
+            WipeAlgorithm::GutmannLite => AlgorithmInfo {
+                name: "Gutmann Lite".to_string(),
+                description: "Gutmann's random passes only - the 27 MFM/RLL-specific passes do nothing on modern drives".to_string(),
+                passes: GUTMANN_RANDOM_PASSES,
+                security_level: SecurityLevel::High,
+                compliance_standards: vec!["Academic Research".to_string()],
+                estimated_time_factor: GUTMANN_RANDOM_PASSES as f64,
+            },
             WipeAlgorithm::Random => AlgorithmInfo {
                 name: "Random".to_string(),
                 description: "Single pass with cryptographically secure random data".to_string(),
@@ -135,6 +177,14 @@ impl WipeAlgorithm {
                 compliance_standards: vec!["NVMe Standard".to_string()],
                 estimated_time_factor: 0.3,
             },
+            WipeAlgorithm::PSIDRevert => AlgorithmInfo {
+                name: "PSID Revert".to_string(),
+                description: "TCG Opal revert authorized by the drive's printed PSID, destroying its media encryption key".to_string(),
+                passes: 1,
+                security_level: SecurityLevel::High,
+                compliance_standards: vec!["TCG Opal".to_string(), "NIST 800-88 Purge (cryptographic erase)".to_string()],
+                estimated_time_factor: 0.01,
+            },
             WipeAlgorithm::Custom(patterns) => AlgorithmInfo {
                 name: "Custom".to_string(),
                 description: "User-defined wipe pattern".to_string(),
@@ -143,6 +193,14 @@ impl WipeAlgorithm {
                 compliance_standards: vec!["Custom".to_string()],
                 estimated_time_factor: patterns.len() as f64,
             },
+            WipeAlgorithm::Named(custom) => AlgorithmInfo {
+                name: custom.name.clone(),
+                description: custom.description.clone(),
+                passes: custom.patterns.len(),
+                security_level: SecurityLevel::Standard,
+                compliance_standards: custom.compliance_claims.clone(),
+                estimated_time_factor: custom.patterns.len() as f64,
+            },
         }
     }
     
@@ -156,18 +214,21 @@ impl WipeAlgorithm {
                 WipePattern::Random,
             ],
             WipeAlgorithm::Gutmann => Self::gutmann_patterns(),
+            WipeAlgorithm::GutmannLite => vec![WipePattern::Random; GUTMANN_RANDOM_PASSES],
             WipeAlgorithm::Random => vec![WipePattern::Random],
             WipeAlgorithm::ZeroFill => vec![WipePattern::Zeros],
             WipeAlgorithm::OneFill => vec![WipePattern::Ones],
             WipeAlgorithm::ATASecureErase => vec![], // Hardware command, no patterns
             WipeAlgorithm::NVMeFormat => vec![], // Hardware command, no patterns
+            WipeAlgorithm::PSIDRevert => vec![], // Hardware command, no patterns
             WipeAlgorithm::Custom(patterns) => patterns.clone(),
+            WipeAlgorithm::Named(custom) => custom.patterns.clone(),
         }
     }
     
     /// Check if this algorithm uses hardware commands
     pub fn is_hardware_based(&self) -> bool {
-        matches!(self, WipeAlgorithm::ATASecureErase | WipeAlgorithm::NVMeFormat)
+        matches!(self, WipeAlgorithm::ATASecureErase | WipeAlgorithm::NVMeFormat | WipeAlgorithm::PSIDRevert)
     }
     
     /// Get recommended algorithms for different device types
@@ -248,19 +309,19 @@ impl WipePattern {
             WipePattern::Ones => vec![0xFFu8; size],
             WipePattern::Fixed(byte) => vec![*byte; size],
             WipePattern::Random => {
-                let mut rng = ChaCha20Rng::from_entropy();
-                (0..size).map(|_| rng.gen()).collect()
+                let mut data = vec![0u8; size];
+                ChaCha20Rng::from_entropy().fill_bytes(&mut data);
+                data
             }
             WipePattern::PseudoRandom(seed) => {
-                let mut rng = ChaCha20Rng::seed_from_u64(*seed);
-                (0..size).map(|_| rng.gen()).collect()
+                let mut data = vec![0u8; size];
+                ChaCha20Rng::seed_from_u64(*seed).fill_bytes(&mut data);
+                data
             }
             WipePattern::Complement => {
-                if let Some(prev) = previous_data {
-                    prev.iter().map(|&b| !b).collect()
-                } else {
-                    vec![0xFFu8; size] // Default to ones if no previous data
-                }
+                // Default to ones past the end of (or without) previous data
+                let prev = previous_data.unwrap_or_default();
+                (0..size).map(|i| prev.get(i).map_or(0xFF, |&b| !b)).collect()
             }
             WipePattern::Pattern(pattern) => {
                 let mut data = Vec::with_capacity(size);
@@ -272,6 +333,25 @@ impl WipePattern {
         }
     }
     
+    /// Regenerate what this pattern writes in blocks of `block_size` bytes,
+    /// or `None` if the output cannot be reproduced (`Random`)
+    pub fn expected_content(&self, block_size: usize) -> Option<ExpectedContent> {
+        if block_size == 0 {
+            return None;
+        }
+        match self {
+            WipePattern::Random => None,
+            WipePattern::Complement => Some(ExpectedContent {
+                block: self.generate_data(block_size, None),
+                alternate_blocks: true,
+            }),
+            _ => Some(ExpectedContent {
+                block: self.generate_data(block_size, None),
+                alternate_blocks: false,
+            }),
+        }
+    }
+    
     /// Get a human-readable description of this pattern
     pub fn description(&self) -> String {
         match self {
@@ -296,12 +376,12 @@ impl WipePattern {
             WipePattern::Ones => hasher.update(b"ones"),
             WipePattern::Fixed(byte) => {
                 hasher.update(b"fixed");
-                hasher.update(&[*byte]);
+                hasher.update([*byte]);
             }
             WipePattern::Random => hasher.update(b"random"),
             WipePattern::PseudoRandom(seed) => {
                 hasher.update(b"pseudorandom");
-                hasher.update(&seed.to_le_bytes());
+                hasher.update(seed.to_le_bytes());
             }
             WipePattern::Complement => hasher.update(b"complement"),
             WipePattern::Pattern(pattern) => {
@@ -313,6 +393,33 @@ impl WipePattern {
     }
 }
 
+impl ExpectedContent {
+    /// Expected bytes at `offset..offset + len`
+    pub fn bytes_at(&self, offset: u64, len: usize) -> Vec<u8> {
+        let block_size = self.block.len() as u64;
+        (0..len as u64)
+            .map(|i| {
+                let position = offset + i;
+                let byte = self.block[(position % block_size) as usize];
+                if self.alternate_blocks && (position / block_size) % 2 == 1 {
+                    !byte
+                } else {
+                    byte
+                }
+            })
+            .collect()
+    }
+    
+    /// Offset of the first byte in `data` (read from `offset`) that differs
+    /// from the expected content, and the number of differing bytes
+    pub fn first_mismatch(&self, offset: u64, data: &[u8]) -> Option<(u64, usize)> {
+        let expected = self.bytes_at(offset, data.len());
+        let mut differing = data.iter().zip(&expected).enumerate().filter(|(_, (a, b))| a != b);
+        let (first, _) = differing.next()?;
+        Some((offset + first as u64, 1 + differing.count()))
+    }
+}
+
 impl std::fmt::Display for WipeAlgorithm {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.info().name)
@@ -341,6 +448,11 @@ mod tests {
         assert_eq!(info.name, "NIST 800-88");
         assert_eq!(info.passes, 1);
         assert_eq!(info.security_level, SecurityLevel::Standard);
+        
+        let revert = WipeAlgorithm::PSIDRevert;
+        assert!(revert.is_hardware_based());
+        assert!(revert.patterns().is_empty());
+        assert_eq!(revert.to_string(), "PSID Revert");
     }
     
     #[test]
@@ -353,11 +465,33 @@ mod tests {
         assert_eq!(patterns[2], WipePattern::Random);
     }
     
+    #[test]
+    fn test_named_custom_algorithm() {
+        let algorithm = WipeAlgorithm::Named(CustomAlgorithm {
+            name: "ACME 2-pass".to_string(),
+            description: "Zeros, then verified random data".to_string(),
+            patterns: vec![WipePattern::Zeros, WipePattern::Random],
+            compliance_claims: vec!["ACME Media Policy 4.2".to_string()],
+        });
+        assert_eq!(algorithm.to_string(), "ACME 2-pass");
+        assert_eq!(algorithm.info().passes, 2);
+        assert_eq!(algorithm.info().compliance_standards, ["ACME Media Policy 4.2"]);
+        
+        let json = serde_json::to_value(&algorithm).unwrap();
+        assert_eq!(json["Named"]["name"], "ACME 2-pass");
+        assert_eq!(serde_json::from_value::<WipeAlgorithm>(json).unwrap(), algorithm);
+    }
+    
     #[test]
     fn test_gutmann_patterns() {
         let gutmann = WipeAlgorithm::Gutmann;
         let patterns = gutmann.patterns();
         assert_eq!(patterns.len(), 35);
+        
+        let lite = WipeAlgorithm::GutmannLite;
+        assert_eq!(lite.patterns().len(), lite.info().passes);
+        assert!(lite.patterns().iter().all(|pattern| *pattern == WipePattern::Random));
+        assert!(lite.info().estimated_time_factor < gutmann.info().estimated_time_factor);
     }
     
     #[test]
@@ -381,6 +515,10 @@ mod tests {
         let complement = WipePattern::Complement;
         let data = complement.generate_data(4, Some(&original));
         assert_eq!(data, vec![0xFF, 0x00, 0x55, 0xAA]);
+        
+        // The last block of a region is shorter than the one before it
+        assert_eq!(complement.generate_data(2, Some(&original)), vec![0xFF, 0x00]);
+        assert_eq!(complement.generate_data(6, Some(&original)), vec![0xFF, 0x00, 0x55, 0xAA, 0xFF, 0xFF]);
     }
     
     #[test]
@@ -397,4 +535,22 @@ mod tests {
         assert!(!WipeAlgorithm::NIST80088.is_hardware_based());
         assert!(!WipeAlgorithm::DoD522022M.is_hardware_based());
     }
+    
+    #[test]
+    fn test_expected_content() {
+        assert!(WipePattern::Random.expected_content(4096).is_none());
+        
+        let seeded = WipePattern::PseudoRandom(42);
+        let content = seeded.expected_content(8).unwrap();
+        let block = seeded.generate_data(8, None);
+        assert_eq!(content.bytes_at(6, 4), [block[6], block[7], block[0], block[1]]);
+        
+        let complement = WipePattern::Complement.expected_content(4).unwrap();
+        assert_eq!(complement.bytes_at(2, 4), [0xFF, 0xFF, 0x00, 0x00]);
+        
+        let pattern = WipePattern::Pattern(vec![0xAA, 0xBB, 0xCC]).expected_content(4).unwrap();
+        assert_eq!(pattern.bytes_at(2, 4), [0xCC, 0xAA, 0xAA, 0xBB]);
+        assert_eq!(pattern.first_mismatch(0, &[0xAA, 0xBB, 0x00, 0x00]), Some((2, 2)));
+        assert_eq!(pattern.first_mismatch(4, &[0xAA, 0xBB]), None);
+    }
 }
@@ -0,0 +1,286 @@
+//! Asset-management CSV import and export
+//!
+//! Wipe benches and inventory systems exchange plain CSV files. An asset
+//! manifest lists the drives expected on the bench (serial, asset tag, work
+//! order); it is matched against discovered devices by serial number so every
+//! batch job is tied to a confirmed asset. After the batch, a completion CSV
+//! maps each serial to its certificate ID and outcome.
+
+use std::io::{self, Write};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::device::DeviceInfo;
+use crate::error::{Result, SafeEraseError};
+use crate::export::csv_escape;
+use crate::logging;
+
+/// One expected asset from an inventory export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetRecord {
+    pub serial: String,
+    pub asset_tag: String,
+    #[serde(default)]
+    pub work_order: Option<String>,
+}
+
+/// Assets expected on the bench
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetManifest {
+    pub assets: Vec<AssetRecord>,
+}
+
+/// Result of matching a manifest against discovered devices
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetMatch {
+    /// Device path and the asset it was confirmed as
+    pub matched: Vec<(String, AssetRecord)>,
+    /// Assets with no attached device
+    pub missing_assets: Vec<AssetRecord>,
+    /// Attached devices that are not in the manifest
+    pub unexpected_devices: Vec<String>,
+}
+
+/// One row of the completion export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletionRecord {
+    pub serial: String,
+    pub asset_tag: String,
+    pub work_order: Option<String>,
+    pub device_path: String,
+    pub certificate_id: Option<Uuid>,
+    /// Final status, e.g. `Completed` or `Failed`
+    pub outcome: String,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Columns of the completion CSV
+const COMPLETION_COLUMNS: [&str; 7] = [
+    "serial",
+    "asset_tag",
+    "work_order",
+    "device_path",
+    "certificate_id",
+    "outcome",
+    "completed_at",
+];
+
+impl AssetManifest {
+    /// Parse a CSV with a header row naming at least `serial` and `asset_tag`
+    /// columns; `work_order` is optional. Column names are case-insensitive and
+    /// other columns are ignored.
+    pub fn parse_csv(contents: &str) -> Result<Self> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines
+            .next()
+            .ok_or_else(|| SafeEraseError::InvalidParameter("asset CSV is empty".to_string()))?;
+        let header: Vec<String> = split_csv_line(header.trim_start_matches('\u{feff}'))
+            .into_iter()
+            .map(|name| name.trim().to_ascii_lowercase().replace([' ', '-'], "_"))
+            .collect();
+        let column = |name: &str| header.iter().position(|h| h == name);
+        let serial_column = column("serial")
+            .ok_or_else(|| SafeEraseError::InvalidParameter("asset CSV has no 'serial' column".to_string()))?;
+        let tag_column = column("asset_tag")
+            .ok_or_else(|| SafeEraseError::InvalidParameter("asset CSV has no 'asset_tag' column".to_string()))?;
+        let work_order_column = column("work_order");
+        
+        let mut assets: Vec<AssetRecord> = Vec::new();
+        for (index, line) in lines {
+            let fields = split_csv_line(line);
+            let field = |column: usize| fields.get(column).map(|f| f.trim().to_string()).unwrap_or_default();
+            let serial = field(serial_column);
+            if serial.is_empty() {
+                return Err(SafeEraseError::InvalidParameter(format!(
+                    "asset CSV line {} has no serial number",
+                    index + 1
+                )));
+            }
+            if assets.iter().any(|asset| same_serial(&asset.serial, &serial)) {
+                return Err(SafeEraseError::InvalidParameter(format!(
+                    "asset CSV line {} repeats serial {}",
+                    index + 1,
+                    serial
+                )));
+            }
+            logging::protect_identifier(&serial);
+            logging::protect_identifier(&field(tag_column));
+            assets.push(AssetRecord {
+                serial,
+                asset_tag: field(tag_column),
+                work_order: work_order_column.map(field).filter(|w| !w.is_empty()),
+            });
+        }
+        Ok(Self { assets })
+    }
+    
+    /// Asset with the given serial number
+    pub fn find_by_serial(&self, serial: &str) -> Option<&AssetRecord> {
+        self.assets.iter().find(|asset| same_serial(&asset.serial, serial))
+    }
+    
+    /// Pair discovered devices with manifest entries by serial number
+    pub fn match_devices(&self, devices: &[DeviceInfo]) -> AssetMatch {
+        let mut result = AssetMatch::default();
+        for device in devices {
+            match self.find_by_serial(&device.serial) {
+                Some(asset) => result.matched.push((device.path.clone(), asset.clone())),
+                None => result.unexpected_devices.push(device.path.clone()),
+            }
+        }
+        result.missing_assets = self
+            .assets
+            .iter()
+            .filter(|asset| !devices.iter().any(|d| same_serial(&d.serial, &asset.serial)))
+            .cloned()
+            .collect();
+        result
+    }
+}
+
+impl AssetRecord {
+    /// Confirm that the device about to be wiped is this asset
+    pub fn confirm_identity(&self, device: &DeviceInfo) -> Result<()> {
+        if same_serial(&self.serial, &device.serial) {
+            Ok(())
+        } else {
+            Err(SafeEraseError::InvalidParameter(format!(
+                "{} has serial {}, expected asset {} with serial {}",
+                device.path, device.serial, self.asset_tag, self.serial
+            )))
+        }
+    }
+}
+
+/// Write the completion CSV; returns the number of rows written
+pub fn write_completion_csv<'a, W, I>(writer: &mut W, records: I) -> io::Result<usize>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a CompletionRecord>,
+{
+    writeln!(writer, "{}", COMPLETION_COLUMNS.join(","))?;
+    let mut count = 0;
+    for record in records {
+        let fields = [
+            record.serial.clone(),
+            record.asset_tag.clone(),
+            record.work_order.clone().unwrap_or_default(),
+            record.device_path.clone(),
+            record.certificate_id.map(|id| id.to_string()).unwrap_or_default(),
+            record.outcome.clone(),
+            record
+                .completed_at
+                .as_ref()
+                .map(crate::time_format::format_timestamp)
+                .unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+        writeln!(writer, "{}", row.join(","))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Serial numbers are compared ignoring case and surrounding whitespace, as
+/// drives and inventory systems disagree on both
+fn same_serial(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Split one CSV line into fields, honouring double-quoted fields with `""`
+/// escapes. Quoted fields spanning several lines are not supported.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{DeviceType, HealthStatus, StorageInterface};
+    
+    fn device(path: &str, serial: &str) -> DeviceInfo {
+        DeviceInfo {
+            path: path.to_string(),
+            name: path.trim_start_matches("/dev/").to_string(),
+            model: "Test SSD".to_string(),
+            serial: serial.to_string(),
+            size: 1_000_000_000,
+            device_type: DeviceType::SSD,
+            interface: StorageInterface::SATA,
+            is_removable: false,
+            is_system_disk: false,
+            supports_secure_erase: true,
+            supports_hpa_dco: true,
+            firmware_version: None,
+            temperature: None,
+            health_status: HealthStatus::Good,
+            hidden_area: None,
+            virtualization: None,
+            bay: None,
+        }
+    }
+    
+    #[test]
+    fn test_parse_and_match_manifest() {
+        let csv = "Serial,Asset Tag,Work-Order,Location\n\
+                   S1,TAG-1,WO-9,\"Rack 4, bay 2\"\n\
+                   \n\
+                   s2 ,TAG-2,,Rack 1\n";
+        let manifest = AssetManifest::parse_csv(csv).unwrap();
+        assert_eq!(manifest.assets.len(), 2);
+        assert_eq!(manifest.assets[0].work_order.as_deref(), Some("WO-9"));
+        assert_eq!(manifest.assets[1].work_order, None);
+        
+        let devices = [device("/dev/sdb", "S2"), device("/dev/sdc", "S3")];
+        let matched = manifest.match_devices(&devices);
+        assert_eq!(matched.matched, [("/dev/sdb".to_string(), manifest.assets[1].clone())]);
+        assert_eq!(matched.missing_assets, [manifest.assets[0].clone()]);
+        assert_eq!(matched.unexpected_devices, ["/dev/sdc"]);
+        
+        assert!(manifest.assets[1].confirm_identity(&devices[0]).is_ok());
+        assert!(manifest.assets[0].confirm_identity(&devices[0]).is_err());
+        
+        assert!(AssetManifest::parse_csv("serial,asset_tag\nS1,A\nS1,B\n").is_err());
+        assert!(AssetManifest::parse_csv("asset_tag\nA\n").is_err());
+    }
+    
+    #[test]
+    fn test_completion_csv() {
+        let record = CompletionRecord {
+            serial: "S1".to_string(),
+            asset_tag: "TAG, 1".to_string(),
+            work_order: None,
+            device_path: "/dev/sdb".to_string(),
+            certificate_id: None,
+            outcome: "Failed".to_string(),
+            completed_at: Some("2024-05-01T12:00:00Z".parse().unwrap()),
+        };
+        let mut output = Vec::new();
+        assert_eq!(write_completion_csv(&mut output, [&record]).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "serial,asset_tag,work_order,device_path,certificate_id,outcome,completed_at\n\
+             S1,\"TAG, 1\",,/dev/sdb,,Failed,2024-05-01T12:00:00Z\n"
+        );
+    }
+}
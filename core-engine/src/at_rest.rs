@@ -0,0 +1,235 @@
+//! Encryption of records kept on the station
+//!
+//! The erase journal, the daemon's recurring-task history and the
+//! certificates it stores name device serials, customers and work orders. With
+//! `[storage] key` configured they are written as sealed envelopes: a magic
+//! header, a random nonce and the ChaCha20-Poly1305 ciphertext, authenticated
+//! together with the kind of record so one file cannot be passed off as
+//! another. Files written before a key was configured are still read as
+//! plaintext and are sealed the next time they are written.
+//!
+//! The key comes from the OS keyring, a passphrase in the environment, a key
+//! file or the output of a command, which is how a KMS client unwrapping a
+//! data key is plugged in.
+
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{SafeEraseError, Result};
+
+/// First bytes of a sealed record
+const MAGIC: &[u8] = b"SAFEERASE-SEALED-1\n";
+
+/// PBKDF2 rounds stretching a passphrase
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+/// Where the storage key comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// 64 hex digits kept in the Secret Service, Keychain or Credential Manager
+    /// (needs the `keyring` feature)
+    Keyring { service: String, account: String },
+    /// Passphrase in an environment variable, stretched with PBKDF2-HMAC-SHA256
+    Passphrase { env: String, salt: String },
+    /// File holding 32 raw bytes or 64 hex digits
+    File(PathBuf),
+    /// Program and arguments printing the key as 64 hex digits
+    Command(Vec<String>),
+}
+
+/// 256-bit key sealing stored records
+#[derive(Clone)]
+pub struct StoreKey([u8; 32]);
+
+impl StoreKey {
+    /// Fetch the key from its source
+    pub fn resolve(source: &KeySource) -> Result<Self> {
+        let invalid = |reason: String| SafeEraseError::InvalidConfiguration(format!("Storage key: {}", reason));
+        let key = match source {
+            KeySource::Keyring { service, account } => {
+                let hex = read_keyring(service, account)?;
+                parse_key(hex.into_bytes()).ok_or_else(|| invalid(format!("keyring entry {}/{} is not 32 bytes", service, account)))?
+            }
+            KeySource::Passphrase { env, salt } => {
+                let passphrase = std::env::var(env).map_err(|_| invalid(format!("{} is not set", env)))?;
+                if passphrase.is_empty() {
+                    return Err(invalid(format!("{} is empty", env)));
+                }
+                let mut key = [0u8; 32];
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    NonZeroU32::new(PASSPHRASE_ITERATIONS).expect("iterations are non-zero"),
+                    salt.as_bytes(),
+                    passphrase.as_bytes(),
+                    &mut key,
+                );
+                key
+            }
+            KeySource::File(path) => {
+                let contents = std::fs::read(path).map_err(|e| invalid(format!("cannot read {}: {}", path.display(), e)))?;
+                parse_key(contents).ok_or_else(|| invalid(format!("{} is not 32 bytes", path.display())))?
+            }
+            KeySource::Command(command) => {
+                let (program, args) = command.split_first().ok_or_else(|| invalid("empty command".to_string()))?;
+                let output = std::process::Command::new(program)
+                    .args(args)
+                    .output()
+                    .map_err(|e| invalid(format!("cannot run {}: {}", program, e)))?;
+                if !output.status.success() {
+                    return Err(invalid(format!("{} exited with {}", program, output.status)));
+                }
+                parse_key(output.stdout).ok_or_else(|| invalid(format!("{} did not print a 32-byte key", program)))?
+            }
+        };
+        Ok(Self(key))
+    }
+    
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+    
+    /// First bytes of the SHA-256 of the key, to tell keys apart in logs
+    pub fn id(&self) -> String {
+        hex::encode(&Sha256::digest(self.0)[..8])
+    }
+    
+    /// Seal a record of the given kind, e.g. `certificate`
+    pub fn seal(&self, kind: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut in_out = plaintext.to_vec();
+        self.aead()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(kind.as_bytes()), &mut in_out)
+            .map_err(|_| SafeEraseError::CryptographicError(format!("Cannot seal {}", kind)))?;
+        Ok([MAGIC, &nonce, &in_out].concat())
+    }
+    
+    /// Open a record sealed by [`StoreKey::seal`] with the same kind
+    pub fn open(&self, kind: &str, sealed: &[u8]) -> Result<Vec<u8>> {
+        let failed = || SafeEraseError::CryptographicError(format!("Cannot open {}: wrong key or damaged record", kind));
+        let body = sealed.strip_prefix(MAGIC).ok_or_else(failed)?;
+        if body.len() < NONCE_LEN {
+            return Err(failed());
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| failed())?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .aead()?
+            .open_in_place(nonce, Aad::from(kind.as_bytes()), &mut in_out)
+            .map_err(|_| failed())?;
+        Ok(plaintext.to_vec())
+    }
+    
+    fn aead(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &self.0)
+            .map_err(|_| SafeEraseError::CryptographicError("Invalid storage key".to_string()))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+impl std::fmt::Debug for StoreKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StoreKey({})", self.id())
+    }
+}
+
+/// Whether stored contents are sealed
+pub fn is_sealed(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Contents to write: sealed when a key is configured, unchanged otherwise
+pub fn seal(key: Option<&StoreKey>, kind: &str, contents: Vec<u8>) -> Result<Vec<u8>> {
+    match key {
+        Some(key) => key.seal(kind, &contents),
+        None => Ok(contents),
+    }
+}
+
+/// Contents as read: opened when sealed, unchanged when written in plaintext
+pub fn open(key: Option<&StoreKey>, kind: &str, contents: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_sealed(&contents) {
+        return Ok(contents);
+    }
+    match key {
+        Some(key) => key.open(kind, &contents),
+        None => Err(SafeEraseError::InvalidConfiguration(format!(
+            "A {} is encrypted but no storage key is configured",
+            kind
+        ))),
+    }
+}
+
+/// A 256-bit key stored as 32 raw bytes or 64 hex digits
+pub(crate) fn parse_key(contents: Vec<u8>) -> Option<[u8; 32]> {
+    let bytes = match std::str::from_utf8(&contents).ok().and_then(|text| hex::decode(text.trim()).ok()) {
+        Some(decoded) => decoded,
+        None => contents,
+    };
+    bytes.try_into().ok()
+}
+
+#[cfg(feature = "keyring")]
+fn read_keyring(service: &str, account: &str) -> Result<String> {
+    keyring::Entry::new(service, account)
+        .and_then(|entry| entry.get_password())
+        .map_err(|e| SafeEraseError::InvalidConfiguration(format!("Storage key: keyring entry {}/{}: {}", service, account, e)))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn read_keyring(service: &str, account: &str) -> Result<String> {
+    Err(SafeEraseError::InvalidConfiguration(format!(
+        "Storage key: keyring entry {}/{} cannot be read; SafeErase was built without the keyring feature",
+        service, account
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_seal_and_open() {
+        let key = StoreKey::from_bytes([3; 32]);
+        let sealed = seal(Some(&key), "certificate", b"serial WD-123".to_vec()).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(6).any(|window| window == b"WD-123"));
+        assert_eq!(open(Some(&key), "certificate", sealed.clone()).unwrap(), b"serial WD-123");
+        
+        // Bound to the kind of record and the key
+        assert!(open(Some(&key), "schedule", sealed.clone()).is_err());
+        assert!(open(Some(&StoreKey::from_bytes([4; 32])), "certificate", sealed.clone()).is_err());
+        assert!(open(None, "certificate", sealed).is_err());
+        
+        // Records from before encryption was enabled are read as they are
+        assert_eq!(open(Some(&key), "certificate", b"{}".to_vec()).unwrap(), b"{}");
+    }
+    
+    #[test]
+    fn test_resolve_key_sources() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, "0f".repeat(32).as_bytes()).unwrap();
+        let from_file = StoreKey::resolve(&KeySource::File(file.path().to_path_buf())).unwrap();
+        assert_eq!(from_file.0, [0x0F; 32]);
+        
+        std::env::set_var("SAFEERASE_TEST_STORAGE_PASSPHRASE", "correct horse");
+        let source = KeySource::Passphrase {
+            env: "SAFEERASE_TEST_STORAGE_PASSPHRASE".to_string(),
+            salt: "bench-7".to_string(),
+        };
+        let first = StoreKey::resolve(&source).unwrap();
+        assert_eq!(StoreKey::resolve(&source).unwrap().0, first.0);
+        
+        let missing = KeySource::Passphrase { env: "SAFEERASE_TEST_UNSET_PASSPHRASE".to_string(), salt: String::new() };
+        assert!(StoreKey::resolve(&missing).is_err());
+        assert!(StoreKey::resolve(&KeySource::Command(Vec::new())).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! Write and verify benchmarks
+//!
+//! Capacity planning needs to know how long an algorithm takes on a class of
+//! drive, and a release should not wipe slower than the one before it.
+//! [`run`] writes every pass of each algorithm over a target through each
+//! [`IoBackend`], reads the final pass back against the digests taken while
+//! writing it, and times both. The target is either a file, which stands in
+//! for a drive and is created at the requested size, or a real device, which
+//! is overwritten and therefore has to be allowed explicitly. The
+//! [`BenchReport`] serializes to JSON, and [`compare`] lists the results that
+//! got slower than a saved baseline.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::algorithms::WipeAlgorithm;
+use crate::error::{SafeEraseError, Result};
+use crate::host::HostInfo;
+
+/// What the benchmark writes to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchTarget {
+    /// Regular file created (or truncated) to the benchmark size
+    File(PathBuf),
+    /// Block device whose first bytes are overwritten
+    Device(String),
+}
+
+/// How blocks reach the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoBackend {
+    /// Writes through the page cache, flushed to the media at the end of each pass
+    Buffered,
+    /// Every block flushed to the media before the next is written
+    Synchronous,
+}
+
+/// Parameters of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchConfig {
+    pub target: BenchTarget,
+    /// Bytes written per pass
+    pub size_bytes: u64,
+    pub block_size: usize,
+    pub algorithms: Vec<WipeAlgorithm>,
+    pub backends: Vec<IoBackend>,
+    /// Permit overwriting a [`BenchTarget::Device`]
+    #[serde(default)]
+    pub allow_device_writes: bool,
+}
+
+/// Timings of one algorithm through one backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub algorithm: String,
+    pub backend: IoBackend,
+    pub passes: usize,
+    pub bytes_written: u64,
+    pub write_seconds: f64,
+    pub write_mb_per_sec: f64,
+    /// Throughput of each pass, in MB/s
+    pub pass_mb_per_sec: Vec<f64>,
+    pub verify_seconds: f64,
+    pub verify_mb_per_sec: f64,
+    /// Whether the final pass read back as written
+    pub verified: bool,
+}
+
+/// Outcome of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub started_at: DateTime<Utc>,
+    pub host: HostInfo,
+    pub target: BenchTarget,
+    pub size_bytes: u64,
+    pub block_size: usize,
+    pub results: Vec<BenchResult>,
+}
+
+/// Result that got slower than its baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regression {
+    pub algorithm: String,
+    pub backend: IoBackend,
+    /// `write` or `verify`
+    pub phase: String,
+    pub baseline_mb_per_sec: f64,
+    pub current_mb_per_sec: f64,
+}
+
+impl BenchConfig {
+    /// Benchmark of the given algorithms through both backends on a file
+    pub fn file(path: impl Into<PathBuf>, size_bytes: u64, algorithms: Vec<WipeAlgorithm>) -> Self {
+        Self {
+            target: BenchTarget::File(path.into()),
+            size_bytes,
+            block_size: 1024 * 1024,
+            algorithms,
+            backends: vec![IoBackend::Buffered, IoBackend::Synchronous],
+            allow_device_writes: false,
+        }
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if self.size_bytes == 0 || self.block_size == 0 || !self.block_size.is_multiple_of(512) {
+            return Err(SafeEraseError::InvalidParameter(
+                "benchmark size must be non-zero and the block size a non-zero multiple of 512".to_string()
+            ));
+        }
+        if matches!(self.target, BenchTarget::Device(_)) && !self.allow_device_writes {
+            return Err(SafeEraseError::InvalidParameter(
+                "benchmarking a device overwrites it; set allow_device_writes to proceed".to_string()
+            ));
+        }
+        if let Some(algorithm) = self.algorithms.iter().find(|algorithm| algorithm.patterns().is_empty()) {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "{} runs inside the drive and cannot be benchmarked by writing",
+                algorithm.info().name
+            )));
+        }
+        Ok(())
+    }
+    
+    fn open(&self) -> Result<File> {
+        let io_error = |e: std::io::Error| SafeEraseError::DeviceIoError(format!("Benchmark target: {}", e));
+        match &self.target {
+            BenchTarget::File(path) => {
+                let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path).map_err(io_error)?;
+                file.set_len(self.size_bytes).map_err(io_error)?;
+                Ok(file)
+            }
+            BenchTarget::Device(path) => {
+                let mut file = OpenOptions::new().read(true).write(true).open(path).map_err(io_error)?;
+                let capacity = file.seek(SeekFrom::End(0)).map_err(io_error)?;
+                if capacity < self.size_bytes {
+                    return Err(SafeEraseError::InvalidParameter(format!(
+                        "{} holds {} bytes, less than the benchmark size {}",
+                        path, capacity, self.size_bytes
+                    )));
+                }
+                Ok(file)
+            }
+        }
+    }
+}
+
+/// Run the benchmark; every algorithm is run through every backend in turn
+pub fn run(config: &BenchConfig) -> Result<BenchReport> {
+    config.validate()?;
+    let started_at = Utc::now();
+    let mut file = config.open()?;
+    
+    let mut results = Vec::new();
+    for algorithm in &config.algorithms {
+        for backend in &config.backends {
+            results.push(run_one(&mut file, config, algorithm, *backend)?);
+        }
+    }
+    
+    Ok(BenchReport {
+        started_at,
+        host: HostInfo::collect(),
+        target: config.target.clone(),
+        size_bytes: config.size_bytes,
+        block_size: config.block_size,
+        results,
+    })
+}
+
+fn run_one(file: &mut File, config: &BenchConfig, algorithm: &WipeAlgorithm, backend: IoBackend) -> Result<BenchResult> {
+    let io_error = |e: std::io::Error| SafeEraseError::DeviceIoError(format!("Benchmark I/O: {}", e));
+    let patterns = algorithm.patterns();
+    let mut pass_mb_per_sec = Vec::with_capacity(patterns.len());
+    let mut final_digests = Vec::new();
+    let mut write_seconds = 0.0;
+    
+    for (index, pattern) in patterns.iter().enumerate() {
+        let last_pass = index + 1 == patterns.len();
+        let mut previous: Option<Vec<u8>> = None;
+        file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        
+        let started = Instant::now();
+        let mut written = 0u64;
+        while written < config.size_bytes {
+            let len = (config.size_bytes - written).min(config.block_size as u64) as usize;
+            let data = pattern.generate_data(len, previous.as_deref());
+            file.write_all(&data).map_err(io_error)?;
+            if backend == IoBackend::Synchronous {
+                file.sync_data().map_err(io_error)?;
+            }
+            if last_pass {
+                final_digests.push(Sha256::digest(&data));
+            }
+            written += len as u64;
+            previous = Some(data);
+        }
+        file.sync_data().map_err(io_error)?;
+        let seconds = started.elapsed().as_secs_f64();
+        write_seconds += seconds;
+        pass_mb_per_sec.push(mb_per_sec(config.size_bytes, seconds));
+    }
+    
+    // Read the final pass back; the page cache may serve it for a file target
+    file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+    let started = Instant::now();
+    let mut buffer = vec![0u8; config.block_size];
+    let mut verified = true;
+    let mut read = 0u64;
+    for expected in &final_digests {
+        let len = (config.size_bytes - read).min(config.block_size as u64) as usize;
+        file.read_exact(&mut buffer[..len]).map_err(io_error)?;
+        verified &= Sha256::digest(&buffer[..len]) == *expected;
+        read += len as u64;
+    }
+    let verify_seconds = started.elapsed().as_secs_f64();
+    
+    let bytes_written = config.size_bytes * patterns.len() as u64;
+    Ok(BenchResult {
+        algorithm: algorithm.info().name,
+        backend,
+        passes: patterns.len(),
+        bytes_written,
+        write_seconds,
+        write_mb_per_sec: mb_per_sec(bytes_written, write_seconds),
+        pass_mb_per_sec,
+        verify_seconds,
+        verify_mb_per_sec: mb_per_sec(config.size_bytes, verify_seconds),
+        verified,
+    })
+}
+
+fn mb_per_sec(bytes: u64, seconds: f64) -> f64 {
+    if seconds > 0.0 { bytes as f64 / 1e6 / seconds } else { 0.0 }
+}
+
+/// Results of `current` slower than the same algorithm and backend in
+/// `baseline` by more than `tolerance` (0.1 for 10%)
+pub fn compare(baseline: &BenchReport, current: &BenchReport, tolerance: f64) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for result in &current.results {
+        let Some(before) = baseline
+            .results
+            .iter()
+            .find(|before| before.algorithm == result.algorithm && before.backend == result.backend)
+        else {
+            continue;
+        };
+        let phases = [
+            ("write", before.write_mb_per_sec, result.write_mb_per_sec),
+            ("verify", before.verify_mb_per_sec, result.verify_mb_per_sec),
+        ];
+        for (phase, baseline_mb_per_sec, current_mb_per_sec) in phases {
+            if current_mb_per_sec < baseline_mb_per_sec * (1.0 - tolerance) {
+                regressions.push(Regression {
+                    algorithm: result.algorithm.clone(),
+                    backend: result.backend,
+                    phase: phase.to_string(),
+                    baseline_mb_per_sec,
+                    current_mb_per_sec,
+                });
+            }
+        }
+    }
+    regressions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_file_benchmark() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BenchConfig::file(dir.path().join("target.img"), 256 * 1024, vec![
+            WipeAlgorithm::NIST80088,
+            WipeAlgorithm::DoD522022M,
+        ]);
+        config.block_size = 64 * 1024;
+        
+        let report = run(&config).unwrap();
+        assert_eq!(report.results.len(), 4);
+        assert!(report.results.iter().all(|result| result.verified));
+        let dod = report.results.iter().find(|result| result.passes == 3).unwrap();
+        assert_eq!(dod.bytes_written, 3 * 256 * 1024);
+        assert_eq!(dod.pass_mb_per_sec.len(), 3);
+        
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"backend\":\"synchronous\""));
+        
+        config.algorithms = vec![WipeAlgorithm::ATASecureErase];
+        assert!(run(&config).is_err());
+        config.target = BenchTarget::Device("/dev/null".to_string());
+        assert!(run(&config).is_err());
+    }
+    
+    #[test]
+    fn test_compare_flags_slowdowns() {
+        let result = BenchResult {
+            algorithm: "NIST 800-88".to_string(),
+            backend: IoBackend::Buffered,
+            passes: 1,
+            bytes_written: 1,
+            write_seconds: 1.0,
+            write_mb_per_sec: 500.0,
+            pass_mb_per_sec: vec![500.0],
+            verify_seconds: 1.0,
+            verify_mb_per_sec: 900.0,
+            verified: true,
+        };
+        let report = |results| BenchReport {
+            started_at: Utc::now(),
+            host: HostInfo::collect(),
+            target: BenchTarget::File(PathBuf::from("target.img")),
+            size_bytes: 1,
+            block_size: 512,
+            results,
+        };
+        let baseline = report(vec![result.clone()]);
+        let slower = report(vec![BenchResult { write_mb_per_sec: 400.0, verify_mb_per_sec: 880.0, ..result }]);
+        
+        let regressions = compare(&baseline, &slower, 0.1);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].phase, "write");
+        assert!(compare(&baseline, &slower, 0.25).is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+//! Reproducible random passes
+//!
+//! Random passes normally draw from the system RNG, so nothing can later show
+//! what a block held after the wipe. With `WipeOptions::seed_key_file` set, a
+//! random master seed is generated per operation and every block of a random
+//! pass is filled from a ChaCha20 stream keyed by the master seed, the pass
+//! number and the block's device offset. The master seed is sealed with
+//! ChaCha20-Poly1305 under the key in that file, bound to the operation id,
+//! and kept in `WipeResult::block_seeds`; whoever holds the key can open it
+//! and regenerate any written block, e.g. to settle a dispute about a sample.
+
+use std::path::Path;
+use rand::rngs::OsRng;
+use rand_chacha::ChaCha20Rng;
+use rand::{RngCore, SeedableRng};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::at_rest::parse_key;
+use crate::error::{SafeEraseError, Result};
+
+/// Name of the sealing algorithm recorded with the seed
+const SEAL_ALGORITHM: &str = "CHACHA20-POLY1305";
+
+/// Seed from which every block of the operation's random passes is derived
+#[derive(Clone, PartialEq, Eq)]
+pub struct MasterSeed([u8; 32]);
+
+/// Key that seals master seeds
+#[derive(Clone)]
+pub struct SeedKey([u8; 32]);
+
+/// What a seeded random pass wrote, regenerated block by block from the
+/// master seed for comparison with the device
+#[derive(Debug, Clone)]
+pub(crate) struct SeededContent {
+    seed: MasterSeed,
+    pass_index: usize,
+    /// `(offset, length)` bytes the pass wrote; blocks are counted from each span's start
+    spans: Vec<(u64, u64)>,
+    block_size: u64,
+}
+
+/// Master seed encrypted for storage in a wipe result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedSeed {
+    pub algorithm: String,
+    /// First bytes of the SHA-256 of the sealing key, to tell keys apart
+    pub key_id: String,
+    /// Hex nonce
+    pub nonce: String,
+    /// Hex ciphertext with the authentication tag appended
+    pub ciphertext: String,
+}
+
+impl MasterSeed {
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        Self(seed)
+    }
+    
+    /// Data written by pass `pass_index` (0-based) to the block at `device_offset`
+    pub fn block_data(&self, pass_index: usize, device_offset: u64, len: usize) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.0);
+        hasher.update((pass_index as u64).to_le_bytes());
+        hasher.update(device_offset.to_le_bytes());
+        let mut data = vec![0u8; len];
+        ChaCha20Rng::from_seed(hasher.finalize().into()).fill_bytes(&mut data);
+        data
+    }
+    
+    /// Encrypt the seed for the result of `operation_id`
+    pub fn seal(&self, key: &SeedKey, operation_id: Uuid) -> Result<SealedSeed> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut in_out = self.0.to_vec();
+        key.aead()?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(operation_id.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| SafeEraseError::CryptographicError("Cannot seal the master seed".to_string()))?;
+        Ok(SealedSeed {
+            algorithm: SEAL_ALGORITHM.to_string(),
+            key_id: key.id(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(in_out),
+        })
+    }
+}
+
+impl SeededContent {
+    pub(crate) fn new(seed: MasterSeed, pass_index: usize, spans: Vec<(u64, u64)>, block_size: u64) -> Self {
+        Self { seed, pass_index, spans, block_size: block_size.max(1) }
+    }
+    
+    /// Device offset of the first byte of `data`, read at device `offset`,
+    /// that differs from what the pass wrote, and how many bytes differ.
+    /// Bytes outside the spans were not written by the pass and are not compared.
+    pub(crate) fn first_mismatch(&self, offset: u64, data: &[u8]) -> Option<(u64, usize)> {
+        let end = offset + data.len() as u64;
+        let mut mismatch: Option<(u64, usize)> = None;
+        for &(start, len) in &self.spans {
+            let span_end = start + len;
+            let mut position = offset.max(start);
+            while position < end.min(span_end) {
+                let block_start = start + (position - start) / self.block_size * self.block_size;
+                let block_len = self.block_size.min(span_end - block_start);
+                let block = self.seed.block_data(self.pass_index, block_start, block_len as usize);
+                let until = (block_start + block_len).min(end);
+                for device_offset in position..until {
+                    if block[(device_offset - block_start) as usize] != data[(device_offset - offset) as usize] {
+                        let (first, count) = mismatch.get_or_insert((device_offset, 0));
+                        *first = (*first).min(device_offset);
+                        *count += 1;
+                    }
+                }
+                position = until;
+            }
+        }
+        mismatch
+    }
+}
+
+impl SeedKey {
+    /// Read a 256-bit key stored as 32 raw bytes or 64 hex digits
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path).map_err(|e| {
+            SafeEraseError::InvalidConfiguration(format!("Cannot read seed key {}: {}", path.display(), e))
+        })?;
+        let key = parse_key(contents).ok_or_else(|| {
+            SafeEraseError::InvalidConfiguration(format!("Seed key {} is not 32 bytes", path.display()))
+        })?;
+        Ok(Self(key))
+    }
+    
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+    
+    fn id(&self) -> String {
+        hex::encode(&Sha256::digest(self.0)[..8])
+    }
+    
+    fn aead(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &self.0)
+            .map_err(|_| SafeEraseError::CryptographicError("Invalid seed key".to_string()))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+impl SealedSeed {
+    /// Decrypt the master seed of the result of `operation_id`
+    pub fn open(&self, key: &SeedKey, operation_id: Uuid) -> Result<MasterSeed> {
+        let failed = |reason: &str| SafeEraseError::CryptographicError(format!("Cannot open the master seed: {}", reason));
+        if self.algorithm != SEAL_ALGORITHM {
+            return Err(failed(&format!("unknown algorithm {}", self.algorithm)));
+        }
+        if self.key_id != key.id() {
+            return Err(failed(&format!("sealed with key {}", self.key_id)));
+        }
+        let nonce: [u8; NONCE_LEN] = hex::decode(&self.nonce)
+            .ok()
+            .and_then(|nonce| nonce.try_into().ok())
+            .ok_or_else(|| failed("malformed nonce"))?;
+        let mut in_out = hex::decode(&self.ciphertext).map_err(|_| failed("malformed ciphertext"))?;
+        let seed = key
+            .aead()?
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(operation_id.as_bytes()), &mut in_out)
+            .map_err(|_| failed("authentication failed"))?;
+        let seed = <[u8; 32]>::try_from(&*seed).map_err(|_| failed("wrong length"))?;
+        Ok(MasterSeed(seed))
+    }
+}
+
+impl std::fmt::Debug for MasterSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterSeed(..)")
+    }
+}
+
+impl std::fmt::Debug for SeedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SeedKey({})", self.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_seal_and_regenerate() {
+        let key = SeedKey::from_bytes([7; 32]);
+        let operation_id = Uuid::new_v4();
+        let seed = MasterSeed::generate();
+        let written = seed.block_data(2, 1 << 20, 4096);
+        
+        let sealed = seed.seal(&key, operation_id).unwrap();
+        let opened = sealed.open(&key, operation_id).unwrap();
+        assert_eq!(opened.block_data(2, 1 << 20, 4096), written);
+        assert_ne!(opened.block_data(2, 2 << 20, 4096), written);
+        assert_ne!(opened.block_data(3, 1 << 20, 4096), written);
+        
+        // Bound to the operation and the key
+        assert!(sealed.open(&key, Uuid::new_v4()).is_err());
+        assert!(sealed.open(&SeedKey::from_bytes([8; 32]), operation_id).is_err());
+    }
+    
+    #[test]
+    fn test_seeded_content_regenerates_blocks() {
+        let seed = MasterSeed::generate();
+        // A region of 12 KiB at 64 KiB, written in 8 KiB blocks: one whole block and a partial one
+        let content = SeededContent::new(seed.clone(), 1, vec![(64 << 10, 12 << 10)], 8 << 10);
+        let mut data = [seed.block_data(1, 64 << 10, 8 << 10), seed.block_data(1, 72 << 10, 4 << 10)].concat();
+        assert_eq!(content.first_mismatch(64 << 10, &data), None);
+        assert_eq!(content.first_mismatch(70 << 10, &data[6 << 10..]), None);
+        
+        data[9000] ^= 0x01;
+        assert_eq!(content.first_mismatch(64 << 10, &data), Some(((64 << 10) + 9000, 1)));
+        // Data from another pass does not match
+        assert!(content.first_mismatch(64 << 10, &seed.block_data(0, 64 << 10, 8 << 10)).is_some());
+    }
+    
+    #[test]
+    fn test_load_hex_key() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, format!("{}\n", "ab".repeat(32)).as_bytes()).unwrap();
+        assert_eq!(SeedKey::load(file.path()).unwrap().0, [0xAB; 32]);
+        
+        std::io::Write::write_all(&mut file, b"00").unwrap();
+        assert!(SeedKey::load(file.path()).is_err());
+    }
+}
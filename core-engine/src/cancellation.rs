@@ -0,0 +1,143 @@
+//! Why a wipe was cancelled
+//!
+//! A cancelled wipe leaves the drive holding its data either way, but what
+//! happens next depends on why it stopped: an operator changing their mind
+//! is routine, while an emergency stop or a policy violation found halfway
+//! through is something an auditor follows up. Whoever cancels passes a
+//! [`Cancellation`] to [`SafeEraseEngine::cancel_wipe`]; the first one given
+//! for an operation is kept in the [`WipeResult`], from which attempt reports
+//! copy it, and the daemon records it in its audit trail.
+//!
+//! [`SafeEraseEngine::cancel_wipe`]: crate::SafeEraseEngine::cancel_wipe
+//! [`WipeResult`]: crate::WipeResult
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SafeEraseError, Result};
+
+/// Why a wipe was cancelled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelReason {
+    /// An operator asked for it
+    #[default]
+    UserRequest,
+    /// The wipe turned out to break an organizational policy
+    PolicyViolation,
+    /// Every wipe on the station was stopped at once
+    EmergencyStop,
+    /// The device disappeared while it was being wiped
+    DeviceRemoved,
+}
+
+/// Cancellation of one operation and who asked for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cancellation {
+    pub reason: CancelReason,
+    /// User name, or the component that stopped the wipe on its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<String>,
+    /// Free text, e.g. the policy rule that was violated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl CancelReason {
+    /// Name used in APIs and the audit trail, e.g. `emergency_stop`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CancelReason::UserRequest => "user_request",
+            CancelReason::PolicyViolation => "policy_violation",
+            CancelReason::EmergencyStop => "emergency_stop",
+            CancelReason::DeviceRemoved => "device_removed",
+        }
+    }
+}
+
+impl std::str::FromStr for CancelReason {
+    type Err = SafeEraseError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        [
+            CancelReason::UserRequest,
+            CancelReason::PolicyViolation,
+            CancelReason::EmergencyStop,
+            CancelReason::DeviceRemoved,
+        ]
+        .into_iter()
+        .find(|reason| reason.as_str() == s)
+        .ok_or_else(|| SafeEraseError::InvalidParameter(format!("Unknown cancellation reason: {}", s)))
+    }
+}
+
+impl std::fmt::Display for CancelReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Cancellation {
+    pub fn new(reason: CancelReason) -> Self {
+        Self {
+            reason,
+            requested_by: None,
+            detail: None,
+            requested_at: Utc::now(),
+        }
+    }
+    
+    /// Set who asked for the cancellation
+    pub fn with_requested_by(mut self, requested_by: impl Into<String>) -> Self {
+        self.requested_by = Some(requested_by.into());
+        self
+    }
+    
+    /// Set the free-text detail
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl std::fmt::Display for Cancellation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cancelled ({})", self.reason)?;
+        if let Some(requested_by) = &self.requested_by {
+            write!(f, " by {}", requested_by)?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {}", detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_reason_names_round_trip() {
+        for reason in [
+            CancelReason::UserRequest,
+            CancelReason::PolicyViolation,
+            CancelReason::EmergencyStop,
+            CancelReason::DeviceRemoved,
+        ] {
+            assert_eq!(reason.as_str().parse::<CancelReason>().unwrap(), reason);
+            assert_eq!(serde_json::to_value(reason).unwrap(), reason.as_str());
+        }
+        assert!("abort".parse::<CancelReason>().is_err());
+    }
+    
+    #[test]
+    fn test_cancellation_display() {
+        let cancellation = Cancellation::new(CancelReason::EmergencyStop)
+            .with_requested_by("alice")
+            .with_detail("smoke in the server room");
+        assert_eq!(cancellation.to_string(), "Cancelled (emergency_stop) by alice: smoke in the server room");
+        assert_eq!(Cancellation::new(CancelReason::UserRequest).to_string(), "Cancelled (user_request)");
+    }
+}
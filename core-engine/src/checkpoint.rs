@@ -0,0 +1,188 @@
+//! Checkpoints of overwrite passes in progress
+//!
+//! A seven-pass wipe of a 20 TB drive runs for days, and a crash or power
+//! cut near the end used to mean starting over. With `checkpoint_dir` set,
+//! the engine flushes the drive's cache every half minute and records how
+//! far the current pass got, together with the passes already finished and
+//! the sealed master seed of the random passes. The checkpoint is removed
+//! once the last pass is written. After a crash,
+//! [`WipeEngine::resume_from_checkpoint`] continues the same operation from
+//! the recorded position, and the random passes generate the same data as
+//! before so the blocks stay reproducible.
+//!
+//! [`WipeEngine::resume_from_checkpoint`]: crate::wipe::WipeEngine::resume_from_checkpoint
+
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::algorithms::WipeAlgorithm;
+use crate::at_rest::{self, StoreKey};
+use crate::block_seeds::SealedSeed;
+use crate::error::{SafeEraseError, Result};
+use crate::wipe::{PassRecord, SkippedRange, WipeOptions};
+
+/// Where an overwrite had got to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointPosition {
+    /// Pass being written, starting at 0
+    pub pass_index: usize,
+    /// Bytes of that pass known to be on the media, counted through the wiped regions in order
+    pub pass_bytes: u64,
+}
+
+/// State of an interrupted overwrite, enough to continue it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WipeCheckpoint {
+    pub operation_id: Uuid,
+    pub device_serial: String,
+    pub device_path: String,
+    pub algorithm: WipeAlgorithm,
+    pub options: WipeOptions,
+    pub position: CheckpointPosition,
+    /// First LBA the current pass has not yet written
+    pub next_lba: u64,
+    /// Passes finished before the checkpoint
+    #[serde(default)]
+    pub passes: Vec<PassRecord>,
+    #[serde(default)]
+    pub skipped_ranges: Vec<SkippedRange>,
+    /// Master seed of the random passes, sealed for `operation_id`
+    #[serde(default)]
+    pub block_seeds: Option<SealedSeed>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Directory of checkpoints, one file per drive serial
+#[derive(Debug, Clone)]
+pub struct CheckpointJournal {
+    dir: PathBuf,
+    key: Option<StoreKey>,
+}
+
+/// Kind of record checkpoints are sealed as
+const SEALED_KIND: &str = "wipe checkpoint";
+
+impl CheckpointJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), key: None }
+    }
+    
+    /// Encrypt checkpoints with `key`; ones written without it are still read
+    pub fn with_key(mut self, key: Option<StoreKey>) -> Self {
+        self.key = key;
+        self
+    }
+    
+    /// Replace the drive's checkpoint
+    pub fn record(&self, checkpoint: &WipeCheckpoint) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| checkpoint_error(&self.dir, e))?;
+        let path = self.path(&checkpoint.device_serial);
+        let json = serde_json::to_vec_pretty(checkpoint)
+            .map_err(|e| SafeEraseError::Internal(format!("Cannot encode wipe checkpoint: {}", e)))?;
+        let json = at_rest::seal(self.key.as_ref(), SEALED_KIND, json)?;
+        // A crash while writing must leave the previous checkpoint intact
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json).map_err(|e| checkpoint_error(&temp, e))?;
+        std::fs::rename(&temp, &path).map_err(|e| checkpoint_error(&path, e))
+    }
+    
+    /// Checkpoint of an interrupted wipe of the drive, if any
+    pub fn load(&self, device_serial: &str) -> Result<Option<WipeCheckpoint>> {
+        let path = self.path(device_serial);
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(checkpoint_error(&path, e)),
+        };
+        let contents = at_rest::open(self.key.as_ref(), SEALED_KIND, contents)?;
+        serde_json::from_slice(&contents).map(Some).map_err(|e| {
+            SafeEraseError::FileSystemError(format!("Wipe checkpoint {} is unreadable: {}", path.display(), e))
+        })
+    }
+    
+    /// Remove the drive's checkpoint once its overwrite has finished
+    pub fn clear(&self, device_serial: &str) -> Result<()> {
+        let path = self.path(device_serial);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(checkpoint_error(&path, e)),
+            _ => Ok(()),
+        }
+    }
+    
+    fn path(&self, device_serial: &str) -> PathBuf {
+        let name: String = device_serial
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.checkpoint.json", name))
+    }
+}
+
+fn checkpoint_error(path: &Path, e: std::io::Error) -> SafeEraseError {
+    SafeEraseError::FileSystemError(format!("Wipe checkpoint {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    fn checkpoint() -> WipeCheckpoint {
+        WipeCheckpoint {
+            operation_id: Uuid::new_v4(),
+            device_serial: "ZL2K 81/A".to_string(),
+            device_path: "/dev/sdc".to_string(),
+            algorithm: WipeAlgorithm::DoD522022M,
+            options: WipeOptions::default(),
+            position: CheckpointPosition { pass_index: 1, pass_bytes: 8 << 30 },
+            next_lba: (8 << 30) / 512,
+            passes: vec![PassRecord {
+                pass: 1,
+                pattern: "All zeros (0x00)".to_string(),
+                bytes_written: 20 << 40,
+                duration: Duration::from_secs(90_000),
+                average_speed: 240e6,
+                verified: None,
+            }],
+            skipped_ranges: Vec::new(),
+            block_seeds: None,
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+    
+    #[test]
+    fn test_record_load_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = CheckpointJournal::new(dir.path().join("checkpoints"));
+        let checkpoint = checkpoint();
+        
+        assert!(journal.load(&checkpoint.device_serial).unwrap().is_none());
+        journal.record(&checkpoint).unwrap();
+        assert!(dir.path().join("checkpoints/ZL2K_81_A.checkpoint.json").exists());
+        let loaded = journal.load(&checkpoint.device_serial).unwrap().unwrap();
+        assert_eq!(loaded.operation_id, checkpoint.operation_id);
+        assert_eq!(loaded.position, checkpoint.position);
+        assert_eq!(loaded.passes, checkpoint.passes);
+        
+        journal.clear(&checkpoint.device_serial).unwrap();
+        assert!(journal.load(&checkpoint.device_serial).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_sealed_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let sealed = CheckpointJournal::new(dir.path()).with_key(Some(StoreKey::from_bytes([3; 32])));
+        let checkpoint = checkpoint();
+        
+        sealed.record(&checkpoint).unwrap();
+        let contents = std::fs::read(dir.path().join("ZL2K_81_A.checkpoint.json")).unwrap();
+        assert!(at_rest::is_sealed(&contents));
+        assert_eq!(sealed.load(&checkpoint.device_serial).unwrap().unwrap().operation_id, checkpoint.operation_id);
+        assert!(CheckpointJournal::new(dir.path()).load(&checkpoint.device_serial).is_err());
+    }
+}
@@ -0,0 +1,837 @@
+//! Layered configuration for SafeErase
+//!
+//! Settings are resolved from, in increasing order of precedence:
+//! built-in defaults, the system configuration file (`/etc/safeerase/config.toml`
+//! or the platform equivalent), additional files, `SAFEERASE_<SECTION>_<KEY>`
+//! environment variables, and per-invocation overrides.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::algorithms::WipeAlgorithm;
+use crate::error::{SafeEraseError, Result};
+use crate::memory::{MemoryBudget, MemoryLimits};
+use crate::at_rest::KeySource;
+use crate::logging::IdentifierMasker;
+use crate::redaction::{LogRedaction, Redactor};
+use crate::smart_monitor::SmartMonitor;
+use crate::verification::{self, VerificationThresholds, VerificationType};
+use crate::wipe::{HpaPolicy, WipeOptions, WriteBackend};
+
+/// Environment variable prefix for configuration overrides
+pub const ENV_PREFIX: &str = "SAFEERASE_";
+
+/// Environment variable that replaces the system configuration file path
+pub const CONFIG_PATH_ENV: &str = "SAFEERASE_CONFIG";
+
+/// Fully resolved SafeErase configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SafeEraseConfig {
+    pub wipe: WipeDefaults,
+    pub certificates: CertificateDefaults,
+    pub policy: PolicyDefaults,
+    pub events: EventDefaults,
+    pub spool: SpoolDefaults,
+    pub redaction: RedactionDefaults,
+    pub memory: MemoryDefaults,
+    pub storage: StorageDefaults,
+}
+
+/// Default wipe settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct WipeDefaults {
+    /// Algorithm used when none is given explicitly
+    pub algorithm: WipeAlgorithm,
+    /// Verification type; chosen per device when unset
+    pub verification_type: Option<VerificationType>,
+    pub verify_wipe: bool,
+    pub verification_samples: usize,
+    /// Bytes read per verification sample; rounded up to the physical sector size
+    pub verification_sample_size: usize,
+    /// Criteria a verification must meet for the wipe to succeed
+    pub verification_thresholds: VerificationThresholds,
+    pub clear_hpa_dco: bool,
+    /// `Clear`, `ClearAndRestore` or `Preserve` a detected HPA
+    pub hpa_policy: HpaPolicy,
+    pub block_size: usize,
+    pub prefer_hardware_erase: bool,
+    /// Limit on each overwrite pass; unset for none
+    #[serde(with = "crate::time_format::option_duration")]
+    pub pass_timeout: Option<Duration>,
+    /// Time a write may make no progress before the wipe fails as stalled; unset to wait forever
+    #[serde(with = "crate::time_format::option_duration")]
+    pub stall_timeout: Option<Duration>,
+    /// Skip sectors that fail to write and record them, rather than failing the wipe
+    pub skip_bad_sectors: bool,
+    /// Check pattern buffers against their hash before writing and read blocks back periodically
+    pub paranoid_writes: bool,
+    /// `synchronous`, or `{ io_uring = { queue_depth = N } }` on Linux builds with the `io-uring` feature
+    pub write_backend: WriteBackend,
+    /// Batches of pattern data generated ahead of the writes; 0 generates them in turn
+    pub pipeline_depth: usize,
+    /// Hash the whole device after the final pass
+    pub compute_device_digest: bool,
+    /// Discard all blocks after the final pass and verify they read as zeros
+    pub discard_after_wipe: bool,
+    /// Scan the device for sensitive content before wiping
+    pub scan_sensitive_data: bool,
+    /// Watch SMART counters during wipes; unset disables monitoring
+    pub smart_monitor: Option<SmartMonitor>,
+    /// Where hardware erases in flight are recorded so an interrupted one can be resumed
+    pub erase_journal_dir: Option<PathBuf>,
+    /// Where overwrite passes record checkpoints so an interrupted wipe can be resumed
+    pub checkpoint_dir: Option<PathBuf>,
+    /// 256-bit key sealing the master seed of random passes; unset writes unrecorded random data
+    pub seed_key_file: Option<PathBuf>,
+}
+
+/// Default certificate settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct CertificateDefaults {
+    /// Directory certificates are written to
+    pub output_dir: PathBuf,
+    /// Output format: `pdf`, `json` or `both`
+    pub format: String,
+    pub include_qr_code: bool,
+    pub include_technical_details: bool,
+    pub include_compliance_info: bool,
+    pub template_name: Option<String>,
+    /// Output file name without extension; placeholders such as `{serial}`,
+    /// `{asset_tag}`, `{work_order}` and `{date}` are filled in, `/` creates subdirectories
+    pub file_name_template: String,
+    /// When the file name is taken: `suffix`, `overwrite` or `error`
+    pub on_collision: String,
+    /// PEM private key used to sign certificates; an ephemeral key is used when unset
+    pub signing_key: Option<PathBuf>,
+    /// PEM public key matching `signing_key`
+    pub public_key: Option<PathBuf>,
+    /// Encrypt PDF certificates (AES-256); this password grants full access
+    pub pdf_owner_password: Option<String>,
+    /// Password needed to open encrypted PDF certificates; anyone may open
+    /// them when unset, but editing and copying still need the owner password
+    pub pdf_user_password: Option<String>,
+    /// Check the system clock against NTP before signing: `off`, `flag`
+    /// (record a warning in the certificate) or `refuse`
+    pub clock_policy: String,
+    /// NTP servers queried in order, as `host` or `host:port`
+    pub ntp_servers: Vec<String>,
+    /// Largest accepted clock offset in seconds
+    pub max_clock_skew_secs: u64,
+    /// BCP 47 language tag of PDF certificates, read by screen readers
+    pub pdf_language: String,
+    /// Also write a drive label next to each certificate: `pdf` or `png`
+    pub label_format: Option<String>,
+    /// Symbol printed on labels: `code128` (certificate ID) or `qr` (verification URL)
+    pub label_symbol: String,
+}
+
+/// Organizational policy settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PolicyDefaults {
+    /// Signed policy file enforced by the engine
+    pub file: Option<PathBuf>,
+    /// PEM public key the policy signature is verified against
+    pub public_key: Option<PathBuf>,
+}
+
+/// Structured event output for external log pipelines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct EventDefaults {
+    /// Send operation events to the systemd journal
+    pub journald: bool,
+    /// Send operation events to the local syslog socket
+    pub syslog: bool,
+    pub syslog_socket: PathBuf,
+    /// Identifier (`SYSLOG_IDENTIFIER` / APP-NAME) attached to every event
+    pub identifier: String,
+    /// SIEM collector (`host:port`) receiving security events over TCP
+    pub siem_address: Option<String>,
+    /// SIEM record format: `cef` or `leef`
+    pub siem_format: String,
+}
+
+/// Results spooling for boot environments without persistent storage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SpoolDefaults {
+    /// Directory (normally on tmpfs) holding results until delivery; spooling is off when unset
+    pub dir: Option<PathBuf>,
+    /// Endpoint spooled files are uploaded to with `PUT <upload_url>/<name>`
+    pub upload_url: Option<String>,
+    /// Bearer token sent with uploads
+    pub upload_token: Option<String>,
+    /// Mount point of a USB key spooled files are copied to
+    pub usb_dir: Option<PathBuf>,
+    /// Delivery attempts per file and destination
+    pub attempts: u32,
+}
+
+/// Masking of device serials and asset tags in shared reports
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RedactionDefaults {
+    /// Mask identifiers in generated certificates
+    pub certificates: bool,
+    /// Mask serials in operation events sent to journald, syslog and SIEM
+    pub events: bool,
+    /// `hash` or `truncate` serials and asset tags in log output and the daemon's audit file
+    pub logs: LogRedaction,
+    /// Secret mixed into the correlation hash; required when redaction is enabled
+    pub salt: Option<String>,
+}
+
+/// Memory used by verification data, for low-RAM boot environments
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct MemoryDefaults {
+    /// Bytes of sector analyses one operation keeps in memory; unlimited when unset
+    pub operation_limit_bytes: Option<usize>,
+    /// Bytes of sector analyses all concurrent operations keep in memory; unlimited when unset
+    pub global_limit_bytes: Option<usize>,
+    /// Directory receiving analyses beyond the limits; they are summarized only when unset
+    pub spill_dir: Option<PathBuf>,
+}
+
+/// Encryption of the records kept on the station
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct StorageDefaults {
+    /// Source of the key encrypting the erase journal, schedules and stored
+    /// certificates; they are written in plaintext when unset
+    pub key: Option<KeySource>,
+}
+
+/// Builder that merges configuration layers
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    files: Vec<(PathBuf, bool)>,
+    use_env: bool,
+    overrides: Vec<(String, toml::Value)>,
+}
+
+impl SafeEraseConfig {
+    /// Load the configuration from the standard locations and the environment
+    pub fn load() -> Result<Self> {
+        ConfigLoader::new().load()
+    }
+    
+    /// Validate cross-field constraints
+    pub fn validate(&self) -> Result<()> {
+        let wipe = &self.wipe;
+        if wipe.block_size == 0 || !wipe.block_size.is_multiple_of(512) {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "wipe.block_size must be a non-zero multiple of 512, got {}",
+                wipe.block_size
+            )));
+        }
+        if wipe.verify_wipe && wipe.verification_samples == 0 {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "wipe.verification_samples must be at least 1 when verification is enabled".to_string()
+            ));
+        }
+        if wipe.verification_sample_size == 0 || wipe.verification_sample_size > verification::MAX_SAMPLE_SIZE {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "wipe.verification_sample_size must be between 1 and {} bytes, got {}",
+                verification::MAX_SAMPLE_SIZE,
+                wipe.verification_sample_size
+            )));
+        }
+        if !(0.0..=1.0).contains(&wipe.verification_thresholds.min_success_rate) {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "wipe.verification_thresholds.min_success_rate must be between 0 and 1, got {}",
+                wipe.verification_thresholds.min_success_rate
+            )));
+        }
+        for (key, timeout) in [("pass_timeout", wipe.pass_timeout), ("stall_timeout", wipe.stall_timeout)] {
+            if timeout.is_some_and(|timeout| timeout.is_zero()) {
+                return Err(SafeEraseError::InvalidConfiguration(format!(
+                    "wipe.{} must be greater than zero; leave it unset to disable the limit",
+                    key
+                )));
+            }
+        }
+        if wipe.smart_monitor.as_ref().is_some_and(|monitor| monitor.interval.is_zero()) {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "wipe.smart_monitor.interval must be greater than zero".to_string()
+            ));
+        }
+        if let WipeAlgorithm::Custom(patterns) = &wipe.algorithm {
+            if patterns.is_empty() {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "wipe.algorithm: custom algorithms need at least one pattern".to_string()
+                ));
+            }
+        }
+        if let WipeAlgorithm::Named(custom) = &wipe.algorithm {
+            if custom.name.trim().is_empty() || custom.patterns.is_empty() {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "wipe.algorithm: named custom algorithms need a name and at least one pattern".to_string()
+                ));
+            }
+        }
+        
+        let certificates = &self.certificates;
+        if certificates.output_dir.as_os_str().is_empty() {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "certificates.output_dir must not be empty".to_string()
+            ));
+        }
+        if !matches!(certificates.format.to_ascii_lowercase().as_str(), "pdf" | "json" | "both") {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "certificates.format must be pdf, json or both, got '{}'",
+                certificates.format
+            )));
+        }
+        match (&certificates.signing_key, &certificates.public_key) {
+            (Some(private_key), Some(public_key)) => {
+                for path in [private_key, public_key] {
+                    if !path.is_file() {
+                        return Err(SafeEraseError::InvalidConfiguration(format!(
+                            "Signing key file not found: {}",
+                            path.display()
+                        )));
+                    }
+                }
+            }
+            (None, None) => {}
+            _ => {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "certificates.signing_key and certificates.public_key must be set together".to_string()
+                ));
+            }
+        }
+        
+        if certificates.file_name_template.trim().is_empty() || certificates.file_name_template.starts_with('/') {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "certificates.file_name_template must be a relative name, got '{}'",
+                certificates.file_name_template
+            )));
+        }
+        if !matches!(certificates.on_collision.as_str(), "suffix" | "overwrite" | "error") {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "certificates.on_collision must be suffix, overwrite or error, got '{}'",
+                certificates.on_collision
+            )));
+        }
+        match (&certificates.pdf_owner_password, &certificates.pdf_user_password) {
+            (Some(owner), _) if owner.is_empty() => {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "certificates.pdf_owner_password must not be empty".to_string()
+                ));
+            }
+            (None, Some(_)) => {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "certificates.pdf_user_password requires certificates.pdf_owner_password".to_string()
+                ));
+            }
+            (Some(owner), Some(user)) if owner == user => {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "certificates.pdf_user_password must differ from the owner password".to_string()
+                ));
+            }
+            _ => {}
+        }
+        match certificates.clock_policy.as_str() {
+            "off" => {}
+            "flag" | "refuse" if certificates.ntp_servers.is_empty() => {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "certificates.ntp_servers must list at least one server when clock_policy is set".to_string()
+                ));
+            }
+            "flag" | "refuse" => {}
+            other => {
+                return Err(SafeEraseError::InvalidConfiguration(format!(
+                    "certificates.clock_policy must be off, flag or refuse, got '{}'", other
+                )));
+            }
+        }
+        if certificates.pdf_language.trim().is_empty() {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "certificates.pdf_language must be a language tag such as en-US".to_string()
+            ));
+        }
+        if let Some(format) = &certificates.label_format {
+            if !matches!(format.to_ascii_lowercase().as_str(), "pdf" | "png") {
+                return Err(SafeEraseError::InvalidConfiguration(format!(
+                    "certificates.label_format must be pdf or png, got '{}'", format
+                )));
+            }
+        }
+        if !matches!(certificates.label_symbol.to_ascii_lowercase().as_str(), "code128" | "qr") {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "certificates.label_symbol must be code128 or qr, got '{}'", certificates.label_symbol
+            )));
+        }
+        if certificates.max_clock_skew_secs == 0 {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "certificates.max_clock_skew_secs must be positive".to_string()
+            ));
+        }
+        
+        if self.policy.file.is_some() != self.policy.public_key.is_some() {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "policy.file and policy.public_key must be set together".to_string()
+            ));
+        }
+        
+        if self.events.identifier.is_empty() || self.events.identifier.contains(char::is_whitespace) {
+            return Err(SafeEraseError::InvalidConfiguration(format!(
+                "events.identifier must be a single non-empty word, got '{}'",
+                self.events.identifier
+            )));
+        }
+        self.events.siem_format.parse::<crate::events::SiemFormat>()?;
+        
+        let spool = &self.spool;
+        if spool.dir.is_some() {
+            if spool.upload_url.is_none() && spool.usb_dir.is_none() {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "spool.dir needs spool.upload_url or spool.usb_dir to deliver results to".to_string()
+                ));
+            }
+            if spool.attempts == 0 {
+                return Err(SafeEraseError::InvalidConfiguration(
+                    "spool.attempts must be at least 1".to_string()
+                ));
+            }
+        }
+        if let Some(url) = &spool.upload_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(SafeEraseError::InvalidConfiguration(format!(
+                    "spool.upload_url must be an http(s) URL, got '{}'",
+                    url
+                )));
+            }
+        }
+        
+        let redaction = &self.redaction;
+        let hashing = redaction.certificates || redaction.events || redaction.logs == LogRedaction::Hash;
+        if hashing && redaction.salt.as_deref().is_none_or(str::is_empty) {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "redaction.salt must be set when redaction is enabled".to_string()
+            ));
+        }
+        
+        let memory = &self.memory;
+        if memory.operation_limit_bytes == Some(0) || memory.global_limit_bytes == Some(0) {
+            return Err(SafeEraseError::InvalidConfiguration(
+                "memory limits must be at least 1 byte; leave them unset for no limit".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+}
+
+impl MemoryDefaults {
+    /// Limits for a verification engine; the global budget is shared by its operations
+    pub fn limits(&self) -> MemoryLimits {
+        MemoryLimits {
+            global: self.global_limit_bytes.map_or_else(MemoryBudget::unlimited, MemoryBudget::new),
+            per_operation: self.operation_limit_bytes,
+            spill_dir: self.spill_dir.clone(),
+        }
+    }
+}
+
+impl WipeDefaults {
+    /// Engine options for these defaults
+    pub fn wipe_options(&self) -> WipeOptions {
+        WipeOptions {
+            verify_wipe: self.verify_wipe,
+            verification_samples: self.verification_samples,
+            verification_sample_size: self.verification_sample_size,
+            clear_hpa_dco: self.clear_hpa_dco,
+            hpa_policy: self.hpa_policy,
+            block_size: self.block_size,
+            prefer_hardware_erase: self.prefer_hardware_erase,
+            pass_timeout: self.pass_timeout,
+            stall_timeout: self.stall_timeout,
+            skip_bad_sectors: self.skip_bad_sectors,
+            paranoid_writes: self.paranoid_writes,
+            write_backend: self.write_backend,
+            pipeline_depth: self.pipeline_depth,
+            compute_device_digest: self.compute_device_digest,
+            discard_after_wipe: self.discard_after_wipe,
+            scan_sensitive_data: self.scan_sensitive_data,
+            smart_monitor: self.smart_monitor.clone(),
+            erase_journal_dir: self.erase_journal_dir.clone(),
+            checkpoint_dir: self.checkpoint_dir.clone(),
+            seed_key_file: self.seed_key_file.clone(),
+            ..WipeOptions::default()
+        }
+    }
+}
+
+impl ConfigLoader {
+    /// Loader with the system configuration file and environment variables enabled
+    pub fn new() -> Self {
+        let system_file = std::env::var_os(CONFIG_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(system_config_path);
+        
+        Self {
+            files: vec![(system_file, false)],
+            use_env: true,
+            overrides: Vec::new(),
+        }
+    }
+    
+    /// Loader without any implicit layers
+    pub fn empty() -> Self {
+        Self {
+            files: Vec::new(),
+            use_env: false,
+            overrides: Vec::new(),
+        }
+    }
+    
+    /// Add a configuration file that must exist
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.files.push((path.as_ref().to_path_buf(), true));
+        self
+    }
+    
+    /// Add a configuration file that is skipped when missing
+    pub fn optional_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.files.push((path.as_ref().to_path_buf(), false));
+        self
+    }
+    
+    /// Enable or disable environment variable overrides
+    pub fn env(mut self, enabled: bool) -> Self {
+        self.use_env = enabled;
+        self
+    }
+    
+    /// Override a single setting for this invocation, e.g. `set("wipe.algorithm", "Gutmann")`
+    pub fn set<V: Into<toml::Value>>(mut self, key: &str, value: V) -> Self {
+        self.overrides.push((key.to_string(), value.into()));
+        self
+    }
+    
+    /// Merge all layers and validate the result
+    pub fn load(&self) -> Result<SafeEraseConfig> {
+        let mut merged = toml::Value::try_from(SafeEraseConfig::default())
+            .map_err(|e| SafeEraseError::Internal(e.to_string()))?;
+        
+        for (path, required) in &self.files {
+            let contents = match std::fs::read_to_string(path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => continue,
+                Err(e) => {
+                    return Err(SafeEraseError::InvalidConfiguration(format!("{}: {}", path.display(), e)));
+                }
+            };
+            let layer: toml::Value = toml::from_str(&contents)
+                .map_err(|e| SafeEraseError::InvalidConfiguration(format!("{}: {}", path.display(), e)))?;
+            debug!("Loaded configuration layer from {}", path.display());
+            merge(&mut merged, layer);
+        }
+        
+        if self.use_env {
+            for (name, value) in std::env::vars() {
+                if let Some((key, value)) = env_override(&name, &value) {
+                    set_path(&mut merged, &key, value)?;
+                }
+            }
+        }
+        
+        for (key, value) in &self.overrides {
+            set_path(&mut merged, key, value.clone())?;
+        }
+        
+        let config: SafeEraseConfig = merged
+            .try_into()
+            .map_err(|e: toml::de::Error| SafeEraseError::InvalidConfiguration(e.message().to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Platform location of the system-wide configuration file
+pub fn system_config_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let base = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+        PathBuf::from(base).join("SafeErase").join("config.toml")
+    }
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support/SafeErase/config.toml")
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        PathBuf::from("/etc/safeerase/config.toml")
+    }
+}
+
+/// Recursively merge `layer` into `base`; tables merge, everything else replaces
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Set a dotted key such as `certificates.output_dir`
+fn set_path(root: &mut toml::Value, key: &str, value: toml::Value) -> Result<()> {
+    let (section, field) = key
+        .split_once('.')
+        .ok_or_else(|| SafeEraseError::InvalidConfiguration(format!("Expected <section>.<key>, got '{}'", key)))?;
+    
+    let table = root
+        .as_table_mut()
+        .and_then(|t| t.get_mut(section))
+        .and_then(toml::Value::as_table_mut)
+        .ok_or_else(|| SafeEraseError::InvalidConfiguration(format!("Unknown configuration section: {}", section)))?;
+    table.insert(field.to_string(), value);
+    Ok(())
+}
+
+/// Map `SAFEERASE_WIPE_BLOCK_SIZE=4096` to (`wipe.block_size`, 4096)
+fn env_override(name: &str, value: &str) -> Option<(String, toml::Value)> {
+    let rest = name.strip_prefix(ENV_PREFIX)?.to_ascii_lowercase();
+    let (section, field) = ["wipe", "certificates", "policy", "events", "spool", "redaction", "memory"]
+        .iter()
+        .find_map(|section| rest.strip_prefix(&format!("{}_", section)).map(|field| (*section, field)))?;
+    
+    let value = if let Ok(flag) = value.parse::<bool>() {
+        toml::Value::Boolean(flag)
+    } else if let Ok(number) = value.parse::<i64>() {
+        toml::Value::Integer(number)
+    } else {
+        toml::Value::String(value.to_string())
+    };
+    Some((format!("{}.{}", section, field), value))
+}
+
+impl RedactionDefaults {
+    /// Redactor for certificates, when certificate redaction is enabled
+    pub fn certificate_redactor(&self) -> Option<Redactor> {
+        self.salt.as_ref().filter(|_| self.certificates).map(Redactor::new)
+    }
+    
+    /// Redactor for operation events, when event redaction is enabled
+    pub fn event_redactor(&self) -> Option<Redactor> {
+        self.salt.as_ref().filter(|_| self.events).map(Redactor::new)
+    }
+    
+    /// Masker for log output, when log redaction is enabled
+    pub fn log_masker(&self) -> Option<IdentifierMasker> {
+        match self.logs {
+            LogRedaction::Off => None,
+            LogRedaction::Hash => self.salt.as_ref().map(|salt| IdentifierMasker::hashing(Redactor::new(salt))),
+            LogRedaction::Truncate => Some(IdentifierMasker::truncating()),
+        }
+    }
+}
+
+impl Default for WipeDefaults {
+    fn default() -> Self {
+        let options = WipeOptions::default();
+        Self {
+            algorithm: WipeAlgorithm::NIST80088,
+            verification_type: None,
+            verify_wipe: options.verify_wipe,
+            verification_samples: options.verification_samples,
+            verification_sample_size: options.verification_sample_size,
+            verification_thresholds: VerificationThresholds::default(),
+            clear_hpa_dco: options.clear_hpa_dco,
+            hpa_policy: options.hpa_policy,
+            block_size: options.block_size,
+            prefer_hardware_erase: options.prefer_hardware_erase,
+            pass_timeout: options.pass_timeout,
+            stall_timeout: options.stall_timeout,
+            skip_bad_sectors: options.skip_bad_sectors,
+            paranoid_writes: options.paranoid_writes,
+            write_backend: options.write_backend,
+            pipeline_depth: options.pipeline_depth,
+            compute_device_digest: options.compute_device_digest,
+            discard_after_wipe: options.discard_after_wipe,
+            scan_sensitive_data: options.scan_sensitive_data,
+            smart_monitor: options.smart_monitor,
+            erase_journal_dir: options.erase_journal_dir,
+            checkpoint_dir: options.checkpoint_dir,
+            seed_key_file: options.seed_key_file,
+        }
+    }
+}
+
+impl Default for CertificateDefaults {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("certificates"),
+            format: "both".to_string(),
+            include_qr_code: true,
+            include_technical_details: true,
+            include_compliance_info: true,
+            template_name: None,
+            file_name_template: "wipe_certificate_{certificate_id}".to_string(),
+            on_collision: "suffix".to_string(),
+            signing_key: None,
+            public_key: None,
+            pdf_owner_password: None,
+            pdf_user_password: None,
+            clock_policy: "off".to_string(),
+            ntp_servers: vec!["pool.ntp.org".to_string()],
+            max_clock_skew_secs: 300,
+            pdf_language: "en-US".to_string(),
+            label_format: None,
+            label_symbol: "code128".to_string(),
+        }
+    }
+}
+
+impl Default for EventDefaults {
+    fn default() -> Self {
+        Self {
+            journald: false,
+            syslog: false,
+            syslog_socket: PathBuf::from(crate::events::syslog::DEFAULT_SYSLOG_SOCKET),
+            identifier: "safeerase".to_string(),
+            siem_address: None,
+            siem_format: "cef".to_string(),
+        }
+    }
+}
+
+impl Default for SpoolDefaults {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            upload_url: None,
+            upload_token: None,
+            usb_dir: None,
+            attempts: 3,
+        }
+    }
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = ConfigLoader::empty().load().unwrap();
+        assert_eq!(config, SafeEraseConfig::default());
+    }
+    
+    #[test]
+    fn test_file_then_override_precedence() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "[wipe]\nalgorithm = \"DoD522022M\"\nblock_size = 4096\n\n[certificates]\nformat = \"pdf\"").unwrap();
+        writeln!(file, "\n[storage]\nkey = {{ keyring = {{ service = \"safeerase\", account = \"storage\" }} }}").unwrap();
+        
+        let config = ConfigLoader::empty()
+            .file(file.path())
+            .set("wipe.algorithm", "Gutmann")
+            .load()
+            .unwrap();
+        assert_eq!(config.wipe.algorithm, WipeAlgorithm::Gutmann);
+        assert_eq!(config.wipe.block_size, 4096);
+        assert_eq!(config.certificates.format, "pdf");
+        assert!(config.certificates.include_qr_code);
+        assert_eq!(
+            config.storage.key,
+            Some(KeySource::Keyring { service: "safeerase".to_string(), account: "storage".to_string() })
+        );
+    }
+    
+    #[test]
+    fn test_unknown_key_rejected() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "[wipe]\nalgoritm = \"Gutmann\"").unwrap();
+        
+        let result = ConfigLoader::empty().file(file.path()).load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+    }
+    
+    #[test]
+    fn test_validation_errors() {
+        let result = ConfigLoader::empty().set("wipe.block_size", 1000).load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("certificates.signing_key", "/nonexistent.pem").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("spool.dir", "/run/safeerase/spool").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("certificates.pdf_user_password", "open").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("redaction.certificates", true).load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("redaction.logs", "hash").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        let config = ConfigLoader::empty().set("redaction.logs", "truncate").load().unwrap();
+        assert!(config.redaction.log_masker().is_some());
+        
+        let result = ConfigLoader::empty().set("certificates.clock_policy", "strict").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("certificates.label_format", "svg").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("memory.operation_limit_bytes", 0).load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let monitor: toml::Value = toml::from_str("interval = \"PT0S\"").unwrap();
+        let result = ConfigLoader::empty().set("wipe.smart_monitor", monitor).load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let result = ConfigLoader::empty().set("wipe.stall_timeout", "PT0S").load();
+        assert!(matches!(result, Err(SafeEraseError::InvalidConfiguration(_))));
+        
+        let config = ConfigLoader::empty().set("wipe.pass_timeout", "PT6H").load().unwrap();
+        assert_eq!(config.wipe.wipe_options().pass_timeout, Some(Duration::from_secs(6 * 3600)));
+    }
+    
+    #[test]
+    fn test_env_override_mapping() {
+        let (key, value) = env_override("SAFEERASE_WIPE_VERIFY_WIPE", "false").unwrap();
+        assert_eq!(key, "wipe.verify_wipe");
+        assert_eq!(value, toml::Value::Boolean(false));
+        
+        let (key, value) = env_override("SAFEERASE_EVENTS_JOURNALD", "true").unwrap();
+        assert_eq!(key, "events.journald");
+        assert_eq!(value, toml::Value::Boolean(true));
+        
+        let (key, value) = env_override("SAFEERASE_CERTIFICATES_OUTPUT_DIR", "/srv/certs").unwrap();
+        assert_eq!(key, "certificates.output_dir");
+        assert_eq!(value, toml::Value::String("/srv/certs".to_string()));
+        
+        let (key, value) = env_override("SAFEERASE_MEMORY_GLOBAL_LIMIT_BYTES", "268435456").unwrap();
+        assert_eq!(key, "memory.global_limit_bytes");
+        assert_eq!(value, toml::Value::Integer(268435456));
+        
+        assert!(env_override("SAFEERASE_CONFIG", "/etc/x.toml").is_none());
+        assert!(env_override("PATH", "/usr/bin").is_none());
+    }
+}
@@ -1,13 +1,14 @@
 //! Device detection and management for SafeErase
 
-use std::path::PathBuf;
-use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use tokio::fs;
 use tracing::{debug, info, warn};
 
 use crate::error::{SafeEraseError, Result};
+use crate::mounts::MountTable;
 use crate::platform;
+use crate::pool_membership::{self, PoolMembership};
+use crate::readiness::{ReadinessInputs, WipeReadiness};
+use crate::retry::{retry_recoverable, RetryPolicy};
 
 /// Information about a storage device
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +27,58 @@ pub struct DeviceInfo {
     pub firmware_version: Option<String>,
     pub temperature: Option<i32>,
     pub health_status: HealthStatus,
+    /// Capacity hidden by an HPA when the device was opened
+    #[serde(default)]
+    pub hidden_area: Option<HiddenArea>,
+    /// Layer between the host and the physical media, when the device is not a plain drive
+    #[serde(default)]
+    pub virtualization: Option<VirtualizationLayer>,
+    /// Enclosure slot or controller port the drive is attached to
+    #[serde(default)]
+    pub bay: Option<DriveBay>,
+}
+
+/// Capacity hidden from the host by a Host Protected Area or Device Configuration Overlay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HiddenArea {
+    /// Sectors behind the HPA
+    pub hpa_sectors: u64,
+    /// Sectors behind the DCO; only known once the DCO has been inspected during a wipe
+    pub dco_sectors: u64,
+    pub sector_size: u32,
+}
+
+/// What sits between the host and the physical media of a virtual or thin-provisioned disk.
+/// Overwrites reach this layer, which may not write them to the same physical blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualizationLayer {
+    Virtio,
+    Vmware,
+    HyperV,
+    Xen,
+    Qemu,
+    VirtualBox,
+    /// A LUN that allocates storage on write, typically from a SAN
+    ThinProvisioned,
+}
+
+/// Where a drive sits in a multi-bay tower or backplane. Enclosures with an
+/// SES processor report the slot number printed on the bay; otherwise the
+/// drive is placed by its ATA port and, behind a SATA port multiplier, the
+/// multiplier port it hangs off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriveBay {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u32>,
+    /// Enclosure the slot belongs to, when the host sees several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclosure: Option<String>,
+    /// Number of the controller's ATA port, as in `ata3`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_port: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplier_port: Option<u32>,
 }
 
 /// Types of storage devices
@@ -34,6 +87,7 @@ pub enum DeviceType {
     HDD,
     SSD,
     NVMe,
+    #[allow(non_camel_case_types)]
     eMMC,
     SD,
     USB,
@@ -49,24 +103,42 @@ pub enum StorageInterface {
     SCSI,
     IDE,
     MMC,
+    /// Paravirtualized disk of a hypervisor (VirtIO, Xen)
+    Virtual,
     Unknown,
 }
 
 /// Device health status
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HealthStatus {
     Good,
     Warning,
     Critical,
+    #[default]
     Unknown,
 }
 
+/// What reads of discarded (trimmed) blocks return
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimReadBehavior {
+    /// The device makes no promise; old data may still be returned
+    #[default]
+    Undefined,
+    /// Reads return the same data every time, but not necessarily zeros (DRAT)
+    Deterministic,
+    /// Reads return zeros (RZAT, or NVMe DLFEAT "read zeroes")
+    Zeroes,
+}
+
 /// Represents an opened storage device
 #[derive(Debug)]
 pub struct Device {
     info: DeviceInfo,
     handle: platform::DeviceHandle,
     capabilities: DeviceCapabilities,
+    /// Windows volume set the partition table assigns the device to
+    pool_membership: Option<PoolMembership>,
 }
 
 /// Device capabilities for wiping operations
@@ -81,6 +153,12 @@ pub struct DeviceCapabilities {
     pub max_lba: u64,
     pub logical_sector_size: u32,
     pub physical_sector_size: u32,
+    /// ATA security is frozen, so hardware erase commands will be rejected
+    pub security_frozen: bool,
+    /// The kernel reports the device as read-only
+    pub write_protected: bool,
+    /// Read behavior after TRIM/deallocate
+    pub trim_read_behavior: TrimReadBehavior,
 }
 
 impl Device {
@@ -88,19 +166,41 @@ impl Device {
     pub async fn open(device_path: &str) -> Result<Self> {
         debug!("Opening device: {}", device_path);
         
-        // Check if we have sufficient privileges
-        if !platform::has_admin_privileges() {
+        // Check if we have sufficient privileges; disk images need only file permissions
+        let is_image = std::fs::metadata(device_path).is_ok_and(|metadata| metadata.is_file());
+        if !is_image && !platform::has_admin_privileges() {
             return Err(SafeEraseError::InsufficientPrivileges);
         }
         
-        // Open the device handle
-        let handle = platform::open_device(device_path).await?;
+        // Open the device handle; udev or an automounter may still hold it briefly
+        let handle = retry_recoverable(&RetryPolicy::default(), || platform::open_device(device_path)).await?;
         
         // Get device information
-        let info = Self::query_device_info(&handle, device_path).await?;
+        let mut info = Self::query_device_info(&handle, device_path).await?;
         
         // Query device capabilities
-        let capabilities = Self::query_capabilities(&handle, &info).await?;
+        let capabilities = Self::query_capabilities(&handle).await?;
+        
+        // An HPA is worth reporting before anyone decides how to wipe
+        if capabilities.supports_hpa_detection {
+            match platform::read_hpa(&handle).await {
+                Ok(hpa) if hpa.is_present() => {
+                    info.hidden_area = Some(HiddenArea {
+                        hpa_sectors: hpa.hidden_sectors(),
+                        dco_sectors: 0,
+                        sector_size: capabilities.logical_sector_size,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Cannot read HPA state of {}: {}", device_path, e),
+            }
+        }
+        
+        // Wiping one member of a dynamic volume or storage pool corrupts the rest
+        let pool_membership = Self::read_pool_membership(&handle, &capabilities).await;
+        if let Some(membership) = pool_membership {
+            warn!("{} is a {}", device_path, membership);
+        }
         
         info!("Successfully opened device: {} ({})", info.name, info.model);
         
@@ -108,6 +208,7 @@ impl Device {
             info,
             handle,
             capabilities,
+            pool_membership,
         })
     }
     
@@ -136,6 +237,23 @@ impl Device {
         self.capabilities.supports_hpa_detection || self.capabilities.supports_dco_detection
     }
     
+    /// Check whether the device can be wiped right now and why not
+    pub fn readiness(&self) -> WipeReadiness {
+        let mounts = MountTable::load();
+        WipeReadiness::assess(ReadinessInputs {
+            info: &self.info,
+            capabilities: &self.capabilities,
+            mount_points: mounts.mount_points(&self.info.path),
+            privileged: platform::has_admin_privileges(),
+            pool_membership: self.pool_membership,
+        })
+    }
+    
+    /// Dynamic disk or Storage Spaces membership found when the device was opened
+    pub fn pool_membership(&self) -> Option<PoolMembership> {
+        self.pool_membership
+    }
+    
     /// Get the device handle for low-level operations
     pub(crate) fn handle(&self) -> &platform::DeviceHandle {
         &self.handle
@@ -146,7 +264,9 @@ impl Device {
         device_path: &str,
     ) -> Result<DeviceInfo> {
         let basic_info = platform::get_device_info(handle).await?;
-        let smart_info = platform::get_smart_info(handle).await.unwrap_or_default();
+        let smart_info = retry_recoverable(&RetryPolicy::default(), || platform::get_smart_info(handle))
+            .await
+            .unwrap_or_default();
         
         Ok(DeviceInfo {
             path: device_path.to_string(),
@@ -163,12 +283,28 @@ impl Device {
             firmware_version: basic_info.firmware_version,
             temperature: smart_info.temperature,
             health_status: smart_info.health_status,
+            hidden_area: None,
+            virtualization: basic_info.virtualization,
+            bay: basic_info.bay,
         })
     }
     
+    async fn read_pool_membership(
+        handle: &platform::DeviceHandle,
+        capabilities: &DeviceCapabilities,
+    ) -> Option<PoolMembership> {
+        let mut disk_start = vec![0u8; pool_membership::PARTITION_TABLE_BYTES];
+        match platform::read_sectors(handle, 0, &mut disk_start).await {
+            Ok(read) => pool_membership::detect(&disk_start[..read], capabilities.logical_sector_size as usize),
+            Err(e) => {
+                debug!("Cannot read the partition table: {}", e);
+                None
+            }
+        }
+    }
+    
     async fn query_capabilities(
         handle: &platform::DeviceHandle,
-        info: &DeviceInfo,
     ) -> Result<DeviceCapabilities> {
         let caps = platform::query_device_capabilities(handle).await?;
         
@@ -182,6 +318,9 @@ impl Device {
             max_lba: caps.max_lba,
             logical_sector_size: caps.logical_sector_size,
             physical_sector_size: caps.physical_sector_size,
+            security_frozen: caps.security_frozen,
+            write_protected: caps.write_protected,
+            trim_read_behavior: caps.trim_read_behavior,
         })
     }
 }
@@ -260,11 +399,99 @@ impl std::fmt::Display for StorageInterface {
             StorageInterface::SCSI => write!(f, "SCSI"),
             StorageInterface::IDE => write!(f, "IDE"),
             StorageInterface::MMC => write!(f, "MMC"),
+            StorageInterface::Virtual => write!(f, "Virtual"),
             StorageInterface::Unknown => write!(f, "Unknown"),
         }
     }
 }
 
+impl VirtualizationLayer {
+    /// Recognize a hypervisor's virtual disk from its kernel name and the
+    /// vendor and model strings it reports
+    pub fn from_identity(device_name: &str, vendor: &str, model: &str) -> Option<Self> {
+        let vendor = vendor.trim().to_ascii_lowercase();
+        let model = model.trim().to_ascii_lowercase();
+        if device_name.starts_with("vd") {
+            Some(Self::Virtio)
+        } else if device_name.starts_with("xvd") || vendor == "xen" {
+            Some(Self::Xen)
+        } else if vendor == "vmware" || model.starts_with("vmware virtual") {
+            Some(Self::Vmware)
+        } else if vendor == "msft" && model.starts_with("virtual disk") {
+            Some(Self::HyperV)
+        } else if vendor == "qemu" || model.starts_with("qemu harddisk") {
+            Some(Self::Qemu)
+        } else if vendor == "vbox" || model.starts_with("vbox harddisk") {
+            Some(Self::VirtualBox)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for VirtualizationLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VirtualizationLayer::Virtio => write!(f, "VirtIO virtual disk"),
+            VirtualizationLayer::Vmware => write!(f, "VMware virtual disk"),
+            VirtualizationLayer::HyperV => write!(f, "Hyper-V virtual disk"),
+            VirtualizationLayer::Xen => write!(f, "Xen virtual disk"),
+            VirtualizationLayer::Qemu => write!(f, "QEMU virtual disk"),
+            VirtualizationLayer::VirtualBox => write!(f, "VirtualBox virtual disk"),
+            VirtualizationLayer::ThinProvisioned => write!(f, "Thin-provisioned LUN"),
+        }
+    }
+}
+
+impl DriveBay {
+    /// Whether anything about the drive's position is known
+    pub fn is_known(&self) -> bool {
+        self.slot.is_some() || self.host_port.is_some()
+    }
+}
+
+impl std::fmt::Display for DriveBay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.slot, self.host_port) {
+            (Some(slot), _) => {
+                write!(f, "Bay {}", slot)?;
+                if let Some(enclosure) = &self.enclosure {
+                    write!(f, " of {}", enclosure)?;
+                }
+                Ok(())
+            }
+            (None, Some(host_port)) => {
+                write!(f, "ata{}", host_port)?;
+                if let Some(port) = self.multiplier_port {
+                    write!(f, " port {}", port)?;
+                }
+                Ok(())
+            }
+            (None, None) => write!(f, "Unknown bay"),
+        }
+    }
+}
+
+impl HiddenArea {
+    pub fn hidden_sectors(&self) -> u64 {
+        self.hpa_sectors + self.dco_sectors
+    }
+    
+    pub fn hidden_bytes(&self) -> u64 {
+        self.hidden_sectors() * u64::from(self.sector_size)
+    }
+}
+
+impl std::fmt::Display for TrimReadBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrimReadBehavior::Undefined => write!(f, "Undefined"),
+            TrimReadBehavior::Deterministic => write!(f, "Deterministic"),
+            TrimReadBehavior::Zeroes => write!(f, "Zeroes"),
+        }
+    }
+}
+
 impl std::fmt::Display for HealthStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -304,6 +531,9 @@ mod tests {
                 firmware_version: None,
                 temperature: None,
                 health_status: HealthStatus::Good,
+                hidden_area: None,
+                virtualization: None,
+                bay: None,
             },
         ];
         
@@ -313,4 +543,45 @@ mod tests {
         let filtered = filter_devices(&devices, true, true, None);
         assert_eq!(filtered.len(), 1); // System disk included
     }
+    
+    #[test]
+    fn test_hidden_area_size() {
+        let hidden = HiddenArea { hpa_sectors: 2113, dco_sectors: 1000, sector_size: 512 };
+        assert_eq!(hidden.hidden_sectors(), 3113);
+        assert_eq!(hidden.hidden_bytes(), 3113 * 512);
+        
+        // Device lists saved before hidden areas were reported still load
+        let saved = serde_json::json!({
+            "path": "/dev/sdb", "name": "sdb", "model": "M", "serial": "S", "size": 1,
+            "device_type": "HDD", "interface": "SATA", "is_removable": false, "is_system_disk": false,
+            "supports_secure_erase": false, "supports_hpa_dco": true, "firmware_version": null,
+            "temperature": null, "health_status": "Good"
+        });
+        assert!(serde_json::from_value::<DeviceInfo>(saved).unwrap().hidden_area.is_none());
+    }
+    
+    #[test]
+    fn test_drive_bay_display() {
+        let slot = DriveBay { slot: Some(7), enclosure: Some("0:0:16:0".to_string()), host_port: Some(3), multiplier_port: None };
+        assert_eq!(slot.to_string(), "Bay 7 of 0:0:16:0");
+        let multiplier = DriveBay { host_port: Some(3), multiplier_port: Some(2), ..DriveBay::default() };
+        assert_eq!(multiplier.to_string(), "ata3 port 2");
+        assert!(!DriveBay::default().is_known());
+    }
+    
+    #[test]
+    fn test_virtualization_from_identity() {
+        assert_eq!(VirtualizationLayer::from_identity("vda", "0x1af4", ""), Some(VirtualizationLayer::Virtio));
+        assert_eq!(
+            VirtualizationLayer::from_identity("sdb", "VMware  ", "Virtual disk    "),
+            Some(VirtualizationLayer::Vmware)
+        );
+        assert_eq!(
+            VirtualizationLayer::from_identity("sda", "Msft    ", "Virtual Disk    "),
+            Some(VirtualizationLayer::HyperV)
+        );
+        assert_eq!(VirtualizationLayer::from_identity("sdc", "ATA     ", "QEMU HARDDISK"), Some(VirtualizationLayer::Qemu));
+        assert_eq!(VirtualizationLayer::from_identity("sda", "ATA     ", "Samsung SSD 870"), None);
+        assert_eq!(serde_json::to_value(VirtualizationLayer::HyperV).unwrap(), "hyper_v");
+    }
 }
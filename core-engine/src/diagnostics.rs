@@ -0,0 +1,194 @@
+//! Self-diagnostics of the running environment
+//!
+//! Frontends disable options the environment cannot honour: secure erase
+//! without root, HPA removal without `hdparm`, direct discard on a kernel
+//! without the ioctl. Rather than have each of them rediscover that, the
+//! engine probes once and reports every capability with a reason when it is
+//! missing, so the user is told up front instead of by a failed wipe.
+
+use serde::{Deserialize, Serialize};
+
+use crate::platform::{self, tools::Tool};
+
+/// Result of one probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+    pub name: String,
+    pub available: bool,
+    /// Where it was found when available, why not otherwise
+    pub detail: Option<String>,
+}
+
+/// What the current environment can do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub os: String,
+    pub arch: String,
+    /// Running as root or administrator
+    pub privileged: bool,
+    /// Device backend compiled for this target
+    pub platform_backend: String,
+    /// Kernel release, e.g. `6.8.0-45-generic`, on Linux
+    pub kernel_release: Option<String>,
+    /// Cryptographic backends in use; the core engine signs nothing, so
+    /// frontends add those of the certificates crate
+    pub signing_backends: Vec<String>,
+    pub probes: Vec<Probe>,
+}
+
+impl Probe {
+    pub fn available(name: &str, detail: Option<String>) -> Self {
+        Self { name: name.to_string(), available: true, detail }
+    }
+    
+    pub fn unavailable(name: &str, reason: impl Into<String>) -> Self {
+        Self { name: name.to_string(), available: false, detail: Some(reason.into()) }
+    }
+    
+    fn check(name: &str, available: bool, reason: &str) -> Self {
+        if available {
+            Self::available(name, None)
+        } else {
+            Self::unavailable(name, reason)
+        }
+    }
+}
+
+impl Diagnostics {
+    /// Probe the current environment
+    pub fn collect() -> Self {
+        let kernel_release = kernel_release();
+        let kernel = kernel_release.as_deref().and_then(parse_kernel_version);
+        let privileged = platform::has_admin_privileges();
+        
+        let mut probes = vec![
+            Probe::check("device-backend", platform::SUPPORTED, if cfg!(feature = "device-access") {
+                "no device backend for this operating system"
+            } else {
+                "not compiled in (device-access feature)"
+            }),
+            Probe::check("privileges", privileged, "run as root or administrator to open devices"),
+            Probe::check("secure-erase", cfg!(feature = "secure-erase"), "not compiled in (secure-erase feature)"),
+            Probe::check("hpa-dco", cfg!(feature = "hpa-dco"), "not compiled in (hpa-dco feature)"),
+        ];
+        for tool in Tool::ALL {
+            probes.push(match tool.find() {
+                Some(path) => Probe::available(tool.program(), Some(path.display().to_string())),
+                None if cfg!(feature = "external-tools") => Probe::unavailable(tool.program(), "not installed"),
+                None => Probe::unavailable(tool.program(), "not compiled in (external-tools feature)"),
+            });
+        }
+        probes.push(io_uring_probe(kernel));
+        probes.push(blkdiscard_probe(kernel));
+        
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            privileged,
+            platform_backend: platform::BACKEND.to_string(),
+            kernel_release,
+            signing_backends: Vec::new(),
+            probes,
+        }
+    }
+    
+    /// Record a cryptographic backend used outside the core engine
+    pub fn add_signing_backend(&mut self, backend: &str) {
+        if !self.signing_backends.iter().any(|known| known == backend) {
+            self.signing_backends.push(backend.to_string());
+        }
+    }
+    
+    pub fn probe(&self, name: &str) -> Option<&Probe> {
+        self.probes.iter().find(|probe| probe.name == name)
+    }
+    
+    /// Whether a probe succeeded; unknown names are unavailable
+    pub fn is_available(&self, name: &str) -> bool {
+        self.probe(name).is_some_and(|probe| probe.available)
+    }
+    
+    /// Probes that failed, with their reasons
+    pub fn unavailable(&self) -> impl Iterator<Item = &Probe> {
+        self.probes.iter().filter(|probe| !probe.available)
+    }
+}
+
+pub(crate) fn kernel_release() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+/// Major, minor and patch of a release such as `6.8.0-45-generic` or `5.15.153.1-microsoft`
+pub fn parse_kernel_version(release: &str) -> Option<(u32, u32, u32)> {
+    let numeric = release.split(|c: char| !c.is_ascii_digit() && c != '.').next()?;
+    let mut parts = numeric.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// io_uring appeared in 5.1; since 6.6 it can be switched off with a sysctl
+fn io_uring_probe(kernel: Option<(u32, u32, u32)>) -> Probe {
+    let Some(version) = kernel else {
+        return Probe::unavailable("io_uring", "Linux only");
+    };
+    if version < (5, 1, 0) {
+        return Probe::unavailable("io_uring", "requires Linux 5.1 or later");
+    }
+    match std::fs::read_to_string("/proc/sys/kernel/io_uring_disabled").map(|value| value.trim().to_string()) {
+        Ok(value) if value == "2" => Probe::unavailable("io_uring", "disabled by kernel.io_uring_disabled"),
+        Ok(value) if value == "1" && !platform::has_admin_privileges() => {
+            Probe::unavailable("io_uring", "restricted to privileged processes by kernel.io_uring_disabled")
+        }
+        _ => Probe::available("io_uring", None),
+    }
+}
+
+/// The BLKDISCARD ioctl used to discard whole devices exists since 2.6.28
+fn blkdiscard_probe(kernel: Option<(u32, u32, u32)>) -> Probe {
+    match kernel {
+        Some(version) if version >= (2, 6, 28) => Probe::available("blkdiscard-ioctl", None),
+        Some(_) => Probe::unavailable("blkdiscard-ioctl", "requires Linux 2.6.28 or later"),
+        None => Probe::unavailable("blkdiscard-ioctl", "Linux only"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_kernel_version() {
+        assert_eq!(parse_kernel_version("6.8.0-45-generic"), Some((6, 8, 0)));
+        assert_eq!(parse_kernel_version("5.15.153.1-microsoft-standard-WSL2"), Some((5, 15, 153)));
+        assert_eq!(parse_kernel_version("6.1"), Some((6, 1, 0)));
+        assert_eq!(parse_kernel_version("4.19-rc1"), Some((4, 19, 0)));
+        assert_eq!(parse_kernel_version("unknown"), None);
+        
+        assert!(!io_uring_probe(Some((4, 19, 0))).available);
+        assert!(blkdiscard_probe(Some((4, 19, 0))).available);
+        assert!(!blkdiscard_probe(None).available);
+    }
+    
+    #[test]
+    fn test_collect_reports_every_probe() {
+        let mut diagnostics = Diagnostics::collect();
+        assert_eq!(diagnostics.platform_backend, platform::BACKEND);
+        for name in ["device-backend", "privileges", "hdparm", "io_uring", "blkdiscard-ioctl"] {
+            assert!(diagnostics.probe(name).is_some(), "{}", name);
+        }
+        assert!(diagnostics.unavailable().all(|probe| probe.detail.is_some()));
+        assert!(!diagnostics.is_available("no-such-probe"));
+        
+        assert!(diagnostics.signing_backends.is_empty());
+        diagnostics.add_signing_backend("rustcrypto");
+        diagnostics.add_signing_backend("rustcrypto");
+        assert_eq!(diagnostics.signing_backends, ["rustcrypto"]);
+    }
+}
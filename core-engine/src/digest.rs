@@ -0,0 +1,155 @@
+//! Whole-device content digests
+//!
+//! After the final pass the engine can hash every byte of the device. The
+//! digest is recorded in the wipe result and the certificate; hashing the
+//! device again later and comparing proves it has not been written to since.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::device::Device;
+use crate::error::{ErrorContext, Result, ResultExt, SafeEraseError};
+use crate::platform;
+
+/// Hash function used for device digests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    Sha256,
+}
+
+/// Digest of the full contents of a device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceDigest {
+    pub algorithm: DigestAlgorithm,
+    /// Hex-encoded digest
+    pub value: String,
+    /// Number of bytes hashed, i.e. the device size at the time
+    pub bytes_hashed: u64,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Incremental digest over device contents read in order
+#[derive(Debug, Clone, Default)]
+pub struct DigestBuilder {
+    hasher: Sha256,
+    bytes_hashed: u64,
+}
+
+impl DigestBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Hash the next chunk of device contents
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.bytes_hashed += data.len() as u64;
+    }
+    
+    pub fn finish(self) -> DeviceDigest {
+        DeviceDigest {
+            algorithm: DigestAlgorithm::Sha256,
+            value: hex::encode(self.hasher.finalize()),
+            bytes_hashed: self.bytes_hashed,
+            computed_at: Utc::now(),
+        }
+    }
+}
+
+impl DeviceDigest {
+    /// Whether two digests cover the same content
+    pub fn matches(&self, other: &DeviceDigest) -> bool {
+        self.algorithm == other.algorithm
+            && self.bytes_hashed == other.bytes_hashed
+            && self.value.eq_ignore_ascii_case(&other.value)
+    }
+}
+
+/// Read the whole device and hash it
+pub async fn digest_device(
+    device: &Device,
+    block_size: usize,
+    cancel_token: &tokio_util::sync::CancellationToken,
+) -> Result<DeviceDigest> {
+    let size = device.get_info().await?.size;
+    let sector_size = device.capabilities().logical_sector_size.max(1) as usize;
+    // Reads must cover whole sectors
+    let block_size = (block_size.max(sector_size) / sector_size) * sector_size;
+    
+    info!("Computing digest of {} ({} bytes)", device.path(), size);
+    let mut builder = DigestBuilder::new();
+    let mut buffer = vec![0u8; block_size];
+    let mut offset = 0u64;
+    while offset < size {
+        if cancel_token.is_cancelled() {
+            return Err(SafeEraseError::WipeCancelled);
+        }
+        let length = block_size.min((size - offset) as usize);
+        let start_lba = offset / sector_size as u64;
+        let read = platform::read_sectors(device.handle(), start_lba, &mut buffer[..length])
+            .await
+            .with_error_context(|| ErrorContext::new().lba(start_lba))?;
+        if read == 0 {
+            return Err(SafeEraseError::DeviceIoError(format!(
+                "Unexpected end of device at offset {} while computing digest",
+                offset
+            )));
+        }
+        builder.update(&buffer[..read]);
+        offset += read as u64;
+    }
+    
+    Ok(builder.finish())
+}
+
+impl std::fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestAlgorithm::Sha256 => write!(f, "SHA-256"),
+        }
+    }
+}
+
+impl std::fmt::Display for DeviceDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_chunked_digest_matches_whole() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let mut chunked = DigestBuilder::new();
+        for chunk in data.chunks(4096) {
+            chunked.update(chunk);
+        }
+        let chunked = chunked.finish();
+        
+        let mut whole = DigestBuilder::new();
+        whole.update(&data);
+        let whole = whole.finish();
+        
+        assert!(chunked.matches(&whole));
+        assert_eq!(chunked.bytes_hashed, 10_000);
+        assert_eq!(chunked.value, hex::encode(Sha256::digest(&data)));
+        
+        let mut changed = DigestBuilder::new();
+        changed.update(&data[..9_999]);
+        assert!(!changed.finish().matches(&whole));
+    }
+    
+    #[test]
+    fn test_serialized_form() {
+        let digest = DigestBuilder::new().finish();
+        let json = serde_json::to_value(&digest).unwrap();
+        assert_eq!(json["algorithm"], "sha256");
+        assert_eq!(json["bytes_hashed"], 0);
+    }
+}
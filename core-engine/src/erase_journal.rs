@@ -0,0 +1,144 @@
+//! Record of hardware erases in flight
+//!
+//! ATA Secure Erase and NVMe Format run inside the drive and can take hours.
+//! If the process dies meanwhile, the drive is left half-way: an ATA drive
+//! still has the temporary password set and may be locked after a power
+//! cycle. Before a hardware erase is issued it is recorded here, one JSON
+//! file per drive serial; the next wipe of that drive finds the record,
+//! asks the drive how the erase ended and resumes or finalizes it. With a
+//! storage key the records are encrypted, as they name the drive's serial.
+
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::algorithms::WipeAlgorithm;
+use crate::at_rest::{self, StoreKey};
+use crate::error::{SafeEraseError, Result};
+
+/// A hardware erase that was issued and has not been seen to finish
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingErase {
+    pub operation_id: Uuid,
+    pub device_serial: String,
+    pub device_path: String,
+    pub algorithm: WipeAlgorithm,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Directory of pending erase records
+#[derive(Debug, Clone)]
+pub struct EraseJournal {
+    dir: PathBuf,
+    key: Option<StoreKey>,
+}
+
+/// Kind of record the journal's files are sealed as
+const SEALED_KIND: &str = "journal entry";
+
+impl EraseJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), key: None }
+    }
+    
+    /// Encrypt records with `key`; records written without one are still read
+    pub fn with_key(mut self, key: Option<StoreKey>) -> Self {
+        self.key = key;
+        self
+    }
+    
+    /// Record an erase about to be issued, replacing any earlier record for the drive
+    pub fn record(&self, erase: &PendingErase) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| journal_error(&self.dir, e))?;
+        let path = self.path(&erase.device_serial);
+        let json = serde_json::to_vec_pretty(erase)
+            .map_err(|e| SafeEraseError::Internal(format!("Cannot encode erase record: {}", e)))?;
+        let json = at_rest::seal(self.key.as_ref(), SEALED_KIND, json)?;
+        // Written under a temporary name so a crash never leaves half a record
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json).map_err(|e| journal_error(&temp, e))?;
+        std::fs::rename(&temp, &path).map_err(|e| journal_error(&path, e))
+    }
+    
+    /// The unfinished erase of a drive, if any; unreadable records count as none
+    pub fn pending(&self, device_serial: &str) -> Option<PendingErase> {
+        let contents = std::fs::read(self.path(device_serial)).ok()?;
+        let contents = at_rest::open(self.key.as_ref(), SEALED_KIND, contents).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+    
+    /// Forget the erase of a drive once its outcome is known
+    pub fn clear(&self, device_serial: &str) -> Result<()> {
+        let path = self.path(device_serial);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(journal_error(&path, e)),
+            _ => Ok(()),
+        }
+    }
+    
+    fn path(&self, device_serial: &str) -> PathBuf {
+        let name: String = device_serial
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+fn journal_error(path: &Path, e: std::io::Error) -> SafeEraseError {
+    SafeEraseError::FileSystemError(format!("Erase journal {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_record_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = EraseJournal::new(dir.path().join("erases"));
+        let erase = PendingErase {
+            operation_id: Uuid::new_v4(),
+            device_serial: "WD-WX12/34 ".to_string(),
+            device_path: "/dev/sdb".to_string(),
+            algorithm: WipeAlgorithm::ATASecureErase,
+            started_at: Utc::now(),
+        };
+        
+        assert!(journal.pending(&erase.device_serial).is_none());
+        journal.record(&erase).unwrap();
+        assert!(dir.path().join("erases/WD-WX12_34.json").exists());
+        assert_eq!(journal.pending(&erase.device_serial), Some(erase.clone()));
+        assert!(journal.pending("OTHER").is_none());
+        
+        journal.clear(&erase.device_serial).unwrap();
+        assert!(journal.pending(&erase.device_serial).is_none());
+        journal.clear(&erase.device_serial).unwrap();
+    }
+    
+    #[test]
+    fn test_encrypted_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = EraseJournal::new(dir.path());
+        let sealed = EraseJournal::new(dir.path()).with_key(Some(StoreKey::from_bytes([9; 32])));
+        let erase = PendingErase {
+            operation_id: Uuid::new_v4(),
+            device_serial: "S3Z9NB0K".to_string(),
+            device_path: "/dev/nvme0n1".to_string(),
+            algorithm: WipeAlgorithm::NVMeFormat,
+            started_at: Utc::now(),
+        };
+        
+        // A record from before the key was configured is still found
+        plain.record(&erase).unwrap();
+        assert_eq!(sealed.pending(&erase.device_serial), Some(erase.clone()));
+        
+        sealed.record(&erase).unwrap();
+        let contents = std::fs::read(dir.path().join("S3Z9NB0K.json")).unwrap();
+        assert!(at_rest::is_sealed(&contents));
+        assert_eq!(sealed.pending(&erase.device_serial), Some(erase.clone()));
+        assert!(plain.pending(&erase.device_serial).is_none());
+    }
+}
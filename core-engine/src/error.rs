@@ -1,6 +1,10 @@
 //! Error types for SafeErase operations
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use uuid::Uuid;
+
+use crate::i18n::LocalizedError;
 
 /// Result type alias for SafeErase operations
 pub type Result<T> = std::result::Result<T, SafeEraseError>;
@@ -24,6 +28,10 @@ pub enum SafeEraseError {
     #[error("Unsupported device type: {0}")]
     UnsupportedDevice(String),
     
+    /// The device disappeared while it was open, e.g. unplugged or dead
+    #[error("Device was removed: {0}")]
+    DeviceRemoved(String),
+    
     /// Wipe operation errors
     #[error("Wipe operation failed: {0}")]
     WipeFailed(String),
@@ -37,6 +45,14 @@ pub enum SafeEraseError {
     #[error("Unsupported wipe algorithm: {0}")]
     UnsupportedAlgorithm(String),
     
+    /// A write made no progress, e.g. behind a hung USB bridge
+    #[error("No data written for {} s at LBA {start_lba}-{end_lba}", .stalled_for.as_secs())]
+    OperationStalled {
+        start_lba: u64,
+        end_lba: u64,
+        stalled_for: std::time::Duration,
+    },
+    
     /// System-level errors
     #[error("Insufficient privileges - administrator/root access required")]
     InsufficientPrivileges,
@@ -64,6 +80,9 @@ pub enum SafeEraseError {
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
     
+    #[error("Operation violates wipe policy: {0}")]
+    PolicyViolation(String),
+    
     #[error("Operation timeout: {0}")]
     Timeout(String),
     
@@ -87,12 +106,127 @@ pub enum SafeEraseError {
     
     #[error("Unknown error: {0}")]
     Unknown(String),
+    
+    /// Another error annotated with where it happened
+    #[error("{source}")]
+    Contextual {
+        source: Box<SafeEraseError>,
+        context: ErrorContext,
+    },
+}
+
+/// Where an error occurred
+///
+/// Kept next to the error instead of in its message so logs, the audit trail
+/// and API responses can show the exact failure location of each of many
+/// concurrent wipes. Fields are filled in as the error travels outwards: the
+/// I/O layer knows the LBA, the pass loop the pass index and the engine the
+/// operation and device.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_serial: Option<String>,
+    /// Zero-based index of the overwrite pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pass_index: Option<usize>,
+    /// First logical block of the failed I/O
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lba: Option<u64>,
+}
+
+/// Stable error code for programmatic handling
+///
+/// Codes never change meaning once released: FFI consumers, REST clients and
+/// support scripts can branch on `number` or `name` instead of parsing
+/// messages. Engine errors use 1000-1999 and certificate errors 2000-2999;
+/// see `docs/error-codes.md` for the full table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ErrorCode {
+    /// Numeric code
+    pub number: u16,
+    /// Symbolic name, e.g. `DEVICE_NOT_FOUND`
+    pub name: &'static str,
+}
+
+impl ErrorCode {
+    pub const fn new(number: u16, name: &'static str) -> Self {
+        Self { number, name }
+    }
 }
 
 impl SafeEraseError {
+    /// Stable code identifying this kind of error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            SafeEraseError::Contextual { source, .. } => source.code(),
+            SafeEraseError::DeviceNotFound(_) => ErrorCode::new(1001, "DEVICE_NOT_FOUND"),
+            SafeEraseError::DeviceAccessDenied(_) => ErrorCode::new(1002, "DEVICE_ACCESS_DENIED"),
+            SafeEraseError::DeviceBusy(_) => ErrorCode::new(1003, "DEVICE_BUSY"),
+            SafeEraseError::DeviceIoError(_) => ErrorCode::new(1004, "DEVICE_IO_ERROR"),
+            SafeEraseError::UnsupportedDevice(_) => ErrorCode::new(1005, "UNSUPPORTED_DEVICE"),
+            SafeEraseError::DeviceRemoved(_) => ErrorCode::new(1006, "DEVICE_REMOVED"),
+            SafeEraseError::WipeFailed(_) => ErrorCode::new(1101, "WIPE_FAILED"),
+            SafeEraseError::WipeCancelled => ErrorCode::new(1102, "WIPE_CANCELLED"),
+            SafeEraseError::VerificationFailed => ErrorCode::new(1103, "VERIFICATION_FAILED"),
+            SafeEraseError::UnsupportedAlgorithm(_) => ErrorCode::new(1104, "UNSUPPORTED_ALGORITHM"),
+            SafeEraseError::OperationStalled { .. } => ErrorCode::new(1105, "OPERATION_STALLED"),
+            SafeEraseError::InsufficientPrivileges => ErrorCode::new(1201, "INSUFFICIENT_PRIVILEGES"),
+            SafeEraseError::SystemCommandFailed(_) => ErrorCode::new(1202, "SYSTEM_COMMAND_FAILED"),
+            SafeEraseError::UnsupportedPlatform(_) => ErrorCode::new(1203, "UNSUPPORTED_PLATFORM"),
+            SafeEraseError::CertificateError(_) => ErrorCode::new(1301, "CERTIFICATE_ERROR"),
+            SafeEraseError::CryptographicError(_) => ErrorCode::new(1302, "CRYPTOGRAPHIC_ERROR"),
+            SafeEraseError::SignatureVerificationFailed => ErrorCode::new(1303, "SIGNATURE_VERIFICATION_FAILED"),
+            SafeEraseError::InvalidConfiguration(_) => ErrorCode::new(1401, "INVALID_CONFIGURATION"),
+            SafeEraseError::InvalidParameter(_) => ErrorCode::new(1402, "INVALID_PARAMETER"),
+            SafeEraseError::PolicyViolation(_) => ErrorCode::new(1403, "POLICY_VIOLATION"),
+            SafeEraseError::Timeout(_) => ErrorCode::new(1404, "TIMEOUT"),
+            SafeEraseError::FileSystemError(_) => ErrorCode::new(1501, "FILE_SYSTEM_ERROR"),
+            SafeEraseError::PermissionDenied(_) => ErrorCode::new(1502, "PERMISSION_DENIED"),
+            SafeEraseError::NetworkError(_) => ErrorCode::new(1601, "NETWORK_ERROR"),
+            SafeEraseError::CommunicationTimeout => ErrorCode::new(1602, "COMMUNICATION_TIMEOUT"),
+            SafeEraseError::Internal(_) => ErrorCode::new(1901, "INTERNAL"),
+            SafeEraseError::Unknown(_) => ErrorCode::new(1999, "UNKNOWN"),
+        }
+    }
+    
+    /// Attach context; fields already recorded closer to the failure win
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            SafeEraseError::Contextual { source, context: mut existing } => {
+                existing.fill_from(context);
+                SafeEraseError::Contextual { source, context: existing }
+            }
+            error => SafeEraseError::Contextual {
+                source: Box::new(error),
+                context,
+            },
+        }
+    }
+    
+    /// Where the error occurred, if recorded
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            SafeEraseError::Contextual { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// The underlying error without context, for matching on variants
+    pub fn root(&self) -> &SafeEraseError {
+        match self {
+            SafeEraseError::Contextual { source, .. } => source.root(),
+            error => error,
+        }
+    }
+    
     /// Check if the error is recoverable
     pub fn is_recoverable(&self) -> bool {
         match self {
+            SafeEraseError::Contextual { source, .. } => source.is_recoverable(),
             SafeEraseError::DeviceBusy(_) => true,
             SafeEraseError::CommunicationTimeout => true,
             SafeEraseError::NetworkError(_) => true,
@@ -104,11 +238,15 @@ impl SafeEraseError {
     /// Get error severity level
     pub fn severity(&self) -> ErrorSeverity {
         match self {
+            SafeEraseError::Contextual { source, .. } => source.severity(),
             SafeEraseError::InsufficientPrivileges => ErrorSeverity::Critical,
             SafeEraseError::UnsupportedPlatform(_) => ErrorSeverity::Critical,
             SafeEraseError::VerificationFailed => ErrorSeverity::High,
             SafeEraseError::WipeFailed(_) => ErrorSeverity::High,
+            SafeEraseError::OperationStalled { .. } => ErrorSeverity::High,
+            SafeEraseError::DeviceRemoved(_) => ErrorSeverity::High,
             SafeEraseError::CertificateError(_) => ErrorSeverity::High,
+            SafeEraseError::PolicyViolation(_) => ErrorSeverity::High,
             SafeEraseError::DeviceNotFound(_) => ErrorSeverity::Medium,
             SafeEraseError::DeviceAccessDenied(_) => ErrorSeverity::Medium,
             SafeEraseError::InvalidConfiguration(_) => ErrorSeverity::Medium,
@@ -121,6 +259,7 @@ impl SafeEraseError {
     /// Get user-friendly error message
     pub fn user_message(&self) -> String {
         match self {
+            SafeEraseError::Contextual { source, .. } => source.user_message(),
             SafeEraseError::InsufficientPrivileges => {
                 "Administrator or root privileges are required to access storage devices.".to_string()
             }
@@ -142,11 +281,162 @@ impl SafeEraseError {
             SafeEraseError::UnsupportedDevice(device) => {
                 format!("Device type '{}' is not supported for secure wiping.", device)
             }
+            SafeEraseError::DeviceRemoved(device) => {
+                format!("Device '{}' was removed during the operation. Reconnect it and start again.", device)
+            }
+            SafeEraseError::PolicyViolation(reason) => {
+                format!("This operation is not permitted by your organization's wipe policy: {}", reason)
+            }
             _ => self.to_string(),
         }
     }
 }
 
+impl LocalizedError for SafeEraseError {
+    fn message_id(&self) -> &'static str {
+        match self {
+            SafeEraseError::Contextual { source, .. } => source.message_id(),
+            SafeEraseError::DeviceNotFound(_) => "error-device-not-found",
+            SafeEraseError::DeviceAccessDenied(_) => "error-device-access-denied",
+            SafeEraseError::DeviceBusy(_) => "error-device-busy",
+            SafeEraseError::DeviceIoError(_) => "error-device-io",
+            SafeEraseError::UnsupportedDevice(_) => "error-unsupported-device",
+            SafeEraseError::DeviceRemoved(_) => "error-device-removed",
+            SafeEraseError::WipeFailed(_) => "error-wipe-failed",
+            SafeEraseError::WipeCancelled => "error-wipe-cancelled",
+            SafeEraseError::VerificationFailed => "error-verification-failed",
+            SafeEraseError::UnsupportedAlgorithm(_) => "error-unsupported-algorithm",
+            SafeEraseError::OperationStalled { .. } => "error-operation-stalled",
+            SafeEraseError::InsufficientPrivileges => "error-insufficient-privileges",
+            SafeEraseError::SystemCommandFailed(_) => "error-system-command-failed",
+            SafeEraseError::UnsupportedPlatform(_) => "error-unsupported-platform",
+            SafeEraseError::CertificateError(_) => "error-certificate",
+            SafeEraseError::CryptographicError(_) => "error-cryptographic",
+            SafeEraseError::SignatureVerificationFailed => "error-signature-verification-failed",
+            SafeEraseError::InvalidConfiguration(_) => "error-invalid-configuration",
+            SafeEraseError::InvalidParameter(_) => "error-invalid-parameter",
+            SafeEraseError::PolicyViolation(_) => "error-policy-violation",
+            SafeEraseError::Timeout(_) => "error-timeout",
+            SafeEraseError::FileSystemError(_) => "error-file-system",
+            SafeEraseError::PermissionDenied(_) => "error-permission-denied",
+            SafeEraseError::NetworkError(_) => "error-network",
+            SafeEraseError::CommunicationTimeout => "error-communication-timeout",
+            SafeEraseError::Internal(_) => "error-internal",
+            SafeEraseError::Unknown(_) => "error-unknown",
+        }
+    }
+    
+    fn message_args(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SafeEraseError::Contextual { source, .. } => source.message_args(),
+            SafeEraseError::DeviceNotFound(device)
+            | SafeEraseError::DeviceAccessDenied(device)
+            | SafeEraseError::DeviceBusy(device)
+            | SafeEraseError::UnsupportedDevice(device)
+            | SafeEraseError::DeviceRemoved(device) => vec![("device", device.clone())],
+            SafeEraseError::WipeFailed(reason) | SafeEraseError::PolicyViolation(reason) => {
+                vec![("reason", reason.clone())]
+            }
+            SafeEraseError::DeviceIoError(detail)
+            | SafeEraseError::UnsupportedAlgorithm(detail)
+            | SafeEraseError::SystemCommandFailed(detail)
+            | SafeEraseError::UnsupportedPlatform(detail)
+            | SafeEraseError::CertificateError(detail)
+            | SafeEraseError::CryptographicError(detail)
+            | SafeEraseError::InvalidConfiguration(detail)
+            | SafeEraseError::InvalidParameter(detail)
+            | SafeEraseError::Timeout(detail)
+            | SafeEraseError::FileSystemError(detail)
+            | SafeEraseError::PermissionDenied(detail)
+            | SafeEraseError::NetworkError(detail)
+            | SafeEraseError::Internal(detail)
+            | SafeEraseError::Unknown(detail) => vec![("detail", detail.clone())],
+            SafeEraseError::OperationStalled { start_lba, end_lba, stalled_for } => vec![
+                ("start_lba", start_lba.to_string()),
+                ("end_lba", end_lba.to_string()),
+                ("seconds", stalled_for.as_secs().to_string()),
+            ],
+            SafeEraseError::WipeCancelled
+            | SafeEraseError::VerificationFailed
+            | SafeEraseError::InsufficientPrivileges
+            | SafeEraseError::SignatureVerificationFailed
+            | SafeEraseError::CommunicationTimeout => Vec::new(),
+        }
+    }
+    
+    fn canonical_message(&self) -> String {
+        self.user_message()
+    }
+}
+
+impl ErrorContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn operation(mut self, operation_id: Uuid) -> Self {
+        self.operation_id = Some(operation_id);
+        self
+    }
+    
+    pub fn device(mut self, path: impl Into<String>, serial: impl Into<String>) -> Self {
+        self.device_path = Some(path.into());
+        self.device_serial = Some(serial.into());
+        self
+    }
+    
+    pub fn pass(mut self, pass_index: usize) -> Self {
+        self.pass_index = Some(pass_index);
+        self
+    }
+    
+    pub fn lba(mut self, lba: u64) -> Self {
+        self.lba = Some(lba);
+        self
+    }
+    
+    /// Fill fields that are still unset from `other`
+    pub fn fill_from(&mut self, other: ErrorContext) {
+        self.operation_id = self.operation_id.or(other.operation_id);
+        self.device_path = self.device_path.take().or(other.device_path);
+        self.device_serial = self.device_serial.take().or(other.device_serial);
+        self.pass_index = self.pass_index.or(other.pass_index);
+        self.lba = self.lba.or(other.lba);
+    }
+    
+    /// Context as `(name, value)` pairs for structured log and event fields
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(operation_id) = self.operation_id {
+            fields.push(("operation_id", operation_id.to_string()));
+        }
+        if let Some(device_path) = &self.device_path {
+            fields.push(("device", device_path.clone()));
+        }
+        if let Some(device_serial) = &self.device_serial {
+            fields.push(("serial", device_serial.clone()));
+        }
+        if let Some(pass_index) = self.pass_index {
+            fields.push(("pass", pass_index.to_string()));
+        }
+        if let Some(lba) = self.lba {
+            fields.push(("lba", lba.to_string()));
+        }
+        fields
+    }
+}
+
+/// Attach [`ErrorContext`] to the error of a `Result`
+pub trait ResultExt<T> {
+    fn with_error_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn with_error_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T> {
+        self.map_err(|error| error.with_context(context()))
+    }
+}
+
 /// Error severity levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorSeverity {
@@ -156,6 +446,23 @@ pub enum ErrorSeverity {
     Critical,
 }
 
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .fields()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{} {}", self.number, self.name)
+    }
+}
+
 impl std::fmt::Display for ErrorSeverity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -179,6 +486,7 @@ impl From<std::io::Error> for SafeEraseError {
     }
 }
 
+#[cfg(feature = "signed-policy")]
 impl From<openssl::error::ErrorStack> for SafeEraseError {
     fn from(err: openssl::error::ErrorStack) -> Self {
         SafeEraseError::CryptographicError(err.to_string())
@@ -202,6 +510,61 @@ mod tests {
         assert!(!SafeEraseError::InsufficientPrivileges.is_recoverable());
     }
     
+    #[test]
+    fn test_error_codes_are_unique() {
+        let errors = [
+            SafeEraseError::DeviceNotFound(String::new()),
+            SafeEraseError::DeviceAccessDenied(String::new()),
+            SafeEraseError::DeviceBusy(String::new()),
+            SafeEraseError::DeviceIoError(String::new()),
+            SafeEraseError::UnsupportedDevice(String::new()),
+            SafeEraseError::DeviceRemoved(String::new()),
+            SafeEraseError::WipeFailed(String::new()),
+            SafeEraseError::WipeCancelled,
+            SafeEraseError::VerificationFailed,
+            SafeEraseError::UnsupportedAlgorithm(String::new()),
+            SafeEraseError::OperationStalled { start_lba: 0, end_lba: 0, stalled_for: std::time::Duration::ZERO },
+            SafeEraseError::InsufficientPrivileges,
+            SafeEraseError::SystemCommandFailed(String::new()),
+            SafeEraseError::UnsupportedPlatform(String::new()),
+            SafeEraseError::CertificateError(String::new()),
+            SafeEraseError::CryptographicError(String::new()),
+            SafeEraseError::SignatureVerificationFailed,
+            SafeEraseError::InvalidConfiguration(String::new()),
+            SafeEraseError::InvalidParameter(String::new()),
+            SafeEraseError::PolicyViolation(String::new()),
+            SafeEraseError::Timeout(String::new()),
+            SafeEraseError::FileSystemError(String::new()),
+            SafeEraseError::PermissionDenied(String::new()),
+            SafeEraseError::NetworkError(String::new()),
+            SafeEraseError::CommunicationTimeout,
+            SafeEraseError::Internal(String::new()),
+            SafeEraseError::Unknown(String::new()),
+        ];
+        let codes: std::collections::HashSet<u16> = errors.iter().map(|e| e.code().number).collect();
+        assert_eq!(codes.len(), errors.len());
+        assert!(codes.iter().all(|code| (1000..2000).contains(code)));
+        assert_eq!(SafeEraseError::WipeCancelled.code().to_string(), "E1102 WIPE_CANCELLED");
+    }
+    
+    #[test]
+    fn test_context_is_kept_out_of_the_message() {
+        let operation_id = Uuid::new_v4();
+        let error = SafeEraseError::DeviceIoError("write failed".to_string())
+            .with_context(ErrorContext::new().pass(1).lba(2048))
+            .with_context(ErrorContext::new().operation(operation_id).device("/dev/sdb", "SN1").lba(0));
+        
+        assert_eq!(error.to_string(), "Device I/O error: write failed");
+        assert_eq!(error.code().name, "DEVICE_IO_ERROR");
+        assert!(matches!(error.root(), SafeEraseError::DeviceIoError(_)));
+        
+        let context = error.context().unwrap();
+        assert_eq!(context.lba, Some(2048));
+        assert_eq!(context.pass_index, Some(1));
+        assert_eq!(context.operation_id, Some(operation_id));
+        assert_eq!(context.device_serial.as_deref(), Some("SN1"));
+    }
+    
     #[test]
     fn test_user_messages() {
         let error = SafeEraseError::DeviceNotFound("sda".to_string());
@@ -0,0 +1,121 @@
+//! systemd journal sink using the native journal protocol
+//!
+//! Each event becomes one journal entry with `MESSAGE`, `PRIORITY` and
+//! `SYSLOG_IDENTIFIER` plus the event fields as `SAFEERASE_<FIELD>`, so entries
+//! can be queried with e.g. `journalctl SAFEERASE_EVENT=operation_finished`.
+
+use tracing::warn;
+
+use super::{EventSeverity, EventSink, OperationEvent};
+use crate::error::Result;
+
+/// Socket journald listens on for native protocol datagrams
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Prefix of the event fields in journal entries
+pub const FIELD_PREFIX: &str = "SAFEERASE_";
+
+/// Sends operation events to the systemd journal
+#[derive(Debug)]
+pub struct JournaldSink {
+    identifier: String,
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+impl JournaldSink {
+    /// Create a sink tagging entries with the given syslog identifier
+    pub fn new(identifier: &str) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            let socket = std::os::unix::net::UnixDatagram::unbound()
+                .map_err(|e| crate::SafeEraseError::Internal(format!("Cannot create journal socket: {}", e)))?;
+            Ok(Self {
+                identifier: identifier.to_string(),
+                socket,
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = identifier;
+            Err(crate::SafeEraseError::UnsupportedPlatform("journald is only available on Linux".to_string()))
+        }
+    }
+}
+
+impl EventSink for JournaldSink {
+    fn emit(&self, event: &OperationEvent) {
+        let entry = encode_entry(event, &self.identifier);
+        #[cfg(unix)]
+        if let Err(e) = self.socket.send_to(&entry, JOURNAL_SOCKET) {
+            warn!("Failed to send {} event to journald: {}", event.name(), e);
+        }
+        #[cfg(not(unix))]
+        warn!("Dropping {} event: journald is not available ({} bytes)", event.name(), entry.len());
+    }
+}
+
+/// Encode an event as a native journal protocol entry
+pub fn encode_entry(event: &OperationEvent, identifier: &str) -> Vec<u8> {
+    let mut entry = Vec::new();
+    append_field(&mut entry, "MESSAGE", &event.message());
+    append_field(&mut entry, "PRIORITY", priority(event.severity()));
+    append_field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+    
+    for (name, value) in event.fields() {
+        let key = format!("{}{}", FIELD_PREFIX, name.to_ascii_uppercase());
+        append_field(&mut entry, &key, &value);
+    }
+    entry
+}
+
+/// Append `KEY=value\n`, or the length-prefixed form for values containing newlines
+fn append_field(entry: &mut Vec<u8>, key: &str, value: &str) {
+    entry.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+fn priority(severity: EventSeverity) -> &'static str {
+    match severity {
+        EventSeverity::Info => "6",
+        EventSeverity::Warning => "4",
+        EventSeverity::Error => "3",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_encode_entry() {
+        let event = OperationEvent::Error {
+            device_path: "/dev/sdb".to_string(),
+            error: "Device not found".to_string(),
+            context: None,
+        };
+        let entry = String::from_utf8(encode_entry(&event, "safeerase")).unwrap();
+        
+        assert!(entry.contains("PRIORITY=3\n"));
+        assert!(entry.contains("SYSLOG_IDENTIFIER=safeerase\n"));
+        assert!(entry.contains("SAFEERASE_EVENT=operation_error\n"));
+        assert!(entry.contains("SAFEERASE_DEVICE=/dev/sdb\n"));
+    }
+    
+    #[test]
+    fn test_multiline_values_are_length_prefixed() {
+        let mut entry = Vec::new();
+        append_field(&mut entry, "SAFEERASE_ERROR", "line one\nline two");
+        
+        assert!(entry.starts_with(b"SAFEERASE_ERROR\n"));
+        assert_eq!(&entry[16..24], &17u64.to_le_bytes());
+        assert!(entry.ends_with(b"line two\n"));
+    }
+}
@@ -0,0 +1,477 @@
+//! Structured operation events for external log pipelines
+//!
+//! In addition to its `tracing` output, the engine reports operation start and
+//! stop, status transitions and errors to every registered [`EventSink`]. The
+//! field names returned by [`OperationEvent::fields`] are part of the public
+//! interface and shared by all sinks, so log pipelines can rely on them.
+
+pub mod journald;
+pub mod siem;
+pub mod syslog;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::algorithms::WipeAlgorithm;
+use crate::config::EventDefaults;
+use crate::error::{ErrorContext, Result};
+use crate::operator::OperatorContext;
+use crate::redaction::Redactor;
+use crate::wipe::{WipeProgress, WipeResult, WipeStatus};
+
+pub use journald::JournaldSink;
+pub use siem::{SiemFormat, SiemSink};
+pub use syslog::SyslogSink;
+
+/// Event describing a step in the life of a wipe operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationEvent {
+    /// A wipe was requested for a device
+    Started {
+        device_path: String,
+        algorithm: WipeAlgorithm,
+        operator: OperatorContext,
+    },
+    /// The operation moved to a new status
+    StatusChanged {
+        operation_id: Uuid,
+        device_path: String,
+        status: WipeStatus,
+    },
+    /// The operation ended, successfully or not
+    Finished {
+        operation_id: Uuid,
+        device_path: String,
+        algorithm: WipeAlgorithm,
+        status: WipeStatus,
+        bytes_wiped: u64,
+        duration: Option<Duration>,
+        error: Option<String>,
+        /// Where the wipe failed
+        context: Option<ErrorContext>,
+        operator: Option<OperatorContext>,
+    },
+    /// Post-wipe verification found data that was not erased
+    VerificationFailed {
+        operation_id: Uuid,
+        device_path: String,
+    },
+    /// A safety interlock or policy restriction was bypassed for a wipe
+    PolicyOverride {
+        device_path: String,
+        /// User or client that requested the override
+        actor: String,
+        detail: String,
+    },
+    /// The operator entered the PSID of a locked Opal drive to revert it;
+    /// the PSID itself is never part of an event
+    PsidEntered {
+        device_path: String,
+        operator: OperatorContext,
+    },
+    /// The operation could not be carried out
+    Error {
+        device_path: String,
+        error: String,
+        context: Option<ErrorContext>,
+    },
+}
+
+/// Severity attached to an event, mapped onto syslog priorities by the sinks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Destination for operation events
+///
+/// Sinks must not block for long and must not fail the wipe: delivery
+/// problems are logged and the event is dropped.
+pub trait EventSink: Send + Sync + std::fmt::Debug {
+    fn emit(&self, event: &OperationEvent);
+}
+
+/// Fans events out to all registered sinks
+#[derive(Debug, Clone, Default)]
+pub struct EventDispatcher {
+    sinks: Vec<Arc<dyn EventSink>>,
+    /// Masks device serials before events reach the sinks
+    redactor: Option<Redactor>,
+}
+
+impl OperationEvent {
+    /// Build the terminal event for a wipe result
+    pub fn finished(result: &WipeResult) -> Self {
+        OperationEvent::Finished {
+            operation_id: result.operation_id,
+            device_path: result.device_path.clone(),
+            algorithm: result.algorithm.clone(),
+            status: result.status,
+            bytes_wiped: result.bytes_wiped,
+            duration: result.duration,
+            error: result.error_message.clone(),
+            context: result.error_context.clone(),
+            operator: result.operator.clone(),
+        }
+    }
+    
+    /// Copy of the event with the device serial masked in the failure
+    /// location and in error messages that quote it
+    pub fn redacted(&self, redactor: &Redactor) -> Self {
+        let mut event = self.clone();
+        match &mut event {
+            OperationEvent::Finished { error, context, .. } => {
+                redact_context(redactor, error.as_mut(), context.as_mut());
+            }
+            OperationEvent::Error { error, context, .. } => {
+                redact_context(redactor, Some(error), context.as_mut());
+            }
+            _ => {}
+        }
+        event
+    }
+    
+    /// Stable event name, e.g. `operation_started`
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperationEvent::Started { .. } => "operation_started",
+            OperationEvent::StatusChanged { .. } => "status_changed",
+            OperationEvent::Finished { .. } => "operation_finished",
+            OperationEvent::VerificationFailed { .. } => "verification_failed",
+            OperationEvent::PolicyOverride { .. } => "policy_override",
+            OperationEvent::PsidEntered { .. } => "psid_entered",
+            OperationEvent::Error { .. } => "operation_error",
+        }
+    }
+    
+    /// Severity of the event
+    pub fn severity(&self) -> EventSeverity {
+        match self {
+            OperationEvent::Finished { status: WipeStatus::Failed | WipeStatus::DeviceRemoved, .. } => EventSeverity::Error,
+            OperationEvent::Finished { status: WipeStatus::Cancelled, .. } => EventSeverity::Warning,
+            OperationEvent::VerificationFailed { .. } => EventSeverity::Error,
+            OperationEvent::PolicyOverride { .. } => EventSeverity::Warning,
+            OperationEvent::PsidEntered { .. } => EventSeverity::Warning,
+            OperationEvent::Error { .. } => EventSeverity::Error,
+            _ => EventSeverity::Info,
+        }
+    }
+    
+    /// Device the event refers to
+    pub fn device_path(&self) -> &str {
+        match self {
+            OperationEvent::Started { device_path, .. }
+            | OperationEvent::StatusChanged { device_path, .. }
+            | OperationEvent::Finished { device_path, .. }
+            | OperationEvent::VerificationFailed { device_path, .. }
+            | OperationEvent::PolicyOverride { device_path, .. }
+            | OperationEvent::PsidEntered { device_path, .. }
+            | OperationEvent::Error { device_path, .. } => device_path,
+        }
+    }
+    
+    /// Human-readable summary
+    pub fn message(&self) -> String {
+        match self {
+            OperationEvent::Started { device_path, algorithm, operator } => {
+                format!("Wipe of {} started with {} by {}", device_path, algorithm, operator.identity)
+            }
+            OperationEvent::StatusChanged { device_path, status, .. } => {
+                format!("Wipe of {} is now {}", device_path, status)
+            }
+            OperationEvent::Finished { device_path, status, error: Some(error), .. } => {
+                format!("Wipe of {} finished with status {}: {}", device_path, status, error)
+            }
+            OperationEvent::Finished { device_path, status, .. } => {
+                format!("Wipe of {} finished with status {}", device_path, status)
+            }
+            OperationEvent::VerificationFailed { device_path, .. } => {
+                format!("Verification of the wipe of {} failed", device_path)
+            }
+            OperationEvent::PolicyOverride { device_path, actor, detail } => {
+                format!("{} overrode safeguards for {}: {}", actor, device_path, detail)
+            }
+            OperationEvent::PsidEntered { device_path, operator } => {
+                format!("{} entered the PSID of {} for a PSID revert", operator.identity, device_path)
+            }
+            OperationEvent::Error { device_path, error, .. } => {
+                format!("Wipe of {} failed: {}", device_path, error)
+            }
+        }
+    }
+    
+    /// Structured fields with stable lowercase names
+    ///
+    /// `event` and `device` are always present; `operation_id`, `algorithm`,
+    /// `status`, `bytes_wiped`, `duration_ms`, `error`, `actor`, `auth_method`,
+    /// `session_id` and `detail` appear when known, as do `serial`, `pass` and
+    /// `lba` for failures whose location was recorded.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("event", self.name().to_string()),
+            ("device", self.device_path().to_string()),
+        ];
+        
+        match self {
+            OperationEvent::Started { algorithm, operator, .. } => {
+                fields.push(("algorithm", algorithm.to_string()));
+                push_operator(&mut fields, Some(operator));
+            }
+            OperationEvent::StatusChanged { operation_id, status, .. } => {
+                fields.push(("operation_id", operation_id.to_string()));
+                fields.push(("status", format!("{:?}", status)));
+            }
+            OperationEvent::Finished { operation_id, algorithm, status, bytes_wiped, duration, error, context, operator, .. } => {
+                fields.push(("operation_id", operation_id.to_string()));
+                fields.push(("algorithm", algorithm.to_string()));
+                fields.push(("status", format!("{:?}", status)));
+                fields.push(("bytes_wiped", bytes_wiped.to_string()));
+                if let Some(duration) = duration {
+                    fields.push(("duration_ms", duration.as_millis().to_string()));
+                }
+                if let Some(error) = error {
+                    fields.push(("error", error.clone()));
+                }
+                push_location(&mut fields, context.as_ref());
+                push_operator(&mut fields, operator.as_ref());
+            }
+            OperationEvent::VerificationFailed { operation_id, .. } => {
+                fields.push(("operation_id", operation_id.to_string()));
+            }
+            OperationEvent::PolicyOverride { actor, detail, .. } => {
+                fields.push(("actor", actor.clone()));
+                fields.push(("detail", detail.clone()));
+            }
+            OperationEvent::PsidEntered { operator, .. } => {
+                push_operator(&mut fields, Some(operator));
+            }
+            OperationEvent::Error { error, context, .. } => {
+                fields.push(("error", error.clone()));
+                if let Some(operation_id) = context.as_ref().and_then(|c| c.operation_id) {
+                    fields.push(("operation_id", operation_id.to_string()));
+                }
+                push_location(&mut fields, context.as_ref());
+            }
+        }
+        
+        fields
+    }
+}
+
+/// Failure location fields not already covered by the event itself
+fn push_location(fields: &mut Vec<(&'static str, String)>, context: Option<&ErrorContext>) {
+    let Some(context) = context else { return };
+    for (name, value) in context.fields() {
+        if matches!(name, "serial" | "pass" | "lba") {
+            fields.push((name, value));
+        }
+    }
+}
+
+fn push_operator(fields: &mut Vec<(&'static str, String)>, operator: Option<&OperatorContext>) {
+    let Some(operator) = operator else { return };
+    fields.push(("actor", operator.identity.clone()));
+    fields.push(("auth_method", operator.auth_method.to_string()));
+    if let Some(session_id) = &operator.session_id {
+        fields.push(("session_id", session_id.clone()));
+    }
+}
+
+fn redact_context(redactor: &Redactor, error: Option<&mut String>, context: Option<&mut ErrorContext>) {
+    let Some(serial) = context.and_then(|c| c.device_serial.as_mut()) else { return };
+    if let Some(error) = error {
+        *error = redactor.redact_in(error, serial);
+    }
+    *serial = redactor.redact(serial);
+}
+
+impl EventDispatcher {
+    /// Create a dispatcher without sinks
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Register a sink
+    pub fn add_sink(&mut self, sink: Arc<dyn EventSink>) {
+        self.sinks.push(sink);
+    }
+    
+    /// Mask device serials in every event from now on
+    pub fn set_redactor(&mut self, redactor: Redactor) {
+        self.redactor = Some(redactor);
+    }
+    
+    /// Whether no sinks are registered
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+    
+    /// Deliver an event to every sink
+    pub fn emit(&self, event: &OperationEvent) {
+        let redacted = self.redactor.as_ref().map(|redactor| event.redacted(redactor));
+        let event = redacted.as_ref().unwrap_or(event);
+        for sink in &self.sinks {
+            sink.emit(event);
+        }
+    }
+    
+    /// Forward status transitions of operations on a device until the task is aborted
+    pub fn watch_status(
+        &self,
+        mut progress_rx: broadcast::Receiver<WipeProgress>,
+        device_path: &str,
+    ) -> Option<JoinHandle<()>> {
+        if self.is_empty() {
+            return None;
+        }
+        
+        let dispatcher = self.clone();
+        let device_path = device_path.to_string();
+        Some(tokio::spawn(async move {
+            let mut last_status: HashMap<Uuid, WipeStatus> = HashMap::new();
+            loop {
+                match progress_rx.recv().await {
+                    Ok(progress) if progress.device_path == device_path => {
+                        if last_status.insert(progress.operation_id, progress.status) != Some(progress.status) {
+                            dispatcher.emit(&OperationEvent::StatusChanged {
+                                operation_id: progress.operation_id,
+                                device_path: progress.device_path,
+                                status: progress.status,
+                            });
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }))
+    }
+}
+
+/// Build the sinks enabled in the `[events]` configuration section
+pub fn sinks_from_config(config: &EventDefaults) -> Result<Vec<Arc<dyn EventSink>>> {
+    let mut sinks: Vec<Arc<dyn EventSink>> = Vec::new();
+    if config.journald {
+        sinks.push(Arc::new(JournaldSink::new(&config.identifier)?));
+    }
+    if config.syslog {
+        sinks.push(Arc::new(SyslogSink::new(&config.syslog_socket, &config.identifier)?));
+    }
+    if let Some(address) = &config.siem_address {
+        let format = config.siem_format.parse()?;
+        sinks.push(Arc::new(SiemSink::new(address, format, &config.identifier)));
+    }
+    Ok(sinks)
+}
+
+impl std::fmt::Display for EventSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventSeverity::Info => write!(f, "info"),
+            EventSeverity::Warning => write!(f, "warning"),
+            EventSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<OperationEvent>>,
+    }
+    
+    impl EventSink for RecordingSink {
+        fn emit(&self, event: &OperationEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+    
+    fn progress(status: WipeStatus) -> WipeProgress {
+        WipeProgress {
+            operation_id: Uuid::nil(),
+            device_path: "/dev/sdb".to_string(),
+            bay: None,
+            algorithm: WipeAlgorithm::ZeroFill,
+            current_pass: 1,
+            total_passes: 1,
+            bytes_processed: 0,
+            total_bytes: 1024,
+            percentage: 0.0,
+            current_speed: 0.0,
+            average_speed: 0.0,
+            estimated_remaining: None,
+            current_pattern: None,
+            status,
+            started_at: chrono::Utc::now(),
+            last_updated: chrono::Utc::now(),
+            sequence: 1,
+        }
+    }
+    
+    #[test]
+    fn test_finished_fields() {
+        let event = OperationEvent::Finished {
+            operation_id: Uuid::nil(),
+            device_path: "/dev/sdb".to_string(),
+            algorithm: WipeAlgorithm::NIST80088,
+            status: WipeStatus::Failed,
+            bytes_wiped: 4096,
+            duration: Some(Duration::from_millis(1500)),
+            error: Some("I/O error".to_string()),
+            context: Some(ErrorContext::new().device("/dev/sdb", "SN1").pass(2).lba(8192)),
+            operator: Some(OperatorContext::new("alice", crate::AuthMethod::SmartCard).with_session_id("7")),
+        };
+        
+        let fields = event.fields();
+        assert_eq!(fields[0], ("event", "operation_finished".to_string()));
+        assert!(fields.contains(&("pass", "2".to_string())));
+        assert!(fields.contains(&("lba", "8192".to_string())));
+        assert!(fields.contains(&("status", "Failed".to_string())));
+        assert!(fields.contains(&("duration_ms", "1500".to_string())));
+        assert!(fields.contains(&("actor", "alice".to_string())));
+        assert!(fields.contains(&("auth_method", "smart_card".to_string())));
+        assert!(fields.contains(&("session_id", "7".to_string())));
+        assert_eq!(event.severity(), EventSeverity::Error);
+        
+        let redactor = Redactor::new("salt");
+        let redacted = OperationEvent::Error {
+            device_path: "/dev/sdb".to_string(),
+            error: "serial SN12345678 does not match".to_string(),
+            context: Some(ErrorContext::new().device("/dev/sdb", "SN12345678")),
+        }
+        .redacted(&redactor);
+        let fields = redacted.fields();
+        assert!(fields.contains(&("serial", redactor.redact("SN12345678"))));
+        assert!(fields.iter().all(|(_, value)| !value.contains("SN12345678")));
+    }
+    
+    #[tokio::test]
+    async fn test_status_transitions_are_deduplicated() {
+        let sink = Arc::new(RecordingSink::default());
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.add_sink(sink.clone());
+        
+        let (tx, rx) = broadcast::channel(16);
+        let watcher = dispatcher.watch_status(rx, "/dev/sdb").unwrap();
+        
+        tx.send(progress(WipeStatus::Wiping)).unwrap();
+        tx.send(progress(WipeStatus::Wiping)).unwrap();
+        tx.send(progress(WipeStatus::Completed)).unwrap();
+        drop(tx);
+        watcher.await.unwrap();
+        
+        let events = sink.events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], OperationEvent::StatusChanged { status: WipeStatus::Completed, .. }));
+    }
+}
@@ -0,0 +1,341 @@
+//! SIEM export in Common Event Format (CEF) or Log Event Extended Format (LEEF)
+//!
+//! Security-relevant events (wipe started and finished, verification failures,
+//! safeguard overrides and errors) are wrapped in a syslog header and streamed
+//! to a collector such as Splunk or QRadar over TCP, one record per line.
+//! Status transitions are not exported.
+
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::time::Duration;
+use chrono::{SecondsFormat, Utc};
+use tracing::{debug, warn};
+
+use super::{syslog, EventSeverity, EventSink, OperationEvent};
+use crate::error::{Result, SafeEraseError};
+use crate::wipe::WipeStatus;
+
+/// Vendor and product reported in record headers
+pub const VENDOR: &str = "SafeErase";
+pub const PRODUCT: &str = "SafeErase";
+
+/// Records buffered while the collector is slow or unreachable
+const QUEUE_CAPACITY: usize = 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Record format expected by the SIEM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiemFormat {
+    /// ArcSight Common Event Format
+    Cef,
+    /// IBM QRadar Log Event Extended Format 1.0
+    Leef,
+}
+
+/// Streams security events to a SIEM collector over TCP
+///
+/// Records are handed to a background thread so a slow collector never
+/// blocks a wipe; when the queue is full, records are dropped with a warning.
+#[derive(Debug)]
+pub struct SiemSink {
+    format: SiemFormat,
+    identifier: String,
+    tx: SyncSender<String>,
+}
+
+impl SiemSink {
+    /// Create a sink sending to `host:port`
+    pub fn new(address: &str, format: SiemFormat, identifier: &str) -> Self {
+        let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let address = address.to_string();
+        std::thread::Builder::new()
+            .name("safeerase-siem".to_string())
+            .spawn(move || run_sender(&address, rx))
+            .expect("failed to spawn SIEM sender thread");
+        
+        Self {
+            format,
+            identifier: identifier.to_string(),
+            tx,
+        }
+    }
+}
+
+impl EventSink for SiemSink {
+    fn emit(&self, event: &OperationEvent) {
+        if !is_exported(event) {
+            return;
+        }
+        
+        let record = match self.format {
+            SiemFormat::Cef => format_cef(event),
+            SiemFormat::Leef => format_leef(event),
+        };
+        let line = format!(
+            "<{}>1 {} - {} - - - {}\n",
+            syslog::priority(event.severity()),
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            self.identifier,
+            record
+        );
+        
+        match self.tx.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("SIEM queue is full, dropping {} event", event.name()),
+            Err(TrySendError::Disconnected(_)) => warn!("SIEM sender stopped, dropping {} event", event.name()),
+        }
+    }
+}
+
+/// Whether an event is forwarded to the SIEM
+pub fn is_exported(event: &OperationEvent) -> bool {
+    !matches!(event, OperationEvent::StatusChanged { .. })
+}
+
+/// Format an event as a CEF record
+pub fn format_cef(event: &OperationEvent) -> String {
+    let mut extension = vec![
+        format!("rt={}", Utc::now().timestamp_millis()),
+        format!("msg={}", escape_cef_extension(&event.message())),
+    ];
+    for (name, value) in event.fields() {
+        let value = escape_cef_extension(&value);
+        match name {
+            "device" => extension.push(format!("cs1Label=devicePath cs1={}", value)),
+            "algorithm" => extension.push(format!("cs2Label=algorithm cs2={}", value)),
+            "operation_id" => extension.push(format!("externalId={}", value)),
+            "status" => extension.push(format!("outcome={}", value)),
+            "bytes_wiped" => extension.push(format!("cn1Label=bytesWiped cn1={}", value)),
+            "duration_ms" => extension.push(format!("cn2Label=durationMs cn2={}", value)),
+            "actor" => extension.push(format!("suser={}", value)),
+            "error" | "detail" => extension.push(format!("reason={}", value)),
+            _ => {}
+        }
+    }
+    
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        escape_cef_header(VENDOR),
+        escape_cef_header(PRODUCT),
+        escape_cef_header(env!("CARGO_PKG_VERSION")),
+        signature_id(event),
+        escape_cef_header(title(event)),
+        severity_score(event.severity()),
+        extension.join(" ")
+    )
+}
+
+/// Format an event as a tab-delimited LEEF 1.0 record
+pub fn format_leef(event: &OperationEvent) -> String {
+    let mut attributes = vec![
+        format!("sev={}", severity_score(event.severity())),
+        format!("msg={}", escape_leef_value(&event.message())),
+    ];
+    for (name, value) in event.fields() {
+        let value = escape_leef_value(&value);
+        match name {
+            "device" => attributes.push(format!("devicePath={}", value)),
+            "algorithm" => attributes.push(format!("algorithm={}", value)),
+            "operation_id" => attributes.push(format!("operationId={}", value)),
+            "status" => attributes.push(format!("outcome={}", value)),
+            "bytes_wiped" => attributes.push(format!("bytesWiped={}", value)),
+            "duration_ms" => attributes.push(format!("durationMs={}", value)),
+            "actor" => attributes.push(format!("usrName={}", value)),
+            "error" | "detail" => attributes.push(format!("reason={}", value)),
+            _ => {}
+        }
+    }
+    
+    format!(
+        "LEEF:1.0|{}|{}|{}|{}|{}",
+        VENDOR,
+        PRODUCT,
+        env!("CARGO_PKG_VERSION"),
+        signature_id(event),
+        attributes.join("\t")
+    )
+}
+
+/// Stable event class identifier
+fn signature_id(event: &OperationEvent) -> &'static str {
+    match event {
+        OperationEvent::Started { .. } => "wipe_started",
+        OperationEvent::StatusChanged { .. } => "wipe_status",
+        OperationEvent::Finished { status: WipeStatus::Completed, .. } => "wipe_completed",
+        OperationEvent::Finished { status: WipeStatus::Cancelled, .. } => "wipe_cancelled",
+        OperationEvent::Finished { .. } => "wipe_failed",
+        OperationEvent::VerificationFailed { .. } => "verification_failed",
+        OperationEvent::PolicyOverride { .. } => "policy_override",
+        OperationEvent::PsidEntered { .. } => "psid_entered",
+        OperationEvent::Error { .. } => "wipe_error",
+    }
+}
+
+fn title(event: &OperationEvent) -> &'static str {
+    match signature_id(event) {
+        "wipe_started" => "Wipe started",
+        "wipe_completed" => "Wipe completed",
+        "wipe_cancelled" => "Wipe cancelled",
+        "wipe_failed" => "Wipe failed",
+        "verification_failed" => "Wipe verification failed",
+        "policy_override" => "Wipe safeguard overridden",
+        "psid_entered" => "PSID entered for revert",
+        "wipe_error" => "Wipe error",
+        _ => "Wipe status changed",
+    }
+}
+
+/// Severity on the 0-10 scale shared by CEF and LEEF
+fn severity_score(severity: EventSeverity) -> u8 {
+    match severity {
+        EventSeverity::Info => 3,
+        EventSeverity::Warning => 6,
+        EventSeverity::Error => 8,
+    }
+}
+
+/// Escape `\` and `|` in CEF header fields
+fn escape_cef_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Escape `\`, `=` and line breaks in CEF extension values
+fn escape_cef_extension(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+/// LEEF values cannot contain the tab delimiter or line breaks
+fn escape_leef_value(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+/// Deliver queued records, reconnecting after failures
+fn run_sender(address: &str, rx: Receiver<String>) {
+    let mut stream: Option<TcpStream> = None;
+    
+    for line in rx {
+        // One reconnect attempt per record: a connection dropped by the
+        // collector is only noticed on the next write
+        for _ in 0..2 {
+            if stream.is_none() {
+                stream = connect(address);
+            }
+            let Some(connection) = stream.as_mut() else { break };
+            match connection.write_all(line.as_bytes()) {
+                Ok(()) => break,
+                Err(e) => {
+                    debug!("SIEM connection to {} lost: {}", address, e);
+                    stream = None;
+                }
+            }
+        }
+        if stream.is_none() {
+            warn!("SIEM collector {} unreachable, record dropped", address);
+        }
+    }
+}
+
+fn connect(address: &str) -> Option<TcpStream> {
+    let resolved = match address.to_socket_addrs() {
+        Ok(mut addresses) => addresses.next(),
+        Err(e) => {
+            warn!("Cannot resolve SIEM collector {}: {}", address, e);
+            return None;
+        }
+    }?;
+    
+    match TcpStream::connect_timeout(&resolved, CONNECT_TIMEOUT) {
+        Ok(stream) => {
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            Some(stream)
+        }
+        Err(e) => {
+            warn!("Cannot connect to SIEM collector {}: {}", address, e);
+            None
+        }
+    }
+}
+
+impl FromStr for SiemFormat {
+    type Err = SafeEraseError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cef" => Ok(SiemFormat::Cef),
+            "leef" => Ok(SiemFormat::Leef),
+            other => Err(SafeEraseError::InvalidConfiguration(format!(
+                "Unknown SIEM format '{}', expected cef or leef",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for SiemFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SiemFormat::Cef => write!(f, "cef"),
+            SiemFormat::Leef => write!(f, "leef"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn override_event() -> OperationEvent {
+        OperationEvent::PolicyOverride {
+            device_path: "/dev/sda".to_string(),
+            actor: "night-shift".to_string(),
+            detail: "system disk interlock=off".to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_cef_record() {
+        let record = format_cef(&override_event());
+        assert!(record.starts_with("CEF:0|SafeErase|SafeErase|"));
+        assert!(record.contains("|policy_override|Wipe safeguard overridden|6|"));
+        assert!(record.contains("suser=night-shift"));
+        assert!(record.contains("cs1Label=devicePath cs1=/dev/sda"));
+        assert!(record.contains("reason=system disk interlock\\=off"));
+    }
+    
+    #[test]
+    fn test_leef_record() {
+        let record = format_leef(&OperationEvent::VerificationFailed {
+            operation_id: uuid::Uuid::nil(),
+            device_path: "/dev/sdb".to_string(),
+        });
+        assert!(record.starts_with("LEEF:1.0|SafeErase|SafeErase|"));
+        assert!(record.contains("|verification_failed|sev=8\t"));
+        assert!(record.contains("\tdevicePath=/dev/sdb"));
+    }
+    
+    #[test]
+    fn test_status_changes_are_not_exported() {
+        let event = OperationEvent::StatusChanged {
+            operation_id: uuid::Uuid::nil(),
+            device_path: "/dev/sdb".to_string(),
+            status: WipeStatus::Wiping,
+        };
+        assert!(!is_exported(&event));
+        assert!(is_exported(&override_event()));
+    }
+    
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("CEF".parse::<SiemFormat>().unwrap(), SiemFormat::Cef);
+        assert_eq!("leef".parse::<SiemFormat>().unwrap(), SiemFormat::Leef);
+        assert!("json".parse::<SiemFormat>().is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! RFC 5424 syslog sink
+//!
+//! Events are sent to the local syslog socket with the event fields carried as
+//! structured data, e.g.
+//!
+//! ```text
+//! <30>1 2024-01-01T12:00:00Z - safeerase 4242 operation_started [safeerase@32473 event="operation_started" device="/dev/sdb" algorithm="NIST 800-88"] Wipe of /dev/sdb started with NIST 800-88
+//! ```
+
+use std::path::{Path, PathBuf};
+use chrono::{SecondsFormat, Utc};
+use tracing::warn;
+
+use super::{EventSeverity, EventSink, OperationEvent};
+use crate::error::Result;
+
+/// Default local syslog socket
+pub const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Structured data ID used for the event fields
+pub const STRUCTURED_DATA_ID: &str = "safeerase@32473";
+
+/// Syslog facility `daemon`
+const FACILITY_DAEMON: u8 = 3;
+
+/// Sends operation events to a local syslog daemon
+#[derive(Debug)]
+pub struct SyslogSink {
+    path: PathBuf,
+    identifier: String,
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+}
+
+impl SyslogSink {
+    /// Create a sink writing to the given syslog socket
+    pub fn new<P: AsRef<Path>>(path: P, identifier: &str) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            let socket = std::os::unix::net::UnixDatagram::unbound()
+                .map_err(|e| crate::SafeEraseError::Internal(format!("Cannot create syslog socket: {}", e)))?;
+            Ok(Self {
+                path: path.as_ref().to_path_buf(),
+                identifier: identifier.to_string(),
+                socket,
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (path, identifier);
+            Err(crate::SafeEraseError::UnsupportedPlatform("syslog sockets are only available on Unix".to_string()))
+        }
+    }
+}
+
+impl EventSink for SyslogSink {
+    fn emit(&self, event: &OperationEvent) {
+        let message = format_message(event, &self.identifier);
+        #[cfg(unix)]
+        if let Err(e) = self.socket.send_to(message.as_bytes(), &self.path) {
+            warn!("Failed to send {} event to syslog at {}: {}", event.name(), self.path.display(), e);
+        }
+        #[cfg(not(unix))]
+        warn!("Dropping {} event for {}: {}", event.name(), self.path.display(), message);
+    }
+}
+
+/// Format an event as an RFC 5424 message
+pub fn format_message(event: &OperationEvent, identifier: &str) -> String {
+    let structured_data = event
+        .fields()
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape_param_value(value)))
+        .collect::<String>();
+    
+    format!(
+        "<{}>1 {} - {} {} {} [{}{}] {}",
+        priority(event.severity()),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        identifier,
+        std::process::id(),
+        event.name(),
+        STRUCTURED_DATA_ID,
+        structured_data,
+        event.message()
+    )
+}
+
+/// PRI value: facility * 8 + severity
+pub(crate) fn priority(severity: EventSeverity) -> u8 {
+    let severity = match severity {
+        EventSeverity::Info => 6,
+        EventSeverity::Warning => 4,
+        EventSeverity::Error => 3,
+    };
+    FACILITY_DAEMON * 8 + severity
+}
+
+/// Escape `"`, `\` and `]` in structured data parameter values
+fn escape_param_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::WipeAlgorithm;
+    
+    #[test]
+    fn test_format_message() {
+        let event = OperationEvent::Started {
+            device_path: "/dev/sdb".to_string(),
+            algorithm: WipeAlgorithm::ZeroFill,
+            operator: crate::OperatorContext::local(),
+        };
+        let message = format_message(&event, "safeerase");
+        
+        assert!(message.starts_with("<30>1 "));
+        assert!(message.contains(" safeerase "));
+        assert!(message.contains("[safeerase@32473 event=\"operation_started\" device=\"/dev/sdb\""));
+    }
+    
+    #[test]
+    fn test_escape_param_value() {
+        assert_eq!(escape_param_value(r#"a "b" [c]"#), r#"a \"b\" [c\]"#);
+        assert_eq!(escape_param_value(r"C:\disk"), r"C:\\disk");
+    }
+}
@@ -0,0 +1,353 @@
+//! Streaming export of verification data and wipe results
+//!
+//! A Comprehensive verification can record tens of thousands of sector
+//! analyses, which are awkward to pull out of a single result document. These
+//! writers stream them one record per line as NDJSON or CSV so they can be
+//! loaded into pandas or a spreadsheet.
+//!
+//! [`ResultsExporter`] does the same for whole operations: one summary record
+//! per wipe, for reporting systems that want raw data rather than signed
+//! certificates.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SafeEraseError, Result};
+use crate::time_format;
+use crate::verification::{EntropyAnalysis, SectorAnalysis};
+use crate::wipe::WipeResult;
+
+/// Line-oriented export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// Column names of the sector analysis CSV export
+const SECTOR_COLUMNS: [&str; 6] = ["sector_offset", "entropy", "pattern_type", "confidence", "data_hash", "anomalies"];
+
+/// Column names of the wipe result CSV export, in the order of [`WipeResult::to_csv_row`]
+pub const RESULT_COLUMNS: [&str; 18] = [
+    "operation_id",
+    "device_path",
+    "device_serial",
+    "device_model",
+    "algorithm",
+    "status",
+    "started_at",
+    "completed_at",
+    "duration_seconds",
+    "bytes_wiped",
+    "passes_completed",
+    "verification_passed",
+    "hpa_detected",
+    "hpa_cleared",
+    "dco_detected",
+    "dco_cleared",
+    "work_order",
+    "error_message",
+];
+
+/// Writes one summary record per wipe result, as NDJSON or CSV
+#[derive(Debug)]
+pub struct ResultsExporter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    header_pending: bool,
+    count: usize,
+}
+
+impl<W: Write> ResultsExporter<W> {
+    /// Exporter starting a new output; CSV output begins with a header row
+    pub fn new(writer: W, format: ExportFormat) -> Self {
+        Self {
+            writer,
+            format,
+            header_pending: format == ExportFormat::Csv,
+            count: 0,
+        }
+    }
+    
+    pub fn write(&mut self, result: &WipeResult) -> io::Result<()> {
+        if self.header_pending {
+            writeln!(self.writer, "{}", RESULT_COLUMNS.join(","))?;
+            self.header_pending = false;
+        }
+        match self.format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, result)?;
+                writeln!(self.writer)?;
+            }
+            ExportFormat::Csv => writeln!(self.writer, "{}", result.to_csv_row())?,
+        }
+        self.count += 1;
+        Ok(())
+    }
+    
+    /// Records written by this exporter
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    
+    /// Flush and return the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl ResultsExporter<BufWriter<File>> {
+    /// Append to a results file, creating it if needed; a CSV header is only
+    /// written to an empty file
+    pub fn append<P: AsRef<Path>>(path: P, format: ExportFormat) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let mut exporter = Self::new(BufWriter::new(file), format);
+        exporter.header_pending &= empty;
+        Ok(exporter)
+    }
+}
+
+impl WipeResult {
+    /// The full result as a single-line JSON document
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| SafeEraseError::Internal(format!("Cannot encode wipe result: {}", e)))
+    }
+    
+    /// Summary of the result as one CSV line, without line break; see [`RESULT_COLUMNS`]
+    pub fn to_csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let fields = [
+            self.operation_id.to_string(),
+            self.device_path.clone(),
+            self.device_serial.clone(),
+            self.device_model.clone(),
+            self.algorithm.to_string(),
+            self.status.to_string(),
+            time_format::format_timestamp(&self.started_at),
+            optional(self.completed_at.as_ref().map(time_format::format_timestamp)),
+            optional(self.duration.map(|duration| format!("{:.3}", duration.as_secs_f64()))),
+            self.bytes_wiped.to_string(),
+            self.passes_completed.to_string(),
+            optional(self.verification_passed.map(|passed| passed.to_string())),
+            self.hpa_detected.to_string(),
+            self.hpa_cleared.to_string(),
+            self.dco_detected.to_string(),
+            self.dco_cleared.to_string(),
+            optional(self.work_order.clone()),
+            optional(self.error_message.clone()),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+        row.join(",")
+    }
+}
+
+/// Write sector analyses as they are produced; returns the number of records written
+pub fn write_sector_analyses<'a, W, I>(writer: &mut W, format: ExportFormat, analyses: I) -> io::Result<usize>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a SectorAnalysis>,
+{
+    if format == ExportFormat::Csv {
+        writeln!(writer, "{}", SECTOR_COLUMNS.join(","))?;
+    }
+    
+    let mut count = 0;
+    for analysis in analyses {
+        match format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *writer, analysis)?;
+                writeln!(writer)?;
+            }
+            ExportFormat::Csv => {
+                let fields = [
+                    analysis.sector_offset.to_string(),
+                    analysis.entropy.to_string(),
+                    format!("{:?}", analysis.pattern_type),
+                    analysis.confidence.to_string(),
+                    analysis.data_hash.clone(),
+                    analysis.anomalies.join("; "),
+                ];
+                write_csv_row(writer, &fields)?;
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Write the entropy histogram as `bucket,count` records in ascending bucket order
+pub fn write_entropy_distribution<W: Write>(
+    writer: &mut W,
+    format: ExportFormat,
+    analysis: &EntropyAnalysis,
+) -> io::Result<usize> {
+    let mut buckets: Vec<(&String, &usize)> = analysis.entropy_distribution.iter().collect();
+    // Buckets are formatted entropies such as "7.9"; sort numerically
+    buckets.sort_by(|(a, _), (b, _)| {
+        let a = a.parse::<f64>().unwrap_or(f64::MAX);
+        let b = b.parse::<f64>().unwrap_or(f64::MAX);
+        a.total_cmp(&b)
+    });
+    
+    if format == ExportFormat::Csv {
+        writeln!(writer, "bucket,count")?;
+    }
+    for (bucket, count) in &buckets {
+        match format {
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *writer, &serde_json::json!({ "bucket": bucket, "count": count }))?;
+                writeln!(writer)?;
+            }
+            ExportFormat::Csv => write_csv_row(writer, &[bucket.to_string(), count.to_string()])?,
+        }
+    }
+    Ok(buckets.len())
+}
+
+fn write_csv_row<W: Write>(writer: &mut W, fields: &[String]) -> io::Result<()> {
+    let row: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+    writeln!(writer, "{}", row.join(","))
+}
+
+/// Quote a field when it contains a delimiter, quote or line break
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Ndjson => write!(f, "NDJSON"),
+            ExportFormat::Csv => write!(f, "CSV"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::verification::PatternType;
+    
+    fn analysis(sector_offset: u64, anomalies: Vec<String>) -> SectorAnalysis {
+        SectorAnalysis {
+            sector_offset,
+            entropy: 7.5,
+            pattern_type: PatternType::Random,
+            confidence: 0.9,
+            data_hash: "abc".to_string(),
+            anomalies,
+        }
+    }
+    
+    #[test]
+    fn test_sector_exports() {
+        let analyses = vec![
+            analysis(0, vec![]),
+            analysis(4096, vec!["mismatch at 12, expected \"0x00\"".to_string()]),
+        ];
+        
+        let mut csv = Vec::new();
+        assert_eq!(write_sector_analyses(&mut csv, ExportFormat::Csv, &analyses).unwrap(), 2);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "sector_offset,entropy,pattern_type,confidence,data_hash,anomalies");
+        assert_eq!(lines[1], "0,7.5,Random,0.9,abc,");
+        assert_eq!(lines[2], r#"4096,7.5,Random,0.9,abc,"mismatch at 12, expected ""0x00""""#);
+        
+        let mut ndjson = Vec::new();
+        write_sector_analyses(&mut ndjson, ExportFormat::Ndjson, &analyses).unwrap();
+        let records: Vec<serde_json::Value> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["sector_offset"], 4096);
+    }
+    
+    #[test]
+    fn test_entropy_buckets_sorted_numerically() {
+        let analysis = EntropyAnalysis {
+            average_entropy: 5.0,
+            min_entropy: 0.0,
+            max_entropy: 10.0,
+            entropy_distribution: HashMap::from([
+                ("10.0".to_string(), 1),
+                ("0.0".to_string(), 3),
+                ("7.9".to_string(), 2),
+            ]),
+            low_entropy_sectors: Vec::new(),
+        };
+        let mut csv = Vec::new();
+        write_entropy_distribution(&mut csv, ExportFormat::Csv, &analysis).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "bucket,count\n0.0,3\n7.9,2\n10.0,1\n");
+    }
+    
+    fn wipe_result(serial: &str) -> WipeResult {
+        serde_json::from_value(serde_json::json!({
+            "operation_id": "6f1c2b0e-8a4d-4c3e-9b1a-2d5e7f9a0c11",
+            "device_path": "/dev/sdb",
+            "device_serial": serial,
+            "device_model": "Test SSD",
+            "algorithm": "ZeroFill",
+            "options": crate::wipe::WipeOptions::default(),
+            "status": "Completed",
+            "started_at": "2024-05-01T10:00:00Z",
+            "completed_at": "2024-05-01T10:30:00Z",
+            "duration": "PT30M",
+            "bytes_wiped": 1000,
+            "passes_completed": 1,
+            "verification_requested": true,
+            "verification_passed": true,
+            "hpa_detected": false,
+            "hpa_cleared": false,
+            "dco_detected": false,
+            "dco_cleared": false,
+            "error_message": null,
+            "performance_stats": {
+                "average_speed": 1.0, "peak_speed": 1.0, "total_time": "PT30M", "wipe_time": "PT30M"
+            },
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_results_exporter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        for serial in ["SN1", "SN,2"] {
+            let mut exporter = ResultsExporter::append(&path, ExportFormat::Csv).unwrap();
+            exporter.write(&wipe_result(serial)).unwrap();
+            assert_eq!(exporter.count(), 1);
+            exporter.finish().unwrap();
+        }
+        
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], RESULT_COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "6f1c2b0e-8a4d-4c3e-9b1a-2d5e7f9a0c11,/dev/sdb,SN1,Test SSD,Zero Fill,Completed,\
+             2024-05-01T10:00:00Z,2024-05-01T10:30:00Z,1800.000,1000,1,true,false,false,false,false,,"
+        );
+        assert!(lines[2].contains(",\"SN,2\","));
+        
+        let mut ndjson = ResultsExporter::new(Vec::new(), ExportFormat::Ndjson);
+        ndjson.write(&wipe_result("SN1")).unwrap();
+        let output = String::from_utf8(ndjson.finish().unwrap()).unwrap();
+        assert_eq!(output.trim_end(), wipe_result("SN1").to_json().unwrap());
+    }
+}
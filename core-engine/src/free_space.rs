@@ -0,0 +1,211 @@
+//! Free-space wiping of mounted filesystems
+//!
+//! Overwrites the unallocated blocks of a live filesystem without touching
+//! existing files: a fill file is grown until the filesystem reports it is
+//! full, synced, rewritten in place for every further pass of the algorithm and
+//! finally removed.
+
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::algorithms::{WipeAlgorithm, WipePattern};
+use crate::error::{SafeEraseError, Result};
+
+/// Prefix of the temporary file used to fill the free space
+pub const FILL_FILE_PREFIX: &str = ".safeerase-fill-";
+
+/// Smallest write attempted once the filesystem starts rejecting full blocks
+const MIN_WRITE_SIZE: usize = 512;
+
+/// Result of a free-space wipe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeSpaceWipeResult {
+    pub operation_id: Uuid,
+    pub mount_point: PathBuf,
+    pub algorithm: WipeAlgorithm,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub passes_completed: usize,
+    /// Free space overwritten by each pass
+    pub bytes_per_pass: u64,
+}
+
+/// Overwrite all free space on the filesystem mounted at `mount_point`
+///
+/// Cancelling `cancel` stops the wipe after the current block and removes the
+/// fill file; the result is then `WipeCancelled`.
+pub async fn wipe_free_space(
+    mount_point: &Path,
+    algorithm: WipeAlgorithm,
+    block_size: usize,
+    cancel: &CancellationToken,
+) -> Result<FreeSpaceWipeResult> {
+    if algorithm.is_hardware_based() {
+        return Err(SafeEraseError::UnsupportedAlgorithm(format!(
+            "{} cannot be applied to free space",
+            algorithm
+        )));
+    }
+    if !mount_point.is_dir() {
+        return Err(SafeEraseError::InvalidParameter(format!(
+            "{} is not a mounted directory",
+            mount_point.display()
+        )));
+    }
+    if block_size < MIN_WRITE_SIZE {
+        return Err(SafeEraseError::InvalidParameter(format!(
+            "block size must be at least {} bytes",
+            MIN_WRITE_SIZE
+        )));
+    }
+    
+    let operation_id = Uuid::new_v4();
+    let started_at = Utc::now();
+    let fill_path = mount_point.join(format!("{}{}", FILL_FILE_PREFIX, operation_id));
+    info!("Starting free-space wipe {} of {}", operation_id, mount_point.display());
+    
+    let patterns = algorithm.patterns();
+    let path = fill_path.clone();
+    let token = cancel.clone();
+    let outcome = tokio::task::spawn_blocking(move || fill_passes(&path, &patterns, block_size, None, &token))
+        .await
+        .map_err(|e| SafeEraseError::Internal(e.to_string()))?;
+    
+    if let Err(e) = std::fs::remove_file(&fill_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove fill file {}: {}", fill_path.display(), e);
+        }
+    }
+    
+    if cancel.is_cancelled() {
+        info!("Free-space wipe {} of {} cancelled", operation_id, mount_point.display());
+        return Err(SafeEraseError::WipeCancelled);
+    }
+    let (passes_completed, bytes_per_pass) = outcome
+        .map_err(|e| SafeEraseError::FileSystemError(format!("{}: {}", mount_point.display(), e)))?;
+    info!(
+        "Free-space wipe {} overwrote {} bytes in {} pass(es)",
+        operation_id, bytes_per_pass, passes_completed
+    );
+    
+    Ok(FreeSpaceWipeResult {
+        operation_id,
+        mount_point: mount_point.to_path_buf(),
+        algorithm,
+        started_at,
+        completed_at: Utc::now(),
+        passes_completed,
+        bytes_per_pass,
+    })
+}
+
+/// Run every pass over the fill file
+///
+/// Without a `limit` the first pass writes until the filesystem is full and
+/// later passes rewrite the same number of bytes.
+fn fill_passes(
+    path: &Path,
+    patterns: &[WipePattern],
+    block_size: usize,
+    mut limit: Option<u64>,
+    cancel: &CancellationToken,
+) -> std::io::Result<(usize, u64)> {
+    let mut file = OpenOptions::new().create_new(true).write(true).open(path)?;
+    let mut previous: Option<Vec<u8>> = None;
+    
+    for pattern in patterns {
+        file.seek(SeekFrom::Start(0))?;
+        let mut block = pattern.generate_data(block_size, previous.as_deref());
+        let mut written = 0u64;
+        let mut chunk = block_size;
+        
+        loop {
+            if cancel.is_cancelled() {
+                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "free-space wipe cancelled"));
+            }
+            if let Some(limit) = limit {
+                if written >= limit {
+                    break;
+                }
+                chunk = chunk.min((limit - written) as usize);
+            }
+            match file.write(&block[..chunk]) {
+                Ok(0) => break,
+                Ok(n) => written += n as u64,
+                Err(e) if is_disk_full(&e) => {
+                    // Use up the last partial blocks before giving up
+                    if chunk <= MIN_WRITE_SIZE {
+                        break;
+                    }
+                    chunk /= 2;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            if *pattern == WipePattern::Random {
+                block = pattern.generate_data(block_size, None);
+            }
+        }
+        
+        file.sync_all()?;
+        limit.get_or_insert(written);
+        previous = Some(block);
+    }
+    
+    Ok((patterns.len(), limit.unwrap_or(0)))
+}
+
+fn is_disk_full(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        error.raw_os_error() == Some(libc::ENOSPC) || error.raw_os_error() == Some(libc::EDQUOT)
+    }
+    #[cfg(windows)]
+    {
+        // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL
+        matches!(error.raw_os_error(), Some(39) | Some(112))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_rejects_hardware_algorithms() {
+        let dir = tempfile::tempdir().unwrap();
+        let cancel = CancellationToken::new();
+        let result = wipe_free_space(dir.path(), WipeAlgorithm::ATASecureErase, 4096, &cancel).await;
+        assert!(matches!(result, Err(SafeEraseError::UnsupportedAlgorithm(_))));
+        
+        cancel.cancel();
+        let result = wipe_free_space(dir.path(), WipeAlgorithm::ZeroFill, 4096, &cancel).await;
+        assert!(matches!(result, Err(SafeEraseError::WipeCancelled)));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+    
+    #[test]
+    fn test_later_passes_overwrite_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fill");
+        
+        let (passes, bytes) = fill_passes(&path, &[WipePattern::Zeros, WipePattern::Ones], 4096, Some(10_000), &CancellationToken::new()).unwrap();
+        assert_eq!(passes, 2);
+        assert_eq!(bytes, 10_000);
+        
+        let contents = std::fs::read(&path).unwrap();
+        assert_eq!(contents.len(), 10_000);
+        assert!(contents.iter().all(|&b| b == 0xFF));
+    }
+}
@@ -0,0 +1,154 @@
+//! Identity of the host performing a wipe
+//!
+//! Audit schemes ask which machine and which software build sanitized a
+//! drive. [`HostInfo::collect`] records the host name, operating system,
+//! kernel, architecture and byte order, the SafeErase version, and the
+//! SHA-256 of every helper program found, so a later review can tell whether
+//! the `hdparm` that cleared an HPA was the packaged one.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::diagnostics;
+use crate::platform::tools::Tool;
+
+/// The host and software build a wipe ran on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    /// `linux`, `windows` or `macos`
+    pub os: String,
+    /// Distribution or release name, e.g. `Ubuntu 24.04.1 LTS`
+    pub os_version: Option<String>,
+    pub kernel_release: Option<String>,
+    pub arch: String,
+    /// `little` or `big`
+    pub endianness: String,
+    pub safeerase_version: String,
+    /// Helper programs installed on the host, with their digests
+    pub tools: Vec<ToolIdentity>,
+}
+
+/// A helper program and the digest of its executable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolIdentity {
+    pub tool: Tool,
+    pub path: PathBuf,
+    /// Hex-encoded SHA-256; absent when the file could not be read
+    pub sha256: Option<String>,
+}
+
+impl HostInfo {
+    /// Describe the running host; hashes the helper programs, so call it off the async runtime
+    pub fn collect() -> Self {
+        let tools = Tool::ALL
+            .iter()
+            .filter_map(|tool| {
+                let path = tool.find()?;
+                let sha256 = match hash_file(&path) {
+                    Ok(digest) => Some(digest),
+                    Err(e) => {
+                        debug!("Cannot hash {}: {}", path.display(), e);
+                        None
+                    }
+                };
+                Some(ToolIdentity { tool: *tool, path, sha256 })
+            })
+            .collect();
+        
+        Self {
+            hostname: hostname(),
+            os: std::env::consts::OS.to_string(),
+            os_version: os_version(),
+            kernel_release: diagnostics::kernel_release(),
+            arch: std::env::consts::ARCH.to_string(),
+            endianness: if cfg!(target_endian = "big") { "big" } else { "little" }.to_string(),
+            safeerase_version: env!("CARGO_PKG_VERSION").to_string(),
+            tools,
+        }
+    }
+}
+
+impl std::fmt::Display for HostInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let os = self.os_version.as_deref().unwrap_or(&self.os);
+        write!(f, "{} ({}, {}), SafeErase {}", self.hostname, os, self.arch, self.safeerase_version)
+    }
+}
+
+/// Name of this machine, `unknown` when none is configured
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn os_version() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let os_release = std::fs::read_to_string("/etc/os-release").ok()?;
+    parse_os_release(&os_release)
+}
+
+/// `PRETTY_NAME` of an os-release file, falling back to `NAME` and `VERSION`
+fn parse_os_release(contents: &str) -> Option<String> {
+    let value = |key: &str| {
+        contents.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').to_string())
+        })
+    };
+    value("PRETTY_NAME").or_else(|| match (value("NAME"), value("VERSION")) {
+        (Some(name), Some(version)) => Some(format!("{} {}", name, version)),
+        (name, _) => name,
+    })
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_os_release() {
+        let ubuntu = "NAME=\"Ubuntu\"\nVERSION=\"24.04.1 LTS (Noble Numbat)\"\nPRETTY_NAME=\"Ubuntu 24.04.1 LTS\"\n";
+        assert_eq!(parse_os_release(ubuntu).as_deref(), Some("Ubuntu 24.04.1 LTS"));
+        assert_eq!(parse_os_release("NAME=Alpine\nVERSION=3.20\n").as_deref(), Some("Alpine 3.20"));
+        assert_eq!(parse_os_release("ID=minimal\n"), None);
+    }
+    
+    #[test]
+    fn test_collect_describes_this_build() {
+        let host = HostInfo::collect();
+        assert_eq!(host.safeerase_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(host.os, std::env::consts::OS);
+        assert!(host.endianness == "little" || host.endianness == "big");
+        
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"abc").unwrap();
+        assert_eq!(
+            hash_file(file.path()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Localized user-facing messages
+//!
+//! Error messages are looked up in a Fluent message catalog so front ends can
+//! show them in the operator's language. Logs and `user_message()` keep the
+//! canonical English text; translations are only produced on request through
+//! [`LocalizedError::localized_message`].
+//!
+//! Locales resolve from most to least specific: `de-AT` falls back to `de`
+//! and then to English. Applications can ship additional or corrected
+//! translations with [`MessageCatalog::add_resource`].
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::error::{Result, SafeEraseError};
+
+/// Locale of the canonical messages
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Message resources shipped with the engine
+const BUILTIN_RESOURCES: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US/errors.ftl")),
+    ("de", include_str!("../locales/de/errors.ftl")),
+    ("fr", include_str!("../locales/fr/errors.ftl")),
+];
+
+/// Translated message catalog keyed by locale
+pub struct MessageCatalog {
+    bundles: Vec<(LanguageIdentifier, FluentBundle<FluentResource>)>,
+}
+
+/// Errors that can be rendered from a message catalog
+pub trait LocalizedError {
+    /// Catalog message ID
+    fn message_id(&self) -> &'static str;
+    
+    /// Named arguments substituted into the message
+    fn message_args(&self) -> Vec<(&'static str, String)>;
+    
+    /// Canonical English message, used when no translation is available
+    fn canonical_message(&self) -> String;
+    
+    /// Message in the requested locale
+    fn localized_message(&self, catalog: &MessageCatalog, locale: &str) -> String {
+        catalog
+            .format(locale, self.message_id(), &self.message_args())
+            .unwrap_or_else(|| self.canonical_message())
+    }
+}
+
+impl MessageCatalog {
+    /// Empty catalog
+    pub fn new() -> Self {
+        Self { bundles: Vec::new() }
+    }
+    
+    /// Catalog containing the engine's built-in translations
+    pub fn builtin() -> Result<Self> {
+        let mut catalog = Self::new();
+        for (locale, source) in BUILTIN_RESOURCES {
+            catalog.add_resource(locale, source)?;
+        }
+        Ok(catalog)
+    }
+    
+    /// Add Fluent messages for a locale; later resources override earlier ones
+    pub fn add_resource(&mut self, locale: &str, source: &str) -> Result<()> {
+        let langid: LanguageIdentifier = locale
+            .parse()
+            .map_err(|_| SafeEraseError::InvalidParameter(format!("Invalid locale: {}", locale)))?;
+        let resource = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+            SafeEraseError::InvalidConfiguration(format!(
+                "Invalid message resource for {}: {:?}",
+                locale, errors
+            ))
+        })?;
+        
+        let index = match self.bundles.iter().position(|(id, _)| *id == langid) {
+            Some(index) => index,
+            None => {
+                let mut bundle = FluentBundle::new_concurrent(vec![langid.clone()]);
+                // Messages are shown in plain text widgets and logs
+                bundle.set_use_isolating(false);
+                self.bundles.push((langid, bundle));
+                self.bundles.len() - 1
+            }
+        };
+        self.bundles[index].1.add_resource_overriding(resource);
+        Ok(())
+    }
+    
+    /// Locales with at least one resource
+    pub fn locales(&self) -> Vec<String> {
+        self.bundles.iter().map(|(id, _)| id.to_string()).collect()
+    }
+    
+    /// Render a message, or `None` if no locale in the fallback chain defines it
+    pub fn format(&self, locale: &str, id: &str, args: &[(&str, String)]) -> Option<String> {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        
+        self.fallback_chain(locale).into_iter().find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+            errors.is_empty().then(|| text.into_owned())
+        })
+    }
+    
+    fn fallback_chain(&self, locale: &str) -> Vec<&FluentBundle<FluentResource>> {
+        let mut chain = Vec::new();
+        if let Ok(requested) = locale.parse::<LanguageIdentifier>() {
+            chain.extend(self.bundles.iter().filter(|(id, _)| *id == requested).map(|(_, b)| b));
+            chain.extend(
+                self.bundles
+                    .iter()
+                    .filter(|(id, _)| *id != requested && id.language == requested.language)
+                    .map(|(_, b)| b),
+            );
+        }
+        let default: LanguageIdentifier = DEFAULT_LOCALE.parse().expect("valid default locale");
+        chain.extend(self.bundles.iter().filter(|(id, _)| *id == default).map(|(_, b)| b));
+        chain
+    }
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for MessageCatalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageCatalog").field("locales", &self.locales()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_locale_fallback() {
+        let catalog = MessageCatalog::builtin().unwrap();
+        let error = SafeEraseError::DeviceNotFound("/dev/sdb".to_string());
+        
+        let german = error.localized_message(&catalog, "de-AT");
+        assert!(german.contains("/dev/sdb"));
+        assert!(german.contains("nicht gefunden"));
+        assert_eq!(error.localized_message(&catalog, "ja"), error.user_message());
+    }
+    
+    #[test]
+    fn test_english_catalog_matches_canonical_messages() {
+        let catalog = MessageCatalog::builtin().unwrap();
+        let errors = [
+            SafeEraseError::DeviceBusy("sda".to_string()),
+            SafeEraseError::DeviceRemoved("/dev/sdb".to_string()),
+            SafeEraseError::WipeCancelled,
+            SafeEraseError::PolicyViolation("algorithm not approved".to_string()),
+            SafeEraseError::InsufficientPrivileges,
+            SafeEraseError::Timeout("5s".to_string()),
+            SafeEraseError::OperationStalled { start_lba: 2048, end_lba: 4095, stalled_for: std::time::Duration::from_secs(120) },
+        ];
+        for error in errors {
+            assert_eq!(
+                catalog.format(DEFAULT_LOCALE, error.message_id(), &error.message_args()),
+                Some(error.user_message())
+            );
+        }
+    }
+    
+    #[test]
+    fn test_added_resource_overrides_builtin() {
+        let mut catalog = MessageCatalog::builtin().unwrap();
+        catalog
+            .add_resource("de", "error-wipe-cancelled = Löschen abgebrochen")
+            .unwrap();
+        assert_eq!(
+            SafeEraseError::WipeCancelled.localized_message(&catalog, "de"),
+            "Löschen abgebrochen"
+        );
+        assert!(catalog.add_resource("de", "error-broken = {").is_err());
+    }
+}
@@ -10,26 +10,98 @@ pub mod algorithms;
 pub mod verification;
 pub mod platform;
 pub mod error;
+pub mod config;
+pub mod policy;
+pub mod events;
+pub mod free_space;
+pub mod i18n;
+pub mod retry;
+pub mod mounts;
+pub mod readiness;
+pub mod time_format;
+pub mod digest;
+pub mod trim;
+pub mod export;
+pub mod sampling;
+pub mod sensitive_data;
+pub mod assets;
+pub mod redaction;
+pub mod diagnostics;
+pub mod memory;
+pub mod logging;
+pub mod smart_monitor;
+pub mod host;
+pub mod erase_journal;
+pub mod progress_history;
+pub mod regions;
+pub mod wear;
+pub mod block_seeds;
+pub mod at_rest;
+pub mod bench;
+pub mod operator;
+pub mod result_chain;
+pub mod opal;
+pub mod live_env;
+pub mod pool_membership;
+pub mod sleep_inhibit;
+pub mod cancellation;
+pub mod write_pipeline;
+pub mod checkpoint;
 
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 
-// Add missing dependency
-use tokio_util;
-
-pub use device::{Device, DeviceInfo, DeviceType, StorageInterface};
-pub use wipe::{WipeEngine, WipeProgress, WipeResult, WipeOptions};
-pub use algorithms::{WipeAlgorithm, WipePattern};
-pub use verification::{VerificationEngine, VerificationResult};
-pub use error::{SafeEraseError, Result};
+pub use device::{Device, DeviceInfo, DeviceType, DriveBay, HiddenArea, StorageInterface, TrimReadBehavior, VirtualizationLayer};
+pub use wipe::{HpaPolicy, PassRecord, SkippedRange, WipeEngine, WipeProgress, WipeResult, WipeOptions, WipeStatus, WriteBackend};
+pub use algorithms::{CustomAlgorithm, SecurityLevel, WipeAlgorithm, WipePattern};
+pub use verification::{VerificationEngine, VerificationResult, VerificationStatus, VerificationThresholds, VerificationType};
+pub use error::{ErrorCode, ErrorContext, ResultExt, SafeEraseError, Result};
+pub use config::{ConfigLoader, SafeEraseConfig};
+pub use policy::{SignedPolicy, WipePolicy};
+pub use events::{EventSink, OperationEvent};
+pub use free_space::FreeSpaceWipeResult;
+pub use i18n::{LocalizedError, MessageCatalog};
+pub use retry::{retry_recoverable, Recoverable, RetryPolicy};
+pub use readiness::{ReadinessIssue, WipeReadiness};
+pub use digest::{DeviceDigest, DigestAlgorithm};
+pub use trim::{TrimOutcome, TrimVerification};
+pub use export::{ExportFormat, ResultsExporter};
+pub use sensitive_data::{RiskLevel, SensitiveDataScan};
+pub use assets::{AssetManifest, AssetRecord};
+pub use redaction::Redactor;
+pub use diagnostics::{Diagnostics, Probe};
+pub use memory::{MemoryBudget, MemoryLimits};
+pub use smart_monitor::{SmartCounter, SmartDegradation, SmartMonitor};
+pub use host::{HostInfo, ToolIdentity};
+pub use erase_journal::{EraseJournal, PendingErase};
+pub use progress_history::{ProgressReplay, ProgressToken};
+pub use regions::{RegionResult, WipeRegion};
+pub use wear::{WearIndicators, WearReport};
+pub use block_seeds::{MasterSeed, SealedSeed, SeedKey};
+pub use at_rest::{KeySource, StoreKey};
+pub use bench::{BenchConfig, BenchReport, BenchTarget, IoBackend};
+pub use operator::{AuthMethod, OperatorContext};
+pub use result_chain::{ChainLink, ResultChain};
+pub use opal::{OpalStatus, Psid};
+pub use live_env::LiveEnvironment;
+pub use pool_membership::PoolMembership;
+pub use sleep_inhibit::{SleepGuard, SleepInhibitor};
+pub use cancellation::{CancelReason, Cancellation};
+pub use checkpoint::{CheckpointJournal, CheckpointPosition, WipeCheckpoint};
 
 /// Main SafeErase engine that coordinates all wiping operations
 #[derive(Debug)]
 pub struct SafeEraseEngine {
-    devices: Arc<RwLock<Vec<Device>>>,
+    /// Opened devices; wipes share them so a removal seen during one retires the stored handle
+    devices: Arc<RwLock<Vec<Arc<Device>>>>,
     wipe_engine: WipeEngine,
     verification_engine: VerificationEngine,
+    verification_tx: tokio::sync::broadcast::Sender<VerificationResult>,
+    policy: Option<WipePolicy>,
+    events: events::EventDispatcher,
+    storage_key: Option<StoreKey>,
+    sleep_inhibitor: SleepInhibitor,
 }
 
 impl SafeEraseEngine {
@@ -39,14 +111,84 @@ impl SafeEraseEngine {
         
         let wipe_engine = WipeEngine::new()?;
         let verification_engine = VerificationEngine::new()?;
+        let (verification_tx, _) = tokio::sync::broadcast::channel(64);
         
         Ok(Self {
             devices: Arc::new(RwLock::new(Vec::new())),
             wipe_engine,
             verification_engine,
+            verification_tx,
+            policy: None,
+            events: events::EventDispatcher::new(),
+            storage_key: None,
+            sleep_inhibitor: SleepInhibitor::new(),
         })
     }
     
+    /// Create an engine that applies the configured defaults
+    pub fn with_config(config: &SafeEraseConfig) -> Result<Self> {
+        let mut engine = Self::new()?;
+        if let Some(verification_type) = config.wipe.verification_type {
+            engine.verification_engine.set_verification_type(verification_type);
+        }
+        engine.verification_engine.set_sample_size(config.wipe.verification_sample_size);
+        engine.verification_engine.set_thresholds(config.wipe.verification_thresholds.clone());
+        engine.verification_engine.set_memory_limits(config.memory.limits());
+        if let (Some(policy_file), Some(public_key)) = (&config.policy.file, &config.policy.public_key) {
+            engine.set_policy(policy::load_policy(policy_file, public_key)?);
+        }
+        for sink in events::sinks_from_config(&config.events)? {
+            engine.add_event_sink(sink);
+        }
+        if let Some(redactor) = config.redaction.event_redactor() {
+            engine.events.set_redactor(redactor);
+        }
+        if let Some(masker) = config.redaction.log_masker() {
+            logging::mask_identifiers(masker);
+        }
+        if let Some(source) = &config.storage.key {
+            engine.set_storage_key(StoreKey::resolve(source)?);
+        }
+        Ok(engine)
+    }
+    
+    /// Encrypt the records the engine keeps, such as the erase journal; see [`at_rest`]
+    pub fn set_storage_key(&mut self, key: StoreKey) {
+        info!("Encrypting stored records with storage key {}", key.id());
+        self.storage_key = Some(key);
+    }
+    
+    /// Key stored records are encrypted with, for programs embedding the engine to use on theirs
+    pub fn storage_key(&self) -> Option<&StoreKey> {
+        self.storage_key.as_ref()
+    }
+    
+    /// Enforce an organizational wipe policy for all subsequent operations
+    pub fn set_policy(&mut self, policy: WipePolicy) {
+        info!("Enforcing wipe policy {}", policy.policy_id);
+        self.policy = Some(policy);
+    }
+    
+    /// Active wipe policy, if any
+    pub fn policy(&self) -> Option<&WipePolicy> {
+        self.policy.as_ref()
+    }
+    
+    /// Report operation events to an additional sink
+    pub fn add_event_sink(&mut self, sink: Arc<dyn EventSink>) {
+        self.events.add_sink(sink);
+    }
+    
+    /// Report an event raised outside the engine, e.g. an interlock override
+    pub fn emit_event(&self, event: &OperationEvent) {
+        self.events.emit(event);
+    }
+    
+    /// What the current environment supports, with the reason for anything missing
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::collect()
+    }
+    
     /// Discover all available storage devices
     pub async fn discover_devices(&self) -> Result<Vec<DeviceInfo>> {
         info!("Discovering storage devices");
@@ -56,10 +198,11 @@ impl SafeEraseEngine {
         devices.clear();
         
         for device_info in &discovered {
+            logging::protect_identifier(&device_info.serial);
             match Device::open(&device_info.path).await {
                 Ok(device) => {
                     info!("Successfully opened device: {}", device_info.name);
-                    devices.push(device);
+                    devices.push(Arc::new(device));
                 }
                 Err(e) => {
                     warn!("Failed to open device {}: {}", device_info.name, e);
@@ -70,34 +213,160 @@ impl SafeEraseEngine {
         Ok(discovered)
     }
     
+    /// Open a device that discovery does not list, such as a disk image, so it can be wiped
+    pub async fn open_device(&self, device_path: &str) -> Result<DeviceInfo> {
+        let device = Device::open(device_path).await?;
+        let info = device.get_info().await?;
+        let mut devices = self.devices.write().await;
+        devices.retain(|d| d.path() != device_path);
+        devices.push(Arc::new(device));
+        Ok(info)
+    }
+    
     /// Start a secure wipe operation on the specified device
+    ///
+    /// `operator` is whoever the frontend authenticated; it is recorded in the
+    /// operation events and the result, see [`operator`].
     pub async fn start_wipe(
         &self,
         device_path: &str,
         algorithm: WipeAlgorithm,
         options: WipeOptions,
+        operator: OperatorContext,
+    ) -> Result<WipeResult> {
+        self.supervise_wipe(device_path, algorithm, options, operator, None).await
+    }
+    
+    /// Continue a wipe of the device that a crash or power loss interrupted,
+    /// from the checkpoint in `checkpoint_dir`; see [`checkpoint`]
+    pub async fn resume_from_checkpoint(
+        &self,
+        device_path: &str,
+        checkpoint_dir: &std::path::Path,
+        operator: OperatorContext,
+    ) -> Result<WipeResult> {
+        let serial = {
+            let devices = self.devices.read().await;
+            let device = devices
+                .iter()
+                .find(|d| d.path() == device_path)
+                .ok_or_else(|| SafeEraseError::DeviceNotFound(device_path.to_string()))?;
+            device.get_info().await?.serial
+        };
+        let mut checkpoint = CheckpointJournal::new(checkpoint_dir)
+            .with_key(self.storage_key.clone())
+            .load(&serial)?
+            .ok_or_else(|| SafeEraseError::InvalidParameter(format!(
+                "No checkpoint for {} in {}", device_path, checkpoint_dir.display()
+            )))?;
+        // Checkpoints are written under the directory they are found in, even if it moved
+        checkpoint.options.checkpoint_dir = Some(checkpoint_dir.to_path_buf());
+        let (algorithm, options) = (checkpoint.algorithm.clone(), checkpoint.options.clone());
+        self.supervise_wipe(device_path, algorithm, options, operator, Some(checkpoint)).await
+    }
+    
+    async fn supervise_wipe(
+        &self,
+        device_path: &str,
+        algorithm: WipeAlgorithm,
+        options: WipeOptions,
+        operator: OperatorContext,
+        checkpoint: Option<WipeCheckpoint>,
+    ) -> Result<WipeResult> {
+        info!("Wipe of {} requested by {}", device_path, operator);
+        self.events.emit(&OperationEvent::Started {
+            device_path: device_path.to_string(),
+            algorithm: algorithm.clone(),
+            operator: operator.clone(),
+        });
+        if options.psid.is_some() {
+            self.events.emit(&OperationEvent::PsidEntered {
+                device_path: device_path.to_string(),
+                operator: operator.clone(),
+            });
+        }
+        let watcher = self.events.watch_status(self.subscribe_progress(), device_path);
+        
+        let awake = self.sleep_inhibitor.acquire();
+        let outcome = self.run_wipe(device_path, algorithm, options, operator, checkpoint).await;
+        drop(awake);
+        self.forget_removed_devices().await;
+        
+        if let Some(watcher) = watcher {
+            watcher.abort();
+        }
+        match &outcome {
+            Ok(result) => self.events.emit(&OperationEvent::finished(result)),
+            Err(e) => self.events.emit(&OperationEvent::Error {
+                device_path: device_path.to_string(),
+                error: e.to_string(),
+                context: e.context().cloned(),
+            }),
+        }
+        outcome
+    }
+    
+    async fn run_wipe(
+        &self,
+        device_path: &str,
+        algorithm: WipeAlgorithm,
+        options: WipeOptions,
+        operator: OperatorContext,
+        checkpoint: Option<WipeCheckpoint>,
     ) -> Result<WipeResult> {
         info!("Starting wipe operation on device: {}", device_path);
         
-        let devices = self.devices.read().await;
-        let device = devices
+        let device = self.devices
+            .read()
+            .await
             .iter()
             .find(|d| d.path() == device_path)
+            .cloned()
             .ok_or_else(|| SafeEraseError::DeviceNotFound(device_path.to_string()))?;
         
+        if let Some(policy) = &self.policy {
+            policy.check(&device.get_info().await?, &algorithm, &options)?;
+        }
+        
+        let mut options = options;
+        options.storage_key = self.storage_key.clone();
+        
         // Perform the wipe operation
-        let wipe_result = self.wipe_engine.wipe_device(device, algorithm, options).await?;
+        let mut wipe_result = match checkpoint {
+            Some(mut checkpoint) => {
+                checkpoint.options = options;
+                self.wipe_engine.resume_from_checkpoint(&device, checkpoint).await?
+            }
+            None => self.wipe_engine.wipe_device(&device, algorithm, options).await?,
+        };
+        wipe_result.policy_id = self.policy.as_ref().map(|p| p.policy_id.clone());
+        wipe_result.operator = Some(operator);
+        if wipe_result.verification_passed == Some(false) {
+            self.emit_verification_failed(&wipe_result);
+        }
         
-        // Verify the wipe if requested
-        if wipe_result.verification_requested {
+        // Verify only wipes that finished; a failed or cancelled one keeps its own outcome
+        if wipe_result.verification_requested && wipe_result.status == WipeStatus::Completed {
             info!("Starting verification for device: {}", device_path);
             let verification_result = self.verification_engine
-                .verify_wipe(device, &wipe_result)
+                .verify_wipe(&device, &wipe_result)
                 .await?;
+            let _ = self.verification_tx.send(verification_result.clone());
             
-            if !verification_result.is_successful() {
-                error!("Wipe verification failed for device: {}", device_path);
-                return Err(SafeEraseError::VerificationFailed);
+            let failures = verification_result.threshold_failures(self.verification_engine.thresholds());
+            if !failures.is_empty() {
+                error!("Wipe verification failed for device {}: {}", device_path, failures.join("; "));
+                if wipe_result.verification_passed != Some(false) {
+                    self.emit_verification_failed(&wipe_result);
+                }
+                // The result is kept so the failed wipe can still be reported
+                wipe_result.status = WipeStatus::Failed;
+                wipe_result.verification_passed = Some(false);
+                if let Some(last_pass) = wipe_result.passes.last_mut() {
+                    last_pass.verified = Some(false);
+                }
+                wipe_result.error_message = Some(format!("Wipe verification failed: {}", failures.join("; ")));
+                return Ok(wipe_result);
             }
         }
         
@@ -105,6 +374,109 @@ impl SafeEraseEngine {
         Ok(wipe_result)
     }
     
+    /// Drop devices whose handles were retired after removal, so a drive that
+    /// reappears at the same path is only used once discovery opens it afresh
+    async fn forget_removed_devices(&self) {
+        self.devices.write().await.retain(|device| !device.handle().is_removed());
+    }
+    
+    /// Verify a device again against the stored result of an earlier wipe, e.g. before shipping
+    /// drives kept in storage; see [`VerificationEngine::reverify`]
+    pub async fn verify_device(&self, device_path: &str, wipe_result: &WipeResult) -> Result<VerificationResult> {
+        info!("Re-verifying device {} wiped by operation {}", device_path, wipe_result.operation_id);
+        
+        let devices = self.devices.read().await;
+        let device = devices
+            .iter()
+            .find(|d| d.path() == device_path)
+            .ok_or_else(|| SafeEraseError::DeviceNotFound(device_path.to_string()))?;
+        
+        let _awake = self.sleep_inhibitor.acquire();
+        let verification_result = self.verification_engine.reverify(device, wipe_result).await?;
+        let _ = self.verification_tx.send(verification_result.clone());
+        if !verification_result.is_successful(self.verification_engine.thresholds()) {
+            warn!("Re-verification failed for device: {}", device_path);
+            self.emit_verification_failed(wipe_result);
+        }
+        Ok(verification_result)
+    }
+    
+    /// Hash the current contents of a device, e.g. to compare with the digest
+    /// recorded after its wipe
+    pub async fn device_digest(&self, device_path: &str) -> Result<DeviceDigest> {
+        let devices = self.devices.read().await;
+        let device = devices
+            .iter()
+            .find(|d| d.path() == device_path)
+            .ok_or_else(|| SafeEraseError::DeviceNotFound(device_path.to_string()))?;
+        
+        let block_size = WipeOptions::default().write_block_size();
+        digest::digest_device(device, block_size, &tokio_util::sync::CancellationToken::new()).await
+    }
+    
+    /// Criteria verification results are judged against
+    pub fn verification_thresholds(&self) -> &VerificationThresholds {
+        self.verification_engine.thresholds()
+    }
+    
+    /// Overwrite the free space of a mounted filesystem without touching its files
+    ///
+    /// Cancelling `cancel` stops the wipe and removes its fill file.
+    pub async fn wipe_free_space(
+        &self,
+        mount_point: &std::path::Path,
+        algorithm: WipeAlgorithm,
+        options: &WipeOptions,
+        cancel: &tokio_util::sync::CancellationToken,
+    ) -> Result<FreeSpaceWipeResult> {
+        let _awake = self.sleep_inhibitor.acquire();
+        free_space::wipe_free_space(mount_point, algorithm, options.block_size, cancel).await
+    }
+    
+    /// Keep the system awake while work outside the engine runs, e.g. a
+    /// frontend's own verification; see [`sleep_inhibit`]
+    pub fn inhibit_sleep(&self) -> SleepGuard {
+        self.sleep_inhibitor.acquire()
+    }
+    
+    fn emit_verification_failed(&self, wipe_result: &WipeResult) {
+        self.events.emit(&OperationEvent::VerificationFailed {
+            operation_id: wipe_result.operation_id,
+            device_path: wipe_result.device_path.clone(),
+        });
+    }
+    
+    /// Subscribe to progress updates from all wipe operations
+    pub fn subscribe_progress(&self) -> tokio::sync::broadcast::Receiver<WipeProgress> {
+        self.wipe_engine.subscribe_progress()
+    }
+    
+    /// Catch up on an operation's progress after reconnecting; see [`progress_history`]
+    pub fn resume_progress(&self, token: &ProgressToken) -> Result<ProgressReplay> {
+        self.wipe_engine.resume_progress(token)
+    }
+    
+    /// Subscribe to verification results as they are produced
+    pub fn subscribe_verification(&self) -> tokio::sync::broadcast::Receiver<VerificationResult> {
+        self.verification_tx.subscribe()
+    }
+    
+    /// Cancel the active wipe operation on the specified device, recording why
+    pub async fn cancel_wipe(&self, device_path: &str, cancellation: Cancellation) -> Result<uuid::Uuid> {
+        info!("Cancelling wipe operation on device: {}", device_path);
+        self.wipe_engine.cancel_device_operation(device_path, cancellation).await
+    }
+    
+    /// Pause a running overwrite before its next block
+    pub async fn pause_wipe(&self, operation_id: uuid::Uuid) -> Result<()> {
+        self.wipe_engine.pause_operation(operation_id).await
+    }
+    
+    /// Resume a paused overwrite
+    pub async fn resume_wipe(&self, operation_id: uuid::Uuid) -> Result<()> {
+        self.wipe_engine.resume_operation(operation_id).await
+    }
+    
     /// Get the current status of all devices
     pub async fn get_device_status(&self) -> Result<Vec<DeviceInfo>> {
         let devices = self.devices.read().await;
@@ -116,6 +488,36 @@ impl SafeEraseEngine {
         
         Ok(device_infos)
     }
+    
+    /// Free the drive a live environment booted from so it can be wiped,
+    /// then rediscover devices; see [`live_env`]
+    pub async fn release_boot_medium(&self, device_path: &str) -> Result<Vec<DeviceInfo>> {
+        let environment = LiveEnvironment::detect();
+        let device_path = device_path.to_string();
+        tokio::task::spawn_blocking(move || environment.release(&device_path))
+            .await
+            .map_err(|e| SafeEraseError::Internal(format!("Release task failed: {}", e)))??;
+        self.discover_devices().await
+    }
+    
+    /// Check whether a device can be wiped, listing blockers and warnings
+    pub async fn device_readiness(&self, device_path: &str) -> Result<WipeReadiness> {
+        let devices = self.devices.read().await;
+        if let Some(device) = devices.iter().find(|d| d.path() == device_path) {
+            return Ok(device.readiness());
+        }
+        drop(devices);
+        
+        match Device::open(device_path).await {
+            Ok(device) => Ok(device.readiness()),
+            // Opening requires the same rights the wipe does; report that as a blocker
+            Err(SafeEraseError::InsufficientPrivileges) => Ok(WipeReadiness {
+                blockers: vec![ReadinessIssue::InsufficientPrivileges],
+                warnings: Vec::new(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl Default for SafeEraseEngine {
@@ -141,4 +543,68 @@ mod tests {
         // Should not fail even if no devices are found
         assert!(result.is_ok());
     }
+    
+    #[tokio::test]
+    async fn test_open_device_registers_disk_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 1 << 20]).unwrap();
+        let path = path.to_str().unwrap();
+        
+        let engine = SafeEraseEngine::new().unwrap();
+        let info = engine.open_device(path).await.unwrap();
+        assert_eq!(info.size, 1 << 20);
+        engine.open_device(path).await.unwrap();
+        let devices = engine.get_device_status().await.unwrap();
+        assert_eq!(devices.iter().filter(|d| d.path == path).count(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_cancellation_reason_survives_verified_wipe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 64 << 20]).unwrap();
+        let path = path.to_str().unwrap();
+        let engine = SafeEraseEngine::new().unwrap();
+        engine.open_device(path).await.unwrap();
+        
+        let options = WipeOptions { verify_wipe: true, ..WipeOptions::default() };
+        let stop = Cancellation::new(CancelReason::EmergencyStop).with_requested_by("bob");
+        let (result, _) = tokio::join!(
+            engine.start_wipe(path, WipeAlgorithm::Gutmann, options, OperatorContext::local()),
+            async {
+                while engine.cancel_wipe(path, stop.clone()).await.is_err() {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+            },
+        );
+        
+        let result = result.unwrap();
+        assert_eq!(result.status, WipeStatus::Cancelled);
+        let cancellation = result.cancellation.unwrap();
+        assert_eq!(cancellation.reason, CancelReason::EmergencyStop);
+        assert_eq!(cancellation.requested_by.as_deref(), Some("bob"));
+        assert!(result.error_message.unwrap().contains("emergency_stop"));
+    }
+    
+    #[tokio::test]
+    async fn test_failed_verification_keeps_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 1 << 20]).unwrap();
+        let path = path.to_str().unwrap();
+        let mut engine = SafeEraseEngine::new().unwrap();
+        engine.open_device(path).await.unwrap();
+        // No sample set reaches a success rate above 100%
+        engine.verification_engine.set_thresholds(VerificationThresholds {
+            min_success_rate: 1.5,
+            ..VerificationThresholds::default()
+        });
+        
+        let options = WipeOptions { verify_wipe: true, ..WipeOptions::default() };
+        let result = engine.start_wipe(path, WipeAlgorithm::ZeroFill, options, OperatorContext::local()).await.unwrap();
+        assert_eq!(result.status, WipeStatus::Failed);
+        assert_eq!(result.verification_passed, Some(false));
+        assert!(result.error_message.unwrap().contains("success rate"));
+    }
 }
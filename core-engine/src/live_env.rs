@@ -0,0 +1,379 @@
+//! Wiping the drive a live environment booted from
+//!
+//! Stations often boot SafeErase from the very drive that has to be
+//! sanitized, e.g. a live image written to an NVMe drive so the machine needs
+//! no USB stick. That drive cannot be wiped while the running system still
+//! reads from it. Live systems can copy themselves into RAM at boot (`toram`,
+//! `rd.live.ram`), after which the boot medium is only kept mounted, or held
+//! by a loop device or swap, out of habit. [`LiveEnvironment`] works out from
+//! the mount table, swap list and loop devices whether the root filesystem
+//! still depends on any disk and what holds each one; [`LiveEnvironment::release`]
+//! then lets go of a disk the root does not depend on, so that it is no longer
+//! reported as mounted or as the system disk and can be wiped like any other.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::error::{SafeEraseError, Result};
+use crate::mounts::parent_disk;
+
+/// Something on a disk the running system still uses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Holder {
+    /// A filesystem on the disk, or on a loop device backed by a file on it
+    Mount { target: String, loop_device: Option<String> },
+    /// Swap on a partition of the disk or in a file on it
+    Swap { path: String },
+    /// Loop device backed by a file on the disk
+    Loop { device: String },
+}
+
+/// Whether the running system can do without its boot medium
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveEnvironment {
+    /// Filesystem type of `/`, e.g. `overlay` or `tmpfs`
+    pub root_fs_type: Option<String>,
+    /// Disks the root filesystem is read from
+    pub root_disks: Vec<String>,
+    /// What holds each disk, keyed by whole-disk path
+    pub holders: BTreeMap<String, Vec<Holder>>,
+}
+
+/// A parsed `/proc/mounts` line
+struct MountEntry<'a> {
+    source: &'a str,
+    target: String,
+    fs_type: &'a str,
+    options: &'a str,
+}
+
+impl LiveEnvironment {
+    /// Inspect the running system; empty where the platform offers no such view
+    pub fn detect() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            let read = |path: &str| std::fs::read_to_string(path).unwrap_or_default();
+            Self::from_parts(&read("/proc/self/mounts"), &read("/proc/swaps"), &loop_backing_files())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::default()
+        }
+    }
+    
+    /// Analyze a mount table, a `/proc/swaps` listing and the backing file of
+    /// each loop device
+    pub fn from_parts(mounts: &str, swaps: &str, loops: &[(String, String)]) -> Self {
+        let entries: Vec<MountEntry<'_>> = mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                Some(MountEntry {
+                    source: fields.next()?,
+                    target: fields.next()?.replace("\\040", " "),
+                    fs_type: fields.next()?,
+                    options: fields.next().unwrap_or(""),
+                })
+            })
+            .collect();
+        let resolver = Resolver { entries: &entries, loops };
+        
+        let mut environment = Self::default();
+        if let Some(root) = entries.iter().rev().find(|entry| entry.target == "/") {
+            environment.root_fs_type = Some(root.fs_type.to_string());
+            let mut sources = vec![resolver.disk_of_source(root.source, 0)];
+            if root.fs_type == "overlay" {
+                // Layers are directories on other mounts, e.g. a squashfs from the medium
+                for option in root.options.split(',') {
+                    let Some((key, dirs)) = option.split_once('=') else { continue };
+                    if matches!(key, "lowerdir" | "upperdir") {
+                        sources.extend(dirs.split(':').map(|dir| resolver.disk_of_path(dir, 0)));
+                    }
+                }
+            }
+            for (disk, _) in sources.into_iter().flatten() {
+                if !environment.root_disks.contains(&disk) {
+                    environment.root_disks.push(disk);
+                }
+            }
+        }
+        
+        for entry in &entries {
+            if let Some((disk, loop_device)) = resolver.disk_of_source(entry.source, 0) {
+                environment.add(disk, Holder::Mount { target: entry.target.clone(), loop_device });
+            }
+        }
+        for line in swaps.lines().skip(1) {
+            let Some(path) = line.split_whitespace().next() else { continue };
+            if let Some((disk, _)) = resolver.disk_of_source(path, 0).or_else(|| resolver.disk_of_path(path, 0)) {
+                environment.add(disk, Holder::Swap { path: path.to_string() });
+            }
+        }
+        for (device, backing_file) in loops {
+            if let Some((disk, _)) = resolver.disk_of_path(backing_file, 0) {
+                environment.add(disk, Holder::Loop { device: device.clone() });
+            }
+        }
+        environment
+    }
+    
+    /// Whether `/` lives entirely in memory
+    pub fn is_ram_resident(&self) -> bool {
+        self.root_fs_type.is_some() && self.root_disks.is_empty()
+    }
+    
+    /// What holds the disk
+    pub fn holders(&self, device_path: &str) -> &[Holder] {
+        self.holders.get(device_path).map(Vec::as_slice).unwrap_or(&[])
+    }
+    
+    /// Whether [`release`](Self::release) can free the disk for wiping
+    pub fn can_release(&self, device_path: &str) -> bool {
+        self.is_ram_resident() && !self.holders(device_path).is_empty()
+    }
+    
+    /// Swap off, unmount and detach everything on the disk, provided the
+    /// running system is in RAM; check with a fresh [`detect`](Self::detect)
+    /// that nothing is left
+    pub fn release(&self, device_path: &str) -> Result<()> {
+        if !self.is_ram_resident() {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "The running system still reads from {}; boot it with the live image's \
+                 copy-to-RAM option (toram, rd.live.ram) before wiping its boot drive",
+                self.root_disks.join(", ")
+            )));
+        }
+        
+        // Swap and loop-backed mounts first, then the loops, then the disk's own
+        // filesystems, deepest mount point first
+        let holders = self.holders(device_path);
+        let depth = |target: &str| target.matches('/').count();
+        let mut mounts: Vec<(&str, bool)> = holders
+            .iter()
+            .filter_map(|holder| match holder {
+                Holder::Mount { target, loop_device } => Some((target.as_str(), loop_device.is_some())),
+                _ => None,
+            })
+            .collect();
+        mounts.sort_by_key(|(target, through_loop)| (!through_loop, std::cmp::Reverse(depth(target))));
+        
+        for holder in holders {
+            if let Holder::Swap { path } = holder {
+                release::swap_off(path)?;
+            }
+        }
+        for (target, _) in mounts.iter().filter(|(_, through_loop)| *through_loop) {
+            release::unmount(target)?;
+        }
+        for holder in holders {
+            if let Holder::Loop { device } = holder {
+                release::detach_loop(device)?;
+            }
+        }
+        for (target, _) in mounts.iter().filter(|(_, through_loop)| !through_loop) {
+            release::unmount(target)?;
+        }
+        
+        let remaining = Self::detect();
+        if !remaining.holders(device_path).is_empty() {
+            return Err(SafeEraseError::DeviceBusy(format!(
+                "{} is still in use: {}",
+                device_path,
+                remaining.holders(device_path).iter().map(Holder::to_string).collect::<Vec<_>>().join(", ")
+            )));
+        }
+        info!("Released boot medium {}; the system runs from RAM", device_path);
+        Ok(())
+    }
+    
+    fn add(&mut self, disk: String, holder: Holder) {
+        let holders = self.holders.entry(disk).or_default();
+        if !holders.contains(&holder) {
+            holders.push(holder);
+        }
+    }
+}
+
+/// Follows mount sources and loop devices down to physical disks
+struct Resolver<'a> {
+    entries: &'a [MountEntry<'a>],
+    loops: &'a [(String, String)],
+}
+
+/// Loop devices stacked deeper than this are not followed
+const MAX_LOOP_DEPTH: usize = 4;
+
+impl Resolver<'_> {
+    /// Disk a mount source or swap device is on, with the loop device in between if any
+    fn disk_of_source(&self, source: &str, depth: usize) -> Option<(String, Option<String>)> {
+        let name = source.strip_prefix("/dev/")?;
+        if name.starts_with("loop") {
+            let (_, backing_file) = self.loops.iter().find(|(device, _)| device == source)?;
+            let (disk, _) = self.disk_of_path(backing_file, depth + 1)?;
+            return Some((disk, Some(source.to_string())));
+        }
+        if name.starts_with("zram") || name.starts_with("ram") {
+            return None;
+        }
+        Some((parent_disk(source), None))
+    }
+    
+    /// Disk holding a file or directory, through the mount that contains it
+    fn disk_of_path(&self, path: &str, depth: usize) -> Option<(String, Option<String>)> {
+        if depth > MAX_LOOP_DEPTH {
+            return None;
+        }
+        let mount = self
+            .entries
+            .iter()
+            .filter(|entry| Path::new(path).starts_with(&entry.target))
+            .max_by_key(|entry| entry.target.len())?; // the last of equal mounts is on top
+        self.disk_of_source(mount.source, depth)
+    }
+}
+
+impl std::fmt::Display for Holder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Holder::Mount { target, loop_device: Some(loop_device) } => {
+                write!(f, "mounted at {} through {}", target, loop_device)
+            }
+            Holder::Mount { target, loop_device: None } => write!(f, "mounted at {}", target),
+            Holder::Swap { path } => write!(f, "swap {}", path),
+            Holder::Loop { device } => write!(f, "backs {}", device),
+        }
+    }
+}
+
+/// Backing file of every attached loop device
+#[cfg(target_os = "linux")]
+fn loop_backing_files() -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir("/sys/block") else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with("loop") {
+                return None;
+            }
+            let backing_file = std::fs::read_to_string(entry.path().join("loop/backing_file")).ok()?;
+            Some((format!("/dev/{}", name), backing_file.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+mod release {
+    use std::ffi::CString;
+    use std::os::unix::io::AsRawFd;
+    
+    use crate::error::{SafeEraseError, Result};
+    
+    /// `LOOP_CLR_FD` from `linux/loop.h`
+    const LOOP_CLR_FD: libc::c_ulong = 0x4C01;
+    
+    fn c_path(path: &str) -> Result<CString> {
+        CString::new(path).map_err(|_| SafeEraseError::InvalidParameter(format!("Invalid path: {}", path)))
+    }
+    
+    fn failed(action: &str, path: &str) -> SafeEraseError {
+        SafeEraseError::SystemCommandFailed(format!("{} {}: {}", action, path, std::io::Error::last_os_error()))
+    }
+    
+    pub fn swap_off(path: &str) -> Result<()> {
+        let c_path = c_path(path)?;
+        if unsafe { libc::swapoff(c_path.as_ptr()) } != 0 {
+            return Err(failed("swapoff", path));
+        }
+        Ok(())
+    }
+    
+    pub fn unmount(target: &str) -> Result<()> {
+        let c_path = c_path(target)?;
+        if unsafe { libc::umount2(c_path.as_ptr(), 0) } != 0 {
+            return Err(failed("umount", target));
+        }
+        Ok(())
+    }
+    
+    pub fn detach_loop(device: &str) -> Result<()> {
+        let file = std::fs::File::open(device).map_err(|e| SafeEraseError::DeviceIoError(format!("{}: {}", device, e)))?;
+        if unsafe { libc::ioctl(file.as_raw_fd(), LOOP_CLR_FD as _) } != 0 {
+            return Err(failed("detach", device));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod release {
+    use crate::error::{SafeEraseError, Result};
+    
+    fn unsupported() -> SafeEraseError {
+        SafeEraseError::UnsupportedPlatform("releasing a boot medium is only supported on Linux".to_string())
+    }
+    
+    pub fn swap_off(_path: &str) -> Result<()> {
+        Err(unsupported())
+    }
+    
+    pub fn unmount(_target: &str) -> Result<()> {
+        Err(unsupported())
+    }
+    
+    pub fn detach_loop(_device: &str) -> Result<()> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SWAPS_HEADER: &str = "Filename\tType\tSize\tUsed\tPriority\n";
+    
+    #[test]
+    fn test_live_image_read_from_disk() {
+        // Debian live without toram: the squashfs under / is read from the medium
+        let mounts = "/dev/nvme0n1p1 /run/live/medium iso9660 ro 0 0\n\
+            /dev/loop0 /run/live/rootfs/filesystem.squashfs squashfs ro 0 0\n\
+            tmpfs /run/live/overlay tmpfs rw 0 0\n\
+            overlay / overlay rw,lowerdir=/run/live/rootfs/filesystem.squashfs/,upperdir=/run/live/overlay/rw,workdir=/run/live/overlay/work 0 0\n\
+            /dev/sda1 /mnt/usb vfat rw 0 0\n";
+        let loops = [("/dev/loop0".to_string(), "/run/live/medium/live/filesystem.squashfs".to_string())];
+        let environment = LiveEnvironment::from_parts(mounts, SWAPS_HEADER, &loops);
+        
+        assert_eq!(environment.root_disks, ["/dev/nvme0n1"]);
+        assert!(!environment.is_ram_resident());
+        assert!(!environment.can_release("/dev/nvme0n1"));
+        assert!(environment.holders("/dev/nvme0n1").contains(&Holder::Loop { device: "/dev/loop0".to_string() }));
+        assert_eq!(environment.holders("/dev/sda").len(), 1);
+        assert!(environment.release("/dev/nvme0n1").is_err());
+    }
+    
+    #[test]
+    fn test_live_image_copied_to_ram() {
+        // With toram the squashfs is read from tmpfs; the medium stays mounted
+        let mounts = "/dev/nvme0n1p1 /run/live/medium iso9660 ro 0 0\n\
+            tmpfs /run/live/toram tmpfs rw 0 0\n\
+            /dev/loop0 /run/live/rootfs/filesystem.squashfs squashfs ro 0 0\n\
+            overlay / overlay rw,lowerdir=/run/live/rootfs/filesystem.squashfs/,upperdir=/run/live/overlay/rw 0 0\n";
+        let loops = [("/dev/loop0".to_string(), "/run/live/toram/filesystem.squashfs".to_string())];
+        let swaps = format!("{}/dev/nvme0n1p3\tpartition\t1048572\t0\t-2\n", SWAPS_HEADER);
+        let environment = LiveEnvironment::from_parts(mounts, &swaps, &loops);
+        
+        assert!(environment.is_ram_resident());
+        assert!(environment.can_release("/dev/nvme0n1"));
+        assert_eq!(environment.holders("/dev/nvme0n1"), [
+            Holder::Mount { target: "/run/live/medium".to_string(), loop_device: None },
+            Holder::Swap { path: "/dev/nvme0n1p3".to_string() },
+        ]);
+        assert!(environment.holders("/dev/loop0").is_empty());
+        
+        let installed = LiveEnvironment::from_parts("/dev/sda2 / ext4 rw 0 0\n", SWAPS_HEADER, &[]);
+        assert_eq!(installed.root_disks, ["/dev/sda"]);
+    }
+}
@@ -0,0 +1,338 @@
+//! Log output for services
+//!
+//! Daemon deployments ship their logs to journald, Loki or Elasticsearch and
+//! need one JSON object per line with fields that can be indexed. [`init`]
+//! installs such a subscriber. Every wipe runs inside an `operation` span, so
+//! each line logged during it carries `span.operation_id` and
+//! `span.device_serial`; `status` and `bytes` are filled in when the
+//! operation ends. These field names are part of the public interface.
+//!
+//! When logs leave the organization, `redaction.logs` masks device serials
+//! and asset tags in every line, wherever they appear. Identifiers are
+//! registered with [`protect_identifier`] as the engine learns them, from
+//! discovered devices, asset manifests and operation spans, and the output is
+//! rewritten before it is written. Results and certificates are not affected.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, OnceLock, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::field::{self, Empty};
+use tracing::Span;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+use crate::error::{SafeEraseError, Result};
+use crate::redaction::{self, LogRedaction, Redactor};
+use crate::wipe::WipeStatus;
+
+/// Name of the span wrapping each wipe operation
+pub const OPERATION_SPAN: &str = "operation";
+
+/// Filter used when `RUST_LOG` is not set
+const DEFAULT_FILTER: &str = "info";
+
+/// Identifiers shorter than this are not masked, so short values do not
+/// mangle unrelated text
+const MIN_MASKED_LENGTH: usize = 4;
+
+/// Masker applied to the installed subscriber's output and the daemon's audit file
+static MASKER: OnceLock<Arc<IdentifierMasker>> = OnceLock::new();
+
+/// Output format of the installed subscriber
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Install the global subscriber, filtered by `RUST_LOG` (default `info`)
+pub fn init(format: LogFormat) -> Result<()> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let installed = match format {
+        LogFormat::Text => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(MaskingWriter::global(std::io::stdout))
+            .try_init(),
+        LogFormat::Json => json_subscriber(filter, MaskingWriter::global(std::io::stdout)).try_init(),
+    };
+    installed.map_err(|e| SafeEraseError::Internal(format!("Cannot install log subscriber: {}", e)))
+}
+
+/// Subscriber writing events flattened to the top level, with the fields of
+/// the innermost span under `span`
+fn json_subscriber<W>(
+    filter: EnvFilter,
+    writer: W,
+) -> tracing_subscriber::fmt::SubscriberBuilder<
+    tracing_subscriber::fmt::format::JsonFields,
+    tracing_subscriber::fmt::format::Format<tracing_subscriber::fmt::format::Json>,
+    EnvFilter,
+    W,
+>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+}
+
+/// Span for one wipe operation; see [`record_outcome`]
+pub fn operation_span(operation_id: Uuid, device_serial: &str) -> Span {
+    protect_identifier(device_serial);
+    tracing::info_span!(
+        OPERATION_SPAN,
+        operation_id = %operation_id,
+        device_serial = %device_serial,
+        status = Empty,
+        bytes = Empty,
+    )
+}
+
+/// Record how an operation ended on its span
+pub fn record_outcome(span: &Span, status: WipeStatus, bytes: u64) {
+    span.record("status", field::debug(status));
+    span.record("bytes", bytes);
+}
+
+/// Device serials and asset tags to mask, with their masked forms
+#[derive(Debug)]
+pub struct IdentifierMasker {
+    mode: LogRedaction,
+    redactor: Option<Redactor>,
+    masked: RwLock<HashMap<String, String>>,
+}
+
+impl IdentifierMasker {
+    /// Masker replacing identifiers with their last characters and a salted hash
+    pub fn hashing(redactor: Redactor) -> Self {
+        Self { mode: LogRedaction::Hash, redactor: Some(redactor), masked: RwLock::default() }
+    }
+    
+    /// Masker replacing identifiers with their last characters
+    pub fn truncating() -> Self {
+        Self { mode: LogRedaction::Truncate, redactor: None, masked: RwLock::default() }
+    }
+    
+    /// Mask `value` wherever it appears from now on
+    pub fn protect(&self, value: &str) {
+        let value = value.trim();
+        if value.chars().count() < MIN_MASKED_LENGTH {
+            return;
+        }
+        let masked = match (self.mode, &self.redactor) {
+            (LogRedaction::Hash, Some(redactor)) => redactor.redact(value),
+            _ => redaction::truncate(value),
+        };
+        let mut identifiers = self.masked.write().unwrap_or_else(|e| e.into_inner());
+        identifiers.entry(value.to_string()).or_insert(masked);
+    }
+    
+    /// Text with every protected identifier masked, longest first
+    pub fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let identifiers = self.masked.read().unwrap_or_else(|e| e.into_inner());
+        let mut found: Vec<(&String, &String)> = identifiers.iter().filter(|(value, _)| text.contains(value.as_str())).collect();
+        if found.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        found.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+        let mut masked = text.to_string();
+        for (value, replacement) in found {
+            masked = masked.replace(value.as_str(), replacement);
+        }
+        Cow::Owned(masked)
+    }
+}
+
+/// Mask identifiers in all log output from now on; only the first call takes effect
+pub fn mask_identifiers(masker: IdentifierMasker) {
+    if MASKER.set(Arc::new(masker)).is_err() {
+        tracing::warn!("Log redaction is already configured; keeping the first setting");
+    }
+}
+
+/// Register a device serial or asset tag to be masked in log output, if log redaction is on
+pub fn protect_identifier(value: &str) {
+    if let Some(masker) = MASKER.get() {
+        masker.protect(value);
+    }
+}
+
+/// Text with the protected identifiers masked, for sinks other than the subscriber
+pub fn mask_text(text: &str) -> Cow<'_, str> {
+    match MASKER.get() {
+        Some(masker) => masker.mask(text),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Writer masking identifiers in each formatted line
+#[derive(Debug)]
+pub struct MaskingWriter<W> {
+    inner: W,
+    /// The masker installed by [`mask_identifiers`] when unset
+    masker: Option<Arc<IdentifierMasker>>,
+}
+
+impl<W> MaskingWriter<W> {
+    /// Writer using the masker installed by [`mask_identifiers`], if any
+    pub fn global(inner: W) -> Self {
+        Self { inner, masker: None }
+    }
+    
+    pub fn new(inner: W, masker: Arc<IdentifierMasker>) -> Self {
+        Self { inner, masker: Some(masker) }
+    }
+    
+    fn masker(&self) -> Option<&IdentifierMasker> {
+        self.masker.as_deref().or_else(|| MASKER.get().map(Arc::as_ref))
+    }
+}
+
+impl<W: Write> Write for MaskingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(masker) = self.masker() else {
+            return self.inner.write(buf);
+        };
+        // The formatter writes each event in one call, so a line is never split
+        let text = String::from_utf8_lossy(buf);
+        match masker.mask(&text) {
+            Cow::Borrowed(_) => self.inner.write_all(buf)?,
+            Cow::Owned(masked) => self.inner.write_all(masked.as_bytes())?,
+        }
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, W: MakeWriter<'a>> MakeWriter<'a> for MaskingWriter<W> {
+    type Writer = MaskingWriter<W::Writer>;
+    
+    fn make_writer(&'a self) -> Self::Writer {
+        MaskingWriter { inner: self.inner.make_writer(), masker: self.masker.clone() }
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = SafeEraseError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(SafeEraseError::InvalidConfiguration(format!(
+                "Unknown log format '{}', expected text or json",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+        
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+    
+    #[test]
+    fn test_json_lines_carry_operation_fields() {
+        let buffer = Buffer::default();
+        let subscriber = json_subscriber(EnvFilter::new("info"), buffer.clone()).finish();
+        let operation_id = Uuid::new_v4();
+        
+        tracing::subscriber::with_default(subscriber, || {
+            let span = operation_span(operation_id, "WD-123");
+            span.in_scope(|| tracing::info!(pass = 1, "Pass started"));
+            record_outcome(&span, WipeStatus::Completed, 4096);
+            span.in_scope(|| tracing::info!("Operation finished"));
+        });
+        
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "Pass started");
+        assert_eq!(lines[0]["pass"], 1);
+        assert_eq!(lines[0]["span"]["name"], OPERATION_SPAN);
+        assert_eq!(lines[0]["span"]["operation_id"], operation_id.to_string());
+        assert_eq!(lines[0]["span"]["device_serial"], "WD-123");
+        assert_eq!(lines[1]["span"]["status"], "Completed");
+        assert_eq!(lines[1]["span"]["bytes"], 4096);
+    }
+    
+    #[test]
+    fn test_masking_writer() {
+        let masker = Arc::new(IdentifierMasker::truncating());
+        masker.protect("WD-WCC4N1234567");
+        masker.protect("WD-WCC4N12");
+        masker.protect("A1");
+        let buffer = Buffer::default();
+        let subscriber = json_subscriber(EnvFilter::new("info"), MaskingWriter::new(buffer.clone(), masker)).finish();
+        
+        tracing::subscriber::with_default(subscriber, || {
+            let span = operation_span(Uuid::new_v4(), "WD-WCC4N1234567");
+            span.in_scope(|| tracing::info!(slot = "A1", "Serial WD-WCC4N1234567 confirmed"));
+        });
+        
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["message"], "Serial ****4567 confirmed");
+        assert_eq!(line["span"]["device_serial"], "****4567");
+        // Too short to mask safely
+        assert_eq!(line["slot"], "A1");
+        
+        let hashing = IdentifierMasker::hashing(Redactor::new("salt"));
+        hashing.protect("AT-00042");
+        assert_eq!(hashing.mask("asset AT-00042"), format!("asset {}", Redactor::new("salt").redact("AT-00042")));
+        assert!(matches!(hashing.mask("nothing here"), Cow::Borrowed(_)));
+    }
+    
+    #[test]
+    fn test_log_format_parsing() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap().to_string(), "text");
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
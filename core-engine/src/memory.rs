@@ -0,0 +1,262 @@
+//! Memory budgets for verification data
+//!
+//! A Comprehensive verification records up to 10,000 sector analyses per
+//! operation. A bootable environment with little RAM wiping a dozen disks at
+//! once cannot hold all of them, so analyses are charged against a limit per
+//! operation and a budget shared by every operation of the engine. Once either
+//! is exhausted the remaining analyses go to an NDJSON spill file, or are only
+//! counted when no spill directory is configured. Entropy and pattern
+//! statistics are aggregated as samples are read and never need the analyses.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::export::{self, ExportFormat};
+use crate::verification::SectorAnalysis;
+
+/// Byte budget shared by all clones
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    limit: Option<usize>,
+    used: Arc<AtomicUsize>,
+}
+
+/// Memory settings of a verification engine
+#[derive(Debug, Clone)]
+pub struct MemoryLimits {
+    /// Budget shared by concurrent operations
+    pub global: MemoryBudget,
+    /// Bytes of sector analyses a single operation may keep in memory
+    pub per_operation: Option<usize>,
+    /// Directory receiving analyses beyond the limits
+    pub spill_dir: Option<PathBuf>,
+}
+
+/// Sector analyses of one operation, kept in memory while the budget allows
+#[derive(Debug)]
+pub struct SectorStore {
+    budget: MemoryBudget,
+    per_operation: Option<usize>,
+    reserved: usize,
+    analyses: Vec<SectorAnalysis>,
+    spill_path: Option<PathBuf>,
+    spill: Option<BufWriter<File>>,
+    overflowed: bool,
+    omitted: usize,
+}
+
+/// What a [`SectorStore`] holds once all samples are analysed
+#[derive(Debug)]
+pub struct StoredSectors {
+    /// Analyses that fitted in memory, in sample order
+    pub analyses: Vec<SectorAnalysis>,
+    /// NDJSON file with every analysis, when the store had to spill
+    pub spill_path: Option<PathBuf>,
+    /// Analyses left out of `analyses`
+    pub omitted: usize,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: usize) -> Self {
+        Self { limit: Some(limit), used: Arc::new(AtomicUsize::new(0)) }
+    }
+    
+    pub fn unlimited() -> Self {
+        Self { limit: None, used: Arc::new(AtomicUsize::new(0)) }
+    }
+    
+    /// Charge `bytes` to the budget; false when they do not fit
+    pub fn try_reserve(&self, bytes: usize) -> bool {
+        let Some(limit) = self.limit else {
+            self.used.fetch_add(bytes, Ordering::Relaxed);
+            return true;
+        };
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|&total| total <= limit)
+            })
+            .is_ok()
+    }
+    
+    pub fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+    
+    /// Bytes currently charged
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Acquire)
+    }
+    
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        Self {
+            global: MemoryBudget::unlimited(),
+            per_operation: None,
+            spill_dir: None,
+        }
+    }
+}
+
+impl MemoryLimits {
+    /// Store for the analyses of one verification
+    pub fn sector_store(&self, verification_id: Uuid) -> SectorStore {
+        SectorStore {
+            budget: self.global.clone(),
+            per_operation: self.per_operation,
+            reserved: 0,
+            analyses: Vec::new(),
+            spill_path: self.spill_dir.as_ref().map(|dir| dir.join(format!("{}-sectors.ndjson", verification_id))),
+            spill: None,
+            overflowed: false,
+            omitted: 0,
+        }
+    }
+}
+
+impl SectorStore {
+    pub fn push(&mut self, analysis: SectorAnalysis) {
+        if !self.overflowed {
+            let size = estimated_size(&analysis);
+            let fits_operation = self.per_operation.is_none_or(|limit| self.reserved + size <= limit);
+            if fits_operation && self.budget.try_reserve(size) {
+                self.reserved += size;
+                self.analyses.push(analysis);
+                return;
+            }
+            self.overflow();
+        }
+        
+        self.omitted += 1;
+        if let Some(writer) = self.spill.as_mut() {
+            if let Err(e) = export::write_sector_analyses(writer, ExportFormat::Ndjson, [&analysis]) {
+                warn!("Stopped spilling sector analyses: {}", e);
+                self.spill = None;
+                self.spill_path = None;
+            }
+        }
+    }
+    
+    /// Open the spill file with the analyses kept so far, so it is complete
+    fn overflow(&mut self) {
+        self.overflowed = true;
+        let Some(path) = self.spill_path.clone() else {
+            debug!("Memory budget exhausted after {} sector analyses; keeping statistics only", self.analyses.len());
+            return;
+        };
+        let opened = File::create(&path).map(BufWriter::new).and_then(|mut writer| {
+            export::write_sector_analyses(&mut writer, ExportFormat::Ndjson, &self.analyses)?;
+            Ok(writer)
+        });
+        match opened {
+            Ok(writer) => {
+                debug!("Memory budget exhausted; spilling sector analyses to {}", path.display());
+                self.spill = Some(writer);
+            }
+            Err(e) => {
+                warn!("Cannot spill sector analyses to {}: {}", path.display(), e);
+                self.spill_path = None;
+            }
+        }
+    }
+    
+    pub fn finish(mut self) -> StoredSectors {
+        let mut spill_path = self.spill_path.take().filter(|_| self.spill.is_some());
+        if let Some(mut writer) = self.spill.take() {
+            if let Err(e) = writer.flush() {
+                warn!("Cannot complete sector analysis spill file: {}", e);
+                spill_path = None;
+            }
+        }
+        StoredSectors {
+            analyses: std::mem::take(&mut self.analyses),
+            spill_path,
+            omitted: self.omitted,
+        }
+    }
+}
+
+impl Drop for SectorStore {
+    fn drop(&mut self) {
+        self.budget.release(self.reserved);
+    }
+}
+
+/// Approximate heap and inline bytes held by one analysis
+fn estimated_size(analysis: &SectorAnalysis) -> usize {
+    std::mem::size_of::<SectorAnalysis>()
+        + analysis.data_hash.capacity()
+        + analysis.anomalies.iter().map(|anomaly| std::mem::size_of::<String>() + anomaly.capacity()).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::PatternType;
+    
+    fn analysis(sector_offset: u64) -> SectorAnalysis {
+        SectorAnalysis {
+            sector_offset,
+            entropy: 0.0,
+            pattern_type: PatternType::AllZeros,
+            confidence: 1.0,
+            data_hash: "0".repeat(64),
+            anomalies: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn test_budget_is_shared_and_released() {
+        let budget = MemoryBudget::new(100);
+        let clone = budget.clone();
+        assert!(budget.try_reserve(60));
+        assert!(!clone.try_reserve(60));
+        clone.release(60);
+        assert!(clone.try_reserve(100));
+        assert_eq!(budget.used(), 100);
+        
+        let size = estimated_size(&analysis(0));
+        let limits = MemoryLimits { global: MemoryBudget::new(size * 2), ..MemoryLimits::default() };
+        let mut store = limits.sector_store(Uuid::new_v4());
+        for offset in 0..5 {
+            store.push(analysis(offset));
+        }
+        assert_eq!(limits.global.used(), size * 2);
+        let stored = store.finish();
+        assert_eq!((stored.analyses.len(), stored.omitted, stored.spill_path), (2, 3, None));
+        assert_eq!(limits.global.used(), 0);
+    }
+    
+    #[test]
+    fn test_spill_file_holds_every_analysis() {
+        let dir = tempfile::tempdir().unwrap();
+        let size = estimated_size(&analysis(0));
+        let limits = MemoryLimits {
+            per_operation: Some(size * 3),
+            spill_dir: Some(dir.path().to_path_buf()),
+            ..MemoryLimits::default()
+        };
+        let mut store = limits.sector_store(Uuid::new_v4());
+        for offset in 0..10 {
+            store.push(analysis(offset * 4096));
+        }
+        let stored = store.finish();
+        assert_eq!((stored.analyses.len(), stored.omitted), (3, 7));
+        
+        let spilled = std::fs::read_to_string(stored.spill_path.unwrap()).unwrap();
+        let offsets: Vec<u64> = spilled
+            .lines()
+            .map(|line| serde_json::from_str::<SectorAnalysis>(line).unwrap().sector_offset)
+            .collect();
+        assert_eq!(offsets, (0..10).map(|offset| offset * 4096).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,99 @@
+//! Mount state lookup for whole-disk devices
+
+use std::collections::HashMap;
+
+/// Mount points grouped by the whole-disk device they belong to
+#[derive(Debug, Clone, Default)]
+pub struct MountTable {
+    mounts: HashMap<String, Vec<String>>,
+}
+
+impl MountTable {
+    /// Read the current mount table (`/proc/self/mounts` on Linux)
+    pub fn load() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            match std::fs::read_to_string("/proc/self/mounts") {
+                Ok(contents) => Self::parse(&contents),
+                Err(_) => Self::default(),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::default()
+        }
+    }
+    
+    /// Parse a `/proc/mounts` style table
+    pub fn parse(contents: &str) -> Self {
+        let mut mounts: HashMap<String, Vec<String>> = HashMap::new();
+        
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(source), Some(target)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if !source.starts_with("/dev/") {
+                continue;
+            }
+            mounts
+                .entry(parent_disk(source))
+                .or_default()
+                .push(target.replace("\\040", " "));
+        }
+        
+        Self { mounts }
+    }
+    
+    /// Mount points of any partition on the given disk
+    pub fn mount_points(&self, device_path: &str) -> &[String] {
+        self.mounts.get(device_path).map(Vec::as_slice).unwrap_or(&[])
+    }
+    
+    /// Whether any partition of the disk is mounted
+    pub fn is_mounted(&self, device_path: &str) -> bool {
+        !self.mount_points(device_path).is_empty()
+    }
+}
+
+/// Map a partition path to its whole-disk path (`/dev/sda1` -> `/dev/sda`,
+/// `/dev/nvme0n1p2` -> `/dev/nvme0n1`, `/dev/mmcblk0p1` -> `/dev/mmcblk0`)
+pub(crate) fn parent_disk(partition: &str) -> String {
+    if let Some(index) = partition.rfind('p') {
+        let (disk, rest) = partition.split_at(index);
+        let digits = &rest[1..];
+        let disk_ends_in_digit = disk.chars().last().is_some_and(|c| c.is_ascii_digit());
+        if disk_ends_in_digit && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            return disk.to_string();
+        }
+    }
+    
+    if partition.starts_with("/dev/sd") || partition.starts_with("/dev/hd") || partition.starts_with("/dev/vd") {
+        return partition.trim_end_matches(|c: char| c.is_ascii_digit()).to_string();
+    }
+    
+    partition.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parent_disk() {
+        assert_eq!(parent_disk("/dev/sda1"), "/dev/sda");
+        assert_eq!(parent_disk("/dev/nvme0n1p2"), "/dev/nvme0n1");
+        assert_eq!(parent_disk("/dev/mmcblk0p1"), "/dev/mmcblk0");
+        assert_eq!(parent_disk("/dev/nvme0n1"), "/dev/nvme0n1");
+    }
+    
+    #[test]
+    fn test_parse_mounts() {
+        let table = MountTable::parse(
+            "/dev/sda2 / ext4 rw 0 0\nproc /proc proc rw 0 0\n/dev/sdb1 /media/usb\\040stick vfat rw 0 0\n",
+        );
+        assert!(table.is_mounted("/dev/sda"));
+        assert_eq!(table.mount_points("/dev/sdb"), ["/media/usb stick".to_string()]);
+        assert!(!table.is_mounted("/dev/sdc"));
+    }
+}
@@ -0,0 +1,129 @@
+//! TCG Opal self-encrypting drives
+//!
+//! A self-encrypting drive whose locking range was set up by its previous
+//! owner refuses reads and writes until it is unlocked, so no overwrite can
+//! reach it and ATA Secure Erase is refused as well. Such a drive can still
+//! be sanitized with a PSID revert: the Physical Secure ID printed on its
+//! label authorizes a factory reset that discards the media encryption key,
+//! leaving everything written before unreadable. The operator types the PSID
+//! in from the label; it is held in [`WipeOptions::psid`] only for the
+//! operation and is never serialized, logged or written to an audit trail.
+//! Only the fact that it was entered, and by whom, is recorded.
+//!
+//! [`WipeOptions::psid`]: crate::WipeOptions::psid
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SafeEraseError, Result};
+
+/// Number of characters in a PSID
+const PSID_LENGTH: usize = 32;
+
+/// Physical Secure ID of a drive, as printed on its label
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Psid(String);
+
+/// Opal state of a drive as reported by `sedutil-cli --query`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpalStatus {
+    /// The drive implements an Opal security subsystem
+    pub supported: bool,
+    /// An owner has enabled a locking range
+    pub locking_enabled: bool,
+    /// A locking range is currently locked; the drive cannot be overwritten
+    pub locked: bool,
+}
+
+impl Psid {
+    /// Check the PSID as typed by the operator; spaces and dashes some labels
+    /// print between groups are dropped and letters are upper-cased
+    pub fn new(psid: &str) -> Result<Self> {
+        let psid: String = psid
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if psid.len() != PSID_LENGTH || !psid.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "A PSID has {} letters and digits", PSID_LENGTH
+            )));
+        }
+        Ok(Self(psid))
+    }
+    
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Psid {
+    type Error = SafeEraseError;
+    
+    fn try_from(psid: String) -> Result<Self> {
+        Self::new(&psid)
+    }
+}
+
+impl std::fmt::Debug for Psid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Psid(..)")
+    }
+}
+
+impl OpalStatus {
+    /// Whether the drive has to be reverted with its PSID to be sanitized
+    pub fn needs_psid_revert(&self) -> bool {
+        self.supported && self.locked
+    }
+}
+
+/// Parse the Locking feature lines of `sedutil-cli --query`, e.g.
+/// `Locked = Y, LockingEnabled = Y, LockingSupported = Y, ...`
+pub(crate) fn parse_sedutil_query(output: &str) -> OpalStatus {
+    let flag = |name: &str| {
+        output.split([',', '\n']).any(|item| {
+            let mut parts = item.splitn(2, '=');
+            parts.next().map(str::trim) == Some(name) && parts.next().map(str::trim) == Some("Y")
+        })
+    };
+    OpalStatus {
+        supported: output.contains("Opal") && flag("LockingSupported"),
+        locking_enabled: flag("LockingEnabled"),
+        locked: flag("Locked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_psid_validation() {
+        let psid = Psid::new("abcd efgh-ijkl mnop qrst uvwx yz01 2345").unwrap();
+        assert_eq!(psid.expose(), "ABCDEFGHIJKLMNOPQRSTUVWXYZ012345");
+        assert_eq!(format!("{:?}", psid), "Psid(..)");
+        assert!(Psid::new("ABCD").is_err());
+        assert!(Psid::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ01234!").is_err());
+        
+        let parsed: Psid = serde_json::from_str("\"ABCDEFGHIJKLMNOPQRSTUVWXYZ012345\"").unwrap();
+        assert_eq!(parsed, psid);
+        assert!(serde_json::from_str::<Psid>("\"short\"").is_err());
+    }
+    
+    #[test]
+    fn test_parse_sedutil_query() {
+        let output = "/dev/sdb ATA Samsung SSD 860 EVO 500GB RVT04B6Q S3Z1NB0K123456\n\
+            TPer function (0x0001)\n\
+            Locking function (0x0002)\n    \
+            Locked = Y, LockingEnabled = Y, LockingSupported = Y, MBRDone = N, MBREnabled = N, MediaEncrypt = Y\n\
+            Opal 2.0 function (0x0203)\n";
+        let status = parse_sedutil_query(output);
+        assert_eq!(status, OpalStatus { supported: true, locking_enabled: true, locked: true });
+        assert!(status.needs_psid_revert());
+        
+        let unlocked = parse_sedutil_query(&output.replace("Locked = Y", "Locked = N"));
+        assert!(!unlocked.needs_psid_revert());
+        assert_eq!(parse_sedutil_query("/dev/sda No Opal support"), OpalStatus::default());
+    }
+}
@@ -0,0 +1,142 @@
+//! Who started a wipe
+//!
+//! Every frontend authenticates its users differently: the daemon by API key,
+//! the desktop app through the OS session or single sign-on, a remote agent
+//! through assignments signed by its controller. [`SafeEraseEngine::start_wipe`]
+//! takes the result as an [`OperatorContext`] and records it in the
+//! `operation_started` and `operation_finished` events and in the
+//! [`WipeResult`], from which certificates copy it, so the person behind an
+//! erasure is captured the same way whichever frontend was used.
+//!
+//! [`SafeEraseEngine::start_wipe`]: crate::SafeEraseEngine::start_wipe
+//! [`WipeResult`]: crate::WipeResult
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SafeEraseError, Result};
+
+/// How the operator proved their identity to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// Logged in to the operating system the frontend runs on
+    LocalSession,
+    /// Username and password checked by the frontend
+    Password,
+    /// OpenID Connect or SAML sign-on
+    SingleSignOn,
+    /// Smart card or other client certificate
+    SmartCard,
+    /// API key of a daemon client
+    ApiKey,
+    /// Job assignment signed by a central controller
+    SignedAssignment,
+}
+
+/// Authenticated identity behind a wipe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorContext {
+    /// User name, e-mail address or API client name
+    pub identity: String,
+    pub auth_method: AuthMethod,
+    /// Login session or request the wipe was started from, to correlate with
+    /// the frontend's own logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl AuthMethod {
+    /// Name used in events and configuration, e.g. `api_key`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthMethod::LocalSession => "local_session",
+            AuthMethod::Password => "password",
+            AuthMethod::SingleSignOn => "single_sign_on",
+            AuthMethod::SmartCard => "smart_card",
+            AuthMethod::ApiKey => "api_key",
+            AuthMethod::SignedAssignment => "signed_assignment",
+        }
+    }
+}
+
+impl std::str::FromStr for AuthMethod {
+    type Err = SafeEraseError;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        [
+            AuthMethod::LocalSession,
+            AuthMethod::Password,
+            AuthMethod::SingleSignOn,
+            AuthMethod::SmartCard,
+            AuthMethod::ApiKey,
+            AuthMethod::SignedAssignment,
+        ]
+        .into_iter()
+        .find(|method| method.as_str() == s)
+        .ok_or_else(|| SafeEraseError::InvalidParameter(format!("Unknown authentication method: {}", s)))
+    }
+}
+
+impl std::fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl OperatorContext {
+    pub fn new(identity: impl Into<String>, auth_method: AuthMethod) -> Self {
+        Self {
+            identity: identity.into(),
+            auth_method,
+            session_id: None,
+        }
+    }
+    
+    /// Set the session or request ID
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+    
+    /// The user logged in to this machine, for frontends without a login of their own
+    pub fn local() -> Self {
+        let identity = ["USER", "USERNAME", "LOGNAME"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let context = Self::new(identity, AuthMethod::LocalSession);
+        match std::env::var("XDG_SESSION_ID") {
+            Ok(session_id) if !session_id.is_empty() => context.with_session_id(session_id),
+            _ => context,
+        }
+    }
+}
+
+impl std::fmt::Display for OperatorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.identity, self.auth_method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_serialization() {
+        let operator = OperatorContext::new("alice@example.com", AuthMethod::SingleSignOn).with_session_id("s-42");
+        let json = serde_json::to_value(&operator).unwrap();
+        assert_eq!(json["auth_method"], "single_sign_on");
+        assert_eq!(json["session_id"], "s-42");
+        assert_eq!(operator.to_string(), "alice@example.com (single_sign_on)");
+        
+        let without_session = serde_json::to_value(OperatorContext::new("mdm", AuthMethod::ApiKey)).unwrap();
+        assert!(without_session.get("session_id").is_none());
+    }
+    
+    #[test]
+    fn test_parse_auth_method() {
+        assert_eq!("smart_card".parse::<AuthMethod>().unwrap(), AuthMethod::SmartCard);
+        assert!("magic_link".parse::<AuthMethod>().is_err());
+    }
+}
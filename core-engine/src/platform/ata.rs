@@ -0,0 +1,187 @@
+//! ATA commands for HPA and DCO handling
+//!
+//! Backends without a helper program issue these commands themselves through
+//! an ATA pass-through interface. This module builds the 48-bit task files
+//! and decodes what the device returns; the backend only moves the bytes.
+
+use super::HpaState;
+
+/// READ NATIVE MAX ADDRESS EXT
+pub(crate) const READ_NATIVE_MAX_ADDRESS_EXT: u8 = 0x27;
+/// SET MAX ADDRESS EXT
+pub(crate) const SET_MAX_ADDRESS_EXT: u8 = 0x37;
+/// DEVICE CONFIGURATION OVERLAY; the subcommand goes in the features register
+pub(crate) const DEVICE_CONFIGURATION: u8 = 0xB1;
+/// IDENTIFY DEVICE
+pub(crate) const IDENTIFY_DEVICE: u8 = 0xEC;
+
+/// DEVICE CONFIGURATION RESTORE: undo a DCO, exposing the factory capacity
+pub(crate) const DCO_RESTORE: u8 = 0xC0;
+/// DEVICE CONFIGURATION IDENTIFY: return the factory settings
+pub(crate) const DCO_IDENTIFY: u8 = 0xC2;
+
+/// Bytes returned by IDENTIFY DEVICE and DEVICE CONFIGURATION IDENTIFY
+pub(crate) const DATA_BLOCK_SIZE: usize = 512;
+
+/// LBA addressing bit of the device register
+const DEVICE_LBA: u8 = 0x40;
+
+/// Register indices within a task file
+const FEATURES: usize = 0;
+const SECTOR_COUNT: usize = 1;
+const LBA_LOW: usize = 2;
+const DEVICE: usize = 5;
+const COMMAND: usize = 6;
+
+/// A 48-bit task file as laid out by `ATA_PASS_THROUGH_EX` and SAT: features,
+/// sector count, LBA low, mid and high, device, command (status on return)
+/// and a reserved byte. `previous` carries the high-order bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct TaskFile {
+    pub previous: [u8; 8],
+    pub current: [u8; 8],
+}
+
+/// How much of the device a DCO hides, from DEVICE CONFIGURATION IDENTIFY
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DcoState {
+    /// Sectors the device had when it left the factory
+    pub factory_max_sectors: u64,
+}
+
+impl TaskFile {
+    fn command(command: u8) -> Self {
+        let mut task = Self::default();
+        task.current[DEVICE] = DEVICE_LBA;
+        task.current[COMMAND] = command;
+        task
+    }
+    
+    pub fn read_native_max_address() -> Self {
+        Self::command(READ_NATIVE_MAX_ADDRESS_EXT)
+    }
+    
+    /// Set the highest addressable sector count; a non-volatile setting survives power cycles.
+    /// The device only accepts this directly after READ NATIVE MAX ADDRESS EXT.
+    pub fn set_max_address(sectors: u64, volatile: bool) -> Self {
+        let mut task = Self::command(SET_MAX_ADDRESS_EXT);
+        task.set_lba(sectors.saturating_sub(1));
+        task.current[SECTOR_COUNT] = u8::from(!volatile);
+        task
+    }
+    
+    pub fn identify_device() -> Self {
+        Self::command(IDENTIFY_DEVICE)
+    }
+    
+    pub fn dco(subcommand: u8) -> Self {
+        let mut task = Self::command(DEVICE_CONFIGURATION);
+        task.current[FEATURES] = subcommand;
+        task
+    }
+    
+    fn set_lba(&mut self, lba: u64) {
+        let bytes = lba.to_le_bytes();
+        self.current[LBA_LOW..LBA_LOW + 3].copy_from_slice(&bytes[0..3]);
+        self.previous[LBA_LOW..LBA_LOW + 3].copy_from_slice(&bytes[3..6]);
+    }
+    
+    /// 48-bit LBA in the returned registers
+    pub fn lba(&self) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes[0..3].copy_from_slice(&self.current[LBA_LOW..LBA_LOW + 3]);
+        bytes[3..6].copy_from_slice(&self.previous[LBA_LOW..LBA_LOW + 3]);
+        u64::from_le_bytes(bytes)
+    }
+    
+    /// Whether the returned status register reports an error
+    pub fn failed(&self) -> bool {
+        self.current[COMMAND] & 0x01 != 0
+    }
+}
+
+/// Native capacity in sectors from the registers READ NATIVE MAX ADDRESS EXT returned
+pub(crate) fn native_max_sectors(returned: &TaskFile) -> u64 {
+    returned.lba() + 1
+}
+
+/// HPA state from IDENTIFY DEVICE data and the native capacity
+pub(crate) fn hpa_state(identify: &[u8; DATA_BLOCK_SIZE], native_max_sectors: u64) -> HpaState {
+    // Words 100-103: total number of user addressable sectors for 48-bit commands
+    HpaState {
+        current_max_sectors: quad_word(identify, 100),
+        native_max_sectors,
+    }
+}
+
+/// Parse DEVICE CONFIGURATION IDENTIFY data; `None` when the block is not valid
+pub(crate) fn parse_dco_identify(data: &[u8; DATA_BLOCK_SIZE]) -> Option<DcoState> {
+    // Word 255 holds the 0xA5 signature and a checksum making all bytes sum to zero
+    if data[510] == 0xA5 && data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) != 0 {
+        return None;
+    }
+    // Words 3-6: maximum LBA
+    let max_lba = quad_word(data, 3);
+    (max_lba > 0).then_some(DcoState { factory_max_sectors: max_lba + 1 })
+}
+
+/// Little-endian value of four consecutive words starting at `word`
+fn quad_word(data: &[u8; DATA_BLOCK_SIZE], word: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[word * 2..word * 2 + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_set_max_address_registers() {
+        let task = TaskFile::set_max_address(1_953_525_168, false);
+        assert_eq!(task.current[COMMAND], SET_MAX_ADDRESS_EXT);
+        assert_eq!(task.current[SECTOR_COUNT], 1);
+        assert_eq!(task.lba(), 1_953_525_167);
+        assert_eq!(task.current[LBA_LOW..LBA_LOW + 3], [0xAF, 0x6D, 0x70]);
+        assert_eq!(task.previous[LBA_LOW], 0x74);
+        assert_eq!(TaskFile::set_max_address(1000, true).current[SECTOR_COUNT], 0);
+        
+        let mut returned = TaskFile::read_native_max_address();
+        returned.current[COMMAND] = 0x50;
+        returned.set_lba(1_953_525_167);
+        assert!(!returned.failed());
+        assert_eq!(native_max_sectors(&returned), 1_953_525_168);
+    }
+    
+    #[test]
+    fn test_identify_and_dco_commands() {
+        let identify = TaskFile::identify_device();
+        assert_eq!(identify.current[COMMAND], IDENTIFY_DEVICE);
+        assert_eq!(identify.current[DEVICE], DEVICE_LBA);
+        
+        for subcommand in [DCO_IDENTIFY, DCO_RESTORE] {
+            let task = TaskFile::dco(subcommand);
+            assert_eq!(task.current[COMMAND], DEVICE_CONFIGURATION);
+            assert_eq!(task.current[FEATURES], subcommand);
+        }
+    }
+    
+    #[test]
+    fn test_identify_and_dco_parsing() {
+        let mut identify = [0u8; DATA_BLOCK_SIZE];
+        identify[200..208].copy_from_slice(&1_953_523_055u64.to_le_bytes());
+        let state = hpa_state(&identify, 1_953_525_168);
+        assert_eq!(state.hidden_sectors(), 2113);
+        
+        let mut dco = [0u8; DATA_BLOCK_SIZE];
+        dco[0] = 0x02;
+        dco[6..14].copy_from_slice(&1_953_525_167u64.to_le_bytes());
+        dco[510] = 0xA5;
+        let sum = dco.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        dco[511] = sum.wrapping_neg();
+        assert_eq!(parse_dco_identify(&dco).unwrap().factory_max_sectors, 1_953_525_168);
+        
+        dco[6] ^= 0xFF;
+        assert!(parse_dco_identify(&dco).is_none());
+    }
+}
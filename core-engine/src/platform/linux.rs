@@ -1,22 +1,55 @@
 //! Linux-specific implementation for device access and operations
 
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write, Seek, SeekFrom};
-use std::os::unix::fs::OpenOptionsExt;
-use std::path::Path;
+use std::os::unix::fs::{FileExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
 use tokio::fs;
-use tokio::process::Command;
-use tracing::{debug, warn, error};
+use tracing::{debug, warn};
 
-use crate::device::{DeviceType, StorageInterface, HealthStatus};
+use crate::device::{DeviceType, DriveBay, StorageInterface, HealthStatus, TrimReadBehavior, VirtualizationLayer};
 use crate::error::{SafeEraseError, Result};
-use super::{PlatformDeviceInfo, SmartInfo, PlatformDeviceCapabilities};
+use crate::live_env::LiveEnvironment;
+use crate::mounts::MountTable;
+use crate::algorithms::WipeAlgorithm;
+use crate::opal::{self, OpalStatus, Psid};
+use super::{HardwareEraseState, HpaState, PlatformDeviceInfo, SmartInfo, PlatformDeviceCapabilities};
+use super::tools::Tool;
 
 /// Linux-specific device handle
 #[derive(Debug)]
 pub struct LinuxDeviceHandle {
     file: File,
     device_path: String,
+    /// Logical sector size; O_DIRECT transfers must be whole sectors
+    sector_size: u32,
+    /// Kernel disk sequence number, unique to each attachment of a disk (Linux 5.15+)
+    disk_seq: Option<u64>,
+}
+
+/// Memory alignment of O_DIRECT buffers; covers every logical block size in use
+const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// Zeroed buffer whose contents start on a [`DIRECT_IO_ALIGNMENT`] boundary
+struct AlignedBuffer {
+    storage: Vec<u8>,
+    start: usize,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    fn new(len: usize) -> Self {
+        let storage = vec![0u8; len + DIRECT_IO_ALIGNMENT];
+        let start = storage.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+        Self { storage, start, len }
+    }
+    
+    fn as_slice(&self) -> &[u8] {
+        &self.storage[self.start..self.start + self.len]
+    }
+    
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.storage[self.start..self.start + self.len]
+    }
 }
 
 /// Check if the current process has root privileges
@@ -73,12 +106,15 @@ pub async fn open_device(device_path: &str) -> Result<LinuxDeviceHandle> {
         .map_err(|e| match e.kind() {
             std::io::ErrorKind::PermissionDenied => SafeEraseError::DeviceAccessDenied(device_path.to_string()),
             std::io::ErrorKind::NotFound => SafeEraseError::DeviceNotFound(device_path.to_string()),
+            _ if e.raw_os_error() == Some(libc::EBUSY) => SafeEraseError::DeviceBusy(device_path.to_string()),
             _ => SafeEraseError::DeviceIoError(e.to_string()),
         })?;
     
     Ok(LinuxDeviceHandle {
         file,
         device_path: device_path.to_string(),
+        sector_size: get_logical_sector_size(device_path).await?,
+        disk_seq: sysfs_block_attribute(device_path, "diskseq").await,
     })
 }
 
@@ -105,6 +141,12 @@ pub async fn get_device_info(handle: &LinuxDeviceHandle) -> Result<PlatformDevic
     // Check if it's a system disk (contains root filesystem)
     let is_system_disk = check_if_system_disk(&handle.device_path).await;
     
+    let virtualization = detect_virtualization(device_name, model.as_deref().unwrap_or_default()).await;
+    if let Some(layer) = virtualization {
+        warn!("{} is a {}; overwrites may not reach the physical media", handle.device_path, layer);
+    }
+    let bay = detect_bay(device_name).await;
+    
     Ok(PlatformDeviceInfo {
         name: device_name.to_string(),
         model: model.unwrap_or_else(|| "Unknown Model".to_string()),
@@ -117,13 +159,19 @@ pub async fn get_device_info(handle: &LinuxDeviceHandle) -> Result<PlatformDevic
         supports_secure_erase: device_type == DeviceType::SSD || device_type == DeviceType::NVMe,
         supports_hpa_dco: device_type == DeviceType::HDD || device_type == DeviceType::SSD,
         firmware_version: None, // TODO: Implement firmware version detection
+        virtualization,
+        bay,
     })
 }
 
 /// Get SMART information from device on Linux
 pub async fn get_smart_info(handle: &LinuxDeviceHandle) -> Result<SmartInfo> {
     // Use smartctl to get SMART information
-    let output = Command::new("smartctl")
+    let Ok(mut smartctl) = Tool::Smartctl.command() else {
+        debug!("smartctl is unavailable, no SMART data for {}", handle.device_path);
+        return Ok(SmartInfo::default());
+    };
+    let output = smartctl
         .args(["-A", &handle.device_path])
         .output()
         .await;
@@ -133,6 +181,10 @@ pub async fn get_smart_info(handle: &LinuxDeviceHandle) -> Result<SmartInfo> {
             let stdout = String::from_utf8_lossy(&output.stdout);
             parse_smart_output(&stdout)
         }
+        // Exit status bit 1: smartctl could not open the device
+        Ok(output) if output.status.code().is_some_and(|code| code & 0x02 != 0) => {
+            Err(SafeEraseError::DeviceBusy(handle.device_path.clone()))
+        }
         _ => {
             warn!("Failed to get SMART info for {}", handle.device_path);
             Ok(SmartInfo::default())
@@ -150,27 +202,35 @@ pub async fn query_device_capabilities(handle: &LinuxDeviceHandle) -> Result<Pla
     // Check for various capabilities
     let supports_trim = check_trim_support(&handle.device_path).await;
     let supports_ata_secure_erase = check_ata_secure_erase_support(&handle.device_path).await;
-    let supports_nvme_format = handle.device_path.contains("nvme");
+    let supports_nvme_format = handle.device_path.contains("nvme") && Tool::Nvme.is_available();
+    let security_frozen = check_security_frozen(&handle.device_path).await;
+    let write_protected = check_write_protected(&handle.device_path).await;
+    let trim_read_behavior = if supports_trim {
+        query_trim_read_behavior(&handle.device_path).await
+    } else {
+        TrimReadBehavior::Undefined
+    };
     
     Ok(PlatformDeviceCapabilities {
         supports_ata_secure_erase,
         supports_nvme_format,
         supports_trim,
         supports_write_same: true, // Most Linux systems support WRITE SAME
-        supports_hpa_detection: true,
+        supports_hpa_detection: Tool::Hdparm.is_available(),
         supports_dco_detection: true,
         max_lba,
         logical_sector_size,
         physical_sector_size,
+        security_frozen,
+        write_protected,
+        trim_read_behavior,
     })
 }
 
 /// Execute ATA Secure Erase command on Linux
 pub async fn ata_secure_erase(handle: &LinuxDeviceHandle, enhanced: bool) -> Result<()> {
-    let erase_type = if enhanced { "enhanced" } else { "normal" };
-    
     // First, set a user password (required for secure erase)
-    let set_password = Command::new("hdparm")
+    let set_password = Tool::Hdparm.command()?
         .args(["--user-master", "u", "--security-set-pass", "p", &handle.device_path])
         .output()
         .await
@@ -182,8 +242,14 @@ pub async fn ata_secure_erase(handle: &LinuxDeviceHandle, enhanced: bool) -> Res
         ));
     }
     
-    // Execute secure erase
-    let erase_cmd = Command::new("hdparm")
+    ata_resume_secure_erase(handle, enhanced).await
+}
+
+/// Execute SECURITY ERASE UNIT with the password `ata_secure_erase` sets;
+/// a drive locked by a power loss during the erase accepts it as well
+pub async fn ata_resume_secure_erase(handle: &LinuxDeviceHandle, enhanced: bool) -> Result<()> {
+    let erase_type = if enhanced { "enhanced" } else { "normal" };
+    let erase_cmd = Tool::Hdparm.command()?
         .args(["--user-master", "u", "--security-erase", erase_type, &handle.device_path])
         .output()
         .await
@@ -198,6 +264,51 @@ pub async fn ata_secure_erase(handle: &LinuxDeviceHandle, enhanced: bool) -> Res
     Ok(())
 }
 
+/// ATA: security stays enabled until SECURITY ERASE UNIT finishes, which
+/// disables it again. NVMe: the sanitize status log; Format leaves no record.
+pub async fn hardware_erase_state(handle: &LinuxDeviceHandle, algorithm: &WipeAlgorithm) -> Result<HardwareEraseState> {
+    match algorithm {
+        WipeAlgorithm::ATASecureErase => {
+            let output = Tool::Hdparm.command()?
+                .args(["-I", &handle.device_path])
+                .output()
+                .await
+                .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
+            if !output.status.success() {
+                return Ok(HardwareEraseState::Unknown);
+            }
+            Ok(if is_security_enabled(&String::from_utf8_lossy(&output.stdout)) {
+                HardwareEraseState::Interrupted
+            } else {
+                HardwareEraseState::Completed
+            })
+        }
+        WipeAlgorithm::NVMeFormat => {
+            let output = Tool::Nvme.command()?
+                .args(["sanitize-log", &handle.device_path, "-o", "json"])
+                .output()
+                .await
+                .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
+            if !output.status.success() {
+                return Ok(HardwareEraseState::Unknown);
+            }
+            Ok(match parse_sanitize_log(&String::from_utf8_lossy(&output.stdout)) {
+                Some(HardwareEraseState::InProgress { progress }) => HardwareEraseState::InProgress { progress },
+                _ => HardwareEraseState::Unknown,
+            })
+        }
+        WipeAlgorithm::PSIDRevert => {
+            // The revert is a single command; locking stays enabled until it has run
+            Ok(if opal_status(handle).await?.locking_enabled {
+                HardwareEraseState::Interrupted
+            } else {
+                HardwareEraseState::Completed
+            })
+        }
+        _ => Err(SafeEraseError::UnsupportedAlgorithm(algorithm.to_string())),
+    }
+}
+
 /// Execute NVMe Format command on Linux
 pub async fn nvme_format(handle: &LinuxDeviceHandle, secure_erase: bool) -> Result<()> {
     let mut args = vec!["format", &handle.device_path];
@@ -205,7 +316,7 @@ pub async fn nvme_format(handle: &LinuxDeviceHandle, secure_erase: bool) -> Resu
         args.extend_from_slice(&["--ses", "1"]);
     }
     
-    let output = Command::new("nvme")
+    let output = Tool::Nvme.command()?
         .args(&args)
         .output()
         .await
@@ -220,81 +331,357 @@ pub async fn nvme_format(handle: &LinuxDeviceHandle, secure_erase: bool) -> Resu
     Ok(())
 }
 
+/// Query the Opal locking state with `sedutil-cli --query`
+pub async fn opal_status(handle: &LinuxDeviceHandle) -> Result<OpalStatus> {
+    let output = Tool::Sedutil.command()?
+        .args(["--query", &handle.device_path])
+        .output()
+        .await
+        .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
+    
+    // sedutil-cli exits non-zero for drives without Opal support
+    Ok(opal::parse_sedutil_query(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Execute a PSID revert on Linux; the PSID is passed on the command line
+/// because sedutil-cli takes it nowhere else, and is not logged
+pub async fn psid_revert(handle: &LinuxDeviceHandle, psid: &Psid) -> Result<()> {
+    let output = Tool::Sedutil.command()?
+        .args(["--PSIDrevert", psid.expose(), &handle.device_path])
+        .output()
+        .await
+        .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
+    
+    if !output.status.success() {
+        return Err(SafeEraseError::WipeFailed(
+            "PSID revert failed; check the PSID against the drive label".to_string()
+        ));
+    }
+    
+    Ok(())
+}
+
 /// Write data to device sectors on Linux
+///
+/// The device is open with O_DIRECT | O_SYNC, so the data is copied into an
+/// aligned buffer and has to be a whole number of sectors; the write has
+/// reached the device when this returns.
 pub async fn write_sectors(
     handle: &LinuxDeviceHandle,
     start_lba: u64,
     data: &[u8],
 ) -> Result<usize> {
-    // This is a simplified implementation
-    // In a real implementation, you'd need to handle sector alignment and use proper I/O
-    Err(SafeEraseError::Internal("Direct sector writing not yet implemented".to_string()))
+    let sector_size = handle.sector_size as usize;
+    if !data.len().is_multiple_of(sector_size) {
+        return Err(SafeEraseError::InvalidParameter(format!(
+            "Write of {} bytes is not a multiple of the {}-byte sector size", data.len(), sector_size
+        )));
+    }
+    let offset = start_lba * sector_size as u64;
+    let mut buffer = AlignedBuffer::new(data.len());
+    buffer.as_mut_slice().copy_from_slice(data);
+    
+    let file = direct_io_file(handle)?;
+    let device_path = handle.device_path.clone();
+    blocking_io(move || {
+        let data = buffer.as_slice();
+        let mut written = 0;
+        while written < data.len() {
+            match file.write_at(&data[written..], offset + written as u64) {
+                Ok(0) => return Err(SafeEraseError::DeviceIoError(format!(
+                    "{} accepted no data at offset {}", device_path, offset + written as u64
+                ))),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(io_error(&device_path, "Write to", offset + written as u64, e)),
+            }
+        }
+        Ok(written)
+    }).await
 }
 
 /// Read data from device sectors on Linux
+///
+/// Reads whole sectors into an aligned buffer and copies out what the caller
+/// asked for; returns fewer bytes than requested only at the end of the device.
 pub async fn read_sectors(
     handle: &LinuxDeviceHandle,
     start_lba: u64,
     buffer: &mut [u8],
 ) -> Result<usize> {
-    // This is a simplified implementation
-    // In a real implementation, you'd need to handle sector alignment and use proper I/O
-    Err(SafeEraseError::Internal("Direct sector reading not yet implemented".to_string()))
+    let sector_size = handle.sector_size as usize;
+    let offset = start_lba * sector_size as u64;
+    let mut aligned = AlignedBuffer::new(buffer.len().div_ceil(sector_size) * sector_size);
+    
+    let file = direct_io_file(handle)?;
+    let device_path = handle.device_path.clone();
+    let (aligned, read) = blocking_io(move || {
+        let data = aligned.as_mut_slice();
+        let mut read = 0;
+        while read < data.len() {
+            match file.read_at(&mut data[read..], offset + read as u64) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(io_error(&device_path, "Read from", offset + read as u64, e)),
+            }
+        }
+        Ok((aligned, read))
+    }).await?;
+    
+    let read = read.min(buffer.len());
+    buffer[..read].copy_from_slice(&aligned.as_slice()[..read]);
+    Ok(read)
+}
+
+/// Write blocks through io_uring, keeping up to `queue_depth` writes in flight
+///
+/// One write at a time leaves a fast NVMe drive idle between requests; with
+/// several queued it stays busy. Each block is `(start_lba, data)`, and the
+/// result of each is returned in the same order so the caller can skip the
+/// ones that failed.
+#[cfg(feature = "io-uring")]
+pub async fn write_sectors_queued(
+    handle: &LinuxDeviceHandle,
+    blocks: &[(u64, &[u8])],
+    queue_depth: usize,
+) -> Result<Vec<Result<usize>>> {
+    let sector_size = handle.sector_size as usize;
+    let mut writes = Vec::with_capacity(blocks.len());
+    for (start_lba, data) in blocks {
+        if !data.len().is_multiple_of(sector_size) {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "Write of {} bytes is not a multiple of the {}-byte sector size", data.len(), sector_size
+            )));
+        }
+        let mut buffer = AlignedBuffer::new(data.len());
+        buffer.as_mut_slice().copy_from_slice(data);
+        writes.push((start_lba * sector_size as u64, buffer));
+    }
+    
+    let file = direct_io_file(handle)?;
+    let device_path = handle.device_path.clone();
+    blocking_io(move || uring_write(&file, &device_path, &writes, queue_depth)).await
+}
+
+#[cfg(feature = "io-uring")]
+fn uring_write(
+    file: &File,
+    device_path: &str,
+    writes: &[(u64, AlignedBuffer)],
+    queue_depth: usize,
+) -> Result<Vec<Result<usize>>> {
+    use io_uring::{opcode, types, IoUring};
+    use std::os::unix::io::AsRawFd;
+    
+    let depth = queue_depth.clamp(1, writes.len().max(1));
+    let mut ring = IoUring::new(depth as u32)
+        .map_err(|e| SafeEraseError::DeviceIoError(format!("Cannot set up io_uring for {}: {}", device_path, e)))?;
+    let mut results: Vec<Option<Result<usize>>> = (0..writes.len()).map(|_| None).collect();
+    let mut next = 0;
+    let mut in_flight = 0;
+    
+    while next < writes.len() || in_flight > 0 {
+        while next < writes.len() && in_flight < depth {
+            let (offset, buffer) = &writes[next];
+            let data = buffer.as_slice();
+            let entry = opcode::Write::new(types::Fd(file.as_raw_fd()), data.as_ptr(), data.len() as u32)
+                .offset(*offset)
+                .build()
+                .user_data(next as u64);
+            // SAFETY: the buffers in `writes` and the descriptor are borrowed
+            // for the whole call, so they outlive the ring dropped at its end
+            unsafe { ring.submission().push(&entry) }
+                .map_err(|_| SafeEraseError::Internal("io_uring submission queue is full".to_string()))?;
+            next += 1;
+            in_flight += 1;
+        }
+        
+        match ring.submit_and_wait(1) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(SafeEraseError::DeviceIoError(format!("io_uring submit for {} failed: {}", device_path, e))),
+        }
+        
+        for completion in ring.completion() {
+            let index = completion.user_data() as usize;
+            let (offset, buffer) = &writes[index];
+            let result = completion.result();
+            results[index] = Some(if result < 0 {
+                Err(io_error(device_path, "Write to", *offset, std::io::Error::from_raw_os_error(-result)))
+            } else if result as usize != buffer.len {
+                Err(SafeEraseError::DeviceIoError(format!(
+                    "{} accepted {} of {} bytes at offset {}", device_path, result, buffer.len, offset
+                )))
+            } else {
+                Ok(result as usize)
+            });
+            in_flight -= 1;
+        }
+    }
+    
+    Ok(results.into_iter().map(|result| result.expect("every write completes")).collect())
+}
+
+/// Error of a failed transfer; errors meaning the device is gone become `DeviceRemoved`
+fn io_error(device_path: &str, what: &str, offset: u64, e: std::io::Error) -> SafeEraseError {
+    match e.raw_os_error() {
+        Some(libc::ENODEV | libc::ENXIO | libc::ENOMEDIUM | libc::ESHUTDOWN) => {
+            SafeEraseError::DeviceRemoved(device_path.to_string())
+        }
+        _ => SafeEraseError::DeviceIoError(format!("{} {} at offset {} failed: {}", what, device_path, offset, e)),
+    }
+}
+
+/// Whether the drive the handle was opened on is still attached at its path
+///
+/// The old descriptor of an unplugged disk stays open, so the path is checked
+/// instead. A disk plugged in afterwards can take the same name and device
+/// number, but not the same disk sequence number.
+pub async fn device_present(handle: &LinuxDeviceHandle) -> bool {
+    let (Ok(opened), Ok(current)) = (handle.file.metadata(), fs::metadata(&handle.device_path).await) else {
+        return false;
+    };
+    // A disk image is present as long as the path still names the same file
+    if opened.is_file() {
+        return opened.dev() == current.dev() && opened.ino() == current.ino();
+    }
+    if opened.rdev() != current.rdev() {
+        return false;
+    }
+    match handle.disk_seq {
+        Some(disk_seq) => sysfs_block_attribute(&handle.device_path, "diskseq").await == Some(disk_seq),
+        None => sysfs_block_attribute(&handle.device_path, "size").await.is_some(),
+    }
+}
+
+/// Second descriptor on the open device, so the transfer can run on a blocking thread
+fn direct_io_file(handle: &LinuxDeviceHandle) -> Result<File> {
+    handle.file.try_clone().map_err(|e| SafeEraseError::DeviceIoError(e.to_string()))
+}
+
+/// Run synchronous device I/O off the async runtime's worker threads
+async fn blocking_io<T, F>(io: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(|e| SafeEraseError::Internal(format!("Device I/O task failed: {}", e)))?
+}
+
+/// Discard every block of the device with `blkdiscard`
+pub async fn discard_device(handle: &LinuxDeviceHandle) -> Result<()> {
+    let output = Tool::Blkdiscard.command()?
+        .arg(&handle.device_path)
+        .output()
+        .await
+        .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("busy") {
+            return Err(SafeEraseError::DeviceBusy(handle.device_path.clone()));
+        }
+        return Err(SafeEraseError::SystemCommandFailed(format!(
+            "blkdiscard failed on {}: {}",
+            handle.device_path,
+            stderr.trim()
+        )));
+    }
+    
+    Ok(())
 }
 
 /// Flush device write cache on Linux
 pub async fn flush_cache(handle: &LinuxDeviceHandle) -> Result<()> {
-    let output = Command::new("hdparm")
+    let Ok(mut hdparm) = Tool::Hdparm.command() else {
+        // Without hdparm, at least push the kernel's buffers to the device
+        return handle.file.sync_all().map_err(|e| SafeEraseError::DeviceIoError(e.to_string()));
+    };
+    let output = hdparm
         .args(["-f", &handle.device_path])
         .output()
         .await
         .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
     
     if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("busy") {
+            return Err(SafeEraseError::DeviceBusy(handle.device_path.clone()));
+        }
         warn!("Failed to flush cache for {}", handle.device_path);
     }
     
     Ok(())
 }
 
-/// Detect and clear HPA (Host Protected Area) on Linux
-pub async fn detect_and_clear_hpa(handle: &LinuxDeviceHandle) -> Result<bool> {
-    // Check for HPA using hdparm
-    let output = Command::new("hdparm")
+/// Read the HPA (Host Protected Area) state on Linux with `hdparm -N`
+pub async fn read_hpa(handle: &LinuxDeviceHandle) -> Result<HpaState> {
+    let output = Tool::Hdparm.command()?
         .args(["-N", &handle.device_path])
         .output()
         .await
         .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
     
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.contains("HPA") {
-            // Clear HPA
-            let clear_output = Command::new("hdparm")
-                .args(["-N", "p", &handle.device_path])
-                .output()
-                .await
-                .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
-            
-            return Ok(clear_output.status.success());
-        }
+    if !output.status.success() {
+        return Err(SafeEraseError::SystemCommandFailed(format!(
+            "hdparm -N {}: {}",
+            handle.device_path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
     }
+    parse_hdparm_max_sectors(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        SafeEraseError::SystemCommandFailed(format!("Unexpected hdparm -N output for {}", handle.device_path))
+    })
+}
+
+/// Set the max address on Linux; `hdparm -N p<n>` makes it survive power cycles
+pub async fn set_max_sectors(handle: &LinuxDeviceHandle, sectors: u64, volatile: bool) -> Result<()> {
+    let setting = if volatile { sectors.to_string() } else { format!("p{}", sectors) };
+    let output = Tool::Hdparm.command()?
+        .args(["-N", &setting, &handle.device_path])
+        .output()
+        .await
+        .map_err(|e| SafeEraseError::SystemCommandFailed(e.to_string()))?;
     
-    Ok(false)
+    if !output.status.success() {
+        return Err(SafeEraseError::SystemCommandFailed(format!(
+            "hdparm -N {} {}: {}",
+            setting,
+            handle.device_path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
 }
 
 /// Detect and clear DCO (Device Configuration Overlay) on Linux
-pub async fn detect_and_clear_dco(handle: &LinuxDeviceHandle) -> Result<bool> {
+pub async fn detect_and_clear_dco(_handle: &LinuxDeviceHandle) -> Result<Option<u64>> {
     // DCO detection and clearing is more complex and typically requires specialized tools
     // This is a placeholder implementation
     warn!("DCO detection/clearing not fully implemented for Linux");
-    Ok(false)
+    Ok(None)
 }
 
 // Helper functions
 
 async fn get_device_size(device_path: &str) -> Result<u64> {
-    let output = Command::new("blockdev")
+    // Disk images, such as VM disks, are wiped like the drives they stand in for
+    if let Ok(metadata) = fs::metadata(device_path).await {
+        if metadata.is_file() {
+            return Ok(metadata.len());
+        }
+    }
+    let Ok(mut blockdev) = Tool::Blockdev.command() else {
+        // sysfs counts 512-byte sectors whatever the logical block size
+        return sysfs_block_attribute(device_path, "size")
+            .await
+            .map(|sectors| sectors * 512)
+            .ok_or_else(|| SafeEraseError::SystemCommandFailed("Failed to get device size".to_string()));
+    };
+    let output = blockdev
         .args(["--getsize64", device_path])
         .output()
         .await
@@ -353,6 +740,8 @@ async fn determine_interface(device_name: &str, device_type: &DeviceType) -> Sto
         _ => {
             if device_name.starts_with("sd") {
                 StorageInterface::SATA
+            } else if device_name.starts_with("vd") || device_name.starts_with("xvd") {
+                StorageInterface::Virtual
             } else if device_name.starts_with("hd") {
                 StorageInterface::IDE
             } else {
@@ -362,6 +751,75 @@ async fn determine_interface(device_name: &str, device_type: &DeviceType) -> Sto
     }
 }
 
+/// Hypervisor disks by name and SCSI identity; otherwise SCSI LUNs reporting
+/// logical block provisioning (LBPME). libata sets LBPME for any SATA drive
+/// with TRIM, so `ATA` vendors are left out.
+async fn detect_virtualization(device_name: &str, model: &str) -> Option<VirtualizationLayer> {
+    let vendor = fs::read_to_string(format!("/sys/block/{}/device/vendor", device_name))
+        .await
+        .unwrap_or_default();
+    if let Some(layer) = VirtualizationLayer::from_identity(device_name, &vendor, model) {
+        return Some(layer);
+    }
+    if vendor.trim() == "ATA" {
+        return None;
+    }
+    
+    let mut scsi_disks = fs::read_dir(format!("/sys/block/{}/device/scsi_disk", device_name)).await.ok()?;
+    while let Ok(Some(entry)) = scsi_disks.next_entry().await {
+        let thin = fs::read_to_string(entry.path().join("thin_provisioning")).await.unwrap_or_default();
+        if thin.trim() == "1" {
+            return Some(VirtualizationLayer::ThinProvisioned);
+        }
+    }
+    None
+}
+
+/// Enclosure slot from the SES link the kernel adds to the SCSI device, and
+/// the ATA port the drive is attached to
+async fn detect_bay(device_name: &str) -> Option<DriveBay> {
+    let device_dir = PathBuf::from(format!("/sys/block/{}/device", device_name));
+    let mut bay = DriveBay::default();
+    
+    if let Ok(mut entries) = fs::read_dir(&device_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if !entry.file_name().to_string_lossy().starts_with("enclosure_device:") {
+                continue;
+            }
+            bay.slot = fs::read_to_string(entry.path().join("slot")).await.ok()
+                .and_then(|slot| slot.trim().parse().ok());
+            bay.enclosure = fs::canonicalize(entry.path()).await.ok()
+                .and_then(|component| Some(component.parent()?.file_name()?.to_string_lossy().into_owned()));
+            break;
+        }
+    }
+    
+    if let Ok(scsi_device) = fs::canonicalize(&device_dir).await {
+        if let Some((ata_dir, port, channel)) = ata_location(&scsi_device) {
+            bay.host_port = Some(port);
+            // libata puts each port multiplier port on its own link and
+            // addresses the drive behind it through the SCSI channel
+            if fs::metadata(ata_dir.join(format!("link{}.{}", port, channel))).await.is_ok() {
+                bay.multiplier_port = Some(channel);
+            }
+        }
+    }
+    
+    bay.is_known().then_some(bay)
+}
+
+/// `ataN` directory above a libata SCSI device such as
+/// `.../ata3/host2/target2:1:0/2:1:0:0`, its port number and the SCSI channel
+fn ata_location(scsi_device: &Path) -> Option<(&Path, u32, u32)> {
+    let (ata_dir, port) = scsi_device.ancestors().find_map(|dir| {
+        let port = dir.file_name()?.to_str()?.strip_prefix("ata")?.parse().ok()?;
+        Some((dir, port))
+    })?;
+    let address = scsi_device.file_name()?.to_str()?;
+    let channel = address.split(':').nth(1)?.parse().ok()?;
+    Some((ata_dir, port, channel))
+}
+
 async fn check_if_removable(device_name: &str) -> bool {
     let removable_path = format!("/sys/block/{}/removable", device_name);
     fs::read_to_string(&removable_path).await
@@ -370,8 +828,15 @@ async fn check_if_removable(device_name: &str) -> bool {
 }
 
 async fn check_if_system_disk(device_path: &str) -> bool {
+    // A live system's root is an overlay that may still read from this device
+    if LiveEnvironment::detect().root_disks.iter().any(|disk| disk == device_path) {
+        return true;
+    }
     // Check if any partition of this device contains the root filesystem
-    let output = Command::new("lsblk")
+    let Ok(mut lsblk) = Tool::Lsblk.command() else {
+        return MountTable::load().mount_points(device_path).iter().any(|mount_point| mount_point == "/");
+    };
+    let output = lsblk
         .args(["-n", "-o", "MOUNTPOINT", device_path])
         .output()
         .await;
@@ -385,7 +850,10 @@ async fn check_if_system_disk(device_path: &str) -> bool {
 }
 
 async fn get_logical_sector_size(device_path: &str) -> Result<u32> {
-    let output = Command::new("blockdev")
+    let Ok(mut blockdev) = Tool::Blockdev.command() else {
+        return Ok(sysfs_block_attribute(device_path, "queue/logical_block_size").await.map_or(512, |size| size as u32));
+    };
+    let output = blockdev
         .args(["--getss", device_path])
         .output()
         .await
@@ -401,7 +869,10 @@ async fn get_logical_sector_size(device_path: &str) -> Result<u32> {
 }
 
 async fn get_physical_sector_size(device_path: &str) -> Result<u32> {
-    let output = Command::new("blockdev")
+    let Ok(mut blockdev) = Tool::Blockdev.command() else {
+        return Ok(sysfs_block_attribute(device_path, "queue/physical_block_size").await.map_or(512, |size| size as u32));
+    };
+    let output = blockdev
         .args(["--getpbsz", device_path])
         .output()
         .await
@@ -417,7 +888,10 @@ async fn get_physical_sector_size(device_path: &str) -> Result<u32> {
 }
 
 async fn check_trim_support(device_path: &str) -> bool {
-    let output = Command::new("lsblk")
+    let Ok(mut lsblk) = Tool::Lsblk.command() else {
+        return sysfs_block_attribute(device_path, "queue/discard_granularity").await.unwrap_or(0) > 0;
+    };
+    let output = lsblk
         .args(["-D", "-o", "DISC-GRAN", device_path])
         .output()
         .await;
@@ -431,7 +905,10 @@ async fn check_trim_support(device_path: &str) -> bool {
 }
 
 async fn check_ata_secure_erase_support(device_path: &str) -> bool {
-    let output = Command::new("hdparm")
+    let Ok(mut hdparm) = Tool::Hdparm.command() else {
+        return false;
+    };
+    let output = hdparm
         .args(["-I", device_path])
         .output()
         .await;
@@ -444,14 +921,166 @@ async fn check_ata_secure_erase_support(device_path: &str) -> bool {
     false
 }
 
+async fn check_security_frozen(device_path: &str) -> bool {
+    let Ok(mut hdparm) = Tool::Hdparm.command() else {
+        return false;
+    };
+    let output = hdparm
+        .args(["-I", device_path])
+        .output()
+        .await;
+    
+    match output {
+        Ok(output) => is_security_frozen(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => false,
+    }
+}
+
+/// Parse the security section of `hdparm -I`, where the state is printed as
+/// a bare `frozen` line or as `not frozen`
+fn is_security_frozen(hdparm_output: &str) -> bool {
+    hdparm_output.lines().any(|line| line.trim() == "frozen")
+}
+
+/// Security enabled, printed as a bare `enabled` line or as `not enabled`
+fn is_security_enabled(hdparm_output: &str) -> bool {
+    hdparm_output.lines().any(|line| line.trim() == "enabled")
+}
+
+/// State from `nvme sanitize-log -o json`: the status in bits 2:0 of `sstat`
+/// and the progress of a running sanitize in `sprog`, out of 65536. Newer
+/// nvme-cli versions nest the log under the device name.
+fn parse_sanitize_log(json: &str) -> Option<HardwareEraseState> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let log = if value.get("sstat").is_some() {
+        &value
+    } else {
+        value.as_object()?.values().find(|log| log.get("sstat").is_some())?
+    };
+    let status = log.get("sstat")?.as_u64()? & 0x7;
+    Some(match status {
+        1 | 4 => HardwareEraseState::Completed,
+        2 => HardwareEraseState::InProgress {
+            progress: log.get("sprog").and_then(serde_json::Value::as_u64).map(|sprog| sprog as f64 / 65536.0),
+        },
+        3 => HardwareEraseState::Failed,
+        _ => HardwareEraseState::Unknown,
+    })
+}
+
+async fn query_trim_read_behavior(device_path: &str) -> TrimReadBehavior {
+    if device_path.contains("nvme") {
+        let Ok(mut nvme) = Tool::Nvme.command() else {
+            return TrimReadBehavior::Undefined;
+        };
+        let output = nvme
+            .args(["id-ns", device_path, "--output-format=json"])
+            .output()
+            .await;
+        return match output {
+            Ok(output) if output.status.success() => {
+                serde_json::from_slice::<serde_json::Value>(&output.stdout)
+                    .ok()
+                    .and_then(|ns| ns.get("dlfeat").and_then(|v| v.as_u64()))
+                    .map(nvme_trim_read_behavior)
+                    .unwrap_or_default()
+            }
+            _ => TrimReadBehavior::Undefined,
+        };
+    }
+    
+    let Ok(mut hdparm) = Tool::Hdparm.command() else {
+        return TrimReadBehavior::Undefined;
+    };
+    let output = hdparm
+        .args(["-I", device_path])
+        .output()
+        .await;
+    match output {
+        Ok(output) => ata_trim_read_behavior(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => TrimReadBehavior::Undefined,
+    }
+}
+
+/// Parse the RZAT/DRAT feature lines of `hdparm -I`
+fn ata_trim_read_behavior(hdparm_output: &str) -> TrimReadBehavior {
+    if hdparm_output.contains("Deterministic read ZEROs after TRIM") {
+        TrimReadBehavior::Zeroes
+    } else if hdparm_output.contains("Deterministic read data after TRIM") {
+        TrimReadBehavior::Deterministic
+    } else {
+        TrimReadBehavior::Undefined
+    }
+}
+
+/// Interpret the NVMe DLFEAT read behavior bits (2:0)
+fn nvme_trim_read_behavior(dlfeat: u64) -> TrimReadBehavior {
+    match dlfeat & 0x7 {
+        0b001 => TrimReadBehavior::Zeroes,
+        0b010 => TrimReadBehavior::Deterministic,
+        _ => TrimReadBehavior::Undefined,
+    }
+}
+
+/// Numeric attribute below `/sys/block/<device>/`
+async fn sysfs_block_attribute(device_path: &str, attribute: &str) -> Option<u64> {
+    let device_name = Path::new(device_path).file_name()?.to_str()?;
+    fs::read_to_string(format!("/sys/block/{}/{}", device_name, attribute))
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+async fn check_write_protected(device_path: &str) -> bool {
+    let device_name = Path::new(device_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown");
+    fs::read_to_string(format!("/sys/block/{}/ro", device_name)).await
+        .map(|content| content.trim() == "1")
+        .unwrap_or(false)
+}
+
 fn parse_smart_output(output: &str) -> Result<SmartInfo> {
     let mut smart_info = SmartInfo::default();
     
+    // ATA attributes carry their normalized value in the fourth column and their raw value in the tenth
+    fn raw_value<T: std::str::FromStr>(line: &str) -> Option<T> {
+        line.split_whitespace().nth(9)?.parse().ok()
+    }
+    let normalized = |line: &str| line.split_whitespace().nth(3).and_then(|value| value.parse::<u8>().ok());
+    // NVMe fields look like `Percentage Used:    3%` or `Data Units Written:  1,234 [632 MB]`
+    let nvme_value = |value: &str| -> Option<u64> {
+        value.split_whitespace().next()?.trim_end_matches('%').replace(',', "").parse().ok()
+    };
     for line in output.lines() {
         if line.contains("Temperature_Celsius") {
-            if let Some(temp_str) = line.split_whitespace().nth(9) {
-                smart_info.temperature = temp_str.parse().ok();
-            }
+            smart_info.temperature = raw_value(line);
+        } else if line.contains("Reallocated_Sector_Ct") {
+            smart_info.reallocated_sectors = raw_value(line);
+        } else if line.contains("Current_Pending_Sector") {
+            smart_info.pending_sectors = raw_value(line);
+        } else if let Some(count) = line.strip_prefix("Media and Data Integrity Errors:") {
+            smart_info.media_errors = count.trim().replace(',', "").parse().ok();
+        } else if line.contains("Media_Wearout_Indicator")
+            || line.contains("Wear_Leveling_Count")
+            || line.contains("Percent_Lifetime_Remain")
+        {
+            // The normalized value counts down from 100 as the drive wears
+            smart_info.percentage_used = normalized(line).map(|remaining| 100u8.saturating_sub(remaining));
+        } else if line.contains("Total_LBAs_Written") {
+            smart_info.host_bytes_written = raw_value(line).map(|lbas: u64| lbas * 512);
+        } else if line.contains("Available_Reservd_Space") {
+            smart_info.available_spare = normalized(line);
+        } else if let Some(value) = line.strip_prefix("Percentage Used:") {
+            smart_info.percentage_used = nvme_value(value).map(|used| used.min(255) as u8);
+        } else if let Some(value) = line.strip_prefix("Available Spare:") {
+            smart_info.available_spare = nvme_value(value).map(|spare| spare.min(100) as u8);
+        } else if let Some(value) = line.strip_prefix("Data Units Written:") {
+            // NVMe counts data units of 1000 512-byte blocks
+            smart_info.host_bytes_written = nvme_value(value).map(|units| units * 512_000);
         }
         // Add more SMART attribute parsing as needed
     }
@@ -459,3 +1088,115 @@ fn parse_smart_output(output: &str) -> Result<SmartInfo> {
     smart_info.health_status = HealthStatus::Good; // Simplified
     Ok(smart_info)
 }
+
+/// Parse ` max sectors   = 1953523055/1953525168, HPA is enabled`
+fn parse_hdparm_max_sectors(output: &str) -> Option<HpaState> {
+    let line = output.lines().find(|line| line.trim_start().starts_with("max sectors"))?;
+    let (_, values) = line.split_once('=')?;
+    let (current, native) = values.split(',').next()?.split_once('/')?;
+    Some(HpaState {
+        current_max_sectors: current.trim().parse().ok()?,
+        native_max_sectors: native.trim().parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_removal_errors() {
+        let gone = io_error("/dev/sdb", "Write to", 4096, std::io::Error::from_raw_os_error(libc::ENODEV));
+        assert!(matches!(gone, SafeEraseError::DeviceRemoved(ref path) if path == "/dev/sdb"));
+        let failed = io_error("/dev/sdb", "Write to", 4096, std::io::Error::from_raw_os_error(libc::EIO));
+        assert!(matches!(failed, SafeEraseError::DeviceIoError(ref detail) if detail.contains("offset 4096")));
+    }
+    
+    #[test]
+    fn test_ata_location() {
+        let behind_multiplier = Path::new("/sys/devices/pci0000:00/0000:00:1f.2/ata3/host2/target2:1:0/2:1:0:0");
+        let (ata_dir, port, channel) = ata_location(behind_multiplier).unwrap();
+        assert_eq!(ata_dir, Path::new("/sys/devices/pci0000:00/0000:00:1f.2/ata3"));
+        assert_eq!((port, channel), (3, 1));
+        assert!(ata_location(Path::new("/sys/devices/pci0000:00/0000:00:1d.0/usb2/2-1/host6/target6:0:0/6:0:0:0")).is_none());
+    }
+    
+    #[test]
+    fn test_aligned_buffer() {
+        for len in [512, 4096, 1 << 20] {
+            let mut buffer = AlignedBuffer::new(len);
+            assert_eq!(buffer.as_slice().as_ptr() as usize % DIRECT_IO_ALIGNMENT, 0);
+            assert_eq!(buffer.as_slice().len(), len);
+            buffer.as_mut_slice()[len - 1] = 0xFF;
+            assert_eq!(buffer.as_slice()[len - 1], 0xFF);
+        }
+    }
+    
+    #[test]
+    fn test_parse_hdparm_max_sectors() {
+        let output = "\n/dev/sdb:\n max sectors   = 1953523055/1953525168, HPA is enabled\n";
+        let state = parse_hdparm_max_sectors(output).unwrap();
+        assert_eq!(state.current_max_sectors, 1953523055);
+        assert_eq!(state.native_max_sectors, 1953525168);
+        
+        let disabled = " max sectors   = 976773168/976773168, ACCESSIBLE MAX ADDRESS disabled\n";
+        assert!(!parse_hdparm_max_sectors(disabled).unwrap().is_present());
+        assert!(parse_hdparm_max_sectors("/dev/sdb: bad/missing sense data").is_none());
+    }
+    
+    #[test]
+    fn test_hardware_erase_state_parsing() {
+        let security = "Security: \n\tMaster password revision code = 65534\n\t\tsupported\n\t\tenabled\n\t\tlocked\n\tnot\tfrozen\n";
+        assert!(is_security_enabled(security));
+        assert!(!is_security_enabled(&security.replace("\t\tenabled", "\tnot\tenabled")));
+        
+        assert_eq!(
+            parse_sanitize_log(r#"{"sprog": 32768, "sstat": 2}"#),
+            Some(HardwareEraseState::InProgress { progress: Some(0.5) })
+        );
+        assert_eq!(
+            parse_sanitize_log(r#"{"/dev/nvme0": {"sprog": 65535, "sstat": 257}}"#),
+            Some(HardwareEraseState::Completed)
+        );
+        assert_eq!(parse_sanitize_log(r#"{"sstat": 3}"#), Some(HardwareEraseState::Failed));
+        assert_eq!(parse_sanitize_log("not json"), None);
+    }
+    
+    #[test]
+    fn test_parse_smart_counters() {
+        let ata = "\
+  5 Reallocated_Sector_Ct   0x0033   100   100   010    Pre-fail  Always       -       8
+194 Temperature_Celsius     0x0022   036   045   000    Old_age   Always       -       36 (Min/Max 20/45)
+197 Current_Pending_Sector  0x0012   100   100   000    Old_age   Always       -       2
+";
+        let smart = parse_smart_output(ata).unwrap();
+        assert_eq!(smart.reallocated_sectors, Some(8));
+        assert_eq!(smart.pending_sectors, Some(2));
+        assert_eq!(smart.temperature, Some(36));
+        assert_eq!(smart.media_errors, None);
+        
+        let nvme = "Temperature:                        38 Celsius\nMedia and Data Integrity Errors:    1,024\n";
+        assert_eq!(parse_smart_output(nvme).unwrap().media_errors, Some(1024));
+    }
+    
+    #[test]
+    fn test_parse_wear_indicators() {
+        let nvme = "\
+Available Spare:                    98%
+Percentage Used:                    7%
+Data Units Written:                 12,345 [6.32 GB]
+";
+        let smart = parse_smart_output(nvme).unwrap();
+        assert_eq!(smart.available_spare, Some(98));
+        assert_eq!(smart.percentage_used, Some(7));
+        assert_eq!(smart.host_bytes_written, Some(12_345 * 512_000));
+        
+        let ata = "\
+177 Wear_Leveling_Count     0x0013   095   095   000    Pre-fail  Always       -       61
+241 Total_LBAs_Written      0x0032   099   099   000    Old_age   Always       -       2000
+";
+        let smart = parse_smart_output(ata).unwrap();
+        assert_eq!(smart.percentage_used, Some(5));
+        assert_eq!(smart.host_bytes_written, Some(2000 * 512));
+    }
+}
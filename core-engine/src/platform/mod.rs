@@ -1,35 +1,87 @@
 //! Platform-specific implementations for device access and operations
+//!
+//! Each supported operating system has a backend module; on any other target
+//! the `unsupported` backend is compiled instead, so the crate still builds
+//! and every device operation reports `UnsupportedPlatform`. Operations that
+//! depend on external programs probe for them through [`tools`].
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use serde::{Deserialize, Serialize};
-use crate::device::{DeviceType, StorageInterface, HealthStatus};
-use crate::error::Result;
+use tracing::warn;
+use crate::device::{DeviceType, StorageInterface, HealthStatus, TrimReadBehavior, VirtualizationLayer, DriveBay};
+use crate::algorithms::WipeAlgorithm;
+use crate::error::{Result, SafeEraseError};
+use crate::opal::{OpalStatus, Psid};
 
-#[cfg(target_os = "windows")]
+pub mod tools;
+#[cfg(any(all(feature = "device-access", target_os = "windows"), test))]
+mod ata;
+
+#[cfg(all(feature = "device-access", target_os = "windows"))]
+mod windows_ata;
+
+#[cfg(all(feature = "device-access", target_os = "windows"))]
 mod windows;
-#[cfg(target_os = "windows")]
+#[cfg(all(feature = "device-access", target_os = "windows"))]
 pub use windows::*;
+#[cfg(all(feature = "device-access", target_os = "windows"))]
+use windows as imp;
 
-#[cfg(target_os = "linux")]
+#[cfg(all(feature = "device-access", target_os = "linux"))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(all(feature = "device-access", target_os = "linux"))]
 pub use linux::*;
+#[cfg(all(feature = "device-access", target_os = "linux"))]
+use linux as imp;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "device-access", target_os = "macos"))]
 mod macos;
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "device-access", target_os = "macos"))]
 pub use macos::*;
+#[cfg(all(feature = "device-access", target_os = "macos"))]
+use macos as imp;
+
+#[cfg(not(all(feature = "device-access", any(target_os = "windows", target_os = "linux", target_os = "macos"))))]
+mod unsupported;
+#[cfg(not(all(feature = "device-access", any(target_os = "windows", target_os = "linux", target_os = "macos"))))]
+use unsupported as imp;
+
+/// Name of the device backend compiled for this target
+pub const BACKEND: &str = if !cfg!(feature = "device-access") {
+    "unsupported"
+} else if cfg!(target_os = "windows") {
+    "windows"
+} else if cfg!(target_os = "linux") {
+    "linux"
+} else if cfg!(target_os = "macos") {
+    "macos"
+} else {
+    "unsupported"
+};
+
+/// Whether this target has a device backend at all
+pub const SUPPORTED: bool = cfg!(all(feature = "device-access", any(target_os = "windows", target_os = "linux", target_os = "macos")));
+
+/// Whether queued writes through io_uring are compiled in
+pub const IO_URING: bool = cfg!(all(feature = "io-uring", target_os = "linux"));
+
+#[cfg(all(feature = "device-access", target_os = "windows"))]
+type PlatformDeviceHandle = windows::WindowsDeviceHandle;
+#[cfg(all(feature = "device-access", target_os = "linux"))]
+type PlatformDeviceHandle = linux::LinuxDeviceHandle;
+#[cfg(all(feature = "device-access", target_os = "macos"))]
+type PlatformDeviceHandle = macos::MacOSDeviceHandle;
+#[cfg(not(all(feature = "device-access", any(target_os = "windows", target_os = "linux", target_os = "macos"))))]
+type PlatformDeviceHandle = unsupported::UnsupportedDeviceHandle;
 
 /// Platform-agnostic device handle
 #[derive(Debug)]
 pub struct DeviceHandle {
-    #[cfg(target_os = "windows")]
-    pub(crate) handle: windows::WindowsDeviceHandle,
-    
-    #[cfg(target_os = "linux")]
-    pub(crate) handle: linux::LinuxDeviceHandle,
-    
-    #[cfg(target_os = "macos")]
-    pub(crate) handle: macos::MacOSDeviceHandle,
+    pub(crate) handle: PlatformDeviceHandle,
+    device_path: String,
+    /// Set once the device is found gone; the handle is never used for I/O
+    /// again, even if another drive appears at the same path
+    removed: AtomicBool,
 }
 
 /// Basic device information from platform APIs
@@ -46,6 +98,8 @@ pub struct PlatformDeviceInfo {
     pub supports_secure_erase: bool,
     pub supports_hpa_dco: bool,
     pub firmware_version: Option<String>,
+    pub virtualization: Option<VirtualizationLayer>,
+    pub bay: Option<DriveBay>,
 }
 
 /// SMART information from device
@@ -57,6 +111,14 @@ pub struct SmartInfo {
     pub power_cycle_count: Option<u64>,
     pub reallocated_sectors: Option<u64>,
     pub pending_sectors: Option<u64>,
+    /// NVMe media and data integrity errors
+    pub media_errors: Option<u64>,
+    /// Share of the rated life used, in percent (NVMe `Percentage Used`, ATA wearout attributes)
+    pub percentage_used: Option<u8>,
+    /// Bytes written by the host over the drive's lifetime
+    pub host_bytes_written: Option<u64>,
+    /// Remaining spare blocks, in percent
+    pub available_spare: Option<u8>,
 }
 
 /// Device capabilities for wiping operations
@@ -71,111 +133,180 @@ pub struct PlatformDeviceCapabilities {
     pub max_lba: u64,
     pub logical_sector_size: u32,
     pub physical_sector_size: u32,
+    pub security_frozen: bool,
+    pub write_protected: bool,
+    pub trim_read_behavior: TrimReadBehavior,
+}
+
+/// Accessible and native capacity of an ATA device, in sectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HpaState {
+    /// Sectors the host can currently address (`SET MAX ADDRESS`)
+    pub current_max_sectors: u64,
+    /// Sectors the device reports natively (`READ NATIVE MAX ADDRESS`)
+    pub native_max_sectors: u64,
+}
+
+impl HpaState {
+    /// Whether a Host Protected Area hides part of the device
+    pub fn is_present(&self) -> bool {
+        self.current_max_sectors < self.native_max_sectors
+    }
+    
+    pub fn hidden_sectors(&self) -> u64 {
+        self.native_max_sectors.saturating_sub(self.current_max_sectors)
+    }
+}
+
+/// What a drive reports about a hardware erase started earlier
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum HardwareEraseState {
+    /// Still running in the drive; `progress` is 0.0 to 1.0 when reported
+    InProgress { progress: Option<f64> },
+    /// Finished successfully
+    Completed,
+    /// The drive reports that the erase failed
+    Failed,
+    /// Stopped before finishing, e.g. by a power loss, and can be issued again
+    Interrupted,
+    /// The drive keeps no record that tells
+    Unknown,
 }
 
 /// Check if the current process has administrative privileges
 pub fn has_admin_privileges() -> bool {
-    #[cfg(target_os = "windows")]
-    return windows::has_admin_privileges();
-    
-    #[cfg(target_os = "linux")]
-    return linux::has_admin_privileges();
-    
-    #[cfg(target_os = "macos")]
-    return macos::has_admin_privileges();
+    imp::has_admin_privileges()
 }
 
 /// Enumerate all storage devices on the system
 pub async fn enumerate_storage_devices() -> Result<Vec<String>> {
-    #[cfg(target_os = "windows")]
-    return windows::enumerate_storage_devices().await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::enumerate_storage_devices().await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::enumerate_storage_devices().await;
+    imp::enumerate_storage_devices().await
 }
 
 /// Open a device for low-level access
 pub async fn open_device(device_path: &str) -> Result<DeviceHandle> {
-    #[cfg(target_os = "windows")]
-    {
-        let handle = windows::open_device(device_path).await?;
-        Ok(DeviceHandle { handle })
+    let handle = imp::open_device(device_path).await?;
+    Ok(DeviceHandle {
+        handle,
+        device_path: device_path.to_string(),
+        removed: AtomicBool::new(false),
+    })
+}
+
+impl DeviceHandle {
+    /// Whether the device has been removed since it was opened
+    pub fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Acquire)
     }
     
-    #[cfg(target_os = "linux")]
-    {
-        let handle = linux::open_device(device_path).await?;
-        Ok(DeviceHandle { handle })
+    /// Retire the handle after its device disappeared
+    pub(crate) fn mark_removed(&self) {
+        if !self.removed.swap(true, Ordering::AcqRel) {
+            warn!("{} was removed; its handle will not be used again", self.device_path);
+        }
     }
     
-    #[cfg(target_os = "macos")]
-    {
-        let handle = macos::open_device(device_path).await?;
-        Ok(DeviceHandle { handle })
+    fn ensure_present(&self) -> Result<()> {
+        if self.is_removed() {
+            return Err(SafeEraseError::DeviceRemoved(self.device_path.clone()));
+        }
+        Ok(())
+    }
+    
+    /// Retire the handle when an operation found the device gone
+    fn note_removal<T>(&self, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            if matches!(e.root(), SafeEraseError::DeviceRemoved(_)) {
+                self.mark_removed();
+            }
+        }
+        result
+    }
+}
+
+/// Whether the drive the handle was opened on is still attached at its path;
+/// retires the handle when it is not
+pub async fn device_present(handle: &DeviceHandle) -> bool {
+    if handle.is_removed() {
+        return false;
+    }
+    let present = imp::device_present(&handle.handle).await;
+    if !present {
+        handle.mark_removed();
     }
+    present
 }
 
 /// Get basic device information
 pub async fn get_device_info(handle: &DeviceHandle) -> Result<PlatformDeviceInfo> {
-    #[cfg(target_os = "windows")]
-    return windows::get_device_info(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::get_device_info(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::get_device_info(&handle.handle).await;
+    imp::get_device_info(&handle.handle).await
 }
 
 /// Get SMART information from device
 pub async fn get_smart_info(handle: &DeviceHandle) -> Result<SmartInfo> {
-    #[cfg(target_os = "windows")]
-    return windows::get_smart_info(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::get_smart_info(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::get_smart_info(&handle.handle).await;
+    imp::get_smart_info(&handle.handle).await
 }
 
 /// Query device capabilities for wiping operations
 pub async fn query_device_capabilities(handle: &DeviceHandle) -> Result<PlatformDeviceCapabilities> {
-    #[cfg(target_os = "windows")]
-    return windows::query_device_capabilities(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::query_device_capabilities(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::query_device_capabilities(&handle.handle).await;
+    let mut capabilities = imp::query_device_capabilities(&handle.handle).await?;
+    // Features compiled out are reported as unsupported rather than failing later
+    capabilities.supports_ata_secure_erase &= cfg!(feature = "secure-erase");
+    capabilities.supports_nvme_format &= cfg!(feature = "secure-erase");
+    capabilities.supports_hpa_detection &= cfg!(feature = "hpa-dco");
+    capabilities.supports_dco_detection &= cfg!(feature = "hpa-dco");
+    Ok(capabilities)
 }
 
 /// Execute ATA Secure Erase command
 pub async fn ata_secure_erase(handle: &DeviceHandle, enhanced: bool) -> Result<()> {
-    #[cfg(target_os = "windows")]
-    return windows::ata_secure_erase(&handle.handle, enhanced).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::ata_secure_erase(&handle.handle, enhanced).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::ata_secure_erase(&handle.handle, enhanced).await;
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("ATA Secure Erase", "secure-erase"));
+    }
+    imp::ata_secure_erase(&handle.handle, enhanced).await
+}
+
+/// Issue SECURITY ERASE UNIT again on a drive whose earlier erase was
+/// interrupted; the password set for that erase is still in place
+pub async fn ata_resume_secure_erase(handle: &DeviceHandle, enhanced: bool) -> Result<()> {
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("ATA Secure Erase", "secure-erase"));
+    }
+    imp::ata_resume_secure_erase(&handle.handle, enhanced).await
+}
+
+/// State of the last hardware erase as far as the drive reports it
+pub async fn hardware_erase_state(handle: &DeviceHandle, algorithm: &WipeAlgorithm) -> Result<HardwareEraseState> {
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("Hardware erase status", "secure-erase"));
+    }
+    imp::hardware_erase_state(&handle.handle, algorithm).await
 }
 
 /// Execute NVMe Format command
 pub async fn nvme_format(handle: &DeviceHandle, secure_erase: bool) -> Result<()> {
-    #[cfg(target_os = "windows")]
-    return windows::nvme_format(&handle.handle, secure_erase).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::nvme_format(&handle.handle, secure_erase).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::nvme_format(&handle.handle, secure_erase).await;
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("NVMe Format", "secure-erase"));
+    }
+    imp::nvme_format(&handle.handle, secure_erase).await
+}
+
+/// Opal locking state of a self-encrypting drive
+pub async fn opal_status(handle: &DeviceHandle) -> Result<OpalStatus> {
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("Opal status", "secure-erase"));
+    }
+    imp::opal_status(&handle.handle).await
+}
+
+/// Revert an Opal drive to its factory state with the PSID from its label,
+/// discarding the media encryption key
+pub async fn psid_revert(handle: &DeviceHandle, psid: &Psid) -> Result<()> {
+    if !cfg!(feature = "secure-erase") {
+        return Err(compiled_out("PSID revert", "secure-erase"));
+    }
+    imp::psid_revert(&handle.handle, psid).await
 }
 
 /// Write data to device sectors
@@ -184,14 +315,35 @@ pub async fn write_sectors(
     start_lba: u64,
     data: &[u8],
 ) -> Result<usize> {
-    #[cfg(target_os = "windows")]
-    return windows::write_sectors(&handle.handle, start_lba, data).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::write_sectors(&handle.handle, start_lba, data).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::write_sectors(&handle.handle, start_lba, data).await;
+    handle.ensure_present()?;
+    handle.note_removal(imp::write_sectors(&handle.handle, start_lba, data).await)
+}
+
+/// Write several blocks of `(start_lba, data)` with up to `queue_depth` in
+/// flight at once, returning the result of each block in order
+pub async fn write_sectors_queued(
+    handle: &DeviceHandle,
+    blocks: &[(u64, &[u8])],
+    queue_depth: usize,
+) -> Result<Vec<Result<usize>>> {
+    handle.ensure_present()?;
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    {
+        let results = handle.note_removal(imp::write_sectors_queued(&handle.handle, blocks, queue_depth).await)?;
+        if results.iter().any(|result| matches!(result, Err(SafeEraseError::DeviceRemoved(_)))) {
+            handle.mark_removed();
+        }
+        Ok(results)
+    }
+    #[cfg(not(all(feature = "io-uring", target_os = "linux")))]
+    {
+        let _ = (blocks, queue_depth);
+        if cfg!(target_os = "linux") {
+            Err(compiled_out("Queued writes", "io-uring"))
+        } else {
+            Err(SafeEraseError::UnsupportedPlatform("Queued writes need io_uring, which only Linux has".to_string()))
+        }
+    }
 }
 
 /// Read data from device sectors
@@ -200,50 +352,71 @@ pub async fn read_sectors(
     start_lba: u64,
     buffer: &mut [u8],
 ) -> Result<usize> {
-    #[cfg(target_os = "windows")]
-    return windows::read_sectors(&handle.handle, start_lba, buffer).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::read_sectors(&handle.handle, start_lba, buffer).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::read_sectors(&handle.handle, start_lba, buffer).await;
+    handle.ensure_present()?;
+    handle.note_removal(imp::read_sectors(&handle.handle, start_lba, buffer).await)
+}
+
+/// Discard (TRIM/deallocate) every block of the device
+pub async fn discard_device(handle: &DeviceHandle) -> Result<()> {
+    imp::discard_device(&handle.handle).await
 }
 
 /// Flush device write cache
 pub async fn flush_cache(handle: &DeviceHandle) -> Result<()> {
-    #[cfg(target_os = "windows")]
-    return windows::flush_cache(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::flush_cache(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::flush_cache(&handle.handle).await;
+    handle.ensure_present()?;
+    handle.note_removal(imp::flush_cache(&handle.handle).await)
 }
 
-/// Detect and clear HPA (Host Protected Area)
-pub async fn detect_and_clear_hpa(handle: &DeviceHandle) -> Result<bool> {
-    #[cfg(target_os = "windows")]
-    return windows::detect_and_clear_hpa(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::detect_and_clear_hpa(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::detect_and_clear_hpa(&handle.handle).await;
+/// Read the current and native max address to detect an HPA (Host Protected Area)
+pub async fn read_hpa(handle: &DeviceHandle) -> Result<HpaState> {
+    if !cfg!(feature = "hpa-dco") {
+        return Err(compiled_out("HPA detection", "hpa-dco"));
+    }
+    // Windows has no hdparm; the commands go to the drive through ATA pass-through
+    #[cfg(all(feature = "device-access", target_os = "windows"))]
+    {
+        windows_ata::read_hpa(handle.handle.raw_handle())
+    }
+    #[cfg(not(all(feature = "device-access", target_os = "windows")))]
+    {
+        imp::read_hpa(&handle.handle).await
+    }
 }
 
-/// Detect and clear DCO (Device Configuration Overlay)
-pub async fn detect_and_clear_dco(handle: &DeviceHandle) -> Result<bool> {
-    #[cfg(target_os = "windows")]
-    return windows::detect_and_clear_dco(&handle.handle).await;
-    
-    #[cfg(target_os = "linux")]
-    return linux::detect_and_clear_dco(&handle.handle).await;
-    
-    #[cfg(target_os = "macos")]
-    return macos::detect_and_clear_dco(&handle.handle).await;
+/// Set the max address, clearing an HPA when it is the native capacity;
+/// a volatile setting is lost at the next power cycle
+pub async fn set_max_sectors(handle: &DeviceHandle, sectors: u64, volatile: bool) -> Result<()> {
+    if !cfg!(feature = "hpa-dco") {
+        return Err(compiled_out("HPA removal", "hpa-dco"));
+    }
+    #[cfg(all(feature = "device-access", target_os = "windows"))]
+    {
+        windows_ata::set_max_sectors(handle.handle.raw_handle(), sectors, volatile)
+    }
+    #[cfg(not(all(feature = "device-access", target_os = "windows")))]
+    {
+        imp::set_max_sectors(&handle.handle, sectors, volatile).await
+    }
+}
+
+/// Detect and clear DCO (Device Configuration Overlay); returns the sectors
+/// it hid when one was found and removed
+pub async fn detect_and_clear_dco(handle: &DeviceHandle) -> Result<Option<u64>> {
+    if !cfg!(feature = "hpa-dco") {
+        return Err(compiled_out("DCO removal", "hpa-dco"));
+    }
+    #[cfg(all(feature = "device-access", target_os = "windows"))]
+    {
+        windows_ata::detect_and_clear_dco(handle.handle.raw_handle())
+    }
+    #[cfg(not(all(feature = "device-access", target_os = "windows")))]
+    {
+        imp::detect_and_clear_dco(&handle.handle).await
+    }
+}
+
+fn compiled_out(operation: &str, feature: &str) -> SafeEraseError {
+    SafeEraseError::UnsupportedPlatform(format!("{} requires a build with the {} feature", operation, feature))
 }
 
 #[cfg(test)]
@@ -257,4 +430,15 @@ mod tests {
         // Just ensure it doesn't panic
         println!("Has admin privileges: {}", has_privs);
     }
+    
+    #[test]
+    fn test_hpa_state() {
+        let hidden = HpaState { current_max_sectors: 1_953_523_055, native_max_sectors: 1_953_525_168 };
+        assert!(hidden.is_present());
+        assert_eq!(hidden.hidden_sectors(), 2113);
+        
+        let full = HpaState { current_max_sectors: 1_000, native_max_sectors: 1_000 };
+        assert!(!full.is_present());
+        assert_eq!(full.hidden_sectors(), 0);
+    }
 }
@@ -0,0 +1,136 @@
+//! External helper programs
+//!
+//! Several device operations on Linux go through command-line tools such as
+//! `hdparm` and `nvme`. ARM wipe appliances and minimal musl images often
+//! ship without them, so every use is probed first: a missing tool turns the
+//! operation into an `UnsupportedPlatform` error and the matching capability
+//! into `false`, never a failed spawn deep inside a wipe. Builds without the
+//! `external-tools` feature treat every tool as missing.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::error::{SafeEraseError, Result};
+
+/// Helper programs the platform backends can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    Hdparm,
+    Nvme,
+    Smartctl,
+    Blkdiscard,
+    Blockdev,
+    Lsblk,
+    /// Drive Trust Alliance `sedutil-cli`, for TCG Opal drives
+    Sedutil,
+    /// Holds a logind lock against suspend while wipes run
+    SystemdInhibit,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 8] = [
+        Tool::Hdparm,
+        Tool::Nvme,
+        Tool::Smartctl,
+        Tool::Blkdiscard,
+        Tool::Blockdev,
+        Tool::Lsblk,
+        Tool::Sedutil,
+        Tool::SystemdInhibit,
+    ];
+    
+    /// Executable name looked up on `PATH`
+    pub fn program(&self) -> &'static str {
+        match self {
+            Tool::Hdparm => "hdparm",
+            Tool::Nvme => "nvme",
+            Tool::Smartctl => "smartctl",
+            Tool::Blkdiscard => "blkdiscard",
+            Tool::Blockdev => "blockdev",
+            Tool::Lsblk => "lsblk",
+            Tool::Sedutil => "sedutil-cli",
+            Tool::SystemdInhibit => "systemd-inhibit",
+        }
+    }
+    
+    /// Location of the tool, if this build may use it and it is installed
+    pub fn find(&self) -> Option<PathBuf> {
+        if !cfg!(feature = "external-tools") {
+            return None;
+        }
+        std::env::var_os("PATH").and_then(|path| find_in(&path, self.program()))
+    }
+    
+    pub fn is_available(&self) -> bool {
+        self.find().is_some()
+    }
+    
+    /// Command running the tool, or why it cannot be run
+    pub(crate) fn command(&self) -> Result<Command> {
+        if !cfg!(feature = "external-tools") {
+            return Err(SafeEraseError::UnsupportedPlatform(format!(
+                "{} is not used by builds without the external-tools feature", self.program()
+            )));
+        }
+        self.find()
+            .map(Command::new)
+            .ok_or_else(|| SafeEraseError::UnsupportedPlatform(format!("{} is not installed", self.program())))
+    }
+}
+
+/// First executable file named `program` in a `PATH`-style list of directories
+fn find_in(path: &std::ffi::OsStr, program: &str) -> Option<PathBuf> {
+    std::env::split_paths(path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+impl std::fmt::Display for Tool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.program())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_find_in_skips_non_executables() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let (first, second) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        std::fs::write(first.path().join("hdparm"), "not executable").unwrap();
+        let tool = second.path().join("hdparm");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(find_in(&path, "hdparm"), Some(tool));
+        assert_eq!(find_in(&path, "nvme"), None);
+    }
+    
+    #[test]
+    fn test_missing_tool_is_unsupported() {
+        let path = std::env::join_paths([tempfile::tempdir().unwrap().path()]).unwrap();
+        assert!(find_in(&path, Tool::Smartctl.program()).is_none());
+        assert_eq!(serde_json::to_string(&Tool::Blkdiscard).unwrap(), "\"blkdiscard\"");
+        if !cfg!(feature = "external-tools") {
+            assert!(matches!(Tool::Hdparm.command(), Err(SafeEraseError::UnsupportedPlatform(_))));
+        }
+    }
+}
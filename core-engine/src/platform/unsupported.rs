@@ -0,0 +1,101 @@
+//! Fallback for targets without a device backend
+//!
+//! Keeps the engine building everywhere: no devices are enumerated and any
+//! attempt to open one reports `UnsupportedPlatform`.
+
+use crate::error::{SafeEraseError, Result};
+use crate::algorithms::WipeAlgorithm;
+use crate::opal::{OpalStatus, Psid};
+use super::{HardwareEraseState, HpaState, PlatformDeviceInfo, SmartInfo, PlatformDeviceCapabilities};
+
+/// Never constructed; devices cannot be opened on this target
+#[derive(Debug)]
+pub enum UnsupportedDeviceHandle {}
+
+fn unsupported() -> SafeEraseError {
+    if cfg!(feature = "device-access") {
+        SafeEraseError::UnsupportedPlatform(format!("no device backend for {}", std::env::consts::OS))
+    } else {
+        SafeEraseError::UnsupportedPlatform("built without device access (device-access feature)".to_string())
+    }
+}
+
+pub fn has_admin_privileges() -> bool {
+    false
+}
+
+pub async fn enumerate_storage_devices() -> Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+pub async fn open_device(_device_path: &str) -> Result<UnsupportedDeviceHandle> {
+    Err(unsupported())
+}
+
+pub async fn get_device_info(handle: &UnsupportedDeviceHandle) -> Result<PlatformDeviceInfo> {
+    match *handle {}
+}
+
+pub async fn get_smart_info(handle: &UnsupportedDeviceHandle) -> Result<SmartInfo> {
+    match *handle {}
+}
+
+pub async fn query_device_capabilities(handle: &UnsupportedDeviceHandle) -> Result<PlatformDeviceCapabilities> {
+    match *handle {}
+}
+
+pub async fn ata_secure_erase(handle: &UnsupportedDeviceHandle, _enhanced: bool) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn ata_resume_secure_erase(handle: &UnsupportedDeviceHandle, _enhanced: bool) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn hardware_erase_state(handle: &UnsupportedDeviceHandle, _algorithm: &WipeAlgorithm) -> Result<HardwareEraseState> {
+    match *handle {}
+}
+
+pub async fn nvme_format(handle: &UnsupportedDeviceHandle, _secure_erase: bool) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn opal_status(handle: &UnsupportedDeviceHandle) -> Result<OpalStatus> {
+    match *handle {}
+}
+
+pub async fn psid_revert(handle: &UnsupportedDeviceHandle, _psid: &Psid) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn write_sectors(handle: &UnsupportedDeviceHandle, _start_lba: u64, _data: &[u8]) -> Result<usize> {
+    match *handle {}
+}
+
+pub async fn read_sectors(handle: &UnsupportedDeviceHandle, _start_lba: u64, _buffer: &mut [u8]) -> Result<usize> {
+    match *handle {}
+}
+
+pub async fn device_present(handle: &UnsupportedDeviceHandle) -> bool {
+    match *handle {}
+}
+
+pub async fn discard_device(handle: &UnsupportedDeviceHandle) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn flush_cache(handle: &UnsupportedDeviceHandle) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn read_hpa(handle: &UnsupportedDeviceHandle) -> Result<HpaState> {
+    match *handle {}
+}
+
+pub async fn set_max_sectors(handle: &UnsupportedDeviceHandle, _sectors: u64, _volatile: bool) -> Result<()> {
+    match *handle {}
+}
+
+pub async fn detect_and_clear_dco(handle: &UnsupportedDeviceHandle) -> Result<Option<u64>> {
+    match *handle {}
+}
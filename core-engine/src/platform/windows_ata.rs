@@ -0,0 +1,111 @@
+//! HPA and DCO handling on Windows through `IOCTL_ATA_PASS_THROUGH`
+//!
+//! Windows has no `hdparm`, so the Windows backend sends the ATA commands
+//! built by [`super::ata`] to the open physical drive itself. Controllers
+//! and USB bridges that do not pass ATA commands through fail the IOCTL;
+//! that is reported as `UnsupportedPlatform`, never as "no HPA".
+
+use std::mem::{offset_of, size_of};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::um::ioapiset::DeviceIoControl;
+use winapi::um::ntddscsi::{
+    ATA_FLAGS_48BIT_COMMAND, ATA_FLAGS_DATA_IN, ATA_FLAGS_DRDY_REQUIRED, ATA_PASS_THROUGH_EX,
+    IOCTL_ATA_PASS_THROUGH,
+};
+use winapi::um::winnt::HANDLE;
+
+use super::ata::{self, TaskFile, DATA_BLOCK_SIZE, DCO_IDENTIFY, DCO_RESTORE};
+use super::HpaState;
+use crate::error::{SafeEraseError, Result};
+
+/// Seconds the drive may take for a command
+const TIMEOUT_SECONDS: u32 = 30;
+
+/// Pass-through header followed by the data block, as the IOCTL expects
+#[repr(C)]
+struct PassThrough {
+    header: ATA_PASS_THROUGH_EX,
+    data: [u8; DATA_BLOCK_SIZE],
+}
+
+/// Send one command; `data` receives a 512-byte block for data-in commands
+fn pass_through(handle: HANDLE, task: TaskFile, data: Option<&mut [u8; DATA_BLOCK_SIZE]>) -> Result<TaskFile> {
+    // SAFETY: ATA_PASS_THROUGH_EX is plain data, all-zero is a valid value
+    let mut request: PassThrough = unsafe { std::mem::zeroed() };
+    request.header.Length = size_of::<ATA_PASS_THROUGH_EX>() as u16;
+    request.header.AtaFlags = ATA_FLAGS_48BIT_COMMAND | ATA_FLAGS_DRDY_REQUIRED;
+    request.header.TimeOutValue = TIMEOUT_SECONDS;
+    request.header.PreviousTaskFile = task.previous;
+    request.header.CurrentTaskFile = task.current;
+    if data.is_some() {
+        request.header.AtaFlags |= ATA_FLAGS_DATA_IN;
+        request.header.DataTransferLength = DATA_BLOCK_SIZE as u32;
+        request.header.DataBufferOffset = offset_of!(PassThrough, data);
+    }
+    
+    let size = size_of::<PassThrough>() as DWORD;
+    let mut returned: DWORD = 0;
+    let request_ptr = &mut request as *mut PassThrough as *mut _;
+    // SAFETY: the request buffer is valid for `size` bytes in both directions
+    // and outlives the synchronous call
+    let ok = unsafe {
+        DeviceIoControl(handle, IOCTL_ATA_PASS_THROUGH, request_ptr, size, request_ptr, size, &mut returned, std::ptr::null_mut())
+    };
+    if ok == 0 {
+        return Err(SafeEraseError::UnsupportedPlatform(format!(
+            "ATA pass-through is not available for this drive: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    
+    let result = TaskFile {
+        previous: request.header.PreviousTaskFile,
+        current: request.header.CurrentTaskFile,
+    };
+    if result.failed() {
+        return Err(SafeEraseError::DeviceIoError(format!(
+            "ATA command {:#04x} aborted by the drive (error {:#04x})",
+            task.current[6], result.current[0]
+        )));
+    }
+    if let Some(data) = data {
+        data.copy_from_slice(&request.data);
+    }
+    Ok(result)
+}
+
+fn read_native_max_sectors(handle: HANDLE) -> Result<u64> {
+    pass_through(handle, TaskFile::read_native_max_address(), None).map(|returned| ata::native_max_sectors(&returned))
+}
+
+/// Accessible capacity from IDENTIFY DEVICE and native capacity from READ NATIVE MAX ADDRESS EXT
+pub(crate) fn read_hpa(handle: HANDLE) -> Result<HpaState> {
+    let mut identify = [0u8; DATA_BLOCK_SIZE];
+    pass_through(handle, TaskFile::identify_device(), Some(&mut identify))?;
+    let native = read_native_max_sectors(handle)?;
+    Ok(ata::hpa_state(&identify, native))
+}
+
+/// SET MAX ADDRESS EXT, preceded by the READ NATIVE MAX ADDRESS EXT the drive requires
+pub(crate) fn set_max_sectors(handle: HANDLE, sectors: u64, volatile: bool) -> Result<()> {
+    read_native_max_sectors(handle)?;
+    pass_through(handle, TaskFile::set_max_address(sectors, volatile), None)?;
+    Ok(())
+}
+
+/// Detect a DCO by comparing the factory capacity with the native one, and
+/// restore the factory configuration when it hides sectors; returns how many
+pub(crate) fn detect_and_clear_dco(handle: HANDLE) -> Result<Option<u64>> {
+    let mut identify = [0u8; DATA_BLOCK_SIZE];
+    pass_through(handle, TaskFile::dco(DCO_IDENTIFY), Some(&mut identify))?;
+    let Some(dco) = ata::parse_dco_identify(&identify) else {
+        return Err(SafeEraseError::DeviceIoError("Invalid DEVICE CONFIGURATION IDENTIFY data".to_string()));
+    };
+    let native = read_native_max_sectors(handle)?;
+    if dco.factory_max_sectors <= native {
+        return Ok(None);
+    }
+    pass_through(handle, TaskFile::dco(DCO_RESTORE), None)?;
+    Ok(Some(dco.factory_max_sectors - native))
+}
@@ -0,0 +1,246 @@
+//! Organizational wipe policy enforcement
+//!
+//! A policy file is a JSON document `{"payload": "<policy JSON>", "signature": "<base64>"}`
+//! where the signature is an RSA/ECDSA SHA-256 signature over the payload bytes,
+//! made with the administrator's private key. Policies are only accepted when the
+//! signature verifies against a trusted public key.
+
+use std::path::Path;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "signed-policy")]
+use openssl::{
+    base64,
+    hash::MessageDigest,
+    pkey::{PKey, Private, Public},
+    sign::{Signer, Verifier},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::algorithms::{SecurityLevel, WipeAlgorithm};
+use crate::device::{DeviceInfo, DeviceType};
+use crate::error::{SafeEraseError, Result};
+use crate::wipe::WipeOptions;
+
+/// Rules an administrator mandates for every wipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WipePolicy {
+    /// Identifier recorded in wipe results
+    pub policy_id: String,
+    pub issued_by: String,
+    pub issued_at: DateTime<Utc>,
+    /// Minimum security level of the wipe algorithm
+    #[serde(default)]
+    pub min_security_level: Option<SecurityLevel>,
+    /// Refuse to wipe the disk the system is running from
+    #[serde(default = "default_true")]
+    pub prohibit_system_disks: bool,
+    /// Require post-wipe verification
+    #[serde(default)]
+    pub require_verification: bool,
+    /// Require a certificate to be issued for every wipe
+    #[serde(default)]
+    pub require_certificate: bool,
+    /// Device classes that may be wiped; all classes when unset
+    #[serde(default)]
+    pub allowed_device_types: Option<Vec<DeviceType>>,
+}
+
+/// Signed policy document as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPolicy {
+    /// Policy JSON exactly as signed
+    pub payload: String,
+    /// Base64-encoded signature over `payload`
+    pub signature: String,
+}
+
+impl WipePolicy {
+    /// Check a requested wipe against the policy
+    pub fn check(&self, device: &DeviceInfo, algorithm: &WipeAlgorithm, options: &WipeOptions) -> Result<()> {
+        if self.prohibit_system_disks && device.is_system_disk {
+            return Err(self.violation(format!("{} is a system disk", device.path)));
+        }
+        
+        if let Some(allowed) = &self.allowed_device_types {
+            if !allowed.contains(&device.device_type) {
+                return Err(self.violation(format!(
+                    "device class {} is not permitted",
+                    device.device_type
+                )));
+            }
+        }
+        
+        if let Some(minimum) = self.min_security_level {
+            let level = algorithm.info().security_level;
+            if level < minimum {
+                return Err(self.violation(format!(
+                    "{} has security level {}, policy requires at least {}",
+                    algorithm, level, minimum
+                )));
+            }
+        }
+        
+        if self.require_verification && !options.verify_wipe {
+            return Err(self.violation("verification must be enabled".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    fn violation(&self, reason: String) -> SafeEraseError {
+        warn!("Policy {} violated: {}", self.policy_id, reason);
+        SafeEraseError::PolicyViolation(format!("{} ({})", reason, self.policy_id))
+    }
+}
+
+#[cfg(feature = "signed-policy")]
+impl SignedPolicy {
+    /// Sign a policy with an administrator key
+    pub fn sign(policy: &WipePolicy, private_key: &PKey<Private>) -> Result<Self> {
+        let payload = serde_json::to_string(policy)
+            .map_err(|e| SafeEraseError::Internal(e.to_string()))?;
+        
+        let mut signer = Signer::new(MessageDigest::sha256(), private_key)
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        signer
+            .update(payload.as_bytes())
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        let signature = signer
+            .sign_to_vec()
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        
+        Ok(Self {
+            payload,
+            signature: base64::encode_block(&signature),
+        })
+    }
+    
+    /// Verify the signature and return the enclosed policy
+    pub fn verify(&self, public_key: &PKey<Public>) -> Result<WipePolicy> {
+        let signature = base64::decode_block(&self.signature)
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        
+        let mut verifier = Verifier::new(MessageDigest::sha256(), public_key)
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        verifier
+            .update(self.payload.as_bytes())
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        let valid = verifier
+            .verify(&signature)
+            .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+        if !valid {
+            return Err(SafeEraseError::SignatureVerificationFailed);
+        }
+        
+        serde_json::from_str(&self.payload)
+            .map_err(|e| SafeEraseError::InvalidConfiguration(format!("Invalid policy: {}", e)))
+    }
+}
+
+/// Load and verify a signed policy file against a PEM public key file
+#[cfg(feature = "signed-policy")]
+pub fn load_policy<P: AsRef<Path>, K: AsRef<Path>>(policy_path: P, public_key_path: K) -> Result<WipePolicy> {
+    let contents = std::fs::read_to_string(policy_path.as_ref())
+        .map_err(|e| SafeEraseError::FileSystemError(format!("{}: {}", policy_path.as_ref().display(), e)))?;
+    let signed: SignedPolicy = serde_json::from_str(&contents)
+        .map_err(|e| SafeEraseError::InvalidConfiguration(format!("Invalid policy file: {}", e)))?;
+    
+    let key_pem = std::fs::read(public_key_path.as_ref())
+        .map_err(|e| SafeEraseError::FileSystemError(format!("{}: {}", public_key_path.as_ref().display(), e)))?;
+    let public_key = PKey::public_key_from_pem(&key_pem)
+        .map_err(|e| SafeEraseError::CryptographicError(e.to_string()))?;
+    
+    let policy = signed.verify(&public_key)?;
+    info!("Loaded wipe policy {} issued by {}", policy.policy_id, policy.issued_by);
+    Ok(policy)
+}
+
+/// Refuse a configured policy file; this build cannot check its signature
+#[cfg(not(feature = "signed-policy"))]
+pub fn load_policy<P: AsRef<Path>, K: AsRef<Path>>(policy_path: P, _public_key_path: K) -> Result<WipePolicy> {
+    Err(SafeEraseError::InvalidConfiguration(format!(
+        "Cannot verify policy file {}: built without the signed-policy feature",
+        policy_path.as_ref().display()
+    )))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{HealthStatus, StorageInterface};
+    
+    fn test_policy() -> WipePolicy {
+        WipePolicy {
+            policy_id: "POL-2024-01".to_string(),
+            issued_by: "it-security".to_string(),
+            issued_at: Utc::now(),
+            min_security_level: Some(SecurityLevel::High),
+            prohibit_system_disks: true,
+            require_verification: true,
+            require_certificate: true,
+            allowed_device_types: Some(vec![DeviceType::HDD, DeviceType::SSD]),
+        }
+    }
+    
+    fn test_device(device_type: DeviceType, is_system_disk: bool) -> DeviceInfo {
+        DeviceInfo {
+            path: "/dev/sdb".to_string(),
+            name: "sdb".to_string(),
+            model: "Test".to_string(),
+            serial: "SN1".to_string(),
+            size: 1024,
+            device_type,
+            interface: StorageInterface::SATA,
+            is_removable: false,
+            is_system_disk,
+            supports_secure_erase: false,
+            supports_hpa_dco: false,
+            firmware_version: None,
+            temperature: None,
+            health_status: HealthStatus::Good,
+            hidden_area: None,
+            virtualization: None,
+            bay: None,
+        }
+    }
+    
+    #[test]
+    fn test_policy_checks() {
+        let policy = test_policy();
+        let options = WipeOptions::default();
+        let hdd = test_device(DeviceType::HDD, false);
+        
+        assert!(policy.check(&hdd, &WipeAlgorithm::DoD522022M, &options).is_ok());
+        assert!(matches!(
+            policy.check(&hdd, &WipeAlgorithm::ZeroFill, &options),
+            Err(SafeEraseError::PolicyViolation(_))
+        ));
+        assert!(policy.check(&test_device(DeviceType::HDD, true), &WipeAlgorithm::Gutmann, &options).is_err());
+        assert!(policy.check(&test_device(DeviceType::USB, false), &WipeAlgorithm::Gutmann, &options).is_err());
+        
+        let unverified = WipeOptions { verify_wipe: false, ..WipeOptions::default() };
+        assert!(policy.check(&hdd, &WipeAlgorithm::Gutmann, &unverified).is_err());
+    }
+    
+    #[cfg(feature = "signed-policy")]
+    #[test]
+    fn test_signed_policy_roundtrip() {
+        let rsa = openssl::rsa::Rsa::generate(2048).unwrap();
+        let private_key = PKey::from_rsa(rsa).unwrap();
+        let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+        
+        let signed = SignedPolicy::sign(&test_policy(), &private_key).unwrap();
+        assert_eq!(signed.verify(&public_key).unwrap().policy_id, "POL-2024-01");
+        
+        let tampered = SignedPolicy {
+            payload: signed.payload.replace("\"require_verification\":true", "\"require_verification\":false"),
+            signature: signed.signature.clone(),
+        };
+        assert!(matches!(tampered.verify(&public_key), Err(SafeEraseError::SignatureVerificationFailed)));
+    }
+}
@@ -0,0 +1,161 @@
+//! Windows dynamic disk and Storage Spaces membership
+//!
+//! A dynamic disk holds its volumes in the Logical Disk Manager database, and
+//! a Storage Spaces pool spreads every virtual disk across all its physical
+//! members. Wiping one member destroys data on volumes that still appear
+//! healthy on the other disks until Windows next reads the stripe, so the
+//! damage is not noticed until it is too late. Both leave a mark in the
+//! partition table: an MBR partition of type `0x42`, or GPT partitions with
+//! the LDM or Storage Spaces type GUIDs. The table is read from the disk
+//! itself, so membership is found on any platform, including a Windows pool
+//! member attached to a Linux station.
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes read from the start of a disk to find its partition entries; the
+/// standard 128 GPT entries fit behind the header for sector sizes up to 4 KiB
+pub(crate) const PARTITION_TABLE_BYTES: usize = 32 * 1024;
+
+const MBR_SIGNATURE_OFFSET: usize = 510;
+const MBR_PARTITIONS_OFFSET: usize = 446;
+const MBR_LDM: u8 = 0x42;
+const MBR_PROTECTIVE: u8 = 0xEE;
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+const LDM_METADATA_GUID: &str = "5808C8AA-7E8F-42E0-85D2-E1E90434CFB3";
+const LDM_DATA_GUID: &str = "AF9B60A0-1431-4F62-BC68-3311714A69AD";
+const STORAGE_SPACES_GUID: &str = "E75CAF8F-F680-4CEE-AFA3-B001E56EFC2D";
+
+/// Windows volume set a disk belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolMembership {
+    /// Dynamic disk managed by the Logical Disk Manager
+    DynamicDisk,
+    /// Physical disk of a Storage Spaces pool
+    StorageSpaces,
+}
+
+/// Membership recorded in the partition table at the start of a disk
+pub fn detect(disk_start: &[u8], sector_size: usize) -> Option<PoolMembership> {
+    if disk_start.len() < 512 || disk_start[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2] != [0x55, 0xAA] {
+        return None;
+    }
+    let mbr_types: Vec<u8> = (0..4).map(|i| disk_start[MBR_PARTITIONS_OFFSET + i * 16 + 4]).collect();
+    if mbr_types.contains(&MBR_LDM) {
+        return Some(PoolMembership::DynamicDisk);
+    }
+    if mbr_types.contains(&MBR_PROTECTIVE) {
+        return detect_gpt(disk_start, sector_size);
+    }
+    None
+}
+
+fn detect_gpt(disk_start: &[u8], sector_size: usize) -> Option<PoolMembership> {
+    let header = disk_start.get(sector_size..sector_size + 92)?;
+    if &header[..8] != GPT_SIGNATURE {
+        return None;
+    }
+    let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap_or_default()) as usize;
+    let entries_lba = u64::from_le_bytes(header[72..80].try_into().ok()?) as usize;
+    let (count, entry_size) = (u32_at(80), u32_at(84));
+    if entry_size < 16 {
+        return None;
+    }
+    
+    let start = entries_lba.checked_mul(sector_size)?;
+    let mut membership = None;
+    for index in 0..count {
+        let Some(entry) = disk_start.get(start + index * entry_size..start + index * entry_size + 16) else {
+            break;
+        };
+        match guid(entry).as_str() {
+            STORAGE_SPACES_GUID => return Some(PoolMembership::StorageSpaces),
+            LDM_METADATA_GUID | LDM_DATA_GUID => membership = Some(PoolMembership::DynamicDisk),
+            _ => {}
+        }
+    }
+    membership
+}
+
+/// GUID in its usual text form; the first three fields are stored little-endian
+fn guid(bytes: &[u8]) -> String {
+    format!(
+        "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{}-{}",
+        bytes[3], bytes[2], bytes[1], bytes[0],
+        bytes[5], bytes[4],
+        bytes[7], bytes[6],
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..16]),
+    )
+}
+
+impl std::fmt::Display for PoolMembership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PoolMembership::DynamicDisk => write!(f, "Windows dynamic disk"),
+            PoolMembership::StorageSpaces => write!(f, "Storage Spaces pool member"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn guid_bytes(guid: &str) -> Vec<u8> {
+        let hex: String = guid.chars().filter(|c| *c != '-').collect();
+        let mut bytes = hex::decode(hex).unwrap();
+        bytes[0..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+        bytes
+    }
+    
+    fn gpt_disk(sector_size: usize, type_guids: &[&str]) -> Vec<u8> {
+        let mut disk = vec![0u8; PARTITION_TABLE_BYTES];
+        disk[MBR_SIGNATURE_OFFSET] = 0x55;
+        disk[MBR_SIGNATURE_OFFSET + 1] = 0xAA;
+        disk[MBR_PARTITIONS_OFFSET + 4] = MBR_PROTECTIVE;
+        let header = sector_size;
+        disk[header..header + 8].copy_from_slice(GPT_SIGNATURE);
+        disk[header + 72..header + 80].copy_from_slice(&2u64.to_le_bytes());
+        disk[header + 80..header + 84].copy_from_slice(&128u32.to_le_bytes());
+        disk[header + 84..header + 88].copy_from_slice(&128u32.to_le_bytes());
+        for (index, type_guid) in type_guids.iter().enumerate() {
+            let entry = 2 * sector_size + index * 128;
+            disk[entry..entry + 16].copy_from_slice(&guid_bytes(type_guid));
+        }
+        disk
+    }
+    
+    #[test]
+    fn test_detect_gpt_membership() {
+        let basic_data = "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7";
+        assert_eq!(guid(&guid_bytes(basic_data)), basic_data);
+        
+        assert_eq!(detect(&gpt_disk(512, &[basic_data]), 512), None);
+        assert_eq!(
+            detect(&gpt_disk(512, &[LDM_METADATA_GUID, LDM_DATA_GUID]), 512),
+            Some(PoolMembership::DynamicDisk)
+        );
+        assert_eq!(
+            detect(&gpt_disk(4096, &[basic_data, STORAGE_SPACES_GUID]), 4096),
+            Some(PoolMembership::StorageSpaces)
+        );
+        // Read with the wrong sector size, the header is not found
+        assert_eq!(detect(&gpt_disk(4096, &[STORAGE_SPACES_GUID]), 512), None);
+    }
+    
+    #[test]
+    fn test_detect_mbr_membership() {
+        let mut disk = vec![0u8; 512];
+        assert_eq!(detect(&disk, 512), None);
+        disk[MBR_SIGNATURE_OFFSET] = 0x55;
+        disk[MBR_SIGNATURE_OFFSET + 1] = 0xAA;
+        disk[MBR_PARTITIONS_OFFSET + 4] = 0x07;
+        assert_eq!(detect(&disk, 512), None);
+        disk[MBR_PARTITIONS_OFFSET + 16 + 4] = MBR_LDM;
+        assert_eq!(detect(&disk, 512), Some(PoolMembership::DynamicDisk));
+    }
+}
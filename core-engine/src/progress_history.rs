@@ -0,0 +1,278 @@
+//! Recent progress snapshots for clients that reconnect
+//!
+//! Progress is broadcast as it happens, so a UI that drops its receiver, for
+//! example on a browser refresh, misses everything sent meanwhile. Every
+//! snapshot is numbered per operation and the last few are kept. The
+//! [`ProgressToken`] of the last snapshot a client saw lets it fetch the ones
+//! it missed and continue with the live stream, without gaps or duplicates.
+
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::{SafeEraseError, Result};
+use crate::wipe::{WipeProgress, WipeStatus};
+
+/// Snapshots kept per operation
+const HISTORY_LEN: usize = 64;
+
+/// How long the history of a finished operation stays available
+const FINISHED_RETENTION: Duration = Duration::from_secs(3600);
+
+/// Position in the progress of one operation, written `<operation_id>.<sequence>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProgressToken {
+    pub operation_id: Uuid,
+    /// Sequence number of the last snapshot seen; 0 before the first
+    pub sequence: u64,
+}
+
+/// Missed snapshots followed by the live stream of one operation
+#[derive(Debug)]
+pub struct ProgressReplay {
+    operation_id: Uuid,
+    missed: VecDeque<WipeProgress>,
+    /// Snapshots after the token had already been dropped from the history
+    truncated: bool,
+    live: broadcast::Receiver<WipeProgress>,
+    finished: bool,
+}
+
+#[derive(Debug)]
+struct OperationHistory {
+    snapshots: VecDeque<WipeProgress>,
+    next_sequence: u64,
+    finished_at: Option<Instant>,
+}
+
+/// Numbers, keeps and broadcasts progress snapshots
+#[derive(Debug, Clone)]
+pub(crate) struct ProgressHistory {
+    operations: Arc<Mutex<HashMap<Uuid, OperationHistory>>>,
+    tx: broadcast::Sender<WipeProgress>,
+}
+
+impl ProgressToken {
+    /// Token for a client that has seen nothing of the operation yet
+    pub fn start(operation_id: Uuid) -> Self {
+        Self { operation_id, sequence: 0 }
+    }
+}
+
+impl WipeProgress {
+    /// Token to resume from after this snapshot
+    pub fn resume_token(&self) -> ProgressToken {
+        ProgressToken { operation_id: self.operation_id, sequence: self.sequence }
+    }
+}
+
+impl ProgressHistory {
+    pub(crate) fn new(tx: broadcast::Sender<WipeProgress>) -> Self {
+        Self {
+            operations: Arc::new(Mutex::new(HashMap::new())),
+            tx,
+        }
+    }
+    
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<WipeProgress> {
+        self.tx.subscribe()
+    }
+    
+    /// Number, keep and broadcast a snapshot
+    pub(crate) fn publish(&self, mut snapshot: WipeProgress) {
+        let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        operations.retain(|_, history| {
+            history.finished_at.is_none_or(|finished| finished.elapsed() < FINISHED_RETENTION)
+        });
+        
+        let history = operations.entry(snapshot.operation_id).or_insert_with(|| OperationHistory {
+            snapshots: VecDeque::with_capacity(HISTORY_LEN),
+            next_sequence: 1,
+            finished_at: None,
+        });
+        snapshot.sequence = history.next_sequence;
+        history.next_sequence += 1;
+        if is_final(snapshot.status) {
+            history.finished_at = Some(Instant::now());
+        }
+        if history.snapshots.len() == HISTORY_LEN {
+            history.snapshots.pop_front();
+        }
+        history.snapshots.push_back(snapshot.clone());
+        // Sent under the lock so a replay sees each snapshot either in the history or live
+        let _ = self.tx.send(snapshot);
+    }
+    
+    /// Snapshots after `token` and a receiver for those still to come
+    pub(crate) fn resume(&self, token: &ProgressToken) -> Result<ProgressReplay> {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        let history = operations.get(&token.operation_id).ok_or_else(|| {
+            SafeEraseError::InvalidParameter(format!("No progress history for operation {}", token.operation_id))
+        })?;
+        let missed: VecDeque<WipeProgress> = history
+            .snapshots
+            .iter()
+            .filter(|snapshot| snapshot.sequence > token.sequence)
+            .cloned()
+            .collect();
+        let oldest = history.snapshots.front().map_or(history.next_sequence, |snapshot| snapshot.sequence);
+        Ok(ProgressReplay {
+            operation_id: token.operation_id,
+            truncated: token.sequence + 1 < oldest,
+            finished: history.finished_at.is_some() && missed.is_empty(),
+            missed,
+            live: self.tx.subscribe(),
+        })
+    }
+}
+
+impl ProgressReplay {
+    /// Whether snapshots between the token and the first replayed one were lost
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+    
+    /// Snapshots already available, without waiting for live ones
+    pub fn missed(&self) -> impl Iterator<Item = &WipeProgress> {
+        self.missed.iter()
+    }
+    
+    /// Next snapshot of the operation; `None` once it has finished
+    pub async fn next(&mut self) -> Option<WipeProgress> {
+        if self.finished {
+            return None;
+        }
+        let snapshot = match self.missed.pop_front() {
+            Some(snapshot) => snapshot,
+            None => loop {
+                match self.live.recv().await {
+                    Ok(snapshot) if snapshot.operation_id == self.operation_id => break snapshot,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            },
+        };
+        self.finished = is_final(snapshot.status);
+        Some(snapshot)
+    }
+}
+
+fn is_final(status: WipeStatus) -> bool {
+    matches!(status, WipeStatus::Completed | WipeStatus::Failed | WipeStatus::Cancelled | WipeStatus::DeviceRemoved)
+}
+
+impl std::fmt::Display for ProgressToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.operation_id, self.sequence)
+    }
+}
+
+impl FromStr for ProgressToken {
+    type Err = SafeEraseError;
+    
+    fn from_str(token: &str) -> Result<Self> {
+        let invalid = || SafeEraseError::InvalidParameter(format!("Invalid progress token '{}'", token));
+        let (operation_id, sequence) = token.rsplit_once('.').ok_or_else(invalid)?;
+        Ok(Self {
+            operation_id: operation_id.parse().map_err(|_| invalid())?,
+            sequence: sequence.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl TryFrom<String> for ProgressToken {
+    type Error = SafeEraseError;
+    
+    fn try_from(token: String) -> Result<Self> {
+        token.parse()
+    }
+}
+
+impl From<ProgressToken> for String {
+    fn from(token: ProgressToken) -> Self {
+        token.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::algorithms::WipeAlgorithm;
+    
+    fn snapshot(operation_id: Uuid, status: WipeStatus, bytes: u64) -> WipeProgress {
+        WipeProgress {
+            operation_id,
+            device_path: "/dev/sdb".to_string(),
+            bay: None,
+            algorithm: WipeAlgorithm::ZeroFill,
+            current_pass: 1,
+            total_passes: 1,
+            bytes_processed: bytes,
+            total_bytes: 100,
+            percentage: bytes as f64,
+            current_speed: 0.0,
+            average_speed: 0.0,
+            estimated_remaining: None,
+            current_pattern: None,
+            status,
+            started_at: Utc::now(),
+            last_updated: Utc::now(),
+            sequence: 0,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_resume_replays_missed_then_live() {
+        let (tx, _) = broadcast::channel(16);
+        let history = ProgressHistory::new(tx);
+        let operation_id = Uuid::new_v4();
+        let mut first = history.subscribe();
+        history.publish(snapshot(operation_id, WipeStatus::Wiping, 10));
+        let seen = first.recv().await.unwrap();
+        assert_eq!(seen.sequence, 1);
+        
+        // The client drops its receiver; two snapshots go by before it reconnects
+        drop(first);
+        history.publish(snapshot(operation_id, WipeStatus::Wiping, 20));
+        history.publish(snapshot(operation_id, WipeStatus::Wiping, 30));
+        let token: ProgressToken = seen.resume_token().to_string().parse().unwrap();
+        let mut replay = history.resume(&token).unwrap();
+        assert!(!replay.truncated());
+        assert_eq!(replay.missed().count(), 2);
+        
+        history.publish(snapshot(operation_id, WipeStatus::Completed, 100));
+        let bytes: Vec<u64> = [
+            replay.next().await.unwrap(),
+            replay.next().await.unwrap(),
+            replay.next().await.unwrap(),
+        ]
+        .iter()
+        .map(|snapshot| snapshot.bytes_processed)
+        .collect();
+        assert_eq!(bytes, [20, 30, 100]);
+        assert!(replay.next().await.is_none());
+    }
+    
+    #[test]
+    fn test_history_is_bounded() {
+        let (tx, _) = broadcast::channel(16);
+        let history = ProgressHistory::new(tx);
+        let operation_id = Uuid::new_v4();
+        for bytes in 0..HISTORY_LEN as u64 + 10 {
+            history.publish(snapshot(operation_id, WipeStatus::Wiping, bytes));
+        }
+        
+        let replay = history.resume(&ProgressToken::start(operation_id)).unwrap();
+        assert!(replay.truncated());
+        assert_eq!(replay.missed().count(), HISTORY_LEN);
+        assert_eq!(replay.missed().next().unwrap().sequence, 11);
+        assert!(history.resume(&ProgressToken::start(Uuid::new_v4())).is_err());
+        assert!("not-a-token".parse::<ProgressToken>().is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Pre-flight checks for whether a device can be wiped
+//!
+//! Frontends call [`Device::readiness`](crate::Device::readiness) to grey out
+//! devices that cannot be wiped and explain why before the operator tries.
+//! Blockers prevent a wipe; warnings are shown but do not stop it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::device::{DeviceCapabilities, DeviceInfo, HealthStatus, StorageInterface, VirtualizationLayer};
+use crate::pool_membership::PoolMembership;
+
+/// Reason a device cannot, or should not, be wiped right now
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadinessIssue {
+    /// Partitions of the device are mounted
+    Mounted { mount_points: Vec<String> },
+    /// The device holds the running operating system
+    SystemDisk,
+    /// ATA security is frozen by the BIOS; hardware erase needs a power cycle
+    SecurityFrozen,
+    /// The device or its adapter only allows reads
+    WriteProtected,
+    /// SMART reports the device as failing
+    CriticalHealth,
+    /// SMART reports degraded health
+    DegradedHealth,
+    /// The process lacks administrator/root rights
+    InsufficientPrivileges,
+    /// The storage interface is not recognized
+    UnsupportedInterface { interface: StorageInterface },
+    /// A hypervisor or storage array maps the blocks; a wipe only covers what it exposes
+    VirtualDisk { layer: VirtualizationLayer },
+    /// The device is a Windows dynamic disk or Storage Spaces member; wiping
+    /// it silently corrupts the volumes spanning the other disks
+    PoolMember { pool: PoolMembership },
+}
+
+/// Blockers and warnings for one device
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WipeReadiness {
+    pub blockers: Vec<ReadinessIssue>,
+    pub warnings: Vec<ReadinessIssue>,
+}
+
+/// Observed device state the checks are based on
+#[derive(Debug, Clone, Copy)]
+pub struct ReadinessInputs<'a> {
+    pub info: &'a DeviceInfo,
+    pub capabilities: &'a DeviceCapabilities,
+    pub mount_points: &'a [String],
+    pub privileged: bool,
+    pub pool_membership: Option<PoolMembership>,
+}
+
+impl WipeReadiness {
+    /// Evaluate all checks
+    pub fn assess(inputs: ReadinessInputs<'_>) -> Self {
+        let mut readiness = Self::default();
+        let ReadinessInputs { info, capabilities, mount_points, privileged, pool_membership } = inputs;
+        
+        if !privileged {
+            readiness.blockers.push(ReadinessIssue::InsufficientPrivileges);
+        }
+        if info.is_system_disk {
+            readiness.blockers.push(ReadinessIssue::SystemDisk);
+        }
+        if !mount_points.is_empty() {
+            readiness.blockers.push(ReadinessIssue::Mounted {
+                mount_points: mount_points.to_vec(),
+            });
+        }
+        if let Some(pool) = pool_membership {
+            readiness.blockers.push(ReadinessIssue::PoolMember { pool });
+        }
+        if capabilities.write_protected {
+            readiness.blockers.push(ReadinessIssue::WriteProtected);
+        }
+        if info.interface == StorageInterface::Unknown {
+            readiness.blockers.push(ReadinessIssue::UnsupportedInterface {
+                interface: info.interface,
+            });
+        }
+        match info.health_status {
+            HealthStatus::Critical => readiness.blockers.push(ReadinessIssue::CriticalHealth),
+            HealthStatus::Warning => readiness.warnings.push(ReadinessIssue::DegradedHealth),
+            HealthStatus::Good | HealthStatus::Unknown => {}
+        }
+        // Software overwrites still work on a frozen drive
+        if capabilities.security_frozen {
+            readiness.warnings.push(ReadinessIssue::SecurityFrozen);
+        }
+        if let Some(layer) = info.virtualization {
+            readiness.warnings.push(ReadinessIssue::VirtualDisk { layer });
+        }
+        
+        readiness
+    }
+    
+    /// Whether nothing prevents a wipe
+    pub fn is_wipeable(&self) -> bool {
+        self.blockers.is_empty()
+    }
+    
+    /// Every issue, blockers first
+    pub fn issues(&self) -> impl Iterator<Item = &ReadinessIssue> {
+        self.blockers.iter().chain(self.warnings.iter())
+    }
+}
+
+impl ReadinessIssue {
+    /// Stable identifier matching the serialized `kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReadinessIssue::Mounted { .. } => "mounted",
+            ReadinessIssue::SystemDisk => "system_disk",
+            ReadinessIssue::SecurityFrozen => "security_frozen",
+            ReadinessIssue::WriteProtected => "write_protected",
+            ReadinessIssue::CriticalHealth => "critical_health",
+            ReadinessIssue::DegradedHealth => "degraded_health",
+            ReadinessIssue::InsufficientPrivileges => "insufficient_privileges",
+            ReadinessIssue::UnsupportedInterface { .. } => "unsupported_interface",
+            ReadinessIssue::VirtualDisk { .. } => "virtual_disk",
+            ReadinessIssue::PoolMember { .. } => "pool_member",
+        }
+    }
+}
+
+impl std::fmt::Display for ReadinessIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadinessIssue::Mounted { mount_points } => {
+                write!(f, "Mounted at {}; unmount it first", mount_points.join(", "))
+            }
+            ReadinessIssue::SystemDisk => write!(f, "Contains the running operating system"),
+            ReadinessIssue::SecurityFrozen => {
+                write!(f, "Security is frozen; hardware erase needs a power cycle or hot-plug")
+            }
+            ReadinessIssue::WriteProtected => write!(f, "Write-protected"),
+            ReadinessIssue::CriticalHealth => {
+                write!(f, "SMART reports the drive as failing; consider physical destruction")
+            }
+            ReadinessIssue::DegradedHealth => write!(f, "SMART reports degraded health"),
+            ReadinessIssue::InsufficientPrivileges => write!(f, "Administrator or root privileges are required"),
+            ReadinessIssue::UnsupportedInterface { interface } => {
+                write!(f, "Storage interface {} is not supported", interface)
+            }
+            ReadinessIssue::VirtualDisk { layer } => {
+                write!(f, "{}; overwrites may not reach the physical media", layer)
+            }
+            ReadinessIssue::PoolMember { pool } => {
+                write!(f, "{}; remove it from its pool or convert it to a basic disk first", pool)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{DeviceType, TrimReadBehavior};
+    
+    fn info() -> DeviceInfo {
+        DeviceInfo {
+            path: "/dev/sdb".to_string(),
+            name: "sdb".to_string(),
+            model: "Test SSD".to_string(),
+            serial: "SN1".to_string(),
+            size: 1_000_000_000,
+            device_type: DeviceType::SSD,
+            interface: StorageInterface::SATA,
+            is_removable: false,
+            is_system_disk: false,
+            supports_secure_erase: true,
+            supports_hpa_dco: true,
+            firmware_version: None,
+            temperature: None,
+            health_status: HealthStatus::Good,
+            hidden_area: None,
+            virtualization: None,
+            bay: None,
+        }
+    }
+    
+    fn capabilities() -> DeviceCapabilities {
+        DeviceCapabilities {
+            supports_ata_secure_erase: true,
+            supports_nvme_format: false,
+            supports_trim: true,
+            supports_write_same: true,
+            supports_hpa_detection: true,
+            supports_dco_detection: true,
+            max_lba: 1_953_125,
+            logical_sector_size: 512,
+            physical_sector_size: 4096,
+            security_frozen: false,
+            write_protected: false,
+            trim_read_behavior: TrimReadBehavior::Zeroes,
+        }
+    }
+    
+    #[test]
+    fn test_ready_device() {
+        let readiness = WipeReadiness::assess(ReadinessInputs {
+            info: &info(),
+            capabilities: &capabilities(),
+            mount_points: &[],
+            privileged: true,
+            pool_membership: None,
+        });
+        assert!(readiness.is_wipeable());
+        assert_eq!(readiness.issues().count(), 0);
+    }
+    
+    #[test]
+    fn test_blockers_and_warnings() {
+        let mut info = info();
+        info.health_status = HealthStatus::Warning;
+        info.virtualization = Some(VirtualizationLayer::Vmware);
+        let mut capabilities = capabilities();
+        capabilities.security_frozen = true;
+        capabilities.write_protected = true;
+        let mounts = vec!["/media/usb".to_string()];
+        
+        let readiness = WipeReadiness::assess(ReadinessInputs {
+            info: &info,
+            capabilities: &capabilities,
+            mount_points: &mounts,
+            privileged: false,
+            pool_membership: Some(PoolMembership::StorageSpaces),
+        });
+        assert!(!readiness.is_wipeable());
+        let blockers: Vec<&str> = readiness.blockers.iter().map(ReadinessIssue::kind).collect();
+        assert_eq!(blockers, ["insufficient_privileges", "mounted", "pool_member", "write_protected"]);
+        assert_eq!(
+            readiness.warnings,
+            [
+                ReadinessIssue::DegradedHealth,
+                ReadinessIssue::SecurityFrozen,
+                ReadinessIssue::VirtualDisk { layer: VirtualizationLayer::Vmware },
+            ]
+        );
+        
+        let json = serde_json::to_value(&readiness.blockers[1]).unwrap();
+        assert_eq!(json["kind"], "mounted");
+        assert_eq!(json["mount_points"][0], "/media/usb");
+    }
+}
@@ -0,0 +1,125 @@
+//! Identifier redaction for shared reports
+//!
+//! Certificates and event logs are sometimes handed to parties who must not
+//! see full device serials or asset tags. A [`Redactor`] masks such values
+//! down to their last characters and appends a salted hash, so two reports
+//! about the same drive can still be correlated by whoever holds the salt.
+//! Log output can be masked the same way, or truncated to the last characters
+//! without a hash; see [`crate::logging::IdentifierMasker`].
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Number of trailing characters left visible
+const VISIBLE_CHARS: usize = 4;
+
+/// Values shorter than this are masked completely
+const MIN_PARTIAL_LENGTH: usize = 8;
+
+/// Hex characters of the salted hash kept in a redacted value
+const HASH_CHARS: usize = 12;
+
+/// How device serials and asset tags appear in log output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRedaction {
+    /// Logged in full
+    #[default]
+    Off,
+    /// Last characters and a salted hash, as in redacted certificates
+    Hash,
+    /// Last characters only; lines about one drive cannot be tied together
+    Truncate,
+}
+
+/// Masks identifiers with a salted, correlatable hash
+#[derive(Clone, PartialEq, Eq)]
+pub struct Redactor {
+    salt: String,
+}
+
+impl Redactor {
+    pub fn new(salt: impl Into<String>) -> Self {
+        Self { salt: salt.into() }
+    }
+    
+    /// Salted hash of an identifier; case and surrounding whitespace are
+    /// ignored so serials reported differently by drive and inventory match
+    pub fn hash(&self, value: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.trim().to_ascii_uppercase().as_bytes());
+        let mut hash = hex::encode(hasher.finalize());
+        hash.truncate(HASH_CHARS);
+        hash
+    }
+    
+    /// Masked form of an identifier, e.g. `****3456#9f86d081884c`
+    pub fn redact(&self, value: &str) -> String {
+        let value = value.trim();
+        if value.is_empty() {
+            return String::new();
+        }
+        format!("{}#{}", truncate(value), self.hash(value))
+    }
+    
+    /// Replace every occurrence of an identifier in free text, such as an
+    /// error message that quotes the serial
+    pub fn redact_in(&self, text: &str, value: &str) -> String {
+        let value = value.trim();
+        if value.is_empty() {
+            text.to_string()
+        } else {
+            text.replace(value, &self.redact(value))
+        }
+    }
+}
+
+/// Identifier cut down to its last characters, e.g. `****3456`
+pub fn truncate(value: &str) -> String {
+    let chars: Vec<char> = value.trim().chars().collect();
+    let visible: String = if chars.len() >= MIN_PARTIAL_LENGTH {
+        chars[chars.len() - VISIBLE_CHARS..].iter().collect()
+    } else {
+        String::new()
+    };
+    format!("****{}", visible)
+}
+
+// The salt is a secret; keep it out of debug output and logs
+impl std::fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Redactor").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_redact_keeps_suffix_and_correlates() {
+        let redactor = Redactor::new("bench-7");
+        let redacted = redactor.redact("WD-WCC4N1234567");
+        assert!(redacted.starts_with("****4567#"));
+        assert!(!redacted.contains("WCC4N"));
+        assert_eq!(redacted, redactor.redact(" wd-wcc4n1234567 "));
+        assert_ne!(redacted, Redactor::new("bench-8").redact("WD-WCC4N1234567"));
+        
+        // Short values reveal nothing but the hash
+        assert_eq!(redactor.redact("TAG1"), format!("****#{}", redactor.hash("TAG1")));
+        assert_eq!(redactor.redact(""), "");
+        assert_eq!(truncate("WD-WCC4N1234567"), "****4567");
+        assert_eq!(truncate("TAG1"), "****");
+    }
+    
+    #[test]
+    fn test_redact_in_text_and_debug() {
+        let redactor = Redactor::new("salt");
+        let message = redactor.redact_in("/dev/sdb has serial S1234567890, expected S1", "S1234567890");
+        assert!(!message.contains("S1234567890"));
+        assert!(message.contains("****7890#"));
+        assert!(!format!("{:?}", redactor).contains("salt"));
+    }
+}
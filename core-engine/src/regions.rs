@@ -0,0 +1,207 @@
+//! Wiping selected regions of a disk
+//!
+//! Some machines keep an OEM diagnostics or recovery partition that has to
+//! survive a refurbishment. A wipe given [`WipeRegion`]s in
+//! `WipeOptions::regions` overwrites only those LBA ranges, as one
+//! operation: every pass walks the regions in order, the result lists what
+//! each region received, and the certificate records them together. Each
+//! region starts its pattern afresh, so deterministic passes can still be
+//! regenerated for verification. Hardware erases act on the whole drive and
+//! cannot be limited to regions.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SafeEraseError, Result};
+use crate::wipe::SkippedRange;
+
+/// Range of logical blocks to wipe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WipeRegion {
+    /// Name shown in results, e.g. `partition 2`
+    #[serde(default)]
+    pub label: Option<String>,
+    pub start_lba: u64,
+    /// Last LBA of the region, inclusive
+    pub end_lba: u64,
+}
+
+/// What one region received
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegionResult {
+    pub region: WipeRegion,
+    pub bytes_wiped: u64,
+    pub passes_completed: usize,
+    /// Sectors of the region that could not be written
+    #[serde(default)]
+    pub skipped_sectors: u64,
+}
+
+impl WipeRegion {
+    /// Number of sectors in the region
+    pub fn sectors(&self) -> u64 {
+        self.end_lba - self.start_lba + 1
+    }
+    
+    /// First byte and length of the region
+    pub fn byte_span(&self, sector_size: u32) -> (u64, u64) {
+        let sector_size = u64::from(sector_size);
+        (self.start_lba * sector_size, self.sectors() * sector_size)
+    }
+    
+    /// Result for the region after `passes_completed` passes
+    pub fn result(&self, sector_size: u32, passes_completed: usize, skipped: &[SkippedRange]) -> RegionResult {
+        let skipped_sectors = skipped
+            .iter()
+            .map(|range| {
+                let start = range.start_lba.max(self.start_lba);
+                let end = range.end_lba.min(self.end_lba);
+                if start <= end { end - start + 1 } else { 0 }
+            })
+            .sum();
+        RegionResult {
+            region: self.clone(),
+            bytes_wiped: self.byte_span(sector_size).1 * passes_completed as u64,
+            passes_completed,
+            skipped_sectors,
+        }
+    }
+}
+
+/// Check that regions are non-empty, lie on a device of `device_sectors`
+/// sectors and do not overlap; returns them in LBA order
+pub fn validate(regions: &[WipeRegion], device_sectors: u64) -> Result<Vec<WipeRegion>> {
+    let mut sorted = regions.to_vec();
+    sorted.sort_by_key(|region| region.start_lba);
+    for region in &sorted {
+        if region.end_lba < region.start_lba || region.end_lba >= device_sectors {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "Region LBA {}-{} is not within the device's {} sectors",
+                region.start_lba, region.end_lba, device_sectors
+            )));
+        }
+    }
+    for pair in sorted.windows(2) {
+        if pair[1].start_lba <= pair[0].end_lba {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "Regions LBA {}-{} and {}-{} overlap",
+                pair[0].start_lba, pair[0].end_lba, pair[1].start_lba, pair[1].end_lba
+            )));
+        }
+    }
+    Ok(sorted)
+}
+
+/// Byte spans a pass writes: the regions, or the whole device when there are none
+pub(crate) fn byte_spans(regions: &[WipeRegion], sector_size: u32, device_size: u64) -> Vec<(u64, u64)> {
+    if regions.is_empty() {
+        return vec![(0, device_size)];
+    }
+    regions.iter().map(|region| region.byte_span(sector_size)).collect()
+}
+
+/// Bytes a pass writes
+pub(crate) fn wiped_bytes(regions: &[WipeRegion], sector_size: u32, device_size: u64) -> u64 {
+    byte_spans(regions, sector_size, device_size).iter().map(|(_, len)| len).sum()
+}
+
+/// Where the `position`-th byte of the regions, laid end to end, lies on the
+/// device, and its offset within its region. A read of `len` bytes is moved
+/// back so it does not run past the end of its region.
+pub(crate) fn locate(regions: &[WipeRegion], sector_size: u32, position: u64, len: u64) -> (u64, u64) {
+    let mut remaining = position;
+    for region in regions {
+        let (start, size) = region.byte_span(sector_size);
+        if remaining < size {
+            let within = remaining.min(size.saturating_sub(len));
+            return (start + within, within);
+        }
+        remaining -= size;
+    }
+    (position, position)
+}
+
+/// Partitions of a disk as regions labelled `partition <n>`, as last seen by the kernel
+pub fn partition_regions(device_path: &str, sector_size: u32) -> Vec<WipeRegion> {
+    #[cfg(target_os = "linux")]
+    {
+        let name = device_path.trim_start_matches("/dev/");
+        let Ok(entries) = std::fs::read_dir(format!("/sys/block/{}", name)) else {
+            return Vec::new();
+        };
+        let read = |path: std::path::PathBuf| -> Option<u64> {
+            std::fs::read_to_string(path).ok()?.trim().parse().ok()
+        };
+        // sysfs reports 512-byte sectors regardless of the logical sector size
+        let per_sector = u64::from(sector_size.max(512)) / 512;
+        let mut regions: Vec<WipeRegion> = entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(name))
+            .filter_map(|entry| {
+                let number = read(entry.path().join("partition"))?;
+                let start = read(entry.path().join("start"))?;
+                let size = read(entry.path().join("size"))?;
+                (size > 0).then(|| WipeRegion {
+                    label: Some(format!("partition {}", number)),
+                    start_lba: start / per_sector,
+                    end_lba: (start + size) / per_sector - 1,
+                })
+            })
+            .collect();
+        regions.sort_by_key(|region| region.start_lba);
+        regions
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (device_path, sector_size);
+        Vec::new()
+    }
+}
+
+/// Regions of the partitions with the given numbers
+pub fn select_partitions(device_path: &str, sector_size: u32, numbers: &[u32]) -> Result<Vec<WipeRegion>> {
+    let partitions = partition_regions(device_path, sector_size);
+    numbers
+        .iter()
+        .map(|number| {
+            let label = format!("partition {}", number);
+            partitions
+                .iter()
+                .find(|region| region.label.as_deref() == Some(label.as_str()))
+                .cloned()
+                .ok_or_else(|| SafeEraseError::InvalidParameter(format!("{} has no {}", device_path, label)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn region(start_lba: u64, end_lba: u64) -> WipeRegion {
+        WipeRegion { label: None, start_lba, end_lba }
+    }
+    
+    #[test]
+    fn test_validate_regions() {
+        let sorted = validate(&[region(100, 199), region(0, 9)], 1000).unwrap();
+        assert_eq!(sorted[0].start_lba, 0);
+        assert!(validate(&[region(0, 10), region(10, 20)], 1000).is_err());
+        assert!(validate(&[region(900, 1000)], 1000).is_err());
+        assert!(validate(&[region(20, 10)], 1000).is_err());
+    }
+    
+    #[test]
+    fn test_locate_within_regions() {
+        let regions = [region(0, 7), region(100, 107)];
+        // 4096 bytes per region at 512-byte sectors
+        assert_eq!(locate(&regions, 512, 0, 512), (0, 0));
+        assert_eq!(locate(&regions, 512, 4096, 512), (51200, 0));
+        // A read near the end of the first region is kept inside it
+        assert_eq!(locate(&regions, 512, 4000, 512), (3584, 3584));
+        
+        let skipped = [SkippedRange { start_lba: 105, end_lba: 120, reason: String::new() }];
+        let result = regions[1].result(512, 3, &skipped);
+        assert_eq!(result.bytes_wiped, 3 * 4096);
+        assert_eq!(result.skipped_sectors, 3);
+    }
+}
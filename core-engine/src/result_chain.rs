@@ -0,0 +1,206 @@
+//! Hash chain over the results of a batch
+//!
+//! Certificates protect each wipe on its own, but nothing in a set of them
+//! shows that one was dropped, and a batch may be reported before any
+//! certificate exists. A [`ResultChain`] links the results of a batch in the
+//! order they finished: each [`ChainLink`] holds the SHA-256 of its result and
+//! the hash of the link before it, and the last link hash (the head) goes into
+//! the batch report. Changing a result breaks its link, removing or
+//! reordering one breaks the link after it, and dropping the last one no
+//! longer reaches the head; [`verify`] reports the first such break.
+//!
+//! Results are hashed as compact JSON with object keys in sorted order, so the
+//! digest does not depend on the iteration order of maps inside the result.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::{SafeEraseError, Result};
+use crate::wipe::WipeResult;
+
+/// Domain separation of the first link's predecessor
+const GENESIS_PREFIX: &[u8] = b"safeerase-result-chain-v1:";
+
+/// One result's place in a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainLink {
+    /// Position in the chain, counting from 0
+    pub sequence: u64,
+    pub operation_id: Uuid,
+    /// Hex SHA-256 of the result
+    pub result_sha256: String,
+    /// Hash of the previous link, or of the chain ID for the first one
+    pub previous: String,
+    /// Hex SHA-256 of `previous` and `result_sha256`
+    pub link: String,
+}
+
+/// Running chain of one batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultChain {
+    pub chain_id: Uuid,
+    /// Hash of the last link
+    pub head: String,
+    pub length: u64,
+}
+
+/// Where a chain stopped matching its results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainBreak {
+    /// The result at this position no longer hashes to its link
+    Altered { sequence: u64, operation_id: Uuid },
+    /// The link at this position does not follow the one before it; a result
+    /// was removed or the results were reordered
+    Broken { sequence: u64 },
+    /// The last link is not the recorded head; results were removed from the end
+    HeadMismatch,
+}
+
+impl ResultChain {
+    /// Empty chain; the chain ID is usually the batch ID
+    pub fn new(chain_id: Uuid) -> Self {
+        Self {
+            chain_id,
+            head: genesis(chain_id),
+            length: 0,
+        }
+    }
+    
+    /// Link the next result into the chain
+    pub fn append(&mut self, result: &WipeResult) -> Result<ChainLink> {
+        let result_sha256 = result_digest(result)?;
+        let link = ChainLink {
+            sequence: self.length,
+            operation_id: result.operation_id,
+            link: link_hash(&self.head, &result_sha256),
+            previous: std::mem::take(&mut self.head),
+            result_sha256,
+        };
+        self.head = link.link.clone();
+        self.length += 1;
+        Ok(link)
+    }
+}
+
+/// SHA-256 of a result's JSON with sorted keys
+pub fn result_digest(result: &WipeResult) -> Result<String> {
+    let value = serde_json::to_value(result)
+        .map_err(|e| SafeEraseError::InvalidParameter(format!("Cannot encode wipe result: {}", e)))?;
+    let bytes = serde_json::to_vec(&value)
+        .map_err(|e| SafeEraseError::InvalidParameter(format!("Cannot encode wipe result: {}", e)))?;
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+/// Check results against their links, in chain order, and the recorded head
+pub fn verify(chain_id: Uuid, entries: &[(&WipeResult, &ChainLink)], head: &str) -> std::result::Result<(), ChainBreak> {
+    let mut previous = genesis(chain_id);
+    for (sequence, (result, link)) in entries.iter().enumerate() {
+        let sequence = sequence as u64;
+        if link.sequence != sequence || link.previous != previous || link.link != link_hash(&previous, &link.result_sha256) {
+            return Err(ChainBreak::Broken { sequence });
+        }
+        if result_digest(result).ok().as_deref() != Some(link.result_sha256.as_str()) {
+            return Err(ChainBreak::Altered { sequence, operation_id: link.operation_id });
+        }
+        previous = link.link.clone();
+    }
+    if previous != head {
+        return Err(ChainBreak::HeadMismatch);
+    }
+    Ok(())
+}
+
+fn genesis(chain_id: Uuid) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(GENESIS_PREFIX);
+    hasher.update(chain_id.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn link_hash(previous: &str, result_sha256: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous.as_bytes());
+    hasher.update(result_sha256.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+impl std::fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainBreak::Altered { sequence, operation_id } => {
+                write!(f, "result {} (operation {}) was altered", sequence, operation_id)
+            }
+            ChainBreak::Broken { sequence } => {
+                write!(f, "chain is broken at result {}; a result was removed or reordered", sequence)
+            }
+            ChainBreak::HeadMismatch => write!(f, "chain does not end at the recorded head; results are missing"),
+        }
+    }
+}
+
+impl std::error::Error for ChainBreak {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn result(serial: &str) -> WipeResult {
+        serde_json::from_value(serde_json::json!({
+            "operation_id": Uuid::new_v4(),
+            "device_path": "/dev/sdb",
+            "device_serial": serial,
+            "device_model": "Test Drive",
+            "algorithm": "NIST80088",
+            "options": crate::WipeOptions::default(),
+            "status": "Completed",
+            "started_at": "2024-05-01T12:00:00Z",
+            "completed_at": "2024-05-01T13:00:00Z",
+            "bytes_wiped": 1024,
+            "passes_completed": 1,
+            "verification_requested": false,
+            "verification_passed": null,
+            "hpa_detected": false,
+            "hpa_cleared": false,
+            "dco_detected": false,
+            "dco_cleared": false,
+            "error_message": null,
+            "performance_stats": {
+                "average_speed": 1.0, "peak_speed": 1.0, "total_time": "PT1H", "wipe_time": "PT1H"
+            }
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_chain_detects_changes() {
+        let batch_id = Uuid::new_v4();
+        let results = vec![result("S1"), result("S2"), result("S3")];
+        let mut chain = ResultChain::new(batch_id);
+        let links: Vec<ChainLink> = results.iter().map(|result| chain.append(result).unwrap()).collect();
+        assert_eq!(chain.length, 3);
+        assert_eq!(links[2].link, chain.head);
+        
+        let entries = |results: &[WipeResult], links: &[ChainLink]| -> Vec<(WipeResult, ChainLink)> {
+            results.iter().cloned().zip(links.iter().cloned()).collect()
+        };
+        let check = |pairs: Vec<(WipeResult, ChainLink)>| {
+            let refs: Vec<(&WipeResult, &ChainLink)> = pairs.iter().map(|(result, link)| (result, link)).collect();
+            verify(batch_id, &refs, &chain.head)
+        };
+        assert_eq!(check(entries(&results, &links)), Ok(()));
+        
+        // Survives a trip through JSON
+        let stored: Vec<WipeResult> = serde_json::from_str(&serde_json::to_string(&results).unwrap()).unwrap();
+        assert_eq!(check(entries(&stored, &links)), Ok(()));
+        
+        let mut altered = results.clone();
+        altered[1].bytes_wiped = 2048;
+        assert!(matches!(check(entries(&altered, &links)), Err(ChainBreak::Altered { sequence: 1, .. })));
+        
+        let without_middle = vec![(results[0].clone(), links[0].clone()), (results[2].clone(), links[2].clone())];
+        assert_eq!(check(without_middle), Err(ChainBreak::Broken { sequence: 1 }));
+        
+        assert_eq!(check(entries(&results[..2], &links[..2])), Err(ChainBreak::HeadMismatch));
+    }
+}
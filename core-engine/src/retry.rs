@@ -0,0 +1,184 @@
+//! Retrying operations that fail with recoverable errors
+//!
+//! Device opens, SMART reads and cache flushes can fail transiently while
+//! udev, a filesystem or another tool still holds the device; uploads fail
+//! while the network comes up. [`retry_recoverable`] re-runs such operations
+//! with exponential backoff and jitter as long as the error reports itself as
+//! recoverable, and returns the last error once the attempts are used up.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use rand::Rng;
+use tracing::debug;
+
+use crate::error::SafeEraseError;
+
+/// Errors that can tell whether retrying may succeed
+pub trait Recoverable {
+    fn is_recoverable(&self) -> bool;
+}
+
+impl Recoverable for SafeEraseError {
+    fn is_recoverable(&self) -> bool {
+        SafeEraseError::is_recoverable(self)
+    }
+}
+
+/// Backoff schedule for retried operations
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound for any single delay
+    pub max_delay: Duration,
+    /// Factor applied to the delay after each retry
+    pub multiplier: f64,
+    /// Fraction of each delay (0.0-1.0) that is randomized so concurrent
+    /// wipes hitting the same controller don't retry in lockstep
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Default policy with a different number of attempts
+    pub fn with_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+    
+    /// Policy that runs the operation exactly once
+    pub fn none() -> Self {
+        Self::with_attempts(1)
+    }
+    
+    /// Delay to wait after the given failed attempt (1-based)
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let base = self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let capped = base.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            1.0 - jitter * rand::thread_rng().gen_range(0.0..=1.0)
+        } else {
+            1.0
+        };
+        Duration::from_secs_f64(capped * factor)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+/// Run `operation` until it succeeds, fails with a non-recoverable error, or
+/// the policy's attempts are exhausted
+pub async fn retry_recoverable<T, E, F, Fut>(policy: &RetryPolicy, operation: F) -> Result<T, E>
+where
+    E: Recoverable + Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_when(policy, |error: &E| error.is_recoverable(), operation).await
+}
+
+/// Like [`retry_recoverable`] with a caller-supplied retry predicate
+pub async fn retry_when<T, E, P, F, Fut>(policy: &RetryPolicy, should_retry: P, mut operation: F) -> Result<T, E>
+where
+    E: Display,
+    P: Fn(&E) -> bool,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt < policy.max_attempts && should_retry(&error) => {
+                let delay = policy.delay_after(attempt);
+                debug!(
+                    "Attempt {}/{} failed ({}); retrying in {:?}",
+                    attempt, policy.max_attempts, error, delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_recoverable_errors_are_retried() {
+        let calls = AtomicU32::new(0);
+        let result = retry_recoverable(&fast_policy(3), || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(SafeEraseError::DeviceBusy("/dev/sdb".to_string()))
+            } else {
+                Ok("opened")
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "opened");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_permanent_errors_and_exhaustion() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = retry_recoverable(&fast_policy(5), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(SafeEraseError::DeviceNotFound("/dev/sdz".to_string()))
+        })
+        .await;
+        assert!(matches!(result, Err(SafeEraseError::DeviceNotFound(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = retry_recoverable(&fast_policy(4), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(SafeEraseError::CommunicationTimeout)
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+    
+    #[test]
+    fn test_delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay_after(1), Duration::from_millis(250));
+        assert_eq!(policy.delay_after(3), Duration::from_secs(1));
+        assert_eq!(policy.delay_after(20), policy.max_delay);
+        
+        let jittered = RetryPolicy::default().delay_after(2);
+        assert!(jittered <= Duration::from_millis(500) && jittered >= Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,292 @@
+//! Stratified sampling plans for very large devices
+//!
+//! Uniformly random offsets can cluster and leave wide stretches of a
+//! 16-100 TB device unsampled. A stratified plan always samples the first and
+//! last GiB (where partition tables, boot code and backup GPT headers live),
+//! the area around each partition boundary, and then splits the rest of the
+//! device into equal strata with the remaining samples spread evenly across
+//! them. Results are reported per stratum.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Devices at least this large are verified with a stratified plan
+pub const STRATIFIED_SAMPLING_THRESHOLD: u64 = 16 * 1000 * 1000 * 1000 * 1000;
+
+/// Region of the device a stratum covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StratumKind {
+    /// Start of the device
+    Head,
+    /// End of the device
+    Tail,
+    /// Bytes around the start of a partition that existed before the wipe
+    PartitionBoundary { partition: usize },
+    /// One of the evenly sized strata between head and tail
+    Body { index: usize },
+}
+
+/// Byte range of the device and how many samples it receives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stratum {
+    pub kind: StratumKind,
+    pub start: u64,
+    pub end: u64,
+    pub sample_count: usize,
+}
+
+/// Per-stratum outcome of a verification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StratumResult {
+    pub kind: StratumKind,
+    pub start: u64,
+    pub end: u64,
+    pub samples_tested: usize,
+    pub samples_passed: usize,
+}
+
+/// Shape of a stratified plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct StratifiedSampling {
+    /// Bytes at each end of the device that are always sampled
+    pub edge_bytes: u64,
+    /// Number of equal strata between head and tail
+    pub body_strata: usize,
+    /// Bytes on each side of a partition boundary that are sampled
+    pub boundary_window: u64,
+    /// Share of all samples given to the head and tail together
+    pub edge_share: f64,
+    /// Share of all samples given to partition boundaries together
+    pub boundary_share: f64,
+}
+
+impl Default for StratifiedSampling {
+    fn default() -> Self {
+        Self {
+            edge_bytes: 1024 * 1024 * 1024,
+            body_strata: 64,
+            boundary_window: 1024 * 1024,
+            edge_share: 0.2,
+            boundary_share: 0.1,
+        }
+    }
+}
+
+/// Strata and sample allocation for one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingPlan {
+    pub strata: Vec<Stratum>,
+    sample_size: usize,
+}
+
+impl StratifiedSampling {
+    /// Build a plan for `sample_count` reads of `sample_size` bytes.
+    /// `partition_starts` are byte offsets of partitions present before the wipe.
+    pub fn plan(&self, device_size: u64, sample_count: usize, sample_size: usize, partition_starts: &[u64]) -> SamplingPlan {
+        let sample_size_u64 = sample_size as u64;
+        let mut strata = Vec::new();
+        if device_size < sample_size_u64 || sample_count == 0 {
+            return SamplingPlan { strata, sample_size };
+        }
+        
+        // Head and tail never overlap, even on small devices
+        let edge = self.edge_bytes.min(device_size / 2).max(sample_size_u64.min(device_size));
+        let edge_samples = ((sample_count as f64 * self.edge_share) as usize).max(2);
+        strata.push(Stratum {
+            kind: StratumKind::Head,
+            start: 0,
+            end: edge,
+            sample_count: edge_samples - edge_samples / 2,
+        });
+        strata.push(Stratum {
+            kind: StratumKind::Tail,
+            start: device_size - edge,
+            end: device_size,
+            sample_count: edge_samples / 2,
+        });
+        
+        let boundaries: Vec<(usize, u64)> = partition_starts
+            .iter()
+            .enumerate()
+            .filter(|(_, &start)| start > 0 && start < device_size)
+            .map(|(partition, &start)| (partition + 1, start))
+            .collect();
+        let mut allocated = edge_samples;
+        if !boundaries.is_empty() {
+            let per_boundary = ((sample_count as f64 * self.boundary_share) as usize / boundaries.len()).max(1);
+            for (partition, start) in boundaries {
+                strata.push(Stratum {
+                    kind: StratumKind::PartitionBoundary { partition },
+                    start: start.saturating_sub(self.boundary_window),
+                    end: (start + self.boundary_window).min(device_size),
+                    sample_count: per_boundary,
+                });
+                allocated += per_boundary;
+            }
+        }
+        
+        let body_start = edge;
+        let body_end = device_size - edge;
+        let remaining = sample_count.saturating_sub(allocated);
+        if body_end > body_start && remaining > 0 {
+            let body_len = body_end - body_start;
+            let count = self.body_strata.clamp(1, remaining);
+            for index in 0..count {
+                let start = body_start + body_len * index as u64 / count as u64;
+                let end = body_start + body_len * (index as u64 + 1) / count as u64;
+                strata.push(Stratum {
+                    kind: StratumKind::Body { index },
+                    start,
+                    end,
+                    sample_count: remaining / count + usize::from(index < remaining % count),
+                });
+            }
+        }
+        
+        SamplingPlan { strata, sample_size }
+    }
+}
+
+impl SamplingPlan {
+    /// Sample offsets paired with the index of their stratum.
+    ///
+    /// Each stratum is split into one cell per sample and one offset is drawn
+    /// from every cell, so samples cannot cluster inside a stratum. Offsets
+    /// are aligned to the sample size.
+    pub fn sample_offsets(&self) -> Vec<(usize, u64)> {
+        let sample_size = self.sample_size.max(1) as u64;
+        let mut rng = rand::thread_rng();
+        let mut offsets = Vec::new();
+        
+        for (index, stratum) in self.strata.iter().enumerate() {
+            let first_aligned = stratum.start.div_ceil(sample_size) * sample_size;
+            let last_aligned = stratum.end.saturating_sub(sample_size) / sample_size * sample_size;
+            // Strata narrower than one aligned sample are read from their start
+            let (first, last, align) = if first_aligned <= last_aligned {
+                (first_aligned, last_aligned, sample_size)
+            } else {
+                (stratum.start, stratum.start, 1)
+            };
+            let span = last - first;
+            let count = stratum.sample_count as u64;
+            for cell in 0..count {
+                let cell_start = first + span * cell / count;
+                let cell_end = first + span * (cell + 1) / count;
+                let offset = rng.gen_range(cell_start..=cell_end);
+                offsets.push((index, (offset / align * align).max(first)));
+            }
+        }
+        
+        offsets.sort_by_key(|&(_, offset)| offset);
+        offsets
+    }
+    
+    /// Empty per-stratum results to be filled in during verification
+    pub fn results(&self) -> Vec<StratumResult> {
+        self.strata
+            .iter()
+            .map(|stratum| StratumResult {
+                kind: stratum.kind,
+                start: stratum.start,
+                end: stratum.end,
+                samples_tested: 0,
+                samples_passed: 0,
+            })
+            .collect()
+    }
+}
+
+impl StratumResult {
+    pub fn success_rate(&self) -> f64 {
+        if self.samples_tested == 0 {
+            0.0
+        } else {
+            self.samples_passed as f64 / self.samples_tested as f64
+        }
+    }
+}
+
+/// Byte offsets where the device's partitions start, as last seen by the
+/// kernel. Partitions are usually still listed right after a wipe because
+/// the kernel keeps the old table until it is re-read.
+pub fn partition_starts(device_path: &str) -> Vec<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let name = device_path.trim_start_matches("/dev/");
+        let Ok(entries) = std::fs::read_dir(format!("/sys/block/{}", name)) else {
+            return Vec::new();
+        };
+        let mut starts: Vec<u64> = entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(name))
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("start")).ok())
+            // sysfs reports 512-byte sectors regardless of the logical sector size
+            .filter_map(|start| start.trim().parse::<u64>().ok())
+            .map(|sectors| sectors * 512)
+            .collect();
+        starts.sort_unstable();
+        starts
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = device_path;
+        Vec::new()
+    }
+}
+
+impl std::fmt::Display for StratumKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StratumKind::Head => write!(f, "Head"),
+            StratumKind::Tail => write!(f, "Tail"),
+            StratumKind::PartitionBoundary { partition } => write!(f, "Partition {} boundary", partition),
+            StratumKind::Body { index } => write!(f, "Body stratum {}", index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TB: u64 = 1000 * 1000 * 1000 * 1000;
+    const GIB: u64 = 1024 * 1024 * 1024;
+    
+    #[test]
+    fn test_plan_covers_edges_boundaries_and_body() {
+        let sampling = StratifiedSampling::default();
+        let plan = sampling.plan(20 * TB, 1000, 4096, &[GIB * 2, 10 * TB]);
+        
+        assert_eq!(plan.strata[0].kind, StratumKind::Head);
+        assert_eq!(plan.strata[1].kind, StratumKind::Tail);
+        assert_eq!(plan.strata[1].end, 20 * TB);
+        let boundaries = plan
+            .strata
+            .iter()
+            .filter(|s| matches!(s.kind, StratumKind::PartitionBoundary { .. }))
+            .count();
+        assert_eq!(boundaries, 2);
+        let body: Vec<&Stratum> = plan.strata.iter().filter(|s| matches!(s.kind, StratumKind::Body { .. })).collect();
+        assert_eq!(body.len(), 64);
+        assert_eq!(body[0].start, GIB);
+        assert_eq!(body[63].end, 20 * TB - GIB);
+        assert_eq!(plan.strata.iter().map(|s| s.sample_count).sum::<usize>(), 1000);
+    }
+    
+    #[test]
+    fn test_offsets_stay_inside_their_strata() {
+        let plan = StratifiedSampling::default().plan(17 * TB, 500, 4096, &[]);
+        let offsets = plan.sample_offsets();
+        assert_eq!(offsets.len(), 500);
+        for (index, offset) in offsets {
+            let stratum = &plan.strata[index];
+            assert!(offset >= stratum.start && offset + 4096 <= stratum.end, "{:?} {}", stratum, offset);
+            assert_eq!(offset % 4096, 0);
+        }
+        
+        // Tiny devices still get a usable plan
+        let small = StratifiedSampling::default().plan(8192, 10, 4096, &[]);
+        assert!(small.sample_offsets().iter().all(|&(_, offset)| offset + 4096 <= 8192));
+    }
+}
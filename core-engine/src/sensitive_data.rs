@@ -0,0 +1,335 @@
+//! Pre-wipe scan for recognizable sensitive content
+//!
+//! Before anything is written the engine can sample the device and record
+//! what kind of data the media held: filesystems, common document formats and
+//! simple PII patterns such as payment card numbers and IBANs. Only counts
+//! and format names are kept; the matched bytes never leave the scan. The
+//! resulting risk level documents what was destroyed.
+
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::device::Device;
+use crate::error::{ErrorContext, Result, ResultExt};
+use crate::platform;
+use crate::sampling;
+
+/// Size of each sampled read
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Bytes read at the start of the device and of each partition to find filesystem signatures
+const HEADER_SIZE: usize = 128 * 1024;
+
+/// Sensitivity of the data found on the media
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    /// Nothing recognizable was found
+    Low,
+    /// Filesystems or documents were found
+    Medium,
+    /// Personal or financial identifiers were found
+    High,
+}
+
+/// Counts of what the pre-wipe scan recognized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensitiveDataScan {
+    pub scanned_at: DateTime<Utc>,
+    pub samples_scanned: usize,
+    pub bytes_scanned: u64,
+    /// Filesystems and partition tables found at the device or partition starts
+    pub filesystems: Vec<String>,
+    /// Number of sectors starting with each document signature
+    pub document_signatures: BTreeMap<String, usize>,
+    /// Digit sequences that pass the Luhn check with a card network prefix
+    pub payment_card_numbers: usize,
+    /// Strings in IBAN format with a valid check digit
+    pub ibans: usize,
+    pub risk_level: RiskLevel,
+}
+
+/// Accumulates findings over sampled blocks
+#[derive(Debug, Clone, Default)]
+pub struct ScanFindings {
+    samples_scanned: usize,
+    bytes_scanned: u64,
+    filesystems: Vec<String>,
+    document_signatures: BTreeMap<String, usize>,
+    payment_card_numbers: usize,
+    ibans: usize,
+}
+
+/// Signatures at fixed offsets from the start of a device or partition
+const FILESYSTEM_SIGNATURES: &[(&str, usize, &[u8])] = &[
+    ("GPT", 512, b"EFI PART"),
+    ("GPT", 4096, b"EFI PART"),
+    ("NTFS", 3, b"NTFS    "),
+    ("exFAT", 3, b"EXFAT   "),
+    ("FAT32", 82, b"FAT32   "),
+    ("FAT", 54, b"FAT1"),
+    ("ext2/3/4", 1080, &[0x53, 0xEF]),
+    ("XFS", 0, b"XFSB"),
+    ("APFS", 32, b"NXSB"),
+    ("Btrfs", 0x10040, b"_BHRfS_M"),
+    ("LUKS", 0, b"LUKS\xBA\xBE"),
+];
+
+/// Signatures at the start of a file; files usually begin on a sector boundary
+const DOCUMENT_SIGNATURES: &[(&str, &[u8])] = &[
+    ("PDF", b"%PDF-"),
+    ("ZIP/Office Open XML", b"PK\x03\x04"),
+    ("OLE2/Legacy Office", &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]),
+    ("JPEG", &[0xFF, 0xD8, 0xFF]),
+    ("PNG", b"\x89PNG\r\n\x1a\n"),
+    ("SQLite", b"SQLite format 3\0"),
+    ("RTF", b"{\\rtf"),
+];
+
+impl ScanFindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Look for filesystem and partition table signatures in the first bytes of a device or partition
+    pub fn scan_header(&mut self, data: &[u8]) {
+        let mut found: Vec<&str> = FILESYSTEM_SIGNATURES
+            .iter()
+            .filter(|(_, offset, signature)| {
+                data.get(*offset..offset + signature.len()) == Some(*signature)
+            })
+            .map(|(name, _, _)| *name)
+            .collect();
+        // Volume boot records carry the same boot signature, so a bare one means an MBR
+        if found.is_empty() && data.get(510..512) == Some(&[0x55u8, 0xAA][..]) {
+            found.push("MBR");
+        }
+        for name in found {
+            if !self.filesystems.iter().any(|known| known == name) {
+                self.filesystems.push(name.to_string());
+            }
+        }
+    }
+    
+    /// Look for document signatures and PII patterns in a sampled block
+    pub fn scan_block(&mut self, data: &[u8]) {
+        self.samples_scanned += 1;
+        self.bytes_scanned += data.len() as u64;
+        
+        for sector in data.chunks(512) {
+            for (name, signature) in DOCUMENT_SIGNATURES {
+                if sector.starts_with(signature) {
+                    *self.document_signatures.entry(name.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+        self.payment_card_numbers += count_payment_card_numbers(data);
+        self.ibans += count_ibans(data);
+    }
+    
+    pub fn finish(self) -> SensitiveDataScan {
+        let risk_level = if self.payment_card_numbers > 0 || self.ibans > 0 {
+            RiskLevel::High
+        } else if !self.filesystems.is_empty() || !self.document_signatures.is_empty() {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+        SensitiveDataScan {
+            scanned_at: Utc::now(),
+            samples_scanned: self.samples_scanned,
+            bytes_scanned: self.bytes_scanned,
+            filesystems: self.filesystems,
+            document_signatures: self.document_signatures,
+            payment_card_numbers: self.payment_card_numbers,
+            ibans: self.ibans,
+            risk_level,
+        }
+    }
+}
+
+impl SensitiveDataScan {
+    /// One-line description of the findings for certificates and logs
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.filesystems.is_empty() {
+            parts.push(format!("filesystems: {}", self.filesystems.join(", ")));
+        }
+        if !self.document_signatures.is_empty() {
+            let documents: Vec<String> = self
+                .document_signatures
+                .iter()
+                .map(|(name, count)| format!("{} x{}", name, count))
+                .collect();
+            parts.push(format!("documents: {}", documents.join(", ")));
+        }
+        if self.payment_card_numbers > 0 {
+            parts.push(format!("{} payment card numbers", self.payment_card_numbers));
+        }
+        if self.ibans > 0 {
+            parts.push(format!("{} IBANs", self.ibans));
+        }
+        if parts.is_empty() {
+            "no recognizable content".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// Sample the device for sensitive content without modifying it
+pub async fn scan_device(device: &Device, sample_count: usize) -> Result<SensitiveDataScan> {
+    let size = device.get_info().await?.size;
+    let sector_size = device.capabilities().logical_sector_size.max(1) as u64;
+    let mut findings = ScanFindings::new();
+    
+    info!("Scanning {} for sensitive content before wiping", device.path());
+    
+    // Filesystem signatures live at the start of the device and of each partition
+    let mut header_offsets = vec![0];
+    header_offsets.extend(sampling::partition_starts(device.path()));
+    for offset in header_offsets {
+        if offset + HEADER_SIZE as u64 > size {
+            continue;
+        }
+        let buffer = read_at(device, offset, HEADER_SIZE, sector_size).await?;
+        findings.scan_header(&buffer);
+    }
+    
+    let max_sector = size.saturating_sub(SAMPLE_SIZE as u64) / sector_size;
+    let offsets: Vec<u64> = {
+        let mut rng = rand::thread_rng();
+        (0..sample_count).map(|_| rng.gen_range(0..=max_sector) * sector_size).collect()
+    };
+    for offset in offsets {
+        let buffer = read_at(device, offset, SAMPLE_SIZE, sector_size).await?;
+        findings.scan_block(&buffer);
+    }
+    
+    let scan = findings.finish();
+    debug!("Sensitive data scan of {}: {}", device.path(), scan.summary());
+    Ok(scan)
+}
+
+async fn read_at(device: &Device, offset: u64, length: usize, sector_size: u64) -> Result<Vec<u8>> {
+    let start_lba = offset / sector_size;
+    let mut buffer = vec![0u8; length];
+    platform::read_sectors(device.handle(), start_lba, &mut buffer)
+        .await
+        .with_error_context(|| ErrorContext::new().lba(start_lba))?;
+    Ok(buffer)
+}
+
+/// Count 13-19 digit runs that pass the Luhn check and start with a card network prefix
+fn count_payment_card_numbers(data: &[u8]) -> usize {
+    digit_runs(data)
+        .filter(|digits| (13..=19).contains(&digits.len()))
+        .filter(|digits| matches!(digits[0], b'3' | b'4' | b'5' | b'6'))
+        // Long runs of one digit are padding, not card numbers
+        .filter(|digits| digits.iter().any(|&d| d != digits[0]))
+        .filter(|digits| luhn_valid(digits))
+        .count()
+}
+
+/// Maximal runs of ASCII digits
+fn digit_runs(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    data.split(|b| !b.is_ascii_digit()).filter(|run| !run.is_empty())
+}
+
+fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            let value = (d - b'0') as u32;
+            if i % 2 == 1 {
+                let doubled = value * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                value
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Count IBAN-formatted tokens (country code, check digits, 11-30 alphanumerics) with a valid checksum
+fn count_ibans(data: &[u8]) -> usize {
+    data.split(|b| !b.is_ascii_alphanumeric())
+        .filter(|token| (15..=34).contains(&token.len()))
+        .filter(|token| {
+            token[..2].iter().all(u8::is_ascii_uppercase)
+                && token[2..4].iter().all(u8::is_ascii_digit)
+                && token[4..].iter().all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+        })
+        .filter(|token| iban_checksum_valid(token))
+        .count()
+}
+
+/// ISO 13616 mod-97 check
+fn iban_checksum_valid(iban: &[u8]) -> bool {
+    let rearranged = iban[4..].iter().chain(&iban[..4]);
+    let mut remainder: u32 = 0;
+    for &b in rearranged {
+        let value = if b.is_ascii_digit() { (b - b'0') as u32 } else { (b - b'A') as u32 + 10 };
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+impl std::fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskLevel::Low => write!(f, "Low"),
+            RiskLevel::Medium => write!(f, "Medium"),
+            RiskLevel::High => write!(f, "High"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_pii_patterns() {
+        let text = b"card 4111111111111111, not 4111111111111112 or 0000000000000000; \
+                     iban GB82WEST12345698765432 and bad GB00WEST12345698765432";
+        assert_eq!(count_payment_card_numbers(text), 1);
+        assert_eq!(count_ibans(text), 1);
+        
+        let mut findings = ScanFindings::new();
+        findings.scan_block(text);
+        let scan = findings.finish();
+        assert_eq!(scan.risk_level, RiskLevel::High);
+        // Only counts are recorded, never the matched values
+        let json = serde_json::to_string(&scan).unwrap();
+        assert!(!json.contains("4111111111111111") && !json.contains("GB82WEST"));
+    }
+    
+    #[test]
+    fn test_filesystem_and_document_signatures() {
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[3..11].copy_from_slice(b"NTFS    ");
+        header[510..512].copy_from_slice(&[0x55, 0xAA]);
+        let mut block = vec![0u8; 4096];
+        block[1024..1029].copy_from_slice(b"%PDF-");
+        
+        let mut findings = ScanFindings::new();
+        findings.scan_header(&header);
+        findings.scan_block(&block);
+        let scan = findings.finish();
+        assert_eq!(scan.filesystems, ["NTFS"]);
+        assert_eq!(scan.document_signatures.get("PDF"), Some(&1));
+        assert_eq!(scan.risk_level, RiskLevel::Medium);
+        assert_eq!(ScanFindings::new().finish().risk_level, RiskLevel::Low);
+    }
+}
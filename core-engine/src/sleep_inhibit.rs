@@ -0,0 +1,191 @@
+//! Keeping the host awake during wipes
+//!
+//! Laptops used as wipe stations suspend when left alone, and a wipe that
+//! sleeps halfway loses its timing and whatever the drive had cached, and on
+//! resume often its device as well. While any wipe or verification runs the
+//! engine holds a [`SleepGuard`]; the first guard asks the operating system
+//! not to sleep and the last one to be dropped allows it again. Linux is
+//! asked through `systemd-inhibit`, macOS through `caffeinate`, which takes
+//! the IOKit power assertions, and Windows through `SetThreadExecutionState`.
+//! Where none of these is available the wipe runs anyway and a warning is
+//! logged.
+
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Hands out guards and holds the system awake while any is alive
+#[derive(Debug, Clone, Default)]
+pub struct SleepInhibitor {
+    state: Arc<Mutex<State>>,
+}
+
+/// Keeps the system awake until dropped
+#[derive(Debug)]
+pub struct SleepGuard {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    guards: usize,
+    inhibition: Option<imp::Inhibition>,
+}
+
+impl SleepInhibitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Keep the system awake until the guard is dropped
+    pub fn acquire(&self) -> SleepGuard {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.guards += 1;
+        if state.guards == 1 {
+            match imp::Inhibition::start() {
+                Ok(inhibition) => {
+                    debug!("Inhibiting system sleep while wipes run");
+                    state.inhibition = Some(inhibition);
+                }
+                Err(e) => warn!("Cannot keep the system awake; it may suspend during the wipe: {}", e),
+            }
+        }
+        SleepGuard { state: Arc::clone(&self.state) }
+    }
+    
+    /// Whether the system is currently held awake
+    pub fn is_active(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).inhibition.is_some()
+    }
+    
+    /// Guards currently alive
+    pub fn guards(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).guards
+    }
+}
+
+impl Drop for SleepGuard {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.guards -= 1;
+        if state.guards == 0 && state.inhibition.take().is_some() {
+            debug!("Allowing system sleep again");
+        }
+    }
+}
+
+#[cfg(all(feature = "device-access", target_os = "linux"))]
+mod imp {
+    use crate::error::Result;
+    use crate::platform::tools::Tool;
+    
+    /// `systemd-inhibit` holding a block lock for as long as its child runs;
+    /// the child also exits with this process, so a crash cannot leave the lock behind
+    #[derive(Debug)]
+    pub(super) struct Inhibition {
+        _child: tokio::process::Child,
+    }
+    
+    impl Inhibition {
+        pub(super) fn start() -> Result<Self> {
+            let child = Tool::SystemdInhibit
+                .command()?
+                .args(["--what=sleep:idle:handle-lid-switch", "--who=SafeErase", "--why=Wiping storage devices", "--mode=block"])
+                .args(["tail", "-f", "/dev/null", &format!("--pid={}", std::process::id())])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .spawn()?;
+            Ok(Self { _child: child })
+        }
+    }
+}
+
+#[cfg(all(feature = "device-access", target_os = "macos"))]
+mod imp {
+    use crate::error::Result;
+    
+    /// `caffeinate` holding idle and system sleep assertions; `-w` ends it with this process
+    #[derive(Debug)]
+    pub(super) struct Inhibition {
+        _child: tokio::process::Child,
+    }
+    
+    impl Inhibition {
+        pub(super) fn start() -> Result<Self> {
+            let child = tokio::process::Command::new("/usr/bin/caffeinate")
+                .args(["-i", "-s", "-w", &std::process::id().to_string()])
+                .kill_on_drop(true)
+                .spawn()?;
+            Ok(Self { _child: child })
+        }
+    }
+}
+
+#[cfg(all(feature = "device-access", target_os = "windows"))]
+mod imp {
+    use std::sync::mpsc;
+    use winapi::um::winbase::SetThreadExecutionState;
+    use winapi::um::winnt::{ES_CONTINUOUS, ES_SYSTEM_REQUIRED};
+    
+    use crate::error::{SafeEraseError, Result};
+    
+    /// The execution state belongs to the thread that set it, so a dedicated
+    /// thread sets it and clears it once the sender is dropped
+    #[derive(Debug)]
+    pub(super) struct Inhibition {
+        _release: mpsc::Sender<()>,
+    }
+    
+    impl Inhibition {
+        pub(super) fn start() -> Result<Self> {
+            let (release, released) = mpsc::channel::<()>();
+            let (started, result) = mpsc::channel();
+            std::thread::Builder::new()
+                .name("sleep-inhibit".to_string())
+                .spawn(move || {
+                    // SAFETY: only changes the power requirements of this thread
+                    let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+                    let _ = started.send(previous != 0);
+                    let _ = released.recv();
+                    // SAFETY: as above
+                    unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+                })?;
+            match result.recv() {
+                Ok(true) => Ok(Self { _release: release }),
+                _ => Err(SafeEraseError::SystemCommandFailed("SetThreadExecutionState failed".to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "device-access", any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+mod imp {
+    use crate::error::{SafeEraseError, Result};
+    
+    #[derive(Debug)]
+    pub(super) struct Inhibition;
+    
+    impl Inhibition {
+        pub(super) fn start() -> Result<Self> {
+            Err(SafeEraseError::UnsupportedPlatform("sleep inhibition needs a device backend".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_guards_are_counted() {
+        let inhibitor = SleepInhibitor::new();
+        let first = inhibitor.acquire();
+        let second = inhibitor.clone().acquire();
+        assert_eq!(inhibitor.guards(), 2);
+        drop(first);
+        assert_eq!(inhibitor.guards(), 1);
+        drop(second);
+        assert_eq!(inhibitor.guards(), 0);
+        assert!(!inhibitor.is_active());
+    }
+}
@@ -0,0 +1,196 @@
+//! SMART health monitoring during a wipe
+//!
+//! Overwriting a drive that is failing takes hours and ends in a failed wipe
+//! anyway. When [`SmartMonitor`] is set in the wipe options, SMART counters
+//! are read periodically and compared with a baseline taken before the first
+//! pass. Growth beyond the tolerated delta is recorded in the result and,
+//! if requested, ends the wipe.
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+use crate::device::Device;
+use crate::platform::{self, SmartInfo};
+
+/// When to react to a degrading drive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SmartMonitor {
+    /// Time between SMART reads
+    #[serde(with = "crate::time_format::duration")]
+    pub interval: Duration,
+    /// Increase of any counter tolerated over the baseline
+    pub max_increase: u64,
+    /// Stop the wipe once the increase is exceeded, rather than only recording it
+    pub abort: bool,
+}
+
+/// SMART counters that indicate failing media
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartCounter {
+    ReallocatedSectors,
+    PendingSectors,
+    /// NVMe media and data integrity errors
+    MediaErrors,
+}
+
+/// The counter that grew too much during the wipe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmartDegradation {
+    pub counter: SmartCounter,
+    pub baseline: u64,
+    pub current: u64,
+    pub detected_at: DateTime<Utc>,
+    /// The wipe was stopped because of it
+    pub aborted: bool,
+}
+
+/// Monitoring task of one wipe; see [`SmartWatch::finish`]
+#[derive(Debug)]
+pub(crate) struct SmartWatch {
+    stop: CancellationToken,
+    task: JoinHandle<Option<SmartDegradation>>,
+}
+
+impl Default for SmartMonitor {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            max_increase: 10,
+            abort: true,
+        }
+    }
+}
+
+impl SmartMonitor {
+    /// First counter that rose by more than `max_increase` over the baseline
+    pub fn check(&self, baseline: &SmartInfo, current: &SmartInfo) -> Option<(SmartCounter, u64, u64)> {
+        let counters = [
+            (SmartCounter::ReallocatedSectors, baseline.reallocated_sectors, current.reallocated_sectors),
+            (SmartCounter::PendingSectors, baseline.pending_sectors, current.pending_sectors),
+            (SmartCounter::MediaErrors, baseline.media_errors, current.media_errors),
+        ];
+        counters.into_iter().find_map(|(counter, baseline, current)| match (baseline, current) {
+            (Some(baseline), Some(current)) if current.saturating_sub(baseline) > self.max_increase => {
+                Some((counter, baseline, current))
+            }
+            _ => None,
+        })
+    }
+    
+    /// Poll the device until the wipe ends; cancels `wipe_token` on degradation when aborting
+    pub(crate) fn watch(&self, device: Arc<Device>, baseline: SmartInfo, wipe_token: CancellationToken) -> SmartWatch {
+        let monitor = self.clone();
+        let stop = CancellationToken::new();
+        let stopped = stop.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = stopped.cancelled() => return None,
+                    _ = tokio::time::sleep(monitor.interval) => {}
+                }
+                let current = match platform::get_smart_info(device.handle()).await {
+                    Ok(current) => current,
+                    Err(e) => {
+                        debug!("Cannot read SMART data of {}: {}", device.path(), e);
+                        continue;
+                    }
+                };
+                let Some((counter, baseline, current)) = monitor.check(&baseline, &current) else {
+                    continue;
+                };
+                let degradation = SmartDegradation {
+                    counter,
+                    baseline,
+                    current,
+                    detected_at: Utc::now(),
+                    aborted: monitor.abort,
+                };
+                if monitor.abort {
+                    error!("Aborting wipe of {}: {}", device.path(), degradation);
+                    wipe_token.cancel();
+                } else {
+                    warn!("Drive {} is degrading: {}", device.path(), degradation);
+                }
+                return Some(degradation);
+            }
+        });
+        SmartWatch { stop, task }
+    }
+}
+
+impl SmartWatch {
+    /// Stop monitoring and return what was detected
+    pub(crate) async fn finish(self) -> Option<SmartDegradation> {
+        self.stop.cancel();
+        self.task.await.ok().flatten()
+    }
+}
+
+impl std::fmt::Display for SmartCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmartCounter::ReallocatedSectors => write!(f, "reallocated sectors"),
+            SmartCounter::PendingSectors => write!(f, "pending sectors"),
+            SmartCounter::MediaErrors => write!(f, "media errors"),
+        }
+    }
+}
+
+impl std::fmt::Display for SmartDegradation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} rose from {} to {}", self.counter, self.baseline, self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn smart(reallocated: Option<u64>, pending: Option<u64>, media_errors: Option<u64>) -> SmartInfo {
+        SmartInfo {
+            reallocated_sectors: reallocated,
+            pending_sectors: pending,
+            media_errors,
+            ..SmartInfo::default()
+        }
+    }
+    
+    #[test]
+    fn test_check_reports_first_counter_over_delta() {
+        let monitor = SmartMonitor { max_increase: 5, ..SmartMonitor::default() };
+        let baseline = smart(Some(10), Some(0), None);
+        
+        assert_eq!(monitor.check(&baseline, &smart(Some(15), Some(3), None)), None);
+        assert_eq!(
+            monitor.check(&baseline, &smart(Some(12), Some(8), None)),
+            Some((SmartCounter::PendingSectors, 0, 8))
+        );
+        // A counter that cannot be read is never a trigger
+        assert_eq!(monitor.check(&baseline, &smart(None, None, Some(100))), None);
+    }
+    
+    #[test]
+    fn test_degradation_serialization() {
+        let degradation = SmartDegradation {
+            counter: SmartCounter::ReallocatedSectors,
+            baseline: 8,
+            current: 40,
+            detected_at: Utc::now(),
+            aborted: true,
+        };
+        assert_eq!(degradation.to_string(), "reallocated sectors rose from 8 to 40");
+        let json = serde_json::to_value(&degradation).unwrap();
+        assert_eq!(json["counter"], "reallocated_sectors");
+        
+        let monitor: SmartMonitor = serde_json::from_str(r#"{"interval": "PT1M"}"#).unwrap();
+        assert_eq!(monitor.interval, Duration::from_secs(60));
+        assert!(monitor.abort);
+    }
+}
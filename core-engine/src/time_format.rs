@@ -0,0 +1,227 @@
+//! Serialized form of durations and timestamps
+//!
+//! Durations are written as ISO-8601 duration strings (`PT1H2M3.5S`) rather
+//! than serde's default `{"secs": .., "nanos": ..}` object. Reading accepts the
+//! ISO-8601 form, a plain number of seconds, and the old object form, so
+//! results and certificates written by earlier releases still load.
+//!
+//! Timestamps are RFC 3339 in UTC with a `Z` suffix, which is what `chrono`
+//! already serializes for `DateTime<Utc>`; [`format_timestamp`] produces the
+//! same text for places that build strings by hand.
+//!
+//! Use the submodules with `#[serde(with = "...")]`:
+//!
+//! ```ignore
+//! #[serde(with = "crate::time_format::duration")]
+//! pub total_time: Duration,
+//! #[serde(default, with = "crate::time_format::option_duration")]
+//! pub duration: Option<Duration>,
+//! ```
+
+use std::time::Duration;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+
+/// Format a duration as an ISO-8601 duration (`PT90S` is written `PT1M30S`)
+pub fn format_duration(duration: Duration) -> String {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+    let nanos = duration.subsec_nanos();
+    
+    let mut output = String::from("PT");
+    if hours > 0 {
+        output.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        output.push_str(&format!("{}M", minutes));
+    }
+    if seconds > 0 || nanos > 0 || (hours == 0 && minutes == 0) {
+        output.push_str(&seconds.to_string());
+        if nanos > 0 {
+            let fraction = format!("{:09}", nanos);
+            output.push('.');
+            output.push_str(fraction.trim_end_matches('0'));
+        }
+        output.push('S');
+    }
+    output
+}
+
+/// Parse an ISO-8601 duration with day, hour, minute and (fractional) second parts
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let rest = text.trim().strip_prefix('P')?;
+    let (date_part, time_part) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return None,
+        None => (rest, None),
+    };
+    if date_part.is_empty() && time_part.is_none() {
+        return None;
+    }
+    
+    let mut total = Duration::ZERO;
+    if !date_part.is_empty() {
+        let days: u64 = date_part.strip_suffix('D')?.parse().ok()?;
+        total += Duration::from_secs(days.checked_mul(86_400)?);
+    }
+    
+    let mut remaining = time_part.unwrap_or("");
+    let mut last_unit = 0;
+    while !remaining.is_empty() {
+        let end = remaining.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let (number, unit) = (&remaining[..end], remaining[end..].chars().next()?);
+        let (rank, seconds_per_unit) = match unit {
+            'H' => (1, 3600),
+            'M' => (2, 60),
+            'S' => (3, 1),
+            _ => return None,
+        };
+        // Units must appear once each, in order
+        if rank <= last_unit {
+            return None;
+        }
+        last_unit = rank;
+        
+        if unit == 'S' {
+            total += parse_seconds(number)?;
+        } else {
+            let value: u64 = number.parse().ok()?;
+            total += Duration::from_secs(value.checked_mul(seconds_per_unit)?);
+        }
+        remaining = &remaining[end + 1..];
+    }
+    Some(total)
+}
+
+/// Format a timestamp as RFC 3339 UTC (`2024-05-01T12:00:00.250Z`)
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn parse_seconds(number: &str) -> Option<Duration> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<9}", fraction).parse().ok()?
+    };
+    Some(Duration::new(whole.parse().ok()?, nanos))
+}
+
+/// Accepted serialized forms of a duration
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationRepr {
+    Iso(String),
+    Seconds(f64),
+    Legacy { secs: u64, nanos: u32 },
+}
+
+impl DurationRepr {
+    fn into_duration<E: serde::de::Error>(self) -> Result<Duration, E> {
+        match self {
+            DurationRepr::Iso(text) => parse_duration(&text)
+                .ok_or_else(|| E::custom(format!("invalid ISO-8601 duration '{}'", text))),
+            DurationRepr::Seconds(seconds) => Duration::try_from_secs_f64(seconds)
+                .map_err(|_| E::custom(format!("invalid duration of {} seconds", seconds))),
+            DurationRepr::Legacy { secs, nanos } => Ok(Duration::new(secs, nanos)),
+        }
+    }
+}
+
+/// `#[serde(with)]` module for `Duration` fields
+pub mod duration {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+    
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(*duration))
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        DurationRepr::deserialize(deserializer)?.into_duration()
+    }
+}
+
+/// `#[serde(with)]` module for `Option<Duration>` fields
+pub mod option_duration {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+    
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&format_duration(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Option::<DurationRepr>::deserialize(deserializer)?
+            .map(DurationRepr::into_duration)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Timed {
+        #[serde(with = "duration")]
+        total: Duration,
+        #[serde(default, with = "option_duration")]
+        remaining: Option<Duration>,
+    }
+    
+    #[test]
+    fn test_duration_round_trip() {
+        let cases = [
+            (Duration::ZERO, "PT0S"),
+            (Duration::from_millis(1500), "PT1.5S"),
+            (Duration::from_secs(90), "PT1M30S"),
+            (Duration::from_secs(3600), "PT1H"),
+            (Duration::new(93_784, 5), "PT26H3M4.000000005S"),
+        ];
+        for (duration, text) in cases {
+            assert_eq!(format_duration(duration), text);
+            assert_eq!(parse_duration(text), Some(duration));
+        }
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::from_secs(93_600)));
+        for invalid in ["", "P", "PT", "1H", "PT1S1H", "PT1.5H", "PT5X"] {
+            assert_eq!(parse_duration(invalid), None, "{}", invalid);
+        }
+    }
+    
+    #[test]
+    fn test_serde_accepts_legacy_forms() {
+        let value = Timed {
+            total: Duration::from_millis(2250),
+            remaining: Some(Duration::from_secs(60)),
+        };
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"total":"PT2.25S","remaining":"PT1M"}"#);
+        assert_eq!(serde_json::from_str::<Timed>(&json).unwrap(), value);
+        
+        let legacy: Timed =
+            serde_json::from_str(r#"{"total":{"secs":2,"nanos":250000000},"remaining":60}"#).unwrap();
+        assert_eq!(legacy, value);
+        
+        let missing: Timed = serde_json::from_str(r#"{"total":0.5}"#).unwrap();
+        assert_eq!(missing.remaining, None);
+        assert!(serde_json::from_str::<Timed>(r#"{"total":-1}"#).is_err());
+    }
+    
+    #[test]
+    fn test_timestamps_use_utc_suffix() {
+        let timestamp: DateTime<Utc> = "2024-05-01T14:00:00.250+02:00".parse().unwrap();
+        assert_eq!(format_timestamp(&timestamp), "2024-05-01T12:00:00.250Z");
+        assert_eq!(serde_json::to_value(timestamp).unwrap(), "2024-05-01T12:00:00.250Z");
+    }
+}
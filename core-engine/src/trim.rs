@@ -0,0 +1,160 @@
+//! Verification of TRIM/discard-based sanitization
+//!
+//! Discarding every block only sanitizes a device if later reads cannot
+//! return the old data. Devices advertise this through RZAT (ATA) or the
+//! DLFEAT read behavior (NVMe); without that promise the discard is recorded
+//! but not counted, and an overwrite or crypto erase is recommended instead.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::device::{Device, TrimReadBehavior};
+use crate::error::{ErrorContext, Result, ResultExt};
+use crate::platform;
+
+/// Size of each sampled read
+const SAMPLE_SIZE: usize = 4096;
+
+/// Outcome of checking discarded ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimOutcome {
+    /// Every sampled range read back as zeros
+    Verified,
+    /// The device does not guarantee zeros after TRIM; the discard does not count as sanitization
+    NotDeterministic,
+    /// Some sampled ranges still returned data
+    DataRemaining,
+}
+
+/// Result of verifying a discard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrimVerification {
+    pub read_behavior: TrimReadBehavior,
+    pub outcome: TrimOutcome,
+    pub samples_tested: usize,
+    /// Offsets of sampled ranges that were not all zeros
+    pub nonzero_offsets: Vec<u64>,
+    pub recommendation: Option<String>,
+}
+
+impl TrimVerification {
+    /// Judge sampled reads of discarded ranges
+    pub fn assess(read_behavior: TrimReadBehavior, samples: &[(u64, Vec<u8>)]) -> Self {
+        if read_behavior != TrimReadBehavior::Zeroes {
+            return Self {
+                read_behavior,
+                outcome: TrimOutcome::NotDeterministic,
+                samples_tested: 0,
+                nonzero_offsets: Vec::new(),
+                recommendation: Some(
+                    "Device does not guarantee zeros after TRIM; overwrite the device or use a cryptographic erase"
+                        .to_string(),
+                ),
+            };
+        }
+        
+        let nonzero_offsets: Vec<u64> = samples
+            .iter()
+            .filter(|(_, data)| data.iter().any(|&b| b != 0))
+            .map(|(offset, _)| *offset)
+            .collect();
+        let (outcome, recommendation) = if nonzero_offsets.is_empty() {
+            (TrimOutcome::Verified, None)
+        } else {
+            (
+                TrimOutcome::DataRemaining,
+                Some(format!(
+                    "{} of {} discarded ranges still returned data; overwrite the device",
+                    nonzero_offsets.len(),
+                    samples.len()
+                )),
+            )
+        };
+        Self {
+            read_behavior,
+            outcome,
+            samples_tested: samples.len(),
+            nonzero_offsets,
+            recommendation,
+        }
+    }
+    
+    /// Whether the discard counts as sanitization
+    pub fn is_verified(&self) -> bool {
+        self.outcome == TrimOutcome::Verified
+    }
+}
+
+/// Discard every block of the device and check that discarded ranges read as zeros
+pub async fn discard_and_verify(device: &Device, sample_count: usize) -> Result<TrimVerification> {
+    info!("Discarding all blocks on {}", device.path());
+    platform::discard_device(device.handle()).await?;
+    
+    let read_behavior = device.capabilities().trim_read_behavior;
+    if read_behavior != TrimReadBehavior::Zeroes {
+        warn!("{} reports {} reads after TRIM; not counting the discard", device.path(), read_behavior);
+        return Ok(TrimVerification::assess(read_behavior, &[]));
+    }
+    
+    let size = device.get_info().await?.size;
+    let sector_size = device.capabilities().logical_sector_size.max(1) as u64;
+    let max_sector = size.saturating_sub(SAMPLE_SIZE as u64) / sector_size;
+    let offsets: Vec<u64> = {
+        let mut rng = rand::thread_rng();
+        (0..sample_count.max(1))
+            .map(|_| rng.gen_range(0..=max_sector) * sector_size)
+            .collect()
+    };
+    
+    let mut samples = Vec::with_capacity(offsets.len());
+    for offset in offsets {
+        let start_lba = offset / sector_size;
+        let mut buffer = vec![0u8; SAMPLE_SIZE];
+        platform::read_sectors(device.handle(), start_lba, &mut buffer)
+            .await
+            .with_error_context(|| ErrorContext::new().lba(start_lba))?;
+        samples.push((offset, buffer));
+    }
+    
+    Ok(TrimVerification::assess(read_behavior, &samples))
+}
+
+impl std::fmt::Display for TrimOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrimOutcome::Verified => write!(f, "Verified"),
+            TrimOutcome::NotDeterministic => write!(f, "Not deterministic"),
+            TrimOutcome::DataRemaining => write!(f, "Data remaining"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_zero_reads_verify() {
+        let samples = vec![(0, vec![0u8; 16]), (4096, vec![0u8; 16])];
+        let verification = TrimVerification::assess(TrimReadBehavior::Zeroes, &samples);
+        assert!(verification.is_verified());
+        assert_eq!(verification.samples_tested, 2);
+        
+        let mut dirty = samples.clone();
+        dirty[1].1[3] = 0x42;
+        let verification = TrimVerification::assess(TrimReadBehavior::Zeroes, &dirty);
+        assert_eq!(verification.outcome, TrimOutcome::DataRemaining);
+        assert_eq!(verification.nonzero_offsets, [4096]);
+    }
+    
+    #[test]
+    fn test_without_rzat_is_downgraded() {
+        for behavior in [TrimReadBehavior::Undefined, TrimReadBehavior::Deterministic] {
+            let verification = TrimVerification::assess(behavior, &[(0, vec![0u8; 16])]);
+            assert_eq!(verification.outcome, TrimOutcome::NotDeterministic);
+            assert!(verification.recommendation.unwrap().contains("cryptographic erase"));
+        }
+    }
+}
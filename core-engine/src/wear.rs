@@ -0,0 +1,141 @@
+//! SSD wear before and after a wipe
+//!
+//! Every overwrite pass costs a flash drive endurance, and a 35-pass wipe of
+//! a worn SSD can take a noticeable share of what is left. The wear
+//! indicators are read before and after the wipe and the difference goes
+//! into the result. Before a multi-pass overwrite starts, the bytes it will
+//! write are compared with the endurance the drive has left, estimated from
+//! the bytes written so far per percent of life used; above
+//! [`SIGNIFICANT_ENDURANCE_SHARE`] the result carries a warning suggesting a
+//! hardware erase, which sanitizes the drive without the wear.
+
+use serde::{Deserialize, Serialize};
+
+use crate::platform::SmartInfo;
+
+/// Share of the remaining endurance a wipe may use before it is warned about
+pub const SIGNIFICANT_ENDURANCE_SHARE: f64 = 0.02;
+
+/// Wear indicators of a flash drive at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WearIndicators {
+    /// Vendor estimate of the rated life used, 0-100 (may exceed 100)
+    pub percentage_used: Option<u8>,
+    /// Bytes the host has written over the drive's lifetime
+    pub bytes_written: Option<u64>,
+    /// Remaining spare blocks in percent of the original
+    pub available_spare: Option<u8>,
+}
+
+/// Wear caused by a wipe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WearReport {
+    pub before: WearIndicators,
+    /// Unset when the indicators could not be read after the wipe
+    pub after: Option<WearIndicators>,
+    /// Rise in the percentage of life used during the wipe
+    pub percentage_used_delta: Option<u8>,
+    /// Bytes the drive counted as written during the wipe
+    pub bytes_written_delta: Option<u64>,
+    /// Estimated share (0.0-1.0) of the remaining endurance the wipe would use
+    pub estimated_share: Option<f64>,
+    /// Shown when the estimate exceeds [`SIGNIFICANT_ENDURANCE_SHARE`]
+    pub warning: Option<String>,
+}
+
+impl WearIndicators {
+    /// Wear indicators in SMART data, if the drive reports any
+    pub fn from_smart(smart: &SmartInfo) -> Option<Self> {
+        let indicators = Self {
+            percentage_used: smart.percentage_used,
+            bytes_written: smart.host_bytes_written,
+            available_spare: smart.available_spare,
+        };
+        (indicators != Self::default()).then_some(indicators)
+    }
+    
+    /// Bytes the drive can still take, extrapolated from the writes so far
+    pub fn remaining_endurance(&self) -> Option<u64> {
+        let used = u64::from(self.percentage_used?);
+        let written = self.bytes_written?;
+        if used == 0 || used >= 100 || written == 0 {
+            return None;
+        }
+        Some(written / used * (100 - used))
+    }
+}
+
+impl WearReport {
+    /// Start a report, estimating the cost of writing `planned_bytes`
+    pub fn new(before: WearIndicators, planned_bytes: u64, passes: usize) -> Self {
+        let estimated_share = before
+            .remaining_endurance()
+            .map(|remaining| planned_bytes as f64 / remaining as f64);
+        let warning = estimated_share
+            .filter(|share| passes > 1 && *share >= SIGNIFICANT_ENDURANCE_SHARE)
+            .map(|share| {
+                format!(
+                    "{} overwrite passes write {:.1} GB, about {:.1}% of the drive's remaining endurance; \
+                     a hardware erase (ATA Secure Erase or NVMe Format) sanitizes it without the wear",
+                    passes,
+                    planned_bytes as f64 / 1e9,
+                    share * 100.0
+                )
+            });
+        Self {
+            before,
+            after: None,
+            percentage_used_delta: None,
+            bytes_written_delta: None,
+            estimated_share,
+            warning,
+        }
+    }
+    
+    /// Record the indicators read after the wipe
+    pub fn finish(&mut self, after: WearIndicators) {
+        let before = self.before;
+        self.percentage_used_delta = after
+            .percentage_used
+            .zip(before.percentage_used)
+            .map(|(after, before)| after.saturating_sub(before));
+        self.bytes_written_delta = after
+            .bytes_written
+            .zip(before.bytes_written)
+            .map(|(after, before)| after.saturating_sub(before));
+        self.after = Some(after);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TB: u64 = 1_000_000_000_000;
+    
+    #[test]
+    fn test_warns_when_share_is_significant() {
+        // 10% used after 60 TB: about 540 TB left
+        let worn = WearIndicators { percentage_used: Some(10), bytes_written: Some(60 * TB), available_spare: Some(100) };
+        assert_eq!(worn.remaining_endurance(), Some(540 * TB));
+        
+        let gutmann = WearReport::new(worn, 35 * TB, 35);
+        assert!(gutmann.warning.as_deref().unwrap().contains("35 overwrite passes"));
+        assert!(WearReport::new(worn, TB, 1).warning.is_none());
+        
+        let fresh = WearIndicators { percentage_used: Some(0), ..worn };
+        assert!(WearReport::new(fresh, 35 * TB, 35).estimated_share.is_none());
+    }
+    
+    #[test]
+    fn test_deltas() {
+        let before = WearIndicators { percentage_used: Some(10), bytes_written: Some(60 * TB), available_spare: None };
+        let mut report = WearReport::new(before, TB, 1);
+        assert_eq!(report.bytes_written_delta, None);
+        
+        report.finish(WearIndicators { percentage_used: Some(11), bytes_written: Some(61 * TB), ..before });
+        assert_eq!(report.percentage_used_delta, Some(1));
+        assert_eq!(report.bytes_written_delta, Some(TB));
+        assert!(WearIndicators::from_smart(&SmartInfo::default()).is_none());
+    }
+}
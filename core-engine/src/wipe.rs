@@ -1,23 +1,45 @@
 //! Core wiping engine for SafeErase
 
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{RwLock, broadcast, watch};
 use tokio::time::sleep;
-use tracing::{info, warn, error, debug};
+use tracing::{info, warn, error, debug, Instrument};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-use crate::device::{Device, DeviceType};
+use crate::device::{Device, DeviceType, DriveBay, HiddenArea, VirtualizationLayer};
 use crate::algorithms::{WipeAlgorithm, WipePattern};
-use crate::platform;
-use crate::error::{SafeEraseError, Result};
+use crate::digest::{self, DeviceDigest};
+use crate::trim::{self, TrimVerification};
+use crate::sensitive_data::{self, SensitiveDataScan};
+use crate::smart_monitor::{SmartDegradation, SmartMonitor};
+use crate::host::HostInfo;
+use crate::operator::OperatorContext;
+use crate::cancellation::{CancelReason, Cancellation};
+use crate::erase_journal::{EraseJournal, PendingErase};
+use crate::checkpoint::{CheckpointJournal, CheckpointPosition, WipeCheckpoint};
+use crate::progress_history::{ProgressHistory, ProgressReplay, ProgressToken};
+use crate::regions::{self, RegionResult, WipeRegion};
+use crate::wear::{WearIndicators, WearReport};
+use crate::at_rest::StoreKey;
+use crate::opal::Psid;
+use crate::block_seeds::{MasterSeed, SealedSeed, SeedKey};
+use crate::platform::{self, HardwareEraseState};
+use crate::write_pipeline::{self, buffer_digest, BlockGenerator, BlockPipeline, BlockWrite};
+use crate::logging;
+use crate::verification::{self, FinalContent, VerificationEngine};
+use crate::error::{ErrorContext, ResultExt, SafeEraseError, Result};
+use crate::retry::{retry_recoverable, RetryPolicy};
 
 /// Main wiping engine
 #[derive(Debug)]
 pub struct WipeEngine {
     active_operations: Arc<RwLock<Vec<WipeOperation>>>,
+    progress: ProgressHistory,
 }
 
 /// Configuration options for wipe operations
@@ -27,18 +49,119 @@ pub struct WipeOptions {
     pub verify_wipe: bool,
     /// Number of verification samples to take
     pub verification_samples: usize,
+    /// Bytes read per verification sample; rounded up to the physical sector size
+    #[serde(default = "verification::default_sample_size")]
+    pub verification_sample_size: usize,
     /// Whether to detect and clear HPA/DCO
     pub clear_hpa_dco: bool,
+    /// What to do with a detected HPA when `clear_hpa_dco` is set
+    #[serde(default)]
+    pub hpa_policy: HpaPolicy,
     /// Block size for wiping operations (in bytes)
     pub block_size: usize,
     /// Maximum number of concurrent operations
     pub max_concurrent_ops: usize,
-    /// Timeout for the entire operation
+    /// Optional limit on the entire operation; a multi-pass wipe of a large
+    /// drive is better bounded by `pass_timeout` and `stall_timeout`
+    #[serde(default, with = "crate::time_format::option_duration")]
     pub operation_timeout: Option<Duration>,
+    /// Limit on each overwrite pass
+    #[serde(default, with = "crate::time_format::option_duration")]
+    pub pass_timeout: Option<Duration>,
+    /// Fail with [`SafeEraseError::OperationStalled`] when a write makes no
+    /// progress for this long
+    #[serde(default = "default_stall_timeout", with = "crate::time_format::option_duration")]
+    pub stall_timeout: Option<Duration>,
+    /// Record blocks whose write fails with an I/O error in
+    /// `WipeResult::skipped_ranges` and carry on, instead of failing the wipe
+    #[serde(default)]
+    pub skip_bad_sectors: bool,
+    /// Hash each pattern buffer when generated and check it again just before
+    /// the write, and periodically read back the block just written; guards
+    /// against memory corruption on unreliable machines at some speed cost
+    #[serde(default)]
+    pub paranoid_writes: bool,
+    /// How overwrite passes submit their writes
+    #[serde(default)]
+    pub write_backend: WriteBackend,
+    /// Batches of pattern data generated ahead of the one being written, up
+    /// to 16; 0 generates each batch only after the previous write
+    #[serde(default = "default_pipeline_depth")]
+    pub pipeline_depth: usize,
+    /// Wipe only these LBA ranges, e.g. to keep an OEM diagnostics partition;
+    /// empty wipes the whole device
+    #[serde(default)]
+    pub regions: Vec<WipeRegion>,
     /// Whether to use hardware secure erase when available
     pub prefer_hardware_erase: bool,
     /// Custom progress reporting interval
+    #[serde(with = "crate::time_format::duration")]
     pub progress_interval: Duration,
+    /// Hash the whole device after the final pass
+    #[serde(default)]
+    pub compute_device_digest: bool,
+    /// Discard (TRIM) every block after the final pass and verify it reads back as zeros
+    #[serde(default)]
+    pub discard_after_wipe: bool,
+    /// Sample the device for sensitive content before wiping and record the risk level
+    #[serde(default)]
+    pub scan_sensitive_data: bool,
+    /// Work order or ticket number the wipe is performed under
+    #[serde(default)]
+    pub work_order: Option<String>,
+    /// Link to the ticket in the service desk
+    #[serde(default)]
+    pub ticket_url: Option<String>,
+    /// Watch SMART counters during the wipe and react when the drive degrades
+    #[serde(default)]
+    pub smart_monitor: Option<SmartMonitor>,
+    /// Directory recording hardware erases in flight, so one interrupted by a
+    /// crash or power loss is resumed by the next wipe of the drive
+    #[serde(default)]
+    pub erase_journal_dir: Option<PathBuf>,
+    /// Directory of checkpoints recording how far overwrite passes got, so a
+    /// wipe interrupted by a crash can be resumed; see [`crate::checkpoint`]
+    #[serde(default)]
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Derive the data of random passes from a per-operation master seed,
+    /// stored in `WipeResult::block_seeds` sealed with the 256-bit key in
+    /// this file, so written blocks can be regenerated later
+    #[serde(default)]
+    pub seed_key_file: Option<PathBuf>,
+    /// Key encrypting the erase journal; set by the engine from `[storage] key`
+    #[serde(skip)]
+    pub storage_key: Option<StoreKey>,
+    /// PSID typed in from the drive label for [`WipeAlgorithm::PSIDRevert`];
+    /// never serialized, see [`crate::opal`]
+    #[serde(skip)]
+    pub psid: Option<Psid>,
+}
+
+/// How overwrite passes submit their writes to the device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteBackend {
+    /// One write at a time; works everywhere
+    #[default]
+    Synchronous,
+    /// Up to `queue_depth` writes in flight through io_uring, which keeps
+    /// drives writing several GB/s busy; Linux builds with the `io-uring`
+    /// feature only
+    IoUring { queue_depth: usize },
+}
+
+/// Handling of a Host Protected Area found before wiping
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HpaPolicy {
+    /// Remove the HPA permanently and wipe the native capacity
+    #[default]
+    Clear,
+    /// Remove the HPA until the wipe is done, then set the original max
+    /// address again, e.g. to keep an OEM recovery area. Removal is volatile,
+    /// so a power loss during the wipe also brings the HPA back.
+    ClearAndRestore,
+    /// Leave the HPA in place and wipe only the accessible capacity
+    Preserve,
 }
 
 /// Progress information for a wipe operation
@@ -46,6 +169,9 @@ pub struct WipeOptions {
 pub struct WipeProgress {
     pub operation_id: Uuid,
     pub device_path: String,
+    /// Slot of the device, so operators can match progress to a bay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bay: Option<DriveBay>,
     pub algorithm: WipeAlgorithm,
     pub current_pass: usize,
     pub total_passes: usize,
@@ -54,11 +180,15 @@ pub struct WipeProgress {
     pub percentage: f64,
     pub current_speed: f64, // bytes per second
     pub average_speed: f64,
+    #[serde(default, with = "crate::time_format::option_duration")]
     pub estimated_remaining: Option<Duration>,
     pub current_pattern: Option<String>,
     pub status: WipeStatus,
     pub started_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
+    /// Position of the snapshot within its operation, counting from 1
+    #[serde(default)]
+    pub sequence: u64,
 }
 
 /// Status of a wipe operation
@@ -74,6 +204,10 @@ pub enum WipeStatus {
     Completed,
     Failed,
     Cancelled,
+    /// Failed because the device disappeared during the operation
+    DeviceRemoved,
+    /// Held between blocks of an overwrite pass until resumed
+    Paused,
 }
 
 /// Result of a completed wipe operation
@@ -88,6 +222,7 @@ pub struct WipeResult {
     pub status: WipeStatus,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::time_format::option_duration")]
     pub duration: Option<Duration>,
     pub bytes_wiped: u64,
     pub passes_completed: usize,
@@ -95,10 +230,102 @@ pub struct WipeResult {
     pub verification_passed: Option<bool>,
     pub hpa_detected: bool,
     pub hpa_cleared: bool,
+    /// The HPA was put back after wiping, as requested by [`HpaPolicy::ClearAndRestore`]
+    #[serde(default)]
+    pub hpa_restored: bool,
     pub dco_detected: bool,
     pub dco_cleared: bool,
+    /// Capacity the HPA and DCO hid before the wipe
+    #[serde(default)]
+    pub hidden_area: Option<HiddenArea>,
+    /// Bytes made accessible by clearing them
+    #[serde(default)]
+    pub recovered_bytes: u64,
+    /// Virtualization layer of the device; the wipe covers only what it exposes
+    #[serde(default)]
+    pub virtualization: Option<VirtualizationLayer>,
     pub error_message: Option<String>,
+    /// Where the wipe failed, when it did
+    #[serde(default)]
+    pub error_context: Option<ErrorContext>,
     pub performance_stats: PerformanceStats,
+    /// Organizational policy the wipe was checked against
+    #[serde(default)]
+    pub policy_id: Option<String>,
+    /// Digest of the device contents after the final pass
+    #[serde(default)]
+    pub device_digest: Option<DeviceDigest>,
+    /// Outcome of the post-wipe discard, when one was requested
+    #[serde(default)]
+    pub trim_verification: Option<TrimVerification>,
+    /// What the device held before the wipe, when a scan was requested
+    #[serde(default)]
+    pub sensitive_data_scan: Option<SensitiveDataScan>,
+    /// Work order or ticket number the wipe was performed under
+    #[serde(default)]
+    pub work_order: Option<String>,
+    /// Link to the ticket in the service desk
+    #[serde(default)]
+    pub ticket_url: Option<String>,
+    /// Each completed pass in order
+    #[serde(default)]
+    pub passes: Vec<PassRecord>,
+    /// SMART counter that grew beyond the tolerated delta during the wipe
+    #[serde(default)]
+    pub smart_degradation: Option<SmartDegradation>,
+    /// Host and software build that performed the wipe
+    #[serde(default)]
+    pub host: Option<HostInfo>,
+    /// Earlier operation whose interrupted hardware erase this wipe resumed or finalized
+    #[serde(default)]
+    pub resumed_from: Option<Uuid>,
+    /// Sectors the wipe could not write and that may still hold data
+    #[serde(default)]
+    pub skipped_ranges: Vec<SkippedRange>,
+    /// What each region received, when the wipe was limited to regions
+    #[serde(default)]
+    pub regions: Vec<RegionResult>,
+    /// SSD wear indicators before and after the wipe
+    #[serde(default)]
+    pub wear: Option<WearReport>,
+    /// Sealed master seed of the random passes, when `seed_key_file` was set
+    #[serde(default)]
+    pub block_seeds: Option<SealedSeed>,
+    /// Who started the wipe; set by the engine from the context passed to `start_wipe`
+    #[serde(default)]
+    pub operator: Option<OperatorContext>,
+    /// Why the wipe was cancelled, when it was; `error_message` holds its text form
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<Cancellation>,
+    /// Position the overwrite continued from after an interruption
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_at: Option<CheckpointPosition>,
+}
+
+/// Sectors left unwritten because the device failed to write them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedRange {
+    pub start_lba: u64,
+    /// Last skipped LBA, inclusive
+    pub end_lba: u64,
+    /// Error the write failed with
+    pub reason: String,
+}
+
+/// Outcome of one overwrite pass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassRecord {
+    /// Pass number, starting at 1
+    pub pass: usize,
+    /// Description of the written pattern
+    pub pattern: String,
+    pub bytes_written: u64,
+    #[serde(with = "crate::time_format::duration")]
+    pub duration: Duration,
+    pub average_speed: f64, // bytes per second
+    /// Result of the read-back verification; only the final pass is verified
+    #[serde(default)]
+    pub verified: Option<bool>,
 }
 
 /// Performance statistics for the wipe operation
@@ -106,8 +333,11 @@ pub struct WipeResult {
 pub struct PerformanceStats {
     pub average_speed: f64, // bytes per second
     pub peak_speed: f64,
+    #[serde(with = "crate::time_format::duration")]
     pub total_time: Duration,
+    #[serde(with = "crate::time_format::duration")]
     pub wipe_time: Duration,
+    #[serde(default, with = "crate::time_format::option_duration")]
     pub verification_time: Option<Duration>,
 }
 
@@ -117,43 +347,148 @@ struct WipeOperation {
     id: Uuid,
     device: Arc<Device>,
     algorithm: WipeAlgorithm,
-    options: WipeOptions,
-    progress_tx: mpsc::UnboundedSender<WipeProgress>,
     cancel_token: tokio_util::sync::CancellationToken,
-    started_at: Instant,
+    /// First reason given for cancelling; later ones are ignored
+    cancellation: Arc<OnceLock<Cancellation>>,
+    /// Whether the operation should hold before its next block
+    pause: watch::Sender<bool>,
+}
+
+/// What a running operation is cancelled and paused through
+#[derive(Debug)]
+struct OperationControl {
+    cancel_token: tokio_util::sync::CancellationToken,
+    cancellation: Arc<OnceLock<Cancellation>>,
+    pause: PauseGate,
+}
+
+/// Lets a paused overwrite wait before its next block, and keeps track of
+/// the time spent paused so it is not counted against the time limits
+#[derive(Debug, Clone)]
+struct PauseGate {
+    paused: watch::Receiver<bool>,
+    clock: Arc<Mutex<PauseClock>>,
+}
+
+#[derive(Debug, Default)]
+struct PauseClock {
+    total: Duration,
+    since: Option<Instant>,
+}
+
+/// Keeps the checkpoint of an overwrite up to date and knows where a resumed one starts
+#[derive(Debug)]
+struct Checkpointer {
+    /// Where checkpoints are recorded; none are without a `checkpoint_dir`
+    journal: Option<CheckpointJournal>,
+    checkpoint: WipeCheckpoint,
+    /// Position the operation started from
+    resumed: CheckpointPosition,
+    last_saved: Instant,
 }
 
 impl WipeEngine {
     /// Create a new wipe engine
     pub fn new() -> Result<Self> {
+        let (progress_tx, _) = broadcast::channel(PROGRESS_CHANNEL_CAPACITY);
+        
         Ok(Self {
             active_operations: Arc::new(RwLock::new(Vec::new())),
+            progress: ProgressHistory::new(progress_tx),
         })
     }
     
+    /// Subscribe to progress updates from all wipe operations
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<WipeProgress> {
+        self.progress.subscribe()
+    }
+    
+    /// Progress of one operation after `token`, followed by its live progress
+    pub fn resume_progress(&self, token: &ProgressToken) -> Result<ProgressReplay> {
+        self.progress.resume(token)
+    }
+    
     /// Start a wipe operation on the specified device
     pub async fn wipe_device(
         &self,
-        device: &Device,
+        device: &Arc<Device>,
         algorithm: WipeAlgorithm,
         options: WipeOptions,
     ) -> Result<WipeResult> {
-        let operation_id = Uuid::new_v4();
-        info!("Starting wipe operation {} on device {}", operation_id, device.path());
+        self.start_operation(device, algorithm, options, None).await
+    }
+    
+    /// Continue an overwrite interrupted by a crash or power loss from its
+    /// checkpoint, under the operation ID it started with
+    pub async fn resume_from_checkpoint(&self, device: &Arc<Device>, checkpoint: WipeCheckpoint) -> Result<WipeResult> {
+        let serial = device.get_info().await?.serial;
+        if serial != checkpoint.device_serial {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "{} has serial {}, but the checkpoint of operation {} is for serial {}",
+                device.path(),
+                serial,
+                checkpoint.operation_id,
+                checkpoint.device_serial
+            )));
+        }
+        info!(
+            "Resuming operation {} on {} at pass {}, byte {}",
+            checkpoint.operation_id,
+            device.path(),
+            checkpoint.position.pass_index + 1,
+            checkpoint.position.pass_bytes
+        );
+        let (algorithm, options) = (checkpoint.algorithm.clone(), checkpoint.options.clone());
+        self.start_operation(device, algorithm, options, Some(checkpoint)).await
+    }
+    
+    async fn start_operation(
+        &self,
+        device: &Arc<Device>,
+        algorithm: WipeAlgorithm,
+        options: WipeOptions,
+        checkpoint: Option<WipeCheckpoint>,
+    ) -> Result<WipeResult> {
+        let operation_id = checkpoint.as_ref().map_or_else(Uuid::new_v4, |checkpoint| checkpoint.operation_id);
+        let span = logging::operation_span(operation_id, &device.get_info().await?.serial);
+        span.in_scope(|| info!("Starting wipe operation {} on device {}", operation_id, device.path()));
+        
+        let mut options = options;
+        options.write_backend.validate()?;
+        if options.pipeline_depth > write_pipeline::MAX_PIPELINE_DEPTH {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "Pipeline depth must be at most {}, got {}", write_pipeline::MAX_PIPELINE_DEPTH, options.pipeline_depth
+            )));
+        }
+        if algorithm == WipeAlgorithm::PSIDRevert && options.psid.is_none() {
+            return Err(SafeEraseError::InvalidParameter(
+                "PSID revert needs the PSID from the drive label".to_string()
+            ));
+        }
+        if !options.regions.is_empty() {
+            if algorithm.is_hardware_based() {
+                return Err(SafeEraseError::InvalidParameter(format!(
+                    "{} erases the whole drive and cannot be limited to regions",
+                    algorithm
+                )));
+            }
+            let sector_size = u64::from(device.capabilities().logical_sector_size);
+            options.regions = regions::validate(&options.regions, device.get_info().await?.size / sector_size)?;
+        }
         
-        // Create progress channel
-        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
         let cancel_token = tokio_util::sync::CancellationToken::new();
+        let cancellation = Arc::new(OnceLock::new());
+        let (pause, paused) = watch::channel(false);
+        let pause_gate = PauseGate::new(paused);
         
         // Create operation state
         let operation = WipeOperation {
             id: operation_id,
-            device: Arc::new(device.clone()),
-            algorithm,
-            options: options.clone(),
-            progress_tx,
+            device: Arc::clone(device),
+            algorithm: algorithm.clone(),
             cancel_token: cancel_token.clone(),
-            started_at: Instant::now(),
+            cancellation: cancellation.clone(),
+            pause,
         };
         
         // Add to active operations
@@ -163,47 +498,47 @@ impl WipeEngine {
         }
         
         // Start the actual wipe operation
-        let device_clone = Arc::new(device.clone());
+        let timeout_cancel = cancel_token.clone();
+        let device_clone = Arc::clone(device);
+        let progress_history = self.progress.clone();
+        let task_options = options.clone();
+        let task_gate = pause_gate.clone();
         let wipe_task = tokio::spawn(async move {
             Self::execute_wipe_operation(
                 operation_id,
                 device_clone,
                 algorithm,
-                options,
-                cancel_token,
+                task_options,
+                OperationControl { cancel_token, cancellation, pause: task_gate },
+                progress_history,
+                checkpoint,
             ).await
-        });
-        
-        // Wait for completion or timeout
-        let result = if let Some(timeout) = options.operation_timeout {
-            match tokio::time::timeout(timeout, wipe_task).await {
-                Ok(Ok(result)) => result,
-                Ok(Err(e)) => {
-                    error!("Wipe operation {} failed: {}", operation_id, e);
-                    return Err(e);
-                }
-                Err(_) => {
-                    error!("Wipe operation {} timed out", operation_id);
-                    return Err(SafeEraseError::Timeout(format!("Operation timed out after {:?}", timeout)));
+        }.instrument(span.clone()));
+        
+        // Wait for completion or timeout; time spent paused does not count
+        let outcome = if let Some(timeout) = options.operation_timeout {
+            match pause_gate.limit(timeout, wipe_task).await {
+                Some(Ok(outcome)) => outcome,
+                Some(Err(e)) => Err(SafeEraseError::Internal(format!("Operation panicked: {}", e))),
+                None => {
+                    // Stop the wipe rather than leave it writing unsupervised
+                    timeout_cancel.cancel();
+                    Err(SafeEraseError::Timeout(format!("Operation timed out after {:?}", timeout)))
                 }
             }
         } else {
-            match wipe_task.await {
-                Ok(result) => result?,
-                Err(e) => {
-                    error!("Wipe operation {} panicked: {}", operation_id, e);
-                    return Err(SafeEraseError::Internal(format!("Operation panicked: {}", e)));
-                }
-            }
+            wipe_task.await.unwrap_or_else(|e| Err(SafeEraseError::Internal(format!("Operation panicked: {}", e))))
         };
         
-        // Remove from active operations
+        // Remove from active operations, however the operation ended
         {
             let mut active_ops = self.active_operations.write().await;
             active_ops.retain(|op| op.id != operation_id);
         }
         
-        info!("Wipe operation {} completed with status: {:?}", operation_id, result.status);
+        let result = outcome.inspect_err(|e| error!("Wipe operation {} failed: {}", operation_id, e))?;
+        logging::record_outcome(&span, result.status, result.bytes_wiped);
+        span.in_scope(|| info!("Wipe operation {} completed with status: {:?}", operation_id, result.status));
         Ok(result)
     }
     
@@ -213,17 +548,46 @@ impl WipeEngine {
         device: Arc<Device>,
         algorithm: WipeAlgorithm,
         options: WipeOptions,
-        cancel_token: tokio_util::sync::CancellationToken,
+        control: OperationControl,
+        progress_history: ProgressHistory,
+        checkpoint: Option<WipeCheckpoint>,
     ) -> Result<WipeResult> {
-        let started_at = Utc::now();
+        let OperationControl { cancel_token, cancellation, pause } = control;
+        let started_at = checkpoint.as_ref().map_or_else(Utc::now, |checkpoint| checkpoint.started_at);
         let device_info = device.get_info().await?;
+        let mut progress = ProgressReporter::new(
+            operation_id,
+            &device_info.path,
+            algorithm.clone(),
+            regions::wiped_bytes(&options.regions, device.capabilities().logical_sector_size, device_info.size),
+            started_at,
+            options.progress_interval,
+            progress_history,
+        )
+        .with_bay(device_info.bay.clone())
+        .with_pause_gate(pause);
+        
+        let sealed_seed = checkpoint.as_ref().and_then(|checkpoint| checkpoint.block_seeds.clone());
+        let master_seed = match (&options.seed_key_file, sealed_seed) {
+            // The resumed passes must write what the interrupted run would have
+            (Some(path), Some(sealed)) => {
+                let seed = sealed.open(&SeedKey::load(path)?, operation_id)?;
+                Some((sealed, seed))
+            }
+            (Some(path), None) if algorithm.patterns().contains(&WipePattern::Random) => {
+                let key = SeedKey::load(path)?;
+                let seed = MasterSeed::generate();
+                Some((seed.seal(&key, operation_id)?, seed))
+            }
+            _ => None,
+        };
         
         let mut result = WipeResult {
             operation_id,
             device_path: device_info.path.clone(),
             device_serial: device_info.serial.clone(),
             device_model: device_info.model.clone(),
-            algorithm,
+            algorithm: algorithm.clone(),
             options: options.clone(),
             status: WipeStatus::Initializing,
             started_at,
@@ -235,9 +599,14 @@ impl WipeEngine {
             verification_passed: None,
             hpa_detected: false,
             hpa_cleared: false,
+            hpa_restored: false,
             dco_detected: false,
             dco_cleared: false,
+            hidden_area: None,
+            recovered_bytes: 0,
+            virtualization: device_info.virtualization,
             error_message: None,
+            error_context: None,
             performance_stats: PerformanceStats {
                 average_speed: 0.0,
                 peak_speed: 0.0,
@@ -245,30 +614,99 @@ impl WipeEngine {
                 wipe_time: Duration::from_secs(0),
                 verification_time: None,
             },
+            policy_id: None,
+            device_digest: None,
+            trim_verification: None,
+            sensitive_data_scan: None,
+            work_order: options.work_order.clone(),
+            ticket_url: options.ticket_url.clone(),
+            passes: Vec::new(),
+            smart_degradation: None,
+            host: tokio::task::spawn_blocking(HostInfo::collect).await.ok(),
+            resumed_from: None,
+            skipped_ranges: Vec::new(),
+            regions: Vec::new(),
+            wear: None,
+            block_seeds: master_seed.as_ref().map(|(sealed, _)| sealed.clone()),
+            operator: None,
+            cancellation: None,
+            resumed_at: checkpoint.as_ref().map(|checkpoint| checkpoint.position),
         };
+        let mut checkpointer = Checkpointer::new(
+            options.checkpoint_dir.as_ref().map(|dir| CheckpointJournal::new(dir).with_key(options.storage_key.clone())),
+            checkpoint.unwrap_or_else(|| WipeCheckpoint {
+                operation_id,
+                device_serial: device_info.serial.clone(),
+                device_path: device_info.path.clone(),
+                algorithm: algorithm.clone(),
+                options: options.clone(),
+                position: CheckpointPosition::default(),
+                next_lba: 0,
+                passes: Vec::new(),
+                skipped_ranges: Vec::new(),
+                block_seeds: result.block_seeds.clone(),
+                started_at,
+                updated_at: started_at,
+            }),
+        );
         
         let operation_start = Instant::now();
+        let _presence = PresenceWatch::start(device.clone(), cancel_token.clone());
         
         // Check for cancellation
         if cancel_token.is_cancelled() {
             result.status = WipeStatus::Cancelled;
+            result.record_cancellation(&cancellation);
             return Ok(result);
         }
         
-        // Step 1: Detect and clear HPA/DCO if requested
-        if options.clear_hpa_dco && device.supports_hpa_dco() {
+        // Step 1: Record what the media holds before anything is written
+        if options.scan_sensitive_data {
+            match sensitive_data::scan_device(&device, options.verification_samples).await {
+                Ok(scan) => {
+                    info!("Pre-wipe scan of {}: {} risk ({})", device.path(), scan.risk_level, scan.summary());
+                    result.sensitive_data_scan = Some(scan);
+                }
+                Err(e) => warn!("Failed to scan device {} for sensitive data: {}", device.path(), e),
+            }
+        }
+        
+        // Step 2: Detect and clear HPA/DCO if requested
+        let mut restore_max_sectors = None;
+        let sector_size = device.capabilities().logical_sector_size;
+        if !options.regions.is_empty() {
+            debug!("Wiping {} regions of {}; leaving HPA and DCO alone", options.regions.len(), device.path());
+        } else if options.clear_hpa_dco && device.supports_hpa_dco() {
             result.status = WipeStatus::DetectingHPA;
             debug!("Detecting HPA on device {}", device.path());
             
-            match platform::detect_and_clear_hpa(device.handle()).await {
-                Ok(detected) => {
-                    result.hpa_detected = detected;
-                    if detected {
+            match platform::read_hpa(device.handle()).await {
+                Ok(hpa) if hpa.is_present() => {
+                    result.hpa_detected = true;
+                    result.hidden_area = Some(HiddenArea {
+                        hpa_sectors: hpa.hidden_sectors(),
+                        dco_sectors: 0,
+                        sector_size,
+                    });
+                    if options.hpa_policy == HpaPolicy::Preserve {
+                        info!("Preserving HPA of {} sectors on device {}", hpa.hidden_sectors(), device.path());
+                    } else {
                         result.status = WipeStatus::ClearingHPA;
-                        result.hpa_cleared = true;
-                        info!("HPA detected and cleared on device {}", device.path());
+                        let volatile = options.hpa_policy == HpaPolicy::ClearAndRestore;
+                        match platform::set_max_sectors(device.handle(), hpa.native_max_sectors, volatile).await {
+                            Ok(()) => {
+                                result.hpa_cleared = true;
+                                result.recovered_bytes += hpa.hidden_sectors() * u64::from(sector_size);
+                                if volatile {
+                                    restore_max_sectors = Some(hpa.current_max_sectors);
+                                }
+                                info!("HPA detected and cleared on device {}", device.path());
+                            }
+                            Err(e) => warn!("Failed to clear HPA on device {}: {}", device.path(), e),
+                        }
                     }
                 }
+                Ok(_) => {}
                 Err(e) => {
                     warn!("Failed to detect/clear HPA on device {}: {}", device.path(), e);
                 }
@@ -278,49 +716,199 @@ impl WipeEngine {
             debug!("Detecting DCO on device {}", device.path());
             
             match platform::detect_and_clear_dco(device.handle()).await {
-                Ok(detected) => {
-                    result.dco_detected = detected;
-                    if detected {
-                        result.status = WipeStatus::ClearingDCO;
-                        result.dco_cleared = true;
-                        info!("DCO detected and cleared on device {}", device.path());
-                    }
+                Ok(Some(dco_sectors)) => {
+                    result.status = WipeStatus::ClearingDCO;
+                    result.dco_detected = true;
+                    result.dco_cleared = true;
+                    result.hidden_area.get_or_insert(HiddenArea { sector_size, ..HiddenArea::default() }).dco_sectors = dco_sectors;
+                    result.recovered_bytes += dco_sectors * u64::from(sector_size);
+                    info!("DCO hiding {} sectors detected and cleared on device {}", dco_sectors, device.path());
                 }
+                Ok(None) => {}
                 Err(e) => {
                     warn!("Failed to detect/clear DCO on device {}: {}", device.path(), e);
                 }
             }
         }
         
-        // Step 2: Perform the actual wipe
+        // Record flash wear first, warning when the overwrite would use up much of what is left
+        if matches!(device_info.device_type, DeviceType::SSD | DeviceType::NVMe) {
+            match platform::get_smart_info(device.handle()).await {
+                Ok(smart) => {
+                    if let Some(before) = WearIndicators::from_smart(&smart) {
+                        let (passes, planned_bytes) = if result.algorithm.is_hardware_based() {
+                            (1, 0)
+                        } else {
+                            let passes = result.algorithm.patterns().len();
+                            let pass_bytes = regions::wiped_bytes(&options.regions, sector_size, device_info.size);
+                            (passes, pass_bytes * passes as u64)
+                        };
+                        let report = WearReport::new(before, planned_bytes, passes);
+                        if let Some(warning) = &report.warning {
+                            warn!("{}: {}", device.path(), warning);
+                        }
+                        result.wear = Some(report);
+                    }
+                }
+                Err(e) => debug!("Cannot read wear indicators of {}: {}", device.path(), e),
+            }
+        }
+        
+        // Step 3: Perform the actual wipe, watching SMART counters if requested
         result.status = WipeStatus::Wiping;
+        let wipe_token = cancel_token.child_token();
+        let smart_watch = match &options.smart_monitor {
+            Some(monitor) => match platform::get_smart_info(device.handle()).await {
+                Ok(baseline) => Some(monitor.watch(device.clone(), baseline, wipe_token.clone())),
+                Err(e) => {
+                    warn!("Cannot read SMART baseline of {}, not monitoring: {}", device.path(), e);
+                    None
+                }
+            },
+            None => None,
+        };
         let wipe_start = Instant::now();
         
-        match Self::perform_wipe(&device, algorithm, &options, &cancel_token).await {
+        let seed = master_seed.as_ref().map(|(_, seed)| seed);
+        let mut wiped = Self::perform_wipe(&device, algorithm, &options, seed, &wipe_token, &mut progress, &mut checkpointer).await;
+        
+        if let Some(watch) = smart_watch {
+            result.smart_degradation = watch.finish().await;
+        }
+        if let Some(degradation) = result.smart_degradation.as_ref().filter(|d| d.aborted) {
+            if !cancel_token.is_cancelled() {
+                wiped = Err(SafeEraseError::WipeFailed(format!("Aborted on SMART degradation: {}", degradation)));
+            }
+        }
+        
+        // The original HPA goes back whether or not the wipe succeeded
+        if let Some(sectors) = restore_max_sectors {
+            match platform::set_max_sectors(device.handle(), sectors, true).await {
+                Ok(()) => {
+                    result.hpa_restored = true;
+                    info!("Restored HPA on device {} to {} sectors", device.path(), sectors);
+                }
+                Err(e) => error!("Failed to restore HPA on device {}: {}", device.path(), e),
+            }
+        }
+        
+        match wiped {
             Ok(stats) => {
                 result.bytes_wiped = stats.bytes_wiped;
                 result.passes_completed = stats.passes_completed;
                 result.performance_stats.wipe_time = wipe_start.elapsed();
                 result.performance_stats.average_speed = stats.average_speed;
                 result.performance_stats.peak_speed = stats.peak_speed;
+                result.passes = stats.passes;
+                result.resumed_from = stats.resumed_from;
+                if !stats.skipped_ranges.is_empty() {
+                    let sectors: u64 = stats.skipped_ranges.iter().map(SkippedRange::sectors).sum();
+                    warn!("{} sectors of {} could not be written and may still hold data", sectors, device.path());
+                }
+                result.regions = options
+                    .regions
+                    .iter()
+                    .map(|region| region.result(sector_size, stats.passes_completed, &stats.skipped_ranges))
+                    .collect();
+                result.skipped_ranges = stats.skipped_ranges;
+                if let Some(wear) = result.wear.as_mut() {
+                    match platform::get_smart_info(device.handle()).await {
+                        Ok(smart) => {
+                            if let Some(after) = WearIndicators::from_smart(&smart) {
+                                wear.finish(after);
+                            }
+                        }
+                        Err(e) => debug!("Cannot read wear indicators of {} after the wipe: {}", device.path(), e),
+                    }
+                }
             }
             Err(e) => {
-                result.status = WipeStatus::Failed;
-                result.error_message = Some(e.to_string());
+                let e = e.with_context(
+                    ErrorContext::new()
+                        .operation(operation_id)
+                        .device(&device_info.path, &device_info.serial),
+                );
+                // A vanished device often shows up as a plain I/O error first
+                let removed = device.handle().is_removed()
+                    || matches!(e.root(), SafeEraseError::DeviceRemoved(_))
+                    || (matches!(e.root(), SafeEraseError::DeviceIoError(_)) && !platform::device_present(device.handle()).await);
+                result.status = match e.root() {
+                    _ if removed => WipeStatus::DeviceRemoved,
+                    SafeEraseError::WipeCancelled => WipeStatus::Cancelled,
+                    _ => WipeStatus::Failed,
+                };
+                match result.status {
+                    WipeStatus::Failed => {
+                        error!("Wipe failed ({}): {}", e.context().map(ToString::to_string).unwrap_or_default(), e);
+                        result.error_message = Some(e.to_string());
+                    }
+                    WipeStatus::DeviceRemoved => result.record_device_removed(),
+                    _ => result.record_cancellation(&cancellation),
+                }
+                result.error_context = e.context().cloned();
                 result.completed_at = Some(Utc::now());
                 result.duration = Some(operation_start.elapsed());
+                progress.report_status(result.status);
                 return Ok(result);
             }
         }
         
-        // Step 3: Verify the wipe if requested
+        // Step 4: Discard all blocks so the SSD can release them, and check the result
+        if options.discard_after_wipe {
+            if !options.regions.is_empty() {
+                warn!("Discard covers the whole device; skipping it for the region wipe of {}", device.path());
+            } else if device.capabilities().supports_trim {
+                match trim::discard_and_verify(&device, options.verification_samples).await {
+                    Ok(verification) => {
+                        if let Some(recommendation) = &verification.recommendation {
+                            warn!("TRIM on {}: {}: {}", device.path(), verification.outcome, recommendation);
+                        }
+                        result.trim_verification = Some(verification);
+                    }
+                    Err(e) => warn!("Failed to discard device {}: {}", device.path(), e),
+                }
+            } else {
+                warn!("Device {} does not support TRIM; skipping discard", device.path());
+            }
+        }
+        
+        // Step 5: Record what the device holds now, so later reads can be compared
+        if options.compute_device_digest {
+            match digest::digest_device(&device, options.write_block_size(), &cancel_token).await {
+                Ok(digest) => {
+                    info!("Device digest for {}: {}", device.path(), digest);
+                    result.device_digest = Some(digest);
+                }
+                Err(e) if matches!(e.root(), SafeEraseError::WipeCancelled) => {
+                    if device.handle().is_removed() {
+                        result.record_device_removed();
+                    } else {
+                        result.status = WipeStatus::Cancelled;
+                        result.record_cancellation(&cancellation);
+                    }
+                    result.completed_at = Some(Utc::now());
+                    result.duration = Some(operation_start.elapsed());
+                    progress.report_status(result.status);
+                    return Ok(result);
+                }
+                Err(e) => warn!("Failed to compute digest of device {}: {}", device.path(), e),
+            }
+        }
+        
+        // Step 6: Verify the wipe if requested
         if options.verify_wipe {
             result.status = WipeStatus::Verifying;
+            progress.report_status(WipeStatus::Verifying);
             let verify_start = Instant::now();
             
-            match Self::verify_wipe(&device, &options).await {
+            let sector_size = device.capabilities().logical_sector_size;
+            let final_content = verification::final_content(&result, device_info.size, sector_size);
+            match Self::verify_wipe(&device, &options, &final_content).await {
                 Ok(passed) => {
                     result.verification_passed = Some(passed);
+                    if let Some(last_pass) = result.passes.last_mut() {
+                        last_pass.verified = Some(passed);
+                    }
                     result.performance_stats.verification_time = Some(verify_start.elapsed());
                     if !passed {
                         result.status = WipeStatus::Failed;
@@ -331,18 +919,22 @@ impl WipeEngine {
                     warn!("Wipe verification failed: {}", e);
                     result.verification_passed = Some(false);
                     result.performance_stats.verification_time = Some(verify_start.elapsed());
+                    result.status = WipeStatus::Failed;
+                    result.error_message = Some(format!("Wipe verification could not read the device: {}", e));
+                    result.error_context = e.context().cloned();
                 }
             }
         }
         
         // Finalize result
-        if result.status == WipeStatus::Wiping {
+        if matches!(result.status, WipeStatus::Wiping | WipeStatus::Verifying) {
             result.status = WipeStatus::Completed;
         }
         
         result.completed_at = Some(Utc::now());
         result.duration = Some(operation_start.elapsed());
         result.performance_stats.total_time = operation_start.elapsed();
+        progress.report_status(result.status);
         
         Ok(result)
     }
@@ -352,23 +944,26 @@ impl WipeEngine {
         device: &Device,
         algorithm: WipeAlgorithm,
         options: &WipeOptions,
+        master_seed: Option<&MasterSeed>,
         cancel_token: &tokio_util::sync::CancellationToken,
+        progress: &mut ProgressReporter,
+        checkpointer: &mut Checkpointer,
     ) -> Result<WipeStats> {
-        let device_info = device.get_info().await?;
-        
         // Use hardware erase if available and preferred
         if options.prefer_hardware_erase && algorithm.is_hardware_based() {
-            return Self::perform_hardware_wipe(device, algorithm).await;
+            return Self::perform_hardware_wipe(device, algorithm, options, progress.operation_id).await;
         }
         
         // Perform software-based wipe
         let patterns = algorithm.patterns();
         let total_passes = patterns.len();
-        let mut bytes_wiped = 0u64;
-        let mut speeds = Vec::new();
-        let operation_start = Instant::now();
+        progress.set_total_passes(total_passes);
         
         for (pass_index, pattern) in patterns.iter().enumerate() {
+            // Finished before the wipe was interrupted
+            if pass_index < checkpointer.resumed.pass_index {
+                continue;
+            }
             if cancel_token.is_cancelled() {
                 return Err(SafeEraseError::WipeCancelled);
             }
@@ -377,44 +972,121 @@ impl WipeEngine {
                   pass_index + 1, total_passes, pattern.description());
             
             let pass_start = Instant::now();
-            let pass_bytes = Self::wipe_with_pattern(device, pattern, options, cancel_token).await?;
-            let pass_duration = pass_start.elapsed();
+            let start_bytes = checkpointer.start_bytes(pass_index);
+            let pause = progress.pause.clone();
+            let paused_before = pause.paused_for();
+            let pass = Self::wipe_with_pattern(
+                device,
+                pattern,
+                options,
+                master_seed,
+                cancel_token,
+                progress,
+                pass_index,
+                checkpointer,
+            );
+            let pass_result = match options.pass_timeout {
+                Some(limit) => pause.limit(limit, pass).await.unwrap_or_else(|| {
+                    Err(SafeEraseError::Timeout(format!("Pass {} exceeded {:?}", pass_index + 1, limit)))
+                }),
+                None => pass.await,
+            };
+            let pass_bytes = pass_result.with_error_context(|| ErrorContext::new().pass(pass_index))?;
+            let pass_duration = pass_start.elapsed().saturating_sub(pause.paused_for() - paused_before);
             
-            bytes_wiped += pass_bytes;
-            let speed = pass_bytes as f64 / pass_duration.as_secs_f64();
-            speeds.push(speed);
+            // Only this run's share of a resumed pass was timed, and a pass
+            // resumed at its end may have taken no measurable time at all
+            let speed = if pass_duration.is_zero() {
+                0.0
+            } else {
+                pass_bytes.saturating_sub(start_bytes) as f64 / pass_duration.as_secs_f64()
+            };
+            checkpointer.finish_pass(device, PassRecord {
+                pass: pass_index + 1,
+                pattern: pattern.description(),
+                bytes_written: pass_bytes,
+                duration: pass_duration,
+                average_speed: speed,
+                verified: None,
+            }).await;
             
             info!("Completed pass {} in {:?} at {:.2} MB/s", 
                   pass_index + 1, pass_duration, speed / 1_000_000.0);
         }
         
         // Flush device cache
-        platform::flush_cache(device.handle()).await?;
+        retry_recoverable(&RetryPolicy::default(), || platform::flush_cache(device.handle())).await?;
+        checkpointer.clear();
         
+        let passes = checkpointer.checkpoint.passes.clone();
+        let skipped_ranges = checkpointer.checkpoint.skipped_ranges.clone();
+        let bytes_wiped = passes.iter().map(|pass| pass.bytes_written).sum();
+        let speeds: Vec<f64> = passes.iter().map(|pass| pass.average_speed).collect();
         Ok(WipeStats {
             bytes_wiped,
             passes_completed: total_passes,
             average_speed: speeds.iter().sum::<f64>() / speeds.len() as f64,
             peak_speed: speeds.iter().fold(0.0, |a, &b| a.max(b)),
+            passes,
+            resumed_from: None,
+            skipped_ranges,
         })
     }
     
-    /// Perform hardware-based wipe (ATA Secure Erase or NVMe Format)
-    async fn perform_hardware_wipe(device: &Device, algorithm: WipeAlgorithm) -> Result<WipeStats> {
+    /// Perform hardware-based wipe (ATA Secure Erase, NVMe Format or PSID revert), first
+    /// settling an erase of the same drive that an earlier process left unfinished
+    async fn perform_hardware_wipe(
+        device: &Device,
+        algorithm: WipeAlgorithm,
+        options: &WipeOptions,
+        operation_id: Uuid,
+    ) -> Result<WipeStats> {
         let device_info = device.get_info().await?;
         let start_time = Instant::now();
+        let journal = options
+            .erase_journal_dir
+            .as_ref()
+            .map(|dir| EraseJournal::new(dir).with_key(options.storage_key.clone()));
+        
+        let resumed_from = match journal.as_ref().and_then(|journal| journal.pending(&device_info.serial)) {
+            Some(pending) => Self::settle_earlier_erase(device, &pending).await?,
+            None => None,
+        };
         
-        match algorithm {
-            WipeAlgorithm::ATASecureErase => {
-                info!("Performing ATA Secure Erase on device {}", device.path());
-                platform::ata_secure_erase(device.handle(), false).await?;
+        if resumed_from.is_none() {
+            if let Some(journal) = &journal {
+                journal.record(&PendingErase {
+                    operation_id,
+                    device_serial: device_info.serial.clone(),
+                    device_path: device_info.path.clone(),
+                    algorithm: algorithm.clone(),
+                    started_at: Utc::now(),
+                })?;
             }
-            WipeAlgorithm::NVMeFormat => {
-                info!("Performing NVMe Format on device {}", device.path());
-                platform::nvme_format(device.handle(), true).await?;
+            match algorithm {
+                WipeAlgorithm::ATASecureErase => {
+                    info!("Performing ATA Secure Erase on device {}", device.path());
+                    platform::ata_secure_erase(device.handle(), false).await?;
+                }
+                WipeAlgorithm::NVMeFormat => {
+                    info!("Performing NVMe Format on device {}", device.path());
+                    platform::nvme_format(device.handle(), true).await?;
+                }
+                WipeAlgorithm::PSIDRevert => {
+                    let psid = options.psid.as_ref().ok_or_else(|| {
+                        SafeEraseError::InvalidParameter("PSID revert needs the PSID from the drive label".to_string())
+                    })?;
+                    info!("Performing PSID revert on device {}", device.path());
+                    platform::psid_revert(device.handle(), psid).await?;
+                }
+                _ => {
+                    return Err(SafeEraseError::UnsupportedAlgorithm(algorithm.to_string()));
+                }
             }
-            _ => {
-                return Err(SafeEraseError::UnsupportedAlgorithm(algorithm.to_string()));
+        }
+        if let Some(journal) = &journal {
+            if let Err(e) = journal.clear(&device_info.serial) {
+                warn!("Failed to clear erase record of {}: {}", device.path(), e);
             }
         }
         
@@ -426,77 +1098,207 @@ impl WipeEngine {
             passes_completed: 1,
             average_speed: speed,
             peak_speed: speed,
+            passes: vec![PassRecord {
+                pass: 1,
+                pattern: algorithm.to_string(),
+                bytes_written: device_info.size,
+                duration,
+                average_speed: speed,
+                verified: None,
+            }],
+            resumed_from,
+            skipped_ranges: Vec::new(),
         })
     }
     
+    /// Wait for, resume or accept the erase recorded by an earlier operation;
+    /// returns that operation's id when its erase now counts as done
+    async fn settle_earlier_erase(device: &Device, pending: &PendingErase) -> Result<Option<Uuid>> {
+        info!(
+            "Device {} has an unfinished {} from operation {} started {}",
+            device.path(), pending.algorithm, pending.operation_id, pending.started_at
+        );
+        let state = loop {
+            match platform::hardware_erase_state(device.handle(), &pending.algorithm).await {
+                Ok(HardwareEraseState::InProgress { progress }) => {
+                    info!("Earlier erase of {} still running ({:.0}%)", device.path(), progress.unwrap_or(0.0) * 100.0);
+                    sleep(ERASE_POLL_INTERVAL).await;
+                }
+                Ok(state) => break state,
+                Err(e) => {
+                    warn!("Cannot read erase state of {}: {}", device.path(), e);
+                    break HardwareEraseState::Unknown;
+                }
+            }
+        };
+        
+        match state {
+            HardwareEraseState::Completed => {
+                info!("Earlier erase of {} completed; finalizing operation {}", device.path(), pending.operation_id);
+                Ok(Some(pending.operation_id))
+            }
+            HardwareEraseState::Interrupted if pending.algorithm == WipeAlgorithm::ATASecureErase => {
+                info!("Resuming interrupted ATA Secure Erase on device {}", device.path());
+                platform::ata_resume_secure_erase(device.handle(), false).await?;
+                Ok(Some(pending.operation_id))
+            }
+            state => {
+                warn!("Earlier erase of {} ended {:?}; erasing again", device.path(), state);
+                Ok(None)
+            }
+        }
+    }
+    
     /// Wipe device with a specific pattern
+    #[allow(clippy::too_many_arguments)]
     async fn wipe_with_pattern(
         device: &Device,
         pattern: &WipePattern,
         options: &WipeOptions,
+        master_seed: Option<&MasterSeed>,
         cancel_token: &tokio_util::sync::CancellationToken,
+        progress: &mut ProgressReporter,
+        pass_index: usize,
+        checkpointer: &mut Checkpointer,
     ) -> Result<u64> {
         let device_info = device.get_info().await?;
         let capabilities = device.capabilities();
         
-        let block_size = options.block_size.min(1024 * 1024); // Max 1MB blocks
-        let total_blocks = (device_info.size + block_size as u64 - 1) / block_size as u64;
+        let sector_size = capabilities.logical_sector_size as u64;
+        let block_size = options.pass_block_size(sector_size);
+        let spans = regions::byte_spans(&options.regions, capabilities.logical_sector_size, device_info.size);
         
-        let mut bytes_written = 0u64;
-        let mut previous_data: Option<Vec<u8>> = None;
+        // Generation runs ahead on its own thread so the drive is not idle meanwhile
+        let generator = BlockGenerator::new(pattern.clone(), master_seed.cloned(), pass_index, spans, block_size, sector_size)
+            .with_queue_depth(options.write_backend.queue_depth())
+            .with_digests(options.paranoid_writes)
+            .starting_at(checkpointer.start_bytes(pass_index));
+        let mut pipeline = BlockPipeline::start(generator, options.pipeline_depth);
+        let mut bytes_written = checkpointer.start_bytes(pass_index);
+        let mut block_index = 0u64;
         
-        for block_index in 0..total_blocks {
+        while let Some(batch) = pipeline.next_batch().await? {
             if cancel_token.is_cancelled() {
                 return Err(SafeEraseError::WipeCancelled);
             }
+            progress.hold_if_paused(cancel_token).await?;
             
-            let current_block_size = std::cmp::min(
-                block_size,
-                (device_info.size - bytes_written) as usize
-            );
+            let (first_lba, last_lba) = (batch[0].start_lba, batch[batch.len() - 1].end_lba);
+            let results = guard_stall(
+                options.stall_timeout,
+                first_lba,
+                last_lba,
+                Self::write_batch(device, &batch, &options.write_backend),
+            ).await?;
             
-            // Generate pattern data
-            let pattern_data = pattern.generate_data(current_block_size, previous_data.as_deref());
-            
-            // Write to device (this would be implemented with actual I/O)
-            // For now, this is a placeholder
-            let start_lba = bytes_written / capabilities.logical_sector_size as u64;
-            
-            // In a real implementation, you would write the pattern_data to the device
-            // platform::write_sectors(device.handle(), start_lba, &pattern_data).await?;
-            
-            bytes_written += current_block_size as u64;
-            previous_data = Some(pattern_data);
-            
-            // Small delay to prevent overwhelming the system
-            if block_index % 100 == 0 {
-                sleep(Duration::from_millis(1)).await;
+            for (block, result) in batch.iter().zip(results) {
+                let (start_lba, end_lba) = (block.start_lba, block.end_lba);
+                let written = match result {
+                    Err(e) if options.skip_bad_sectors && matches!(e.root(), SafeEraseError::DeviceIoError(_)) => {
+                        warn!("Skipping LBA {}-{} after write error: {}", start_lba, end_lba, e);
+                        // Later passes fail on the same sectors; record them once
+                        let skipped = &mut checkpointer.checkpoint.skipped_ranges;
+                        if !skipped.iter().any(|range| range.start_lba == start_lba && range.end_lba == end_lba) {
+                            skipped.push(SkippedRange { start_lba, end_lba, reason: e.root().to_string() });
+                        }
+                        false
+                    }
+                    result => {
+                        result?;
+                        true
+                    }
+                };
+                
+                // Read the block just written back now and then, catching corruption on its way to the media
+                let read_back_due = written && block_index.is_multiple_of(PARANOID_READBACK_INTERVAL);
+                if let Some(digest) = block.digest.as_ref().filter(|_| read_back_due) {
+                    let mut read_back = vec![0u8; block.data.len()];
+                    platform::read_sectors(device.handle(), start_lba, &mut read_back).await
+                        .with_error_context(|| ErrorContext::new().lba(start_lba))?;
+                    check_buffer(digest, &read_back, "read back differently than written", start_lba, end_lba)?;
+                }
+                
+                bytes_written += block.data.len() as u64;
+                progress.report_bytes(pass_index, bytes_written, pattern);
+                
+                // Small delay to prevent overwhelming the system
+                if block_index.is_multiple_of(100) {
+                    sleep(Duration::from_millis(1)).await;
+                }
+                block_index += 1;
             }
+            checkpointer.save_if_due(device, pass_index, bytes_written, last_lba + 1).await;
         }
         
         Ok(bytes_written)
     }
     
-    /// Verify that the wipe was successful
-    async fn verify_wipe(device: &Device, options: &WipeOptions) -> Result<bool> {
+    /// Write a batch of blocks with the selected backend, returning the result of each
+    async fn write_batch(device: &Device, batch: &[BlockWrite], backend: &WriteBackend) -> Result<Vec<Result<()>>> {
+        // The buffers may have been corrupted in memory since they were generated
+        for block in batch {
+            if let Some(digest) = &block.digest {
+                check_buffer(digest, &block.data, "changed in memory before the write", block.start_lba, block.end_lba)?;
+            }
+        }
+        
+        let results = match backend {
+            WriteBackend::Synchronous => {
+                let mut results = Vec::with_capacity(batch.len());
+                for block in batch {
+                    results.push(platform::write_sectors(device.handle(), block.start_lba, &block.data).await);
+                }
+                results
+            }
+            WriteBackend::IoUring { queue_depth } => {
+                let blocks: Vec<(u64, &[u8])> = batch.iter().map(|block| (block.start_lba, block.data.as_slice())).collect();
+                platform::write_sectors_queued(device.handle(), &blocks, *queue_depth).await?
+            }
+        };
+        Ok(batch
+            .iter()
+            .zip(results)
+            .map(|(block, result)| result.map(|_| ()).with_error_context(|| ErrorContext::new().lba(block.start_lba)))
+            .collect())
+    }
+    
+    /// Verify that the wipe was successful by reading samples back and
+    /// checking each against what the final pass wrote
+    async fn verify_wipe(device: &Device, options: &WipeOptions, final_content: &FinalContent) -> Result<bool> {
         let device_info = device.get_info().await?;
-        let sample_size = 4096; // 4KB samples
+        let physical_sector_size = (device.capabilities().physical_sector_size as usize).max(512);
+        let sample_size = options.verification_sample_size.max(1).div_ceil(physical_sector_size) * physical_sector_size;
         let num_samples = options.verification_samples.min(1000); // Max 1000 samples
         
+        let sector_size = device.capabilities().logical_sector_size;
+        let wiped_size = regions::wiped_bytes(&options.regions, sector_size, device_info.size);
+        let checker = VerificationEngine::new()?;
+        
         info!("Verifying wipe with {} samples", num_samples);
         
         for i in 0..num_samples {
-            // Calculate random offset for this sample
-            let max_offset = device_info.size.saturating_sub(sample_size as u64);
-            let offset = (i as u64 * max_offset) / num_samples as u64;
+            // Calculate an offset for this sample within what was wiped
+            let max_offset = wiped_size.saturating_sub(sample_size as u64);
+            let position = (i as u64 * max_offset) / num_samples as u64;
+            let (offset, content_offset) = if options.regions.is_empty() {
+                (position, position)
+            } else {
+                regions::locate(&options.regions, sector_size, position, sample_size as u64)
+            };
+            let misalignment = offset % sector_size as u64;
+            let (offset, content_offset) = (offset - misalignment, content_offset - misalignment);
+            let start_lba = offset / sector_size as u64;
             
-            // Read sample data (placeholder implementation)
             let mut buffer = vec![0u8; sample_size];
-            // platform::read_sectors(device.handle(), offset / 512, &mut buffer).await?;
+            let read = platform::read_sectors(device.handle(), start_lba, &mut buffer).await
+                .with_error_context(|| ErrorContext::new().lba(start_lba))?;
+            buffer.truncate(read);
+            if buffer.is_empty() {
+                continue;
+            }
             
-            // Check if data appears to be wiped (all zeros or random)
-            if !Self::is_data_wiped(&buffer) {
-                warn!("Verification failed at offset {}", offset);
+            if !checker.check_sample(final_content, &buffer, offset, content_offset)? {
+                warn!("Verification failed at LBA {}", start_lba);
                 return Ok(false);
             }
         }
@@ -505,43 +1307,400 @@ impl WipeEngine {
         Ok(true)
     }
     
-    /// Check if data appears to be properly wiped
-    fn is_data_wiped(data: &[u8]) -> bool {
-        // Simple heuristic: check for patterns that indicate unwiped data
-        // In a real implementation, this would be more sophisticated
-        
-        // Check if all bytes are the same (likely wiped)
-        let first_byte = data[0];
-        if data.iter().all(|&b| b == first_byte) {
-            return true;
-        }
-        
-        // Check for high entropy (random data)
-        let unique_bytes: std::collections::HashSet<u8> = data.iter().cloned().collect();
-        if unique_bytes.len() > data.len() / 4 {
-            return true; // High diversity suggests random data
-        }
-        
-        false
-    }
-    
     /// Get active wipe operations
     pub async fn get_active_operations(&self) -> Vec<Uuid> {
         let active_ops = self.active_operations.read().await;
         active_ops.iter().map(|op| op.id).collect()
     }
     
+    /// Cancel the active wipe operation on a device, returning its operation ID
+    pub async fn cancel_device_operation(&self, device_path: &str, cancellation: Cancellation) -> Result<Uuid> {
+        let active_ops = self.active_operations.read().await;
+        if let Some(operation) = active_ops.iter().find(|op| op.device.path() == device_path) {
+            info!("Cancelling wipe operation {} on device {}: {}", operation.id, device_path, cancellation);
+            operation.cancel(cancellation);
+            Ok(operation.id)
+        } else {
+            Err(SafeEraseError::Internal(format!("No active operation on device {}", device_path)))
+        }
+    }
+    
     /// Cancel a wipe operation
-    pub async fn cancel_operation(&self, operation_id: Uuid) -> Result<()> {
+    pub async fn cancel_operation(&self, operation_id: Uuid, cancellation: Cancellation) -> Result<()> {
         let active_ops = self.active_operations.read().await;
         if let Some(operation) = active_ops.iter().find(|op| op.id == operation_id) {
-            operation.cancel_token.cancel();
-            info!("Cancelled wipe operation {}", operation_id);
+            info!("Cancelling wipe operation {}: {}", operation_id, cancellation);
+            operation.cancel(cancellation);
             Ok(())
         } else {
             Err(SafeEraseError::Internal(format!("Operation {} not found", operation_id)))
         }
     }
+    
+    /// Hold an overwrite before its next block until it is resumed; time
+    /// spent paused does not count against its time limits
+    pub async fn pause_operation(&self, operation_id: Uuid) -> Result<()> {
+        let active_ops = self.active_operations.read().await;
+        match active_ops.iter().find(|op| op.id == operation_id) {
+            Some(operation) => operation.set_paused(true),
+            None => Err(SafeEraseError::Internal(format!("Operation {} not found", operation_id))),
+        }
+    }
+    
+    /// Let a paused operation continue where it stopped
+    pub async fn resume_operation(&self, operation_id: Uuid) -> Result<()> {
+        let active_ops = self.active_operations.read().await;
+        match active_ops.iter().find(|op| op.id == operation_id) {
+            Some(operation) => operation.set_paused(false),
+            None => Err(SafeEraseError::Internal(format!("Operation {} not found", operation_id))),
+        }
+    }
+}
+
+impl WipeOperation {
+    /// Hold or release the operation before its next block
+    fn set_paused(&self, paused: bool) -> Result<()> {
+        if self.algorithm.is_hardware_based() {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "{} runs inside the drive and cannot be paused", self.algorithm
+            )));
+        }
+        if self.pause.send_replace(paused) != paused {
+            info!("{} wipe operation {}", if paused { "Pausing" } else { "Resuming" }, self.id);
+        }
+        Ok(())
+    }
+    
+    fn cancel(&self, cancellation: Cancellation) {
+        if let Err(later) = self.cancellation.set(cancellation) {
+            debug!("Operation {} is already being cancelled; ignoring {}", self.id, later);
+        }
+        self.cancel_token.cancel();
+    }
+}
+
+impl WipeResult {
+    /// Mark the wipe as failed by the removal of its device
+    fn record_device_removed(&mut self) {
+        error!("{} was removed during the wipe", self.device_path);
+        self.status = WipeStatus::DeviceRemoved;
+        self.error_message = Some(SafeEraseError::DeviceRemoved(self.device_path.clone()).to_string());
+    }
+    
+    /// Record why the wipe was cancelled, as given to the engine
+    fn record_cancellation(&mut self, cancellation: &OnceLock<Cancellation>) {
+        let cancellation = cancellation.get().cloned().unwrap_or_else(|| Cancellation::new(CancelReason::UserRequest));
+        self.error_message = Some(cancellation.to_string());
+        self.cancellation = Some(cancellation);
+    }
+}
+
+impl PauseGate {
+    fn new(paused: watch::Receiver<bool>) -> Self {
+        Self { paused, clock: Arc::default() }
+    }
+    
+    /// Time spent paused so far, including a pause still going on
+    fn paused_for(&self) -> Duration {
+        let clock = self.clock.lock().unwrap_or_else(|e| e.into_inner());
+        clock.total + clock.since.map(|since| since.elapsed()).unwrap_or_default()
+    }
+    
+    /// Wait until the operation is no longer paused or is cancelled
+    async fn wait_while_paused(&self, cancel_token: &tokio_util::sync::CancellationToken) -> Result<()> {
+        let mut paused = self.paused.clone();
+        self.clock.lock().unwrap_or_else(|e| e.into_inner()).since = Some(Instant::now());
+        let resumed = tokio::select! {
+            _ = cancel_token.cancelled() => false,
+            // A dropped sender means the operation is gone; nothing will resume it
+            resumed = paused.wait_for(|paused| !paused) => resumed.is_ok(),
+        };
+        let mut clock = self.clock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(since) = clock.since.take() {
+            clock.total += since.elapsed();
+        }
+        if resumed { Ok(()) } else { Err(SafeEraseError::WipeCancelled) }
+    }
+    
+    fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+    
+    /// Run `future` for at most `limit`, not counting time spent paused;
+    /// `None` once the limit is reached
+    async fn limit<F: Future>(&self, limit: Duration, future: F) -> Option<F::Output> {
+        let start = Instant::now();
+        let paused_before = self.paused_for();
+        let mut future = std::pin::pin!(future);
+        loop {
+            let active = start.elapsed().saturating_sub(self.paused_for().saturating_sub(paused_before));
+            let remaining = limit.checked_sub(active).filter(|remaining| !remaining.is_zero())?;
+            if let Ok(output) = tokio::time::timeout(remaining, &mut future).await {
+                return Some(output);
+            }
+        }
+    }
+}
+
+impl Checkpointer {
+    fn new(journal: Option<CheckpointJournal>, checkpoint: WipeCheckpoint) -> Self {
+        Self {
+            journal,
+            resumed: checkpoint.position,
+            checkpoint,
+            last_saved: Instant::now(),
+        }
+    }
+    
+    /// Bytes of pass `pass_index` written before the operation was resumed
+    fn start_bytes(&self, pass_index: usize) -> u64 {
+        if pass_index == self.resumed.pass_index { self.resumed.pass_bytes } else { 0 }
+    }
+    
+    /// Record the position within a pass once [`CHECKPOINT_INTERVAL`] has passed since the last checkpoint
+    async fn save_if_due(&mut self, device: &Device, pass_index: usize, pass_bytes: u64, next_lba: u64) {
+        if self.journal.is_none() || self.last_saved.elapsed() < CHECKPOINT_INTERVAL {
+            return;
+        }
+        self.checkpoint.position = CheckpointPosition { pass_index, pass_bytes };
+        self.checkpoint.next_lba = next_lba;
+        self.save(device).await;
+    }
+    
+    /// Add a finished pass and record that the next one starts from its beginning
+    async fn finish_pass(&mut self, device: &Device, record: PassRecord) {
+        // Pass numbers count from 1, so this is the index of the next pass
+        self.checkpoint.position = CheckpointPosition { pass_index: record.pass, pass_bytes: 0 };
+        self.checkpoint.next_lba = 0;
+        self.checkpoint.passes.push(record);
+        self.save(device).await;
+    }
+    
+    /// Flush the device and record the checkpoint; the wipe goes on without one if either fails
+    async fn save(&mut self, device: &Device) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        self.last_saved = Instant::now();
+        // A resumed run skips what the checkpoint covers, so it must be on the media
+        if let Err(e) = platform::flush_cache(device.handle()).await {
+            warn!("Cannot flush {} for a checkpoint: {}", device.path(), e);
+            return;
+        }
+        self.checkpoint.updated_at = Utc::now();
+        if let Err(e) = journal.record(&self.checkpoint) {
+            warn!("Cannot record checkpoint of operation {}: {}", self.checkpoint.operation_id, e);
+        }
+    }
+    
+    /// Remove the checkpoint once every pass is written
+    fn clear(&self) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.clear(&self.checkpoint.device_serial) {
+                warn!("Cannot remove checkpoint of operation {}: {}", self.checkpoint.operation_id, e);
+            }
+        }
+    }
+}
+
+/// Checks that an operation's device is still attached and cancels the
+/// operation once it is not; stops when dropped
+struct PresenceWatch(tokio::task::JoinHandle<()>);
+
+impl PresenceWatch {
+    fn start(device: Arc<Device>, cancel_token: tokio_util::sync::CancellationToken) -> Self {
+        Self(tokio::spawn(async move {
+            loop {
+                sleep(DEVICE_PRESENCE_INTERVAL).await;
+                if !platform::device_present(device.handle()).await {
+                    cancel_token.cancel();
+                    return;
+                }
+            }
+        }))
+    }
+}
+
+impl Drop for PresenceWatch {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Capacity of the progress broadcast channel
+const PROGRESS_CHANNEL_CAPACITY: usize = 256;
+
+/// Largest block written in one request
+const MAX_WRITE_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// How often a drive still running an earlier hardware erase is polled
+const ERASE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the device of a running wipe is checked for removal
+const DEVICE_PRESENCE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often an overwrite with a `checkpoint_dir` records its position
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Blocks between read-backs in paranoid mode
+const PARANOID_READBACK_INTERVAL: u64 = 64;
+
+/// Deepest io_uring queue accepted for [`WriteBackend::IoUring`]
+const MAX_QUEUE_DEPTH: usize = 4096;
+
+/// Publishes throttled progress snapshots for a single operation
+#[derive(Debug)]
+struct ProgressReporter {
+    operation_id: Uuid,
+    device_path: String,
+    algorithm: WipeAlgorithm,
+    total_passes: usize,
+    total_bytes: u64,
+    started_at: DateTime<Utc>,
+    start_instant: Instant,
+    interval: Duration,
+    last_report: Option<(Instant, u64)>,
+    peak_speed: f64,
+    history: ProgressHistory,
+    bay: Option<DriveBay>,
+    pause: PauseGate,
+}
+
+impl ProgressReporter {
+    fn new(
+        operation_id: Uuid,
+        device_path: &str,
+        algorithm: WipeAlgorithm,
+        total_bytes: u64,
+        started_at: DateTime<Utc>,
+        interval: Duration,
+        history: ProgressHistory,
+    ) -> Self {
+        let total_passes = algorithm.patterns().len().max(1);
+        Self {
+            operation_id,
+            device_path: device_path.to_string(),
+            algorithm,
+            total_passes,
+            total_bytes,
+            started_at,
+            start_instant: Instant::now(),
+            interval,
+            last_report: None,
+            peak_speed: 0.0,
+            history,
+            bay: None,
+            // Never paused unless an operation attaches its own gate
+            pause: PauseGate::new(watch::channel(false).1),
+        }
+    }
+    
+    fn with_bay(mut self, bay: Option<DriveBay>) -> Self {
+        self.bay = bay;
+        self
+    }
+    
+    /// Hold between blocks while `pause` says so
+    fn with_pause_gate(mut self, pause: PauseGate) -> Self {
+        self.pause = pause;
+        self
+    }
+    
+    /// Report the pause and wait for the operation to be resumed
+    async fn hold_if_paused(&mut self, cancel_token: &tokio_util::sync::CancellationToken) -> Result<()> {
+        if !self.pause.is_paused() {
+            return Ok(());
+        }
+        self.report_status(WipeStatus::Paused);
+        let gate = self.pause.clone();
+        gate.wait_while_paused(cancel_token).await?;
+        // The pause would otherwise drag down the next speed sample
+        if let Some((_, processed)) = self.last_report {
+            self.last_report = Some((Instant::now(), processed));
+        }
+        self.report_status(WipeStatus::Wiping);
+        Ok(())
+    }
+    
+    fn set_total_passes(&mut self, total_passes: usize) {
+        self.total_passes = total_passes.max(1);
+    }
+    
+    /// Report bytes written in the current pass, throttled to the configured interval
+    fn report_bytes(&mut self, pass_index: usize, pass_bytes: u64, pattern: &WipePattern) {
+        let now = Instant::now();
+        let pass_complete = pass_bytes >= self.total_bytes;
+        if let Some((last, _)) = self.last_report {
+            if now.duration_since(last) < self.interval && !pass_complete {
+                return;
+            }
+        }
+        
+        let processed = pass_index as u64 * self.total_bytes + pass_bytes;
+        let current_speed = match self.last_report {
+            Some((last, last_processed)) if now > last => {
+                processed.saturating_sub(last_processed) as f64 / now.duration_since(last).as_secs_f64()
+            }
+            _ => 0.0,
+        };
+        self.peak_speed = self.peak_speed.max(current_speed);
+        self.last_report = Some((now, processed));
+        
+        let snapshot = self.snapshot(WipeStatus::Wiping, pass_index + 1, processed, current_speed, Some(pattern.description()));
+        self.history.publish(snapshot);
+    }
+    
+    /// Report a status transition without changing byte counters
+    fn report_status(&mut self, status: WipeStatus) {
+        let processed = self.last_report.map(|(_, bytes)| bytes).unwrap_or(0);
+        let current_pass = (processed / self.total_bytes.max(1)) as usize;
+        let snapshot = self.snapshot(status, current_pass.min(self.total_passes), processed, 0.0, None);
+        self.history.publish(snapshot);
+    }
+    
+    fn snapshot(
+        &self,
+        status: WipeStatus,
+        current_pass: usize,
+        processed: u64,
+        current_speed: f64,
+        current_pattern: Option<String>,
+    ) -> WipeProgress {
+        let overall_bytes = self.total_bytes.saturating_mul(self.total_passes as u64);
+        let elapsed = self.start_instant.elapsed().as_secs_f64();
+        let average_speed = if elapsed > 0.0 { processed as f64 / elapsed } else { 0.0 };
+        let percentage = if overall_bytes > 0 {
+            (processed as f64 / overall_bytes as f64 * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        let estimated_remaining = if average_speed > 0.0 && status == WipeStatus::Wiping {
+            Some(Duration::from_secs_f64(overall_bytes.saturating_sub(processed) as f64 / average_speed))
+        } else {
+            None
+        };
+        
+        WipeProgress {
+            operation_id: self.operation_id,
+            device_path: self.device_path.clone(),
+            bay: self.bay.clone(),
+            algorithm: self.algorithm.clone(),
+            current_pass,
+            total_passes: self.total_passes,
+            bytes_processed: processed,
+            total_bytes: overall_bytes,
+            percentage,
+            current_speed,
+            average_speed,
+            estimated_remaining,
+            current_pattern,
+            status,
+            started_at: self.started_at,
+            last_updated: Utc::now(),
+            // Assigned when published
+            sequence: 0,
+        }
+    }
 }
 
 /// Internal statistics for wipe operations
@@ -551,6 +1710,98 @@ struct WipeStats {
     passes_completed: usize,
     average_speed: f64,
     peak_speed: f64,
+    passes: Vec<PassRecord>,
+    /// Earlier operation whose interrupted hardware erase this one finished
+    resumed_from: Option<Uuid>,
+    skipped_ranges: Vec<SkippedRange>,
+}
+
+/// Run one write, failing with `OperationStalled` if it makes no progress within `limit`
+async fn guard_stall<F, T>(limit: Option<Duration>, start_lba: u64, end_lba: u64, write: F) -> Result<T>
+where
+    F: std::future::Future<Output = Result<T>>,
+{
+    let Some(limit) = limit else {
+        return write.await;
+    };
+    match tokio::time::timeout(limit, write).await {
+        Ok(result) => result,
+        Err(_) => {
+            error!("No data written for {:?} at LBA {}-{}", limit, start_lba, end_lba);
+            Err(SafeEraseError::OperationStalled { start_lba, end_lba, stalled_for: limit })
+        }
+    }
+}
+
+/// Fail the wipe if `data` no longer matches the digest taken when it was generated
+fn check_buffer(digest: &[u8; 32], data: &[u8], what: &str, start_lba: u64, end_lba: u64) -> Result<()> {
+    if buffer_digest(data) == *digest {
+        return Ok(());
+    }
+    error!("Pattern data for LBA {}-{} {}", start_lba, end_lba, what);
+    Err(SafeEraseError::WipeFailed(format!("Pattern data for LBA {}-{} {}", start_lba, end_lba, what)))
+}
+
+fn default_stall_timeout() -> Option<Duration> {
+    Some(Duration::from_secs(120))
+}
+
+fn default_pipeline_depth() -> usize {
+    1
+}
+
+impl SkippedRange {
+    /// Number of sectors in the range
+    pub fn sectors(&self) -> u64 {
+        self.end_lba - self.start_lba + 1
+    }
+    
+    /// Whether `len` bytes starting at byte `offset` touch the range
+    pub fn overlaps(&self, offset: u64, len: u64, sector_size: u32) -> bool {
+        let start = self.start_lba * u64::from(sector_size);
+        let end = (self.end_lba + 1) * u64::from(sector_size);
+        offset < end && start < offset + len
+    }
+}
+
+impl WipeOptions {
+    /// Size of the blocks patterns are generated and written in (capped at 1MB)
+    pub fn write_block_size(&self) -> usize {
+        self.block_size.min(MAX_WRITE_BLOCK_SIZE)
+    }
+    
+    /// Write block size rounded to whole sectors, as passes are written; direct I/O transfers whole sectors
+    pub(crate) fn pass_block_size(&self, sector_size: u64) -> u64 {
+        (self.write_block_size() as u64).max(sector_size) / sector_size * sector_size
+    }
+}
+
+impl WriteBackend {
+    /// Blocks submitted together
+    pub fn queue_depth(&self) -> usize {
+        match self {
+            WriteBackend::Synchronous => 1,
+            WriteBackend::IoUring { queue_depth } => *queue_depth,
+        }
+    }
+    
+    /// Check the queue depth and that this build can use the backend
+    pub fn validate(&self) -> Result<()> {
+        let WriteBackend::IoUring { queue_depth } = *self else {
+            return Ok(());
+        };
+        if !(1..=MAX_QUEUE_DEPTH).contains(&queue_depth) {
+            return Err(SafeEraseError::InvalidParameter(format!(
+                "io_uring queue depth must be between 1 and {}, got {}", MAX_QUEUE_DEPTH, queue_depth
+            )));
+        }
+        if !platform::IO_URING {
+            return Err(SafeEraseError::UnsupportedPlatform(
+                "The io_uring write backend needs a Linux build with the io-uring feature".to_string()
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for WipeOptions {
@@ -558,12 +1809,32 @@ impl Default for WipeOptions {
         Self {
             verify_wipe: true,
             verification_samples: 100,
+            verification_sample_size: verification::DEFAULT_SAMPLE_SIZE,
             clear_hpa_dco: true,
+            hpa_policy: HpaPolicy::default(),
             block_size: 1024 * 1024, // 1MB
             max_concurrent_ops: 1,
-            operation_timeout: Some(Duration::from_secs(24 * 60 * 60)), // 24 hours
+            operation_timeout: None,
+            pass_timeout: None,
+            stall_timeout: default_stall_timeout(),
+            skip_bad_sectors: false,
+            paranoid_writes: false,
+            write_backend: WriteBackend::default(),
+            pipeline_depth: default_pipeline_depth(),
+            regions: Vec::new(),
             prefer_hardware_erase: true,
             progress_interval: Duration::from_secs(1),
+            compute_device_digest: false,
+            discard_after_wipe: false,
+            scan_sensitive_data: false,
+            work_order: None,
+            ticket_url: None,
+            smart_monitor: None,
+            erase_journal_dir: None,
+            checkpoint_dir: None,
+            seed_key_file: None,
+            storage_key: None,
+            psid: None,
         }
     }
 }
@@ -577,10 +1848,12 @@ impl std::fmt::Display for WipeStatus {
             WipeStatus::DetectingDCO => write!(f, "Detecting DCO"),
             WipeStatus::ClearingDCO => write!(f, "Clearing DCO"),
             WipeStatus::Wiping => write!(f, "Wiping"),
+            WipeStatus::Paused => write!(f, "Paused"),
             WipeStatus::Verifying => write!(f, "Verifying"),
             WipeStatus::Completed => write!(f, "Completed"),
             WipeStatus::Failed => write!(f, "Failed"),
             WipeStatus::Cancelled => write!(f, "Cancelled"),
+            WipeStatus::DeviceRemoved => write!(f, "Device removed"),
         }
     }
 }
@@ -595,6 +1868,7 @@ mod tests {
         assert!(options.verify_wipe);
         assert_eq!(options.verification_samples, 100);
         assert!(options.clear_hpa_dco);
+        assert_eq!(options.hpa_policy, HpaPolicy::Clear);
         assert!(options.prefer_hardware_erase);
     }
     
@@ -605,18 +1879,151 @@ mod tests {
         assert_eq!(WipeStatus::Completed.to_string(), "Completed");
     }
     
+    #[tokio::test]
+    async fn test_random_final_pass_passes_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 8 << 20]).unwrap();
+        let device = Arc::new(Device::open(path.to_str().unwrap()).await.unwrap());
+        
+        let engine = WipeEngine::new().unwrap();
+        let options = WipeOptions { verify_wipe: true, ..WipeOptions::default() };
+        let result = engine.wipe_device(&device, WipeAlgorithm::NIST80088, options).await.unwrap();
+        assert_eq!(result.status, WipeStatus::Completed);
+        assert_eq!(result.verification_passed, Some(true));
+    }
+    
+    #[tokio::test]
+    async fn test_complement_pass_stays_within_region() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 64 * 1024]).unwrap();
+        let device = Arc::new(Device::open(path.to_str().unwrap()).await.unwrap());
+        
+        // Two full 4 KiB blocks and one sector
+        let region = WipeRegion { label: None, start_lba: 0, end_lba: 16 };
+        let options = WipeOptions {
+            block_size: 4096,
+            regions: vec![region],
+            verify_wipe: false,
+            ..WipeOptions::default()
+        };
+        let algorithm = WipeAlgorithm::Custom(vec![WipePattern::Zeros, WipePattern::Complement]);
+        let engine = WipeEngine::new().unwrap();
+        let result = engine.wipe_device(&device, algorithm, options).await.unwrap();
+        assert_eq!(result.status, WipeStatus::Completed);
+        
+        let contents = std::fs::read(&path).unwrap();
+        assert!(contents[8704..].iter().all(|&b| b == 0xA5));
+    }
+    
+    #[tokio::test]
+    async fn test_timed_out_operation_leaves_active_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        std::fs::write(&path, vec![0xA5; 64 << 20]).unwrap();
+        let device = Arc::new(Device::open(path.to_str().unwrap()).await.unwrap());
+        
+        let engine = WipeEngine::new().unwrap();
+        let options = WipeOptions { operation_timeout: Some(Duration::from_millis(1)), ..WipeOptions::default() };
+        let result = engine.wipe_device(&device, WipeAlgorithm::Gutmann, options).await;
+        assert!(matches!(result, Err(SafeEraseError::Timeout(_))));
+        assert!(engine.get_active_operations().await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_guard_stall_reports_lba_range() {
+        let limit = Some(Duration::from_millis(10));
+        assert!(guard_stall(limit, 0, 7, async { Ok(()) }).await.is_ok());
+        
+        let stalled = guard_stall(limit, 2048, 4095, std::future::pending::<Result<usize>>()).await.unwrap_err();
+        assert!(matches!(
+            stalled,
+            SafeEraseError::OperationStalled { start_lba: 2048, end_lba: 4095, .. }
+        ));
+        assert_eq!(stalled.code().name, "OPERATION_STALLED");
+    }
+    
+    #[tokio::test]
+    async fn test_pause_gate_excludes_paused_time() {
+        let (pause, paused) = watch::channel(true);
+        let gate = PauseGate::new(paused);
+        let cancel_token = tokio_util::sync::CancellationToken::new();
+        
+        let waiter = gate.clone();
+        let work = async move {
+            waiter.wait_while_paused(&tokio_util::sync::CancellationToken::new()).await.unwrap();
+            sleep(Duration::from_millis(20)).await;
+        };
+        let resume = async {
+            sleep(Duration::from_millis(100)).await;
+            pause.send_replace(false);
+        };
+        // 20 ms of work fits in 60 ms even though it finishes after 120 ms
+        let (limited, _) = tokio::join!(gate.limit(Duration::from_millis(60), work), resume);
+        assert!(limited.is_some());
+        assert!(gate.paused_for() >= Duration::from_millis(100));
+        
+        pause.send_replace(true);
+        cancel_token.cancel();
+        assert!(matches!(gate.wait_while_paused(&cancel_token).await, Err(SafeEraseError::WipeCancelled)));
+    }
+    
     #[test]
-    fn test_is_data_wiped() {
-        // All zeros should be considered wiped
-        let zeros = vec![0u8; 100];
-        assert!(WipeEngine::is_data_wiped(&zeros));
-        
-        // All same value should be considered wiped
-        let ones = vec![0xFFu8; 100];
-        assert!(WipeEngine::is_data_wiped(&ones));
-        
-        // High entropy data should be considered wiped
-        let random: Vec<u8> = (0..100).map(|i| (i * 7 + 13) as u8).collect();
-        assert!(WipeEngine::is_data_wiped(&random));
+    fn test_write_backend_options() {
+        let mut value = serde_json::to_value(WipeOptions::default()).unwrap();
+        value["write_backend"] = serde_json::json!({ "io_uring": { "queue_depth": 32 } });
+        let options: WipeOptions = serde_json::from_value(value).unwrap();
+        assert_eq!(options.write_backend, WriteBackend::IoUring { queue_depth: 32 });
+        assert_eq!(options.write_backend.queue_depth(), 32);
+        assert_eq!(WriteBackend::default().queue_depth(), 1);
+        
+        assert!(WriteBackend::Synchronous.validate().is_ok());
+        assert!(matches!(
+            WriteBackend::IoUring { queue_depth: 0 }.validate(),
+            Err(SafeEraseError::InvalidParameter(_))
+        ));
+        assert_eq!(WriteBackend::IoUring { queue_depth: 64 }.validate().is_ok(), platform::IO_URING);
+    }
+    
+    #[test]
+    fn test_check_buffer_detects_corruption() {
+        let mut data = WipePattern::PseudoRandom(3).generate_data(4096, None);
+        let digest = buffer_digest(&data);
+        assert!(check_buffer(&digest, &data, "changed", 0, 7).is_ok());
+        
+        data[1000] ^= 0x10;
+        let error = check_buffer(&digest, &data, "changed in memory before the write", 0, 7).unwrap_err();
+        assert!(error.to_string().contains("LBA 0-7 changed in memory"));
+    }
+    
+    #[test]
+    fn test_skipped_range_overlap() {
+        let range = SkippedRange { start_lba: 8, end_lba: 15, reason: "medium error".to_string() };
+        assert_eq!(range.sectors(), 8);
+        assert!(range.overlaps(4096, 512, 512));
+        assert!(range.overlaps(0, 4097, 512));
+        assert!(range.overlaps(8191, 4096, 512));
+        assert!(!range.overlaps(0, 4096, 512));
+        assert!(!range.overlaps(8192, 4096, 512));
+    }
+    
+    #[tokio::test]
+    async fn test_read_back_uses_configured_sample_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        let mut contents = vec![0u8; 4 << 20];
+        contents[512 * 1024..512 * 1024 + 16].copy_from_slice(b"quarterly report");
+        std::fs::write(&path, &contents).unwrap();
+        let device = Device::open(path.to_str().unwrap()).await.unwrap();
+        
+        // One 4 KiB sample at the start of the device only sees zeros
+        let mut options = WipeOptions { verification_samples: 1, ..WipeOptions::default() };
+        let zeros = FinalContent::Known(WipePattern::Zeros.expected_content(options.write_block_size()).unwrap());
+        assert!(WipeEngine::verify_wipe(&device, &options, &zeros).await.unwrap());
+        
+        // A 1 MiB sample reaches the data left behind
+        options.verification_sample_size = 1024 * 1024;
+        assert!(!WipeEngine::verify_wipe(&device, &options, &zeros).await.unwrap());
     }
 }
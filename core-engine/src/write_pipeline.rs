@@ -0,0 +1,258 @@
+//! Generating pattern data ahead of the writes
+//!
+//! Filling a megabyte with ChaCha20 output takes about as long as writing it
+//! to a fast SSD, so a pass that generates each block only once the previous
+//! write has finished leaves the drive idle half the time. Instead a
+//! [`BlockGenerator`] runs on a blocking thread and hands batches to the
+//! writer through a bounded channel: while one batch is written the next is
+//! generated, and at most `WipeOptions::pipeline_depth` finished batches wait
+//! in between, which caps the memory a pass holds.
+
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::algorithms::WipePattern;
+use crate::block_seeds::MasterSeed;
+use crate::error::{SafeEraseError, Result};
+
+/// Most batches accepted for `WipeOptions::pipeline_depth`
+pub(crate) const MAX_PIPELINE_DEPTH: usize = 16;
+
+/// Block of pattern data waiting to be written
+pub(crate) struct BlockWrite {
+    pub(crate) start_lba: u64,
+    pub(crate) end_lba: u64,
+    pub(crate) data: Vec<u8>,
+    /// Digest taken when the data was generated, in paranoid mode
+    pub(crate) digest: Option<[u8; 32]>,
+}
+
+/// Pattern data of one pass, in batches of up to `queue_depth` blocks that
+/// never cross from one span into the next
+pub(crate) struct BlockGenerator {
+    pattern: WipePattern,
+    master_seed: Option<MasterSeed>,
+    pass_index: usize,
+    spans: std::vec::IntoIter<(u64, u64)>,
+    /// Next byte to generate and the end of the span it lies in
+    position: (u64, u64),
+    previous_data: Option<Vec<u8>>,
+    block_size: u64,
+    sector_size: u64,
+    queue_depth: usize,
+    digests: bool,
+}
+
+/// Batches of a generator, produced on a blocking thread when the pipeline
+/// has a depth and on demand otherwise
+pub(crate) enum BlockPipeline {
+    Inline(BlockGenerator),
+    Threaded {
+        batches: mpsc::Receiver<Vec<BlockWrite>>,
+        producer: Option<JoinHandle<()>>,
+    },
+}
+
+impl BlockGenerator {
+    /// Generator for the pass `pass_index` over `spans` of `(offset, length)`
+    /// bytes; random passes derive their data from `master_seed` when given
+    pub(crate) fn new(
+        pattern: WipePattern,
+        master_seed: Option<MasterSeed>,
+        pass_index: usize,
+        spans: Vec<(u64, u64)>,
+        block_size: u64,
+        sector_size: u64,
+    ) -> Self {
+        Self {
+            pattern,
+            master_seed,
+            pass_index,
+            spans: spans.into_iter(),
+            position: (0, 0),
+            previous_data: None,
+            block_size,
+            sector_size,
+            queue_depth: 1,
+            digests: false,
+        }
+    }
+    
+    /// Put up to `queue_depth` blocks in each batch
+    pub(crate) fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = queue_depth.max(1);
+        self
+    }
+    
+    /// Hash each block as it is generated
+    pub(crate) fn with_digests(mut self, digests: bool) -> Self {
+        self.digests = digests;
+        self
+    }
+    
+    /// Skip the first `pass_bytes` of the spans, written before the pass was
+    /// interrupted; the position falls on a block boundary of its span
+    pub(crate) fn starting_at(mut self, mut pass_bytes: u64) -> Self {
+        while pass_bytes > 0 {
+            let Some((start, len)) = self.spans.next() else {
+                break;
+            };
+            if pass_bytes >= len {
+                pass_bytes -= len;
+                continue;
+            }
+            self.position = (start + pass_bytes, start + len);
+            // The complement alternates from the span start, and odd blocks follow an all-ones block
+            if self.pattern == WipePattern::Complement && (pass_bytes / self.block_size) % 2 == 1 {
+                self.previous_data = Some(self.pattern.generate_data(self.block_size as usize, None));
+            }
+            break;
+        }
+        self
+    }
+}
+
+impl Iterator for BlockGenerator {
+    type Item = Vec<BlockWrite>;
+    
+    fn next(&mut self) -> Option<Vec<BlockWrite>> {
+        while self.position.0 >= self.position.1 {
+            let (start, len) = self.spans.next()?;
+            // Each region starts the pattern afresh so it can be regenerated from the region start
+            self.position = (start, start + len);
+            self.previous_data = None;
+        }
+        
+        let (mut offset, end) = self.position;
+        let mut batch: Vec<BlockWrite> = Vec::with_capacity(self.queue_depth);
+        while batch.len() < self.queue_depth && offset < end {
+            let len = self.block_size.min(end - offset) as usize;
+            let data = match &self.master_seed {
+                Some(seed) if self.pattern == WipePattern::Random => seed.block_data(self.pass_index, offset, len),
+                _ => {
+                    let previous = batch.last().map(|block| block.data.as_slice()).or(self.previous_data.as_deref());
+                    self.pattern.generate_data(len, previous)
+                }
+            };
+            let start_lba = offset / self.sector_size;
+            batch.push(BlockWrite {
+                start_lba,
+                end_lba: start_lba + (len as u64).div_ceil(self.sector_size) - 1,
+                digest: self.digests.then(|| buffer_digest(&data)),
+                data,
+            });
+            offset += len as u64;
+        }
+        
+        self.position.0 = offset;
+        if self.pattern == WipePattern::Complement {
+            self.previous_data = batch.last().map(|block| block.data.clone());
+        }
+        Some(batch)
+    }
+}
+
+impl BlockPipeline {
+    /// Start generating up to `depth` batches ahead; 0 generates each batch
+    /// when it is asked for
+    pub(crate) fn start(generator: BlockGenerator, depth: usize) -> Self {
+        if depth == 0 {
+            return BlockPipeline::Inline(generator);
+        }
+        let (sender, batches) = mpsc::channel(depth);
+        let producer = tokio::task::spawn_blocking(move || {
+            for batch in generator {
+                // The writer stopped early; nobody needs the rest
+                if sender.blocking_send(batch).is_err() {
+                    break;
+                }
+            }
+        });
+        BlockPipeline::Threaded { batches, producer: Some(producer) }
+    }
+    
+    /// Next batch, or `None` once the pass is fully generated
+    pub(crate) async fn next_batch(&mut self) -> Result<Option<Vec<BlockWrite>>> {
+        match self {
+            BlockPipeline::Inline(generator) => Ok(generator.next()),
+            BlockPipeline::Threaded { batches, producer } => {
+                if let Some(batch) = batches.recv().await {
+                    return Ok(Some(batch));
+                }
+                // The channel also closes when the generator panicked, which
+                // must not pass for the end of the pass
+                if let Some(producer) = producer.take() {
+                    producer.await
+                        .map_err(|e| SafeEraseError::Internal(format!("Pattern generation failed: {}", e)))?;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+pub(crate) fn buffer_digest(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_batches_stay_within_spans() {
+        let spans = vec![(0, 4096), (8192, 1536)];
+        let batches: Vec<Vec<BlockWrite>> = BlockGenerator::new(WipePattern::Complement, None, 0, spans, 1024, 512)
+            .with_queue_depth(3)
+            .collect();
+        
+        let lbas: Vec<Vec<(u64, u64)>> = batches
+            .iter()
+            .map(|batch| batch.iter().map(|block| (block.start_lba, block.end_lba)).collect())
+            .collect();
+        assert_eq!(lbas, vec![
+            vec![(0, 1), (2, 3), (4, 5)],
+            vec![(6, 7)],
+            vec![(16, 17), (18, 18)],
+        ]);
+        // The complement alternates across batches and starts over in the next span
+        assert!(batches[1][0].data.iter().all(|&b| b == 0x00));
+        assert!(batches[2][0].data.iter().all(|&b| b == 0xFF));
+        assert!(batches[0][0].digest.is_none());
+    }
+    
+    #[test]
+    fn test_resumed_generator_continues_the_pass() {
+        let spans = vec![(0, 4096), (8192, 4096)];
+        let generator = || BlockGenerator::new(WipePattern::Complement, None, 0, spans.clone(), 1024, 512);
+        let full: Vec<BlockWrite> = generator().flatten().collect();
+        
+        // 5 KiB in: past the first span and one block into the second
+        let resumed: Vec<BlockWrite> = generator().starting_at(5120).flatten().collect();
+        assert_eq!(resumed.len(), 3);
+        for (resumed, full) in resumed.iter().zip(&full[5..]) {
+            assert_eq!((resumed.start_lba, resumed.end_lba), (full.start_lba, full.end_lba));
+            assert_eq!(resumed.data, full.data);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_pipeline_delivers_every_batch_in_order() {
+        for depth in [0, 1, 4] {
+            let generator = BlockGenerator::new(WipePattern::PseudoRandom(7), None, 0, vec![(0, 64 * 1024)], 4096, 512)
+                .with_queue_depth(2)
+                .with_digests(true);
+            let mut pipeline = BlockPipeline::start(generator, depth);
+            let mut next_lba = 0;
+            while let Some(batch) = pipeline.next_batch().await.unwrap() {
+                for block in batch {
+                    assert_eq!(block.start_lba, next_lba);
+                    assert_eq!(block.digest, Some(buffer_digest(&block.data)));
+                    next_lba = block.end_lba + 1;
+                }
+            }
+            assert_eq!(next_lba, 128);
+        }
+    }
+}
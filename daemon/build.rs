@@ -0,0 +1,5 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/safeerase.proto")
+        .expect("Failed to compile SafeErase protobuf definitions");
+}
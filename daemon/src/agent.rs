@@ -0,0 +1,485 @@
+//! Remote agent mode
+//!
+//! In agent mode the daemon registers with a central controller, polls it for
+//! signed job assignments, runs them through the local job manager, streams
+//! progress and state changes back and uploads the issued certificates. The
+//! HTTP protocol is described in `docs/agent-protocol.md`.
+//!
+//! Assignments are JSON documents `{"payload": "<assignment JSON>", "signature": "<base64>"}`
+//! signed by the controller with SHA-256 over the payload bytes; unsigned,
+//! expired, replayed or misaddressed assignments are rejected.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use openssl::base64;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use openssl::sign::Verifier;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use safe_erase_certificates::CertificateOptions;
+use safe_erase_core::{host, DeviceInfo, WipeAlgorithm, WipeOptions, WipeProgress};
+
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::jobs::{Job, JobManager, JobRequest};
+use crate::queue::JobPriority;
+
+/// Agent settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentConfig {
+    /// Base URL of the controller, e.g. `https://controller.example.com`
+    pub controller_url: String,
+    /// Identifier this station registers under
+    pub agent_id: String,
+    /// Bearer token presented to the controller
+    pub token: String,
+    /// PEM public key assignment signatures are verified against
+    pub controller_public_key: PathBuf,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+/// Device selected by an assignment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceTarget {
+    Path(String),
+    Serial(String),
+}
+
+/// Work the controller assigns to this agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobAssignment {
+    pub assignment_id: Uuid,
+    pub agent_id: String,
+    /// Controller user or process that created the assignment
+    pub issued_by: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub devices: Vec<DeviceTarget>,
+    pub algorithm: WipeAlgorithm,
+    #[serde(default)]
+    pub options: Option<WipeOptions>,
+    #[serde(default)]
+    pub certificate_options: Option<CertificateOptions>,
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+/// Signed assignment as delivered by the controller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAssignment {
+    /// Assignment JSON exactly as signed
+    pub payload: String,
+    /// Base64-encoded signature over `payload`
+    pub signature: String,
+}
+
+/// Registration sent when the agent starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRegistration {
+    pub agent_id: String,
+    pub hostname: String,
+    pub version: String,
+    pub devices: Vec<DeviceInfo>,
+}
+
+/// Report posted back for an assignment; built only to be serialized, so
+/// the size of its largest variants does not matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentReport {
+    /// A job was created for one of the assignment's devices
+    Accepted { device: DeviceTarget, job_id: Uuid },
+    /// A device or the whole assignment could not be processed
+    Rejected { device: Option<DeviceTarget>, reason: String },
+    /// Live progress of an assigned job
+    Progress { job_id: Uuid, progress: WipeProgress },
+    /// Job snapshot after a state change
+    State { job: Job },
+}
+
+/// Connection from this station to the central controller
+#[derive(Debug, Clone)]
+pub struct Agent {
+    config: AgentConfig,
+    jobs: JobManager,
+    client: reqwest::Client,
+    public_key: PKey<Public>,
+    /// Job ID to assignment ID for jobs created from assignments
+    assigned_jobs: Arc<Mutex<HashMap<Uuid, Uuid>>>,
+    seen_assignments: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl AgentConfig {
+    /// Validate the agent settings
+    pub fn validate(&self) -> Result<()> {
+        if !(self.controller_url.starts_with("https://") || self.controller_url.starts_with("http://")) {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "agent.controller_url must use http or https: {}", self.controller_url
+            )));
+        }
+        if self.agent_id.is_empty() || self.agent_id.contains('/') {
+            return Err(DaemonError::InvalidConfiguration(
+                "agent.agent_id must be non-empty and must not contain '/'".to_string()
+            ));
+        }
+        if self.poll_interval_secs == 0 {
+            return Err(DaemonError::InvalidConfiguration(
+                "agent.poll_interval_secs must be at least 1".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl SignedAssignment {
+    /// Verify the controller's signature and return the assignment
+    pub fn verify(&self, public_key: &PKey<Public>) -> Result<JobAssignment> {
+        let signature = base64::decode_block(&self.signature)
+            .map_err(|_| DaemonError::Forbidden("assignment signature is not valid base64".to_string()))?;
+        
+        let mut verifier = Verifier::new(MessageDigest::sha256(), public_key)
+            .map_err(|e| DaemonError::Internal(e.to_string()))?;
+        verifier
+            .update(self.payload.as_bytes())
+            .map_err(|e| DaemonError::Internal(e.to_string()))?;
+        if !verifier.verify(&signature).unwrap_or(false) {
+            return Err(DaemonError::Forbidden("assignment signature does not verify".to_string()));
+        }
+        
+        serde_json::from_str(&self.payload)
+            .map_err(|e| DaemonError::BadRequest(format!("Invalid assignment: {}", e)))
+    }
+}
+
+impl JobAssignment {
+    /// Check that the assignment is addressed to this agent and still valid
+    pub fn check(&self, agent_id: &str, now: DateTime<Utc>) -> Result<()> {
+        if self.agent_id != agent_id {
+            return Err(DaemonError::Forbidden(format!(
+                "assignment {} is addressed to agent {}", self.assignment_id, self.agent_id
+            )));
+        }
+        if now > self.expires_at {
+            return Err(DaemonError::Forbidden(format!("assignment {} has expired", self.assignment_id)));
+        }
+        if self.devices.is_empty() {
+            return Err(DaemonError::BadRequest(format!("assignment {} names no devices", self.assignment_id)));
+        }
+        Ok(())
+    }
+}
+
+impl DeviceTarget {
+    /// Find the device this target refers to
+    pub fn resolve<'a>(&self, devices: &'a [DeviceInfo]) -> Option<&'a DeviceInfo> {
+        devices.iter().find(|device| match self {
+            DeviceTarget::Path(path) => &device.path == path,
+            DeviceTarget::Serial(serial) => &device.serial == serial,
+        })
+    }
+}
+
+impl Agent {
+    /// Create an agent for the given job manager
+    pub fn new(config: AgentConfig, jobs: JobManager) -> Result<Self> {
+        let pem = std::fs::read(&config.controller_public_key).map_err(|e| {
+            DaemonError::InvalidConfiguration(format!("{}: {}", config.controller_public_key.display(), e))
+        })?;
+        let public_key = PKey::public_key_from_pem(&pem)
+            .map_err(|e| DaemonError::InvalidConfiguration(format!("Invalid controller public key: {}", e)))?;
+        
+        Ok(Self {
+            config,
+            jobs,
+            client: reqwest::Client::new(),
+            public_key,
+            assigned_jobs: Arc::new(Mutex::new(HashMap::new())),
+            seen_assignments: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+    
+    /// Register with the controller, then poll for assignments and report back
+    pub fn spawn(self) {
+        let reporter = self.clone();
+        tokio::spawn(async move { reporter.forward_events().await });
+        
+        tokio::spawn(async move {
+            while let Err(e) = self.register().await {
+                warn!("Registration with controller {} failed: {}", self.config.controller_url, e);
+                tokio::time::sleep(Duration::from_secs(self.config.poll_interval_secs)).await;
+            }
+            info!("Agent {} registered with {}", self.config.agent_id, self.config.controller_url);
+            
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+            loop {
+                interval.tick().await;
+                match self.poll().await {
+                    Ok(assignments) => {
+                        for assignment in assignments {
+                            self.accept(assignment).await;
+                        }
+                    }
+                    Err(e) => warn!("Polling controller for assignments failed: {}", e),
+                }
+            }
+        });
+    }
+    
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v1/agents/{}{}",
+            self.config.controller_url.trim_end_matches('/'),
+            self.config.agent_id,
+            path
+        )
+    }
+    
+    async fn register(&self) -> Result<()> {
+        let registration = AgentRegistration {
+            agent_id: self.config.agent_id.clone(),
+            hostname: host::hostname(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            devices: self.jobs.engine().get_device_status().await?,
+        };
+        self.send(self.client.post(self.url("/register")).json(&registration)).await
+    }
+    
+    async fn poll(&self) -> Result<Vec<SignedAssignment>> {
+        let response = self.client
+            .get(self.url("/assignments"))
+            .bearer_auth(&self.config.token)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| DaemonError::Controller(e.to_string()))?;
+        response.json().await.map_err(|e| DaemonError::Controller(e.to_string()))
+    }
+    
+    /// Verify an assignment and submit a job for each of its devices
+    async fn accept(&self, signed: SignedAssignment) {
+        let assignment = match signed.verify(&self.public_key).and_then(|assignment| {
+            assignment.check(&self.config.agent_id, Utc::now())?;
+            Ok(assignment)
+        }) {
+            Ok(assignment) => assignment,
+            Err(e) => {
+                // The assignment ID cannot be trusted, so there is nothing to report against
+                error!("Rejected assignment from controller: {}", e);
+                return;
+            }
+        };
+        
+        let assignment_id = assignment.assignment_id;
+        if !self.seen_assignments.lock().await.insert(assignment_id) {
+            return;
+        }
+        info!(
+            "Received assignment {} from {} for {} device(s)",
+            assignment_id, assignment.issued_by, assignment.devices.len()
+        );
+        
+        let devices = match self.jobs.engine().get_device_status().await {
+            Ok(devices) => devices,
+            Err(e) => {
+                self.report(assignment_id, &AgentReport::Rejected { device: None, reason: e.to_string() }).await;
+                return;
+            }
+        };
+        
+        let submitted_by = format!("controller:{}", assignment.issued_by);
+        for target in &assignment.devices {
+            let Some(device) = target.resolve(&devices) else {
+                let reason = "device not present on this station".to_string();
+                self.report(assignment_id, &AgentReport::Rejected { device: Some(target.clone()), reason }).await;
+                continue;
+            };
+            
+            let request = JobRequest {
+                device_path: device.path.clone(),
+                algorithm: assignment.algorithm.clone(),
+                options: assignment.options.clone(),
+                certificate_options: assignment.certificate_options.clone(),
+                override_interlocks: false,
+                asset: None,
+                priority: assignment.priority,
+                psid: None,
+                tenant: None,
+            };
+            let report = match self.jobs.submit(request, &submitted_by).await {
+                Ok(job) => {
+                    self.assigned_jobs.lock().await.insert(job.job_id, assignment_id);
+                    AgentReport::Accepted { device: target.clone(), job_id: job.job_id }
+                }
+                Err(e) => AgentReport::Rejected { device: Some(target.clone()), reason: e.to_string() },
+            };
+            self.report(assignment_id, &report).await;
+        }
+    }
+    
+    /// Stream progress and state of assigned jobs and upload their certificates
+    async fn forward_events(&self) {
+        let mut events = self.jobs.subscribe_events();
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Agent skipped {} job events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(assignment_id) = self.assigned_jobs.lock().await.get(&event.job_id()).copied() else {
+                continue;
+            };
+            
+            match event {
+                JobEvent::Progress { job_id, progress } => {
+                    self.report(assignment_id, &AgentReport::Progress { job_id, progress }).await;
+                }
+                JobEvent::StateChanged { job_id, state } => {
+                    if let Ok(job) = self.jobs.get(job_id).await {
+                        self.report(assignment_id, &AgentReport::State { job }).await;
+                    }
+                    if state.is_terminal() {
+                        self.assigned_jobs.lock().await.remove(&job_id);
+                    }
+                }
+                JobEvent::CertificateIssued { job_id, .. } => {
+                    if let Ok(job) = self.jobs.get(job_id).await {
+                        self.upload_certificates(assignment_id, &job).await;
+                    }
+                }
+                JobEvent::Verification { .. } => {}
+            }
+        }
+    }
+    
+    async fn upload_certificates(&self, assignment_id: Uuid, job: &Job) {
+        let files = [
+            (job.certificate_json_path.as_ref(), "json", "application/json"),
+            (job.certificate_pdf_path.as_ref(), "pdf", "application/pdf"),
+        ];
+        for (path, extension, content_type) in files {
+            let Some(path) = path else { continue };
+            let contents = match self.jobs.read_certificate(path).await {
+                Ok(contents) => contents,
+                Err(e) => {
+                    error!("Cannot read certificate {} for upload: {}", path, e);
+                    continue;
+                }
+            };
+            
+            let url = self.url(&format!(
+                "/assignments/{}/certificates/{}.{}",
+                assignment_id, job.job_id, extension
+            ));
+            let request = self.client
+                .put(url)
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(contents);
+            if let Err(e) = self.send(request).await {
+                error!("Certificate upload for job {} failed: {}", job.job_id, e);
+            }
+        }
+    }
+    
+    async fn report(&self, assignment_id: Uuid, report: &AgentReport) {
+        let request = self.client
+            .post(self.url(&format!("/assignments/{}/reports", assignment_id)))
+            .json(report);
+        if let Err(e) = self.send(request).await {
+            warn!("Report for assignment {} failed: {}", assignment_id, e);
+        }
+    }
+    
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<()> {
+        request
+            .bearer_auth(&self.config.token)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map(|_| ())
+            .map_err(|e| DaemonError::Controller(e.to_string()))
+    }
+}
+
+fn default_poll_interval_secs() -> u64 {
+    10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::pkey::Private;
+    use openssl::rsa::Rsa;
+    use openssl::sign::Signer;
+    
+    fn assignment(agent_id: &str) -> JobAssignment {
+        JobAssignment {
+            assignment_id: Uuid::new_v4(),
+            agent_id: agent_id.to_string(),
+            issued_by: "dispatcher".to_string(),
+            issued_at: Utc::now(),
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+            devices: vec![DeviceTarget::Serial("WD-123".to_string())],
+            algorithm: WipeAlgorithm::NIST80088,
+            options: None,
+            certificate_options: None,
+            priority: JobPriority::Urgent,
+        }
+    }
+    
+    fn sign(assignment: &JobAssignment, key: &PKey<Private>) -> SignedAssignment {
+        let payload = serde_json::to_string(assignment).unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(payload.as_bytes()).unwrap();
+        SignedAssignment {
+            payload,
+            signature: base64::encode_block(&signer.sign_to_vec().unwrap()),
+        }
+    }
+    
+    #[test]
+    fn test_signed_assignment_verification() {
+        let private_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem().unwrap()).unwrap();
+        
+        let signed = sign(&assignment("bench-07"), &private_key);
+        let verified = signed.verify(&public_key).unwrap();
+        assert_eq!(verified.devices, vec![DeviceTarget::Serial("WD-123".to_string())]);
+        
+        let tampered = SignedAssignment {
+            payload: signed.payload.replace("NIST80088", "ZeroFill"),
+            signature: signed.signature,
+        };
+        assert!(matches!(tampered.verify(&public_key), Err(DaemonError::Forbidden(_))));
+    }
+    
+    #[test]
+    fn test_assignment_checks() {
+        let now = Utc::now();
+        assert!(assignment("bench-07").check("bench-07", now).is_ok());
+        assert!(assignment("bench-08").check("bench-07", now).is_err());
+        
+        let expired = JobAssignment { expires_at: now - chrono::Duration::minutes(1), ..assignment("bench-07") };
+        assert!(expired.check("bench-07", now).is_err());
+    }
+    
+    #[test]
+    fn test_report_serialization() {
+        let report = AgentReport::Rejected { device: Some(DeviceTarget::Path("/dev/sdz".to_string())), reason: "missing".to_string() };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["type"], "rejected");
+        assert_eq!(json["device"]["path"], "/dev/sdz");
+    }
+}
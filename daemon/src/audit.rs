@@ -0,0 +1,176 @@
+//! Audit trail of access-control decisions
+//!
+//! Every authorization decision is kept in memory for the audit API and, when
+//! an audit log path is configured, appended to it as one JSON object per line.
+//! The file gets the same identifier masking as the log output; see
+//! [`safe_erase_core::logging::mask_text`].
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use safe_erase_core::logging;
+
+use crate::auth::{Principal, Role};
+
+/// Number of records kept in memory
+const AUDIT_MEMORY_CAPACITY: usize = 10_000;
+
+/// Outcome of an access-control decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditDecision {
+    Allowed,
+    Denied,
+}
+
+/// A single audit trail entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub principal: String,
+    pub role: Role,
+    /// Tenant of the principal; its keys only see records of the same tenant
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Action that was attempted, e.g. `submit_job`
+    pub action: String,
+    /// Job ID or device path the action applied to
+    pub target: Option<String>,
+    pub decision: AuditDecision,
+    pub reason: Option<String>,
+}
+
+/// Shared audit trail
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    records: Arc<Mutex<VecDeque<AuditRecord>>>,
+    path: Option<PathBuf>,
+}
+
+impl AuditLog {
+    /// Create an audit trail, optionally persisted to a JSON-lines file
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            records: Arc::new(Mutex::new(VecDeque::new())),
+            path,
+        }
+    }
+    
+    /// Record a decision made for a principal
+    pub async fn record(
+        &self,
+        principal: &Principal,
+        action: &str,
+        target: Option<String>,
+        decision: AuditDecision,
+        reason: Option<String>,
+    ) {
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            principal: principal.name.clone(),
+            role: principal.role,
+            tenant: principal.tenant.clone(),
+            action: action.to_string(),
+            target,
+            decision,
+            reason,
+        };
+        
+        match decision {
+            AuditDecision::Allowed => info!("audit: {} ({}) allowed {}", record.principal, record.role, record.action),
+            AuditDecision::Denied => warn!("audit: {} ({}) denied {}", record.principal, record.role, record.action),
+        }
+        
+        if let Some(path) = &self.path {
+            if let Err(e) = append_line(path, &record).await {
+                error!("Failed to write audit record to {}: {}", path.display(), e);
+            }
+        }
+        
+        let mut records = self.records.lock().await;
+        if records.len() == AUDIT_MEMORY_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+    
+    /// Recent records, newest first
+    pub async fn recent(&self, limit: usize) -> Vec<AuditRecord> {
+        self.records.lock().await.iter().rev().take(limit).cloned().collect()
+    }
+    
+    /// Recent records a client may see, newest first
+    pub async fn recent_for(&self, viewer: &Principal, limit: usize) -> Vec<AuditRecord> {
+        self.records
+            .lock()
+            .await
+            .iter()
+            .rev()
+            .filter(|record| viewer.can_see(record.tenant.as_deref()))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+async fn append_line(path: &PathBuf, record: &AuditRecord) -> std::io::Result<()> {
+    let json = serde_json::to_string(record)?;
+    let mut line = logging::mask_text(&json).into_owned().into_bytes();
+    line.push(b'\n');
+    
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&line).await?;
+    file.flush().await
+}
+
+impl std::fmt::Display for AuditDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditDecision::Allowed => write!(f, "Allowed"),
+            AuditDecision::Denied => write!(f, "Denied"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn principal(role: Role) -> Principal {
+        Principal { name: "alice".to_string(), role, tenant: None }
+    }
+    
+    #[tokio::test]
+    async fn test_records_are_newest_first() {
+        let audit = AuditLog::new(None);
+        audit.record(&principal(Role::Operator), "submit_job", None, AuditDecision::Allowed, None).await;
+        audit.record(&principal(Role::Auditor), "cancel_job", None, AuditDecision::Denied, None).await;
+        
+        let records = audit.recent(10).await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].action, "cancel_job");
+        assert_eq!(records[0].decision, AuditDecision::Denied);
+    }
+    
+    #[tokio::test]
+    async fn test_records_are_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let audit = AuditLog::new(Some(path.clone()));
+        audit.record(&principal(Role::Supervisor), "override_interlocks", Some("/dev/sda".to_string()), AuditDecision::Allowed, None).await;
+        
+        let contents = std::fs::read_to_string(path).unwrap();
+        let record: AuditRecord = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+        assert_eq!(record.role, Role::Supervisor);
+        assert_eq!(record.target.as_deref(), Some("/dev/sda"));
+    }
+}
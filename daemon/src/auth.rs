@@ -0,0 +1,436 @@
+//! API key authentication and role-based access control for the daemon
+//!
+//! Approval tokens let a second user approve a held job without calling the API
+//! themselves. A token is `<approver>:<expires_unix>:<hex signature>` where the
+//! SHA-256 signature covers `<approver>:<job_id>:<expires_unix>` and is made with
+//! the approver's private key. Only the matching public key is configured, so
+//! reading the configuration does not allow approving on someone's behalf.
+
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::sign::{Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::audit::{AuditDecision, AuditLog};
+use crate::error::{DaemonError, Result};
+use crate::rate_limit::RateLimits;
+use crate::AppState;
+
+/// Header carrying the API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Configured API key; only the SHA-256 hash of the key is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Human-readable name of the client owning this key
+    pub name: String,
+    /// Hex-encoded SHA-256 hash of the key
+    pub key_sha256: String,
+    /// Role granted to the key holder
+    #[serde(default)]
+    pub role: Role,
+    /// Tenant the key belongs to; unset for the operator of the service
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Limits replacing `DaemonConfig::rate_limits` for this key
+    #[serde(default)]
+    pub rate_limits: Option<RateLimits>,
+    /// PEM public key the holder's approval tokens are verified against;
+    /// without one the holder can only approve through the API
+    #[serde(default)]
+    pub approval_public_key: Option<String>,
+}
+
+/// Authenticated API client attached to each request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+    /// Tenant whose data the client is limited to; see [`crate::tenants`]
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// Access role of an API client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Runs wipes that pass all safety interlocks
+    #[default]
+    Operator,
+    /// Operator rights plus overriding safety interlocks, managing recurring
+    /// tasks and reading the audit trail
+    Supervisor,
+    /// Read-only access to job history, certificates and the audit trail
+    Auditor,
+}
+
+/// Action subject to access control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewDevices,
+    SubmitJob,
+    CancelJob,
+    ApproveJob,
+    OverrideInterlocks,
+    ManageSchedules,
+    ViewJobs,
+    ViewCertificates,
+    ViewAudit,
+    /// Issue a record of physical destruction
+    RecordDestruction,
+}
+
+impl Principal {
+    /// The daemon itself, for decisions it makes on its own such as scheduling
+    pub fn daemon() -> Self {
+        Self { name: "daemon".to_string(), role: Role::Supervisor, tenant: None }
+    }
+    
+    /// Whether the client may see data belonging to `tenant`
+    pub fn can_see(&self, tenant: Option<&str>) -> bool {
+        self.tenant.is_none() || self.tenant.as_deref() == tenant
+    }
+    
+    /// Refuse clients limited to a tenant, for station-wide resources
+    pub fn require_operator(&self) -> Result<()> {
+        match &self.tenant {
+            Some(tenant) => Err(DaemonError::Forbidden(format!("not available to keys of tenant {}", tenant))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Role {
+    /// Whether this role grants a permission
+    pub fn allows(&self, permission: Permission) -> bool {
+        match self {
+            Role::Supervisor => true,
+            Role::Operator => !matches!(
+                permission,
+                Permission::OverrideInterlocks | Permission::ManageSchedules | Permission::ViewAudit
+            ),
+            Role::Auditor => matches!(
+                permission,
+                Permission::ViewJobs | Permission::ViewCertificates | Permission::ViewAudit
+            ),
+        }
+    }
+}
+
+impl Permission {
+    /// Action name used in the audit trail
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ViewDevices => "view_devices",
+            Permission::SubmitJob => "submit_job",
+            Permission::CancelJob => "cancel_job",
+            Permission::ApproveJob => "approve_job",
+            Permission::OverrideInterlocks => "override_interlocks",
+            Permission::ManageSchedules => "manage_schedules",
+            Permission::ViewJobs => "view_jobs",
+            Permission::ViewCertificates => "view_certificates",
+            Permission::ViewAudit => "view_audit",
+            Permission::RecordDestruction => "record_destruction",
+        }
+    }
+}
+
+impl ApiKeyConfig {
+    fn principal(&self) -> Principal {
+        Principal { name: self.name.clone(), role: self.role, tenant: self.tenant.clone() }
+    }
+    
+    /// Check that the approval public key, if any, can be loaded
+    pub fn validate(&self) -> Result<()> {
+        if let Some(pem) = &self.approval_public_key {
+            PKey::public_key_from_pem(pem.as_bytes()).map_err(|e| {
+                DaemonError::InvalidConfiguration(format!("Invalid approval public key of {}: {}", self.name, e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates API keys against the configured key set
+#[derive(Debug, Clone)]
+pub struct ApiKeyAuthenticator {
+    keys: Vec<ApiKeyConfig>,
+}
+
+impl ApiKeyAuthenticator {
+    /// Create an authenticator from configured keys
+    pub fn new(keys: Vec<ApiKeyConfig>) -> Self {
+        Self { keys }
+    }
+    
+    /// Authenticate a presented API key
+    pub fn authenticate(&self, presented: &str) -> Result<Principal> {
+        let presented_hash = hash_api_key(presented);
+        
+        self.keys
+            .iter()
+            .find(|key| constant_time_eq(key.key_sha256.to_lowercase().as_bytes(), presented_hash.as_bytes()))
+            .map(ApiKeyConfig::principal)
+            .ok_or(DaemonError::InvalidApiKey)
+    }
+    
+    /// Verify a signed approval token for a job and return the approver
+    pub fn verify_approval_token(&self, token: &str, job_id: Uuid) -> Result<Principal> {
+        let invalid = || DaemonError::Forbidden("invalid approval token".to_string());
+        
+        let mut parts = token.trim().rsplitn(3, ':');
+        let (Some(signature), Some(expires), Some(approver)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let expires: i64 = expires.parse().map_err(|_| invalid())?;
+        if expires < Utc::now().timestamp() {
+            return Err(DaemonError::Forbidden("approval token has expired".to_string()));
+        }
+        
+        let key = self.keys.iter().find(|key| key.name == approver).ok_or_else(invalid)?;
+        let pem = key.approval_public_key.as_ref().ok_or_else(|| {
+            DaemonError::Forbidden(format!("{} has no approval public key configured", approver))
+        })?;
+        let public_key = PKey::public_key_from_pem(pem.as_bytes()).map_err(|e| {
+            DaemonError::InvalidConfiguration(format!("Invalid approval public key of {}: {}", approver, e))
+        })?;
+        let signature = hex::decode(signature).map_err(|_| invalid())?;
+        
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &public_key)
+            .map_err(|e| DaemonError::Internal(e.to_string()))?;
+        verifier
+            .update(approval_message(approver, job_id, expires).as_bytes())
+            .map_err(|e| DaemonError::Internal(e.to_string()))?;
+        if !verifier.verify(&signature).unwrap_or(false) {
+            return Err(invalid());
+        }
+        
+        Ok(key.principal())
+    }
+    
+    /// Number of configured keys
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+/// Hash an API key for storage in the configuration file
+pub fn hash_api_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Create an approval token for a job, signed with the approver's private key
+pub fn sign_approval_token(
+    private_key: &PKey<Private>,
+    approver: &str,
+    job_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<String> {
+    let expires = expires_at.timestamp();
+    let mut signer = Signer::new(MessageDigest::sha256(), private_key).map_err(|e| DaemonError::Internal(e.to_string()))?;
+    let signature = signer
+        .sign_oneshot_to_vec(approval_message(approver, job_id, expires).as_bytes())
+        .map_err(|e| DaemonError::Internal(e.to_string()))?;
+    Ok(format!("{}:{}:{}", approver, expires, hex::encode(signature)))
+}
+
+fn approval_message(approver: &str, job_id: Uuid, expires: i64) -> String {
+    format!("{}:{}:{}", approver, job_id, expires)
+}
+
+/// Extract the API key from `X-API-Key` or `Authorization: Bearer` headers
+fn extract_api_key(request: &Request) -> Option<&str> {
+    let headers = request.headers();
+    if let Some(value) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(value.trim());
+    }
+    
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Operator => write!(f, "operator"),
+            Role::Supervisor => write!(f, "supervisor"),
+            Role::Auditor => write!(f, "auditor"),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check a permission for a principal and record the decision in the audit trail
+pub async fn authorize(
+    audit: &AuditLog,
+    principal: &Principal,
+    permission: Permission,
+    target: Option<String>,
+) -> Result<()> {
+    if principal.role.allows(permission) {
+        audit.record(principal, permission.as_str(), target, AuditDecision::Allowed, None).await;
+        Ok(())
+    } else {
+        let reason = format!("role {} may not {}", principal.role, permission.as_str());
+        audit.record(principal, permission.as_str(), target, AuditDecision::Denied, Some(reason.clone())).await;
+        Err(DaemonError::Forbidden(reason))
+    }
+}
+
+/// Resolve who approves a job: the token's signer when one is presented,
+/// otherwise the caller. Rejected tokens are recorded against the caller.
+pub async fn resolve_approver(
+    authenticator: &ApiKeyAuthenticator,
+    audit: &AuditLog,
+    caller: &Principal,
+    approval_token: Option<&str>,
+    job_id: Uuid,
+) -> Result<Principal> {
+    let approver = match approval_token {
+        Some(token) => match authenticator.verify_approval_token(token, job_id) {
+            Ok(approver) => approver,
+            Err(e) => {
+                let target = Some(job_id.to_string());
+                audit.record(caller, Permission::ApproveJob.as_str(), target, AuditDecision::Denied, Some(e.to_string())).await;
+                return Err(e);
+            }
+        },
+        None => caller.clone(),
+    };
+    
+    authorize(audit, &approver, Permission::ApproveJob, Some(job_id.to_string())).await?;
+    Ok(approver)
+}
+
+/// Middleware rejecting requests without a valid API key
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> std::result::Result<Response, DaemonError> {
+    let key = extract_api_key(&request).ok_or(DaemonError::MissingApiKey)?;
+    
+    let principal = state.authenticator.authenticate(key).inspect_err(|_| {
+        warn!("Rejected request to {} with invalid API key", request.uri().path());
+    })?;
+    
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn authenticator() -> ApiKeyAuthenticator {
+        ApiKeyAuthenticator::new(vec![ApiKeyConfig {
+            name: "fleet-tool".to_string(),
+            key_sha256: hash_api_key("secret-key"),
+            role: Role::Auditor,
+            tenant: Some("acme".to_string()),
+            rate_limits: None,
+            approval_public_key: None,
+        }])
+    }
+    
+    #[test]
+    fn test_valid_key() {
+        let principal = authenticator().authenticate("secret-key").unwrap();
+        assert_eq!(principal.name, "fleet-tool");
+        assert_eq!(principal.role, Role::Auditor);
+        assert!(principal.can_see(Some("acme")));
+        assert!(!principal.can_see(Some("globex")));
+        assert!(!principal.can_see(None));
+        assert!(principal.require_operator().is_err());
+    }
+    
+    #[test]
+    fn test_role_permissions() {
+        assert!(Role::Operator.allows(Permission::SubmitJob));
+        assert!(!Role::Operator.allows(Permission::OverrideInterlocks));
+        assert!(Role::Supervisor.allows(Permission::OverrideInterlocks));
+        assert!(Role::Auditor.allows(Permission::ViewCertificates));
+        assert!(!Role::Auditor.allows(Permission::SubmitJob));
+        assert!(!Role::Auditor.allows(Permission::ViewDevices));
+        assert!(Role::Operator.allows(Permission::ApproveJob));
+        assert!(!Role::Auditor.allows(Permission::ApproveJob));
+        assert!(!Role::Operator.allows(Permission::ManageSchedules));
+        assert!(Role::Supervisor.allows(Permission::ManageSchedules));
+        assert!(Role::Operator.allows(Permission::RecordDestruction));
+        assert!(!Role::Auditor.allows(Permission::RecordDestruction));
+    }
+    
+    #[tokio::test]
+    async fn test_denied_decision_is_audited() {
+        let audit = AuditLog::new(None);
+        let principal = Principal { name: "viewer".to_string(), role: Role::Auditor, tenant: None };
+        let result = authorize(&audit, &principal, Permission::CancelJob, None).await;
+        assert!(matches!(result, Err(DaemonError::Forbidden(_))));
+        assert_eq!(audit.recent(1).await[0].decision, AuditDecision::Denied);
+    }
+    
+    #[test]
+    fn test_role_defaults_to_operator() {
+        let key: ApiKeyConfig = serde_json::from_str(r#"{"name":"ui","key_sha256":"00"}"#).unwrap();
+        assert_eq!(key.role, Role::Operator);
+    }
+    
+    #[test]
+    fn test_approval_token() {
+        let group = openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = |group| PKey::from_ec_key(openssl::ec::EcKey::generate(group).unwrap()).unwrap();
+        let (private_key, other_key) = (ec_key(&group), ec_key(&group));
+        let authenticator = ApiKeyAuthenticator::new(vec![ApiKeyConfig {
+            name: "supervisor".to_string(),
+            key_sha256: hash_api_key("approver-key"),
+            role: Role::Supervisor,
+            tenant: None,
+            rate_limits: None,
+            approval_public_key: Some(String::from_utf8(private_key.public_key_to_pem().unwrap()).unwrap()),
+        }]);
+        let job_id = Uuid::new_v4();
+        let expires = Utc::now() + chrono::Duration::minutes(10);
+        
+        let token = sign_approval_token(&private_key, "supervisor", job_id, expires).unwrap();
+        let approver = authenticator.verify_approval_token(&token, job_id).unwrap();
+        assert_eq!(approver.role, Role::Supervisor);
+        
+        assert!(authenticator.verify_approval_token(&token, Uuid::new_v4()).is_err());
+        let forged = sign_approval_token(&other_key, "supervisor", job_id, expires).unwrap();
+        assert!(authenticator.verify_approval_token(&forged, job_id).is_err());
+        let expired = Utc::now() - chrono::Duration::minutes(1);
+        let expired = sign_approval_token(&private_key, "supervisor", job_id, expired).unwrap();
+        assert!(authenticator.verify_approval_token(&expired, job_id).is_err());
+    }
+    
+    #[test]
+    fn test_invalid_key() {
+        let result = authenticator().authenticate("wrong-key");
+        assert!(matches!(result, Err(DaemonError::InvalidApiKey)));
+    }
+    
+    #[test]
+    fn test_hash_is_hex_sha256() {
+        assert_eq!(hash_api_key("abc").len(), 64);
+    }
+}
@@ -0,0 +1,227 @@
+//! Deliverable bundles of batch jobs
+//!
+//! A batch of wiped drives goes back to the customer as a single zip: every
+//! certificate in each format it was issued in, a JSON report of the jobs,
+//! the completion CSV and the audit records concerning the batch's jobs.
+//! `manifest.json` at the root lists every file with its size and SHA-256 so
+//! the recipient can confirm nothing was lost or altered on the way. It also
+//! carries the head of the batch's result hash chain, which the `chain_link`
+//! of each job in the report leads up to, so a job taken out of the report or
+//! a result edited in it shows as well; see [`safe_erase_core::result_chain`].
+
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use safe_erase_core::assets::CompletionRecord;
+use safe_erase_core::{result_chain, ResultChain};
+
+use crate::audit::AuditRecord;
+use crate::error::{DaemonError, Result};
+use crate::jobs::{Job, JobManager};
+
+/// Name of the manifest at the root of every bundle
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Table of contents of a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub batch_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub jobs: usize,
+    pub certificates: usize,
+    /// Last link hash of the chain over the jobs' results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_chain_head: Option<String>,
+    /// Every file in the bundle except the manifest itself
+    pub files: Vec<BundleEntry>,
+}
+
+/// A file in a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the contents
+    pub sha256: String,
+}
+
+/// Builds a zip in memory and records each file for the manifest
+pub struct BundleWriter {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+    entries: Vec<BundleEntry>,
+}
+
+impl BundleWriter {
+    pub fn new() -> Self {
+        Self {
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+            entries: Vec::new(),
+        }
+    }
+    
+    /// Whether a file of that name was already added
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.iter().any(|entry| entry.path == path)
+    }
+    
+    pub fn add(&mut self, path: &str, contents: &[u8]) -> Result<()> {
+        if path == MANIFEST_NAME || self.contains(path) {
+            return Err(DaemonError::Internal(format!("Duplicate bundle entry {}", path)));
+        }
+        self.write(path, contents)?;
+        self.entries.push(BundleEntry {
+            path: path.to_string(),
+            size: contents.len() as u64,
+            sha256: hex::encode(Sha256::digest(contents)),
+        });
+        Ok(())
+    }
+    
+    /// Write the manifest and return the zip
+    pub fn finish(mut self, batch_id: Uuid, jobs: usize, certificates: usize, result_chain_head: Option<String>) -> Result<Vec<u8>> {
+        let manifest = BundleManifest {
+            batch_id,
+            created_at: Utc::now(),
+            jobs,
+            certificates,
+            result_chain_head,
+            files: std::mem::take(&mut self.entries),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| DaemonError::Internal(e.to_string()))?;
+        self.write(MANIFEST_NAME, &manifest)?;
+        self.zip
+            .finish()
+            .map(Cursor::into_inner)
+            .map_err(|e| DaemonError::Internal(format!("Cannot finish bundle: {}", e)))
+    }
+    
+    fn write(&mut self, path: &str, contents: &[u8]) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let failed = |e: &dyn std::fmt::Display| DaemonError::Internal(format!("Cannot add {} to bundle: {}", path, e));
+        self.zip.start_file(path, options).map_err(|e| failed(&e))?;
+        self.zip.write_all(contents).map_err(|e| failed(&e))
+    }
+}
+
+impl Default for BundleWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bundle the outputs of a finished batch: certificates under `certificates/`,
+/// `report.json`, `completion.csv` and `audit.jsonl`; certificates are read
+/// through `manager`, which decrypts them when they are stored encrypted.
+/// Results that no longer match `chain` are refused rather than delivered.
+pub async fn build(
+    batch_id: Uuid,
+    jobs: &[Job],
+    chain: Option<&ResultChain>,
+    completion: &[CompletionRecord],
+    audit: &[AuditRecord],
+    manager: &JobManager,
+) -> Result<Vec<u8>> {
+    if let Some(chain) = chain {
+        verify_chain(chain, jobs)?;
+    }
+    let mut bundle = BundleWriter::new();
+    
+    let mut certificates = 0;
+    for job in jobs {
+        let paths = [&job.certificate_json_path, &job.certificate_pdf_path];
+        for path in paths.into_iter().flatten() {
+            let contents = manager.read_certificate(path).await?;
+            let file_name = Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| job.job_id.to_string());
+            // File name templates need not be unique across jobs
+            let mut entry = format!("certificates/{}", file_name);
+            if bundle.contains(&entry) {
+                entry = format!("certificates/{}-{}", job.job_id, file_name);
+            }
+            bundle.add(&entry, &contents)?;
+        }
+        certificates += usize::from(job.certificate_id.is_some());
+    }
+    
+    let report = serde_json::to_vec_pretty(jobs).map_err(|e| DaemonError::Internal(e.to_string()))?;
+    bundle.add("report.json", &report)?;
+    
+    let mut csv = Vec::new();
+    safe_erase_core::assets::write_completion_csv(&mut csv, completion)?;
+    bundle.add("completion.csv", &csv)?;
+    
+    bundle.add("audit.jsonl", &audit_excerpt(jobs, audit)?)?;
+    
+    bundle.finish(batch_id, jobs.len(), certificates, chain.map(|chain| chain.head.clone()))
+}
+
+/// Check the chained results of the jobs against the batch's chain
+fn verify_chain(chain: &ResultChain, jobs: &[Job]) -> Result<()> {
+    let mut entries: Vec<_> = jobs
+        .iter()
+        .filter_map(|job| Some((job.result.as_ref()?, job.chain_link.as_ref()?)))
+        .collect();
+    entries.sort_by_key(|(_, link)| link.sequence);
+    result_chain::verify(chain.chain_id, &entries, &chain.head).map_err(|e| {
+        DaemonError::Internal(format!("Results of batch {} do not match their hash chain: {}", chain.chain_id, e))
+    })
+}
+
+/// Audit records targeting the given jobs, oldest first, one JSON object per line
+fn audit_excerpt(jobs: &[Job], audit: &[AuditRecord]) -> Result<Vec<u8>> {
+    let job_ids: HashSet<String> = jobs.iter().map(|job| job.job_id.to_string()).collect();
+    let mut records: Vec<&AuditRecord> = audit
+        .iter()
+        .filter(|record| record.target.as_ref().is_some_and(|target| job_ids.contains(target)))
+        .collect();
+    records.sort_by_key(|record| record.timestamp);
+    
+    let mut lines = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut lines, record).map_err(|e| DaemonError::Internal(e.to_string()))?;
+        lines.push(b'\n');
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    
+    #[test]
+    fn test_manifest_lists_files_with_digests() {
+        let batch_id = Uuid::new_v4();
+        let mut bundle = BundleWriter::new();
+        bundle.add("certificates/a.json", b"{}").unwrap();
+        bundle.add("completion.csv", b"serial\n").unwrap();
+        assert!(bundle.add("completion.csv", b"again").is_err());
+        assert!(bundle.add(MANIFEST_NAME, b"{}").is_err());
+        let zip = bundle.finish(batch_id, 1, 1, Some("ab".repeat(32))).unwrap();
+        
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert_eq!(archive.len(), 3);
+        let mut manifest = String::new();
+        archive.by_name(MANIFEST_NAME).unwrap().read_to_string(&mut manifest).unwrap();
+        let manifest: BundleManifest = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest.batch_id, batch_id);
+        assert_eq!(manifest.result_chain_head, Some("ab".repeat(32)));
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[0].path, "certificates/a.json");
+        assert_eq!(manifest.files[0].size, 2);
+        assert_eq!(manifest.files[0].sha256, hex::encode(Sha256::digest(b"{}")));
+        
+        let mut csv = String::new();
+        archive.by_name("completion.csv").unwrap().read_to_string(&mut csv).unwrap();
+        assert_eq!(csv, "serial\n");
+    }
+}
@@ -0,0 +1,283 @@
+//! D-Bus system service for desktop integration on Linux
+//!
+//! Publishes `org.safeerase.Daemon1` on the system bus so unprivileged desktop
+//! front ends can drive the root daemon. Destructive methods are authorized
+//! through polkit using the actions declared in `dbus/org.safeerase.Daemon1.policy`.
+
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use uuid::Uuid;
+use zbus::message::Header;
+use zbus::object_server::SignalContext;
+use zbus::zvariant::Value;
+use zbus::{fdo, interface, proxy, Connection};
+
+use safe_erase_core::{CancelReason, Cancellation};
+
+use crate::events::JobEvent;
+use crate::jobs::{JobManager, JobRequest};
+use crate::queue::JobPriority;
+
+/// Well-known bus name of the service
+pub const BUS_NAME: &str = "org.safeerase.Daemon1";
+
+/// Object path of the service
+pub const OBJECT_PATH: &str = "/org/safeerase/Daemon1";
+
+/// polkit action required to list devices
+pub const ACTION_ENUMERATE: &str = "org.safeerase.daemon.enumerate-devices";
+
+/// polkit action required to start a wipe
+pub const ACTION_WIPE: &str = "org.safeerase.daemon.wipe";
+
+/// polkit action required to cancel a wipe
+pub const ACTION_CANCEL: &str = "org.safeerase.daemon.cancel";
+
+/// polkit `AllowUserInteraction` flag
+const POLKIT_ALLOW_USER_INTERACTION: u32 = 1;
+
+#[proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+trait PolkitAuthority {
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// D-Bus interface backed by the daemon job manager
+#[derive(Debug, Clone)]
+pub struct DbusService {
+    jobs: JobManager,
+}
+
+impl DbusService {
+    /// Create a new D-Bus service
+    pub fn new(jobs: JobManager) -> Self {
+        Self { jobs }
+    }
+    
+    /// Check a polkit action for the caller of the current method
+    async fn authorize(&self, connection: &Connection, header: &Header<'_>, action_id: &str) -> fdo::Result<()> {
+        let sender = header
+            .sender()
+            .ok_or_else(|| fdo::Error::AccessDenied("Unknown caller".to_string()))?
+            .to_string();
+        
+        let authority = PolkitAuthorityProxy::new(connection)
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("polkit unavailable: {}", e)))?;
+        
+        let mut subject_details = HashMap::new();
+        subject_details.insert("name", Value::from(sender.as_str()));
+        let subject = ("system-bus-name", subject_details);
+        
+        let (authorized, _challenge, _details) = authority
+            .check_authorization(&subject, action_id, HashMap::new(), POLKIT_ALLOW_USER_INTERACTION, "")
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("polkit check failed: {}", e)))?;
+        
+        if authorized {
+            Ok(())
+        } else {
+            warn!("polkit denied {} for {}", action_id, sender);
+            Err(fdo::Error::AccessDenied(format!("Not authorized for {}", action_id)))
+        }
+    }
+    
+    /// Describe the caller as `dbus:uid=<uid>` for the job audit fields
+    async fn caller_identity(connection: &Connection, header: &Header<'_>) -> String {
+        let Some(sender) = header.sender() else {
+            return "dbus:unknown".to_string();
+        };
+        
+        match fdo::DBusProxy::new(connection).await {
+            Ok(proxy) => match proxy.get_connection_unix_user(sender.clone().into()).await {
+                Ok(uid) => format!("dbus:uid={}", uid),
+                Err(_) => format!("dbus:{}", sender),
+            },
+            Err(_) => format!("dbus:{}", sender),
+        }
+    }
+}
+
+#[interface(name = "org.safeerase.Daemon1")]
+impl DbusService {
+    /// Discover devices; returns a JSON array of `DeviceInfo`
+    async fn enumerate_devices(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<String> {
+        self.authorize(connection, &header, ACTION_ENUMERATE).await?;
+        
+        let devices = self
+            .jobs
+            .engine()
+            .discover_devices()
+            .await
+            .map_err(|e| fdo::Error::Failed(e.user_message()))?;
+        serde_json::to_string(&devices).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+    
+    /// Start a wipe; returns the job ID
+    async fn start_wipe(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+        device_path: String,
+        algorithm: String,
+        options_json: String,
+    ) -> fdo::Result<String> {
+        self.authorize(connection, &header, ACTION_WIPE).await?;
+        
+        let algorithm = serde_json::from_value(serde_json::Value::String(algorithm.clone()))
+            .map_err(|_| fdo::Error::InvalidArgs(format!("Unknown algorithm: {}", algorithm)))?;
+        let options = if options_json.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str(&options_json)
+                .map_err(|e| fdo::Error::InvalidArgs(format!("Invalid options: {}", e)))?)
+        };
+        
+        let caller = Self::caller_identity(connection, &header).await;
+        let request = JobRequest {
+            device_path,
+            algorithm,
+            options,
+            certificate_options: None,
+            override_interlocks: false,
+            asset: None,
+            priority: JobPriority::default(),
+            psid: None,
+            tenant: None,
+        };
+        
+        let job = self
+            .jobs
+            .submit(request, &caller)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(job.job_id.to_string())
+    }
+    
+    /// Cancel a queued or running job; `reason` is a cancellation reason name
+    /// such as `emergency_stop`, or empty for an ordinary user request
+    async fn cancel_wipe(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(header)] header: Header<'_>,
+        job_id: String,
+        reason: String,
+    ) -> fdo::Result<()> {
+        self.authorize(connection, &header, ACTION_CANCEL).await?;
+        
+        let job_id = Uuid::parse_str(&job_id)
+            .map_err(|_| fdo::Error::InvalidArgs(format!("Invalid job ID: {}", job_id)))?;
+        let reason: CancelReason = if reason.is_empty() {
+            CancelReason::UserRequest
+        } else {
+            reason.parse().map_err(|e: safe_erase_core::SafeEraseError| fdo::Error::InvalidArgs(e.to_string()))?
+        };
+        let cancellation = Cancellation::new(reason).with_requested_by(Self::caller_identity(connection, &header).await);
+        self.jobs
+            .cancel(job_id, cancellation)
+            .await
+            .map(|_| ())
+            .map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+    
+    /// Current job state as JSON
+    async fn get_job(&self, job_id: String) -> fdo::Result<String> {
+        let job_id = Uuid::parse_str(&job_id)
+            .map_err(|_| fdo::Error::InvalidArgs(format!("Invalid job ID: {}", job_id)))?;
+        let job = self.jobs.get(job_id).await.map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        serde_json::to_string(&job).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+    
+    /// Emitted for every progress snapshot of a running job
+    #[zbus(signal)]
+    async fn progress(
+        ctxt: &SignalContext<'_>,
+        job_id: &str,
+        percentage: f64,
+        status: &str,
+        progress_json: &str,
+    ) -> zbus::Result<()>;
+    
+    /// Emitted when a job changes lifecycle state
+    #[zbus(signal)]
+    async fn job_state_changed(ctxt: &SignalContext<'_>, job_id: &str, state: &str) -> zbus::Result<()>;
+}
+
+/// Register the service on the system bus and forward job events as signals
+pub async fn serve(jobs: JobManager) -> crate::Result<Connection> {
+    let mut events = jobs.subscribe_events();
+    let connection = zbus::connection::Builder::system()
+        .and_then(|b| b.name(BUS_NAME))
+        .and_then(|b| b.serve_at(OBJECT_PATH, DbusService::new(jobs)))
+        .map_err(|e| crate::DaemonError::Internal(e.to_string()))?
+        .build()
+        .await
+        .map_err(|e| crate::DaemonError::Internal(e.to_string()))?;
+    
+    info!("SafeErase D-Bus service registered as {}", BUS_NAME);
+    
+    let signal_connection = connection.clone();
+    tokio::spawn(async move {
+        let Ok(ctxt) = SignalContext::new(&signal_connection, OBJECT_PATH) else { return };
+        loop {
+            let result = match events.recv().await {
+                Ok(JobEvent::Progress { job_id, progress }) => {
+                    let json = serde_json::to_string(&progress).unwrap_or_default();
+                    DbusService::progress(
+                        &ctxt,
+                        &job_id.to_string(),
+                        progress.percentage,
+                        &progress.status.to_string(),
+                        &json,
+                    ).await
+                }
+                Ok(JobEvent::StateChanged { job_id, state }) => {
+                    DbusService::job_state_changed(&ctxt, &job_id.to_string(), &state.to_string()).await
+                }
+                Ok(JobEvent::Verification { .. } | JobEvent::CertificateIssued { .. })
+                | Err(broadcast::error::RecvError::Lagged(_)) => Ok(()),
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if let Err(e) = result {
+                warn!("Failed to emit D-Bus signal: {}", e);
+            }
+        }
+    });
+    
+    Ok(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_action_ids_are_namespaced() {
+        for action in [ACTION_ENUMERATE, ACTION_WIPE, ACTION_CANCEL] {
+            assert!(action.starts_with("org.safeerase.daemon."));
+        }
+    }
+    
+    #[test]
+    fn test_policy_file_declares_actions() {
+        let policy = include_str!("../dbus/org.safeerase.Daemon1.policy");
+        for action in [ACTION_ENUMERATE, ACTION_WIPE, ACTION_CANCEL] {
+            assert!(policy.contains(action));
+        }
+    }
+}
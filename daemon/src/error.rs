@@ -0,0 +1,223 @@
+//! Error types for the SafeErase daemon
+
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use thiserror::Error;
+
+use safe_erase_core::{ErrorCode, ErrorContext};
+
+/// Result type alias for daemon operations
+pub type Result<T> = std::result::Result<T, DaemonError>;
+
+/// Error types surfaced by the daemon API
+#[derive(Error, Debug)]
+pub enum DaemonError {
+    /// Authentication errors
+    #[error("Missing API key")]
+    MissingApiKey,
+    
+    #[error("Invalid API key")]
+    InvalidApiKey,
+    
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    
+    /// Job errors
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+    
+    #[error("Invalid job state: {0}")]
+    InvalidJobState(String),
+    
+    #[error("Batch not found: {0}")]
+    BatchNotFound(String),
+    
+    #[error("Scheduled task not found: {0}")]
+    TaskNotFound(String),
+    
+    #[error("Certificate not available: {0}")]
+    CertificateNotAvailable(String),
+    
+    /// Request errors
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    
+    #[error("Rate limited: {message}")]
+    RateLimited { message: String, retry_after_secs: u64 },
+    
+    /// Configuration errors
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    
+    /// Central controller errors (agent mode)
+    #[error("Controller error: {0}")]
+    Controller(String),
+    
+    /// Wrapped engine errors
+    #[error(transparent)]
+    Engine(#[from] safe_erase_core::SafeEraseError),
+    
+    #[error(transparent)]
+    Certificate(#[from] safe_erase_certificates::CertificateError),
+    
+    /// Generic errors
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+/// JSON error body returned to API clients
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub error: String,
+    pub message: String,
+    /// Stable engine or certificate error code, when the error came from either
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    /// Where an engine error occurred
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<ErrorContext>,
+    /// Seconds until a rate-limited request would be accepted, also sent as `Retry-After`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl DaemonError {
+    /// HTTP status code for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            DaemonError::MissingApiKey | DaemonError::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            DaemonError::Forbidden(_) => StatusCode::FORBIDDEN,
+            DaemonError::JobNotFound(_) => StatusCode::NOT_FOUND,
+            DaemonError::BatchNotFound(_) => StatusCode::NOT_FOUND,
+            DaemonError::TaskNotFound(_) => StatusCode::NOT_FOUND,
+            DaemonError::CertificateNotAvailable(_) => StatusCode::NOT_FOUND,
+            DaemonError::InvalidJobState(_) => StatusCode::CONFLICT,
+            DaemonError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            DaemonError::QuotaExceeded(_) | DaemonError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            DaemonError::Controller(_) => StatusCode::BAD_GATEWAY,
+            DaemonError::Engine(e) => match e.root() {
+                safe_erase_core::SafeEraseError::DeviceNotFound(_) => StatusCode::NOT_FOUND,
+                safe_erase_core::SafeEraseError::DeviceRemoved(_) => StatusCode::GONE,
+                safe_erase_core::SafeEraseError::DeviceBusy(_) => StatusCode::CONFLICT,
+                safe_erase_core::SafeEraseError::InvalidParameter(_) => StatusCode::BAD_REQUEST,
+                safe_erase_core::SafeEraseError::PolicyViolation(_) => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    
+    /// Seconds the client should wait before retrying
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            DaemonError::RateLimited { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        }
+    }
+    
+    /// Stable code of the wrapped engine or certificate error
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            DaemonError::Engine(e) => Some(e.code()),
+            DaemonError::Certificate(e) => Some(e.code()),
+            _ => None,
+        }
+    }
+    
+    /// Short machine-readable error kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DaemonError::MissingApiKey => "missing_api_key",
+            DaemonError::InvalidApiKey => "invalid_api_key",
+            DaemonError::Forbidden(_) => "forbidden",
+            DaemonError::JobNotFound(_) => "job_not_found",
+            DaemonError::BatchNotFound(_) => "batch_not_found",
+            DaemonError::TaskNotFound(_) => "task_not_found",
+            DaemonError::InvalidJobState(_) => "invalid_job_state",
+            DaemonError::CertificateNotAvailable(_) => "certificate_not_available",
+            DaemonError::BadRequest(_) => "bad_request",
+            DaemonError::QuotaExceeded(_) => "quota_exceeded",
+            DaemonError::RateLimited { .. } => "rate_limited",
+            DaemonError::InvalidConfiguration(_) => "invalid_configuration",
+            DaemonError::Controller(_) => "controller_error",
+            DaemonError::Engine(_) => "engine_error",
+            DaemonError::Certificate(_) => "certificate_error",
+            DaemonError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl IntoResponse for DaemonError {
+    fn into_response(self) -> Response {
+        let message = match &self {
+            DaemonError::Engine(e) => e.user_message(),
+            DaemonError::Certificate(e) => e.user_message(),
+            other => other.to_string(),
+        };
+        let body = ErrorBody {
+            error: self.kind().to_string(),
+            message,
+            code: self.code(),
+            context: match &self {
+                DaemonError::Engine(e) => e.context().cloned(),
+                _ => None,
+            },
+            retry_after_secs: self.retry_after_secs(),
+        };
+        
+        let mut response = (self.status_code(), Json(body)).into_response();
+        if let Some(seconds) = self.retry_after_secs() {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+impl From<std::io::Error> for DaemonError {
+    fn from(err: std::io::Error) -> Self {
+        DaemonError::Internal(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for DaemonError {
+    fn from(err: serde_json::Error) -> Self {
+        DaemonError::BadRequest(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(DaemonError::InvalidApiKey.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(DaemonError::JobNotFound("x".to_string()).status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(DaemonError::InvalidJobState("x".to_string()).status_code(), StatusCode::CONFLICT);
+        assert_eq!(DaemonError::Forbidden("x".to_string()).status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(DaemonError::QuotaExceeded("x".to_string()).status_code(), StatusCode::TOO_MANY_REQUESTS);
+        
+        let limited = DaemonError::RateLimited { message: "x".to_string(), retry_after_secs: 12 };
+        let response = limited.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "12");
+    }
+    
+    #[test]
+    fn test_engine_error_mapping() {
+        let error = DaemonError::from(safe_erase_core::SafeEraseError::DeviceNotFound("/dev/sdz".to_string()));
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(error.kind(), "engine_error");
+        assert_eq!(error.code().map(|code| code.name), Some("DEVICE_NOT_FOUND"));
+        assert_eq!(DaemonError::InvalidApiKey.code(), None);
+        
+        let busy = safe_erase_core::SafeEraseError::DeviceBusy("/dev/sdb".to_string())
+            .with_context(ErrorContext::new().pass(0));
+        assert_eq!(DaemonError::from(busy).status_code(), StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,172 @@
+//! Live job events streamed to API clients
+
+use std::convert::Infallible;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use safe_erase_core::{VerificationResult, WipeProgress};
+
+use crate::jobs::JobState;
+
+/// Event emitted for a daemon job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    /// Progress snapshot from the wipe engine
+    Progress {
+        job_id: Uuid,
+        progress: WipeProgress,
+    },
+    /// Verification result once the post-wipe verification finished
+    Verification {
+        job_id: Uuid,
+        result: VerificationResult,
+    },
+    /// Job lifecycle transition
+    StateChanged {
+        job_id: Uuid,
+        state: JobState,
+    },
+    /// Certificate written for a completed job
+    CertificateIssued {
+        job_id: Uuid,
+        certificate_id: Uuid,
+    },
+}
+
+impl JobEvent {
+    /// Job this event belongs to
+    pub fn job_id(&self) -> Uuid {
+        match self {
+            JobEvent::Progress { job_id, .. } => *job_id,
+            JobEvent::Verification { job_id, .. } => *job_id,
+            JobEvent::StateChanged { job_id, .. } => *job_id,
+            JobEvent::CertificateIssued { job_id, .. } => *job_id,
+        }
+    }
+    
+    /// SSE event name
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            JobEvent::Progress { .. } => "progress",
+            JobEvent::Verification { .. } => "verification",
+            JobEvent::StateChanged { .. } => "state",
+            JobEvent::CertificateIssued { .. } => "certificate",
+        }
+    }
+    
+    /// Whether this event ends the stream for its job
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobEvent::StateChanged { state, .. } if state.is_terminal())
+    }
+    
+    fn to_sse(&self) -> Event {
+        let event = Event::default()
+            .event(self.event_name())
+            .json_data(self)
+            .unwrap_or_else(|_| Event::default().event("error"));
+        match self {
+            JobEvent::Progress { progress, .. } => event.id(progress.resume_token().to_string()),
+            _ => event,
+        }
+    }
+    
+    /// Progress already delivered according to `last`, the operation and sequence sent most recently
+    fn is_replayed(&self, last: Option<(Uuid, u64)>) -> bool {
+        match (self, last) {
+            (JobEvent::Progress { progress, .. }, Some((operation_id, sequence))) => {
+                progress.operation_id == operation_id && progress.sequence <= sequence
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Turn a job event subscription into a server-sent event stream.
+///
+/// With a `job_id`, only that job's events are forwarded and the stream ends
+/// when the job reaches a terminal state; without one, all jobs are streamed.
+pub fn sse_stream(
+    mut events: broadcast::Receiver<JobEvent>,
+    job_id: Option<Uuid>,
+    initial: Vec<JobEvent>,
+) -> Sse<ReceiverStream<Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel(64);
+    // Replayed progress may also arrive live; it is sent only once
+    let last_replayed = initial.iter().rev().find_map(|event| match event {
+        JobEvent::Progress { progress, .. } => Some((progress.operation_id, progress.sequence)),
+        _ => None,
+    });
+    
+    tokio::spawn(async move {
+        for event in &initial {
+            if tx.send(Ok(event.to_sse())).await.is_err() {
+                return;
+            }
+            if job_id.is_some() && event.is_terminal() {
+                return;
+            }
+        }
+        
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if job_id.is_some_and(|id| id != event.job_id()) || event.is_replayed(last_replayed) {
+                        continue;
+                    }
+                    if tx.send(Ok(event.to_sse())).await.is_err() {
+                        break;
+                    }
+                    if job_id.is_some() && event.is_terminal() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    let lagged = Event::default().event("lagged").data(skipped.to_string());
+                    if tx.send(Ok(lagged)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_event_names() {
+        let event = JobEvent::StateChanged {
+            job_id: Uuid::new_v4(),
+            state: JobState::Running,
+        };
+        assert_eq!(event.event_name(), "state");
+        assert!(!event.is_terminal());
+    }
+    
+    #[test]
+    fn test_terminal_state_event() {
+        let event = JobEvent::StateChanged {
+            job_id: Uuid::new_v4(),
+            state: JobState::Completed,
+        };
+        assert!(event.is_terminal());
+    }
+    
+    #[test]
+    fn test_event_serialization_is_tagged() {
+        let job_id = Uuid::new_v4();
+        let event = JobEvent::StateChanged { job_id, state: JobState::Failed };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "state_changed");
+        assert_eq!(json["state"], "Failed");
+    }
+}
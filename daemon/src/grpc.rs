@@ -0,0 +1,377 @@
+//! gRPC control interface for the daemon
+//!
+//! Exposes the same job management operations as the REST API through the
+//! `safeerase.v1.SafeErase` service defined in `proto/safeerase.proto`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::info;
+use uuid::Uuid;
+
+use safe_erase_core::time_format::format_timestamp;
+use safe_erase_core::{Cancellation, DeviceInfo, WipeProgress};
+
+use crate::audit::{AuditDecision, AuditLog};
+use crate::auth::{authorize, resolve_approver, ApiKeyAuthenticator, Permission, Principal, API_KEY_HEADER};
+use crate::error::DaemonError;
+use crate::jobs::{Job, JobManager, JobRequest, JobState};
+use crate::queue::JobPriority;
+use crate::rate_limit::RateLimiter;
+
+/// Generated protobuf types
+pub mod proto {
+    tonic::include_proto!("safeerase.v1");
+}
+
+use proto::safe_erase_server::{SafeErase, SafeEraseServer};
+
+/// gRPC service implementation backed by the daemon job manager
+#[derive(Debug, Clone)]
+pub struct GrpcService {
+    jobs: JobManager,
+    authenticator: Arc<ApiKeyAuthenticator>,
+    audit: AuditLog,
+    rate_limiter: RateLimiter,
+}
+
+impl GrpcService {
+    /// Create a new gRPC service
+    pub fn new(
+        jobs: JobManager,
+        authenticator: Arc<ApiKeyAuthenticator>,
+        audit: AuditLog,
+        rate_limiter: RateLimiter,
+    ) -> Self {
+        Self { jobs, authenticator, audit, rate_limiter }
+    }
+    
+    /// Caller attached by the authentication interceptor
+    fn principal<T>(request: &Request<T>) -> Result<Principal, Status> {
+        request
+            .extensions()
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Missing API key"))
+    }
+    
+    /// Check a permission for the caller
+    async fn authorize<T>(&self, request: &Request<T>, permission: Permission, target: Option<String>) -> Result<Principal, Status> {
+        let principal = Self::principal(request)?;
+        authorize(&self.audit, &principal, permission, target).await?;
+        Ok(principal)
+    }
+}
+
+/// Serve the gRPC interface on the given address
+pub async fn serve(
+    address: SocketAddr,
+    jobs: JobManager,
+    authenticator: Arc<ApiKeyAuthenticator>,
+    audit: AuditLog,
+    rate_limiter: RateLimiter,
+) -> crate::Result<()> {
+    let service = GrpcService::new(jobs, authenticator, audit, rate_limiter);
+    let authenticator = service.authenticator.clone();
+    let rate_limiter = service.rate_limiter.clone();
+    let interceptor = move |mut request: Request<()>| -> Result<Request<()>, Status> {
+        let key = request
+            .metadata()
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Status::unauthenticated("Missing API key"))?;
+        let principal = authenticator
+            .authenticate(key)
+            .map_err(|_| Status::unauthenticated("Invalid API key"))?;
+        rate_limiter.check_request(&principal.name)?;
+        request.extensions_mut().insert(principal);
+        Ok(request)
+    };
+    
+    info!("SafeErase gRPC interface listening on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(SafeEraseServer::with_interceptor(service, interceptor))
+        .serve(address)
+        .await
+        .map_err(|e| DaemonError::Internal(e.to_string()))
+}
+
+#[tonic::async_trait]
+impl SafeErase for GrpcService {
+    async fn list_devices(
+        &self,
+        request: Request<proto::ListDevicesRequest>,
+    ) -> Result<Response<proto::ListDevicesResponse>, Status> {
+        self.authorize(&request, Permission::ViewDevices, None).await?;
+        let devices = self.jobs.engine().discover_devices().await.map_err(DaemonError::from)?;
+        Ok(Response::new(proto::ListDevicesResponse {
+            devices: devices.iter().map(device_to_proto).collect(),
+        }))
+    }
+    
+    async fn submit_job(
+        &self,
+        request: Request<proto::SubmitJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let target = Some(request.get_ref().device_path.clone());
+        let principal = self.authorize(&request, Permission::SubmitJob, target.clone()).await?;
+        if request.get_ref().override_interlocks {
+            self.authorize(&request, Permission::OverrideInterlocks, target).await?;
+        }
+        let mut job_request = job_request_from_proto(request.into_inner())?;
+        job_request.tenant = principal.tenant.clone();
+        self.rate_limiter.check_submissions(&principal.name, 1, &self.jobs.list().await)?;
+        
+        let job = self.jobs.submit(job_request, &principal.name).await?;
+        Ok(Response::new(job_to_proto(&job)))
+    }
+    
+    async fn get_job(
+        &self,
+        request: Request<proto::GetJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let principal = self.authorize(&request, Permission::ViewJobs, Some(job_id.to_string())).await?;
+        let job = self.jobs.get_for(job_id, &principal).await?;
+        Ok(Response::new(job_to_proto(&job)))
+    }
+    
+    async fn cancel_job(
+        &self,
+        request: Request<proto::CancelJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let target = Some(job_id.to_string());
+        let principal = self.authorize(&request, Permission::CancelJob, target.clone()).await?;
+        self.jobs.get_for(job_id, &principal).await?;
+        let cancellation = cancellation_from_proto(request.get_ref(), &principal)?;
+        let reason = cancellation.to_string();
+        let job = self.jobs.cancel(job_id, cancellation).await?;
+        self.audit.record(&principal, "job_cancelled", target, AuditDecision::Allowed, Some(reason)).await;
+        Ok(Response::new(job_to_proto(&job)))
+    }
+    
+    async fn approve_job(
+        &self,
+        request: Request<proto::ApproveJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let caller = Self::principal(&request)?;
+        let token = Some(request.get_ref().approval_token.as_str()).filter(|token| !token.is_empty());
+        self.jobs.get_for(job_id, &caller).await?;
+        let approver = resolve_approver(&self.authenticator, &self.audit, &caller, token, job_id).await?;
+        self.jobs.get_for(job_id, &approver).await?;
+        let job = self.jobs.approve(job_id, &approver, &self.audit).await?;
+        Ok(Response::new(job_to_proto(&job)))
+    }
+    
+    type WatchProgressStream = ReceiverStream<Result<proto::Progress, Status>>;
+    
+    async fn watch_progress(
+        &self,
+        request: Request<proto::WatchProgressRequest>,
+    ) -> Result<Response<Self::WatchProgressStream>, Status> {
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let principal = self.authorize(&request, Permission::ViewJobs, Some(job_id.to_string())).await?;
+        let job = self.jobs.get_for(job_id, &principal).await?;
+        let (tx, rx) = mpsc::channel(32);
+        
+        if let Some(progress) = &job.progress {
+            let _ = tx.send(Ok(progress_to_proto(progress))).await;
+        }
+        if job.state.is_terminal() {
+            return Ok(Response::new(ReceiverStream::new(rx)));
+        }
+        
+        let mut progress_rx = self.jobs.engine().subscribe_progress();
+        let jobs = self.jobs.clone();
+        tokio::spawn(async move {
+            loop {
+                match progress_rx.recv().await {
+                    Ok(progress) if progress.device_path == job.device_path => {
+                        if tx.send(Ok(progress_to_proto(&progress))).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+                
+                match jobs.get(job_id).await {
+                    Ok(current) if !current.state.is_terminal() => {}
+                    _ => break,
+                }
+            }
+        });
+        
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+impl From<DaemonError> for Status {
+    fn from(err: DaemonError) -> Self {
+        let message = err.to_string();
+        let retry_after = err.retry_after_secs();
+        let mut status = match err.status_code().as_u16() {
+            400 => Status::invalid_argument(message),
+            401 => Status::unauthenticated(message),
+            403 => Status::permission_denied(message),
+            404 => Status::not_found(message),
+            409 => Status::failed_precondition(message),
+            429 => Status::resource_exhausted(message),
+            _ => Status::internal(message),
+        };
+        if let Some(seconds) = retry_after {
+            status.metadata_mut().insert("retry-after", seconds.into());
+        }
+        status
+    }
+}
+
+fn parse_job_id(value: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(value).map_err(|_| Status::invalid_argument(format!("Invalid job ID: {}", value)))
+}
+
+fn cancellation_from_proto(request: &proto::CancelJobRequest, principal: &Principal) -> Result<Cancellation, Status> {
+    let reason = if request.reason.is_empty() {
+        Default::default()
+    } else {
+        request.reason.parse().map_err(|e: safe_erase_core::SafeEraseError| Status::invalid_argument(e.to_string()))?
+    };
+    let cancellation = Cancellation::new(reason).with_requested_by(&principal.name);
+    Ok(if request.detail.is_empty() { cancellation } else { cancellation.with_detail(&request.detail) })
+}
+
+fn job_request_from_proto(request: proto::SubmitJobRequest) -> Result<JobRequest, Status> {
+    let algorithm = serde_json::from_value(serde_json::Value::String(request.algorithm.clone()))
+        .map_err(|_| Status::invalid_argument(format!("Unknown algorithm: {}", request.algorithm)))?;
+    
+    let options = if request.options_json.is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(&request.options_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid options: {}", e)))?)
+    };
+    
+    let certificate_options = if request.certificate_options_json.is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(&request.certificate_options_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid certificate options: {}", e)))?)
+    };
+    
+    Ok(JobRequest {
+        device_path: request.device_path,
+        algorithm,
+        options,
+        certificate_options,
+        override_interlocks: request.override_interlocks,
+        asset: None,
+        priority: JobPriority::default(),
+        psid: None,
+        tenant: None,
+    })
+}
+
+fn device_to_proto(device: &DeviceInfo) -> proto::Device {
+    proto::Device {
+        path: device.path.clone(),
+        name: device.name.clone(),
+        model: device.model.clone(),
+        serial: device.serial.clone(),
+        size: device.size,
+        device_type: device.device_type.to_string(),
+        interface: device.interface.to_string(),
+        is_removable: device.is_removable,
+        is_system_disk: device.is_system_disk,
+        supports_secure_erase: device.supports_secure_erase,
+        supports_hpa_dco: device.supports_hpa_dco,
+        health_status: device.health_status.to_string(),
+    }
+}
+
+fn job_state_to_proto(state: JobState) -> proto::JobState {
+    match state {
+        JobState::PendingApproval => proto::JobState::PendingApproval,
+        JobState::Queued => proto::JobState::Queued,
+        JobState::Running => proto::JobState::Running,
+        JobState::Completed => proto::JobState::Completed,
+        JobState::Failed => proto::JobState::Failed,
+        JobState::Cancelled => proto::JobState::Cancelled,
+    }
+}
+
+fn job_to_proto(job: &Job) -> proto::Job {
+    proto::Job {
+        job_id: job.job_id.to_string(),
+        device_path: job.device_path.clone(),
+        algorithm: job.algorithm.to_string(),
+        state: job_state_to_proto(job.state) as i32,
+        submitted_by: job.submitted_by.clone(),
+        submitted_at: format_timestamp(&job.submitted_at),
+        started_at: job.started_at.as_ref().map(format_timestamp).unwrap_or_default(),
+        finished_at: job.finished_at.as_ref().map(format_timestamp).unwrap_or_default(),
+        progress: job.progress.as_ref().map(progress_to_proto),
+        certificate_id: job.certificate_id.map(|id| id.to_string()).unwrap_or_default(),
+        error_message: job.error_message.clone().unwrap_or_default(),
+        approved_by: job.approved_by.clone().unwrap_or_default(),
+    }
+}
+
+fn progress_to_proto(progress: &WipeProgress) -> proto::Progress {
+    proto::Progress {
+        operation_id: progress.operation_id.to_string(),
+        device_path: progress.device_path.clone(),
+        current_pass: progress.current_pass as u32,
+        total_passes: progress.total_passes as u32,
+        bytes_processed: progress.bytes_processed,
+        total_bytes: progress.total_bytes,
+        percentage: progress.percentage,
+        current_speed: progress.current_speed,
+        average_speed: progress.average_speed,
+        estimated_remaining_secs: progress.estimated_remaining.map(|d| d.as_secs()).unwrap_or(0),
+        current_pattern: progress.current_pattern.clone().unwrap_or_default(),
+        status: progress.status.to_string(),
+        last_updated: format_timestamp(&progress.last_updated),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_job_request_from_proto() {
+        let request = proto::SubmitJobRequest {
+            device_path: "/dev/sdb".to_string(),
+            algorithm: "DoD522022M".to_string(),
+            options_json: String::new(),
+            certificate_options_json: String::new(),
+            override_interlocks: false,
+        };
+        let job_request = job_request_from_proto(request).unwrap();
+        assert_eq!(job_request.algorithm, safe_erase_core::WipeAlgorithm::DoD522022M);
+        assert!(job_request.options.is_none());
+    }
+    
+    #[test]
+    fn test_unknown_algorithm_rejected() {
+        let request = proto::SubmitJobRequest {
+            device_path: "/dev/sdb".to_string(),
+            algorithm: "Shred".to_string(),
+            options_json: String::new(),
+            certificate_options_json: String::new(),
+            override_interlocks: false,
+        };
+        let status = job_request_from_proto(request).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+    
+    #[test]
+    fn test_error_to_status() {
+        let status: Status = DaemonError::JobNotFound("x".to_string()).into();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+}
@@ -0,0 +1,1327 @@
+//! Wipe job management for the daemon
+//!
+//! With a queue file configured, jobs that have not started yet are written
+//! to it whenever the queue changes and put back when the daemon restarts.
+//! The drive at a job's path may have been swapped in between, so every job
+//! checks that the device still has the serial it was submitted for before
+//! the wipe starts.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use safe_erase_certificates::{CertificateEngine, CertificateFormat, CertificateOptions, DestructionRequest, DocumentResult};
+use safe_erase_core::assets::{AssetManifest, AssetRecord, CompletionRecord};
+use safe_erase_core::{at_rest, logging};
+use safe_erase_core::{
+    AuthMethod, Cancellation, ChainLink, DeviceInfo, ErrorContext, OperationEvent, OperatorContext, Psid, Redactor, ResultChain, SafeEraseEngine, VerificationResult, WipeAlgorithm, WipeOptions, WipeProgress, WipeResult, WipeStatus,
+};
+
+use crate::audit::{AuditDecision, AuditLog};
+use crate::auth::Principal;
+use crate::bundle;
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::queue::{self, BackgroundRun, JobPriority};
+use crate::tenants::TenantConfig;
+
+/// Kind of record stored certificate files are sealed as
+pub(crate) const CERTIFICATE_KIND: &str = "certificate";
+
+/// Kind of record the queue file is sealed as
+const QUEUE_KIND: &str = "job queue";
+
+/// Request body for submitting a wipe job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRequest {
+    pub device_path: String,
+    pub algorithm: WipeAlgorithm,
+    #[serde(default)]
+    pub options: Option<WipeOptions>,
+    #[serde(default)]
+    pub certificate_options: Option<CertificateOptions>,
+    /// Bypass the daemon's safety interlocks (supervisors only)
+    #[serde(default)]
+    pub override_interlocks: bool,
+    /// Inventory asset the device must be; its serial is confirmed at submission and again before wiping
+    #[serde(default)]
+    pub asset: Option<AssetRecord>,
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// PSID from the drive label for `PSIDRevert`; only handed to the engine
+    #[serde(default, skip_serializing)]
+    pub psid: Option<Psid>,
+    /// Tenant the job is submitted for; taken from the API key, never from the body
+    #[serde(skip)]
+    pub tenant: Option<String>,
+}
+
+/// Jobs created from an asset manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSubmission {
+    /// Identifies the batch's jobs, e.g. when downloading its bundle
+    pub batch_id: Uuid,
+    pub jobs: Vec<Job>,
+    /// Assets in the manifest with no attached device
+    pub missing_assets: Vec<AssetRecord>,
+    /// Attached devices that are not in the manifest and were left alone
+    pub unexpected_devices: Vec<String>,
+    /// Matched devices whose job was refused, with the reason
+    pub rejected: Vec<(AssetRecord, String)>,
+}
+
+/// Lifecycle state of a daemon job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobState {
+    /// Waiting for a second user to approve the wipe
+    PendingApproval,
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A wipe job tracked by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub job_id: Uuid,
+    pub device_path: String,
+    pub algorithm: WipeAlgorithm,
+    pub options: WipeOptions,
+    pub state: JobState,
+    pub submitted_by: String,
+    pub submitted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub priority: JobPriority,
+    #[serde(default)]
+    pub approved_by: Option<String>,
+    #[serde(default)]
+    pub approved_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub progress: Option<WipeProgress>,
+    pub result: Option<WipeResult>,
+    pub verification: Option<VerificationResult>,
+    pub certificate_id: Option<Uuid>,
+    pub certificate_json_path: Option<String>,
+    pub certificate_pdf_path: Option<String>,
+    /// Signed report of what was attempted, when the wipe failed or was cancelled
+    #[serde(default)]
+    pub attempt_report_path: Option<String>,
+    pub error_message: Option<String>,
+    /// Why the job was cancelled and by whom
+    #[serde(default)]
+    pub cancellation: Option<Cancellation>,
+    /// Where the wipe failed: operation, device, pass and LBA when known
+    #[serde(default)]
+    pub error_context: Option<ErrorContext>,
+    /// Inventory asset confirmed for the device
+    #[serde(default)]
+    pub asset: Option<AssetRecord>,
+    /// Batch the job was submitted in
+    #[serde(default)]
+    pub batch_id: Option<Uuid>,
+    /// Place of the job's result in its batch's hash chain; see [`safe_erase_core::result_chain`]
+    #[serde(default)]
+    pub chain_link: Option<ChainLink>,
+    /// Tenant the job belongs to
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Serial of the device when the job was submitted
+    #[serde(default)]
+    pub device_serial: Option<String>,
+    #[serde(skip)]
+    certificate_options: CertificateOptions,
+}
+
+/// A job that has not started, as kept in the queue file
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredJob {
+    job: Job,
+    certificate_options: CertificateOptions,
+    /// Secret the job needs that is never written out, e.g. the PSID
+    #[serde(default)]
+    withheld: Option<String>,
+}
+
+/// A wipe slot held by work outside the job queue; see [`JobManager::try_acquire_background`]
+#[derive(Debug)]
+pub struct BackgroundSlot {
+    id: Uuid,
+    cancel: CancellationToken,
+    manager: JobManager,
+}
+
+/// Schedules wipe jobs onto the engine and tracks their state
+#[derive(Debug, Clone)]
+pub struct JobManager {
+    engine: Arc<SafeEraseEngine>,
+    certificates: Arc<CertificateEngine>,
+    jobs: Arc<RwLock<HashMap<Uuid, Job>>>,
+    /// Scheduled work holding a wipe slot; always locked after `jobs`
+    background: Arc<RwLock<HashMap<Uuid, BackgroundRun>>>,
+    max_concurrent_jobs: usize,
+    /// Urgent jobs may stop background work to get a slot
+    preemption: bool,
+    audit: Option<AuditLog>,
+    certificate_dir: PathBuf,
+    events_tx: broadcast::Sender<JobEvent>,
+    require_approval: bool,
+    /// Masks identifiers in issued certificates
+    redactor: Option<Redactor>,
+    tenants: Arc<HashMap<String, TenantConfig>>,
+    /// Hash chains over the results of each batch, by batch ID
+    result_chains: Arc<std::sync::Mutex<HashMap<Uuid, ResultChain>>>,
+    /// File jobs not yet started are kept in; held while it is written
+    queue_file: Option<Arc<Mutex<PathBuf>>>,
+}
+
+impl JobManager {
+    /// Create a new job manager
+    pub fn new(
+        engine: Arc<SafeEraseEngine>,
+        certificates: Arc<CertificateEngine>,
+        max_concurrent_jobs: usize,
+        certificate_dir: PathBuf,
+    ) -> Self {
+        let (events_tx, _) = broadcast::channel(1024);
+        
+        Self {
+            engine,
+            certificates,
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            background: Arc::new(RwLock::new(HashMap::new())),
+            max_concurrent_jobs: max_concurrent_jobs.max(1),
+            preemption: false,
+            audit: None,
+            certificate_dir,
+            events_tx,
+            require_approval: false,
+            redactor: None,
+            tenants: Arc::new(HashMap::new()),
+            result_chains: Arc::default(),
+            queue_file: None,
+        }
+    }
+    
+    /// Hold submitted jobs until a second user approves them
+    pub fn with_approval_required(mut self, require_approval: bool) -> Self {
+        self.require_approval = require_approval;
+        self
+    }
+    
+    /// Mask device serials and asset tags in issued certificates
+    pub fn with_redactor(mut self, redactor: Option<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+    
+    /// Let urgent jobs stop scheduled free-space wipes when no slot is free
+    pub fn with_preemption(mut self, preemption: bool) -> Self {
+        self.preemption = preemption;
+        self
+    }
+    
+    /// Apply tenant policies and quotas to jobs submitted for a tenant
+    pub fn with_tenants(mut self, tenants: Vec<TenantConfig>) -> Self {
+        self.tenants = Arc::new(tenants.into_iter().map(|tenant| (tenant.id.clone(), tenant)).collect());
+        self
+    }
+    
+    /// Record queueing and preemption decisions in the audit trail
+    pub fn with_audit(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+    
+    /// Keep jobs that have not started in `path` so they survive a restart;
+    /// see [`JobManager::restore_queue`]
+    pub fn with_queue_file(mut self, path: Option<PathBuf>) -> Self {
+        self.queue_file = path.map(|path| Arc::new(Mutex::new(path)));
+        self
+    }
+    
+    /// Access the underlying engine
+    pub fn engine(&self) -> &Arc<SafeEraseEngine> {
+        &self.engine
+    }
+    
+    /// Encrypt the files of an issued certificate in place when a storage key is configured
+    async fn seal_certificate(&self, paths: &[&String]) -> Result<()> {
+        let Some(key) = self.engine.storage_key() else {
+            return Ok(());
+        };
+        for path in paths {
+            let sealed = key.seal(CERTIFICATE_KIND, &tokio::fs::read(path).await?)?;
+            let temp = format!("{}.tmp", path);
+            tokio::fs::write(&temp, sealed).await?;
+            tokio::fs::rename(&temp, path).await?;
+        }
+        Ok(())
+    }
+    
+    /// Issue a signed record of a physical destruction, stored with the certificates
+    pub async fn record_destruction(&self, request: DestructionRequest, recorded_by: &str) -> Result<DocumentResult> {
+        request.validate().map_err(|e| DaemonError::BadRequest(e.to_string()))?;
+        let options = CertificateOptions {
+            redactor: self.redactor.clone(),
+            ..CertificateOptions::default()
+        };
+        let record = self
+            .certificates
+            .generate_destruction_record(request, recorded_by, &options, &self.certificate_dir)
+            .await?;
+        self.seal_certificate(&[&record.json_path, &record.text_path]).await?;
+        info!("Recorded destruction {} reported by {}", record.document_id, recorded_by);
+        Ok(record)
+    }
+    
+    /// Contents of a stored certificate file, decrypted when it was encrypted
+    pub async fn read_certificate(&self, path: &str) -> Result<Vec<u8>> {
+        let contents = tokio::fs::read(path).await?;
+        Ok(at_rest::open(self.engine.storage_key(), CERTIFICATE_KIND, contents)?)
+    }
+    
+    /// Subscribe to live events for all jobs
+    pub fn subscribe_events(&self) -> broadcast::Receiver<JobEvent> {
+        self.events_tx.subscribe()
+    }
+    
+    fn emit(&self, event: JobEvent) {
+        let _ = self.events_tx.send(event);
+    }
+    
+    /// Submit a new wipe job
+    pub async fn submit(&self, request: JobRequest, submitted_by: &str) -> Result<Job> {
+        let devices = self.engine.get_device_status().await?;
+        let device = devices
+            .iter()
+            .find(|d| d.path == request.device_path)
+            .ok_or_else(|| safe_erase_core::SafeEraseError::DeviceNotFound(request.device_path.clone()))?;
+        
+        if request.algorithm == WipeAlgorithm::PSIDRevert && request.psid.is_none() {
+            return Err(DaemonError::BadRequest("PSIDRevert needs the psid printed on the drive label".to_string()));
+        }
+        if device.is_system_disk && !request.override_interlocks {
+            return Err(DaemonError::BadRequest(format!(
+                "Refusing to wipe system disk {}", device.path
+            )));
+        }
+        if let Some(asset) = &request.asset {
+            logging::protect_identifier(&asset.serial);
+            logging::protect_identifier(&asset.asset_tag);
+            asset.confirm_identity(device)?;
+        }
+        
+        let tenant = match &request.tenant {
+            Some(id) => Some(self.tenants.get(id).ok_or_else(|| {
+                DaemonError::Forbidden(format!("tenant {} is not configured", id))
+            })?),
+            None => None,
+        };
+        
+        // Reject policy violations up front instead of failing the queued job later
+        let options = request.options.clone().unwrap_or_default();
+        if let Some(policy) = self.engine.policy() {
+            policy.check(device, &request.algorithm, &options)?;
+        }
+        if let Some(policy) = tenant.and_then(|tenant| tenant.policy.as_ref()) {
+            policy.check(device, &request.algorithm, &options)?;
+        }
+        
+        {
+            let jobs = self.jobs.read().await;
+            let busy = jobs.values().any(|job| {
+                job.device_path == request.device_path
+                    && matches!(job.state, JobState::PendingApproval | JobState::Queued | JobState::Running)
+            });
+            if busy {
+                return Err(safe_erase_core::SafeEraseError::DeviceBusy(request.device_path.clone()).into());
+            }
+            if let Some(tenant) = tenant {
+                tenant.check_quota(jobs.values(), Utc::now())?;
+            }
+        }
+        
+        let mut options = request.options.clone().unwrap_or_default();
+        options.psid = request.psid.clone();
+        if options.work_order.is_none() {
+            options.work_order = request.asset.as_ref().and_then(|asset| asset.work_order.clone());
+        }
+        
+        let state = if self.require_approval { JobState::PendingApproval } else { JobState::Queued };
+        let job = Job {
+            job_id: Uuid::new_v4(),
+            device_path: request.device_path.clone(),
+            algorithm: request.algorithm.clone(),
+            options,
+            state,
+            submitted_by: submitted_by.to_string(),
+            submitted_at: Utc::now(),
+            priority: request.priority,
+            approved_by: None,
+            approved_at: None,
+            started_at: None,
+            finished_at: None,
+            progress: None,
+            result: None,
+            verification: None,
+            certificate_id: None,
+            certificate_json_path: None,
+            certificate_pdf_path: None,
+            attempt_report_path: None,
+            error_message: None,
+            cancellation: None,
+            error_context: None,
+            asset: request.asset.clone(),
+            batch_id: None,
+            chain_link: None,
+            tenant: request.tenant.clone(),
+            device_serial: Some(device.serial.clone()),
+            certificate_options: request.certificate_options.unwrap_or_default(),
+        };
+        
+        self.jobs.write().await.insert(job.job_id, job.clone());
+        self.save_queue().await;
+        self.emit(JobEvent::StateChanged { job_id: job.job_id, state });
+        
+        if request.override_interlocks {
+            self.engine.emit_event(&OperationEvent::PolicyOverride {
+                device_path: job.device_path.clone(),
+                actor: submitted_by.to_string(),
+                detail: format!("safety interlocks overridden for job {}", job.job_id),
+            });
+        }
+        
+        if state == JobState::PendingApproval {
+            info!("Job {} for device {} submitted by {} awaits approval", job.job_id, job.device_path, submitted_by);
+        } else {
+            info!("Job {} queued for device {} by {} with {} priority", job.job_id, job.device_path, submitted_by, job.priority);
+            self.record_decision(
+                "queue_job",
+                job.job_id.to_string(),
+                format!("priority {}, submitted by {}", job.priority, submitted_by),
+            ).await;
+            self.dispatch().await;
+        }
+        
+        Ok(job)
+    }
+    
+    /// Submit a job for every attached device listed in an asset manifest
+    pub async fn submit_batch(
+        &self,
+        manifest: &AssetManifest,
+        algorithm: WipeAlgorithm,
+        options: Option<WipeOptions>,
+        submitted_by: &Principal,
+    ) -> Result<BatchSubmission> {
+        let devices = self.engine.get_device_status().await?;
+        let matched = manifest.match_devices(&devices);
+        
+        let mut batch = BatchSubmission {
+            batch_id: Uuid::new_v4(),
+            jobs: Vec::new(),
+            missing_assets: matched.missing_assets,
+            unexpected_devices: matched.unexpected_devices,
+            rejected: Vec::new(),
+        };
+        for (device_path, asset) in matched.matched {
+            let request = JobRequest {
+                device_path,
+                algorithm: algorithm.clone(),
+                options: options.clone(),
+                certificate_options: None,
+                override_interlocks: false,
+                asset: Some(asset.clone()),
+                priority: JobPriority::default(),
+                psid: None,
+                tenant: submitted_by.tenant.clone(),
+            };
+            match self.submit(request, &submitted_by.name).await {
+                Ok(mut job) => {
+                    job.batch_id = Some(batch.batch_id);
+                    if let Some(tracked) = self.jobs.write().await.get_mut(&job.job_id) {
+                        tracked.batch_id = job.batch_id;
+                    }
+                    batch.jobs.push(job);
+                }
+                Err(e) => {
+                    warn!("Batch job for asset {} refused: {}", asset.asset_tag, e);
+                    batch.rejected.push((asset, e.to_string()));
+                }
+            }
+        }
+        if !batch.jobs.is_empty() {
+            self.save_queue().await;
+        }
+        
+        info!(
+            "Batch {} by {}: {} jobs, {} missing assets, {} unexpected devices",
+            batch.batch_id,
+            submitted_by.name,
+            batch.jobs.len(),
+            batch.missing_assets.len(),
+            batch.unexpected_devices.len()
+        );
+        Ok(batch)
+    }
+    
+    /// Completion rows for finished jobs that were tied to an asset and
+    /// that `viewer` may see, oldest first
+    pub async fn completion_records(&self, viewer: &Principal) -> Vec<CompletionRecord> {
+        let mut jobs: Vec<Job> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| job.asset.is_some() && job.state.is_terminal() && viewer.can_see(job.tenant.as_deref()))
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| job.submitted_at);
+        jobs.iter().filter_map(completion_record).collect()
+    }
+    
+    /// Zip of everything delivered for a finished batch; see [`crate::bundle`]
+    pub async fn batch_bundle(&self, batch_id: Uuid, audit: &AuditLog, viewer: &Principal) -> Result<Vec<u8>> {
+        let mut jobs: Vec<Job> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| job.batch_id == Some(batch_id) && viewer.can_see(job.tenant.as_deref()))
+            .cloned()
+            .collect();
+        if jobs.is_empty() {
+            return Err(DaemonError::BatchNotFound(batch_id.to_string()));
+        }
+        let unfinished = jobs.iter().filter(|job| !job.state.is_terminal()).count();
+        if unfinished > 0 {
+            return Err(DaemonError::InvalidJobState(format!(
+                "Batch {} has {} unfinished jobs", batch_id, unfinished
+            )));
+        }
+        jobs.sort_by_key(|job| job.submitted_at);
+        
+        let chain = self.result_chains.lock().unwrap_or_else(|e| e.into_inner()).get(&batch_id).cloned();
+        let completion: Vec<CompletionRecord> = jobs.iter().filter_map(completion_record).collect();
+        let records = audit.recent(usize::MAX).await;
+        bundle::build(batch_id, &jobs, chain.as_ref(), &completion, &records, self).await
+    }
+    
+    /// Approve a job held for two-person approval and queue it
+    ///
+    /// The approver must be a different user than the submitter. The outcome
+    /// is recorded in the audit trail together with both identities.
+    pub async fn approve(&self, job_id: Uuid, approver: &Principal, audit: &AuditLog) -> Result<Job> {
+        let target = Some(job_id.to_string());
+        let outcome = {
+            let mut jobs = self.jobs.write().await;
+            match jobs.get_mut(&job_id) {
+                None => Err(DaemonError::JobNotFound(job_id.to_string())),
+                Some(job) if job.state != JobState::PendingApproval => {
+                    Err(DaemonError::InvalidJobState(format!("Job {} is {:?}", job_id, job.state)))
+                }
+                Some(job) if job.submitted_by == approver.name => Err(DaemonError::Forbidden(
+                    "a job cannot be approved by the user who submitted it".to_string()
+                )),
+                Some(job) => {
+                    job.state = JobState::Queued;
+                    job.approved_by = Some(approver.name.clone());
+                    job.approved_at = Some(Utc::now());
+                    Ok(job.clone())
+                }
+            }
+        };
+        
+        let job = match outcome {
+            Ok(job) => job,
+            Err(e) => {
+                audit.record(approver, "approve_job", target, AuditDecision::Denied, Some(e.to_string())).await;
+                return Err(e);
+            }
+        };
+        self.save_queue().await;
+        
+        audit.record(
+            approver,
+            "approve_job",
+            target,
+            AuditDecision::Allowed,
+            Some(format!(
+                "submitted by {}, approved by {}, priority {}",
+                job.submitted_by, approver.name, job.priority
+            )),
+        ).await;
+        self.emit(JobEvent::StateChanged { job_id, state: JobState::Queued });
+        info!("Job {} submitted by {} approved by {}", job_id, job.submitted_by, approver.name);
+        
+        self.dispatch().await;
+        Ok(job)
+    }
+    
+    /// Take a wipe slot for work outside the job queue, such as a scheduled
+    /// free-space wipe that has waited since `waiting_since`
+    ///
+    /// The work ranks as `Low` priority: `None` is returned while every slot
+    /// is busy or a queued job ranks at least as high.
+    pub async fn try_acquire_background(&self, label: &str, waiting_since: DateTime<Utc>) -> Option<BackgroundSlot> {
+        let jobs = self.jobs.read().await;
+        let mut background = self.background.write().await;
+        if running_count(&jobs) + background.len() >= self.max_concurrent_jobs {
+            return None;
+        }
+        let now = Utc::now();
+        let key = queue::queue_key(JobPriority::Low, waiting_since, now);
+        let outranked = jobs
+            .values()
+            .filter(|job| job.state == JobState::Queued)
+            .any(|job| queue::queue_key(job.priority, queued_since(job), now) <= key);
+        if outranked {
+            return None;
+        }
+        
+        let id = Uuid::new_v4();
+        let cancel = CancellationToken::new();
+        background.insert(id, BackgroundRun { label: label.to_string(), cancel: cancel.clone(), preempted_by: None });
+        Some(BackgroundSlot { id, cancel, manager: self.clone() })
+    }
+    
+    /// Start queued jobs, best ranked first, while slots are free
+    ///
+    /// An urgent job that finds no free slot stops one background run when
+    /// preemption is enabled; the slot is handed to it once the run ends.
+    async fn dispatch(&self) {
+        let mut started = Vec::new();
+        let mut preempted = Vec::new();
+        {
+            let mut jobs = self.jobs.write().await;
+            let mut background = self.background.write().await;
+            let now = Utc::now();
+            let mut queued: Vec<(Uuid, JobPriority, DateTime<Utc>)> = jobs
+                .values()
+                .filter(|job| job.state == JobState::Queued)
+                .map(|job| (job.job_id, job.priority, queued_since(job)))
+                .collect();
+            queued.sort_by_key(|(_, priority, since)| queue::queue_key(*priority, *since, now));
+            
+            let mut busy = running_count(&jobs) + background.len();
+            // Preempted runs that have not ended yet; each already frees a slot for an urgent job
+            let mut freeing = background.values().filter(|run| run.preempted_by.is_some()).count();
+            for (job_id, priority, _) in queued {
+                if busy < self.max_concurrent_jobs {
+                    if let Some(job) = jobs.get_mut(&job_id) {
+                        job.state = JobState::Running;
+                        job.started_at = Some(now);
+                        started.push(job_id);
+                        busy += 1;
+                    }
+                    continue;
+                }
+                if !self.preemption || priority != JobPriority::Urgent {
+                    break;
+                }
+                if freeing > 0 {
+                    freeing -= 1;
+                    continue;
+                }
+                match background.values_mut().find(|run| run.preempted_by.is_none()) {
+                    Some(run) => {
+                        run.preempted_by = Some(job_id);
+                        run.cancel.cancel();
+                        preempted.push((run.label.clone(), job_id));
+                    }
+                    None => break,
+                }
+            }
+        }
+        
+        for (label, job_id) in preempted {
+            warn!("Preempting {} for urgent job {}", label, job_id);
+            self.record_decision("preempt", label, format!("stopped for urgent job {}", job_id)).await;
+        }
+        if !started.is_empty() {
+            self.save_queue().await;
+        }
+        for job_id in started {
+            self.emit(JobEvent::StateChanged { job_id, state: JobState::Running });
+            self.spawn_job(job_id);
+        }
+    }
+    
+    /// Audit a decision the daemon made on its own
+    async fn record_decision(&self, action: &str, target: String, reason: String) {
+        if let Some(audit) = &self.audit {
+            audit.record(&Principal::daemon(), action, Some(target), AuditDecision::Allowed, Some(reason)).await;
+        }
+    }
+    
+    fn spawn_job(&self, job_id: Uuid) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            manager.run_job(job_id).await;
+        });
+    }
+    
+    /// Get a job by ID
+    pub async fn get(&self, job_id: Uuid) -> Result<Job> {
+        self.jobs
+            .read()
+            .await
+            .get(&job_id)
+            .cloned()
+            .ok_or_else(|| DaemonError::JobNotFound(job_id.to_string()))
+    }
+    
+    /// Get a job by ID if `viewer` may see it; other tenants' jobs are not found
+    pub async fn get_for(&self, job_id: Uuid, viewer: &Principal) -> Result<Job> {
+        self.get(job_id)
+            .await
+            .ok()
+            .filter(|job| viewer.can_see(job.tenant.as_deref()))
+            .ok_or_else(|| DaemonError::JobNotFound(job_id.to_string()))
+    }
+    
+    /// List all jobs, newest first
+    pub async fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.read().await.values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.submitted_at));
+        jobs
+    }
+    
+    /// Cancel a pending, queued or running job, recording why
+    pub async fn cancel(&self, job_id: Uuid, cancellation: Cancellation) -> Result<Job> {
+        let (state, device_path) = {
+            let mut jobs = self.jobs.write().await;
+            let job = jobs
+                .get_mut(&job_id)
+                .ok_or_else(|| DaemonError::JobNotFound(job_id.to_string()))?;
+            
+            if matches!(job.state, JobState::PendingApproval | JobState::Queued) {
+                job.state = JobState::Cancelled;
+                job.finished_at = Some(Utc::now());
+                job.error_message = Some(cancellation.to_string());
+                job.cancellation = Some(cancellation.clone());
+                info!("Job {} cancelled before start", job_id);
+                self.emit(JobEvent::StateChanged { job_id, state: JobState::Cancelled });
+                (JobState::Cancelled, job.device_path.clone())
+            } else {
+                (job.state, job.device_path.clone())
+            }
+        };
+        
+        if state == JobState::Cancelled {
+            self.save_queue().await;
+            return self.get(job_id).await;
+        }
+        if state != JobState::Running {
+            return Err(DaemonError::InvalidJobState(format!("Job {} is {:?}", job_id, state)));
+        }
+        
+        self.engine.cancel_wipe(&device_path, cancellation.clone()).await?;
+        if let Some(job) = self.jobs.write().await.get_mut(&job_id) {
+            job.cancellation.get_or_insert(cancellation);
+        }
+        self.get(job_id).await
+    }
+    
+    /// Consume engine progress and verification updates and attach them to running jobs
+    pub fn spawn_progress_listener(&self) {
+        let mut progress_rx = self.engine.subscribe_progress();
+        let mut verification_rx = self.engine.subscribe_verification();
+        let jobs = self.jobs.clone();
+        let events_tx = self.events_tx.clone();
+        
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    received = progress_rx.recv() => match received {
+                        Ok(progress) => {
+                            let mut jobs = jobs.write().await;
+                            if let Some(job) = find_running_job(&mut jobs, &progress.device_path) {
+                                job.progress = Some(progress.clone());
+                                let _ = events_tx.send(JobEvent::Progress { job_id: job.job_id, progress });
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Progress listener lagged, skipped {} updates", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    received = verification_rx.recv() => match received {
+                        Ok(result) => {
+                            let mut jobs = jobs.write().await;
+                            if let Some(job) = find_running_job(&mut jobs, &result.device_path) {
+                                job.verification = Some(result.clone());
+                                let _ = events_tx.send(JobEvent::Verification { job_id: job.job_id, result });
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Verification listener lagged, skipped {} results", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+    }
+    
+    async fn run_job(&self, job_id: Uuid) {
+        let (device_path, device_serial, algorithm, options, certificate_options, asset, operator) = {
+            let jobs = self.jobs.read().await;
+            let Some(job) = jobs.get(&job_id) else { return };
+            if job.state != JobState::Running {
+                return;
+            }
+            (
+                job.device_path.clone(),
+                job.device_serial.clone(),
+                job.algorithm.clone(),
+                job.options.clone(),
+                CertificateOptions {
+                    redactor: self.redactor.clone(),
+                    ..certificate_options_for(job)
+                },
+                job.asset.clone(),
+                operator_for(job),
+            )
+        };
+        
+        info!("Job {} started on device {}", job_id, device_path);
+        // The drive may have been swapped while the job was queued
+        let outcome = match self.confirm_device(&device_path, device_serial.as_deref(), asset.as_ref()).await {
+            Ok(()) => self.engine.start_wipe(&device_path, algorithm, options, operator).await,
+            Err(e) => Err(e),
+        };
+        
+        let verification = self.jobs.read().await.get(&job_id).and_then(|job| job.verification.clone());
+        let certificate = match &outcome {
+            Ok(result) if result.status == WipeStatus::Completed => {
+                match self.certificates
+                    .generate_certificate(
+                        result,
+                        verification.as_ref(),
+                        CertificateFormat::Both,
+                        certificate_options.clone(),
+                        &self.certificate_dir,
+                    )
+                    .await
+                {
+                    Ok(certificate) => {
+                        let paths: Vec<&String> = [&certificate.json_path, &certificate.pdf_path].into_iter().flatten().collect();
+                        if let Err(e) = self.seal_certificate(&paths).await {
+                            error!("Cannot encrypt the certificate of job {}: {}", job_id, e);
+                        }
+                        Some(certificate)
+                    }
+                    Err(e) => {
+                        error!("Certificate generation failed for job {}: {}", job_id, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        // A drive whose wipe did not complete still needs paperwork on its way to destruction
+        let attempt_report = match &outcome {
+            Ok(result) if matches!(result.status, WipeStatus::Failed | WipeStatus::Cancelled | WipeStatus::DeviceRemoved) => {
+                match self.certificates.generate_attempt_report(result, &certificate_options, &self.certificate_dir).await {
+                    Ok(report) => {
+                        if let Err(e) = self.seal_certificate(&[&report.json_path, &report.text_path]).await {
+                            error!("Cannot encrypt the attempt report of job {}: {}", job_id, e);
+                        }
+                        Some(report.json_path)
+                    }
+                    Err(e) => {
+                        error!("Attempt report generation failed for job {}: {}", job_id, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs.get_mut(&job_id) else { return };
+        job.finished_at = Some(Utc::now());
+        job.attempt_report_path = attempt_report;
+        
+        match outcome {
+            Ok(result) => {
+                job.state = match result.status {
+                    WipeStatus::Completed => JobState::Completed,
+                    WipeStatus::Cancelled => JobState::Cancelled,
+                    _ => JobState::Failed,
+                };
+                job.error_message = result.error_message.clone();
+                job.error_context = result.error_context.clone();
+                if let Some(batch_id) = job.batch_id {
+                    let mut chains = self.result_chains.lock().unwrap_or_else(|e| e.into_inner());
+                    let chain = chains.entry(batch_id).or_insert_with(|| ResultChain::new(batch_id));
+                    match chain.append(&result) {
+                        Ok(link) => job.chain_link = Some(link),
+                        Err(e) => error!("Cannot chain the result of job {}: {}", job_id, e),
+                    }
+                }
+                job.result = Some(result);
+            }
+            Err(e) => {
+                error!("Job {} failed: {}", job_id, e);
+                job.state = JobState::Failed;
+                job.error_message = Some(e.to_string());
+                job.error_context = e.context().cloned();
+            }
+        }
+        
+        let issued = certificate.as_ref().map(|certificate| certificate.certificate_id);
+        if let Some(certificate) = certificate {
+            job.certificate_id = Some(certificate.certificate_id);
+            job.certificate_json_path = certificate.json_path;
+            job.certificate_pdf_path = certificate.pdf_path;
+        } else if job.state == JobState::Completed
+            && self.engine.policy().is_some_and(|policy| policy.require_certificate)
+        {
+            job.state = JobState::Failed;
+            job.error_message = Some("Wipe policy requires a certificate but none could be issued".to_string());
+        }
+        
+        info!("Job {} finished with state {}", job_id, job.state);
+        if let Some(certificate_id) = issued {
+            self.emit(JobEvent::CertificateIssued { job_id, certificate_id });
+        }
+        self.emit(JobEvent::StateChanged { job_id, state: job.state });
+        drop(jobs);
+        self.dispatch().await;
+    }
+}
+
+impl BackgroundSlot {
+    /// Cancelled when an urgent job preempts the work
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+    
+    /// Give the slot back; returns the job the work was preempted for, if any
+    pub async fn release(self) -> Option<Uuid> {
+        let run = self.manager.background.write().await.remove(&self.id);
+        self.manager.dispatch().await;
+        run.and_then(|run| run.preempted_by)
+    }
+}
+
+impl JobManager {
+    /// Check that the device at `device_path` is still the drive the job was submitted for
+    async fn confirm_device(
+        &self,
+        device_path: &str,
+        serial: Option<&str>,
+        asset: Option<&AssetRecord>,
+    ) -> safe_erase_core::Result<()> {
+        if serial.is_none() && asset.is_none() {
+            return Ok(());
+        }
+        let devices = self.engine.get_device_status().await?;
+        let device = devices
+            .iter()
+            .find(|d| d.path == device_path)
+            .ok_or_else(|| safe_erase_core::SafeEraseError::DeviceNotFound(device_path.to_string()))?;
+        if let Some(serial) = serial {
+            confirm_serial(device, serial)?;
+        }
+        match asset {
+            Some(asset) => asset.confirm_identity(device),
+            None => Ok(()),
+        }
+    }
+    
+    /// Put back the jobs that had not started when the daemon last stopped
+    /// and start what can run; returns how many were restored
+    ///
+    /// A job needing a secret that is never written out, such as a PSID, fails
+    /// instead and has to be submitted again.
+    pub async fn restore_queue(&self) -> Result<usize> {
+        let Some(queue_file) = &self.queue_file else {
+            return Ok(0);
+        };
+        let stored: Vec<StoredJob> = {
+            let path = queue_file.lock().await;
+            match tokio::fs::read(&*path).await {
+                Ok(contents) => {
+                    let contents = at_rest::open(self.engine.storage_key(), QUEUE_KIND, contents)?;
+                    serde_json::from_slice(&contents).map_err(|e| {
+                        DaemonError::InvalidConfiguration(format!("{}: {}", path.display(), e))
+                    })?
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e.into()),
+            }
+        };
+        
+        let mut restored = Vec::new();
+        {
+            let mut jobs = self.jobs.write().await;
+            for stored in stored {
+                if jobs.contains_key(&stored.job.job_id) {
+                    continue;
+                }
+                let job = restored_job(stored);
+                if let Some(serial) = &job.device_serial {
+                    logging::protect_identifier(serial);
+                }
+                restored.push((job.job_id, job.state, job.error_message.clone()));
+                jobs.insert(job.job_id, job);
+            }
+        }
+        
+        for (job_id, state, error_message) in &restored {
+            match error_message {
+                Some(message) => warn!("Queued job {} not restored: {}", job_id, message),
+                None => info!("Restored {} job {}", state, job_id),
+            }
+            self.record_decision("restore_job", job_id.to_string(), format!("restored as {}", state)).await;
+            self.emit(JobEvent::StateChanged { job_id: *job_id, state: *state });
+        }
+        if !restored.is_empty() {
+            self.save_queue().await;
+            self.dispatch().await;
+        }
+        Ok(restored.len())
+    }
+    
+    /// Write the jobs that have not started to the queue file; failures are logged, not fatal
+    async fn save_queue(&self) {
+        let Some(queue_file) = &self.queue_file else {
+            return;
+        };
+        // Holding the file across the snapshot keeps an older snapshot from overwriting a newer one
+        let path = queue_file.lock().await;
+        let stored: Vec<StoredJob> = {
+            let jobs = self.jobs.read().await;
+            let mut pending: Vec<&Job> = jobs
+                .values()
+                .filter(|job| matches!(job.state, JobState::PendingApproval | JobState::Queued))
+                .collect();
+            pending.sort_by_key(|job| job.submitted_at);
+            pending.into_iter().map(stored_job).collect()
+        };
+        let result = async {
+            let contents = serde_json::to_vec_pretty(&stored)?;
+            let contents = at_rest::seal(self.engine.storage_key(), QUEUE_KIND, contents)
+                .map_err(std::io::Error::other)?;
+            let temp = path.with_extension("tmp");
+            tokio::fs::write(&temp, contents).await?;
+            tokio::fs::rename(&temp, &*path).await
+        }
+        .await;
+        if let Err(e) = result {
+            error!("Failed to persist the job queue to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Entry of the queue file for a job that has not started
+fn stored_job(job: &Job) -> StoredJob {
+    let withheld = if job.options.psid.is_some() {
+        Some("the PSID")
+    } else if job.certificate_options.pdf_protection.is_some() {
+        Some("the certificate PDF password")
+    } else {
+        None
+    };
+    StoredJob {
+        job: job.clone(),
+        certificate_options: job.certificate_options.clone(),
+        withheld: withheld.map(str::to_string),
+    }
+}
+
+/// Job of a queue file entry, failed when it lost a secret it needs
+fn restored_job(stored: StoredJob) -> Job {
+    let mut job = stored.job;
+    job.certificate_options = stored.certificate_options;
+    if let Some(withheld) = stored.withheld {
+        job.state = JobState::Failed;
+        job.finished_at = Some(Utc::now());
+        job.error_message = Some(format!(
+            "The daemon restarted before the job started and {} is not kept across restarts; submit the job again",
+            withheld
+        ));
+    }
+    job
+}
+
+/// Refuse a device whose serial differs from the one the job was submitted for
+fn confirm_serial(device: &DeviceInfo, serial: &str) -> safe_erase_core::Result<()> {
+    if device.serial.trim().eq_ignore_ascii_case(serial.trim()) {
+        Ok(())
+    } else {
+        Err(safe_erase_core::SafeEraseError::InvalidParameter(format!(
+            "{} has serial {}, but the job was submitted for serial {}",
+            device.path, device.serial, serial
+        )))
+    }
+}
+
+/// Jobs holding a wipe slot
+fn running_count(jobs: &HashMap<Uuid, Job>) -> usize {
+    jobs.values().filter(|job| job.state == JobState::Running).count()
+}
+
+/// When a queued job entered the queue: at approval, or at submission
+fn queued_since(job: &Job) -> DateTime<Utc> {
+    job.approved_at.unwrap_or(job.submitted_at)
+}
+
+/// Completion row of a finished job tied to an asset
+fn completion_record(job: &Job) -> Option<CompletionRecord> {
+    let asset = job.asset.clone()?;
+    Some(CompletionRecord {
+        serial: asset.serial,
+        asset_tag: asset.asset_tag,
+        work_order: asset.work_order,
+        device_path: job.device_path.clone(),
+        certificate_id: job.certificate_id,
+        outcome: job.state.to_string(),
+        completed_at: job.finished_at,
+    })
+}
+
+/// The submitter as the engine records it; jobs from the controller were
+/// authenticated by its signature, all others by API key
+fn operator_for(job: &Job) -> OperatorContext {
+    let auth_method = if job.submitted_by.starts_with("controller:") {
+        AuthMethod::SignedAssignment
+    } else {
+        AuthMethod::ApiKey
+    };
+    OperatorContext::new(job.submitted_by.clone(), auth_method).with_session_id(job.job_id.to_string())
+}
+
+/// Certificate options with the submitter and approver identities attached
+fn certificate_options_for(job: &Job) -> CertificateOptions {
+    let mut options = job.certificate_options.clone();
+    options.metadata.insert("submitted_by".to_string(), job.submitted_by.clone());
+    if let Some(approver) = &job.approved_by {
+        options.metadata.insert("approved_by".to_string(), approver.clone());
+    }
+    if let Some(asset) = &job.asset {
+        options.metadata.insert("asset_tag".to_string(), asset.asset_tag.clone());
+    }
+    if let Some(tenant) = &job.tenant {
+        options.metadata.insert("tenant".to_string(), tenant.clone());
+    }
+    options
+}
+
+fn find_running_job<'a>(jobs: &'a mut HashMap<Uuid, Job>, device_path: &str) -> Option<&'a mut Job> {
+    jobs.values_mut()
+        .find(|job| job.state == JobState::Running && job.device_path == device_path)
+}
+
+impl JobState {
+    /// Whether the job has reached a terminal state
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+impl std::fmt::Display for JobState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobState::PendingApproval => write!(f, "PendingApproval"),
+            JobState::Queued => write!(f, "Queued"),
+            JobState::Running => write!(f, "Running"),
+            JobState::Completed => write!(f, "Completed"),
+            JobState::Failed => write!(f, "Failed"),
+            JobState::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safe_erase_core::SafeEraseConfig;
+    
+    #[test]
+    fn test_terminal_states() {
+        assert!(!JobState::PendingApproval.is_terminal());
+        assert!(!JobState::Queued.is_terminal());
+        assert!(!JobState::Running.is_terminal());
+        assert!(JobState::Completed.is_terminal());
+        assert!(JobState::Cancelled.is_terminal());
+    }
+    
+    #[test]
+    fn test_job_request_defaults() {
+        let request: JobRequest = serde_json::from_str(
+            r#"{"device_path": "/dev/sdb", "algorithm": "NIST80088"}"#
+        ).unwrap();
+        assert_eq!(request.device_path, "/dev/sdb");
+        assert!(request.options.is_none());
+        assert!(request.certificate_options.is_none());
+        assert!(!request.override_interlocks);
+        assert_eq!(request.priority, JobPriority::Normal);
+        
+        // A tenant in the body is ignored; it comes from the API key
+        let request: JobRequest = serde_json::from_str(
+            r#"{"device_path": "/dev/sdb", "algorithm": "NIST80088", "tenant": "globex"}"#
+        ).unwrap();
+        assert!(request.tenant.is_none());
+        
+        // The PSID is accepted but never written back out
+        let request: JobRequest = serde_json::from_str(
+            r#"{"device_path": "/dev/sdc", "algorithm": "PSIDRevert", "psid": "ABCDEFGHIJKLMNOPQRSTUVWXYZ012345"}"#
+        ).unwrap();
+        assert!(request.psid.is_some());
+        assert!(!serde_json::to_string(&request).unwrap().contains("ABCDEFGH"));
+        assert!(serde_json::from_str::<JobRequest>(
+            r#"{"device_path": "/dev/sdc", "algorithm": "PSIDRevert", "psid": "1234"}"#
+        ).is_err());
+    }
+    
+    fn queued_job() -> Job {
+        Job {
+            job_id: Uuid::new_v4(),
+            device_path: "/dev/sdb".to_string(),
+            algorithm: WipeAlgorithm::NIST80088,
+            options: WipeOptions::default(),
+            state: JobState::Queued,
+            submitted_by: "alice".to_string(),
+            submitted_at: Utc::now(),
+            priority: JobPriority::High,
+            approved_by: Some("bob".to_string()),
+            approved_at: Some(Utc::now()),
+            started_at: None,
+            finished_at: None,
+            progress: None,
+            result: None,
+            verification: None,
+            certificate_id: None,
+            certificate_json_path: None,
+            certificate_pdf_path: None,
+            attempt_report_path: None,
+            error_message: None,
+            cancellation: None,
+            error_context: None,
+            asset: Some(AssetRecord {
+                serial: "S1".to_string(),
+                asset_tag: "TAG-1".to_string(),
+                work_order: Some("WO-9".to_string()),
+            }),
+            batch_id: None,
+            chain_link: None,
+            tenant: Some("acme".to_string()),
+            device_serial: Some("S1".to_string()),
+            certificate_options: CertificateOptions::default(),
+        }
+    }
+    
+    #[test]
+    fn test_certificate_records_both_identities() {
+        let job = queued_job();
+        
+        let options = certificate_options_for(&job);
+        assert_eq!(options.metadata.get("submitted_by").map(String::as_str), Some("alice"));
+        assert_eq!(options.metadata.get("approved_by").map(String::as_str), Some("bob"));
+        assert_eq!(options.metadata.get("asset_tag").map(String::as_str), Some("TAG-1"));
+        assert_eq!(options.metadata.get("tenant").map(String::as_str), Some("acme"));
+        let operator = operator_for(&job);
+        assert_eq!((operator.identity.as_str(), operator.auth_method), ("alice", AuthMethod::ApiKey));
+        assert_eq!(operator.session_id, Some(job.job_id.to_string()));
+        
+        // The daily quota counts the job; a second is refused
+        let tenant = TenantConfig {
+            id: "acme".to_string(),
+            policy: None,
+            max_active_jobs: None,
+            max_jobs_per_day: Some(1),
+        };
+        assert!(matches!(tenant.check_quota([&job], Utc::now()), Err(DaemonError::QuotaExceeded(_))));
+        assert!(TenantConfig { max_jobs_per_day: Some(2), ..tenant }.check_quota([&job], Utc::now()).is_ok());
+    }
+    
+    #[test]
+    fn test_queue_entries_round_trip_without_secrets() {
+        let mut job = queued_job();
+        job.certificate_options.metadata.insert("site".to_string(), "Leeds".to_string());
+        let json = serde_json::to_vec(&[stored_job(&job)]).unwrap();
+        let stored: Vec<StoredJob> = serde_json::from_slice(&json).unwrap();
+        let restored = restored_job(stored.into_iter().next().unwrap());
+        assert_eq!((restored.job_id, restored.state), (job.job_id, JobState::Queued));
+        assert_eq!(restored.device_serial.as_deref(), Some("S1"));
+        assert_eq!(restored.certificate_options.metadata.get("site").map(String::as_str), Some("Leeds"));
+        
+        // The PSID is not written out, so the job cannot run after a restart
+        job.algorithm = WipeAlgorithm::PSIDRevert;
+        job.options.psid = Some(Psid::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ012345").unwrap());
+        let json = serde_json::to_string(&stored_job(&job)).unwrap();
+        assert!(!json.contains("ABCDEFGH"));
+        let restored = restored_job(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.state, JobState::Failed);
+        assert!(restored.error_message.unwrap().contains("the PSID"));
+    }
+    
+    /// Run a verified zero fill of a 1 MiB disk image through a job manager
+    async fn run_verified_zero_fill(engine: SafeEraseEngine, dir: &tempfile::TempDir) -> Job {
+        let image = dir.path().join("disk.img");
+        std::fs::write(&image, vec![0xA5; 1 << 20]).unwrap();
+        let image = image.to_str().unwrap().to_string();
+        engine.open_device(&image).await.unwrap();
+        let certificate_dir = dir.path().join("certificates");
+        std::fs::create_dir(&certificate_dir).unwrap();
+        let manager = JobManager::new(Arc::new(engine), Arc::new(CertificateEngine::new().unwrap()), 1, certificate_dir);
+        let mut events = manager.subscribe_events();
+        
+        let mut request: JobRequest = serde_json::from_value(serde_json::json!({
+            "device_path": image,
+            "algorithm": "ZeroFill",
+        })).unwrap();
+        request.options = Some(WipeOptions { verify_wipe: true, ..WipeOptions::default() });
+        let job_id = manager.submit(request, "alice").await.unwrap().job_id;
+        
+        let finished = tokio::time::timeout(std::time::Duration::from_secs(60), async {
+            loop {
+                match events.recv().await.unwrap() {
+                    JobEvent::StateChanged { job_id: id, state } if id == job_id && state.is_terminal() => break,
+                    _ => {}
+                }
+            }
+        });
+        finished.await.unwrap();
+        manager.get(job_id).await.unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_verified_job_completes_with_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = run_verified_zero_fill(SafeEraseEngine::new().unwrap(), &dir).await;
+        assert_eq!(job.state, JobState::Completed, "{:?}", job.error_message);
+        assert_eq!(job.result.as_ref().unwrap().verification_passed, Some(true));
+        assert!(job.certificate_id.is_some());
+        assert!(std::path::Path::new(job.certificate_json_path.as_ref().unwrap()).exists());
+        assert!(std::path::Path::new(job.certificate_pdf_path.as_ref().unwrap()).exists());
+        assert!(job.attempt_report_path.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_failed_verification_gets_attempt_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = SafeEraseConfig::default();
+        // No sample set reaches a success rate above 100%
+        config.wipe.verification_thresholds.min_success_rate = 1.5;
+        let job = run_verified_zero_fill(SafeEraseEngine::with_config(&config).unwrap(), &dir).await;
+        assert_eq!(job.state, JobState::Failed);
+        assert!(job.error_message.unwrap().contains("verification failed"));
+        assert_eq!(job.result.as_ref().unwrap().verification_passed, Some(false));
+        assert!(job.certificate_id.is_none());
+        assert!(std::path::Path::new(job.attempt_report_path.as_ref().unwrap()).exists());
+    }
+}
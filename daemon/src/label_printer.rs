@@ -0,0 +1,332 @@
+//! Printing drive labels as certificates are issued
+//!
+//! On a bench wiping drive after drive, the label linking each drive to its
+//! certificate should come out of the printer by itself. When a certificate
+//! is issued, the configured label printer receives a print job with the
+//! device serial, certificate ID and wipe date: ZPL or EPL commands sent to
+//! the printer's raw socket (usually port 9100), or the label as a PDF or
+//! PNG document submitted over IPP. Printing never affects the job; a
+//! printer that cannot be reached is retried a few times and then logged.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use safe_erase_certificates::label::{self, LabelContent, LabelOptions, PrinterLanguage};
+use safe_erase_certificates::CertificateEngine;
+
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::jobs::{Job, JobManager};
+use crate::servicenow::job_serial;
+
+/// Port of the raw print socket ZPL and EPL printers listen on
+const RAW_PRINT_PORT: u16 = 9100;
+
+/// Default port of IPP printers
+const IPP_PORT: u16 = 631;
+
+/// Attempts to reach the printer before a label is given up
+const MAX_ATTEMPTS: u32 = 3;
+
+/// IPP operation ID of Print-Job
+const IPP_PRINT_JOB: u16 = 0x0002;
+
+/// How print jobs reach the printer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrintProtocol {
+    /// ZPL commands over the raw socket
+    Zpl,
+    /// EPL commands over the raw socket
+    Epl,
+    /// The label document (`label.format`) submitted as an IPP Print-Job
+    Ipp,
+}
+
+/// Label printer receiving a label for every issued certificate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelPrinterConfig {
+    /// `host` or `host:port` of a ZPL or EPL printer, or the `ipp://`,
+    /// `ipps://` or `http(s)://` URI of an IPP printer
+    pub address: String,
+    pub protocol: PrintProtocol,
+    /// Label size, resolution and symbol; the resolution must match the printer's
+    #[serde(default)]
+    pub label: LabelOptions,
+    /// Per-attempt timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Prints a label for each certificate the daemon issues
+#[derive(Debug, Clone)]
+pub struct LabelPrinter {
+    config: LabelPrinterConfig,
+    client: reqwest::Client,
+}
+
+impl LabelPrinterConfig {
+    /// Validate the printer settings
+    pub fn validate(&self) -> Result<()> {
+        self.label.validate()?;
+        let is_uri = self.address.contains("://");
+        match self.protocol {
+            PrintProtocol::Ipp => ipp_http_url(&self.address).map(|_| ()),
+            PrintProtocol::Zpl | PrintProtocol::Epl if is_uri || self.address.is_empty() => {
+                Err(DaemonError::InvalidConfiguration(format!(
+                    "label_printer.address must be host or host:port for ZPL and EPL: {}", self.address
+                )))
+            }
+            PrintProtocol::Zpl | PrintProtocol::Epl => Ok(()),
+        }
+    }
+    
+    /// `host:port` of the raw print socket
+    fn socket_address(&self) -> String {
+        // A colon after the last `]` separates the port, also for bracketed IPv6 hosts
+        let host = self.address.rsplit(']').next().unwrap_or_default();
+        if host.contains(':') {
+            self.address.clone()
+        } else {
+            format!("{}:{}", self.address, RAW_PRINT_PORT)
+        }
+    }
+}
+
+impl LabelPrinter {
+    pub fn new(config: LabelPrinterConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+    
+    /// Print labels for issued certificates until the event channel closes
+    pub fn spawn(self, jobs: JobManager) {
+        info!("Printing drive labels on {}", self.config.address);
+        
+        let mut events = jobs.subscribe_events();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Label printer lagged, skipped {} job events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let JobEvent::CertificateIssued { job_id, .. } = event else { continue };
+                let Ok(job) = jobs.get(job_id).await else { continue };
+                
+                let printer = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = printer.print(&job).await {
+                        warn!("Failed to print the label of job {}: {}", job.job_id, e);
+                    }
+                });
+            }
+        });
+    }
+    
+    /// Print the label of a job whose certificate was issued
+    pub async fn print(&self, job: &Job) -> Result<()> {
+        let (Some(certificate_id), Some(serial)) = (job.certificate_id, job_serial(job)) else {
+            debug!("Job {} has no certificate or serial; no label printed", job.job_id);
+            return Ok(());
+        };
+        let completed_at = job.result.as_ref().and_then(|result| result.completed_at).unwrap_or(job.submitted_at);
+        let content = LabelContent::new(
+            &serial,
+            certificate_id,
+            completed_at,
+            &CertificateEngine::verification_url(certificate_id),
+            self.config.label.symbol,
+        );
+        
+        match self.config.protocol {
+            PrintProtocol::Zpl => self.print_raw(&label::printer_commands(&content, &self.config.label, PrinterLanguage::Zpl)?).await?,
+            PrintProtocol::Epl => self.print_raw(&label::printer_commands(&content, &self.config.label, PrinterLanguage::Epl)?).await?,
+            PrintProtocol::Ipp => {
+                let document = label::render_document(&content, &self.config.label)?;
+                self.print_ipp(&format!("SafeErase {}", serial), &document).await?;
+            }
+        }
+        info!("Printed the label of certificate {} for job {}", certificate_id, job.job_id);
+        Ok(())
+    }
+    
+    /// Send commands to the raw print socket; only connecting is retried, so
+    /// a printer that accepted part of a label never prints it twice
+    async fn print_raw(&self, commands: &[u8]) -> Result<()> {
+        let address = self.config.socket_address();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let mut stream = None;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match tokio::time::timeout(timeout, TcpStream::connect(&address)).await {
+                Ok(Ok(connected)) => {
+                    stream = Some(connected);
+                    break;
+                }
+                Ok(Err(e)) => warn!("Label printer {} unreachable (attempt {}/{}): {}", address, attempt, MAX_ATTEMPTS, e),
+                Err(_) => warn!("Label printer {} timed out (attempt {}/{})", address, attempt, MAX_ATTEMPTS),
+            }
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+            }
+        }
+        let mut stream = stream
+            .ok_or_else(|| DaemonError::Internal(format!("Label printer {} cannot be reached", address)))?;
+        
+        let send = async {
+            stream.write_all(commands).await?;
+            stream.shutdown().await
+        };
+        tokio::time::timeout(timeout, send)
+            .await
+            .map_err(|_| DaemonError::Internal(format!("Label printer {} stopped accepting data", address)))?
+            .map_err(|e| DaemonError::Internal(format!("Sending the label to {} failed: {}", address, e)))
+    }
+    
+    /// Submit the document as an IPP Print-Job
+    async fn print_ipp(&self, job_name: &str, document: &[u8]) -> Result<()> {
+        let url = ipp_http_url(&self.config.address)?;
+        let mut body = ipp_print_job(&self.config.address, job_name, self.config.label.format.mime_type());
+        body.extend_from_slice(document);
+        
+        for attempt in 1..=MAX_ATTEMPTS {
+            let response = self
+                .client
+                .post(&url)
+                .timeout(Duration::from_secs(self.config.timeout_secs))
+                .header(reqwest::header::CONTENT_TYPE, "application/ipp")
+                .body(body.clone())
+                .send()
+                .await;
+            let response = match response {
+                Ok(response) => response,
+                Err(e) if e.is_connect() && attempt < MAX_ATTEMPTS => {
+                    warn!("IPP printer {} unreachable (attempt {}/{}): {}", url, attempt, MAX_ATTEMPTS, e);
+                    tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+                    continue;
+                }
+                Err(e) => return Err(DaemonError::Internal(format!("IPP request to {} failed: {}", url, e))),
+            };
+            if !response.status().is_success() {
+                return Err(DaemonError::Internal(format!("IPP printer {} returned {}", url, response.status())));
+            }
+            let reply = response
+                .bytes()
+                .await
+                .map_err(|e| DaemonError::Internal(format!("IPP response from {} failed: {}", url, e)))?;
+            return match ipp_status(&reply) {
+                Some(status) if status < 0x0100 => Ok(()),
+                Some(status) => Err(DaemonError::Internal(format!("IPP printer {} refused the job: status 0x{:04x}", url, status))),
+                None => Err(DaemonError::Internal(format!("IPP printer {} sent a malformed response", url))),
+            };
+        }
+        Err(DaemonError::Internal(format!("IPP printer {} cannot be reached", url)))
+    }
+}
+
+/// HTTP URL an IPP printer URI is reached at; `ipp` is HTTP on port 631 and `ipps` HTTPS
+fn ipp_http_url(uri: &str) -> Result<String> {
+    let (scheme, rest) = match uri.split_once("://") {
+        Some(("ipp", rest)) => ("http", rest),
+        Some(("ipps", rest)) => ("https", rest),
+        Some((scheme @ ("http" | "https"), rest)) => return Ok(format!("{}://{}", scheme, rest)),
+        _ => {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "Unsupported IPP printer URI: {}", uri
+            )))
+        }
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if authority.is_empty() {
+        return Err(DaemonError::InvalidConfiguration(format!("IPP printer URI has no host: {}", uri)));
+    }
+    let has_port = authority.rsplit(']').next().unwrap_or_default().contains(':');
+    Ok(if has_port {
+        format!("{}://{}{}", scheme, authority, path)
+    } else {
+        format!("{}://{}:{}{}", scheme, authority, IPP_PORT, path)
+    })
+}
+
+/// Print-Job request header (RFC 8010); the document follows it in the body
+fn ipp_print_job(printer_uri: &str, job_name: &str, document_format: &str) -> Vec<u8> {
+    let mut request = vec![0x02, 0x00];
+    request.extend_from_slice(&IPP_PRINT_JOB.to_be_bytes());
+    request.extend_from_slice(&1u32.to_be_bytes());
+    request.push(0x01);
+    for (tag, name, value) in [
+        (0x47, "attributes-charset", "utf-8"),
+        (0x48, "attributes-natural-language", "en"),
+        (0x45, "printer-uri", printer_uri),
+        (0x42, "requesting-user-name", "safeerase"),
+        (0x42, "job-name", job_name),
+        (0x49, "document-format", document_format),
+    ] {
+        request.push(tag);
+        request.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        request.extend_from_slice(name.as_bytes());
+        request.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        request.extend_from_slice(value.as_bytes());
+    }
+    request.push(0x03);
+    request
+}
+
+/// Status code of an IPP response
+fn ipp_status(response: &[u8]) -> Option<u16> {
+    response.get(2..4).map(|status| u16::from_be_bytes([status[0], status[1]]))
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_printer_addresses() {
+        assert_eq!(ipp_http_url("ipp://printer.local/ipp/print").unwrap(), "http://printer.local:631/ipp/print");
+        assert_eq!(ipp_http_url("ipps://[fe80::1]:8631/ipp").unwrap(), "https://[fe80::1]:8631/ipp");
+        assert_eq!(ipp_http_url("http://10.0.0.5:631/printers/zd").unwrap(), "http://10.0.0.5:631/printers/zd");
+        assert!(ipp_http_url("lpd://printer").is_err());
+        
+        let config: LabelPrinterConfig = serde_json::from_str(
+            r#"{"address": "10.0.0.7", "protocol": "zpl", "label": {"dpi": 203}}"#
+        ).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.socket_address(), "10.0.0.7:9100");
+        assert_eq!(LabelPrinterConfig { address: "[fe80::7]:6101".to_string(), ..config.clone() }.socket_address(), "[fe80::7]:6101");
+        
+        let ipp_without_uri = LabelPrinterConfig { protocol: PrintProtocol::Ipp, ..config.clone() };
+        assert!(ipp_without_uri.validate().is_err());
+        let zpl_with_uri = LabelPrinterConfig { address: "ipp://printer".to_string(), ..config };
+        assert!(zpl_with_uri.validate().is_err());
+    }
+    
+    #[test]
+    fn test_ipp_print_job_encoding() {
+        let request = ipp_print_job("ipp://printer.local/ipp/print", "SafeErase WD-1", "application/pdf");
+        assert_eq!(&request[..9], &[0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x01]);
+        assert_eq!(request[9], 0x47);
+        assert_eq!(&request[10..12], &(18u16).to_be_bytes());
+        assert_eq!(&request[12..30], b"attributes-charset");
+        assert_eq!(*request.last().unwrap(), 0x03);
+        let format = b"\x49\x00\x0fdocument-format\x00\x0fapplication/pdf";
+        assert!(request.windows(format.len()).any(|window| window == format));
+        
+        assert_eq!(ipp_status(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]), Some(0x0000));
+        assert_eq!(ipp_status(&[0x02, 0x00, 0x04, 0x0a]), Some(0x040a));
+        assert_eq!(ipp_status(&[0x02]), None);
+    }
+}
@@ -0,0 +1,419 @@
+//! SafeErase Daemon
+//!
+//! This crate embeds the SafeErase engine behind an authenticated HTTP API so
+//! fleet management tools and web interfaces can discover devices, submit wipe
+//! jobs, follow their progress, cancel them and download the resulting certificates.
+
+pub mod agent;
+pub mod audit;
+pub mod auth;
+pub mod bundle;
+pub mod error;
+pub mod events;
+pub mod jobs;
+pub mod label_printer;
+pub mod mdm;
+pub mod mqtt;
+pub mod queue;
+pub mod rate_limit;
+pub mod routes;
+pub mod schedule;
+pub mod servicenow;
+pub mod tenants;
+pub mod webhooks;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+pub mod dbus;
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use safe_erase_certificates::CertificateEngine;
+use safe_erase_core::{ConfigLoader, SafeEraseEngine};
+
+pub use agent::AgentConfig;
+pub use audit::{AuditDecision, AuditLog, AuditRecord};
+pub use auth::{sign_approval_token, ApiKeyAuthenticator, ApiKeyConfig, Permission, Principal, Role};
+pub use error::{DaemonError, Result};
+pub use events::JobEvent;
+pub use jobs::{BackgroundSlot, Job, JobManager, JobRequest, JobState};
+pub use label_printer::{LabelPrinterConfig, PrintProtocol};
+pub use mdm::{MdmConfig, MdmProvider};
+pub use mqtt::MqttConfig;
+pub use queue::JobPriority;
+pub use rate_limit::{RateLimiter, RateLimits};
+pub use schedule::{ScheduleRequest, ScheduledTask, Scheduler};
+pub use servicenow::ServiceNowConfig;
+pub use tenants::TenantConfig;
+pub use webhooks::{WebhookConfig, WebhookEvent};
+
+/// Daemon configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Address the HTTP server listens on
+    pub bind_address: SocketAddr,
+    /// Address the gRPC interface listens on, if enabled
+    #[serde(default)]
+    pub grpc_bind_address: Option<SocketAddr>,
+    /// Register the D-Bus system service (Linux only)
+    #[serde(default)]
+    pub dbus_enabled: bool,
+    /// API keys accepted by the server
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// Customers of a shared service; keys naming one see only its data
+    #[serde(default)]
+    pub tenants: Vec<TenantConfig>,
+    /// Request rates and unfinished jobs allowed per API key, unless the key sets its own
+    #[serde(default)]
+    pub rate_limits: RateLimits,
+    /// Directory where issued certificates are written
+    pub certificate_dir: PathBuf,
+    /// Maximum number of wipe jobs running at the same time
+    pub max_concurrent_jobs: usize,
+    /// JSON-lines file the audit trail is appended to
+    #[serde(default)]
+    pub audit_log_path: Option<PathBuf>,
+    /// Hold every job until a second user approves it
+    #[serde(default)]
+    pub require_approval: bool,
+    /// Let urgent jobs stop scheduled free-space wipes when every slot is busy
+    #[serde(default)]
+    pub preemption: bool,
+    /// Endpoints notified about job lifecycle events
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Printer receiving a drive label for every issued certificate
+    #[serde(default)]
+    pub label_printer: Option<LabelPrinterConfig>,
+    /// MQTT broker receiving device and job status
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    /// ServiceNow instance whose asset records are updated as jobs finish
+    #[serde(default)]
+    pub servicenow: Option<ServiceNowConfig>,
+    /// Intune or other MDM told which devices were sanitized
+    #[serde(default)]
+    pub mdm: Option<MdmConfig>,
+    /// Central controller this station takes assignments from
+    #[serde(default)]
+    pub agent: Option<AgentConfig>,
+    /// JSON file recurring tasks and their run history are kept in
+    #[serde(default)]
+    pub schedule_path: Option<PathBuf>,
+    /// JSON file jobs that have not started are kept in across restarts
+    #[serde(default)]
+    pub queue_path: Option<PathBuf>,
+}
+
+/// Shared state handed to every request handler
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub jobs: JobManager,
+    pub authenticator: Arc<ApiKeyAuthenticator>,
+    pub audit: AuditLog,
+    pub scheduler: Scheduler,
+    pub rate_limiter: RateLimiter,
+}
+
+/// The SafeErase HTTP daemon
+#[derive(Debug)]
+pub struct SafeEraseDaemon {
+    config: DaemonConfig,
+    state: AppState,
+}
+
+impl DaemonConfig {
+    /// Load the configuration from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| DaemonError::InvalidConfiguration(format!("{}: {}", path.as_ref().display(), e)))?;
+        let config: Self = serde_json::from_str(&contents)
+            .map_err(|e| DaemonError::InvalidConfiguration(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.api_keys.is_empty() {
+            return Err(DaemonError::InvalidConfiguration(
+                "At least one API key must be configured".to_string()
+            ));
+        }
+        
+        if self.api_keys.iter().any(|key| key.key_sha256.len() != 64) {
+            return Err(DaemonError::InvalidConfiguration(
+                "API key hashes must be hex-encoded SHA-256 digests".to_string()
+            ));
+        }
+        
+        for key in &self.api_keys {
+            key.validate()?;
+        }
+        
+        tenants::validate(&self.tenants, self.api_keys.iter().filter_map(|key| key.tenant.as_deref()))?;
+        
+        self.rate_limits.validate()?;
+        for limits in self.api_keys.iter().filter_map(|key| key.rate_limits.as_ref()) {
+            limits.validate()?;
+        }
+        
+        if self.max_concurrent_jobs == 0 {
+            return Err(DaemonError::InvalidConfiguration(
+                "max_concurrent_jobs must be at least 1".to_string()
+            ));
+        }
+        
+        for webhook in &self.webhooks {
+            webhook.validate()?;
+        }
+        
+        if let Some(label_printer) = &self.label_printer {
+            label_printer.validate()?;
+        }
+        
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.validate()?;
+        }
+        
+        if let Some(servicenow) = &self.servicenow {
+            servicenow.validate()?;
+        }
+        
+        if let Some(mdm) = &self.mdm {
+            mdm.validate()?;
+        }
+        
+        if let Some(agent) = &self.agent {
+            agent.validate()?;
+        }
+        
+        Ok(())
+    }
+}
+
+impl SafeEraseDaemon {
+    /// Create a daemon with a freshly initialized engine
+    pub fn new(config: DaemonConfig) -> Result<Self> {
+        config.validate()?;
+        
+        // Engine defaults, policy and event sinks come from the shared SafeErase configuration
+        let engine_config = ConfigLoader::new().load()?;
+        let engine = Arc::new(SafeEraseEngine::with_config(&engine_config)?);
+        let certificates = Arc::new(CertificateEngine::new()?);
+        let audit = AuditLog::new(config.audit_log_path.clone());
+        let jobs = JobManager::new(
+            engine,
+            certificates,
+            config.max_concurrent_jobs,
+            config.certificate_dir.clone(),
+        )
+        .with_approval_required(config.require_approval)
+        .with_redactor(engine_config.redaction.certificate_redactor())
+        .with_preemption(config.preemption)
+        .with_tenants(config.tenants.clone())
+        .with_audit(audit.clone())
+        .with_queue_file(config.queue_path.clone());
+        let authenticator = Arc::new(ApiKeyAuthenticator::new(config.api_keys.clone()));
+        let scheduler = Scheduler::load(jobs.clone(), config.schedule_path.clone())?;
+        let rate_limiter = RateLimiter::new(config.rate_limits.clone(), &config.api_keys);
+        
+        Ok(Self {
+            config,
+            state: AppState { jobs, authenticator, audit, scheduler, rate_limiter },
+        })
+    }
+    
+    /// Build the HTTP router
+    pub fn router(&self) -> Router {
+        router(self.state.clone())
+    }
+    
+    /// Discover devices and serve the API until the process is stopped
+    pub async fn run(self) -> Result<()> {
+        std::fs::create_dir_all(&self.config.certificate_dir)?;
+        
+        if let Err(e) = self.state.jobs.engine().discover_devices().await {
+            warn!("Initial device discovery failed: {}", e);
+        }
+        self.state.jobs.spawn_progress_listener();
+        webhooks::WebhookDispatcher::new(self.config.webhooks.clone()).spawn(self.state.jobs.clone());
+        if let Some(label_printer) = self.config.label_printer.clone() {
+            label_printer::LabelPrinter::new(label_printer).spawn(self.state.jobs.clone());
+        }
+        if let Some(servicenow) = self.config.servicenow.clone() {
+            servicenow::ServiceNowConnector::new(servicenow).spawn(self.state.jobs.clone());
+        }
+        if let Some(mdm) = self.config.mdm.clone() {
+            mdm::MdmConnector::new(mdm).spawn(self.state.jobs.clone());
+        }
+        self.state.scheduler.clone().spawn();
+        // After discovery, so restored jobs find their devices
+        match self.state.jobs.restore_queue().await {
+            Ok(0) => {}
+            Ok(restored) => info!("Restored {} queued job(s)", restored),
+            Err(e) => tracing::error!("Cannot restore the job queue: {}", e),
+        }
+        
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = self.config.mqtt.clone() {
+            mqtt::MqttPublisher::connect(mqtt).spawn(self.state.jobs.clone());
+        }
+        #[cfg(not(feature = "mqtt"))]
+        if self.config.mqtt.is_some() {
+            warn!("mqtt is configured but the daemon was built without the mqtt feature");
+        }
+        
+        if let Some(agent) = self.config.agent.clone() {
+            agent::Agent::new(agent, self.state.jobs.clone())?.spawn();
+        }
+        
+        #[cfg(feature = "grpc")]
+        if let Some(grpc_address) = self.config.grpc_bind_address {
+            let jobs = self.state.jobs.clone();
+            let authenticator = self.state.authenticator.clone();
+            let audit = self.state.audit.clone();
+            let rate_limiter = self.state.rate_limiter.clone();
+            tokio::spawn(async move {
+                if let Err(e) = grpc::serve(grpc_address, jobs, authenticator, audit, rate_limiter).await {
+                    tracing::error!("gRPC interface stopped: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        if self.config.grpc_bind_address.is_some() {
+            warn!("grpc_bind_address is set but the daemon was built without the grpc feature");
+        }
+        
+        // Keep the bus connection alive for as long as the HTTP server runs
+        #[cfg(all(feature = "dbus", target_os = "linux"))]
+        let _dbus_connection = if self.config.dbus_enabled {
+            Some(dbus::serve(self.state.jobs.clone()).await?)
+        } else {
+            None
+        };
+        #[cfg(not(all(feature = "dbus", target_os = "linux")))]
+        if self.config.dbus_enabled {
+            warn!("dbus_enabled is set but the daemon was built without D-Bus support");
+        }
+        
+        let listener = tokio::net::TcpListener::bind(self.config.bind_address).await?;
+        info!(
+            "SafeErase daemon listening on {} with {} API key(s)",
+            self.config.bind_address,
+            self.state.authenticator.key_count()
+        );
+        
+        axum::serve(listener, self.router())
+            .await
+            .map_err(|e| DaemonError::Internal(e.to_string()))
+    }
+}
+
+/// Build the API router for the given state
+pub fn router(state: AppState) -> Router {
+    let api = Router::new()
+        .route("/audit", get(routes::list_audit))
+        .route("/batches", post(routes::submit_batch))
+        .route("/batches/completion.csv", get(routes::batch_completion_csv))
+        .route("/batches/:batch_id/bundle.zip", get(routes::batch_bundle))
+        .route("/devices", get(routes::list_devices))
+        .route("/destruction-records", post(routes::record_destruction))
+        .route("/diagnostics", get(routes::diagnostics))
+        .route("/events", get(routes::all_events))
+        .route("/jobs", get(routes::list_jobs).post(routes::submit_job))
+        .route("/jobs/:job_id", get(routes::get_job))
+        .route("/jobs/:job_id/cancel", post(routes::cancel_job))
+        .route("/jobs/:job_id/approve", post(routes::approve_job))
+        .route("/jobs/:job_id/events", get(routes::job_events))
+        .route("/jobs/:job_id/certificate", get(routes::get_certificate))
+        .route("/jobs/:job_id/certificate.pdf", get(routes::get_certificate_pdf))
+        .route("/schedules", get(routes::list_schedules).post(routes::create_schedule))
+        .route("/schedules/:task_id", get(routes::get_schedule).delete(routes::delete_schedule))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit::limit_requests))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_api_key));
+    
+    Router::new()
+        .route("/health", get(routes::health))
+        .route("/api/v1/openapi.yaml", get(routes::openapi_spec))
+        .nest("/api/v1", api)
+        .with_state(state)
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([127, 0, 0, 1], 8750)),
+            grpc_bind_address: None,
+            dbus_enabled: false,
+            api_keys: Vec::new(),
+            tenants: Vec::new(),
+            rate_limits: RateLimits::default(),
+            certificate_dir: PathBuf::from("certificates"),
+            max_concurrent_jobs: 4,
+            audit_log_path: None,
+            require_approval: false,
+            preemption: false,
+            webhooks: Vec::new(),
+            label_printer: None,
+            mqtt: None,
+            servicenow: None,
+            mdm: None,
+            agent: None,
+            schedule_path: None,
+            queue_path: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_config_requires_api_key() {
+        let config = DaemonConfig::default();
+        assert!(matches!(config.validate(), Err(DaemonError::InvalidConfiguration(_))));
+    }
+    
+    #[test]
+    fn test_config_validation() {
+        let config = DaemonConfig {
+            api_keys: vec![ApiKeyConfig {
+                name: "ui".to_string(),
+                key_sha256: auth::hash_api_key("key"),
+                role: Role::Operator,
+                tenant: None,
+                rate_limits: None,
+                approval_public_key: None,
+            }],
+            ..DaemonConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        
+        let mut tenant_key = config.api_keys[0].clone();
+        tenant_key.tenant = Some("acme".to_string());
+        let unknown_tenant = DaemonConfig { api_keys: vec![tenant_key], ..config.clone() };
+        assert!(matches!(unknown_tenant.validate(), Err(DaemonError::InvalidConfiguration(_))));
+        
+        let mut throttled = config.api_keys[0].clone();
+        throttled.rate_limits = Some(RateLimits { requests_per_minute: Some(0), ..RateLimits::default() });
+        let zero_rate = DaemonConfig { api_keys: vec![throttled], ..config.clone() };
+        assert!(matches!(zero_rate.validate(), Err(DaemonError::InvalidConfiguration(_))));
+    }
+    
+    #[test]
+    fn test_openapi_spec_embedded() {
+        assert!(routes::OPENAPI_SPEC.contains("openapi: 3.0"));
+        assert!(routes::OPENAPI_SPEC.contains("/jobs/{job_id}/cancel"));
+        assert!(routes::OPENAPI_SPEC.contains("/jobs/{job_id}/approve"));
+        assert!(routes::OPENAPI_SPEC.contains("/schedules/{task_id}"));
+    }
+}
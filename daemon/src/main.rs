@@ -0,0 +1,261 @@
+//! SafeErase daemon entry point
+
+use safe_erase_certificates::crypto::CertificateVerifier;
+use safe_erase_certificates::jwks::{JwksConfig, JwksSource};
+use safe_erase_certificates::report;
+use safe_erase_certificates::rollup::Rollup;
+use safe_erase_core::logging::{self, LogFormat};
+use safe_erase_daemon::{sign_approval_token, DaemonConfig, SafeEraseDaemon};
+
+/// Lifetime of approval tokens created on the command line
+const APPROVAL_TOKEN_TTL_MINUTES: i64 = 60;
+
+/// Trusted keys expiring within this many days are reported by `cert verify`
+const KEY_EXPIRY_WARNING_DAYS: i64 = 30;
+
+#[tokio::main]
+async fn main() {
+    let log_format = match std::env::var("SAFEERASE_LOG_FORMAT") {
+        Ok(format) => match format.parse() {
+            Ok(format) => format,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        },
+        Err(_) => LogFormat::Text,
+    };
+    if let Err(e) = logging::init(log_format) {
+        eprintln!("{}", e);
+    }
+    
+    if std::env::args().nth(1).as_deref() == Some("approval-token") {
+        print_approval_token();
+        return;
+    }
+    if std::env::args().nth(1).as_deref() == Some("cert") {
+        let code = match std::env::args().nth(2).as_deref() {
+            Some("rollup") => rollup_certificates(),
+            _ => verify_certificates().await,
+        };
+        std::process::exit(code);
+    }
+    
+    let config_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/etc/safeerase/daemon.json".to_string());
+    
+    let config = match DaemonConfig::from_file(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load daemon configuration from {}: {}", config_path, e);
+            std::process::exit(2);
+        }
+    };
+    
+    let daemon = match SafeEraseDaemon::new(config) {
+        Ok(daemon) => daemon,
+        Err(e) => {
+            eprintln!("Failed to start SafeErase daemon: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    if let Err(e) = daemon.run().await {
+        eprintln!("SafeErase daemon terminated: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// `safe-erase-daemon approval-token <approver> <job_id>`, signing with the
+/// approver's PEM private key read from the file named by `SAFEERASE_APPROVAL_KEY`
+fn print_approval_token() {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    let (Some(approver), Some(job_id)) = (args.first(), args.get(1)) else {
+        eprintln!("Usage: safe-erase-daemon approval-token <approver> <job_id>");
+        std::process::exit(2);
+    };
+    let Ok(job_id) = uuid::Uuid::parse_str(job_id) else {
+        eprintln!("Invalid job ID: {}", job_id);
+        std::process::exit(2);
+    };
+    let Ok(key_path) = std::env::var("SAFEERASE_APPROVAL_KEY") else {
+        eprintln!("SAFEERASE_APPROVAL_KEY must name the approver's private key file");
+        std::process::exit(2);
+    };
+    let private_key = match std::fs::read(&key_path).map_err(|e| e.to_string()).and_then(|pem| {
+        openssl::pkey::PKey::private_key_from_pem(&pem).map_err(|e| e.to_string())
+    }) {
+        Ok(private_key) => private_key,
+        Err(e) => {
+            eprintln!("Cannot load approval key {}: {}", key_path, e);
+            std::process::exit(2);
+        }
+    };
+    
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(APPROVAL_TOKEN_TTL_MINUTES);
+    match sign_approval_token(&private_key, approver, job_id, expires_at) {
+        Ok(token) => println!("{}", token),
+        Err(e) => {
+            eprintln!("Cannot sign approval token: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `safe-erase-daemon cert verify (--trust <bundle> | --jwks <url> [--pin <key-id>]...) [--json] <file|dir>...`
+///
+/// Directories are searched for `.json` certificates. Exits 0 when every file
+/// is valid, otherwise with the code of the most serious failure: 1 invalid
+/// signature, 3 unknown key, 4 schema error, 5 unreadable file; 2 on usage errors.
+async fn verify_certificates() -> i32 {
+    const USAGE: &str =
+        "Usage: safe-erase-daemon cert verify (--trust <bundle> | --jwks <url> [--pin <key-id>]...) [--json] <file|dir>...";
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    if args.first().map(String::as_str) != Some("verify") {
+        eprintln!("{}", USAGE);
+        return report::EXIT_USAGE;
+    }
+    
+    let mut trust = None;
+    let mut jwks = None;
+    let mut pins = Vec::new();
+    let mut json = false;
+    let mut paths = Vec::new();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trust" => trust = args.next(),
+            "--jwks" => jwks = args.next().map(JwksConfig::new),
+            "--pin" => pins.extend(args.next()),
+            "--json" => json = true,
+            _ => paths.push(std::path::PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() || trust.is_some() == jwks.is_some() {
+        eprintln!("{}", USAGE);
+        return report::EXIT_USAGE;
+    }
+    
+    let mut verifier = match CertificateVerifier::new() {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            eprintln!("Failed to initialize verifier: {}", e);
+            return report::EXIT_USAGE;
+        }
+    };
+    if let Some(trust) = &trust {
+        if let Err(e) = verifier.load_trust_bundle(trust) {
+            eprintln!("Cannot load trust bundle {}: {}", trust, e);
+            return report::EXIT_USAGE;
+        }
+    }
+    if let Some(mut config) = jwks {
+        config.pinned_key_ids = pins;
+        let url = config.url.clone();
+        let loaded = match JwksSource::new(config) {
+            Ok(mut source) => source.refresh(&mut verifier).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = loaded {
+            eprintln!("Cannot load keys from {}: {}", url, e);
+            return report::EXIT_USAGE;
+        }
+    }
+    for warning in verifier.trust_warnings(chrono::Utc::now(), chrono::Duration::days(KEY_EXPIRY_WARNING_DAYS)) {
+        eprintln!("warning: {}", warning);
+    }
+    
+    let mut files = Vec::new();
+    for path in paths {
+        match std::fs::read_dir(&path) {
+            Ok(entries) => {
+                let mut found: Vec<_> = entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+                    .collect();
+                found.sort();
+                files.extend(found);
+            }
+            Err(_) => files.push(path),
+        }
+    }
+    
+    let report = report::verify_files(&verifier, &files).await;
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(output) => println!("{}", output),
+            Err(e) => eprintln!("Failed to serialize report: {}", e),
+        }
+    } else {
+        println!("{}", report);
+    }
+    report.exit_code()
+}
+
+/// `safe-erase-daemon cert rollup [--since <YYYY-MM-DD>] [--until <YYYY-MM-DD>] [--output <file>] <dir>`
+///
+/// Writes one CSV row per certificate below `dir` completed from `--since`
+/// up to, but excluding, `--until`; to stdout unless `--output` is given.
+/// Files that are not certificates are listed on stderr.
+fn rollup_certificates() -> i32 {
+    const USAGE: &str =
+        "Usage: safe-erase-daemon cert rollup [--since <YYYY-MM-DD>] [--until <YYYY-MM-DD>] [--output <file>] <dir>";
+    let parse_date = |value: Option<String>| -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+        value
+            .map(|value| {
+                chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+                    .map_err(|_| format!("Invalid date: {}", value))
+            })
+            .transpose()
+    };
+    
+    let (mut since, mut until, mut output, mut dir) = (None, None, None, None);
+    let mut args = std::env::args().skip(3);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" => since = args.next(),
+            "--until" => until = args.next(),
+            "--output" => output = args.next(),
+            _ => dir = Some(std::path::PathBuf::from(arg)),
+        }
+    }
+    let Some(dir) = dir else {
+        eprintln!("{}", USAGE);
+        return report::EXIT_USAGE;
+    };
+    let (since, until) = match (parse_date(since), parse_date(until)) {
+        (Ok(since), Ok(until)) => (since, until),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return report::EXIT_USAGE;
+        }
+    };
+    
+    let rollup = match Rollup::collect(&dir, since, until) {
+        Ok(rollup) => rollup,
+        Err(e) => {
+            eprintln!("Cannot read certificates: {}", e);
+            return 1;
+        }
+    };
+    for (path, reason) in &rollup.skipped {
+        eprintln!("skipped {}: {}", path.display(), reason);
+    }
+    let written = match &output {
+        Some(path) => std::fs::File::create(path).and_then(|mut file| rollup.write_csv(&mut file)),
+        None => rollup.write_csv(&mut std::io::stdout().lock()),
+    };
+    match written {
+        Ok(rows) => {
+            eprintln!("{} certificates", rows);
+            0
+        }
+        Err(e) => {
+            eprintln!("Cannot write roll-up: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! Device management connector for sanitization evidence
+//!
+//! Retired devices stay in the MDM inventory until someone records what
+//! happened to them. When a job finishes, this connector does that from the
+//! station: with Intune it finds the managed device by serial number through
+//! Microsoft Graph, writes the certificate ID and outcome into its notes and,
+//! if configured, retires it; with any other MDM it posts the evidence as JSON
+//! to an endpoint of the operator's choosing. Intune only hears about
+//! completed wipes, since a failed one leaves the device unsanitized; the
+//! generic endpoint is told about both and can tell them apart.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::jobs::{Job, JobManager, JobState};
+use crate::servicenow::{job_serial, job_values};
+
+/// Where sanitization evidence is reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MdmProvider {
+    /// Microsoft Intune through Graph, authenticated as an app registration
+    /// with `DeviceManagementManagedDevices.ReadWrite.All`
+    Intune {
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+        /// Also issue Intune's retire action once the wipe is recorded
+        #[serde(default)]
+        retire: bool,
+    },
+    /// Any endpoint accepting the evidence as a JSON `POST`
+    Generic {
+        url: String,
+        /// Sent as `Authorization: Bearer <token>`
+        #[serde(default)]
+        bearer_token: Option<String>,
+    },
+}
+
+/// MDM connector settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MdmConfig {
+    pub provider: MdmProvider,
+    /// Microsoft Graph base URL; the managed device notes are only in the beta API
+    #[serde(default = "default_graph_url")]
+    pub graph_url: String,
+    /// Azure AD authority issuing Graph tokens
+    #[serde(default = "default_login_url")]
+    pub login_url: String,
+    /// Per-request timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Reports finished jobs to the device management system
+#[derive(Debug, Clone)]
+pub struct MdmConnector {
+    config: MdmConfig,
+    client: reqwest::Client,
+}
+
+impl MdmConfig {
+    /// Validate the connector settings
+    pub fn validate(&self) -> Result<()> {
+        match &self.provider {
+            MdmProvider::Intune { tenant_id, client_id, client_secret, .. } => {
+                if tenant_id.is_empty() || client_id.is_empty() || client_secret.is_empty() {
+                    return Err(DaemonError::InvalidConfiguration(
+                        "mdm.provider.intune needs tenant_id, client_id and client_secret".to_string()
+                    ));
+                }
+                for url in [&self.graph_url, &self.login_url] {
+                    if !url.starts_with("https://") {
+                        return Err(DaemonError::InvalidConfiguration(format!("mdm URLs must use https: {}", url)));
+                    }
+                }
+            }
+            MdmProvider::Generic { url, .. } => {
+                if !url.starts_with("https://") {
+                    return Err(DaemonError::InvalidConfiguration(format!(
+                        "mdm.provider.generic.url must use https: {}", url
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Evidence of one finished job: the job values also mapped by the
+/// ServiceNow connector, plus whether the device counts as sanitized
+pub fn evidence(job: &Job) -> Value {
+    let mut evidence: serde_json::Map<String, Value> = job_values(job)
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), Value::String(value)))
+        .collect();
+    evidence.insert("sanitized".to_string(), Value::Bool(job.state == JobState::Completed));
+    Value::Object(evidence)
+}
+
+/// Text written into the Intune device notes
+fn intune_note(job: &Job) -> String {
+    let values = job_values(job);
+    let value = |name: &str| values.get(name).map(String::as_str).unwrap_or("-");
+    format!(
+        "Sanitized by SafeErase on {} ({}, verification {}); certificate {}, job {}",
+        value("completed_at"),
+        value("algorithm"),
+        value("verification"),
+        value("certificate_id"),
+        value("job_id"),
+    )
+}
+
+impl MdmConnector {
+    pub fn new(config: MdmConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+    
+    /// Report finished jobs until the event channel closes
+    pub fn spawn(self, jobs: JobManager) {
+        info!("Reporting sanitization evidence to the device management system");
+        
+        let mut events = jobs.subscribe_events();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("MDM connector lagged, skipped {} job events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let finished = matches!(
+                    event,
+                    JobEvent::StateChanged { state: JobState::Completed | JobState::Failed, .. }
+                );
+                if !finished {
+                    continue;
+                }
+                let Ok(job) = jobs.get(event.job_id()).await else { continue };
+                
+                let connector = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = connector.report(&job).await {
+                        warn!("Failed to report job {} to the MDM: {}", job.job_id, e);
+                    }
+                });
+            }
+        });
+    }
+    
+    /// Report one finished job
+    pub async fn report(&self, job: &Job) -> Result<()> {
+        match &self.config.provider {
+            MdmProvider::Intune { .. } => self.report_intune(job).await,
+            MdmProvider::Generic { url, bearer_token } => {
+                let mut request = self.client.post(url).json(&evidence(job));
+                if let Some(token) = bearer_token {
+                    request = request.bearer_auth(token);
+                }
+                self.send(request).await?;
+                info!("Reported job {} to the MDM endpoint", job.job_id);
+                Ok(())
+            }
+        }
+    }
+    
+    async fn report_intune(&self, job: &Job) -> Result<()> {
+        let MdmProvider::Intune { retire, .. } = &self.config.provider else {
+            return Ok(());
+        };
+        if job.state != JobState::Completed {
+            debug!("Job {} did not complete; Intune is not updated", job.job_id);
+            return Ok(());
+        }
+        let Some(serial) = job_serial(job) else {
+            debug!("Job {} has no device serial; not reporting to Intune", job.job_id);
+            return Ok(());
+        };
+        
+        let token = self.graph_token().await?;
+        let Some(device_id) = self.find_managed_device(&token, &serial).await? else {
+            warn!("No Intune managed device with serial number {}", serial);
+            return Ok(());
+        };
+        let device_url = format!("{}/deviceManagement/managedDevices/{}", self.graph_url(), device_id);
+        
+        self.send(
+            self.client
+                .patch(&device_url)
+                .bearer_auth(&token)
+                .json(&json!({ "notes": intune_note(job) })),
+        )
+        .await?;
+        if *retire {
+            self.send(self.client.post(format!("{}/retire", device_url)).bearer_auth(&token)).await?;
+        }
+        
+        info!("Recorded job {} on Intune managed device {}", job.job_id, device_id);
+        Ok(())
+    }
+    
+    /// App-only Graph access token from the client credentials grant
+    async fn graph_token(&self) -> Result<String> {
+        let MdmProvider::Intune { tenant_id, client_id, client_secret, .. } = &self.config.provider else {
+            return Err(DaemonError::Internal("Graph token requested without Intune settings".to_string()));
+        };
+        let url = format!("{}/{}/oauth2/v2.0/token", self.config.login_url.trim_end_matches('/'), tenant_id);
+        let response = self
+            .send(self.client.post(url).form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", client_id.as_str()),
+                ("client_secret", client_secret.as_str()),
+                ("scope", "https://graph.microsoft.com/.default"),
+            ]))
+            .await?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| DaemonError::Internal(format!("Invalid token response: {}", e)))?;
+        body["access_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| DaemonError::Internal("Token response has no access_token".to_string()))
+    }
+    
+    /// Intune ID of the managed device with the serial number
+    async fn find_managed_device(&self, token: &str, serial: &str) -> Result<Option<String>> {
+        let filter = format!("serialNumber eq '{}'", serial.replace('\'', "''"));
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}/deviceManagement/managedDevices", self.graph_url()))
+                    .bearer_auth(token)
+                    .query(&[("$filter", filter.as_str()), ("$select", "id")]),
+            )
+            .await?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| DaemonError::Internal(format!("Invalid Graph response: {}", e)))?;
+        Ok(body["value"][0]["id"].as_str().map(str::to_string))
+    }
+    
+    fn graph_url(&self) -> &str {
+        self.config.graph_url.trim_end_matches('/')
+    }
+    
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .send()
+            .await
+            .map_err(|e| DaemonError::Internal(format!("MDM request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(DaemonError::Internal(format!("MDM returned {}", response.status())));
+        }
+        Ok(response)
+    }
+}
+
+fn default_graph_url() -> String {
+    "https://graph.microsoft.com/beta".to_string()
+}
+
+fn default_login_url() -> String {
+    "https://login.microsoftonline.com".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn job(state: &str) -> Job {
+        serde_json::from_value(json!({
+            "job_id": "6f0c5f0e-6a43-4f52-a4bb-2a4a6d0c1a11",
+            "device_path": "/dev/sdb",
+            "algorithm": "NIST80088",
+            "options": safe_erase_core::WipeOptions::default(),
+            "state": state,
+            "submitted_by": "alice",
+            "submitted_at": "2024-05-01T12:00:00Z",
+            "started_at": null,
+            "finished_at": "2024-05-01T13:00:00Z",
+            "progress": null,
+            "result": null,
+            "verification": null,
+            "certificate_id": "0b7d1f7a-3c1e-4d0a-9d5e-0f3c2b1a0e99",
+            "certificate_json_path": null,
+            "certificate_pdf_path": null,
+            "error_message": null,
+            "asset": {"serial": "S1", "asset_tag": "TAG-1"}
+        }))
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_config_validation() {
+        let intune: MdmConfig = serde_json::from_value(json!({
+            "provider": {"intune": {"tenant_id": "contoso", "client_id": "app", "client_secret": "secret"}}
+        }))
+        .unwrap();
+        assert!(intune.validate().is_ok());
+        assert_eq!(intune.graph_url, "https://graph.microsoft.com/beta");
+        assert!(matches!(intune.provider, MdmProvider::Intune { retire: false, .. }));
+        
+        let generic: MdmConfig = serde_json::from_value(json!({
+            "provider": {"generic": {"url": "http://mdm.example.com/sanitized"}}
+        }))
+        .unwrap();
+        assert!(generic.validate().is_err());
+    }
+    
+    #[test]
+    fn test_evidence() {
+        let completed = evidence(&job("Completed"));
+        assert_eq!(completed["serial"], "S1");
+        assert_eq!(completed["certificate_id"], "0b7d1f7a-3c1e-4d0a-9d5e-0f3c2b1a0e99");
+        assert_eq!(completed["sanitized"], true);
+        assert_eq!(evidence(&job("Failed"))["sanitized"], false);
+        
+        let note = intune_note(&job("Completed"));
+        assert!(note.contains("2024-05-01T13:00:00Z"));
+        assert!(note.contains("certificate 0b7d1f7a-3c1e-4d0a-9d5e-0f3c2b1a0e99"));
+    }
+}
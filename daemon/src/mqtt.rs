@@ -0,0 +1,238 @@
+//! MQTT status publishing for fleet dashboards
+//!
+//! Publishes the station's device list, job state changes and live progress to
+//! an MQTT broker so many wipe stations can be monitored from one place. Topic
+//! templates may use `{station}` and `{job_id}` placeholders.
+//!
+//! The configuration types are always available; publishing requires the
+//! `mqtt` feature.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{DaemonError, Result};
+
+#[cfg(feature = "mqtt")]
+pub use publisher::MqttPublisher;
+
+/// MQTT broker connection and topic settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Name of this wipe station, used in topics and as the client ID
+    pub station_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub topics: MqttTopics,
+    /// How often the device list is republished
+    #[serde(default = "default_device_interval_secs")]
+    pub device_interval_secs: u64,
+}
+
+/// Topic templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttTopics {
+    /// `online`/`offline` station availability (retained, also the last will)
+    pub availability: String,
+    /// Device list as a JSON array (retained)
+    pub devices: String,
+    /// Job snapshot published on every state change (retained)
+    pub job_state: String,
+    /// Progress snapshots while a job runs
+    pub job_progress: String,
+}
+
+impl MqttConfig {
+    /// Validate the broker settings
+    pub fn validate(&self) -> Result<()> {
+        if self.host.is_empty() || self.station_id.is_empty() {
+            return Err(DaemonError::InvalidConfiguration(
+                "mqtt.host and mqtt.station_id must be set".to_string()
+            ));
+        }
+        if self.station_id.contains(['/', '+', '#']) {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "mqtt.station_id must not contain '/', '+' or '#': {}", self.station_id
+            )));
+        }
+        if self.device_interval_secs == 0 {
+            return Err(DaemonError::InvalidConfiguration(
+                "mqtt.device_interval_secs must be at least 1".to_string()
+            ));
+        }
+        Ok(())
+    }
+    
+    /// Expand a topic template
+    pub fn topic(&self, template: &str, job_id: Option<Uuid>) -> String {
+        let topic = template.replace("{station}", &self.station_id);
+        match job_id {
+            Some(job_id) => topic.replace("{job_id}", &job_id.to_string()),
+            None => topic,
+        }
+    }
+}
+
+impl Default for MqttTopics {
+    fn default() -> Self {
+        Self {
+            availability: "safeerase/{station}/status".to_string(),
+            devices: "safeerase/{station}/devices".to_string(),
+            job_state: "safeerase/{station}/jobs/{job_id}/state".to_string(),
+            job_progress: "safeerase/{station}/jobs/{job_id}/progress".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+mod publisher {
+    use std::time::Duration;
+    use rumqttc::{AsyncClient, LastWill, MqttOptions, QoS};
+    use serde::Serialize;
+    use tokio::sync::broadcast;
+    use tracing::{debug, info, warn};
+    
+    use super::MqttConfig;
+    use crate::events::JobEvent;
+    use crate::jobs::JobManager;
+    
+    /// Publishes daemon state to an MQTT broker
+    #[derive(Debug, Clone)]
+    pub struct MqttPublisher {
+        config: MqttConfig,
+        client: AsyncClient,
+    }
+    
+    impl MqttPublisher {
+        /// Connect to the broker; the connection is driven by a background task
+        pub fn connect(config: MqttConfig) -> Self {
+            let mut options = MqttOptions::new(format!("safeerase-{}", config.station_id), &config.host, config.port);
+            options.set_keep_alive(Duration::from_secs(30));
+            if let (Some(username), Some(password)) = (&config.username, &config.password) {
+                options.set_credentials(username, password);
+            }
+            let availability = config.topic(&config.topics.availability, None);
+            options.set_last_will(LastWill::new(&availability, "offline", QoS::AtLeastOnce, true));
+            
+            let (client, mut event_loop) = AsyncClient::new(options, 256);
+            let host = config.host.clone();
+            tokio::spawn(async move {
+                loop {
+                    if let Err(e) = event_loop.poll().await {
+                        warn!("MQTT connection to {} failed: {}", host, e);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            });
+            
+            info!("Publishing station {} status to MQTT broker {}:{}", config.station_id, config.host, config.port);
+            Self { config, client }
+        }
+        
+        /// Publish availability, the device list and job events until the daemon stops
+        pub fn spawn(self, jobs: JobManager) {
+            let publisher = self.clone();
+            let device_jobs = jobs.clone();
+            tokio::spawn(async move {
+                let availability = publisher.config.topic(&publisher.config.topics.availability, None);
+                publisher.publish(&availability, QoS::AtLeastOnce, true, b"online".to_vec()).await;
+                
+                let mut interval = tokio::time::interval(Duration::from_secs(publisher.config.device_interval_secs));
+                loop {
+                    interval.tick().await;
+                    match device_jobs.engine().get_device_status().await {
+                        Ok(devices) => {
+                            let topic = publisher.config.topic(&publisher.config.topics.devices, None);
+                            publisher.publish_json(&topic, QoS::AtLeastOnce, true, &devices).await;
+                        }
+                        Err(e) => warn!("Cannot read device status for MQTT: {}", e),
+                    }
+                }
+            });
+            
+            let mut events = jobs.subscribe_events();
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(JobEvent::Progress { job_id, progress }) => {
+                            let topic = self.config.topic(&self.config.topics.job_progress, Some(job_id));
+                            self.publish_json(&topic, QoS::AtMostOnce, false, &progress).await;
+                        }
+                        Ok(JobEvent::StateChanged { job_id, .. }) => {
+                            if let Ok(job) = jobs.get(job_id).await {
+                                let topic = self.config.topic(&self.config.topics.job_state, Some(job_id));
+                                self.publish_json(&topic, QoS::AtLeastOnce, true, &job).await;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            debug!("MQTT publisher skipped {} job events", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+        
+        async fn publish_json<T: Serialize>(&self, topic: &str, qos: QoS, retain: bool, value: &T) {
+            match serde_json::to_vec(value) {
+                Ok(payload) => self.publish(topic, qos, retain, payload).await,
+                Err(e) => warn!("Failed to serialize MQTT payload for {}: {}", topic, e),
+            }
+        }
+        
+        async fn publish(&self, topic: &str, qos: QoS, retain: bool, payload: Vec<u8>) {
+            if let Err(e) = self.client.publish(topic, qos, retain, payload).await {
+                warn!("Failed to publish to MQTT topic {}: {}", topic, e);
+            }
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_device_interval_secs() -> u64 {
+    30
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config() -> MqttConfig {
+        serde_json::from_str(r#"{"host": "broker.lab", "station_id": "bench-07"}"#).unwrap()
+    }
+    
+    #[test]
+    fn test_defaults() {
+        let config = config();
+        assert_eq!(config.port, 1883);
+        assert_eq!(config.device_interval_secs, 30);
+        assert!(config.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_topic_expansion() {
+        let config = config();
+        let job_id = Uuid::nil();
+        assert_eq!(config.topic(&config.topics.devices, None), "safeerase/bench-07/devices");
+        assert_eq!(
+            config.topic(&config.topics.job_progress, Some(job_id)),
+            format!("safeerase/bench-07/jobs/{}/progress", job_id)
+        );
+    }
+    
+    #[test]
+    fn test_wildcards_rejected_in_station_id() {
+        let config = MqttConfig { station_id: "bench/+".to_string(), ..config() };
+        assert!(config.validate().is_err());
+    }
+}
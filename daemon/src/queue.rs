@@ -0,0 +1,114 @@
+//! Ordering of work waiting for a wipe slot
+//!
+//! The daemon runs at most `max_concurrent_jobs` wipes at a time. Queued jobs
+//! start by priority, oldest first within a priority. A job's priority rises
+//! one level for every [`AGING_INTERVAL`] it waits, up to `High`, so a steady
+//! stream of normal work cannot starve low-priority jobs; only jobs submitted
+//! as `Urgent` rank above everything else.
+//!
+//! Scheduled free-space wipes compete for the same slots as `Low` work. With
+//! preemption enabled, an `Urgent` job that finds every slot taken stops a
+//! running free-space wipe; the task is re-run as soon as a slot frees, with
+//! the age it had when it was first due.
+
+use std::cmp::Reverse;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Waiting time after which queued work is treated as one priority higher
+pub const AGING_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// How urgently a job should run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// Background work, such as scheduled free-space wipes
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// Runs before all other work and may preempt free-space wipes
+    Urgent,
+}
+
+/// Work outside the job queue holding a wipe slot
+#[derive(Debug)]
+pub(crate) struct BackgroundRun {
+    pub label: String,
+    pub cancel: CancellationToken,
+    /// Job the run was stopped for
+    pub preempted_by: Option<Uuid>,
+}
+
+impl JobPriority {
+    /// Priority after aging, given how long the work has waited
+    pub fn effective(self, waiting_since: DateTime<Utc>, now: DateTime<Utc>) -> JobPriority {
+        if self == JobPriority::Urgent {
+            return self;
+        }
+        let waited = now.signed_duration_since(waiting_since);
+        let steps = (waited.num_seconds() / AGING_INTERVAL.as_secs() as i64).max(0);
+        let level = (self as i64 + steps).min(JobPriority::High as i64);
+        match level {
+            0 => JobPriority::Low,
+            1 => JobPriority::Normal,
+            _ => JobPriority::High,
+        }
+    }
+}
+
+/// Sort key of waiting work: smaller keys start first
+pub(crate) fn queue_key(
+    priority: JobPriority,
+    waiting_since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> (Reverse<JobPriority>, DateTime<Utc>) {
+    (Reverse(priority.effective(waiting_since, now)), waiting_since)
+}
+
+impl std::fmt::Display for JobPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobPriority::Low => write!(f, "low"),
+            JobPriority::Normal => write!(f, "normal"),
+            JobPriority::High => write!(f, "high"),
+            JobPriority::Urgent => write!(f, "urgent"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    
+    fn aged(intervals: i32) -> ChronoDuration {
+        ChronoDuration::from_std(AGING_INTERVAL).unwrap() * intervals
+    }
+    
+    #[test]
+    fn test_aging_stops_below_urgent() {
+        let now = Utc::now();
+        assert_eq!(JobPriority::Low.effective(now, now), JobPriority::Low);
+        assert_eq!(JobPriority::Low.effective(now - aged(1), now), JobPriority::Normal);
+        assert_eq!(JobPriority::Low.effective(now - aged(10), now), JobPriority::High);
+        assert_eq!(JobPriority::Urgent.effective(now, now), JobPriority::Urgent);
+        
+        let priority: JobPriority = serde_json::from_str("\"urgent\"").unwrap();
+        assert_eq!(priority, JobPriority::Urgent);
+    }
+    
+    #[test]
+    fn test_older_work_wins_ties() {
+        let now = Utc::now();
+        let early = now - ChronoDuration::minutes(5);
+        let key = |priority, since| queue_key(priority, since, now);
+        assert!(key(JobPriority::Normal, early) < key(JobPriority::Normal, now));
+        assert!(key(JobPriority::High, now) < key(JobPriority::Normal, early));
+        // A low job that waited an hour has overtaken fresh normal work
+        assert!(key(JobPriority::Low, now - aged(2)) < key(JobPriority::Normal, now));
+    }
+}
@@ -0,0 +1,199 @@
+//! Per-client rate limits
+//!
+//! Each API key gets a token bucket for requests and another for job
+//! submissions, refilled evenly over a minute, plus a cap on the jobs it may
+//! have unfinished at once. The limits in `DaemonConfig::rate_limits` apply to
+//! every key unless the key sets its own. A request over a rate is refused
+//! with `429 rate_limited` and a `Retry-After` telling the client when the
+//! next one would be accepted; a submission over the job cap is refused with
+//! `429 quota_exceeded`, since waiting a fixed time does not help there.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{ApiKeyConfig, Principal};
+use crate::error::{DaemonError, Result};
+use crate::jobs::Job;
+use crate::AppState;
+
+/// Limits for one API client; unset fields are not limited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimits {
+    /// API requests per minute, in bursts of up to this many
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Jobs submitted per minute; each asset of a batch manifest counts
+    #[serde(default)]
+    pub submissions_per_minute: Option<u32>,
+    /// Jobs pending approval, queued or running at the same time
+    #[serde(default)]
+    pub max_active_jobs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Counter {
+    Requests,
+    Submissions,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets of all clients
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    defaults: RateLimits,
+    overrides: Arc<HashMap<String, RateLimits>>,
+    buckets: Arc<Mutex<HashMap<(String, Counter), Bucket>>>,
+}
+
+impl RateLimits {
+    /// Check that no rate is zero, which would refuse every request
+    pub fn validate(&self) -> Result<()> {
+        if self.requests_per_minute == Some(0) || self.submissions_per_minute == Some(0) || self.max_active_jobs == Some(0) {
+            return Err(DaemonError::InvalidConfiguration(
+                "rate limits must be at least 1; leave them unset for no limit".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Bucket {
+    /// Take `count` tokens from a bucket holding up to `per_minute`, or tell
+    /// how long until they are there. More than the bucket holds takes a full bucket.
+    fn take(&mut self, count: u32, per_minute: u32, now: Instant) -> std::result::Result<(), Duration> {
+        let capacity = f64::from(per_minute);
+        let per_second = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated = now;
+        
+        let needed = f64::from(count).min(capacity);
+        if needed <= self.tokens {
+            self.tokens -= needed;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((needed - self.tokens) / per_second))
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Limiter applying `defaults` to keys without limits of their own
+    pub fn new(defaults: RateLimits, keys: &[ApiKeyConfig]) -> Self {
+        let overrides = keys
+            .iter()
+            .filter_map(|key| key.rate_limits.clone().map(|limits| (key.name.clone(), limits)))
+            .collect();
+        Self { defaults, overrides: Arc::new(overrides), buckets: Arc::default() }
+    }
+    
+    fn limits(&self, client: &str) -> &RateLimits {
+        self.overrides.get(client).unwrap_or(&self.defaults)
+    }
+    
+    fn take(&self, client: &str, counter: Counter, count: u32, per_minute: Option<u32>, now: Instant) -> Result<()> {
+        let Some(per_minute) = per_minute else {
+            return Ok(());
+        };
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets
+            .entry((client.to_string(), counter))
+            .or_insert(Bucket { tokens: f64::from(per_minute), updated: now });
+        bucket.take(count, per_minute, now).map_err(|wait| {
+            let what = match counter {
+                Counter::Requests => "requests",
+                Counter::Submissions => "job submissions",
+            };
+            DaemonError::RateLimited {
+                message: format!("{} exceeded {} {} per minute", client, per_minute, what),
+                retry_after_secs: wait.as_secs_f64().ceil() as u64,
+            }
+        })
+    }
+    
+    /// Count one API request of `client`
+    pub fn check_request(&self, client: &str) -> Result<()> {
+        self.take(client, Counter::Requests, 1, self.limits(client).requests_per_minute, Instant::now())
+    }
+    
+    /// Count `count` job submissions of `client`, given all jobs the daemon tracks
+    pub fn check_submissions(&self, client: &str, count: usize, jobs: &[Job]) -> Result<()> {
+        let limits = self.limits(client);
+        if let Some(limit) = limits.max_active_jobs {
+            let active = jobs
+                .iter()
+                .filter(|job| job.submitted_by == client && !job.state.is_terminal())
+                .count();
+            if active + count > limit {
+                return Err(DaemonError::QuotaExceeded(format!(
+                    "{} already has {} unfinished jobs (limit {})",
+                    client, active, limit
+                )));
+            }
+        }
+        let count = u32::try_from(count).unwrap_or(u32::MAX);
+        self.take(client, Counter::Submissions, count, limits.submissions_per_minute, Instant::now())
+    }
+}
+
+/// Middleware counting each authenticated request against its client's rate
+pub async fn limit_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> std::result::Result<Response, DaemonError> {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        state.rate_limiter.check_request(&principal.name)?;
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bucket_refills_over_a_minute() {
+        let start = Instant::now();
+        let mut bucket = Bucket { tokens: 2.0, updated: start };
+        assert!(bucket.take(1, 2, start).is_ok());
+        assert!(bucket.take(1, 2, start).is_ok());
+        // One token comes back every 30 seconds
+        let wait = |result: std::result::Result<(), Duration>| result.map_err(|wait| wait.as_secs_f64().round());
+        assert_eq!(wait(bucket.take(1, 2, start)), Err(30.0));
+        assert!(bucket.take(1, 2, start + Duration::from_secs(30)).is_ok());
+        // A batch larger than the bucket waits for a full one
+        assert_eq!(wait(bucket.take(5, 2, start + Duration::from_secs(30))), Err(60.0));
+    }
+    
+    #[test]
+    fn test_limits_per_client() {
+        let keys = vec![ApiKeyConfig {
+            name: "mdm".to_string(),
+            key_sha256: String::new(),
+            role: Default::default(),
+            tenant: None,
+            rate_limits: Some(RateLimits { requests_per_minute: Some(1), ..Default::default() }),
+            approval_public_key: None,
+        }];
+        let limiter = RateLimiter::new(RateLimits { requests_per_minute: Some(100), ..Default::default() }, &keys);
+        
+        assert!(limiter.check_request("mdm").is_ok());
+        let error = limiter.check_request("mdm").unwrap_err();
+        assert!(matches!(error, DaemonError::RateLimited { retry_after_secs: 60, .. }));
+        assert!(limiter.check_request("portal").is_ok());
+        assert!(limiter.check_request("portal").is_ok());
+        
+        assert!(RateLimits { submissions_per_minute: Some(0), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,388 @@
+//! HTTP route handlers for the daemon API
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::response::sse::{Event, Sse};
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use safe_erase_certificates::DestructionRequest;
+use safe_erase_core::assets::{self, AssetManifest};
+use safe_erase_core::{CancelReason, Cancellation, DeviceInfo, Diagnostics, ProgressToken, WipeAlgorithm};
+
+use crate::audit::{AuditDecision, AuditRecord};
+use crate::auth::{authorize, resolve_approver, Permission, Principal};
+use crate::error::{DaemonError, Result};
+use crate::events::{self, JobEvent};
+use crate::jobs::{BatchSubmission, Job, JobRequest};
+use crate::schedule::{ScheduleRequest, ScheduledTask};
+use crate::AppState;
+
+/// OpenAPI specification served at `/api/v1/openapi.yaml`
+pub const OPENAPI_SPEC: &str = include_str!("../openapi.yaml");
+
+/// Health check response
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub version: &'static str,
+}
+
+/// `GET /health`
+pub async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// `GET /api/v1/openapi.yaml`
+pub async fn openapi_spec() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/yaml")], OPENAPI_SPEC)
+}
+
+/// Query parameters for `GET /api/v1/audit`
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default = "default_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_audit_limit() -> usize {
+    100
+}
+
+/// Request body for `POST /api/v1/jobs/{job_id}/approve`
+#[derive(Debug, Default, Deserialize)]
+pub struct ApprovalRequest {
+    /// Token signed by the approver; the caller approves when absent
+    #[serde(default)]
+    pub approval_token: Option<String>,
+}
+
+/// Request body for `POST /api/v1/jobs/{job_id}/cancel`
+#[derive(Debug, Default, Deserialize)]
+pub struct CancelRequest {
+    #[serde(default)]
+    pub reason: CancelReason,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Query parameters for `GET /api/v1/jobs`
+#[derive(Debug, Default, Deserialize)]
+pub struct JobsQuery {
+    /// Only jobs performed under this work order
+    #[serde(default)]
+    pub work_order: Option<String>,
+}
+
+/// Query parameters for `POST /api/v1/batches`
+#[derive(Debug, Deserialize)]
+pub struct BatchQuery {
+    pub algorithm: WipeAlgorithm,
+}
+
+/// `GET /api/v1/devices`
+pub async fn list_devices(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Vec<DeviceInfo>>> {
+    authorize(&state.audit, &principal, Permission::ViewDevices, None).await?;
+    let devices = state.jobs.engine().discover_devices().await?;
+    Ok(Json(devices))
+}
+
+/// `GET /api/v1/diagnostics`
+pub async fn diagnostics(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Diagnostics>> {
+    authorize(&state.audit, &principal, Permission::ViewDevices, None).await?;
+    principal.require_operator()?;
+    let mut diagnostics = state.jobs.engine().diagnostics();
+    diagnostics.add_signing_backend(safe_erase_certificates::keys::BACKEND);
+    Ok(Json(diagnostics))
+}
+
+/// `POST /api/v1/jobs`
+pub async fn submit_job(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(mut request): Json<JobRequest>,
+) -> Result<(StatusCode, Json<Job>)> {
+    let target = Some(request.device_path.clone());
+    authorize(&state.audit, &principal, Permission::SubmitJob, target.clone()).await?;
+    if request.override_interlocks {
+        authorize(&state.audit, &principal, Permission::OverrideInterlocks, target).await?;
+    }
+    request.tenant = principal.tenant.clone();
+    state.rate_limiter.check_submissions(&principal.name, 1, &state.jobs.list().await)?;
+    let psid_entered = request.psid.is_some();
+    let job = state.jobs.submit(request, &principal.name).await?;
+    if psid_entered {
+        let reason = format!("PSID entered for job {}; the PSID is not recorded", job.job_id);
+        state.audit.record(&principal, "enter_psid", Some(job.device_path.clone()), AuditDecision::Allowed, Some(reason)).await;
+    }
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// `POST /api/v1/batches` with an asset CSV (`serial`, `asset_tag`, optional `work_order`) as the body
+pub async fn submit_batch(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<BatchQuery>,
+    body: String,
+) -> Result<(StatusCode, Json<BatchSubmission>)> {
+    authorize(&state.audit, &principal, Permission::SubmitJob, None).await?;
+    let manifest = AssetManifest::parse_csv(&body)?;
+    state.rate_limiter.check_submissions(&principal.name, manifest.assets.len(), &state.jobs.list().await)?;
+    let batch = state.jobs.submit_batch(&manifest, query.algorithm, None, &principal).await?;
+    Ok((StatusCode::ACCEPTED, Json(batch)))
+}
+
+/// `GET /api/v1/batches/completion.csv`
+pub async fn batch_completion_csv(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, None).await?;
+    let records = state.jobs.completion_records(&principal).await;
+    let mut csv = Vec::new();
+    assets::write_completion_csv(&mut csv, &records)?;
+    Ok(([(header::CONTENT_TYPE, "text/csv")], csv))
+}
+
+/// `GET /api/v1/batches/{batch_id}/bundle.zip`
+pub async fn batch_bundle(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(batch_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    authorize(&state.audit, &principal, Permission::ViewCertificates, Some(batch_id.to_string())).await?;
+    let bundle = state.jobs.batch_bundle(batch_id, &state.audit, &principal).await?;
+    let disposition = format!("attachment; filename=\"batch_{}.zip\"", batch_id);
+    Ok(([(header::CONTENT_TYPE, "application/zip".to_string()), (header::CONTENT_DISPOSITION, disposition)], bundle))
+}
+
+/// `GET /api/v1/jobs`
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<JobsQuery>,
+) -> Result<Json<Vec<Job>>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, None).await?;
+    let mut jobs = state.jobs.list().await;
+    jobs.retain(|job| principal.can_see(job.tenant.as_deref()));
+    if let Some(work_order) = &query.work_order {
+        jobs.retain(|job| job.options.work_order.as_deref() == Some(work_order.as_str()));
+    }
+    Ok(Json(jobs))
+}
+
+/// `GET /api/v1/jobs/{job_id}`
+pub async fn get_job(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<Job>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, Some(job_id.to_string())).await?;
+    Ok(Json(state.jobs.get_for(job_id, &principal).await?))
+}
+
+/// `POST /api/v1/jobs/{job_id}/cancel`
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+    body: Option<Json<CancelRequest>>,
+) -> Result<Json<Job>> {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let target = Some(job_id.to_string());
+    authorize(&state.audit, &principal, Permission::CancelJob, target.clone()).await?;
+    state.jobs.get_for(job_id, &principal).await?;
+    let cancellation = Cancellation {
+        detail: request.detail,
+        ..Cancellation::new(request.reason).with_requested_by(&principal.name)
+    };
+    let reason = cancellation.to_string();
+    let job = state.jobs.cancel(job_id, cancellation).await?;
+    state.audit.record(&principal, "job_cancelled", target, AuditDecision::Allowed, Some(reason)).await;
+    Ok(Json(job))
+}
+
+/// `POST /api/v1/jobs/{job_id}/approve`
+pub async fn approve_job(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+    body: Option<Json<ApprovalRequest>>,
+) -> Result<Json<Job>> {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    state.jobs.get_for(job_id, &principal).await?;
+    let approver = resolve_approver(
+        &state.authenticator,
+        &state.audit,
+        &principal,
+        request.approval_token.as_deref(),
+        job_id,
+    ).await?;
+    // A token may carry another tenant's signature
+    state.jobs.get_for(job_id, &approver).await?;
+    Ok(Json(state.jobs.approve(job_id, &approver, &state.audit).await?))
+}
+
+/// `GET /api/v1/jobs/{job_id}/events` (server-sent events)
+///
+/// Progress events carry their resume token as the event ID. A client that
+/// reconnects with it in `Last-Event-ID`, as browsers do, first receives the
+/// progress it missed instead of only the latest snapshot.
+pub async fn job_events(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Sse<ReceiverStream<std::result::Result<Event, Infallible>>>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, Some(job_id.to_string())).await?;
+    
+    // Subscribe before reading the snapshot so no transition is missed
+    let subscription = state.jobs.subscribe_events();
+    let job = state.jobs.get_for(job_id, &principal).await?;
+    
+    let resume_from = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<ProgressToken>().ok());
+    let missed = match (resume_from, &job.progress) {
+        (Some(token), Some(latest)) if token.operation_id == latest.operation_id => {
+            state.jobs.engine().resume_progress(&token).ok().map(|replay| replay.missed().cloned().collect::<Vec<_>>())
+        }
+        _ => None,
+    };
+    
+    let mut initial = Vec::new();
+    match missed {
+        Some(missed) => initial.extend(missed.into_iter().map(|progress| JobEvent::Progress { job_id, progress })),
+        None => initial.extend(job.progress.map(|progress| JobEvent::Progress { job_id, progress })),
+    }
+    if let Some(result) = job.verification {
+        initial.push(JobEvent::Verification { job_id, result });
+    }
+    initial.push(JobEvent::StateChanged { job_id, state: job.state });
+    
+    Ok(events::sse_stream(subscription, Some(job_id), initial))
+}
+
+/// `GET /api/v1/events` (server-sent events for all jobs)
+pub async fn all_events(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Sse<ReceiverStream<std::result::Result<Event, Infallible>>>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, None).await?;
+    // Events carry no tenant; tenants follow their jobs one by one
+    principal.require_operator()?;
+    Ok(events::sse_stream(state.jobs.subscribe_events(), None, Vec::new()))
+}
+
+/// `GET /api/v1/jobs/{job_id}/certificate`
+pub async fn get_certificate(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    authorize(&state.audit, &principal, Permission::ViewCertificates, Some(job_id.to_string())).await?;
+    let job = state.jobs.get_for(job_id, &principal).await?;
+    let path = job
+        .certificate_json_path
+        .ok_or_else(|| DaemonError::CertificateNotAvailable(job_id.to_string()))?;
+    
+    let contents = state.jobs.read_certificate(&path).await?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], contents))
+}
+
+/// `GET /api/v1/jobs/{job_id}/certificate.pdf`
+pub async fn get_certificate_pdf(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(job_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    authorize(&state.audit, &principal, Permission::ViewCertificates, Some(job_id.to_string())).await?;
+    let job = state.jobs.get_for(job_id, &principal).await?;
+    let path = job
+        .certificate_pdf_path
+        .ok_or_else(|| DaemonError::CertificateNotAvailable(job_id.to_string()))?;
+    
+    let contents = state.jobs.read_certificate(&path).await?;
+    Ok(([(header::CONTENT_TYPE, "application/pdf")], contents))
+}
+
+/// `GET /api/v1/audit`
+pub async fn list_audit(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditRecord>>> {
+    authorize(&state.audit, &principal, Permission::ViewAudit, None).await?;
+    Ok(Json(state.audit.recent_for(&principal, query.limit).await))
+}
+
+/// `GET /api/v1/schedules`
+pub async fn list_schedules(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Vec<ScheduledTask>>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, None).await?;
+    principal.require_operator()?;
+    Ok(Json(state.scheduler.list().await))
+}
+
+/// `POST /api/v1/schedules`
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(request): Json<ScheduleRequest>,
+) -> Result<(StatusCode, Json<ScheduledTask>)> {
+    authorize(&state.audit, &principal, Permission::ManageSchedules, Some(request.name.clone())).await?;
+    principal.require_operator()?;
+    let task = state.scheduler.create(request, &principal.name).await?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+/// `POST /api/v1/destruction-records`
+pub async fn record_destruction(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(request): Json<DestructionRequest>,
+) -> Result<impl IntoResponse> {
+    authorize(&state.audit, &principal, Permission::RecordDestruction, Some(request.device_serial.clone())).await?;
+    principal.require_operator()?;
+    let record = state.jobs.record_destruction(request, &principal.name).await?;
+    let contents = state.jobs.read_certificate(&record.json_path).await?;
+    Ok((StatusCode::CREATED, [(header::CONTENT_TYPE, "application/json")], contents))
+}
+
+/// `GET /api/v1/schedules/{task_id}`
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<ScheduledTask>> {
+    authorize(&state.audit, &principal, Permission::ViewJobs, Some(task_id.to_string())).await?;
+    principal.require_operator()?;
+    Ok(Json(state.scheduler.get(task_id).await?))
+}
+
+/// `DELETE /api/v1/schedules/{task_id}`
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<ScheduledTask>> {
+    authorize(&state.audit, &principal, Permission::ManageSchedules, Some(task_id.to_string())).await?;
+    principal.require_operator()?;
+    Ok(Json(state.scheduler.delete(task_id).await?))
+}
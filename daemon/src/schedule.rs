@@ -0,0 +1,635 @@
+//! Recurring maintenance tasks
+//!
+//! Tasks run on cron-style schedules and are limited to work that leaves live
+//! data intact: overwriting the free space of a mounted filesystem and
+//! re-verifying spares that were wiped earlier. Tasks, their next run time and
+//! their run history are persisted to `schedule_path` so they survive daemon
+//! restarts; a run missed while the daemon was down is performed once at startup.
+//! The file is encrypted when the engine has a storage key.
+//!
+//! Free-space wipes take a wipe slot from the [`JobManager`] like any `Low`
+//! priority job and wait for one when all are busy. A run preempted by an
+//! urgent job is owed: it runs again, from the start, as soon as a slot frees.
+//!
+//! Schedules use the usual five fields `minute hour day-of-month month
+//! day-of-week` evaluated in UTC, with `*`, lists (`1,15`), ranges (`1-5`) and
+//! steps (`*/10`, `0-30/5`), plus the shorthands `@hourly`, `@daily`,
+//! `@weekly` and `@monthly`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use safe_erase_core::{at_rest, WipeAlgorithm, WipeOptions, WipeResult};
+
+use crate::error::{DaemonError, Result};
+use crate::jobs::{JobManager, JobState};
+
+/// Run records kept per task
+const HISTORY_LIMIT: usize = 50;
+
+/// Kind of record the schedule file is sealed as
+const SCHEDULE_KIND: &str = "schedule";
+
+/// How often the scheduler looks for due tasks
+const TICK_INTERVAL: Duration = Duration::from_secs(20);
+
+/// How often a due free-space wipe asks for a wipe slot
+const SLOT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far ahead `next_after` searches before giving up (covers leap days)
+const SEARCH_HORIZON_DAYS: i64 = 4 * 366;
+
+/// Parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days_of_month: Vec<u32>,
+    months: Vec<u32>,
+    days_of_week: Vec<u32>,
+    /// Day-of-month and day-of-week were both restricted; either may match
+    day_or: bool,
+}
+
+/// What a scheduled task does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Overwrite the unallocated space of a mounted filesystem
+    FreeSpaceWipe {
+        mount_point: PathBuf,
+        algorithm: WipeAlgorithm,
+    },
+    /// Verify a previously wiped device again
+    Reverify {
+        device_path: String,
+        /// Job whose wipe is being re-checked
+        source_job_id: Uuid,
+        /// Result of that wipe, kept so re-verification works after a restart
+        baseline: Box<WipeResult>,
+    },
+}
+
+/// Task definition as submitted by a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskRequest {
+    FreeSpaceWipe {
+        mount_point: PathBuf,
+        #[serde(default = "default_free_space_algorithm")]
+        algorithm: WipeAlgorithm,
+    },
+    /// Re-verify the device wiped by a completed job
+    Reverify { job_id: Uuid },
+}
+
+/// Request body for creating a scheduled task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: String,
+    /// Cron expression, evaluated in UTC
+    pub schedule: String,
+    pub task: TaskRequest,
+}
+
+/// Outcome of a single run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Succeeded,
+    Failed,
+    /// Not started, e.g. because the device was busy
+    Skipped,
+    /// Stopped to free its slot for an urgent job; run again later
+    Preempted,
+}
+
+/// History entry for one run of a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub run_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: RunOutcome,
+    pub detail: String,
+}
+
+/// A recurring task and its history, newest run first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    pub task_id: Uuid,
+    pub name: String,
+    pub schedule: CronSchedule,
+    pub task: TaskKind,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub next_run: Option<DateTime<Utc>>,
+    /// When a preempted run was first due; set until the run is made up
+    #[serde(default)]
+    pub resume_since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub history: Vec<TaskRun>,
+}
+
+/// Runs scheduled tasks and persists them
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    jobs: JobManager,
+    tasks: Arc<RwLock<HashMap<Uuid, ScheduledTask>>>,
+    running: Arc<RwLock<HashSet<Uuid>>>,
+    path: Option<PathBuf>,
+}
+
+impl CronSchedule {
+    /// Parse a five-field cron expression or shorthand
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid_schedule(expression, "expected five fields"));
+        }
+        
+        let mut days_of_week = parse_field(fields[4], 0, 7).map_err(|e| invalid_schedule(expression, &e))?;
+        // Both 0 and 7 mean Sunday
+        if days_of_week.contains(&7) {
+            days_of_week.retain(|&d| d != 7);
+            if !days_of_week.contains(&0) {
+                days_of_week.insert(0, 0);
+            }
+        }
+        
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            minutes: parse_field(fields[0], 0, 59).map_err(|e| invalid_schedule(expression, &e))?,
+            hours: parse_field(fields[1], 0, 23).map_err(|e| invalid_schedule(expression, &e))?,
+            days_of_month: parse_field(fields[2], 1, 31).map_err(|e| invalid_schedule(expression, &e))?,
+            months: parse_field(fields[3], 1, 12).map_err(|e| invalid_schedule(expression, &e))?,
+            days_of_week,
+            day_or: fields[2] != "*" && fields[4] != "*",
+        })
+    }
+    
+    /// First matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let horizon = start + ChronoDuration::days(SEARCH_HORIZON_DAYS);
+        let mut candidate = start;
+        
+        while candidate < horizon {
+            if !self.months.contains(&candidate.month()) {
+                candidate = start_of_next_month(candidate)?;
+                continue;
+            }
+            if !self.day_matches(candidate) {
+                candidate = start_of_day(candidate)? + ChronoDuration::days(1);
+                continue;
+            }
+            if !self.hours.contains(&candidate.hour()) {
+                candidate = candidate.with_minute(0)? + ChronoDuration::hours(1);
+                continue;
+            }
+            if !self.minutes.contains(&candidate.minute()) {
+                candidate += ChronoDuration::minutes(1);
+                continue;
+            }
+            return Some(candidate);
+        }
+        None
+    }
+    
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let dom = self.days_of_month.contains(&time.day());
+        let dow = self.days_of_week.contains(&time.weekday().num_days_from_sunday());
+        if self.day_or {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+}
+
+impl Scheduler {
+    /// Load persisted tasks, or start empty when no file exists yet
+    pub fn load(jobs: JobManager, path: Option<PathBuf>) -> Result<Self> {
+        let mut tasks = HashMap::new();
+        if let Some(path) = &path {
+            match std::fs::read(path) {
+                Ok(contents) => {
+                    let contents = at_rest::open(jobs.engine().storage_key(), SCHEDULE_KIND, contents)?;
+                    let stored: Vec<ScheduledTask> = serde_json::from_slice(&contents).map_err(|e| {
+                        DaemonError::InvalidConfiguration(format!("{}: {}", path.display(), e))
+                    })?;
+                    info!("Loaded {} scheduled task(s) from {}", stored.len(), path.display());
+                    tasks.extend(stored.into_iter().map(|task| (task.task_id, task)));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        
+        Ok(Self {
+            jobs,
+            tasks: Arc::new(RwLock::new(tasks)),
+            running: Arc::new(RwLock::new(HashSet::new())),
+            path,
+        })
+    }
+    
+    /// Add a recurring task
+    pub async fn create(&self, request: ScheduleRequest, created_by: &str) -> Result<ScheduledTask> {
+        if request.name.trim().is_empty() {
+            return Err(DaemonError::BadRequest("Task name must not be empty".to_string()));
+        }
+        let schedule = CronSchedule::parse(&request.schedule)?;
+        let next_run = schedule.next_after(Utc::now());
+        if next_run.is_none() {
+            return Err(DaemonError::BadRequest(format!("Schedule '{}' never fires", request.schedule)));
+        }
+        
+        let task = match request.task {
+            TaskRequest::FreeSpaceWipe { mount_point, algorithm } => {
+                if algorithm.is_hardware_based() {
+                    return Err(DaemonError::BadRequest(format!(
+                        "{} erases the whole device and cannot be scheduled",
+                        algorithm
+                    )));
+                }
+                if !mount_point.is_absolute() {
+                    return Err(DaemonError::BadRequest("mount_point must be an absolute path".to_string()));
+                }
+                TaskKind::FreeSpaceWipe { mount_point, algorithm }
+            }
+            TaskRequest::Reverify { job_id } => {
+                let job = self.jobs.get(job_id).await?;
+                let baseline = match (job.state, job.result) {
+                    (JobState::Completed, Some(result)) => result,
+                    _ => {
+                        return Err(DaemonError::InvalidJobState(format!(
+                            "Job {} has no completed wipe to re-verify",
+                            job_id
+                        )));
+                    }
+                };
+                TaskKind::Reverify {
+                    device_path: job.device_path,
+                    source_job_id: job_id,
+                    baseline: Box::new(baseline),
+                }
+            }
+        };
+        
+        let task = ScheduledTask {
+            task_id: Uuid::new_v4(),
+            name: request.name,
+            schedule,
+            task,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+            next_run,
+            resume_since: None,
+            history: Vec::new(),
+        };
+        info!("Scheduled task {} ({}) created by {}", task.task_id, task.name, created_by);
+        
+        let mut tasks = self.tasks.write().await;
+        tasks.insert(task.task_id, task.clone());
+        self.save(&tasks).await;
+        Ok(task)
+    }
+    
+    /// All tasks ordered by next run
+    pub async fn list(&self) -> Vec<ScheduledTask> {
+        let mut tasks: Vec<ScheduledTask> = self.tasks.read().await.values().cloned().collect();
+        tasks.sort_by_key(|task| task.next_run);
+        tasks
+    }
+    
+    /// Look up a task
+    pub async fn get(&self, task_id: Uuid) -> Result<ScheduledTask> {
+        self.tasks
+            .read()
+            .await
+            .get(&task_id)
+            .cloned()
+            .ok_or_else(|| DaemonError::TaskNotFound(task_id.to_string()))
+    }
+    
+    /// Remove a task; a run in progress is allowed to finish
+    pub async fn delete(&self, task_id: Uuid) -> Result<ScheduledTask> {
+        let mut tasks = self.tasks.write().await;
+        let task = tasks
+            .remove(&task_id)
+            .ok_or_else(|| DaemonError::TaskNotFound(task_id.to_string()))?;
+        self.save(&tasks).await;
+        info!("Scheduled task {} ({}) deleted", task.task_id, task.name);
+        Ok(task)
+    }
+    
+    /// Run due tasks in the background for as long as the daemon runs
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                for task in self.claim_due(Utc::now()).await {
+                    let scheduler = self.clone();
+                    tokio::spawn(async move { scheduler.execute(task).await });
+                }
+            }
+        });
+    }
+    
+    /// Advance due tasks to their next run and return them for execution
+    async fn claim_due(&self, now: DateTime<Utc>) -> Vec<ScheduledTask> {
+        let mut tasks = self.tasks.write().await;
+        let mut running = self.running.write().await;
+        let mut due = Vec::new();
+        
+        for task in tasks.values_mut() {
+            let scheduled = task.next_run.is_some_and(|next| next <= now);
+            if !(scheduled || task.resume_since.is_some()) || running.contains(&task.task_id) {
+                continue;
+            }
+            // A preempted run being made up stands in for a scheduled one due meanwhile
+            if scheduled {
+                task.next_run = task.schedule.next_after(now);
+            }
+            running.insert(task.task_id);
+            due.push(task.clone());
+        }
+        
+        if !due.is_empty() {
+            self.save(&tasks).await;
+        }
+        due
+    }
+    
+    async fn execute(&self, task: ScheduledTask) {
+        let due_since = task.resume_since.unwrap_or_else(Utc::now);
+        info!("Running scheduled task {} ({})", task.task_id, task.name);
+        let (outcome, detail, started_at) = self.perform(&task, due_since).await;
+        match outcome {
+            RunOutcome::Succeeded => info!("Scheduled task {} succeeded: {}", task.task_id, detail),
+            RunOutcome::Skipped => warn!("Scheduled task {} skipped: {}", task.task_id, detail),
+            RunOutcome::Failed => error!("Scheduled task {} failed: {}", task.task_id, detail),
+            RunOutcome::Preempted => warn!("Scheduled task {} preempted: {}", task.task_id, detail),
+        }
+        
+        let run = TaskRun {
+            run_id: Uuid::new_v4(),
+            started_at,
+            finished_at: Utc::now(),
+            outcome,
+            detail,
+        };
+        self.running.write().await.remove(&task.task_id);
+        let mut tasks = self.tasks.write().await;
+        if let Some(stored) = tasks.get_mut(&task.task_id) {
+            stored.resume_since = (outcome == RunOutcome::Preempted).then_some(due_since);
+            stored.history.insert(0, run);
+            stored.history.truncate(HISTORY_LIMIT);
+            self.save(&tasks).await;
+        }
+    }
+    
+    /// Carry out one run; returns its outcome, detail and actual start time
+    async fn perform(&self, task: &ScheduledTask, due_since: DateTime<Utc>) -> (RunOutcome, String, DateTime<Utc>) {
+        match &task.task {
+            TaskKind::FreeSpaceWipe { mount_point, algorithm } => {
+                let label = format!("scheduled task {} ({})", task.task_id, task.name);
+                let slot = loop {
+                    match self.jobs.try_acquire_background(&label, due_since).await {
+                        Some(slot) => break slot,
+                        None => tokio::time::sleep(SLOT_POLL_INTERVAL).await,
+                    }
+                };
+                let started_at = Utc::now();
+                let outcome = self
+                    .jobs
+                    .engine()
+                    .wipe_free_space(mount_point, algorithm.clone(), &WipeOptions::default(), slot.cancellation())
+                    .await;
+                let preempted_by = slot.release().await;
+                let (outcome, detail) = match (outcome, preempted_by) {
+                    (Ok(result), _) => (
+                        RunOutcome::Succeeded,
+                        format!(
+                            "Overwrote {} bytes of free space in {} pass(es)",
+                            result.bytes_per_pass, result.passes_completed
+                        ),
+                    ),
+                    (Err(_), Some(job_id)) => (RunOutcome::Preempted, format!("Stopped for urgent job {}", job_id)),
+                    (Err(e), None) => (RunOutcome::Failed, e.user_message()),
+                };
+                (outcome, detail, started_at)
+            }
+            TaskKind::Reverify { device_path, baseline, .. } => {
+                let started_at = Utc::now();
+                let (outcome, detail) = self.reverify(device_path, baseline).await;
+                (outcome, detail, started_at)
+            }
+        }
+    }
+    
+    async fn reverify(&self, device_path: &str, baseline: &WipeResult) -> (RunOutcome, String) {
+        let engine = self.jobs.engine();
+        if self.jobs.list().await.iter().any(|job| job.device_path == device_path && !job.state.is_terminal()) {
+            return (RunOutcome::Skipped, format!("{} has an active wipe job", device_path));
+        }
+        if let Err(e) = engine.discover_devices().await {
+            return (RunOutcome::Failed, e.user_message());
+        }
+        match engine.verify_device(device_path, baseline).await {
+            Ok(verification) if verification.is_successful(engine.verification_thresholds()) => (
+                RunOutcome::Succeeded,
+                format!("Verification {} passed", verification.verification_id),
+            ),
+            Ok(verification) => (
+                RunOutcome::Failed,
+                format!(
+                    "Verification {} failed ({:.1}% of samples passed)",
+                    verification.verification_id,
+                    verification.success_rate * 100.0
+                ),
+            ),
+            Err(e) => (RunOutcome::Failed, e.user_message()),
+        }
+    }
+    
+    /// Write all tasks to the schedule file; failures are logged, not fatal
+    async fn save(&self, tasks: &HashMap<Uuid, ScheduledTask>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let stored: Vec<&ScheduledTask> = tasks.values().collect();
+        let result = async {
+            let contents = serde_json::to_vec_pretty(&stored)?;
+            let contents = at_rest::seal(self.jobs.engine().storage_key(), SCHEDULE_KIND, contents)
+                .map_err(std::io::Error::other)?;
+            let temp = path.with_extension("tmp");
+            tokio::fs::write(&temp, contents).await?;
+            tokio::fs::rename(&temp, path).await
+        }
+        .await;
+        if let Err(e) = result {
+            error!("Failed to persist scheduled tasks to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Parse one cron field into the sorted values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> std::result::Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // `5/15` means every 15 from 5 onwards
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start > end {
+            return Err(format!("range '{}' is reversed", range));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> std::result::Result<u32, String> {
+    let parsed: u32 = value.parse().map_err(|_| format!("invalid value '{}'", value))?;
+    if parsed < min || parsed > max {
+        return Err(format!("{} is outside {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+fn invalid_schedule(expression: &str, reason: &str) -> DaemonError {
+    DaemonError::BadRequest(format!("Invalid schedule '{}': {}", expression, reason))
+}
+
+fn start_of_day(time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    Utc.with_ymd_and_hms(time.year(), time.month(), time.day(), 0, 0, 0).single()
+}
+
+fn start_of_next_month(time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+fn default_free_space_algorithm() -> WipeAlgorithm {
+    WipeAlgorithm::ZeroFill
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = DaemonError;
+    
+    fn try_from(expression: String) -> Result<Self> {
+        Self::parse(&expression)
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+    
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_field("*/15", 0, 59).unwrap(), vec![0, 15, 30, 45]);
+        assert_eq!(parse_field("1-3,10", 0, 59).unwrap(), vec![1, 2, 3, 10]);
+        assert_eq!(parse_field("50/5", 0, 59).unwrap(), vec![50, 55]);
+        assert!(parse_field("60", 0, 59).is_err());
+        assert!(parse_field("5-1", 0, 59).is_err());
+        assert!(CronSchedule::parse("* * *").is_err());
+    }
+    
+    #[test]
+    fn test_next_after() {
+        let daily = CronSchedule::parse("30 2 * * *").unwrap();
+        assert_eq!(daily.next_after(at(2024, 3, 1, 1, 0)), Some(at(2024, 3, 1, 2, 30)));
+        assert_eq!(daily.next_after(at(2024, 3, 1, 2, 30)), Some(at(2024, 3, 2, 2, 30)));
+        
+        // Sundays at 03:00; 2024-03-03 is a Sunday
+        let weekly = CronSchedule::parse("0 3 * * 7").unwrap();
+        assert_eq!(weekly.next_after(at(2024, 3, 1, 0, 0)), Some(at(2024, 3, 3, 3, 0)));
+        
+        let leap = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(leap.next_after(at(2024, 3, 1, 0, 0)), Some(at(2028, 2, 29, 0, 0)));
+        
+        let never = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+    
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        // The 10th of the month or any Monday; 2024-04-10 is a Wednesday, 2024-04-15 a Monday
+        let schedule = CronSchedule::parse("0 0 10 * 1").unwrap();
+        assert_eq!(schedule.next_after(at(2024, 4, 9, 0, 0)), Some(at(2024, 4, 10, 0, 0)));
+        assert_eq!(schedule.next_after(at(2024, 4, 10, 0, 0)), Some(at(2024, 4, 15, 0, 0)));
+    }
+    
+    #[test]
+    fn test_schedule_serializes_as_expression() {
+        let schedule = CronSchedule::parse("@weekly").unwrap();
+        assert_eq!(serde_json::to_string(&schedule).unwrap(), "\"@weekly\"");
+        let parsed: CronSchedule = serde_json::from_str("\"*/5 * * * *\"").unwrap();
+        assert_eq!(parsed.minutes.len(), 12);
+        assert!(serde_json::from_str::<CronSchedule>("\"bogus\"").is_err());
+    }
+    
+    #[test]
+    fn test_task_request_shape() {
+        let request: ScheduleRequest = serde_json::from_str(
+            r#"{"name": "scratch", "schedule": "@daily", "task": {"type": "free_space_wipe", "mount_point": "/srv/scratch"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            request.task,
+            TaskRequest::FreeSpaceWipe { algorithm: WipeAlgorithm::ZeroFill, .. }
+        ));
+    }
+}
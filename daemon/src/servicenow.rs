@@ -0,0 +1,373 @@
+//! ServiceNow connector for wipe outcomes and certificates
+//!
+//! When a job finishes, the asset or CI record whose serial number matches the
+//! wiped device is updated through the ServiceNow Table API, and issued
+//! certificates are attached to it through the Attachment API. The table, the
+//! field used to find the record and which job values land in which fields are
+//! configurable, so no middleware is needed between the station and the CMDB.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use safe_erase_core::{at_rest, StoreKey};
+
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::jobs::{Job, JobManager, JobState, CERTIFICATE_KIND};
+
+/// ServiceNow instance, table and field mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceNowConfig {
+    /// Instance base URL, e.g. `https://acme.service-now.com`
+    pub instance_url: String,
+    pub username: String,
+    pub password: String,
+    /// Table holding the asset or CI records
+    #[serde(default = "default_table")]
+    pub table: String,
+    /// Field matched against the device serial number
+    #[serde(default = "default_match_field")]
+    pub match_field: String,
+    /// ServiceNow field name to job value; see [`JOB_VALUES`] for the value names
+    #[serde(default = "default_field_mapping")]
+    pub field_mapping: BTreeMap<String, String>,
+    /// Attach issued JSON and PDF certificates to the record
+    #[serde(default = "default_attach_certificates")]
+    pub attach_certificates: bool,
+    /// Per-request timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Job values that can be mapped onto ServiceNow fields
+pub const JOB_VALUES: [&str; 11] = [
+    "job_id",
+    "outcome",
+    "algorithm",
+    "certificate_id",
+    "completed_at",
+    "serial",
+    "asset_tag",
+    "work_order",
+    "ticket_url",
+    "verification",
+    "error_message",
+];
+
+/// Updates ServiceNow records as jobs finish
+#[derive(Debug, Clone)]
+pub struct ServiceNowConnector {
+    config: ServiceNowConfig,
+    client: reqwest::Client,
+    /// Decrypts stored certificates before they are attached
+    storage_key: Option<StoreKey>,
+}
+
+impl ServiceNowConfig {
+    /// Validate the connector settings
+    pub fn validate(&self) -> Result<()> {
+        if !self.instance_url.starts_with("https://") {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "servicenow.instance_url must use https: {}", self.instance_url
+            )));
+        }
+        if self.username.is_empty() || self.table.is_empty() || self.match_field.is_empty() {
+            return Err(DaemonError::InvalidConfiguration(
+                "servicenow.username, table and match_field must be set".to_string()
+            ));
+        }
+        if let Some((field, value)) = self.field_mapping.iter().find(|(_, value)| !JOB_VALUES.contains(&value.as_str())) {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "servicenow.field_mapping.{} refers to unknown job value '{}'", field, value
+            )));
+        }
+        Ok(())
+    }
+    
+    fn table_url(&self) -> String {
+        format!("{}/api/now/table/{}", self.instance_url.trim_end_matches('/'), self.table)
+    }
+    
+    fn attachment_url(&self) -> String {
+        format!("{}/api/now/attachment/file", self.instance_url.trim_end_matches('/'))
+    }
+    
+    /// Fields to write for a finished job; unset values are left out
+    pub fn record_update(&self, job: &Job) -> serde_json::Map<String, Value> {
+        let values = job_values(job);
+        self.field_mapping
+            .iter()
+            .filter_map(|(field, value)| {
+                values.get(value.as_str()).map(|v| (field.clone(), Value::String(v.clone())))
+            })
+            .collect()
+    }
+}
+
+impl ServiceNowConnector {
+    pub fn new(config: ServiceNowConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            storage_key: None,
+        }
+    }
+    
+    /// Update records for finished jobs until the event channel closes
+    pub fn spawn(mut self, jobs: JobManager) {
+        self.storage_key = jobs.engine().storage_key().cloned();
+        info!("Reporting wipe outcomes to ServiceNow table {}", self.config.table);
+        
+        let mut events = jobs.subscribe_events();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("ServiceNow connector lagged, skipped {} job events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                // Certificates are issued before the final state change, so
+                // the record update below already carries the certificate ID
+                let finished = matches!(
+                    event,
+                    JobEvent::StateChanged { state: JobState::Completed | JobState::Failed, .. }
+                );
+                if !finished {
+                    continue;
+                }
+                let Ok(job) = jobs.get(event.job_id()).await else { continue };
+                
+                let connector = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = connector.report(&job).await {
+                        warn!("Failed to update ServiceNow for job {}: {}", job.job_id, e);
+                    }
+                });
+            }
+        });
+    }
+    
+    /// Update the matching record and attach the job's certificates
+    pub async fn report(&self, job: &Job) -> Result<()> {
+        let Some(serial) = job_serial(job) else {
+            debug!("Job {} has no device serial; not reporting to ServiceNow", job.job_id);
+            return Ok(());
+        };
+        let Some(sys_id) = self.find_record(&serial).await? else {
+            warn!("No {} record with {} = {}", self.config.table, self.config.match_field, serial);
+            return Ok(());
+        };
+        
+        let update = self.config.record_update(job);
+        if !update.is_empty() {
+            self.send(
+                self.client
+                    .patch(format!("{}/{}", self.config.table_url(), sys_id))
+                    .json(&update),
+            )
+            .await?;
+        }
+        
+        if self.config.attach_certificates {
+            let attachments = [
+                (&job.certificate_json_path, "application/json"),
+                (&job.certificate_pdf_path, "application/pdf"),
+            ];
+            for (path, content_type) in attachments {
+                let Some(path) = path else { continue };
+                self.attach(&sys_id, path, content_type).await?;
+            }
+        }
+        
+        info!("Updated ServiceNow {} record {} for job {}", self.config.table, sys_id, job.job_id);
+        Ok(())
+    }
+    
+    /// `sys_id` of the record whose match field equals the serial
+    async fn find_record(&self, serial: &str) -> Result<Option<String>> {
+        let query = format!("{}={}", self.config.match_field, serial);
+        let response = self
+            .send(self.client.get(self.config.table_url()).query(&[
+                ("sysparm_query", query.as_str()),
+                ("sysparm_fields", "sys_id"),
+                ("sysparm_limit", "1"),
+            ]))
+            .await?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| DaemonError::Internal(format!("Invalid ServiceNow response: {}", e)))?;
+        Ok(body["result"][0]["sys_id"].as_str().map(str::to_string))
+    }
+    
+    async fn attach(&self, sys_id: &str, path: &str, content_type: &str) -> Result<()> {
+        let contents = at_rest::open(self.storage_key.as_ref(), CERTIFICATE_KIND, tokio::fs::read(path).await?)?;
+        let file_name = std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        self.send(
+            self.client
+                .post(self.config.attachment_url())
+                .query(&[
+                    ("table_name", self.config.table.as_str()),
+                    ("table_sys_id", sys_id),
+                    ("file_name", file_name.as_str()),
+                ])
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .body(contents),
+        )
+        .await?;
+        Ok(())
+    }
+    
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .send()
+            .await
+            .map_err(|e| DaemonError::Internal(format!("ServiceNow request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(DaemonError::Internal(format!(
+                "ServiceNow returned {}", response.status()
+            )));
+        }
+        Ok(response)
+    }
+}
+
+/// Serial number of the wiped device
+pub(crate) fn job_serial(job: &Job) -> Option<String> {
+    job.result
+        .as_ref()
+        .map(|result| result.device_serial.clone())
+        .filter(|serial| !serial.is_empty())
+        .or_else(|| job.asset.as_ref().map(|asset| asset.serial.clone()))
+}
+
+/// Values of a job by the names in [`JOB_VALUES`]
+pub(crate) fn job_values(job: &Job) -> BTreeMap<&'static str, String> {
+    let mut values = BTreeMap::new();
+    values.insert("job_id", job.job_id.to_string());
+    values.insert("outcome", job.state.to_string());
+    values.insert("algorithm", job.algorithm.to_string());
+    if let Some(certificate_id) = job.certificate_id {
+        values.insert("certificate_id", certificate_id.to_string());
+    }
+    if let Some(finished_at) = &job.finished_at {
+        values.insert("completed_at", safe_erase_core::time_format::format_timestamp(finished_at));
+    }
+    if let Some(serial) = job_serial(job) {
+        values.insert("serial", serial);
+    }
+    if let Some(asset) = &job.asset {
+        values.insert("asset_tag", asset.asset_tag.clone());
+    }
+    if let Some(work_order) = &job.options.work_order {
+        values.insert("work_order", work_order.clone());
+    }
+    if let Some(ticket_url) = &job.options.ticket_url {
+        values.insert("ticket_url", ticket_url.clone());
+    }
+    if let Some(verification) = &job.verification {
+        values.insert("verification", verification.overall_result.to_string());
+    }
+    if let Some(error) = &job.error_message {
+        values.insert("error_message", error.clone());
+    }
+    values
+}
+
+fn default_table() -> String {
+    "alm_hardware".to_string()
+}
+
+fn default_match_field() -> String {
+    "serial_number".to_string()
+}
+
+fn default_field_mapping() -> BTreeMap<String, String> {
+    [
+        ("u_wipe_status", "outcome"),
+        ("u_wipe_method", "algorithm"),
+        ("u_wipe_certificate", "certificate_id"),
+        ("u_wipe_date", "completed_at"),
+    ]
+    .into_iter()
+    .map(|(field, value)| (field.to_string(), value.to_string()))
+    .collect()
+}
+
+fn default_attach_certificates() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config() -> ServiceNowConfig {
+        serde_json::from_str(
+            r#"{"instance_url": "https://acme.service-now.com/", "username": "svc_wipe", "password": "pw"}"#
+        ).unwrap()
+    }
+    
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config = config();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.table_url(), "https://acme.service-now.com/api/now/table/alm_hardware");
+        assert_eq!(config.field_mapping.get("u_wipe_status").map(String::as_str), Some("outcome"));
+        assert!(config.attach_certificates);
+        
+        let mut invalid = config.clone();
+        invalid.field_mapping.insert("u_notes".to_string(), "operator_mood".to_string());
+        assert!(invalid.validate().is_err());
+        
+        let insecure = ServiceNowConfig { instance_url: "http://acme.service-now.com".to_string(), ..config };
+        assert!(insecure.validate().is_err());
+    }
+    
+    #[test]
+    fn test_record_update_skips_missing_values() {
+        let job: Job = serde_json::from_value(serde_json::json!({
+            "job_id": "6f0c5f0e-6a43-4f52-a4bb-2a4a6d0c1a11",
+            "device_path": "/dev/sdb",
+            "algorithm": "NIST80088",
+            "options": safe_erase_core::WipeOptions::default(),
+            "state": "Failed",
+            "submitted_by": "alice",
+            "submitted_at": "2024-05-01T12:00:00Z",
+            "started_at": null,
+            "finished_at": "2024-05-01T13:00:00Z",
+            "progress": null,
+            "result": null,
+            "verification": null,
+            "certificate_id": null,
+            "certificate_json_path": null,
+            "certificate_pdf_path": null,
+            "error_message": "write failed",
+            "asset": {"serial": "S1", "asset_tag": "TAG-1"}
+        }))
+        .unwrap();
+        
+        assert_eq!(job_serial(&job).as_deref(), Some("S1"));
+        let update = config().record_update(&job);
+        assert_eq!(update["u_wipe_status"], "Failed");
+        assert_eq!(update["u_wipe_date"], "2024-05-01T13:00:00Z");
+        assert!(!update.contains_key("u_wipe_certificate"));
+    }
+}
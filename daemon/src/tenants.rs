@@ -0,0 +1,81 @@
+//! Tenants of a shared wipe service
+//!
+//! An API key may belong to a tenant. Its holder then sees only that tenant's
+//! jobs, certificates, batches and audit records; anything else answers as if
+//! it did not exist. Keys without a tenant belong to the operator of the
+//! service and see everything, including the station-wide schedules and event
+//! stream that tenants have no access to. A tenant's jobs are checked against
+//! its own policy on top of the engine's, and its quotas cap how many jobs it
+//! may have unfinished and how many it may submit per day.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use safe_erase_core::WipePolicy;
+
+use crate::error::{DaemonError, Result};
+use crate::jobs::Job;
+
+/// A customer of the wipe service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Identifier referenced by `ApiKeyConfig::tenant`
+    pub id: String,
+    /// Policy the tenant's jobs must also satisfy, e.g. a higher minimum security level
+    #[serde(default)]
+    pub policy: Option<WipePolicy>,
+    /// Jobs pending approval, queued or running at the same time
+    #[serde(default)]
+    pub max_active_jobs: Option<usize>,
+    /// Jobs submitted in any 24 hours
+    #[serde(default)]
+    pub max_jobs_per_day: Option<usize>,
+}
+
+impl TenantConfig {
+    /// Check that one more job fits the tenant's quotas, given all jobs the daemon tracks
+    pub fn check_quota<'a>(&self, jobs: impl IntoIterator<Item = &'a Job>, now: DateTime<Utc>) -> Result<()> {
+        let own: Vec<&Job> = jobs
+            .into_iter()
+            .filter(|job| job.tenant.as_deref() == Some(self.id.as_str()))
+            .collect();
+        
+        if let Some(limit) = self.max_active_jobs {
+            let active = own.iter().filter(|job| !job.state.is_terminal()).count();
+            if active >= limit {
+                return Err(DaemonError::QuotaExceeded(format!(
+                    "tenant {} already has {} unfinished jobs (limit {})",
+                    self.id, active, limit
+                )));
+            }
+        }
+        if let Some(limit) = self.max_jobs_per_day {
+            let today = own.iter().filter(|job| now - job.submitted_at < Duration::days(1)).count();
+            if today >= limit {
+                return Err(DaemonError::QuotaExceeded(format!(
+                    "tenant {} submitted {} jobs in the last 24 hours (limit {})",
+                    self.id, today, limit
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that tenant ids are unique and every key names a configured tenant
+pub fn validate<'a>(tenants: &[TenantConfig], key_tenants: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    for (i, tenant) in tenants.iter().enumerate() {
+        if tenant.id.trim().is_empty() {
+            return Err(DaemonError::InvalidConfiguration("tenant ids must not be empty".to_string()));
+        }
+        if tenants[..i].iter().any(|other| other.id == tenant.id) {
+            return Err(DaemonError::InvalidConfiguration(format!("tenant {} is configured twice", tenant.id)));
+        }
+    }
+    for id in key_tenants {
+        if !tenants.iter().any(|tenant| tenant.id == id) {
+            return Err(DaemonError::InvalidConfiguration(format!("API key refers to unknown tenant {}", id)));
+        }
+    }
+    Ok(())
+}
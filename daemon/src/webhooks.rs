@@ -0,0 +1,273 @@
+//! Outbound webhook notifications for job lifecycle events
+//!
+//! Each configured endpoint receives a JSON `POST` with a snapshot of the job
+//! when a wipe completes or fails, when its verification result is known and
+//! when a certificate is issued. The body is signed with HMAC-SHA256 using the
+//! endpoint's shared secret; receivers should recompute the signature over the
+//! raw body and compare it with the `X-SafeErase-Signature` header.
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::error::{DaemonError, Result};
+use crate::events::JobEvent;
+use crate::jobs::{Job, JobManager, JobState};
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "x-safeerase-signature";
+
+/// Header carrying the event name
+pub const EVENT_HEADER: &str = "x-safeerase-event";
+
+/// Header carrying the unique delivery ID
+pub const DELIVERY_HEADER: &str = "x-safeerase-delivery";
+
+/// Delivery attempts before a notification is given up
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Configured webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Endpoint receiving the notifications
+    pub url: String,
+    /// Shared secret used to sign request bodies
+    pub secret: String,
+    /// Events delivered to this endpoint; all events when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Per-request timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Job lifecycle events that can trigger a webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    WipeCompleted,
+    WipeFailed,
+    VerificationResult,
+    CertificateIssued,
+}
+
+/// JSON body of a webhook request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub delivery_id: Uuid,
+    pub event: WebhookEvent,
+    pub timestamp: DateTime<Utc>,
+    pub job: Job,
+}
+
+/// Delivers job events to the configured endpoints
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    endpoints: Vec<WebhookConfig>,
+    client: reqwest::Client,
+}
+
+impl WebhookConfig {
+    /// Validate the endpoint settings
+    pub fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("https://") || self.url.starts_with("http://")) {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "Webhook URL must use http or https: {}", self.url
+            )));
+        }
+        if self.secret.is_empty() {
+            return Err(DaemonError::InvalidConfiguration(format!(
+                "Webhook {} needs a signing secret", self.url
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Whether this endpoint subscribes to an event
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+impl WebhookEvent {
+    /// Webhook event triggered by a job event, if any
+    pub fn from_job_event(event: &JobEvent) -> Option<Self> {
+        match event {
+            JobEvent::StateChanged { state: JobState::Completed, .. } => Some(WebhookEvent::WipeCompleted),
+            JobEvent::StateChanged { state: JobState::Failed, .. } => Some(WebhookEvent::WipeFailed),
+            JobEvent::Verification { .. } => Some(WebhookEvent::VerificationResult),
+            JobEvent::CertificateIssued { .. } => Some(WebhookEvent::CertificateIssued),
+            _ => None,
+        }
+    }
+    
+    /// Event name sent in the `X-SafeErase-Event` header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::WipeCompleted => "wipe_completed",
+            WebhookEvent::WipeFailed => "wipe_failed",
+            WebhookEvent::VerificationResult => "verification_result",
+            WebhookEvent::CertificateIssued => "certificate_issued",
+        }
+    }
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher for the configured endpoints
+    pub fn new(endpoints: Vec<WebhookConfig>) -> Self {
+        Self {
+            endpoints,
+            client: reqwest::Client::new(),
+        }
+    }
+    
+    /// Forward job events to the endpoints until the event channel closes
+    pub fn spawn(self, jobs: JobManager) {
+        if self.endpoints.is_empty() {
+            return;
+        }
+        info!("Delivering job notifications to {} webhook(s)", self.endpoints.len());
+        
+        let mut events = jobs.subscribe_events();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Webhook dispatcher lagged, skipped {} job events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(webhook_event) = WebhookEvent::from_job_event(&event) else { continue };
+                let Ok(job) = jobs.get(event.job_id()).await else { continue };
+                
+                let payload = WebhookPayload {
+                    delivery_id: Uuid::new_v4(),
+                    event: webhook_event,
+                    timestamp: Utc::now(),
+                    job,
+                };
+                self.dispatch(&payload);
+            }
+        });
+    }
+    
+    /// Send a payload to every endpoint subscribed to its event
+    fn dispatch(&self, payload: &WebhookPayload) {
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        
+        for endpoint in self.endpoints.iter().filter(|endpoint| endpoint.wants(payload.event)) {
+            let client = self.client.clone();
+            let endpoint = endpoint.clone();
+            let body = body.clone();
+            let event = payload.event;
+            let delivery_id = payload.delivery_id;
+            tokio::spawn(async move {
+                deliver(&client, &endpoint, event, delivery_id, body).await;
+            });
+        }
+    }
+}
+
+/// POST a signed body, retrying with exponential backoff
+async fn deliver(client: &reqwest::Client, endpoint: &WebhookConfig, event: WebhookEvent, delivery_id: Uuid, body: Vec<u8>) {
+    let signature = format!("sha256={}", sign(&endpoint.secret, &body));
+    
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = client
+            .post(&endpoint.url)
+            .timeout(Duration::from_secs(endpoint.timeout_secs))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .header(EVENT_HEADER, event.as_str())
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .body(body.clone())
+            .send()
+            .await;
+        
+        match response {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered {} webhook {} to {}", event.as_str(), delivery_id, endpoint.url);
+                return;
+            }
+            Ok(response) => warn!(
+                "Webhook {} returned {} for {} (attempt {}/{})",
+                endpoint.url, response.status(), event.as_str(), attempt, MAX_ATTEMPTS
+            ),
+            Err(e) => warn!(
+                "Webhook {} failed for {} (attempt {}/{}): {}",
+                endpoint.url, event.as_str(), attempt, MAX_ATTEMPTS, e
+            ),
+        }
+        
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+        }
+    }
+    
+    warn!("Giving up on {} webhook {} to {}", event.as_str(), delivery_id, endpoint.url);
+}
+
+/// Hex-encoded HMAC-SHA256 of a body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_event_mapping() {
+        let job_id = Uuid::new_v4();
+        let completed = JobEvent::StateChanged { job_id, state: JobState::Completed };
+        assert_eq!(WebhookEvent::from_job_event(&completed), Some(WebhookEvent::WipeCompleted));
+        
+        let running = JobEvent::StateChanged { job_id, state: JobState::Running };
+        assert_eq!(WebhookEvent::from_job_event(&running), None);
+        
+        let issued = JobEvent::CertificateIssued { job_id, certificate_id: Uuid::new_v4() };
+        assert_eq!(WebhookEvent::from_job_event(&issued), Some(WebhookEvent::CertificateIssued));
+    }
+    
+    #[test]
+    fn test_endpoint_filter_and_validation() {
+        let endpoint: WebhookConfig = serde_json::from_str(
+            r#"{"url": "https://assets.example.com/hooks/safeerase", "secret": "s3cret", "events": ["wipe_failed"]}"#
+        ).unwrap();
+        assert!(endpoint.validate().is_ok());
+        assert!(endpoint.wants(WebhookEvent::WipeFailed));
+        assert!(!endpoint.wants(WebhookEvent::WipeCompleted));
+        assert_eq!(endpoint.timeout_secs, 10);
+        
+        let unsigned = WebhookConfig { secret: String::new(), ..endpoint };
+        assert!(unsigned.validate().is_err());
+    }
+    
+    #[test]
+    fn test_signature_is_stable() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Node.js bindings for SafeErase
+//!
+//! Exposes the engine to Node and Electron through Node-API so operator GUIs
+//! can drive discovery, wipes and progress directly instead of wrapping a CLI:
+//!
+//! ```js
+//! const { SafeErase } = require('@safeerase/engine');
+//!
+//! const engine = new SafeErase();
+//! const subscription = engine.onProgress((err, progress) => render(progress));
+//! const devices = await engine.discoverDevices();
+//! const result = await engine.startWipe('/dev/sdb', 'NIST80088', { verifyWipe: true },
+//!     { identity: 'alice@example.com', authMethod: 'single_sign_on', sessionId });
+//! subscription.unsubscribe();
+//! ```
+//!
+//! Devices, progress and results are passed as plain objects with the same
+//! snake_case fields as the engine's JSON output. Engine errors reject the
+//! promise with the engine's user-facing message, translated once
+//! `setLocale('de')` has been called.
+
+mod options;
+
+use std::sync::{Arc, Mutex, RwLock};
+use napi::bindgen_prelude::spawn;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::JsFunction;
+use napi_derive::napi;
+use tokio::sync::broadcast;
+
+use safe_erase_core::i18n::DEFAULT_LOCALE;
+use safe_erase_core::{
+    Cancellation, ConfigLoader, LocalizedError, MessageCatalog, OperatorContext, SafeEraseConfig, SafeEraseEngine,
+    SafeEraseError,
+};
+
+pub use options::{JsOperator, JsWipeOptions};
+
+/// Handle to the SafeErase engine
+#[napi]
+pub struct SafeErase {
+    engine: Arc<SafeEraseEngine>,
+    config: SafeEraseConfig,
+    messages: MessageCatalog,
+    locale: RwLock<String>,
+}
+
+/// Active `onProgress` subscription
+#[napi]
+pub struct ProgressSubscription {
+    task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[napi]
+impl SafeErase {
+    /// Create an engine using the system configuration file and environment
+    #[napi(constructor)]
+    pub fn new() -> napi::Result<Self> {
+        Self::from_loader(ConfigLoader::new())
+    }
+    
+    /// Create an engine with an additional configuration file layered on top
+    #[napi(factory)]
+    pub fn with_config_file(path: String) -> napi::Result<Self> {
+        Self::from_loader(ConfigLoader::new().file(path))
+    }
+    
+    fn from_loader(loader: ConfigLoader) -> napi::Result<Self> {
+        let messages = MessageCatalog::builtin().map_err(canonical_error)?;
+        let config = loader.load().map_err(canonical_error)?;
+        let engine = SafeEraseEngine::with_config(&config).map_err(canonical_error)?;
+        Ok(Self {
+            engine: Arc::new(engine),
+            config,
+            messages,
+            locale: RwLock::new(DEFAULT_LOCALE.to_string()),
+        })
+    }
+    
+    /// Language for error messages, e.g. `"de"` or `"fr-CA"`
+    #[napi]
+    pub fn set_locale(&self, locale: String) {
+        if let Ok(mut current) = self.locale.write() {
+            *current = locale;
+        }
+    }
+    
+    /// Discover attached storage devices
+    #[napi]
+    pub async fn discover_devices(&self) -> napi::Result<serde_json::Value> {
+        let devices = self.engine.discover_devices().await.map_err(|e| self.to_napi_error(e))?;
+        to_value(&devices)
+    }
+    
+    /// Current state of the devices found by the last discovery
+    #[napi]
+    pub async fn device_status(&self) -> napi::Result<serde_json::Value> {
+        let devices = self.engine.get_device_status().await.map_err(|e| self.to_napi_error(e))?;
+        to_value(&devices)
+    }
+    
+    /// Blockers and warnings for a device, e.g. to grey it out with a tooltip
+    #[napi]
+    pub async fn device_readiness(&self, device_path: String) -> napi::Result<serde_json::Value> {
+        let readiness = self
+            .engine
+            .device_readiness(&device_path)
+            .await
+            .map_err(|e| self.to_napi_error(e))?;
+        to_value(&readiness)
+    }
+    
+    /// Wipe a device on behalf of the signed-in operator; resolves with the
+    /// wipe result once the operation ends
+    #[napi]
+    pub async fn start_wipe(
+        &self,
+        device_path: String,
+        algorithm: Option<String>,
+        options: Option<JsWipeOptions>,
+        operator: Option<JsOperator>,
+    ) -> napi::Result<serde_json::Value> {
+        let algorithm = options::parse_algorithm(algorithm.as_deref(), &self.config.wipe)?;
+        let options = options.unwrap_or_default().resolve(&self.config.wipe);
+        let operator = match operator {
+            Some(operator) => operator.resolve()?,
+            None => OperatorContext::local(),
+        };
+        let result = self
+            .engine
+            .start_wipe(&device_path, algorithm, options, operator)
+            .await
+            .map_err(|e| self.to_napi_error(e))?;
+        to_value(&result)
+    }
+    
+    /// Cancel the wipe running on a device; resolves with the operation ID.
+    /// `reason` is e.g. `"emergency_stop"`; see `CancelReason` for the names
+    #[napi]
+    pub async fn cancel_wipe(
+        &self,
+        device_path: String,
+        reason: Option<String>,
+        detail: Option<String>,
+    ) -> napi::Result<String> {
+        let reason = match reason {
+            Some(reason) => reason.parse().map_err(|e| self.to_napi_error(e))?,
+            None => Default::default(),
+        };
+        let mut cancellation = Cancellation::new(reason);
+        cancellation.detail = detail;
+        let operation_id = self
+            .engine
+            .cancel_wipe(&device_path, cancellation)
+            .await
+            .map_err(|e| self.to_napi_error(e))?;
+        Ok(operation_id.to_string())
+    }
+    
+    /// Call `callback(err, progress)` for every progress update of every wipe
+    #[napi(ts_args_type = "callback: (err: Error | null, progress: any) => void")]
+    pub fn on_progress(&self, callback: JsFunction) -> napi::Result<ProgressSubscription> {
+        let callback: ThreadsafeFunction<serde_json::Value, ErrorStrategy::CalleeHandled> =
+            callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+        let mut progress_rx = self.engine.subscribe_progress();
+        
+        let task = spawn(async move {
+            loop {
+                match progress_rx.recv().await {
+                    Ok(progress) => {
+                        let value = to_value(&progress);
+                        callback.call(value, ThreadsafeFunctionCallMode::NonBlocking);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        
+        Ok(ProgressSubscription {
+            task: Mutex::new(Some(task)),
+        })
+    }
+}
+
+impl SafeErase {
+    fn to_napi_error(&self, error: SafeEraseError) -> napi::Error {
+        let locale = self.locale.read().map(|l| l.clone()).unwrap_or_default();
+        napi::Error::from_reason(error.localized_message(&self.messages, &locale))
+    }
+}
+
+#[napi]
+impl ProgressSubscription {
+    /// Stop delivering progress updates
+    #[napi]
+    pub fn unsubscribe(&self) {
+        if let Some(task) = self.task.lock().ok().and_then(|mut task| task.take()) {
+            task.abort();
+        }
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> napi::Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+fn canonical_error(error: SafeEraseError) -> napi::Error {
+    napi::Error::from_reason(error.user_message())
+}
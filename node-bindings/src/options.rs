@@ -0,0 +1,119 @@
+//! Conversion of JavaScript arguments into engine types
+
+use napi_derive::napi;
+
+use safe_erase_core::config::WipeDefaults;
+use safe_erase_core::{AuthMethod, OperatorContext, WipeAlgorithm, WipeOptions};
+
+/// Wipe options accepted from JavaScript; unset fields use the configured defaults
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct JsWipeOptions {
+    pub verify_wipe: Option<bool>,
+    pub verification_samples: Option<u32>,
+    pub clear_hpa_dco: Option<bool>,
+    pub block_size: Option<u32>,
+    pub prefer_hardware_erase: Option<bool>,
+    pub compute_device_digest: Option<bool>,
+    pub discard_after_wipe: Option<bool>,
+    pub scan_sensitive_data: Option<bool>,
+    pub work_order: Option<String>,
+    pub ticket_url: Option<String>,
+}
+
+impl JsWipeOptions {
+    /// Apply these overrides on top of the configured defaults
+    pub fn resolve(&self, defaults: &WipeDefaults) -> WipeOptions {
+        let mut options = defaults.wipe_options();
+        if let Some(verify_wipe) = self.verify_wipe {
+            options.verify_wipe = verify_wipe;
+        }
+        if let Some(samples) = self.verification_samples {
+            options.verification_samples = samples as usize;
+        }
+        if let Some(clear_hpa_dco) = self.clear_hpa_dco {
+            options.clear_hpa_dco = clear_hpa_dco;
+        }
+        if let Some(block_size) = self.block_size {
+            options.block_size = block_size as usize;
+        }
+        if let Some(prefer_hardware_erase) = self.prefer_hardware_erase {
+            options.prefer_hardware_erase = prefer_hardware_erase;
+        }
+        if let Some(compute_device_digest) = self.compute_device_digest {
+            options.compute_device_digest = compute_device_digest;
+        }
+        if let Some(discard_after_wipe) = self.discard_after_wipe {
+            options.discard_after_wipe = discard_after_wipe;
+        }
+        if let Some(scan_sensitive_data) = self.scan_sensitive_data {
+            options.scan_sensitive_data = scan_sensitive_data;
+        }
+        if let Some(work_order) = &self.work_order {
+            options.work_order = Some(work_order.clone());
+        }
+        if let Some(ticket_url) = &self.ticket_url {
+            options.ticket_url = Some(ticket_url.clone());
+        }
+        options
+    }
+}
+
+/// Operator the GUI authenticated; without one the OS user is recorded
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct JsOperator {
+    pub identity: String,
+    /// e.g. `"single_sign_on"`; see `AuthMethod` for the names
+    pub auth_method: String,
+    pub session_id: Option<String>,
+}
+
+impl JsOperator {
+    /// The operator context recorded by the engine
+    pub fn resolve(&self) -> napi::Result<OperatorContext> {
+        let auth_method: AuthMethod = self
+            .auth_method
+            .parse()
+            .map_err(|e: safe_erase_core::SafeEraseError| napi::Error::from_reason(e.to_string()))?;
+        let operator = OperatorContext::new(self.identity.clone(), auth_method);
+        Ok(match &self.session_id {
+            Some(session_id) => operator.with_session_id(session_id.clone()),
+            None => operator,
+        })
+    }
+}
+
+/// Parse an algorithm name such as `"DoD522022M"`, falling back to the configured default
+pub fn parse_algorithm(name: Option<&str>, defaults: &WipeDefaults) -> napi::Result<WipeAlgorithm> {
+    match name {
+        None => Ok(defaults.algorithm.clone()),
+        Some(name) => serde_json::from_value(serde_json::Value::String(name.to_string()))
+            .map_err(|_| napi::Error::from_reason(format!("Unknown wipe algorithm: {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_unset_options_use_defaults() {
+        let defaults = WipeDefaults::default();
+        let options = JsWipeOptions {
+            verify_wipe: Some(false),
+            ..JsWipeOptions::default()
+        }
+        .resolve(&defaults);
+        assert!(!options.verify_wipe);
+        assert_eq!(options.block_size, defaults.block_size);
+    }
+    
+    #[test]
+    fn test_parse_algorithm() {
+        let defaults = WipeDefaults::default();
+        assert_eq!(parse_algorithm(Some("Gutmann"), &defaults).unwrap(), WipeAlgorithm::Gutmann);
+        assert_eq!(parse_algorithm(None, &defaults).unwrap(), defaults.algorithm);
+        assert!(parse_algorithm(Some("Shred"), &defaults).is_err());
+    }
+}
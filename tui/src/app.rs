@@ -0,0 +1,535 @@
+//! Application state and key handling for the terminal UI
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use uuid::Uuid;
+
+use safe_erase_certificates::CertificateResult;
+use safe_erase_core::mounts::MountTable;
+use safe_erase_core::{DeviceInfo, DeviceType, LiveEnvironment, WipeAlgorithm, WipeOptions, WipeProgress, WipeResult, WipeStatus};
+
+/// Algorithms offered in the configuration pane, in cycling order
+pub const ALGORITHMS: [WipeAlgorithm; 9] = [
+    WipeAlgorithm::NIST80088,
+    WipeAlgorithm::DoD522022M,
+    WipeAlgorithm::Gutmann,
+    WipeAlgorithm::GutmannLite,
+    WipeAlgorithm::Random,
+    WipeAlgorithm::ZeroFill,
+    WipeAlgorithm::OneFill,
+    WipeAlgorithm::ATASecureErase,
+    WipeAlgorithm::NVMeFormat,
+];
+
+/// Events delivered to the UI loop
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum AppEvent {
+    /// Key press from the terminal
+    Key(KeyEvent),
+    /// Progress snapshot from the wipe engine
+    Progress(WipeProgress),
+    /// Device discovery finished
+    DevicesDiscovered(Result<Vec<DeviceInfo>, String>),
+    /// A wipe finished, successfully or not
+    WipeFinished {
+        device_path: String,
+        result: Result<WipeResult, String>,
+    },
+    /// Certificate generation finished for a completed wipe
+    CertificateIssued {
+        device_path: String,
+        result: Result<CertificateResult, String>,
+    },
+    /// Releasing the boot medium finished; devices were rediscovered on success
+    BootMediumReleased {
+        device_path: String,
+        result: Result<Vec<DeviceInfo>, String>,
+    },
+    /// A result file could not be written to the spool
+    SpoolFailed(String),
+    /// Periodic redraw
+    Tick,
+}
+
+/// Work the UI loop must perform in response to a key press
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum Action {
+    None,
+    RefreshDevices,
+    StartWipe {
+        device_path: String,
+        algorithm: WipeAlgorithm,
+        options: WipeOptions,
+    },
+    CancelWipe(String),
+    /// Unmount and detach the boot medium of a system running from RAM
+    ReleaseBootMedium(String),
+    Quit,
+}
+
+/// Interaction mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Navigating the device table
+    Browse,
+    /// Waiting for the operator to confirm a destructive wipe
+    ConfirmWipe,
+}
+
+/// Per-device wipe configuration edited in the configuration pane
+#[derive(Debug, Clone, PartialEq)]
+pub struct WipeConfig {
+    pub algorithm: WipeAlgorithm,
+    pub verify_wipe: bool,
+    pub clear_hpa_dco: bool,
+    pub prefer_hardware_erase: bool,
+}
+
+/// State of a wipe started from the UI
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+/// A wipe shown in the progress pane
+#[derive(Debug, Clone)]
+pub struct JobView {
+    pub device_path: String,
+    pub algorithm: WipeAlgorithm,
+    pub status: JobStatus,
+    pub progress: Option<WipeProgress>,
+}
+
+/// A certificate shown in the certificate pane
+#[derive(Debug, Clone)]
+pub struct CertificateSummary {
+    pub device_path: String,
+    pub certificate_id: Uuid,
+    pub pdf_path: Option<String>,
+    pub json_path: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Terminal UI state
+#[derive(Debug)]
+pub struct App {
+    pub devices: Vec<DeviceInfo>,
+    pub mounts: MountTable,
+    /// Whether the system runs from RAM and what holds each disk
+    pub live: LiveEnvironment,
+    pub selected: usize,
+    pub configs: HashMap<String, WipeConfig>,
+    pub jobs: Vec<JobView>,
+    pub certificates: Vec<CertificateSummary>,
+    pub mode: Mode,
+    pub status_message: Option<String>,
+    pub discovering: bool,
+}
+
+impl WipeConfig {
+    /// Default configuration for a device, using the first recommended algorithm
+    pub fn for_device(device: &DeviceInfo) -> Self {
+        let recommended = match device.device_type {
+            DeviceType::NVMe => WipeAlgorithm::recommended_for_nvme(),
+            DeviceType::SSD => WipeAlgorithm::recommended_for_ssd(),
+            _ => WipeAlgorithm::recommended_for_hdd(),
+        };
+        let algorithm = recommended
+            .into_iter()
+            .find(|a| !a.is_hardware_based() || device.supports_secure_erase)
+            .unwrap_or(WipeAlgorithm::NIST80088);
+        
+        Self {
+            algorithm,
+            verify_wipe: true,
+            clear_hpa_dco: device.supports_hpa_dco,
+            prefer_hardware_erase: device.supports_secure_erase,
+        }
+    }
+    
+    /// Engine options for this configuration
+    pub fn options(&self) -> WipeOptions {
+        WipeOptions {
+            verify_wipe: self.verify_wipe,
+            clear_hpa_dco: self.clear_hpa_dco,
+            prefer_hardware_erase: self.prefer_hardware_erase,
+            ..WipeOptions::default()
+        }
+    }
+    
+    fn next_algorithm(&mut self) {
+        let index = ALGORITHMS.iter().position(|a| *a == self.algorithm).unwrap_or(0);
+        self.algorithm = ALGORITHMS[(index + 1) % ALGORITHMS.len()].clone();
+    }
+}
+
+impl JobView {
+    /// Whether the wipe is still in progress
+    pub fn is_active(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+    
+    /// Completion ratio for the progress gauge
+    pub fn ratio(&self) -> f64 {
+        match (&self.status, &self.progress) {
+            (JobStatus::Completed, _) => 1.0,
+            (_, Some(progress)) => (progress.percentage / 100.0).clamp(0.0, 1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+impl App {
+    /// Create an empty application state
+    pub fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            mounts: MountTable::default(),
+            live: LiveEnvironment::default(),
+            selected: 0,
+            configs: HashMap::new(),
+            jobs: Vec::new(),
+            certificates: Vec::new(),
+            mode: Mode::Browse,
+            status_message: None,
+            discovering: true,
+        }
+    }
+    
+    /// Currently highlighted device
+    pub fn selected_device(&self) -> Option<&DeviceInfo> {
+        self.devices.get(self.selected)
+    }
+    
+    /// Configuration of the highlighted device
+    pub fn selected_config(&self) -> Option<&WipeConfig> {
+        self.selected_device().and_then(|d| self.configs.get(&d.path))
+    }
+    
+    /// Active or most recent job for a device
+    pub fn job_for(&self, device_path: &str) -> Option<&JobView> {
+        self.jobs.iter().rev().find(|j| j.device_path == device_path)
+    }
+    
+    /// Whether any wipe is still running
+    pub fn has_active_jobs(&self) -> bool {
+        self.jobs.iter().any(JobView::is_active)
+    }
+    
+    /// Apply an event from the engine or the terminal
+    pub fn handle_event(&mut self, event: AppEvent) -> Action {
+        match event {
+            AppEvent::Key(key) => return self.handle_key(key),
+            AppEvent::Progress(progress) => {
+                if let Some(job) = self
+                    .jobs
+                    .iter_mut()
+                    .rev()
+                    .find(|j| j.is_active() && j.device_path == progress.device_path)
+                {
+                    job.progress = Some(progress);
+                }
+            }
+            AppEvent::DevicesDiscovered(result) => {
+                self.discovering = false;
+                match result {
+                    Ok(devices) => self.set_devices(devices),
+                    Err(e) => self.status_message = Some(format!("Device discovery failed: {}", e)),
+                }
+            }
+            AppEvent::WipeFinished { device_path, result } => {
+                if let Some(job) = self.jobs.iter_mut().rev().find(|j| j.device_path == device_path) {
+                    job.status = match result {
+                        Ok(result) => {
+                            let status = match result.status {
+                                WipeStatus::Completed => JobStatus::Completed,
+                                WipeStatus::Cancelled => JobStatus::Cancelled,
+                                _ => JobStatus::Failed(
+                                    result.error_message.clone().unwrap_or_else(|| result.status.to_string()),
+                                ),
+                            };
+                            if let Some(progress) = job.progress.as_mut() {
+                                progress.status = result.status;
+                            }
+                            status
+                        }
+                        Err(e) => JobStatus::Failed(e),
+                    };
+                }
+            }
+            AppEvent::CertificateIssued { device_path, result } => match result {
+                Ok(certificate) => {
+                    self.status_message = Some(format!("Certificate issued for {}", device_path));
+                    self.certificates.push(CertificateSummary {
+                        device_path,
+                        certificate_id: certificate.certificate_id,
+                        pdf_path: certificate.pdf_path,
+                        json_path: certificate.json_path,
+                        generated_at: certificate.generated_at,
+                    });
+                }
+                Err(e) => {
+                    self.status_message = Some(format!("Certificate generation failed for {}: {}", device_path, e));
+                }
+            },
+            AppEvent::BootMediumReleased { device_path, result } => match result {
+                Ok(devices) => {
+                    self.set_devices(devices);
+                    self.status_message = Some(format!("Released {}; it can now be wiped", device_path));
+                }
+                Err(e) => self.status_message = Some(format!("Could not release {}: {}", device_path, e)),
+            },
+            AppEvent::SpoolFailed(e) => {
+                self.status_message = Some(format!("Spooling failed: {}", e));
+            }
+            AppEvent::Tick => {}
+        }
+        Action::None
+    }
+    
+    /// Replace the device list, keeping existing per-device configuration
+    pub fn set_devices(&mut self, devices: Vec<DeviceInfo>) {
+        for device in &devices {
+            self.configs
+                .entry(device.path.clone())
+                .or_insert_with(|| WipeConfig::for_device(device));
+        }
+        self.devices = devices;
+        self.mounts = MountTable::load();
+        self.live = LiveEnvironment::detect();
+        self.selected = self.selected.min(self.devices.len().saturating_sub(1));
+    }
+    
+    /// Record a wipe started by the UI loop
+    pub fn record_started(&mut self, device_path: &str, algorithm: WipeAlgorithm) {
+        self.jobs.push(JobView {
+            device_path: device_path.to_string(),
+            algorithm,
+            status: JobStatus::Running,
+            progress: None,
+        });
+    }
+    
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return self.request_quit();
+        }
+        
+        match self.mode {
+            Mode::ConfirmWipe => self.handle_confirm_key(key),
+            Mode::Browse => self.handle_browse_key(key),
+        }
+    }
+    
+    fn handle_browse_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return self.request_quit(),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.devices.len() => self.selected += 1,
+            KeyCode::Char('r') => {
+                self.discovering = true;
+                return Action::RefreshDevices;
+            }
+            KeyCode::Char('a') => self.edit_selected(WipeConfig::next_algorithm),
+            KeyCode::Char('v') => self.edit_selected(|c| c.verify_wipe = !c.verify_wipe),
+            KeyCode::Char('h') => self.edit_selected(|c| c.clear_hpa_dco = !c.clear_hpa_dco),
+            KeyCode::Char('e') => self.edit_selected(|c| c.prefer_hardware_erase = !c.prefer_hardware_erase),
+            KeyCode::Char('w') | KeyCode::Enter => {
+                if let Err(reason) = self.check_wipe_allowed() {
+                    self.status_message = Some(reason);
+                } else {
+                    self.mode = Mode::ConfirmWipe;
+                }
+            }
+            KeyCode::Char('b') => {
+                if let Some(device) = self.selected_device() {
+                    let path = device.path.clone();
+                    if self.live.can_release(&path) {
+                        self.status_message = Some(format!("Releasing boot medium {}", path));
+                        return Action::ReleaseBootMedium(path);
+                    }
+                    self.status_message = Some(format!("{} cannot be released; the system is not running from RAM", path));
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(device) = self.selected_device() {
+                    let path = device.path.clone();
+                    if self.job_for(&path).is_some_and(JobView::is_active) {
+                        self.status_message = Some(format!("Cancelling wipe on {}", path));
+                        return Action::CancelWipe(path);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+    
+    fn handle_confirm_key(&mut self, key: KeyEvent) -> Action {
+        self.mode = Mode::Browse;
+        if key.code != KeyCode::Char('y') {
+            self.status_message = Some("Wipe not started".to_string());
+            return Action::None;
+        }
+        
+        let (Some(device), Some(config)) = (self.selected_device(), self.selected_config()) else {
+            return Action::None;
+        };
+        let action = Action::StartWipe {
+            device_path: device.path.clone(),
+            algorithm: config.algorithm.clone(),
+            options: config.options(),
+        };
+        self.status_message = Some(format!("Wiping {} with {}", device.path, config.algorithm));
+        action
+    }
+    
+    fn request_quit(&mut self) -> Action {
+        if self.has_active_jobs() {
+            self.status_message = Some("Wipes are still running; cancel them with 'c' before quitting".to_string());
+            Action::None
+        } else {
+            Action::Quit
+        }
+    }
+    
+    fn edit_selected(&mut self, edit: impl FnOnce(&mut WipeConfig)) {
+        let Some(path) = self.selected_device().map(|d| d.path.clone()) else { return };
+        if self.job_for(&path).is_some_and(JobView::is_active) {
+            self.status_message = Some("Configuration is locked while the device is being wiped".to_string());
+            return;
+        }
+        if let Some(config) = self.configs.get_mut(&path) {
+            edit(config);
+        }
+    }
+    
+    fn check_wipe_allowed(&self) -> Result<(), String> {
+        let device = self.selected_device().ok_or_else(|| "No device selected".to_string())?;
+        
+        let release_hint = if self.live.can_release(&device.path) {
+            "; the system runs from RAM, press b to release it"
+        } else {
+            ""
+        };
+        if device.is_system_disk {
+            return Err(format!("{} is the system disk and cannot be wiped{}", device.path, release_hint));
+        }
+        if self.mounts.is_mounted(&device.path) {
+            return Err(format!(
+                "{} has mounted filesystems ({}); unmount them first{}",
+                device.path,
+                self.mounts.mount_points(&device.path).join(", "),
+                release_hint
+            ));
+        }
+        if self.job_for(&device.path).is_some_and(JobView::is_active) {
+            return Err(format!("{} is already being wiped", device.path));
+        }
+        Ok(())
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safe_erase_core::StorageInterface;
+    use safe_erase_core::device::HealthStatus;
+    
+    fn test_device(path: &str, is_system_disk: bool) -> DeviceInfo {
+        DeviceInfo {
+            path: path.to_string(),
+            name: "disk".to_string(),
+            model: "Test Disk".to_string(),
+            serial: "SN123".to_string(),
+            size: 1024 * 1024 * 1024,
+            device_type: DeviceType::HDD,
+            interface: StorageInterface::SATA,
+            is_removable: false,
+            is_system_disk,
+            supports_secure_erase: false,
+            supports_hpa_dco: false,
+            firmware_version: None,
+            temperature: None,
+            health_status: HealthStatus::Good,
+            hidden_area: None,
+            virtualization: None,
+            bay: None,
+        }
+    }
+    
+    fn key(code: KeyCode) -> AppEvent {
+        AppEvent::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+    
+    #[test]
+    fn test_system_disk_cannot_be_wiped() {
+        let mut app = App::new();
+        app.set_devices(vec![test_device("/dev/sda", true)]);
+        assert!(matches!(app.handle_event(key(KeyCode::Char('w'))), Action::None));
+        assert_eq!(app.mode, Mode::Browse);
+    }
+    
+    #[test]
+    fn test_release_offered_when_running_from_ram() {
+        let mut app = App::new();
+        app.set_devices(vec![test_device("/dev/nvme0n1", false)]);
+        app.mounts = MountTable::parse("/dev/nvme0n1p1 /run/live/medium iso9660 ro 0 0\n");
+        app.live = LiveEnvironment::from_parts(
+            "/dev/nvme0n1p1 /run/live/medium iso9660 ro 0 0\ntmpfs / tmpfs rw 0 0\n",
+            "",
+            &[],
+        );
+        
+        app.handle_event(key(KeyCode::Char('w')));
+        assert!(app.status_message.as_deref().unwrap().contains("press b"));
+        assert!(matches!(app.handle_event(key(KeyCode::Char('b'))), Action::ReleaseBootMedium(path) if path == "/dev/nvme0n1"));
+        
+        app.live = LiveEnvironment::default();
+        assert!(matches!(app.handle_event(key(KeyCode::Char('b'))), Action::None));
+    }
+    
+    #[test]
+    fn test_wipe_requires_confirmation() {
+        let mut app = App::new();
+        app.set_devices(vec![test_device("/dev/sdb", false)]);
+        app.mounts = MountTable::default();
+        
+        assert!(matches!(app.handle_event(key(KeyCode::Char('w'))), Action::None));
+        assert_eq!(app.mode, Mode::ConfirmWipe);
+        match app.handle_event(key(KeyCode::Char('y'))) {
+            Action::StartWipe { device_path, algorithm, .. } => {
+                assert_eq!(device_path, "/dev/sdb");
+                assert_eq!(algorithm, WipeAlgorithm::DoD522022M);
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_algorithm_cycles() {
+        let mut app = App::new();
+        app.set_devices(vec![test_device("/dev/sdb", false)]);
+        app.handle_event(key(KeyCode::Char('a')));
+        assert_eq!(app.selected_config().unwrap().algorithm, WipeAlgorithm::Gutmann);
+    }
+    
+    #[test]
+    fn test_quit_blocked_while_wiping() {
+        let mut app = App::new();
+        app.record_started("/dev/sdb", WipeAlgorithm::ZeroFill);
+        assert!(matches!(app.handle_event(key(KeyCode::Char('q'))), Action::None));
+    }
+}
@@ -0,0 +1,346 @@
+//! SafeErase terminal UI
+//!
+//! Full-screen front end for the SafeErase engine that runs without a desktop
+//! session, e.g. from the bootable USB image. Usage:
+//!
+//! ```text
+//! safeerase-tui [CERTIFICATE_DIR]
+//! ```
+//!
+//! Defaults come from the SafeErase configuration file and environment. When
+//! `spool.dir` is set, certificates and wipe records are kept in the spool and
+//! delivered to the configured endpoint or USB key on exit; the exit status is
+//! 3 when any of them could not be delivered.
+
+mod app;
+mod spool;
+mod ui;
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use tokio::sync::{broadcast, mpsc};
+
+use safe_erase_certificates::{CertificateEngine, CertificateFormat, CertificateOptions};
+use safe_erase_core::{
+    CancelReason, Cancellation, ConfigLoader, OperatorContext, SafeEraseEngine, WipeAlgorithm, WipeOptions, WipeStatus,
+};
+
+use app::{Action, App, AppEvent};
+use spool::ResultsSpooler;
+
+/// Exit status when spooled results could not all be delivered
+const EXIT_UNDELIVERED: i32 = 3;
+
+/// Redraw interval when no other events arrive
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where and how certificates are issued after a successful wipe
+#[derive(Debug, Clone)]
+struct CertificateSettings {
+    dir: PathBuf,
+    format: CertificateFormat,
+    options: CertificateOptions,
+}
+
+/// What a wipe started from the UI needs to run and issue its certificate
+#[derive(Clone)]
+struct WipeIssuer {
+    engine: Arc<SafeEraseEngine>,
+    certificates: Arc<CertificateEngine>,
+    settings: CertificateSettings,
+    spooler: Option<Arc<ResultsSpooler>>,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut loader = ConfigLoader::new();
+    if let Some(dir) = std::env::args().nth(1) {
+        loader = loader.set("certificates.output_dir", dir);
+    }
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid SafeErase configuration: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let spooler = match ResultsSpooler::from_config(&config.spool) {
+        Ok(spooler) => spooler.map(Arc::new),
+        Err(e) => {
+            eprintln!("Cannot open results spool: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let certificate_dir = match &spooler {
+        Some(spooler) => spooler.certificate_dir(),
+        None => config.certificates.output_dir.clone(),
+    };
+    
+    let engine = match SafeEraseEngine::with_config(&config) {
+        Ok(engine) => Arc::new(engine),
+        Err(e) => {
+            eprintln!("Failed to initialize SafeErase engine: {}", e.user_message());
+            std::process::exit(1);
+        }
+    };
+    let certificates = match CertificateEngine::from_config(&config.certificates) {
+        Ok(certificates) => Arc::new(certificates),
+        Err(e) => {
+            eprintln!("Failed to initialize certificate engine: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&certificate_dir) {
+        eprintln!("Cannot create certificate directory {}: {}", certificate_dir.display(), e);
+        std::process::exit(1);
+    }
+    
+    let settings = CertificateSettings {
+        dir: certificate_dir,
+        format: config.certificates.format.parse().unwrap_or(CertificateFormat::Both),
+        options: CertificateOptions {
+            redactor: config.redaction.certificate_redactor(),
+            ..CertificateOptions::from_config(&config.certificates)
+        },
+    };
+    
+    let issuer = WipeIssuer { engine, certificates, settings, spooler: spooler.clone() };
+    let result = run(issuer).await;
+    if let Some(spooler) = spooler {
+        deliver_spool(&spooler).await;
+    }
+    if let Err(e) = result {
+        eprintln!("SafeErase TUI terminated: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Deliver spooled results once the terminal has been restored
+async fn deliver_spool(spooler: &ResultsSpooler) {
+    let pending = spooler.pending().await;
+    if pending == 0 {
+        return;
+    }
+    
+    eprintln!("Delivering {} spooled result file(s)...", pending);
+    let report = spooler.flush().await;
+    for (name, target, reason) in &report.failed {
+        eprintln!("  {} -> {}: {}", name, target, reason);
+    }
+    if !report.failed.is_empty() {
+        eprintln!("{} delivery(ies) failed; results remain in the spool. Do not power off.", report.failed.len());
+        std::process::exit(EXIT_UNDELIVERED);
+    }
+    eprintln!("All spooled results delivered.");
+}
+
+async fn run(issuer: WipeIssuer) -> io::Result<()> {
+    let engine = issuer.engine.clone();
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    spawn_input_reader(tx.clone());
+    spawn_progress_forwarder(&engine, tx.clone());
+    spawn_discovery(engine.clone(), tx.clone());
+    
+    let mut app = App::new();
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| ui::draw(frame, &app)) {
+            break Err(e);
+        }
+        
+        let Some(event) = rx.recv().await else { break Ok(()) };
+        match app.handle_event(event) {
+            Action::None => {}
+            Action::Quit => break Ok(()),
+            Action::RefreshDevices => spawn_discovery(engine.clone(), tx.clone()),
+            Action::StartWipe { device_path, algorithm, options } => {
+                app.record_started(&device_path, algorithm.clone());
+                tokio::spawn(perform_wipe(issuer.clone(), device_path, algorithm, options, tx.clone()));
+            }
+            Action::ReleaseBootMedium(device_path) => {
+                let engine = engine.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let result = engine.release_boot_medium(&device_path).await.map_err(|e| e.user_message());
+                    let _ = tx.send(AppEvent::BootMediumReleased { device_path, result });
+                });
+            }
+            Action::CancelWipe(device_path) => {
+                let cancellation = Cancellation::new(CancelReason::UserRequest).with_requested_by(OperatorContext::local().identity);
+                if let Err(e) = engine.cancel_wipe(&device_path, cancellation).await {
+                    app.status_message = Some(format!("Cancel failed: {}", e.user_message()));
+                }
+            }
+        }
+    };
+    
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+/// Wipe a device, then issue its certificate if the wipe completed, reporting both to the UI
+async fn perform_wipe(
+    issuer: WipeIssuer,
+    device_path: String,
+    algorithm: WipeAlgorithm,
+    options: WipeOptions,
+    tx: mpsc::UnboundedSender<AppEvent>,
+) {
+    let WipeIssuer { engine, certificates, settings, spooler } = issuer;
+    let mut verification_rx = engine.subscribe_verification();
+    let result = engine.start_wipe(&device_path, algorithm, options, OperatorContext::local()).await;
+    
+    if let (Some(spooler), Ok(wipe_result)) = (&spooler, &result) {
+        spool_wipe_record(spooler, wipe_result, &tx).await;
+    }
+    
+    let completed = result.as_ref().ok().filter(|r| r.status == WipeStatus::Completed).cloned();
+    let _ = tx.send(AppEvent::WipeFinished {
+        device_path: device_path.clone(),
+        result: result.map_err(|e| e.user_message()),
+    });
+    
+    if let Some(wipe_result) = completed {
+        let mut verification = None;
+        while let Ok(candidate) = verification_rx.try_recv() {
+            if candidate.device_path == device_path {
+                verification = Some(candidate);
+            }
+        }
+        let certificate = certificates
+            .generate_certificate(
+                &wipe_result,
+                verification.as_ref(),
+                settings.format,
+                settings.options,
+                &settings.dir,
+            )
+            .await
+            .map_err(|e| e.to_string());
+        if let (Some(spooler), Ok(certificate)) = (&spooler, &certificate) {
+            let paths = certificate.pdf_path.iter().chain(certificate.json_path.iter());
+            for path in paths {
+                if let Err(e) = spooler.track(std::path::Path::new(path)).await {
+                    let _ = tx.send(AppEvent::SpoolFailed(format!("{}: {}", path, e)));
+                }
+            }
+        }
+        let _ = tx.send(AppEvent::CertificateIssued { device_path, result: certificate });
+    }
+}
+
+/// Keep the wipe record alongside the certificates for later delivery
+async fn spool_wipe_record(
+    spooler: &ResultsSpooler,
+    wipe_result: &safe_erase_core::WipeResult,
+    tx: &mpsc::UnboundedSender<AppEvent>,
+) {
+    let name = format!("records/{}.json", wipe_result.operation_id);
+    let stored = match serde_json::to_vec_pretty(wipe_result) {
+        Ok(contents) => spooler.store(&name, &contents).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = stored {
+        let _ = tx.send(AppEvent::SpoolFailed(format!("{}: {}", name, e)));
+    }
+}
+
+/// Read terminal input on a blocking thread and emit ticks between key presses
+fn spawn_input_reader(tx: mpsc::UnboundedSender<AppEvent>) {
+    std::thread::spawn(move || loop {
+        let event = match event::poll(TICK_INTERVAL) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => AppEvent::Key(key),
+                Ok(_) => continue,
+                Err(_) => break,
+            },
+            Ok(false) => AppEvent::Tick,
+            Err(_) => break,
+        };
+        if tx.send(event).is_err() {
+            break;
+        }
+    });
+}
+
+fn spawn_progress_forwarder(engine: &SafeEraseEngine, tx: mpsc::UnboundedSender<AppEvent>) {
+    let mut progress_rx = engine.subscribe_progress();
+    tokio::spawn(async move {
+        loop {
+            match progress_rx.recv().await {
+                Ok(progress) => {
+                    if tx.send(AppEvent::Progress(progress)).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn spawn_discovery(engine: Arc<SafeEraseEngine>, tx: mpsc::UnboundedSender<AppEvent>) {
+    tokio::spawn(async move {
+        let result = engine.discover_devices().await.map_err(|e| e.user_message());
+        let _ = tx.send(AppEvent::DevicesDiscovered(result));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_completed_wipe_issues_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("disk.img");
+        std::fs::write(&image, vec![0xA5; 1 << 20]).unwrap();
+        let image = image.to_str().unwrap().to_string();
+        let engine = Arc::new(SafeEraseEngine::new().unwrap());
+        engine.open_device(&image).await.unwrap();
+        let issuer = WipeIssuer {
+            engine,
+            certificates: Arc::new(CertificateEngine::new().unwrap()),
+            settings: CertificateSettings {
+                dir: dir.path().to_path_buf(),
+                format: CertificateFormat::JSON,
+                options: CertificateOptions::default(),
+            },
+            spooler: None,
+        };
+        
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let options = WipeOptions { verify_wipe: true, ..WipeOptions::default() };
+        perform_wipe(issuer, image.clone(), WipeAlgorithm::ZeroFill, options, tx).await;
+        
+        match rx.recv().await.unwrap() {
+            AppEvent::WipeFinished { device_path, result } => {
+                assert_eq!(device_path, image);
+                assert_eq!(result.unwrap().status, WipeStatus::Completed);
+            }
+            other => panic!("expected the wipe to finish first, got {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            AppEvent::CertificateIssued { device_path, result } => {
+                assert_eq!(device_path, image);
+                let json_path = result.unwrap().json_path.unwrap();
+                assert!(std::path::Path::new(&json_path).exists());
+            }
+            other => panic!("expected a certificate, got {:?}", other),
+        }
+    }
+}
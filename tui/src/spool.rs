@@ -0,0 +1,419 @@
+//! Results spooling for boot environments
+//!
+//! PXE and USB-boot images usually have no persistent disk: certificates and
+//! wipe records written during the session disappear at power-off. When
+//! `spool.dir` is configured the TUI keeps them in that directory (normally on
+//! tmpfs) together with a manifest of SHA-256 digests, and delivers every file
+//! to the configured upload endpoint and/or USB key before it exits.
+//!
+//! Each file is re-hashed before delivery so corruption in the spool is
+//! reported instead of being shipped. Uploads are `PUT <upload_url>/<name>`
+//! carrying the digest in `X-SafeErase-SHA256`; copies to the USB key are read
+//! back and compared before being renamed into place, and the key receives a
+//! `SHA256SUMS` file covering everything delivered to it.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use safe_erase_core::config::SpoolDefaults;
+use safe_erase_core::retry::{retry_recoverable, retry_when, RetryPolicy};
+use safe_erase_core::SafeEraseError;
+
+/// Manifest file kept next to the spooled results
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Checksum listing written to the USB key
+const CHECKSUMS_NAME: &str = "SHA256SUMS";
+
+/// Header carrying the hex SHA-256 digest of an uploaded file
+const DIGEST_HEADER: &str = "x-safeerase-sha256";
+
+/// Timeout for a single upload request
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for the delay between delivery attempts
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Where spooled files are delivered
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpoolTarget {
+    Upload { url: String, token: Option<String> },
+    UsbKey(PathBuf),
+}
+
+/// A spooled file and the destinations it has reached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpoolEntry {
+    /// Path relative to the spool directory, using `/` separators
+    pub name: String,
+    pub sha256: String,
+    pub size: u64,
+    pub spooled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub delivered_to: Vec<String>,
+}
+
+/// Outcome of delivering the spool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlushReport {
+    /// Number of file deliveries that succeeded
+    pub delivered: usize,
+    /// `(file name, destination, reason)` for every delivery that failed
+    pub failed: Vec<(String, String, String)>,
+}
+
+/// Spool of session results awaiting delivery
+#[derive(Debug)]
+pub struct ResultsSpooler {
+    dir: PathBuf,
+    targets: Vec<SpoolTarget>,
+    retry: RetryPolicy,
+    manifest: Mutex<Vec<SpoolEntry>>,
+    client: reqwest::Client,
+}
+
+impl ResultsSpooler {
+    /// Open the configured spool, or `None` when spooling is disabled
+    ///
+    /// An existing manifest is picked up so results from an earlier session
+    /// that was not flushed are still delivered.
+    pub fn from_config(config: &SpoolDefaults) -> std::io::Result<Option<Self>> {
+        let Some(dir) = &config.dir else {
+            return Ok(None);
+        };
+        
+        let mut targets = Vec::new();
+        if let Some(url) = &config.upload_url {
+            targets.push(SpoolTarget::Upload {
+                url: url.trim_end_matches('/').to_string(),
+                token: config.upload_token.clone(),
+            });
+        }
+        if let Some(usb_dir) = &config.usb_dir {
+            targets.push(SpoolTarget::UsbKey(usb_dir.clone()));
+        }
+        
+        Self::open(dir, targets, config.attempts).map(Some)
+    }
+    
+    /// Open a spool directory, creating it when missing
+    pub fn open(dir: &Path, targets: Vec<SpoolTarget>, attempts: u32) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let manifest = match std::fs::read(dir.join(MANIFEST_NAME)) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let client = reqwest::Client::builder()
+            .timeout(UPLOAD_TIMEOUT)
+            .build()
+            .map_err(std::io::Error::other)?;
+        
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            targets,
+            retry: RetryPolicy {
+                max_attempts: attempts.max(1),
+                initial_delay: RETRY_BASE_DELAY,
+                max_delay: RETRY_MAX_DELAY,
+                ..RetryPolicy::default()
+            },
+            manifest: Mutex::new(manifest),
+            client,
+        })
+    }
+    
+    /// Directory certificates should be written to while spooling
+    pub fn certificate_dir(&self) -> PathBuf {
+        self.dir.join("certificates")
+    }
+    
+    /// Record a file already written inside the spool directory
+    pub async fn track(&self, path: &Path) -> std::io::Result<()> {
+        let name = path
+            .strip_prefix(&self.dir)
+            .map_err(|_| std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is outside the spool directory", path.display()),
+            ))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let contents = tokio::fs::read(path).await?;
+        
+        let mut manifest = self.manifest.lock().await;
+        manifest.retain(|entry| entry.name != name);
+        manifest.push(SpoolEntry {
+            name,
+            sha256: sha256_hex(&contents),
+            size: contents.len() as u64,
+            spooled_at: Utc::now(),
+            delivered_to: Vec::new(),
+        });
+        self.save_manifest(&manifest).await
+    }
+    
+    /// Write `contents` to `name` inside the spool and record it
+    pub async fn store(&self, name: &str, contents: &[u8]) -> std::io::Result<()> {
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, contents).await?;
+        self.track(&path).await
+    }
+    
+    /// Number of files not yet delivered to every destination
+    pub async fn pending(&self) -> usize {
+        let manifest = self.manifest.lock().await;
+        manifest.iter().filter(|entry| !self.is_delivered(entry)).count()
+    }
+    
+    /// Deliver every outstanding file to every destination
+    pub async fn flush(&self) -> FlushReport {
+        let mut report = FlushReport::default();
+        let mut manifest = self.manifest.lock().await;
+        
+        for entry in manifest.iter_mut() {
+            let missing: Vec<&SpoolTarget> = self
+                .targets
+                .iter()
+                .filter(|target| !entry.delivered_to.contains(&target.to_string()))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            
+            let contents = match self.read_verified(entry).await {
+                Ok(contents) => contents,
+                Err(reason) => {
+                    for target in missing {
+                        report.failed.push((entry.name.clone(), target.to_string(), reason.clone()));
+                    }
+                    continue;
+                }
+            };
+            
+            for target in missing {
+                match self.deliver_with_retry(target, entry, &contents).await {
+                    Ok(()) => {
+                        entry.delivered_to.push(target.to_string());
+                        report.delivered += 1;
+                    }
+                    Err(reason) => report.failed.push((entry.name.clone(), target.to_string(), reason)),
+                }
+            }
+        }
+        
+        for target in &self.targets {
+            if let SpoolTarget::UsbKey(usb_dir) = target {
+                if let Err(e) = write_checksums(usb_dir, &manifest, &target.to_string()).await {
+                    report.failed.push((CHECKSUMS_NAME.to_string(), target.to_string(), e.to_string()));
+                }
+            }
+        }
+        
+        if let Err(e) = self.save_manifest(&manifest).await {
+            report.failed.push((MANIFEST_NAME.to_string(), self.dir.display().to_string(), e.to_string()));
+        }
+        report
+    }
+    
+    fn is_delivered(&self, entry: &SpoolEntry) -> bool {
+        self.targets.iter().all(|target| entry.delivered_to.contains(&target.to_string()))
+    }
+    
+    /// Read a spooled file and check it still matches the manifest
+    async fn read_verified(&self, entry: &SpoolEntry) -> Result<Vec<u8>, String> {
+        let contents = tokio::fs::read(self.dir.join(&entry.name))
+            .await
+            .map_err(|e| format!("cannot read spooled file: {}", e))?;
+        if sha256_hex(&contents) != entry.sha256 {
+            return Err("spooled file no longer matches its recorded SHA-256".to_string());
+        }
+        Ok(contents)
+    }
+    
+    async fn deliver_with_retry(&self, target: &SpoolTarget, entry: &SpoolEntry, contents: &[u8]) -> Result<(), String> {
+        match target {
+            SpoolTarget::Upload { url, token } => {
+                retry_recoverable(&self.retry, || self.upload(url, token.as_deref(), entry, contents))
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            // A freshly inserted key may not be mounted yet, so every failure is retried
+            SpoolTarget::UsbKey(usb_dir) => {
+                retry_when(&self.retry, |_: &String| true, || copy_verified(usb_dir, entry, contents)).await
+            }
+        }
+    }
+    
+    /// Upload one file; server errors and timeouts are recoverable, rejections are not
+    async fn upload(&self, url: &str, token: Option<&str>, entry: &SpoolEntry, contents: &[u8]) -> Result<(), SafeEraseError> {
+        let mut request = self
+            .client
+            .put(format!("{}/{}", url, entry.name))
+            .header(DIGEST_HEADER, &entry.sha256)
+            .body(contents.to_vec());
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request
+            .send()
+            .await
+            .map_err(|e| SafeEraseError::NetworkError(e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Err(SafeEraseError::NetworkError(format!("endpoint returned {}", status)))
+        } else {
+            Err(SafeEraseError::PermissionDenied(format!("endpoint rejected upload with {}", status)))
+        }
+    }
+    
+    async fn save_manifest(&self, manifest: &[SpoolEntry]) -> std::io::Result<()> {
+        let contents = serde_json::to_vec_pretty(manifest)?;
+        let temp = self.dir.join(format!("{}.tmp", MANIFEST_NAME));
+        tokio::fs::write(&temp, contents).await?;
+        tokio::fs::rename(&temp, self.dir.join(MANIFEST_NAME)).await
+    }
+}
+
+/// Copy a file to the USB key, read it back and only then move it into place
+async fn copy_verified(usb_dir: &Path, entry: &SpoolEntry, contents: &[u8]) -> Result<(), String> {
+    let destination = usb_dir.join(&entry.name);
+    let partial = usb_dir.join(format!("{}.part", entry.name));
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+    }
+    
+    let mut file = tokio::fs::File::create(&partial).await.map_err(|e| e.to_string())?;
+    file.write_all(contents).await.map_err(|e| e.to_string())?;
+    file.sync_all().await.map_err(|e| e.to_string())?;
+    drop(file);
+    
+    let written = tokio::fs::read(&partial).await.map_err(|e| e.to_string())?;
+    if sha256_hex(&written) != entry.sha256 {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err("copy on the USB key does not match the spooled file".to_string());
+    }
+    tokio::fs::rename(&partial, &destination).await.map_err(|e| e.to_string())
+}
+
+/// Write `SHA256SUMS` for every file delivered to the USB key
+async fn write_checksums(usb_dir: &Path, manifest: &[SpoolEntry], target: &str) -> std::io::Result<()> {
+    let delivered: Vec<&SpoolEntry> = manifest
+        .iter()
+        .filter(|entry| entry.delivered_to.iter().any(|t| t == target))
+        .collect();
+    if delivered.is_empty() {
+        return Ok(());
+    }
+    
+    let mut file = tokio::fs::File::create(usb_dir.join(CHECKSUMS_NAME)).await?;
+    file.write_all(checksums(&delivered).as_bytes()).await?;
+    file.sync_all().await
+}
+
+/// `sha256sum`-compatible listing
+fn checksums(entries: &[&SpoolEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| format!("{}  {}\n", entry.sha256, entry.name))
+        .collect()
+}
+
+fn sha256_hex(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+impl std::fmt::Display for SpoolTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpoolTarget::Upload { url, .. } => write!(f, "{}", url),
+            SpoolTarget::UsbKey(dir) => write!(f, "usb:{}", dir.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_store_and_deliver_to_usb_key() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let usb_dir = tempfile::tempdir().unwrap();
+        let spooler = ResultsSpooler::open(
+            spool_dir.path(),
+            vec![SpoolTarget::UsbKey(usb_dir.path().to_path_buf())],
+            1,
+        )
+        .unwrap();
+        
+        spooler.store("records/wipe-1.json", b"{\"status\":\"Completed\"}").await.unwrap();
+        assert_eq!(spooler.pending().await, 1);
+        
+        let report = spooler.flush().await;
+        assert_eq!(report, FlushReport { delivered: 1, failed: Vec::new() });
+        assert_eq!(spooler.pending().await, 0);
+        assert_eq!(
+            std::fs::read(usb_dir.path().join("records/wipe-1.json")).unwrap(),
+            b"{\"status\":\"Completed\"}"
+        );
+        let sums = std::fs::read_to_string(usb_dir.path().join(CHECKSUMS_NAME)).unwrap();
+        assert!(sums.ends_with("  records/wipe-1.json\n"));
+    }
+    
+    #[tokio::test]
+    async fn test_corrupted_file_is_not_delivered() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let usb_dir = tempfile::tempdir().unwrap();
+        let spooler = ResultsSpooler::open(
+            spool_dir.path(),
+            vec![SpoolTarget::UsbKey(usb_dir.path().to_path_buf())],
+            1,
+        )
+        .unwrap();
+        
+        spooler.store("cert.json", b"original").await.unwrap();
+        std::fs::write(spool_dir.path().join("cert.json"), b"tampered").unwrap();
+        
+        let report = spooler.flush().await;
+        assert_eq!(report.delivered, 0);
+        assert_eq!(report.failed.len(), 1);
+        assert!(!usb_dir.path().join("cert.json").exists());
+        assert_eq!(spooler.pending().await, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_manifest_survives_reopen() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let targets = vec![SpoolTarget::UsbKey(PathBuf::from("/nonexistent"))];
+        let spooler = ResultsSpooler::open(spool_dir.path(), targets.clone(), 1).unwrap();
+        spooler.store("a.json", b"{}").await.unwrap();
+        drop(spooler);
+        
+        let reopened = ResultsSpooler::open(spool_dir.path(), targets, 1).unwrap();
+        assert_eq!(reopened.pending().await, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_track_rejects_paths_outside_spool() {
+        let spool_dir = tempfile::tempdir().unwrap();
+        let spooler = ResultsSpooler::open(spool_dir.path(), Vec::new(), 1).unwrap();
+        assert!(spooler.track(Path::new("/etc/hostname")).await.is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Screen layout and rendering for the terminal UI
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::Frame;
+
+use safe_erase_core::device::HealthStatus;
+
+use crate::app::{App, JobStatus, JobView, Mode};
+
+/// Draw the whole screen
+pub fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Min(8),
+            Constraint::Length(7),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+    
+    draw_devices(frame, app, rows[0]);
+    
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(40), Constraint::Min(30)])
+        .split(rows[1]);
+    draw_config(frame, app, middle[0]);
+    draw_jobs(frame, app, middle[1]);
+    
+    draw_certificates(frame, app, rows[2]);
+    draw_status_line(frame, app, rows[3]);
+    
+    if app.mode == Mode::ConfirmWipe {
+        draw_confirmation(frame, app);
+    }
+}
+
+fn draw_devices(frame: &mut Frame, app: &App, area: Rect) {
+    let header = Row::new(["Device", "Model", "Serial", "Size", "Type", "Health", "Mounted", "Wipe"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    
+    let rows = app.devices.iter().map(|device| {
+        let mounted = if app.mounts.is_mounted(&device.path) {
+            app.mounts.mount_points(&device.path).join(",")
+        } else {
+            "-".to_string()
+        };
+        let wipe_state = match app.job_for(&device.path) {
+            Some(job) => job_label(job),
+            None if device.is_system_disk => "system".to_string(),
+            None => "-".to_string(),
+        };
+        
+        Row::new(vec![
+            device.path.clone(),
+            device.model.clone(),
+            device.serial.clone(),
+            format_bytes(device.size),
+            device.device_type.to_string(),
+            device.health_status.to_string(),
+            mounted,
+            wipe_state,
+        ])
+        .style(Style::default().fg(health_color(device.health_status)))
+    });
+    
+    let title = if app.discovering { " Devices (scanning...) " } else { " Devices " };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Min(16),
+            Constraint::Length(18),
+            Constraint::Length(10),
+            Constraint::Length(18),
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    
+    let mut state = TableState::default();
+    if !app.devices.is_empty() {
+        state.select(Some(app.selected));
+    }
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_config(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Wipe configuration ");
+    
+    let lines = match (app.selected_device(), app.selected_config()) {
+        (Some(device), Some(config)) => {
+            let info = config.algorithm.info();
+            vec![
+                Line::from(vec![Span::raw("Device:    "), Span::styled(device.path.clone(), bold())]),
+                Line::from(vec![Span::raw("[a] Algo:  "), Span::styled(info.name, bold())]),
+                Line::from(format!("           {} pass(es), {}", info.passes, info.security_level)),
+                Line::from(format!("[v] Verify:        {}", on_off(config.verify_wipe))),
+                Line::from(format!("[h] Clear HPA/DCO: {}", on_off(config.clear_hpa_dco))),
+                Line::from(format!("[e] Hardware erase: {}", on_off(config.prefer_hardware_erase))),
+                Line::from(""),
+                Line::from(Span::styled("[w] Wipe  [c] Cancel", Style::default().fg(Color::Yellow))),
+            ]
+        }
+        _ => vec![Line::from("No device selected")],
+    };
+    
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_jobs(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Progress ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    
+    if app.jobs.is_empty() {
+        frame.render_widget(Paragraph::new("No wipes started"), inner);
+        return;
+    }
+    
+    // Two lines per job: a label and a gauge; newest jobs first
+    let visible = (inner.height as usize / 2).max(1);
+    let jobs: Vec<&JobView> = app.jobs.iter().rev().take(visible).collect();
+    let slots = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(jobs.iter().map(|_| Constraint::Length(2)).collect::<Vec<_>>())
+        .split(inner);
+    
+    for (job, slot) in jobs.into_iter().zip(slots.iter()) {
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(*slot);
+        
+        let detail = match &job.progress {
+            Some(p) => format!(
+                "{}{}  {}  pass {}/{}  {}/s{}",
+                p.bay.as_ref().map(|bay| format!("[{}] ", bay)).unwrap_or_default(),
+                job.device_path,
+                job.algorithm,
+                p.current_pass,
+                p.total_passes,
+                format_bytes(p.current_speed as u64),
+                p.estimated_remaining
+                    .map(|eta| format!("  ETA {}", format_duration(eta.as_secs())))
+                    .unwrap_or_default(),
+            ),
+            None => format!("{}  {}", job.device_path, job.algorithm),
+        };
+        frame.render_widget(Paragraph::new(detail), parts[0]);
+        
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(job_color(&job.status)))
+            .ratio(job.ratio())
+            .label(job_label(job));
+        frame.render_widget(gauge, parts[1]);
+    }
+}
+
+fn draw_certificates(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .certificates
+        .iter()
+        .rev()
+        .map(|c| {
+            let file = c.pdf_path.as_deref().or(c.json_path.as_deref()).unwrap_or("-");
+            ListItem::new(format!(
+                "{}  {}  {}  {}",
+                c.generated_at.format("%Y-%m-%d %H:%M"),
+                c.device_path,
+                c.certificate_id,
+                file
+            ))
+        })
+        .collect();
+    
+    let title = format!(" Certificates ({}) ", app.certificates.len());
+    frame.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+fn draw_status_line(frame: &mut Frame, app: &App, area: Rect) {
+    let text = app
+        .status_message
+        .clone()
+        .unwrap_or_else(|| "↑/↓ select  a/v/h/e configure  w wipe  c cancel  b release boot drive  r rescan  q quit".to_string());
+    frame.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Gray)), area);
+}
+
+fn draw_confirmation(frame: &mut Frame, app: &App) {
+    let (Some(device), Some(config)) = (app.selected_device(), app.selected_config()) else { return };
+    let area = centered(frame.size(), 60, 9);
+    
+    let text = vec![
+        Line::from(Span::styled("ALL DATA WILL BE PERMANENTLY DESTROYED", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(format!("{}  {}  {}", device.path, device.model, format_bytes(device.size))),
+        Line::from(format!("Serial: {}", device.serial)),
+        Line::from(format!("Algorithm: {}", config.algorithm)),
+        Line::from(""),
+        Line::from("Press 'y' to wipe, any other key to abort"),
+    ];
+    
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(" Confirm wipe ").border_style(Style::default().fg(Color::Red))),
+        area,
+    );
+}
+
+fn job_label(job: &JobView) -> String {
+    match &job.status {
+        JobStatus::Running => match &job.progress {
+            Some(p) => format!("{} {:.1}%", p.status, p.percentage),
+            None => "starting".to_string(),
+        },
+        JobStatus::Completed => "done".to_string(),
+        JobStatus::Cancelled => "cancelled".to_string(),
+        JobStatus::Failed(reason) => format!("failed: {}", reason),
+    }
+}
+
+fn job_color(status: &JobStatus) -> Color {
+    match status {
+        JobStatus::Running => Color::Cyan,
+        JobStatus::Completed => Color::Green,
+        JobStatus::Cancelled => Color::Yellow,
+        JobStatus::Failed(_) => Color::Red,
+    }
+}
+
+fn health_color(health: HealthStatus) -> Color {
+    match health {
+        HealthStatus::Good => Color::Reset,
+        HealthStatus::Warning => Color::Yellow,
+        HealthStatus::Critical => Color::Red,
+        HealthStatus::Unknown => Color::Gray,
+    }
+}
+
+fn bold() -> Style {
+    Style::default().add_modifier(Modifier::BOLD)
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Format a byte count with binary units
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(500 * 1024 * 1024 * 1024), "500.0 GiB");
+    }
+    
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(3725), "01:02:05");
+    }
+}
@@ -0,0 +1,67 @@
+//! SafeErase certificate verifier for WebAssembly
+//!
+//! Lets the public verification page check certificates entirely in the
+//! browser. It shares the canonical JSON encoding with `certificate-gen`
+//! (the module is compiled from that crate's source so both always agree) and
+//! verifies RSA PKCS#1 v1.5 / SHA-256 signatures with the pure-Rust `rsa`
+//! crate instead of OpenSSL.
+//!
+//! ```js
+//! import init, { verifyCertificate, verifyQrOffline, verifyQrPayload } from './safe_erase_verifier_wasm.js';
+//!
+//! await init();
+//! const report = verifyCertificate(certificateJson, trustedKeysPem);
+//! if (!report.valid) console.warn(report.error);
+//!
+//! // A scanned QR code can be checked on its own, without network access
+//! const scan = verifyQrOffline(qrText, trustedKeysPem);
+//! ```
+//!
+//! Reports are plain objects with `valid`, `certificate_id`, `device_serial`,
+//! `key_id`, `signed_at` and `error`.
+
+// Shared with certificate-gen, which also canonicalizes Rust values
+#[path = "../../certificate-gen/src/canonical.rs"]
+#[allow(dead_code)]
+mod canonical;
+pub mod verify;
+
+use wasm_bindgen::prelude::*;
+
+pub use verify::{TrustedKeys, VerificationReport};
+
+/// Verify a signed certificate JSON document against PEM public key(s)
+#[wasm_bindgen(js_name = verifyCertificate)]
+pub fn verify_certificate(certificate_json: &str, trusted_keys_pem: &str) -> Result<JsValue, JsError> {
+    let keys = TrustedKeys::from_pem_bundle(trusted_keys_pem).map_err(|e| JsError::new(&e))?;
+    to_js(&verify::verify_certificate(certificate_json, &keys))
+}
+
+/// Verify a certificate and check that the scanned QR payload was printed from it
+#[wasm_bindgen(js_name = verifyQrPayload)]
+pub fn verify_qr_payload(
+    qr_payload: &str,
+    certificate_json: &str,
+    trusted_keys_pem: &str,
+) -> Result<JsValue, JsError> {
+    let keys = TrustedKeys::from_pem_bundle(trusted_keys_pem).map_err(|e| JsError::new(&e))?;
+    to_js(&verify::verify_with_qr_payload(qr_payload, certificate_json, &keys))
+}
+
+/// Verify the signed payload of a scanned QR code without the certificate document
+#[wasm_bindgen(js_name = verifyQrOffline)]
+pub fn verify_qr_offline(qr_content: &str, trusted_keys_pem: &str) -> Result<JsValue, JsError> {
+    let keys = TrustedKeys::from_pem_bundle(trusted_keys_pem).map_err(|e| JsError::new(&e))?;
+    to_js(&verify::verify_qr_offline(qr_content, &keys))
+}
+
+/// Key IDs found in a PEM bundle, for showing which issuers are trusted
+#[wasm_bindgen(js_name = trustedKeyIds)]
+pub fn trusted_key_ids(trusted_keys_pem: &str) -> Result<Vec<String>, JsError> {
+    let keys = TrustedKeys::from_pem_bundle(trusted_keys_pem).map_err(|e| JsError::new(&e))?;
+    Ok(keys.key_ids())
+}
+
+fn to_js(report: &VerificationReport) -> Result<JsValue, JsError> {
+    serde_wasm_bindgen::to_value(report).map_err(|e| JsError::new(&e.to_string()))
+}
@@ -0,0 +1,336 @@
+//! Signature verification of certificate JSON without OpenSSL
+
+use base64::Engine;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use rsa::signature::Verifier;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::canonical;
+
+/// Signature algorithms the browser verifier understands, with their modulus size in bytes
+const SUPPORTED_ALGORITHMS: &[(&str, usize)] = &[("RSA2048SHA256", 256), ("RSA4096SHA256", 512)];
+
+/// JWS `typ` of the signed QR payload
+const QR_PAYLOAD_TYPE: &str = "safeerase-qr+jws";
+
+/// Outcome of verifying one certificate
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VerificationReport {
+    pub valid: bool,
+    pub certificate_id: Option<String>,
+    pub device_serial: Option<String>,
+    pub key_id: Option<String>,
+    pub signed_at: Option<String>,
+    /// Why verification failed
+    pub error: Option<String>,
+}
+
+/// Public keys the verifier accepts, indexed by key ID
+#[derive(Debug, Clone)]
+pub struct TrustedKeys {
+    keys: Vec<(String, RsaPublicKey)>,
+}
+
+impl TrustedKeys {
+    /// Parse one or more concatenated `BEGIN PUBLIC KEY` PEM blocks
+    pub fn from_pem_bundle(bundle: &str) -> Result<Self, String> {
+        const BEGIN: &str = "-----BEGIN PUBLIC KEY-----";
+        let mut keys = Vec::new();
+        for block in bundle.split(BEGIN).skip(1) {
+            let pem = format!("{}{}", BEGIN, block);
+            let key = RsaPublicKey::from_public_key_pem(pem.trim())
+                .map_err(|e| format!("Invalid public key: {}", e))?;
+            keys.push((key_id(&key)?, key));
+        }
+        if keys.is_empty() {
+            return Err("No public key found".to_string());
+        }
+        Ok(Self { keys })
+    }
+    
+    /// Key IDs in the bundle
+    pub fn key_ids(&self) -> Vec<String> {
+        self.keys.iter().map(|(id, _)| id.clone()).collect()
+    }
+    
+    fn get(&self, key_id: &str) -> Option<&RsaPublicKey> {
+        self.keys.iter().find(|(id, _)| id == key_id).map(|(_, key)| key)
+    }
+}
+
+/// Verify a signed certificate document against the trusted keys
+pub fn verify_certificate(document: &str, keys: &TrustedKeys) -> VerificationReport {
+    let mut report = VerificationReport::default();
+    if let Err(error) = check_certificate(document, keys, &mut report) {
+        report.error = Some(error);
+    } else {
+        report.valid = true;
+    }
+    report
+}
+
+/// Verify a certificate and check that a scanned QR payload belongs to it
+pub fn verify_with_qr_payload(qr_payload: &str, document: &str, keys: &TrustedKeys) -> VerificationReport {
+    let mut report = verify_certificate(document, keys);
+    if !report.valid {
+        return report;
+    }
+    if let Err(error) = check_qr_payload(qr_payload, document, keys) {
+        report.valid = false;
+        report.error = Some(error);
+    }
+    report
+}
+
+/// Verify a scanned QR code on its own, without the certificate document.
+/// The report carries the certificate ID, serial and key ID from the code.
+pub fn verify_qr_offline(qr_content: &str, keys: &TrustedKeys) -> VerificationReport {
+    let mut report = VerificationReport::default();
+    match check_qr_token(qr_content, keys, &mut report) {
+        Ok(_) => report.valid = true,
+        Err(error) => report.error = Some(error),
+    }
+    report
+}
+
+fn check_certificate(document: &str, keys: &TrustedKeys, report: &mut VerificationReport) -> Result<(), String> {
+    let signed: Value = serde_json::from_str(document).map_err(|e| format!("Invalid certificate JSON: {}", e))?;
+    let certificate = signed
+        .get("certificate")
+        .ok_or("Not a signed SafeErase certificate: missing 'certificate'")?;
+    let signature_info = signed
+        .get("signature_info")
+        .ok_or("Not a signed SafeErase certificate: missing 'signature_info'")?;
+    
+    let data = certificate.get("data");
+    report.certificate_id = string_at(data, "certificate_id");
+    report.device_serial = data.and_then(|d| d.get("device_info")).and_then(|d| string_at(Some(d), "serial"));
+    report.signed_at = string_at(Some(&signed), "signed_at");
+    report.key_id = string_at(Some(signature_info), "key_id");
+    
+    let algorithm = string_at(Some(signature_info), "algorithm").unwrap_or_default();
+    let Some(&(_, modulus_bytes)) = SUPPORTED_ALGORITHMS.iter().find(|(name, _)| *name == algorithm) else {
+        return Err(format!("Unsupported signature algorithm '{}'", algorithm));
+    };
+    
+    let canonical_json = canonical::to_canonical_string(certificate);
+    let expected_hash = string_at(Some(signature_info), "certificate_hash").unwrap_or_default();
+    if hex::encode(Sha256::digest(canonical_json.as_bytes())) != expected_hash {
+        return Err("Certificate content does not match its recorded hash".to_string());
+    }
+    
+    let key_id = report.key_id.clone().unwrap_or_default();
+    let key = keys
+        .get(&key_id)
+        .ok_or_else(|| format!("Certificate was signed with an unknown key ({})", key_id))?;
+    
+    let signature = string_at(Some(signature_info), "signature").unwrap_or_default();
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(signature.replace(['\n', '\r'], ""))
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+    let signature = Signature::try_from(signature.as_slice()).map_err(|e| format!("Invalid signature: {}", e))?;
+    
+    // Version 1 signs the certificate; version 2 signs the signature
+    // metadata, which includes the certificate hash checked above
+    let signed_bytes = match signature_info.get("signature_version").and_then(Value::as_u64) {
+        Some(1) => canonical_json,
+        Some(2) => {
+            if key.size() != modulus_bytes {
+                return Err(format!("Key {} does not match algorithm '{}'", key_id, algorithm));
+            }
+            let mut attributes = signature_info.clone();
+            if let Some(fields) = attributes.as_object_mut() {
+                fields.remove("signature");
+            }
+            canonical::to_canonical_string(&attributes)
+        }
+        version => return Err(format!("Unsupported signature version {:?}", version)),
+    };
+    VerifyingKey::<Sha256>::new(key.clone())
+        .verify(signed_bytes.as_bytes(), &signature)
+        .map_err(|_| "Signature is not valid".to_string())
+}
+
+fn check_qr_payload(qr_payload: &str, document: &str, keys: &TrustedKeys) -> Result<(), String> {
+    let signed: Value = serde_json::from_str(document).map_err(|e| e.to_string())?;
+    let certificate_id = string_at(signed.get("certificate").and_then(|c| c.get("data")), "certificate_id");
+    
+    // Certificates issued before signed QR payloads carry the ID and signature as plain JSON
+    let matches = if qr_payload.trim_start().starts_with('{') {
+        let payload: Value = serde_json::from_str(qr_payload).map_err(|e| format!("Invalid QR payload: {}", e))?;
+        string_at(Some(&payload), "certificate_id") == certificate_id
+            && string_at(Some(&payload), "signature") == string_at(signed.get("signature_info"), "signature")
+    } else {
+        let claims = check_qr_token(qr_payload, keys, &mut VerificationReport::default())?;
+        string_at(Some(&claims), "cid") == certificate_id
+            && string_at(Some(&claims), "sha") == string_at(signed.get("signature_info"), "certificate_hash")
+    };
+    if !matches {
+        return Err("QR code does not belong to this certificate".to_string());
+    }
+    Ok(())
+}
+
+/// Check the JWS in a scanned QR code and return its claims
+fn check_qr_token(qr_content: &str, keys: &TrustedKeys, report: &mut VerificationReport) -> Result<Value, String> {
+    // The token is the fragment of the verification URL
+    let token = qr_content.rsplit_once('#').map_or(qr_content, |(_, token)| token).trim();
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, claims, signature] = parts[..] else {
+        return Err("QR code does not hold a SafeErase payload".to_string());
+    };
+    let signing_input = &token[..header.len() + 1 + claims.len()];
+    let decode = |part: &str| {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|e| format!("Invalid QR payload encoding: {}", e))
+    };
+    
+    let header: Value = serde_json::from_slice(&decode(header)?).map_err(|e| format!("Invalid QR payload: {}", e))?;
+    if header["alg"] != "RS256" || header["typ"] != QR_PAYLOAD_TYPE {
+        return Err("Unsupported QR payload type".to_string());
+    }
+    let claims_value: Value = serde_json::from_slice(&decode(claims)?).map_err(|e| format!("Invalid QR payload: {}", e))?;
+    report.key_id = string_at(Some(&header), "kid");
+    report.certificate_id = string_at(Some(&claims_value), "cid");
+    report.device_serial = string_at(Some(&claims_value), "srl");
+    
+    let key_id = report.key_id.clone().unwrap_or_default();
+    let key = keys
+        .get(&key_id)
+        .ok_or_else(|| format!("QR code was signed with an unknown key ({})", key_id))?;
+    let signature = Signature::try_from(decode(signature)?.as_slice()).map_err(|e| format!("Invalid signature: {}", e))?;
+    VerifyingKey::<Sha256>::new(key.clone())
+        .verify(signing_input.as_bytes(), &signature)
+        .map_err(|_| "QR code signature is not valid".to_string())?;
+    Ok(claims_value)
+}
+
+/// Key ID as computed by the signer: first 8 bytes of SHA-256 over the SPKI DER, hex-encoded
+fn key_id(key: &RsaPublicKey) -> Result<String, String> {
+    let der = key.to_public_key_der().map_err(|e| e.to_string())?;
+    Ok(hex::encode(&Sha256::digest(der.as_bytes())[..8]))
+}
+
+fn string_at(value: Option<&Value>, field: &str) -> Option<String> {
+    value?.get(field)?.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::LineEnding;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::RsaPrivateKey;
+    use serde_json::json;
+    
+    fn signed_document(private_key: &RsaPrivateKey, metadata: Value) -> String {
+        let certificate = json!({
+            "data": {
+                "certificate_id": "6f1c2c1e-8e8b-4a43-9d8c-3c1d6f0b7a11",
+                "device_info": {"path": "/dev/sdb", "serial": "SN123", "model": "Disk", "size": 1000},
+                "metadata": metadata,
+            },
+            "version": "1.0",
+            "format_version": 1,
+        });
+        let canonical_json = canonical::to_canonical_string(&certificate);
+        let signature = SigningKey::<Sha256>::new(private_key.clone()).sign(canonical_json.as_bytes());
+        let public_key = RsaPublicKey::from(private_key);
+        
+        json!({
+            "certificate": certificate,
+            "signature_info": {
+                "signature": base64::engine::general_purpose::STANDARD.encode(signature.to_vec()),
+                "algorithm": "RSA2048SHA256",
+                "key_id": key_id(&public_key).unwrap(),
+                "timestamp": "2024-05-01T12:00:00Z",
+                "certificate_hash": hex::encode(Sha256::digest(canonical_json.as_bytes())),
+                "signature_version": 1,
+            },
+            "signed_at": "2024-05-01T12:00:00Z",
+        })
+        .to_string()
+    }
+    
+    fn keys_for(private_key: &RsaPrivateKey) -> TrustedKeys {
+        let pem = RsaPublicKey::from(private_key).to_public_key_pem(LineEnding::LF).unwrap();
+        TrustedKeys::from_pem_bundle(&pem).unwrap()
+    }
+    
+    #[test]
+    fn test_valid_certificate() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let document = signed_document(&private_key, json!({"operator": "alice", "site": "lab"}));
+        
+        let report = verify_certificate(&document, &keys_for(&private_key));
+        assert!(report.valid, "{:?}", report.error);
+        assert_eq!(report.device_serial.as_deref(), Some("SN123"));
+    }
+    
+    #[test]
+    fn test_tampered_certificate_and_unknown_key() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let document = signed_document(&private_key, json!({}));
+        
+        let tampered = document.replace("SN123", "SN999");
+        assert!(!verify_certificate(&tampered, &keys_for(&private_key)).valid);
+        
+        let other_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let report = verify_certificate(&document, &keys_for(&other_key));
+        assert!(report.error.unwrap().contains("unknown key"));
+        
+        // A version 2 signature must come from a key of the recorded size
+        let mut v2: Value = serde_json::from_str(&document).unwrap();
+        v2["signature_info"]["signature_version"] = json!(2);
+        let report = verify_certificate(&v2.to_string(), &keys_for(&private_key));
+        assert!(report.error.unwrap().contains("does not match algorithm"));
+    }
+    
+    #[test]
+    fn test_qr_payload_must_match() {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let document = signed_document(&private_key, json!({}));
+        let signed: Value = serde_json::from_str(&document).unwrap();
+        let payload = json!({
+            "certificate_id": "6f1c2c1e-8e8b-4a43-9d8c-3c1d6f0b7a11",
+            "signature": signed["signature_info"]["signature"],
+        })
+        .to_string();
+        
+        assert!(verify_with_qr_payload(&payload, &document, &keys_for(&private_key)).valid);
+        let foreign = payload.replace("6f1c2c1e", "00000000");
+        assert!(!verify_with_qr_payload(&foreign, &document, &keys_for(&private_key)).valid);
+        
+        // Signed payload as printed since offline verification was added
+        let encode = |value: &Value| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value.to_string());
+        let public_key = RsaPublicKey::from(&private_key);
+        let signing_input = format!(
+            "{}.{}",
+            encode(&json!({"alg": "RS256", "typ": QR_PAYLOAD_TYPE, "kid": key_id(&public_key).unwrap()})),
+            encode(&json!({
+                "cid": "6f1c2c1e-8e8b-4a43-9d8c-3c1d6f0b7a11",
+                "sha": signed["signature_info"]["certificate_hash"],
+                "srl": "SN123",
+            }))
+        );
+        let signature = SigningKey::<Sha256>::new(private_key.clone()).sign(signing_input.as_bytes());
+        let qr = format!(
+            "https://verify.safeerase.com/certificate/x#{}.{}",
+            signing_input,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature.to_vec())
+        );
+        
+        let offline = verify_qr_offline(&qr, &keys_for(&private_key));
+        assert!(offline.valid, "{:?}", offline.error);
+        assert_eq!(offline.device_serial.as_deref(), Some("SN123"));
+        assert!(verify_with_qr_payload(&qr, &document, &keys_for(&private_key)).valid);
+        assert!(!verify_qr_offline(&qr.replacen(".ey", ".eX", 1), &keys_for(&private_key)).valid);
+    }
+}